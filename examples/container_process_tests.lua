@@ -0,0 +1,35 @@
+-- Tests of container_process.lua, run without containers:
+--   rivet pipeline test examples/container_process.lua --tests examples/container_process_tests.lua
+
+test("clone is skipped without a repository", function()
+    run_stage("git_operations")
+    expect.not_ran("git")
+    expect.logged("No git_repo provided")
+end)
+
+test("repository is cloned in the git container", function()
+    mock.inputs({ git_repo = "https://example.com/app.git", git_branch = "main" })
+    mock.process("git", { exit_code = 0 })
+
+    run_stage("git_operations")
+
+    expect.ran("git", { "clone", "--branch", "main", "--depth", "1", "https://example.com/app.git", "repo" })
+    expect.eq(mock.calls()[1].container, "docker.io/alpine/git:latest")
+end)
+
+test("failed clone fails the stage", function()
+    mock.inputs({ git_repo = "https://example.com/app.git" })
+    mock.process("git", { exit_code = 128, stderr = "fatal: repository not found" })
+
+    expect.fails(function() run_stage("git_operations") end, "Failed to clone repository")
+    expect.logged("fatal: repository not found")
+end)
+
+test("make runs the requested target", function()
+    mock.inputs({ build_target = "release" })
+
+    run_stage("build_example")
+
+    expect.ran("make", { "release" })
+    expect.logged("Build completed successfully")
+end)
@@ -0,0 +1,62 @@
+return pipeline.define({
+    name = "Parallel Stages Pipeline",
+    description = "Demonstrates needs: build and lint run concurrently, test waits for both, cleanup always runs",
+
+    stages = {
+        {
+            name = "build",
+            needs = {},
+            script = function()
+                log.info("Building...")
+                process.run({ cmd = "sleep", args = { "2" } })
+                log.info("Build finished")
+            end
+        },
+        {
+            name = "lint",
+            needs = {},
+            script = function()
+                log.info("Linting...")
+                process.run({ cmd = "sleep", args = { "1" } })
+                log.info("Lint finished")
+            end
+        },
+        {
+            name = "test",
+            needs = { "build", "lint" },
+            script = function()
+                log.info("Testing after build and lint...")
+            end
+        },
+        {
+            name = "coverage",
+            needs = { "build" },
+            -- Coverage is informative only, its failure keeps the job green
+            allow_failure = true,
+            script = function()
+                process.run({ cmd = "false" })
+            end
+        },
+        {
+            -- No needs: waits for the stage declared before it
+            name = "package",
+            script = function()
+                log.info("Packaging...")
+            end
+        },
+        {
+            name = "notify_failure",
+            when = "on_failure",
+            script = function()
+                log.warning("Something failed before packaging")
+            end
+        },
+        {
+            name = "cleanup",
+            always = true,
+            script = function()
+                log.info("Cleaning up...")
+            end
+        }
+    }
+})
@@ -47,6 +47,14 @@ return pipeline.define({
     -- Required plugins (if any)
     plugins = {},
 
+    -- Hooks run around every stage script, with the stage name
+    before_each = function(stage)
+        log.debug("Entering stage " .. stage)
+    end,
+    after_each = function(stage)
+        log.debug("Leaving stage " .. stage)
+    end,
+
     -- Define stages
     stages = {
         {
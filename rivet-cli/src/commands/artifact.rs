@@ -0,0 +1,301 @@
+//! Artifact command handlers
+//!
+//! Handles `rivet job artifacts` commands: listing the artifacts of a job,
+//! downloading them with progress reporting and checksum verification, and
+//! inspecting artifact storage.
+
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result, bail};
+use clap::Subcommand;
+use colored::*;
+use indicatif::{ProgressBar, ProgressStyle};
+use sha2::{Digest, Sha256};
+
+use crate::config::Config;
+use crate::id_resolver::resolve_job_id;
+use crate::output;
+use crate::types::IdOrPrefix;
+use rivet_client::OrchestratorClient;
+use rivet_core::dto::artifact::ExpiryReason;
+
+/// Artifact subcommands
+#[derive(Subcommand)]
+pub enum ArtifactCommands {
+    /// List artifacts produced by a job
+    List {
+        /// Job ID, unambiguous prefix or <pipeline>/<number>
+        job: String,
+    },
+    /// Download an artifact
+    Download {
+        /// Job ID, unambiguous prefix or <pipeline>/<number>
+        job: String,
+
+        /// Artifact name
+        name: String,
+
+        /// Output path (defaults to the artifact name in the current directory)
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+    /// Show how much storage artifacts use, per pipeline (admin)
+    Usage,
+    /// Show the artifacts past their pipeline's retention limits (admin)
+    Gc {
+        /// Delete them now instead of waiting for the next collection
+        #[arg(long)]
+        apply: bool,
+    },
+}
+
+/// Handle artifact commands
+///
+/// # Arguments
+/// * `command` - The artifact command to execute
+/// * `config` - The CLI configuration
+pub async fn handle_artifact_command(command: ArtifactCommands, config: &Config) -> Result<()> {
+    let client = &config.client()?;
+
+    match command {
+        ArtifactCommands::List { job } => list_artifacts(client, &job).await,
+        ArtifactCommands::Download { job, name, output } => {
+            // Large artifacts may take longer than the request timeout
+            download_artifact(&config.streaming_client()?, &job, &name, output).await
+        }
+        ArtifactCommands::Usage => artifact_usage(client).await,
+        ArtifactCommands::Gc { apply } => artifact_gc(client, apply).await,
+    }
+}
+
+/// List the artifacts of a job
+async fn list_artifacts(client: &OrchestratorClient, job: &str) -> Result<()> {
+    let job_id = resolve_job_id(client, &IdOrPrefix::parse(job)).await?;
+    let artifacts = client.list_job_artifacts(job_id).await?;
+    if output::is_plain() {
+        for artifact in &artifacts {
+            output::record(
+                &artifact.name,
+                &[&artifact.size_bytes.to_string(), &artifact.sha256],
+            );
+        }
+        return Ok(());
+    }
+
+    if artifacts.is_empty() {
+        println!(
+            "{}",
+            format!("No artifacts found for job {}.", job_id).yellow()
+        );
+        return Ok(());
+    }
+
+    println!(
+        "{}",
+        format!("Found {} artifact(s) for job {}:", artifacts.len(), job_id).bold()
+    );
+    println!();
+    for artifact in artifacts {
+        println!("  {} {}", "▸".cyan(), artifact.name.bold());
+        println!("    Size:    {}", format_size(artifact.size_bytes as u64));
+        println!("    SHA-256: {}", artifact.sha256.dimmed());
+        println!(
+            "    Created: {}",
+            artifact
+                .created_at
+                .format("%Y-%m-%d %H:%M:%S")
+                .to_string()
+                .dimmed()
+        );
+        println!();
+    }
+
+    Ok(())
+}
+
+/// Download an artifact to disk, verifying its checksum
+async fn download_artifact(
+    client: &OrchestratorClient,
+    job: &str,
+    name: &str,
+    output: Option<PathBuf>,
+) -> Result<()> {
+    let job_id = resolve_job_id(client, &IdOrPrefix::parse(job)).await?;
+    let output = output.unwrap_or_else(|| PathBuf::from(name));
+
+    let mut download = client.download_artifact(job_id, name).await?;
+    let expected = download.sha256().map(|s| s.to_lowercase());
+
+    let progress = match download.content_length() {
+        _ if output::is_plain() => ProgressBar::hidden(),
+        Some(len) => ProgressBar::new(len).with_style(
+            ProgressStyle::with_template(
+                "{spinner:.cyan} [{bar:40.cyan/blue}] {bytes}/{total_bytes} ({bytes_per_sec}, {eta})",
+            )?
+            .progress_chars("=> "),
+        ),
+        None => ProgressBar::new_spinner(),
+    };
+
+    // Download to a temporary file so a failed transfer never leaves a partial artifact behind
+    let partial = partial_path(&output);
+    let mut file = std::fs::File::create(&partial)
+        .with_context(|| format!("Failed to create {}", partial.display()))?;
+    let mut hasher = Sha256::new();
+
+    let result: Result<()> = async {
+        while let Some(chunk) = download.chunk().await? {
+            file.write_all(&chunk)?;
+            hasher.update(&chunk);
+            progress.inc(chunk.len() as u64);
+        }
+        file.flush()?;
+        Ok(())
+    }
+    .await;
+    progress.finish_and_clear();
+
+    if let Err(e) = result {
+        let _ = std::fs::remove_file(&partial);
+        return Err(e.context(format!("Failed to download artifact '{}'", name)));
+    }
+
+    let actual = hex::encode(hasher.finalize());
+    match expected {
+        Some(expected) if expected != actual => {
+            let _ = std::fs::remove_file(&partial);
+            bail!(
+                "Checksum mismatch for artifact '{}': expected {}, got {}",
+                name,
+                expected,
+                actual
+            );
+        }
+        Some(_) => {}
+        None => println!(
+            "{}",
+            "⚠ Orchestrator did not send a checksum, skipping verification".yellow()
+        ),
+    }
+
+    std::fs::rename(&partial, &output)
+        .with_context(|| format!("Failed to write {}", output.display()))?;
+
+    println!(
+        "{} Downloaded {} to {}",
+        "✓".green(),
+        name.bold(),
+        output.display()
+    );
+    println!("  SHA-256: {}", actual.dimmed());
+
+    Ok(())
+}
+
+/// Show artifact storage usage, largest pipelines first
+async fn artifact_usage(client: &OrchestratorClient) -> Result<()> {
+    let usage = client.artifact_usage().await?;
+
+    println!(
+        "{}",
+        format!(
+            "{} artifact(s), {}",
+            usage.artifacts,
+            format_size(usage.bytes)
+        )
+        .bold()
+    );
+    for pipeline in usage.pipelines {
+        println!(
+            "  {} {:>10}  {} artifact(s)",
+            pipeline.pipeline_id.to_string().cyan(),
+            format_size(pipeline.bytes),
+            pipeline.artifacts
+        );
+    }
+
+    Ok(())
+}
+
+/// Show (and with `apply`, delete) the artifacts retention limits expire
+async fn artifact_gc(client: &OrchestratorClient, apply: bool) -> Result<()> {
+    let report = client.artifact_gc(!apply).await?;
+
+    if report.expired.is_empty() {
+        println!("{}", "No artifacts past their retention limits.".green());
+        return Ok(());
+    }
+
+    for expired in &report.expired {
+        let reason = match expired.reason {
+            ExpiryReason::KeepLastJobs => "older than the kept jobs",
+            ExpiryReason::MaxTotalBytes => "over the size limit",
+            ExpiryReason::Ttl => "past its TTL",
+        };
+        println!(
+            "  {} {} {} ({}, {})",
+            "▸".cyan(),
+            expired.artifact.job_id.to_string().dimmed(),
+            expired.artifact.name.bold(),
+            format_size(expired.artifact.size_bytes.max(0) as u64),
+            reason
+        );
+    }
+    let summary = format!(
+        "{} artifact(s), {} of {}",
+        report.expired.len(),
+        format_size(report.expired_bytes),
+        format_size(report.usage.bytes)
+    );
+    if report.dry_run {
+        println!("{} {}", "Would delete".yellow().bold(), summary);
+        println!("Run with --apply to delete them now.");
+    } else {
+        println!("{} {}", "✓ Deleted".green().bold(), summary);
+    }
+
+    Ok(())
+}
+
+/// Format a byte count for humans
+pub(crate) fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["B", "KiB", "MiB", "GiB"];
+
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+
+    if unit == 0 {
+        format!("{} {}", bytes, UNITS[0])
+    } else {
+        format!("{:.1} {}", size, UNITS[unit])
+    }
+}
+
+/// Temporary file an artifact is downloaded to: `<output>.part`
+///
+/// The suffix is appended rather than replacing the extension, so that
+/// `a.tar.gz` and `a.tar.xz` do not share `a.tar.part`.
+fn partial_path(output: &Path) -> PathBuf {
+    let mut partial = output.as_os_str().to_os_string();
+    partial.push(".part");
+    PathBuf::from(partial)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_partial_path_keeps_extension() {
+        assert_eq!(
+            partial_path(Path::new("out/a.tar.gz")),
+            Path::new("out/a.tar.gz.part")
+        );
+        assert_eq!(partial_path(Path::new("report")), Path::new("report.part"));
+    }
+}
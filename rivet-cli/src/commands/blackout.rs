@@ -0,0 +1,188 @@
+//! Blackout command handlers
+//!
+//! Handles blackout windows, during which the jobs of a pipeline or the jobs
+//! deploying to an environment stay queued.
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use clap::Subcommand;
+use colored::*;
+use rivet_core::domain::blackout::BlackoutWindow;
+use rivet_core::dto::blackout::CreateBlackoutWindow;
+use uuid::Uuid;
+
+use crate::config::Config;
+use crate::id_resolver::resolve_pipeline_id;
+use crate::types::IdOrPrefix;
+use rivet_client::OrchestratorClient;
+
+/// Blackout subcommands
+#[derive(Subcommand)]
+pub enum BlackoutCommands {
+    /// List the blackout windows that have not ended
+    List,
+    /// Add a blackout window for a pipeline or an environment
+    Add {
+        /// Pipeline whose jobs are deferred (ID, unambiguous prefix or name)
+        #[arg(short, long, required_unless_present = "environment")]
+        pipeline: Option<String>,
+
+        /// Environment the deferred jobs deploy to
+        #[arg(short, long, conflicts_with = "pipeline")]
+        environment: Option<String>,
+
+        /// Start of the window, as RFC 3339 (e.g. 2026-12-24T18:00:00Z); now if omitted
+        #[arg(long, value_parser = parse_time)]
+        from: Option<DateTime<Utc>>,
+
+        /// End of the window, as RFC 3339 or a duration from its start (e.g. 90m, 12h, 2d)
+        #[arg(long)]
+        until: String,
+
+        /// Why jobs are deferred
+        #[arg(short, long)]
+        reason: Option<String>,
+    },
+    /// Remove a blackout window, letting the jobs it deferred start
+    Remove {
+        /// Blackout window ID
+        id: Uuid,
+    },
+}
+
+/// Handle blackout commands
+///
+/// # Arguments
+/// * `command` - The blackout command to execute
+/// * `config` - The CLI configuration
+pub async fn handle_blackout_command(command: BlackoutCommands, config: &Config) -> Result<()> {
+    let client = config.client()?;
+
+    match command {
+        BlackoutCommands::List => list_blackouts(&client).await,
+        BlackoutCommands::Add {
+            pipeline,
+            environment,
+            from,
+            until,
+            reason,
+        } => {
+            let pipeline_id = match pipeline {
+                Some(id) => Some(resolve_pipeline_id(&client, &IdOrPrefix::parse(&id)).await?),
+                None => None,
+            };
+            let req = CreateBlackoutWindow {
+                pipeline_id,
+                environment,
+                starts_at: from,
+                ends_at: parse_end(&until, from.unwrap_or_else(Utc::now))?,
+                reason,
+            };
+            add_blackout(&client, req).await
+        }
+        BlackoutCommands::Remove { id } => remove_blackout(&client, id).await,
+    }
+}
+
+/// Parse an RFC 3339 timestamp
+fn parse_time(s: &str) -> Result<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(s)
+        .map(|time| time.with_timezone(&Utc))
+        .map_err(|_| anyhow::anyhow!("invalid time `{}`: expected e.g. 2026-12-24T18:00:00Z", s))
+}
+
+/// Parse the end of a window: an RFC 3339 timestamp, or a number of minutes,
+/// hours or days (m, h or d suffix) after `start`
+fn parse_end(s: &str, start: DateTime<Utc>) -> Result<DateTime<Utc>> {
+    if let Ok(time) = parse_time(s) {
+        return Ok(time);
+    }
+    let (number, unit) = match s.char_indices().last() {
+        Some((i, 'm')) => (&s[..i], chrono::Duration::minutes(1)),
+        Some((i, 'h')) => (&s[..i], chrono::Duration::hours(1)),
+        Some((i, 'd')) => (&s[..i], chrono::Duration::days(1)),
+        _ => (s, chrono::Duration::zero()),
+    };
+    number
+        .parse::<i32>()
+        .ok()
+        .filter(|_| !unit.is_zero())
+        .and_then(|number| unit.checked_mul(number))
+        .and_then(|duration| start.checked_add_signed(duration))
+        .ok_or_else(|| {
+            anyhow::anyhow!(
+                "invalid end `{}`: expected e.g. 2026-12-26T08:00:00Z, 90m, 12h or 2d",
+                s
+            )
+        })
+}
+
+/// List blackout windows
+async fn list_blackouts(client: &OrchestratorClient) -> Result<()> {
+    let windows = client.list_blackouts().await?;
+
+    if windows.is_empty() {
+        println!("{}", "No blackout windows.".yellow());
+        return Ok(());
+    }
+
+    println!(
+        "{}",
+        format!("Found {} blackout window(s):", windows.len()).bold()
+    );
+    println!();
+    let now = Utc::now();
+    for window in windows {
+        print_blackout(&window, now);
+    }
+
+    Ok(())
+}
+
+/// Add a blackout window
+async fn add_blackout(client: &OrchestratorClient, req: CreateBlackoutWindow) -> Result<()> {
+    let window = client.create_blackout(&req).await?;
+
+    println!("{} Blackout window added", "✓".green());
+    print_blackout(&window, Utc::now());
+
+    Ok(())
+}
+
+/// Remove a blackout window
+async fn remove_blackout(client: &OrchestratorClient, id: Uuid) -> Result<()> {
+    client.delete_blackout(id).await?;
+
+    println!("{} Blackout window {} removed", "✓".green(), id);
+    Ok(())
+}
+
+/// Print a blackout window
+fn print_blackout(window: &BlackoutWindow, now: DateTime<Utc>) {
+    let target = match (&window.pipeline_id, &window.environment) {
+        (Some(pipeline_id), _) => format!("pipeline {}", pipeline_id),
+        (None, Some(environment)) => format!("environment {}", environment),
+        (None, None) => "-".to_string(),
+    };
+    let state = if window.is_active(now) {
+        "active".red().bold()
+    } else {
+        "scheduled".yellow()
+    };
+
+    println!(
+        "  {} {} [{}]",
+        window.id.to_string().cyan(),
+        target.bold(),
+        state
+    );
+    println!(
+        "    From:   {}",
+        window.starts_at.format("%Y-%m-%d %H:%M:%S")
+    );
+    println!("    Until:  {}", window.ends_at.format("%Y-%m-%d %H:%M:%S"));
+    if let Some(reason) = &window.reason {
+        println!("    Reason: {}", reason);
+    }
+    println!();
+}
@@ -0,0 +1,197 @@
+//! Doctor command handler
+//!
+//! Runs a series of environment checks and prints actionable fixes.
+//! Useful when setting up Rivet for the first time and when filing bug reports.
+
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use anyhow::{Result, bail};
+use colored::*;
+
+use crate::config::Config;
+use rivet_client::OrchestratorClient;
+
+/// Outcome of a single check
+enum Check {
+    Pass(String),
+    Warn { detail: String, fix: String },
+    Fail { detail: String, fix: String },
+}
+
+/// Run all diagnostics
+///
+/// # Arguments
+/// * `workspace` - Workspace base directory a local runner would use
+/// * `config` - The CLI configuration
+pub async fn handle_doctor_command(workspace: PathBuf, config: &Config) -> Result<()> {
+    let client = config.client()?;
+
+    println!("{}", "Rivet doctor".bold());
+    println!("{}", "─".repeat(60).dimmed());
+
+    println!(
+        "  {} {}",
+        "CLI version:".dimmed(),
+        env!("CARGO_PKG_VERSION")
+    );
+    println!("  {} {}", "Orchestrator:".dimmed(), config.orchestrator_url);
+    println!(
+        "  {} {}/{}",
+        "OS/arch:".dimmed(),
+        std::env::consts::OS,
+        std::env::consts::ARCH
+    );
+    println!();
+
+    let checks = vec![
+        ("Orchestrator", check_orchestrator(&client, config).await),
+        ("Container runtime", check_container_runtime()),
+        ("Workspace", check_workspace(&workspace)),
+    ];
+
+    let mut failures = 0;
+    for (name, check) in &checks {
+        match check {
+            Check::Pass(detail) => {
+                println!("{} {}: {}", "✓".green(), name.bold(), detail);
+            }
+            Check::Warn { detail, fix } => {
+                println!("{} {}: {}", "⚠".yellow(), name.bold(), detail);
+                println!("    {} {}", "fix:".yellow(), fix);
+            }
+            Check::Fail { detail, fix } => {
+                failures += 1;
+                println!("{} {}: {}", "✗".red(), name.bold(), detail);
+                println!("    {} {}", "fix:".red(), fix);
+            }
+        }
+    }
+
+    println!("{}", "─".repeat(60).dimmed());
+
+    if failures > 0 {
+        bail!("{} check(s) failed", failures);
+    }
+
+    println!("{}", "All checks passed.".green());
+    Ok(())
+}
+
+/// Check orchestrator connectivity, database health and version compatibility
+async fn check_orchestrator(client: &OrchestratorClient, config: &Config) -> Check {
+    let health = match client.health_check().await {
+        Ok(health) => health,
+        Err(e) => {
+            return Check::Fail {
+                detail: format!("cannot reach {} ({})", config.orchestrator_url, e),
+                fix: "start the orchestrator or point the CLI at it with --orchestrator-url / RIVET_ORCHESTRATOR_URL".to_string(),
+            };
+        }
+    };
+
+    if !health.is_ok() {
+        return Check::Fail {
+            detail: format!(
+                "orchestrator is up but its database is not: {}",
+                health.database
+            ),
+            fix: "check that PostgreSQL is running and DATABASE_URL on the orchestrator is correct"
+                .to_string(),
+        };
+    }
+
+    let cli_version = env!("CARGO_PKG_VERSION");
+    if !versions_compatible(cli_version, &health.version) {
+        return Check::Warn {
+            detail: format!(
+                "orchestrator {} may be incompatible with CLI {}",
+                health.version, cli_version
+            ),
+            fix: "install a CLI matching the orchestrator version".to_string(),
+        };
+    }
+
+    Check::Pass(format!(
+        "reachable, version {}, database ok",
+        health.version
+    ))
+}
+
+/// Check that podman (preferred) or docker is installed
+fn check_container_runtime() -> Check {
+    if let Some(version) = command_version("podman") {
+        return Check::Pass(version);
+    }
+
+    match command_version("docker") {
+        Some(version) => Check::Warn {
+            detail: format!("podman not found, only {}", version),
+            fix:
+                "runners execute jobs with podman, install it (https://podman.io/docs/installation)"
+                    .to_string(),
+        },
+        None => Check::Fail {
+            detail: "neither podman nor docker found in PATH".to_string(),
+            fix: "install podman (https://podman.io/docs/installation)".to_string(),
+        },
+    }
+}
+
+/// Check that the workspace base directory exists and is writable
+fn check_workspace(workspace: &Path) -> Check {
+    if !workspace.is_dir() {
+        return Check::Fail {
+            detail: format!("{} does not exist", workspace.display()),
+            fix: format!(
+                "create it with `mkdir -p {}` or set WORKSPACE_BASE",
+                workspace.display()
+            ),
+        };
+    }
+
+    let probe = workspace.join(format!(".rivet-doctor-{}", std::process::id()));
+    match std::fs::write(&probe, b"ok") {
+        Ok(()) => {
+            let _ = std::fs::remove_file(&probe);
+            Check::Pass(format!("{} is writable", workspace.display()))
+        }
+        Err(e) => Check::Fail {
+            detail: format!("{} is not writable ({})", workspace.display(), e),
+            fix: format!(
+                "grant write access to the runner user, e.g. `chown $USER {}`",
+                workspace.display()
+            ),
+        },
+    }
+}
+
+/// Run `<program> --version`, returning its first output line on success
+fn command_version(program: &str) -> Option<String> {
+    let output = Command::new(program).arg("--version").output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .next()
+        .map(|line| line.trim().to_string())
+}
+
+/// Versions are compatible when major matches, or major and minor while on 0.x
+fn versions_compatible(a: &str, b: &str) -> bool {
+    let parse = |v: &str| -> Vec<u64> {
+        v.split(['.', '-', '+'])
+            .take(2)
+            .map(|p| p.parse().unwrap_or(0))
+            .collect()
+    };
+
+    let (a, b) = (parse(a), parse(b));
+    match (a.first(), b.first()) {
+        (Some(0), Some(0)) => a.get(1) == b.get(1),
+        (Some(x), Some(y)) => x == y,
+        _ => false,
+    }
+}
@@ -0,0 +1,288 @@
+//! Environment command handlers
+//!
+//! Handles environments: what is deployed to them, their deployment history,
+//! their locks and the approvals jobs deploying to them require.
+
+use anyhow::Result;
+use clap::Subcommand;
+use colored::*;
+use rivet_core::domain::environment::{Deployment, DeploymentStatus};
+use rivet_core::dto::environment::{EnvironmentSummary, SetEnvironment};
+
+use crate::config::Config;
+use crate::output;
+use rivet_client::OrchestratorClient;
+
+/// Environment subcommands
+#[derive(Subcommand)]
+pub enum EnvironmentCommands {
+    /// List environments with what is deployed to them
+    List,
+    /// Show an environment with what is deployed to it
+    Get {
+        /// Environment name
+        name: String,
+    },
+    /// Create or change an environment
+    Set {
+        /// Environment name
+        name: String,
+
+        /// Description of the environment
+        #[arg(short, long)]
+        description: Option<String>,
+
+        /// Approvals a job needs before deploying to the environment
+        #[arg(short, long, default_value_t = 0)]
+        required_approvals: u32,
+    },
+    /// Delete an environment with its deployment history
+    Delete {
+        /// Environment name
+        name: String,
+    },
+    /// List the deployments to an environment, newest first
+    Deployments {
+        /// Environment name
+        name: String,
+
+        /// Deployments to list
+        #[arg(short = 'n', long)]
+        limit: Option<u32>,
+    },
+    /// Show which job holds the lock of an environment and which jobs wait for it
+    Lock {
+        /// Environment name
+        name: String,
+    },
+    /// Force-release the lock of an environment (the job holding it keeps running)
+    Unlock {
+        /// Environment name
+        name: String,
+    },
+}
+
+/// Handle environment commands
+///
+/// # Arguments
+/// * `command` - The environment command to execute
+/// * `config` - The CLI configuration
+pub async fn handle_environment_command(
+    command: EnvironmentCommands,
+    config: &Config,
+) -> Result<()> {
+    let client = config.client()?;
+
+    match command {
+        EnvironmentCommands::List => list_environments(&client).await,
+        EnvironmentCommands::Get { name } => get_environment(&client, &name).await,
+        EnvironmentCommands::Set {
+            name,
+            description,
+            required_approvals,
+        } => {
+            let req = SetEnvironment {
+                description,
+                required_approvals,
+            };
+            set_environment(&client, &name, req).await
+        }
+        EnvironmentCommands::Delete { name } => delete_environment(&client, &name).await,
+        EnvironmentCommands::Deployments { name, limit } => {
+            list_deployments(&client, &name, limit).await
+        }
+        EnvironmentCommands::Lock { name } => show_lock(&client, &name).await,
+        EnvironmentCommands::Unlock { name } => release_lock(&client, &name).await,
+    }
+}
+
+/// List environments
+async fn list_environments(client: &OrchestratorClient) -> Result<()> {
+    let environments = client.list_environments().await?;
+    if output::is_plain() {
+        for summary in &environments {
+            let environment = &summary.environment;
+            output::record(
+                &environment.name,
+                &[&environment.required_approvals.to_string()],
+            );
+        }
+        return Ok(());
+    }
+
+    if environments.is_empty() {
+        println!("{}", "No environments found.".yellow());
+        return Ok(());
+    }
+
+    println!(
+        "{}",
+        format!("Found {} environment(s):", environments.len()).bold()
+    );
+    println!();
+    for environment in environments {
+        print_environment(&environment);
+    }
+
+    Ok(())
+}
+
+/// Show an environment
+async fn get_environment(client: &OrchestratorClient, name: &str) -> Result<()> {
+    let environment = client.get_environment(name).await?;
+    print_environment(&environment);
+    Ok(())
+}
+
+/// Create or change an environment
+async fn set_environment(
+    client: &OrchestratorClient,
+    name: &str,
+    req: SetEnvironment,
+) -> Result<()> {
+    let environment = client.set_environment(name, &req).await?;
+
+    println!(
+        "{} Environment {} set ({} approval(s) required)",
+        "✓".green(),
+        environment.environment.name.bold(),
+        environment.environment.required_approvals
+    );
+
+    Ok(())
+}
+
+/// Delete an environment
+async fn delete_environment(client: &OrchestratorClient, name: &str) -> Result<()> {
+    client.delete_environment(name).await?;
+
+    println!("{} Environment {} deleted", "✓".green(), name.bold());
+
+    Ok(())
+}
+
+/// List the deployments to an environment
+async fn list_deployments(
+    client: &OrchestratorClient,
+    name: &str,
+    limit: Option<u32>,
+) -> Result<()> {
+    let deployments = client.list_deployments(name, limit).await?;
+
+    if deployments.is_empty() {
+        println!("{}", format!("No deployments to {} yet.", name).yellow());
+        return Ok(());
+    }
+
+    println!(
+        "{}",
+        format!("Deployments to {} (newest first):", name).bold()
+    );
+    println!();
+    for deployment in deployments {
+        println!("  {}", describe_deployment(&deployment));
+    }
+
+    Ok(())
+}
+
+/// Show the lock of an environment
+async fn show_lock(client: &OrchestratorClient, name: &str) -> Result<()> {
+    let status = client.get_environment_lock(name).await?;
+
+    match &status.lock {
+        Some(lock) => println!(
+            "{} {} locked by job {} since {}",
+            "▸".cyan(),
+            status.environment.bold(),
+            lock.job_id.to_string().cyan(),
+            lock.acquired_at.format("%Y-%m-%d %H:%M:%S")
+        ),
+        None => println!(
+            "{} {} {}",
+            "▸".cyan(),
+            status.environment.bold(),
+            "not locked".dimmed()
+        ),
+    }
+
+    if !status.waiting.is_empty() {
+        println!();
+        println!(
+            "{}",
+            format!("{} job(s) waiting:", status.waiting.len()).bold()
+        );
+        for job_id in status.waiting {
+            println!("  {}", job_id.to_string().cyan());
+        }
+    }
+
+    Ok(())
+}
+
+/// Force-release the lock of an environment
+async fn release_lock(client: &OrchestratorClient, name: &str) -> Result<()> {
+    client.release_environment_lock(name).await?;
+
+    println!("{} Lock of {} released", "✓".green(), name.bold());
+
+    Ok(())
+}
+
+/// Print an environment with its current and latest deployments
+fn print_environment(summary: &EnvironmentSummary) {
+    let environment = &summary.environment;
+    println!("{} {}", "▸".cyan(), environment.name.bold());
+    if let Some(description) = &environment.description {
+        println!("    {}", description.dimmed());
+    }
+    if environment.required_approvals > 0 {
+        println!("    Approvals: {} required", environment.required_approvals);
+    }
+    match &summary.current {
+        Some(deployment) => println!("    Current:   {}", describe_deployment(deployment)),
+        None => println!("    Current:   {}", "nothing deployed".dimmed()),
+    }
+    if let Some(latest) = &summary.latest
+        && summary.current.as_ref().map(|d| d.id) != Some(latest.id)
+    {
+        println!("    Latest:    {}", describe_deployment(latest));
+    }
+    if let Some(lock) = &summary.lock {
+        println!(
+            "    Locked by: job {} since {}",
+            lock.job_id.to_string().cyan(),
+            lock.acquired_at.format("%Y-%m-%d %H:%M:%S")
+        );
+    }
+    println!();
+}
+
+/// One-line description of a deployment
+fn describe_deployment(deployment: &Deployment) -> String {
+    let commit = deployment
+        .commit
+        .as_ref()
+        .map(|c| format!(" @ {}", &c.sha[..c.sha.len().min(10)]))
+        .unwrap_or_default();
+    format!(
+        "{} job {} stage {}{}  {}",
+        colorize_status(deployment.status),
+        deployment.job_id.to_string().cyan(),
+        deployment.stage,
+        commit,
+        deployment
+            .started_at
+            .format("%Y-%m-%d %H:%M:%S")
+            .to_string()
+            .dimmed()
+    )
+}
+
+fn colorize_status(status: DeploymentStatus) -> colored::ColoredString {
+    match status {
+        DeploymentStatus::Running => "Running".blue(),
+        DeploymentStatus::Succeeded => "Succeeded".green(),
+        DeploymentStatus::Failed => "Failed".red(),
+    }
+}
@@ -0,0 +1,107 @@
+//! Events command handler
+//!
+//! Streams orchestrator events live, one line per event.
+
+use anyhow::Result;
+use colored::*;
+use futures_util::StreamExt;
+use rivet_core::domain::job::JobStatus;
+use rivet_core::dto::event::Event;
+
+use crate::config::Config;
+
+/// Handle the events command
+///
+/// Subscribes to the orchestrator event stream and prints events until
+/// interrupted or the connection closes.
+///
+/// # Arguments
+/// * `json` - Print raw JSON events instead of formatted lines
+/// * `config` - The CLI configuration
+pub async fn handle_events_command(json: bool, config: &Config) -> Result<()> {
+    let client = config.streaming_client()?;
+    let mut events = client.subscribe_events().await?;
+
+    if !json {
+        println!(
+            "{}",
+            format!(
+                "Watching events from {} (Ctrl+C to stop)",
+                client.base_url()
+            )
+            .bold()
+        );
+    }
+
+    while let Some(event) = events.next().await {
+        let event = event?;
+        if json {
+            println!("{}", serde_json::to_string(&event)?);
+        } else {
+            print_event(&event);
+        }
+    }
+
+    if !json {
+        println!("{}", "Event stream closed by the orchestrator.".yellow());
+    }
+
+    Ok(())
+}
+
+/// Print a single event as a formatted line
+fn print_event(event: &Event) {
+    let time = chrono::Local::now().format("%H:%M:%S").to_string().dimmed();
+
+    let line = match event {
+        Event::JobQueued {
+            job_id,
+            pipeline_id,
+        } => format!(
+            "{} job {} queued (pipeline {})",
+            "●".yellow(),
+            short(job_id),
+            short(pipeline_id)
+        ),
+        Event::JobStarted {
+            job_id, runner_id, ..
+        } => format!(
+            "{} job {} started on {}",
+            "▶".blue(),
+            short(job_id),
+            runner_id.bold()
+        ),
+        Event::JobCompleted { job_id, status, .. } => {
+            let status = match status {
+                JobStatus::Succeeded => "succeeded".green(),
+                JobStatus::Failed | JobStatus::TimedOut | JobStatus::DeadLettered => {
+                    format!("{:?}", status).to_lowercase().red()
+                }
+                _ => format!("{:?}", status).to_lowercase().normal(),
+            };
+            format!("{} job {} {}", "■".cyan(), short(job_id), status)
+        }
+        Event::PipelineCreated { pipeline_id, name } => format!(
+            "{} pipeline {} created ({})",
+            "+".green(),
+            name.bold(),
+            short(pipeline_id)
+        ),
+        Event::PipelineDeleted { pipeline_id } => {
+            format!("{} pipeline {} deleted", "-".red(), short(pipeline_id))
+        }
+        Event::RunnerRegistered { runner_id } => {
+            format!("{} runner {} online", "↑".green(), runner_id.bold())
+        }
+        Event::RunnerOffline { runner_id } => {
+            format!("{} runner {} offline", "↓".red(), runner_id.bold())
+        }
+    };
+
+    println!("{} {}", time, line);
+}
+
+/// First 8 characters of a UUID, matching the prefixes accepted by other commands
+fn short(id: &uuid::Uuid) -> String {
+    id.to_string()[..8].to_string()
+}
@@ -104,7 +104,7 @@ fn generate_luarc_json(output_path: &Path) -> Result<()> {
     "version": "Lua 5.4"
   },
   "diagnostics": {
-    "globals": ["log", "input", "output", "process", "container"]
+    "globals": ["log", "input", "output", "process", "container", "ctx", "job", "env", "annotations"]
   },
   "workspace": {
     "library": [".rivet/stubs"],
@@ -135,7 +135,7 @@ async fn fetch_and_save_stubs(output_path: &Path, config: &Config) -> Result<()>
 
     // Get list of available stubs
     let stubs_list: Vec<String> = client
-        .get(format!("{}/api/stubs", orchestrator_url))
+        .get(format!("{}/api/v1/stubs", orchestrator_url))
         .send()
         .await
         .context("Failed to fetch stubs list from orchestrator")?
@@ -146,7 +146,7 @@ async fn fetch_and_save_stubs(output_path: &Path, config: &Config) -> Result<()>
     // Fetch each stub file
     for stub_name in stubs_list {
         let stub_response: StubResponse = client
-            .get(format!("{}/api/stubs/{}", orchestrator_url, stub_name))
+            .get(format!("{}/api/v1/stubs/{}", orchestrator_url, stub_name))
             .send()
             .await
             .with_context(|| format!("Failed to fetch stub '{}'", stub_name))?
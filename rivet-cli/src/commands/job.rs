@@ -3,47 +3,175 @@
 //! Handles all job-related CLI commands including listing,
 //! viewing details, and accessing logs.
 
-use anyhow::Result;
+use anyhow::{Result, bail};
 use clap::Subcommand;
 use colored::*;
-use rivet_core::domain::job::{Job, JobStatus};
+use rivet_core::domain::job::{Job, JobStatus, StageRun, StageStatus};
 use rivet_core::domain::log::{LogEntry, LogLevel};
+use rivet_core::domain::pipeline::InputType;
+use rivet_core::dto::environment::{ApproveJob, JobApprovals};
+use rivet_core::dto::job::{CreateJob, InputFile};
+use rivet_core::dto::log::{LogQuery, LogSearchQuery};
+use rivet_core::dto::pagination::MAX_PAGE_SIZE;
+use serde_json::Value as JsonValue;
+use similar::{ChangeTag, TextDiff};
 
+use crate::commands::artifact::{ArtifactCommands, handle_artifact_command};
+use crate::commands::pipeline::{parse_key_val, prompt_for_input, validate_and_convert_input};
 use crate::config::Config;
 use crate::id_resolver::{resolve_job_id, resolve_job_id_in_pipeline, resolve_pipeline_id};
+use crate::output;
+use crate::picker;
 use crate::types::IdOrPrefix;
-use rivet_client::OrchestratorClient;
+use futures_util::TryStreamExt;
+use rivet_client::{DEFAULT_PAGE_SIZE, DebugFrame, OrchestratorClient};
+use std::collections::BTreeMap;
+use std::io::Read;
+use std::time::Duration;
+use tokio::io::AsyncWriteExt;
+use uuid::Uuid;
+
+/// How often `rivet job logs --follow` polls for new entries
+const LOG_FOLLOW_INTERVAL: Duration = Duration::from_secs(1);
 
 /// Job subcommands
 #[derive(Subcommand)]
 pub enum JobCommands {
     /// List all jobs
-    List,
+    List {
+        /// Only list jobs carrying this label, as key=value (repeatable)
+        #[arg(short, long, value_parser = parse_key_val)]
+        label: Vec<(String, String)>,
+    },
     /// List scheduled jobs
     Scheduled,
+    /// List jobs that gave up after repeated failures
+    Deadletter,
+    /// Put a dead-lettered job back in the queue
+    Requeue {
+        /// Job ID, unambiguous prefix or <pipeline>/<number>
+        id: String,
+    },
+    /// Launch a job continuing a failed one, skipping its succeeded stages
+    ///
+    /// The new job starts with the failed job's artifacts, and with its
+    /// workspace when it runs on the runner that kept it.
+    Resume {
+        /// Job ID, unambiguous prefix or <pipeline>/<number>
+        id: String,
+    },
+    /// Show the approvals a job has, for each environment it deploys to
+    Approvals {
+        /// Job ID, unambiguous prefix or <pipeline>/<number>
+        id: String,
+    },
+    /// Approve a queued job's deployments to an environment
+    Approve {
+        /// Job ID, unambiguous prefix or <pipeline>/<number>
+        id: String,
+
+        /// Environment to approve deployments to
+        #[arg(short, long)]
+        environment: String,
+
+        /// Who approves (default: $USER)
+        #[arg(short, long, env = "USER")]
+        approver: String,
+
+        /// Comment recorded with the approval
+        #[arg(short, long)]
+        comment: Option<String>,
+    },
     /// Get job details
     Get {
-        /// Job ID or unambiguous prefix
+        /// Job ID, unambiguous prefix or <pipeline>/<number>
         id: String,
     },
     /// Get job logs
     Logs {
-        /// Job ID or unambiguous prefix
-        id: String,
+        /// Job ID, unambiguous prefix or <pipeline>/<number>; picked from a
+        /// list when left out in a terminal
+        id: Option<String>,
 
-        /// Follow logs (not yet implemented)
+        /// Keep printing new entries until the job finishes
         #[arg(short, long)]
         follow: bool,
+
+        /// Only show the last N entries
+        #[arg(short = 'n', long, value_name = "N")]
+        tail: Option<u32>,
+
+        /// Only show the entries of this stage
+        #[arg(short, long)]
+        stage: Option<String>,
+
+        /// Only show entries of this level and above (debug, info, warning, error)
+        #[arg(short, long)]
+        level: Option<LogLevel>,
+
+        /// Only show entries containing these words (`"exact phrase"`,
+        /// `-excluded`), searched by the orchestrator
+        #[arg(short, long, value_name = "PATTERN", conflicts_with_all = ["follow", "tail"])]
+        grep: Option<String>,
+    },
+    /// Compare two jobs: parameters, stage results and durations, and the
+    /// logs of stages that failed in either
+    Compare {
+        /// Job ID, unambiguous prefix or <pipeline>/<number> of the reference
+        /// job (e.g. the last good one)
+        first: String,
+
+        /// Job ID, unambiguous prefix or <pipeline>/<number> of the job to
+        /// compare with it
+        second: String,
+
+        /// Also diff the logs of this stage (repeatable)
+        #[arg(short, long)]
+        stage: Vec<String>,
+
+        /// Lines of unchanged log shown around each difference
+        #[arg(long, default_value_t = 3)]
+        context: usize,
     },
     /// List jobs for a pipeline
     Pipeline {
-        /// Pipeline ID or unambiguous prefix
+        /// Pipeline ID, unambiguous prefix or name
         pipeline_id: String,
 
         /// Also resolve job IDs by prefix within this pipeline
         #[arg(long)]
         job: Option<String>,
     },
+    /// Launch a new job with the parameters of an existing one
+    Rerun {
+        /// Job ID, unambiguous prefix or <pipeline>/<number>
+        id: String,
+
+        /// Parameter overrides as key=value pairs (e.g., branch=hotfix)
+        #[arg(short, long, value_parser = parse_key_val)]
+        param: Vec<(String, String)>,
+
+        /// Fail instead of prompting for required inputs that need a new value
+        #[arg(long)]
+        no_interactive: bool,
+    },
+    /// Open a shell in the environment a failed job ran in
+    ///
+    /// Requires the runner that ran the job to keep failed workspaces
+    /// (KEEP_FAILED_WORKSPACES).
+    Debug {
+        /// Job ID, unambiguous prefix or <pipeline>/<number>
+        id: String,
+
+        /// Container image of the job to restore (default: the default container)
+        #[arg(long)]
+        image: Option<String>,
+    },
+    /// Job artifacts
+    Artifacts {
+        #[command(subcommand)]
+        command: ArtifactCommands,
+    },
 }
 
 /// Handle job commands
@@ -54,24 +182,93 @@ pub enum JobCommands {
 /// * `command` - The job command to execute
 /// * `config` - The CLI configuration
 pub async fn handle_job_command(command: JobCommands, config: &Config) -> Result<()> {
-    let client = OrchestratorClient::new(&config.orchestrator_url);
+    let client = config.client()?;
 
     match command {
-        JobCommands::List => list_all_jobs(&client).await,
+        JobCommands::List { label } => list_all_jobs(&client, label.into_iter().collect()).await,
         JobCommands::Scheduled => list_scheduled_jobs(&client).await,
+        JobCommands::Deadletter => list_dead_lettered_jobs(&client).await,
+        JobCommands::Requeue { id } => requeue_job(&client, &id).await,
+        JobCommands::Resume { id } => resume_job(&client, &id).await,
+        JobCommands::Approvals { id } => show_job_approvals(&client, &id).await,
+        JobCommands::Approve {
+            id,
+            environment,
+            approver,
+            comment,
+        } => {
+            let req = ApproveJob {
+                environment,
+                approver,
+                comment,
+            };
+            approve_job(&client, &id, req).await
+        }
         JobCommands::Get { id } => get_job(&client, &id).await,
-        JobCommands::Logs { id, follow } => get_job_logs(&client, &id, follow).await,
+        JobCommands::Logs {
+            id,
+            stage,
+            level,
+            grep: Some(pattern),
+            ..
+        } => {
+            let id = picker::job_or_pick(&client, id).await?;
+            let query = LogSearchQuery {
+                q: pattern,
+                level: level.map(|level| level.to_string()),
+                pipeline_id: None,
+                limit: Some(MAX_PAGE_SIZE),
+            };
+            grep_job_logs(&client, &id, query, stage).await
+        }
+        JobCommands::Logs {
+            id,
+            follow,
+            tail,
+            stage,
+            level,
+            grep: None,
+        } => {
+            let id = picker::job_or_pick(&client, id).await?;
+            let query = LogQuery {
+                after: None,
+                tail,
+                level: level.map(|level| level.to_string()),
+            };
+            get_job_logs(&client, &id, follow, query, stage).await
+        }
+        JobCommands::Compare {
+            first,
+            second,
+            stage,
+            context,
+        } => compare_jobs(&client, &first, &second, &stage, context).await,
         JobCommands::Pipeline { pipeline_id, job } => {
             list_pipeline_jobs(&client, &pipeline_id, job).await
         }
+        JobCommands::Rerun {
+            id,
+            param,
+            no_interactive,
+        } => rerun_job(&client, &id, param, no_interactive).await,
+        JobCommands::Debug { id, image } => debug_job(&client, &id, image.as_deref()).await,
+        JobCommands::Artifacts { command } => handle_artifact_command(command, config).await,
     }
 }
 
-/// List all jobs
-async fn list_all_jobs(client: &OrchestratorClient) -> Result<()> {
-    let jobs = client.list_all_jobs().await?;
+/// List all jobs, or those carrying all of `labels`
+async fn list_all_jobs(
+    client: &OrchestratorClient,
+    labels: BTreeMap<String, String>,
+) -> Result<()> {
+    let jobs: Vec<_> = client
+        .list_labeled_jobs_paged(labels, DEFAULT_PAGE_SIZE)
+        .try_collect()
+        .await?;
 
-    if jobs.is_empty() {
+    if output::is_plain() {
+        jobs.iter().for_each(job_record);
+    } else if jobs.is_empty() {
         println!("{}", "No jobs found.".yellow());
     } else {
         println!("{}", format!("Found {} job(s):", jobs.len()).bold());
@@ -88,7 +285,9 @@ async fn list_all_jobs(client: &OrchestratorClient) -> Result<()> {
 async fn list_scheduled_jobs(client: &OrchestratorClient) -> Result<()> {
     let jobs = client.list_scheduled_jobs().await?;
 
-    if jobs.is_empty() {
+    if output::is_plain() {
+        jobs.iter().for_each(job_record);
+    } else if jobs.is_empty() {
         println!("{}", "No scheduled jobs found.".yellow());
     } else {
         println!(
@@ -104,43 +303,362 @@ async fn list_scheduled_jobs(client: &OrchestratorClient) -> Result<()> {
     Ok(())
 }
 
+/// List dead-lettered jobs
+async fn list_dead_lettered_jobs(client: &OrchestratorClient) -> Result<()> {
+    let jobs = client.list_dead_lettered_jobs().await?;
+
+    if output::is_plain() {
+        jobs.iter().for_each(job_record);
+    } else if jobs.is_empty() {
+        println!("{}", "No dead-lettered jobs found.".green());
+    } else {
+        println!(
+            "{}",
+            format!("Found {} dead-lettered job(s):", jobs.len()).bold()
+        );
+        println!();
+        for job in jobs {
+            print_job_summary(&job);
+        }
+    }
+
+    Ok(())
+}
+
+/// Requeue a dead-lettered job
+async fn requeue_job(client: &OrchestratorClient, id: &str) -> Result<()> {
+    let id_or_prefix = IdOrPrefix::parse(id);
+    let uuid = resolve_job_id(client, &id_or_prefix).await?;
+
+    let job = client.requeue_job(uuid).await?;
+    if output::is_plain() {
+        job_record(&job);
+        return Ok(());
+    }
+
+    println!("{} Job {} requeued", "✓".green(), job.id.to_string().cyan());
+
+    Ok(())
+}
+
+/// Show the approvals of a job
+async fn show_job_approvals(client: &OrchestratorClient, id: &str) -> Result<()> {
+    let id_or_prefix = IdOrPrefix::parse(id);
+    let uuid = resolve_job_id(client, &id_or_prefix).await?;
+
+    let approvals = client.get_job_approvals(uuid).await?;
+    if approvals.is_empty() {
+        println!("{}", "Job deploys to no environment.".yellow());
+        return Ok(());
+    }
+
+    print_job_approvals(&approvals);
+    Ok(())
+}
+
+/// Approve a job's deployments to an environment
+async fn approve_job(client: &OrchestratorClient, id: &str, req: ApproveJob) -> Result<()> {
+    let id_or_prefix = IdOrPrefix::parse(id);
+    let uuid = resolve_job_id(client, &id_or_prefix).await?;
+
+    let approvals = client.approve_job(uuid, &req).await?;
+
+    println!(
+        "{} Job {} approved for {} by {}",
+        "✓".green(),
+        uuid.to_string().cyan(),
+        req.environment.bold(),
+        req.approver
+    );
+    println!();
+    print_job_approvals(&approvals);
+
+    Ok(())
+}
+
+/// Print the approvals of a job, per environment
+fn print_job_approvals(approvals: &[JobApprovals]) {
+    for environment in approvals {
+        let progress = format!("{}/{}", environment.approvals.len(), environment.required);
+        println!(
+            "  {} {}  {}",
+            "▸".cyan(),
+            environment.environment.bold(),
+            if environment.is_satisfied() {
+                progress.green()
+            } else {
+                progress.yellow()
+            }
+        );
+        for approval in &environment.approvals {
+            println!(
+                "      {}  {}{}",
+                approval.approver,
+                approval
+                    .approved_at
+                    .format("%Y-%m-%d %H:%M:%S")
+                    .to_string()
+                    .dimmed(),
+                approval
+                    .comment
+                    .as_ref()
+                    .map(|c| format!("  {}", c))
+                    .unwrap_or_default()
+            );
+        }
+    }
+}
+
+/// Resume a failed job in a new job
+async fn resume_job(client: &OrchestratorClient, id: &str) -> Result<()> {
+    let id_or_prefix = IdOrPrefix::parse(id);
+    let uuid = resolve_job_id(client, &id_or_prefix).await?;
+
+    let job = client.resume_job(uuid).await?;
+    if output::is_plain() {
+        job_record(&job);
+        return Ok(());
+    }
+
+    println!(
+        "{} Job {} resumes job {}",
+        "✓".green(),
+        job.id.to_string().cyan(),
+        uuid.to_string().dimmed()
+    );
+
+    Ok(())
+}
+
+/// Open an interactive shell in the environment of a failed job
+///
+/// Standard input is forwarded to the shell as it is read and the shell's
+/// output written to standard output; notices from the runner go to
+/// standard error. The session ends when the shell exits or input ends.
+async fn debug_job(client: &OrchestratorClient, id: &str, image: Option<&str>) -> Result<()> {
+    let id_or_prefix = IdOrPrefix::parse(id);
+    let uuid = resolve_job_id(client, &id_or_prefix).await?;
+
+    let (mut sender, mut receiver) = client.debug_job(uuid, image).await?;
+
+    // Standard input is read on a plain thread: a blocking read still
+    // pending in the runtime would keep the CLI from exiting with the shell
+    let (input_tx, mut input_rx) = tokio::sync::mpsc::channel::<Vec<u8>>(16);
+    std::thread::spawn(move || {
+        let mut stdin = std::io::stdin();
+        let mut buffer = [0u8; 4096];
+        loop {
+            match stdin.read(&mut buffer) {
+                Ok(0) | Err(_) => break,
+                Ok(n) => {
+                    if input_tx.blocking_send(buffer[..n].to_vec()).is_err() {
+                        break;
+                    }
+                }
+            }
+        }
+    });
+
+    let input = async {
+        while let Some(data) = input_rx.recv().await {
+            if sender.send(DebugFrame::Data(data)).await.is_err() {
+                break;
+            }
+        }
+        let _ = sender.close().await;
+        // Keep printing output until the runner ends the session
+        std::future::pending::<()>().await;
+    };
+
+    let output = async {
+        let mut stdout = tokio::io::stdout();
+        while let Some(frame) = receiver.next().await {
+            match frame? {
+                DebugFrame::Data(data) => {
+                    stdout.write_all(&data).await?;
+                    stdout.flush().await?;
+                }
+                DebugFrame::Notice(notice) => eprintln!("{}", notice.dimmed()),
+            }
+        }
+        Ok::<_, anyhow::Error>(())
+    };
+
+    tokio::select! {
+        _ = input => Ok(()),
+        result = output => result,
+    }
+}
+
 /// Get and display a single job
 async fn get_job(client: &OrchestratorClient, id: &str) -> Result<()> {
     let id_or_prefix = IdOrPrefix::parse(id);
     let uuid = resolve_job_id(client, &id_or_prefix).await?;
 
     let job = client.get_job(uuid).await?;
+    // Orchestrators without stage tracking have no stages to show
+    let stages = client.get_job_stages(uuid).await.unwrap_or_default();
 
-    print_job_details(&job);
+    print_job_details(&job, &stages);
 
     Ok(())
 }
 
 /// Get and display job logs
-async fn get_job_logs(client: &OrchestratorClient, id: &str, follow: bool) -> Result<()> {
+///
+/// Entries are grouped under a header per stage. When following, only
+/// entries stored since the previous poll are fetched.
+async fn grep_job_logs(
+    client: &OrchestratorClient,
+    id: &str,
+    query: LogSearchQuery,
+    stage: Option<String>,
+) -> Result<()> {
     let id_or_prefix = IdOrPrefix::parse(id);
     let uuid = resolve_job_id(client, &id_or_prefix).await?;
 
-    if follow {
-        println!("{}", "⚠ Log following not yet implemented".yellow());
-        println!("{}", "  Showing current logs only...".dimmed());
-        println!();
+    let logs = client.search_job_logs(uuid, &query).await?;
+    if logs.is_empty() {
+        println!(
+            "{}",
+            format!("No log entries of job {} match '{}'.", uuid, query.q).yellow()
+        );
+        return Ok(());
     }
 
-    let logs = client.get_job_logs(uuid).await?;
+    let count = logs.len();
+    println!(
+        "{}",
+        format!("Logs for job {} matching '{}':", uuid, query.q).bold()
+    );
+    println!("{}", "─".repeat(80).dimmed());
+    LogPrinter::new(stage).print(logs);
+    println!("{}", "─".repeat(80).dimmed());
+    if query.limit.is_some_and(|limit| count >= limit as usize) {
+        println!(
+            "{}",
+            format!(
+                "Showing the first {} matches; refine the pattern to see others.",
+                count
+            )
+            .dimmed()
+        );
+    }
 
-    if logs.is_empty() {
-        println!("{}", "No logs found for this job.".yellow());
+    Ok(())
+}
+
+async fn get_job_logs(
+    client: &OrchestratorClient,
+    id: &str,
+    follow: bool,
+    mut query: LogQuery,
+    stage: Option<String>,
+) -> Result<()> {
+    let id_or_prefix = IdOrPrefix::parse(id);
+    let uuid = resolve_job_id(client, &id_or_prefix).await?;
+
+    let logs = if query.is_filtered() {
+        client.query_job_logs(uuid, &query).await?
     } else {
-        println!("{}", format!("Logs for job {}:", uuid).bold());
-        println!("{}", "─".repeat(80).dimmed());
+        client.get_job_logs(uuid).await?
+    };
+
+    if logs.is_empty() && !follow {
+        println!("{}", "No logs found for this job.".yellow());
+        return Ok(());
+    }
+
+    println!("{}", format!("Logs for job {}:", uuid).bold());
+    println!("{}", "─".repeat(80).dimmed());
+    let mut printer = LogPrinter::new(stage);
+    printer.print(logs);
+
+    if follow {
+        // Keep the level filter; the tail only applies to the first fetch
+        query.tail = None;
+        follow_job_logs(client, uuid, query, &mut printer).await?;
+    }
+
+    println!("{}", "─".repeat(80).dimmed());
+
+    Ok(())
+}
+
+/// Polls for entries after the last printed one until the job finishes
+async fn follow_job_logs(
+    client: &OrchestratorClient,
+    job_id: Uuid,
+    mut query: LogQuery,
+    printer: &mut LogPrinter,
+) -> Result<()> {
+    loop {
+        // Read the status first so entries stored before the job finished
+        // are still fetched below
+        let job = client.get_job(job_id).await?;
+        let finished = !matches!(
+            job.status,
+            JobStatus::Queued | JobStatus::Blocked | JobStatus::Running
+        );
+
+        query.after = Some(printer.last_sequence);
+        let logs = client.query_job_logs(job_id, &query).await?;
+        printer.print(logs);
+
+        if finished {
+            println!("Job finished: {}", colorize_status(&job.status));
+            return Ok(());
+        }
+
+        tokio::time::sleep(LOG_FOLLOW_INTERVAL).await;
+    }
+}
+
+/// Prints log entries grouped by stage
+///
+/// A header is printed whenever the stage changes and the stage's entries
+/// are indented under it. Keeps its position across calls so followed logs
+/// continue the current group.
+struct LogPrinter {
+    /// Highest sequence seen, printed or filtered out
+    last_sequence: u64,
+    /// Stage of the previously printed entry
+    current_stage: Option<String>,
+    /// Only print entries of this stage
+    only_stage: Option<String>,
+}
+
+impl LogPrinter {
+    fn new(only_stage: Option<String>) -> Self {
+        Self {
+            last_sequence: 0,
+            current_stage: None,
+            only_stage,
+        }
+    }
+
+    fn print(&mut self, logs: Vec<LogEntry>) {
         for log in logs {
+            self.last_sequence = self.last_sequence.max(log.sequence.unwrap_or(0));
+
+            if self.only_stage.is_some() && log.stage != self.only_stage {
+                continue;
+            }
+
+            if log.stage != self.current_stage {
+                match &log.stage {
+                    Some(stage) => println!("{} {}", "▸".cyan(), stage.bold()),
+                    None => println!(),
+                }
+                self.current_stage = log.stage.clone();
+            }
+
+            if log.stage.is_some() {
+                print!("  ");
+            }
             print_log_entry(&log);
         }
-        println!("{}", "─".repeat(80).dimmed());
     }
-
-    Ok(())
 }
 
 /// List jobs for a specific pipeline
@@ -158,14 +676,17 @@ async fn list_pipeline_jobs(
         let job_uuid = resolve_job_id_in_pipeline(client, pipeline_uuid, &job_id_or_prefix).await?;
 
         let job = client.get_job(job_uuid).await?;
-        print_job_details(&job);
+        let stages = client.get_job_stages(job_uuid).await.unwrap_or_default();
+        print_job_details(&job, &stages);
         return Ok(());
     }
 
     // Otherwise, list all jobs for the pipeline
     let jobs = client.list_jobs_by_pipeline(pipeline_uuid).await?;
 
-    if jobs.is_empty() {
+    if output::is_plain() {
+        jobs.iter().for_each(job_record);
+    } else if jobs.is_empty() {
         println!(
             "{}",
             format!("No jobs found for pipeline {}.", pipeline_uuid).yellow()
@@ -189,11 +710,163 @@ async fn list_pipeline_jobs(
     Ok(())
 }
 
+/// Relaunch a job with its original parameters plus overrides
+///
+/// Original values that no longer fit the current pipeline inputs (e.g. the
+/// input type changed) are dropped, and required inputs left without a value
+/// are prompted for again. Secret inputs are never kept, as the orchestrator
+/// only shows them masked.
+async fn rerun_job(
+    client: &OrchestratorClient,
+    id: &str,
+    overrides: Vec<(String, String)>,
+    no_interactive: bool,
+) -> Result<()> {
+    let id_or_prefix = IdOrPrefix::parse(id);
+    let uuid = resolve_job_id(client, &id_or_prefix).await?;
+
+    let original = client.get_job(uuid).await?;
+    let pipeline = client.get_pipeline(original.pipeline_id).await?;
+
+    let lua = rivet_lua::create_sandbox()
+        .map_err(|e| anyhow::anyhow!("Failed to create sandbox: {}", e))?;
+    let definition = rivet_lua::parse_pipeline_definition(&lua, &pipeline.script)?;
+
+    let mut parameters = original.parameters.clone();
+
+    // Drop original values the current definition would reject
+    for (key, input_def) in &definition.inputs {
+        let Some(value) = parameters.get(key) else {
+            continue;
+        };
+        let still_valid = match input_def.input_type {
+            InputType::Secret => {
+                parameters.remove(key);
+                if !input_def.required {
+                    println!(
+                        "{}",
+                        format!(
+                            "⚠ Secret input '{}' is not kept, pass it again with -p",
+                            key
+                        )
+                        .yellow()
+                    );
+                }
+                continue;
+            }
+            InputType::Array => value.is_array(),
+            InputType::File => InputFile::parse(value).is_ok(),
+            _ => json_to_input_string(value)
+                .and_then(|s| validate_and_convert_input(key, &s, input_def).ok())
+                .is_some(),
+        };
+        if !still_valid {
+            println!(
+                "{}",
+                format!(
+                    "⚠ Previous value of '{}' no longer matches the pipeline input",
+                    key
+                )
+                .yellow()
+            );
+            parameters.remove(key);
+        }
+    }
+
+    // Apply overrides
+    for (key, value) in overrides {
+        let json_value = match definition.inputs.get(&key) {
+            Some(input_def) => validate_and_convert_input(&key, &value, input_def)?,
+            None => {
+                println!(
+                    "{}",
+                    format!("⚠ '{}' is not an input of pipeline {}", key, pipeline.name).yellow()
+                );
+                JsonValue::String(value)
+            }
+        };
+        parameters.insert(key, json_value);
+    }
+
+    // Fill inputs that still have no value
+    for (key, input_def) in &definition.inputs {
+        if parameters.contains_key(key) {
+            continue;
+        }
+
+        if let Some(default) = &input_def.default {
+            parameters.insert(key.clone(), default.clone());
+        } else if input_def.required {
+            if no_interactive {
+                bail!(
+                    "Missing required input '{}' ({}). Use -p {}=<value> or run without --no-interactive",
+                    key,
+                    input_def.input_type,
+                    key
+                );
+            }
+            if let Some(value) = prompt_for_input(key, input_def)? {
+                parameters.insert(key.clone(), value);
+            }
+        }
+    }
+
+    let job = client
+        .launch_job(CreateJob {
+            pipeline_id: original.pipeline_id,
+            parameters,
+            plan: false,
+            commit: original.commit.clone(),
+            override_blackout: false,
+            labels: original.labels.clone(),
+        })
+        .await?;
+    if output::is_plain() {
+        job_record(&job);
+        return Ok(());
+    }
+
+    println!("{}", "✓ Job relaunched successfully!".green().bold());
+    println!("  Job ID:      {}", job.id.to_string().cyan());
+    println!("  Rerun of:    {}", original.id.to_string().dimmed());
+    println!("  Pipeline ID: {}", job.pipeline_id.to_string().dimmed());
+    println!("  Status:      {}", format!("{:?}", job.status).yellow());
+
+    Ok(())
+}
+
+/// Convert a stored parameter back to the string form accepted on the command line
+fn json_to_input_string(value: &JsonValue) -> Option<String> {
+    match value {
+        JsonValue::String(s) => Some(s.clone()),
+        JsonValue::Number(n) => Some(n.to_string()),
+        JsonValue::Bool(b) => Some(b.to_string()),
+        _ => None,
+    }
+}
+
+/// Print a job as a plain record: ID, pipeline ID, status, requested at
+pub(crate) fn job_record(job: &Job) {
+    output::record(
+        &job.id.to_string(),
+        &[
+            &job.pipeline_id.to_string(),
+            &format!("{:?}", job.status),
+            &job.requested_at.to_rfc3339(),
+        ],
+    );
+}
+
 /// Print a job summary from a full Job object
 fn print_job_summary(job: &Job) {
     let status_colored = colorize_status(&job.status);
 
-    println!("  {} Job {}", "▸".cyan(), job.id.to_string().dimmed());
+    println!(
+        "  {} Job #{} {}",
+        "▸".cyan(),
+        job.number,
+        job.id.to_string().dimmed()
+    );
     println!("    Pipeline: {}", job.pipeline_id.to_string().dimmed());
     println!("    Status:   {}", status_colored);
     println!(
@@ -206,17 +879,37 @@ fn print_job_summary(job: &Job) {
     if let Some(runner) = &job.runner_id {
         println!("    Runner:   {}", runner.dimmed());
     }
+    if job.max_attempts > 1 {
+        println!("    Attempt:  {} of {}", job.attempt, job.max_attempts);
+    }
+    if let Some(reason) = &job.dead_letter_reason {
+        println!("    Reason:   {}", reason.red());
+    }
+    if !job.labels.is_empty() {
+        println!("    Labels:   {}", format_labels(&job.labels).dimmed());
+    }
     println!();
 }
 
-/// Print detailed job information
-fn print_job_details(job: &Job) {
+/// Labels as `key=value` pairs separated by commas, as `--label` filters take them
+fn format_labels(labels: &BTreeMap<String, String>) -> String {
+    labels
+        .iter()
+        .map(|(key, value)| format!("{}={}", key, value))
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+/// Print detailed job information, with its stages
+fn print_job_details(job: &Job, stages: &[StageRun]) {
     let status_colored = colorize_status(&job.status);
 
     println!("{}", "Job Details:".bold());
     println!("  ID:          {}", job.id.to_string().cyan());
+    println!("  Number:      #{}", job.number);
     println!("  Pipeline ID: {}", job.pipeline_id.to_string().dimmed());
     println!("  Status:      {}", status_colored);
+    println!("  Attempt:     {} of {}", job.attempt, job.max_attempts);
     println!(
         "  Requested:   {}",
         job.requested_at.format("%Y-%m-%d %H:%M:%S")
@@ -241,6 +934,29 @@ fn print_job_details(job: &Job) {
         println!("  Runner:      {}", runner);
     }
 
+    if let Some(reason) = &job.dead_letter_reason {
+        println!("  Dead-lettered: {}", reason.red());
+    }
+
+    if let Some(original) = job.resumed_from {
+        println!("  Resumed from: {}", original.to_string().dimmed());
+    }
+
+    if let Some(commit) = &job.commit {
+        println!(
+            "  Commit:      {}@{}",
+            commit.repository,
+            commit.sha.yellow()
+        );
+    }
+
+    if !job.labels.is_empty() {
+        println!("\n{}", "Labels:".bold());
+        for (key, value) in &job.labels {
+            println!("  {} = {}", key.cyan(), value);
+        }
+    }
+
     if !job.parameters.is_empty() {
         println!("\n{}", "Parameters:".bold());
         for (key, value) in &job.parameters {
@@ -248,6 +964,65 @@ fn print_job_details(job: &Job) {
         }
     }
 
+    if !stages.is_empty() {
+        println!("\n{}", "Stages:".bold());
+        let width = stages.iter().map(|s| s.name.len()).max().unwrap_or(0);
+        for stage in stages {
+            let duration = stage
+                .completed_at
+                .map(|completed| {
+                    let seconds = completed
+                        .signed_duration_since(stage.started_at)
+                        .num_seconds();
+                    format!(" {}s", seconds).dimmed()
+                })
+                .unwrap_or_else(|| "".normal());
+            let needs = if stage.needs.is_empty() {
+                "".normal()
+            } else {
+                format!("  (needs {})", stage.needs.join(", ")).dimmed()
+            };
+            println!(
+                "  {:width$}  {}{}{}",
+                stage.name,
+                colorize_stage_status(stage.status),
+                duration,
+                needs,
+                width = width
+            );
+            if let Some(error) = &stage.error_message {
+                println!("  {:width$}  {}", "", error.red(), width = width);
+            }
+            if let Some(result) = &stage.result {
+                if let Some(summary) = &result.summary {
+                    println!("  {:width$}  {}", "", summary, width = width);
+                }
+                if !result.metrics.is_empty() {
+                    let metrics: Vec<String> = result
+                        .metrics
+                        .iter()
+                        .map(|(name, value)| format!("{}={}", name, value))
+                        .collect();
+                    println!(
+                        "  {:width$}  {}",
+                        "",
+                        metrics.join(", ").cyan(),
+                        width = width
+                    );
+                }
+                if !result.artifacts.is_empty() {
+                    println!(
+                        "  {:width$}  {} {}",
+                        "",
+                        "artifacts:".dimmed(),
+                        result.artifacts.join(", "),
+                        width = width
+                    );
+                }
+            }
+        }
+    }
+
     if let Some(result) = &job.result {
         println!("\n{}", "Result:".bold());
         println!(
@@ -276,6 +1051,281 @@ fn print_job_details(job: &Job) {
     }
 }
 
+// =============================================================================
+// Job Comparison
+// =============================================================================
+
+/// A job with its stages and log, as compared by `rivet job compare`
+struct ComparedJob {
+    job: Job,
+    stages: Vec<StageRun>,
+    logs: Vec<LogEntry>,
+}
+
+impl ComparedJob {
+    async fn fetch(client: &OrchestratorClient, id: &str) -> Result<Self> {
+        let uuid = resolve_job_id(client, &IdOrPrefix::parse(id)).await?;
+        let job = client.get_job(uuid).await?;
+        // Orchestrators without stage tracking have no stages to compare
+        let stages = client.get_job_stages(uuid).await.unwrap_or_default();
+        let logs = client.get_job_logs(uuid).await?;
+        Ok(Self { job, stages, logs })
+    }
+
+    fn stage(&self, name: &str) -> Option<&StageRun> {
+        self.stages.iter().find(|s| s.name == name)
+    }
+
+    /// Messages a stage logged, one per line
+    fn stage_log(&self, name: &str) -> String {
+        let mut log = String::new();
+        for entry in self
+            .logs
+            .iter()
+            .filter(|e| e.stage.as_deref() == Some(name))
+        {
+            log.push_str(&entry.message);
+            log.push('\n');
+        }
+        log
+    }
+}
+
+/// Compare two jobs, e.g. the last good run of a pipeline and a failing one
+///
+/// # Arguments
+/// * `first` - Reference job ID or prefix
+/// * `second` - ID or prefix of the job compared with it
+/// * `stages` - Stages whose logs are diffed, besides those that failed
+/// * `context` - Unchanged log lines shown around each difference
+async fn compare_jobs(
+    client: &OrchestratorClient,
+    first: &str,
+    second: &str,
+    stages: &[String],
+    context: usize,
+) -> Result<()> {
+    let a = ComparedJob::fetch(client, first).await?;
+    let b = ComparedJob::fetch(client, second).await?;
+
+    println!("{}", "Comparing jobs:".bold());
+    for (marker, compared) in [("-", &a), ("+", &b)] {
+        let job = &compared.job;
+        println!(
+            "  {} {}  {}  {}{}",
+            marker,
+            job.id.to_string().cyan(),
+            colorize_status(&job.status),
+            job.requested_at
+                .format("%Y-%m-%d %H:%M:%S")
+                .to_string()
+                .dimmed(),
+            job_duration(job)
+                .map(|seconds| format!("  {}s", seconds))
+                .unwrap_or_default()
+        );
+    }
+    if a.job.pipeline_id != b.job.pipeline_id {
+        println!("  {}", "The jobs belong to different pipelines".yellow());
+    }
+
+    compare_parameters(&a.job, &b.job);
+    compare_stages(&a, &b);
+    compare_results(&a.job, &b.job);
+
+    // Logs of the stages that failed in either job, then those asked for
+    let mut diffed: Vec<&str> = Vec::new();
+    for stage in a.stages.iter().chain(&b.stages) {
+        if stage.status == StageStatus::Failed && !diffed.contains(&stage.name.as_str()) {
+            diffed.push(&stage.name);
+        }
+    }
+    for stage in stages {
+        if !diffed.contains(&stage.as_str()) {
+            diffed.push(stage);
+        }
+    }
+    for stage in diffed {
+        print_log_diff(stage, &a.stage_log(stage), &b.stage_log(stage), context);
+    }
+
+    Ok(())
+}
+
+/// Seconds a job ran for, if it finished
+fn job_duration(job: &Job) -> Option<i64> {
+    let started = job.started_at?;
+    Some(
+        job.completed_at?
+            .signed_duration_since(started)
+            .num_seconds(),
+    )
+}
+
+/// Seconds a stage ran for, if it finished
+fn stage_duration(stage: &StageRun) -> Option<i64> {
+    let completed = stage.completed_at?;
+    Some(
+        completed
+            .signed_duration_since(stage.started_at)
+            .num_seconds(),
+    )
+}
+
+fn compare_parameters(a: &Job, b: &Job) {
+    let mut names: Vec<&String> = a.parameters.keys().chain(b.parameters.keys()).collect();
+    names.sort();
+    names.dedup();
+
+    println!("\n{}", "Parameters:".bold());
+    let mut differences = 0;
+    for name in names {
+        match (a.parameters.get(name), b.parameters.get(name)) {
+            (Some(old), Some(new)) if old == new => {}
+            (Some(old), Some(new)) => {
+                println!(
+                    "  {} {} → {}",
+                    name.cyan(),
+                    old.to_string().red(),
+                    new.to_string().green()
+                );
+                differences += 1;
+            }
+            (Some(old), None) => {
+                println!(
+                    "  {} {} → {}",
+                    name.cyan(),
+                    old.to_string().red(),
+                    "(unset)".dimmed()
+                );
+                differences += 1;
+            }
+            (None, Some(new)) => {
+                println!(
+                    "  {} {} → {}",
+                    name.cyan(),
+                    "(unset)".dimmed(),
+                    new.to_string().green()
+                );
+                differences += 1;
+            }
+            (None, None) => {}
+        }
+    }
+    if differences == 0 {
+        println!("  {}", "Identical".dimmed());
+    }
+}
+
+fn compare_stages(a: &ComparedJob, b: &ComparedJob) {
+    let mut names: Vec<&str> = a.stages.iter().map(|s| s.name.as_str()).collect();
+    for stage in &b.stages {
+        if !names.contains(&stage.name.as_str()) {
+            names.push(&stage.name);
+        }
+    }
+    if names.is_empty() {
+        return;
+    }
+
+    println!("\n{}", "Stages:".bold());
+    let width = names.iter().map(|n| n.len()).max().unwrap_or(0);
+    for name in names {
+        let (old, new) = (a.stage(name), b.stage(name));
+        let status = |stage: Option<&StageRun>| {
+            // Pad before colouring, escape codes would count towards the width
+            let text = stage.map_or("-".to_string(), |s| format!("{:?}", s.status));
+            let padded = format!("{:14}", text);
+            match stage {
+                Some(stage) if old.map(|s| s.status) != new.map(|s| s.status) => {
+                    let mut colored = colorize_stage_status(stage.status);
+                    colored.input = padded;
+                    colored
+                }
+                _ => padded.dimmed(),
+            }
+        };
+
+        let durations = match (old.and_then(stage_duration), new.and_then(stage_duration)) {
+            (Some(old), Some(new)) => {
+                let delta = new - old;
+                let delta = match delta {
+                    0 => "±0s".dimmed(),
+                    d if d > 0 => format!("+{}s", d).red(),
+                    d => format!("{}s", d).green(),
+                };
+                format!("{}s → {}s ({})", old, new, delta)
+            }
+            (old, new) => {
+                let show = |d: Option<i64>| d.map_or("-".to_string(), |d| format!("{}s", d));
+                format!("{} → {}", show(old), show(new))
+                    .dimmed()
+                    .to_string()
+            }
+        };
+
+        println!(
+            "  {:width$}  {} → {}  {}",
+            name,
+            status(old),
+            status(new),
+            durations,
+            width = width
+        );
+    }
+}
+
+fn compare_results(a: &Job, b: &Job) {
+    let describe = |job: &Job| match &job.result {
+        Some(result) if result.success => format!("success (exit code {})", result.exit_code),
+        Some(result) => format!("failure (exit code {})", result.exit_code),
+        None => "none".to_string(),
+    };
+    let error = |job: &Job| job.result.as_ref().and_then(|r| r.error_message.clone());
+
+    println!("\n{}", "Result:".bold());
+    let (old, new) = (describe(a), describe(b));
+    if old == new {
+        println!("  {}", old.dimmed());
+    } else {
+        println!("  {} → {}", old.red(), new.green());
+    }
+    if error(a) != error(b) {
+        if let Some(error) = error(a) {
+            println!("  - {}", error.red());
+        }
+        if let Some(error) = error(b) {
+            println!("  + {}", error.green());
+        }
+    }
+}
+
+/// Print a unified diff of the logs of a stage in both jobs
+fn print_log_diff(stage: &str, old: &str, new: &str, context: usize) {
+    println!("\n{}", format!("Logs of stage '{}':", stage).bold());
+    if old == new {
+        println!("  {}", "Identical".dimmed());
+        return;
+    }
+
+    let diff = TextDiff::from_lines(old, new);
+    for (idx, group) in diff.grouped_ops(context).iter().enumerate() {
+        if idx > 0 {
+            println!("  {}", "⋯".dimmed());
+        }
+        for op in group {
+            for change in diff.iter_changes(op) {
+                let line = change.value().trim_end_matches('\n');
+                match change.tag() {
+                    ChangeTag::Delete => println!("  {}", format!("- {}", line).red()),
+                    ChangeTag::Insert => println!("  {}", format!("+ {}", line).green()),
+                    ChangeTag::Equal => println!("  {}", format!("  {}", line).dimmed()),
+                }
+            }
+        }
+    }
+}
+
 /// Print a log entry
 fn print_log_entry(log: &LogEntry) {
     let level_str = format!("{:?}", log.level).to_uppercase();
@@ -286,14 +1336,46 @@ fn print_log_entry(log: &LogEntry) {
         LogLevel::Error => level_str.red(),
     };
 
+    // Process output is marked with its stream, pipeline messages are not
+    let stream = match log.stream {
+        Some(stream) => format!("{} ", stream).dimmed(),
+        None => "".normal(),
+    };
+
+    // Structured fields follow the message as `name=value`
+    let fields: String = log
+        .fields
+        .iter()
+        .flatten()
+        .map(|(name, value)| match value {
+            serde_json::Value::String(value) => format!(" {}={}", name, value),
+            value => format!(" {}={}", name, value),
+        })
+        .collect();
+
     println!(
-        "{} [{}] {}",
+        "{} [{}] {}{}{}",
         log.timestamp.format("%H:%M:%S").to_string().dimmed(),
         level_colored,
-        log.message
+        stream,
+        log.message,
+        fields.dimmed()
     );
 }
 
+/// Colorize stage status for display
+fn colorize_stage_status(status: StageStatus) -> colored::ColoredString {
+    let status_str = format!("{:?}", status);
+    match status {
+        StageStatus::Running => status_str.cyan(),
+        StageStatus::Succeeded => status_str.green(),
+        StageStatus::Failed => status_str.red(),
+        StageStatus::Skipped => status_str.dimmed(),
+        StageStatus::Reused => status_str.green().dimmed(),
+        StageStatus::AllowedFailure => status_str.yellow(),
+    }
+}
+
 /// Colorize job status for display
 fn colorize_status(status: &JobStatus) -> colored::ColoredString {
     let status_str = format!("{:?}", status);
@@ -304,5 +1386,7 @@ fn colorize_status(status: &JobStatus) -> colored::ColoredString {
         JobStatus::Failed => status_str.red(),
         JobStatus::Cancelled => status_str.dimmed(),
         JobStatus::TimedOut => status_str.red(),
+        JobStatus::DeadLettered => status_str.red().bold(),
+        JobStatus::Blocked => status_str.yellow().dimmed(),
     }
 }
@@ -2,24 +2,42 @@
 //!
 //! Defines all CLI commands and their handlers.
 
+mod artifact;
+mod blackout;
+mod doctor;
+mod environment;
+mod events;
 mod init;
 mod job;
 mod pipeline;
+mod project;
 mod runner;
+mod secret;
 
+pub use blackout::BlackoutCommands;
+pub use environment::EnvironmentCommands;
 pub use init::InitCommands;
 pub use job::JobCommands;
 pub use pipeline::PipelineCommands;
+pub(crate) use pipeline::parse_key_val;
+pub use project::ProjectCommands;
 pub use runner::RunnerCommands;
+pub use secret::SecretCommands;
 
 use anyhow::Result;
 use clap::Subcommand;
+use std::path::PathBuf;
 
 use crate::config::Config;
 
 /// Top-level CLI commands
 #[derive(Subcommand)]
 pub enum Commands {
+    /// Project management
+    Project {
+        #[command(subcommand)]
+        command: ProjectCommands,
+    },
     /// Pipeline management
     Pipeline {
         #[command(subcommand)]
@@ -35,6 +53,34 @@ pub enum Commands {
         #[command(subcommand)]
         command: RunnerCommands,
     },
+    /// Secret management
+    Secret {
+        #[command(subcommand)]
+        command: SecretCommands,
+    },
+    /// Environment and deployment management
+    #[command(name = "env")]
+    Environment {
+        #[command(subcommand)]
+        command: EnvironmentCommands,
+    },
+    /// Blackout windows deferring the jobs of pipelines and environments
+    Blackout {
+        #[command(subcommand)]
+        command: BlackoutCommands,
+    },
+    /// Diagnose the local setup and orchestrator connectivity
+    Doctor {
+        /// Workspace base directory used by a local runner
+        #[arg(long, env = "WORKSPACE_BASE", default_value = "/tmp")]
+        workspace: PathBuf,
+    },
+    /// Watch job, pipeline and runner events live
+    Events {
+        /// Print raw JSON events, one per line
+        #[arg(long)]
+        json: bool,
+    },
     /// Initialize development environment
     Init {
         #[command(subcommand)]
@@ -54,9 +100,17 @@ pub enum Commands {
 /// Result indicating success or failure
 pub async fn handle_command(command: Commands, config: &Config) -> Result<()> {
     match command {
+        Commands::Project { command } => project::handle_project_command(command, config).await,
         Commands::Pipeline { command } => pipeline::handle_pipeline_command(command, config).await,
         Commands::Job { command } => job::handle_job_command(command, config).await,
         Commands::Runner { command } => runner::handle_runner_command(command, config).await,
+        Commands::Secret { command } => secret::handle_secret_command(command, config).await,
+        Commands::Environment { command } => {
+            environment::handle_environment_command(command, config).await
+        }
+        Commands::Blackout { command } => blackout::handle_blackout_command(command, config).await,
+        Commands::Doctor { workspace } => doctor::handle_doctor_command(workspace, config).await,
+        Commands::Events { json } => events::handle_events_command(json, config).await,
         Commands::Init { command } => init::handle_init_command(command, config).await,
     }
 }
@@ -6,17 +6,39 @@
 use anyhow::Result;
 use clap::Subcommand;
 use colored::*;
-use rivet_core::domain::pipeline::Pipeline;
-use rivet_core::dto::job::CreateJob;
-use rivet_core::dto::pipeline::CreatePipeline;
+use rivet_core::domain::job::CommitRef;
+use rivet_core::domain::pipeline::{InputType, Pipeline};
+use rivet_core::domain::preset::ParameterPreset;
+use rivet_core::domain::schedule::{CatchUpPolicy, Schedule};
+use rivet_core::domain::webhook::{TriggerFilters, WebhookTrigger};
+use rivet_core::dto::artifact::ArtifactRetention;
+use rivet_core::dto::commit_status::{CommitStatusConfig, GitProvider};
+use rivet_core::dto::job::{
+    CreateJob, InputFile, JobPlan, MASKED_INPUT, MAX_INPUT_FILE_BYTES, StageDecision,
+};
+use rivet_core::dto::pipeline::{
+    CreatePipeline, DeletePipelineQuery, FlakyStagesQuery, PipelineDefaults, PipelineGraph,
+    PipelineStats, PipelineStatsQuery, StatsBucket,
+};
+use rivet_core::dto::preset::SavePreset;
+use rivet_core::dto::schedule::CreateSchedule;
+use rivet_core::dto::version::API_PREFIX;
+use rivet_core::dto::webhook::CreateWebhookTrigger;
 use serde_json::Value as JsonValue;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use uuid::Uuid;
 
+use crate::commands::artifact::format_size;
+use crate::commands::job::job_record;
 use crate::config::Config;
-use crate::id_resolver::resolve_pipeline_id;
+use crate::id_resolver::{resolve_pipeline_id, resolve_pipeline_in};
+use crate::output;
+use crate::picker;
 use crate::types::IdOrPrefix;
 use rivet_client::OrchestratorClient;
+use rivet_lua::definition::InputDefinition;
 
 /// Pipeline subcommands
 #[derive(Subcommand)]
@@ -31,22 +53,87 @@ pub enum PipelineCommands {
         /// Path to Lua script file
         script: String,
     },
+    /// Run a pipeline's tests against mock modules, without containers or
+    /// an orchestrator
+    Test {
+        /// Path to Lua script file
+        script: String,
+
+        /// Path to the Lua file registering the tests
+        #[arg(long)]
+        tests: String,
+    },
+    /// Convert a GitHub Actions workflow or GitLab CI configuration into a
+    /// Lua pipeline, leaving TODO comments where features have no equivalent
+    Import {
+        /// Path to the workflow or .gitlab-ci.yml file
+        path: String,
+
+        /// CI system the file is written for (github or gitlab)
+        #[arg(long)]
+        from: GitProvider,
+
+        /// Write the pipeline to this file instead of stdout
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+
+        /// Overwrite the output file if it exists
+        #[arg(long, requires = "output")]
+        force: bool,
+    },
+    /// Show the order a pipeline's stages run in and what each waits for
+    Graph {
+        /// Pipeline ID, unambiguous prefix, or path to a Lua script
+        target: String,
+
+        /// Output format: ascii, or dot for Graphviz
+        #[arg(long, value_parser = parse_graph_format, default_value = "ascii")]
+        format: GraphFormat,
+    },
     /// List all pipelines
-    List,
+    List {
+        /// List the archived pipelines instead of the active ones
+        #[arg(long)]
+        archived: bool,
+    },
     /// Get pipeline details
     Get {
-        /// Pipeline ID or unambiguous prefix
+        /// Pipeline ID, unambiguous prefix or name
         id: String,
     },
-    /// Delete a pipeline
+    /// Delete a pipeline, or archive it when jobs reference it
     Delete {
-        /// Pipeline ID or unambiguous prefix
+        /// Pipeline ID, unambiguous prefix or name
+        id: String,
+
+        /// Archive the pipeline even if no job references it
+        #[arg(long, conflicts_with = "force")]
+        archive: bool,
+
+        /// Delete the pipeline along with its jobs
+        #[arg(long)]
+        force: bool,
+    },
+    /// Make an archived pipeline active again
+    Unarchive {
+        /// Archived pipeline ID, unambiguous prefix or name
+        id: String,
+    },
+    /// Pause a pipeline: refuse new jobs, manual, scheduled or from webhooks
+    Disable {
+        /// Pipeline ID, unambiguous prefix or name
+        id: String,
+    },
+    /// Resume launching jobs of a disabled pipeline
+    Enable {
+        /// Pipeline ID, unambiguous prefix or name
         id: String,
     },
     /// Launch a job from a pipeline
     Launch {
-        /// Pipeline ID or unambiguous prefix
-        id: String,
+        /// Pipeline ID, unambiguous prefix or name; picked from a list when
+        /// left out in a terminal
+        id: Option<String>,
 
         /// Parameters as key=value pairs (e.g., branch=main repo=myrepo)
         #[arg(short, long, value_parser = parse_key_val)]
@@ -55,11 +142,321 @@ pub enum PipelineCommands {
         /// Skip interactive input prompts, use only provided params
         #[arg(long)]
         no_interactive: bool,
+
+        /// Launch one job per parameter set in a JSON file
+        /// (an array of objects, e.g. one per changed service)
+        #[arg(long, conflicts_with = "param")]
+        params_file: Option<PathBuf>,
+
+        /// Show which stages would run, and in what containers, without
+        /// launching the job
+        #[arg(long, conflicts_with = "params_file")]
+        dry_run: bool,
+
+        /// Commit the job builds, as REPOSITORY@SHA (e.g. acme/api@4f2b8c1);
+        /// its status is reported if the pipeline is set up to
+        #[arg(long, value_parser = parse_commit, conflicts_with = "params_file")]
+        commit: Option<CommitRef>,
+
+        /// Run the job even during a blackout window of the pipeline or of
+        /// an environment it deploys to, for emergencies
+        #[arg(long, conflicts_with = "params_file")]
+        override_blackout: bool,
+
+        /// Labels of the job as key=value pairs (e.g., team=payments)
+        #[arg(short, long, value_parser = parse_key_val, conflicts_with = "params_file")]
+        label: Vec<(String, String)>,
+
+        /// Saved parameter preset to start from; --param values replace its
+        /// values and inputs it leaves out are prompted for
+        #[arg(long, conflicts_with = "params_file")]
+        preset: Option<String>,
+    },
+    /// Show or change the input values the orchestrator supplies to launches
+    Defaults {
+        /// Pipeline ID, unambiguous prefix or name
+        id: String,
+
+        /// Value used when a launch leaves the input out (key=value)
+        #[arg(long = "default", value_parser = parse_key_val)]
+        defaults: Vec<(String, String)>,
+
+        /// Value used whatever a launch asks for (key=value)
+        #[arg(long = "override", value_parser = parse_key_val)]
+        overrides: Vec<(String, String)>,
+
+        /// Remove the default and override of an input
+        #[arg(long)]
+        unset: Vec<String>,
+
+        /// Remove every default and override before applying the others
+        #[arg(long)]
+        clear: bool,
+    },
+    /// Show or change how long the orchestrator keeps a pipeline's artifacts
+    Retention {
+        /// Pipeline ID, unambiguous prefix or name
+        id: String,
+
+        /// Keep the artifacts of the N most recent finished jobs only
+        #[arg(long, value_name = "N")]
+        keep_last: Option<u32>,
+
+        /// Keep at most this many bytes of artifacts, dropping the oldest jobs' first
+        #[arg(long, value_name = "BYTES")]
+        max_bytes: Option<u64>,
+
+        /// Delete artifacts older than this (e.g. 3600, 12h, 30d)
+        #[arg(long, value_parser = parse_ttl)]
+        ttl: Option<u64>,
+
+        /// Remove every limit before applying the others
+        #[arg(long)]
+        clear: bool,
+    },
+    /// Show or change how a pipeline reports commit statuses to GitHub or GitLab
+    CommitStatus {
+        /// Pipeline ID, unambiguous prefix or name
+        id: String,
+
+        /// Provider to report to (github or gitlab)
+        #[arg(long)]
+        provider: Option<GitProvider>,
+
+        /// Secret holding the provider token
+        #[arg(long, value_name = "SECRET")]
+        token_secret: Option<String>,
+
+        /// Name of the status on the commit (default: rivet/<pipeline name>)
+        #[arg(long)]
+        context: Option<String>,
+
+        /// API base URL, for GitHub Enterprise or self-hosted GitLab
+        #[arg(long, value_name = "URL")]
+        api_url: Option<String>,
+
+        /// Link shown with the status, {job_id} and {job_number} replaced by the
+        /// job's id and number
+        #[arg(long, value_name = "URL")]
+        target_url: Option<String>,
+
+        /// Stop reporting commit statuses
+        #[arg(long, conflicts_with_all = ["provider", "token_secret", "context", "api_url", "target_url"])]
+        disable: bool,
+    },
+    /// Show how a pipeline's recent jobs fared
+    Stats {
+        /// Pipeline ID, unambiguous prefix or name
+        id: String,
+
+        /// Days of history to cover
+        #[arg(long, default_value_t = 30)]
+        days: u32,
+
+        /// Group the trend by week instead of by day
+        #[arg(long)]
+        weekly: bool,
+    },
+    /// List stages whose outcome keeps flipping on the same pipeline version
+    Flaky {
+        /// Pipeline ID, unambiguous prefix or name
+        id: String,
+
+        /// Days of history to cover
+        #[arg(long, default_value_t = 30)]
+        days: u32,
+
+        /// Pass/fail flips needed to flag a stage (stages that passed on a
+        /// retry after failing are always flagged)
+        #[arg(long, default_value_t = 2)]
+        min_flips: u32,
+    },
+    /// Manage the webhook triggers launching jobs on pushes
+    Webhook {
+        #[command(subcommand)]
+        command: WebhookCommands,
+    },
+    /// Manage the cron schedules launching jobs at set times
+    Schedule {
+        #[command(subcommand)]
+        command: ScheduleCommands,
+    },
+    /// Manage the parameter presets launches can start from
+    Preset {
+        #[command(subcommand)]
+        command: PresetCommands,
+    },
+}
+
+/// Webhook trigger subcommands
+#[derive(Subcommand)]
+pub enum WebhookCommands {
+    /// List the webhook triggers of a pipeline
+    List {
+        /// Pipeline ID, unambiguous prefix or name
+        id: String,
+    },
+    /// Add a webhook trigger to a pipeline
+    Add {
+        /// Pipeline ID, unambiguous prefix or name
+        id: String,
+
+        /// Provider delivering the pushes (github or gitlab)
+        #[arg(long)]
+        provider: GitProvider,
+
+        /// Secret the deliveries are verified with
+        #[arg(long)]
+        secret: String,
+
+        /// Only launch for branches matching this glob (repeatable)
+        #[arg(long = "branch", value_name = "GLOB")]
+        branches: Vec<String>,
+
+        /// Only launch for tags matching this glob (repeatable)
+        #[arg(long = "tag", value_name = "GLOB")]
+        tags: Vec<String>,
+
+        /// Only launch for branch pushes changing a file matching this glob
+        /// (repeatable, e.g. 'services/api/**')
+        #[arg(long = "path", value_name = "GLOB")]
+        paths: Vec<String>,
+
+        /// Parameters of the launched jobs (key=value); {branch}, {tag}, {ref},
+        /// {sha} and {repository} are replaced from the push
+        #[arg(short, long, value_parser = parse_key_val)]
+        param: Vec<(String, String)>,
+    },
+    /// Remove a webhook trigger from a pipeline
+    Remove {
+        /// Pipeline ID, unambiguous prefix or name
+        id: String,
+
+        /// Trigger ID
+        trigger_id: Uuid,
+    },
+}
+
+/// Schedule subcommands
+#[derive(Subcommand)]
+pub enum ScheduleCommands {
+    /// List the cron schedules of a pipeline
+    List {
+        /// Pipeline ID, unambiguous prefix or name
+        id: String,
+    },
+    /// Add a cron schedule to a pipeline
+    Add {
+        /// Pipeline ID, unambiguous prefix or name
+        id: String,
+
+        /// Cron expression: minute hour day-of-month month day-of-week
+        /// (e.g. '0 3 * * *'), or @hourly, @daily, @weekly...
+        cron: String,
+
+        /// IANA time zone the expression is evaluated in (e.g. Europe/Madrid)
+        #[arg(long, default_value = "UTC")]
+        timezone: String,
+
+        /// What to do about times missed while the orchestrator was down
+        /// (skip, run_once or run_all)
+        #[arg(long, default_value = "run_once")]
+        catch_up: CatchUpPolicy,
+
+        /// Parameters of the launched jobs (key=value)
+        #[arg(short, long, value_parser = parse_key_val)]
+        param: Vec<(String, String)>,
+    },
+    /// Remove a cron schedule from a pipeline
+    Remove {
+        /// Pipeline ID, unambiguous prefix or name
+        id: String,
+
+        /// Schedule ID
+        schedule_id: Uuid,
+    },
+}
+
+/// Preset subcommands
+#[derive(Subcommand)]
+pub enum PresetCommands {
+    /// List the parameter presets of a pipeline
+    List {
+        /// Pipeline ID, unambiguous prefix or name
+        id: String,
+    },
+    /// Save a parameter preset, replacing one with the same name
+    Save {
+        /// Pipeline ID, unambiguous prefix or name
+        id: String,
+
+        /// Name of the preset (e.g., nightly)
+        name: String,
+
+        /// Parameters as key=value pairs (e.g., branch=main env=staging)
+        #[arg(short, long, value_parser = parse_key_val)]
+        param: Vec<(String, String)>,
     },
+    /// Remove a parameter preset from a pipeline
+    Remove {
+        /// Pipeline ID, unambiguous prefix or name
+        id: String,
+
+        /// Name of the preset
+        name: String,
+    },
+}
+
+/// Bars of a trend sparkline, from fewest to most jobs
+const SPARKS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+/// Parse a retention TTL into seconds: a number with an optional s, m, h or d suffix
+fn parse_ttl(s: &str) -> Result<u64> {
+    let (number, unit) = match s.char_indices().last() {
+        Some((i, 's')) => (&s[..i], 1),
+        Some((i, 'm')) => (&s[..i], 60),
+        Some((i, 'h')) => (&s[..i], 3600),
+        Some((i, 'd')) => (&s[..i], 86400),
+        _ => (s, 1),
+    };
+    let number: u64 = number
+        .parse()
+        .map_err(|_| anyhow::anyhow!("invalid TTL `{}`: expected e.g. 3600, 12h or 30d", s))?;
+    Ok(number * unit)
+}
+
+/// Parse a commit given as REPOSITORY@SHA
+fn parse_commit(s: &str) -> Result<CommitRef> {
+    let (repository, sha) = s
+        .rsplit_once('@')
+        .ok_or_else(|| anyhow::anyhow!("invalid commit `{}`: expected REPOSITORY@SHA", s))?;
+    Ok(CommitRef {
+        repository: repository.to_string(),
+        sha: sha.to_string(),
+    })
+}
+
+/// Output format of `rivet pipeline graph`
+#[derive(Debug, Clone, Copy)]
+pub enum GraphFormat {
+    Ascii,
+    Dot,
+}
+
+/// Parse a graph output format
+fn parse_graph_format(s: &str) -> Result<GraphFormat> {
+    match s {
+        "ascii" => Ok(GraphFormat::Ascii),
+        "dot" => Ok(GraphFormat::Dot),
+        _ => Err(anyhow::anyhow!(
+            "invalid format `{}`: expected ascii or dot",
+            s
+        )),
+    }
 }
 
 /// Parse a single key=value pair
-fn parse_key_val(s: &str) -> Result<(String, String)> {
+pub(crate) fn parse_key_val(s: &str) -> Result<(String, String)> {
     let pos = s
         .find('=')
         .ok_or_else(|| anyhow::anyhow!("invalid KEY=value: no `=` found in `{}`", s))?;
@@ -74,19 +471,180 @@ fn parse_key_val(s: &str) -> Result<(String, String)> {
 /// * `command` - The pipeline command to execute
 /// * `config` - The CLI configuration
 pub async fn handle_pipeline_command(command: PipelineCommands, config: &Config) -> Result<()> {
-    let client = OrchestratorClient::new(&config.orchestrator_url);
+    let client = config.client()?;
 
     match command {
         PipelineCommands::Create { script } => create_pipeline(&client, &script).await,
         PipelineCommands::Check { script } => check_pipeline(&script).await,
-        PipelineCommands::List => list_pipelines(&client).await,
+        PipelineCommands::Test { script, tests } => test_pipeline(&script, &tests).await,
+        PipelineCommands::Import {
+            path,
+            from,
+            output,
+            force,
+        } => import_pipeline(&path, from, output.as_deref(), force),
+        PipelineCommands::Graph { target, format } => {
+            show_pipeline_graph(&client, &target, format).await
+        }
+        PipelineCommands::List { archived } => list_pipelines(&client, archived).await,
         PipelineCommands::Get { id } => get_pipeline(&client, &id).await,
-        PipelineCommands::Delete { id } => delete_pipeline(&client, &id).await,
+        PipelineCommands::Delete { id, archive, force } => {
+            delete_pipeline(&client, &id, DeletePipelineQuery { archive, force }).await
+        }
+        PipelineCommands::Unarchive { id } => unarchive_pipeline(&client, &id).await,
+        PipelineCommands::Disable { id } => set_pipeline_disabled(&client, &id, true).await,
+        PipelineCommands::Enable { id } => set_pipeline_disabled(&client, &id, false).await,
+        PipelineCommands::Launch {
+            id,
+            params_file: Some(params_file),
+            ..
+        } => {
+            let id = picker::pipeline_or_pick(&client, id).await?;
+            launch_job_batch(&client, &id, &params_file).await
+        }
         PipelineCommands::Launch {
             id,
             param,
             no_interactive,
-        } => launch_job(&client, &id, param, no_interactive).await,
+            params_file: None,
+            dry_run,
+            commit,
+            override_blackout,
+            label,
+            preset,
+        } => {
+            let id = picker::pipeline_or_pick(&client, id).await?;
+            launch_job(
+                &client,
+                &id,
+                param,
+                preset.as_deref(),
+                no_interactive,
+                dry_run,
+                commit,
+                override_blackout,
+                label.into_iter().collect(),
+            )
+            .await
+        }
+        PipelineCommands::Defaults {
+            id,
+            defaults,
+            overrides,
+            unset,
+            clear,
+        } => pipeline_defaults(&client, &id, defaults, overrides, unset, clear).await,
+        PipelineCommands::Retention {
+            id,
+            keep_last,
+            max_bytes,
+            ttl,
+            clear,
+        } => {
+            let limits = ArtifactRetention {
+                keep_last_jobs: keep_last,
+                max_total_bytes: max_bytes,
+                ttl_seconds: ttl,
+            };
+            pipeline_retention(&client, &id, limits, clear).await
+        }
+        PipelineCommands::CommitStatus {
+            id,
+            provider,
+            token_secret,
+            context,
+            api_url,
+            target_url,
+            disable,
+        } => {
+            let changes = CommitStatusChanges {
+                provider,
+                token_secret,
+                context,
+                api_url,
+                target_url,
+            };
+            pipeline_commit_status(&client, &id, changes, disable).await
+        }
+        PipelineCommands::Stats { id, days, weekly } => {
+            let query = PipelineStatsQuery {
+                days: Some(days),
+                bucket: Some(if weekly {
+                    StatsBucket::Week
+                } else {
+                    StatsBucket::Day
+                }),
+            };
+            pipeline_stats(&client, &id, &query).await
+        }
+        PipelineCommands::Flaky {
+            id,
+            days,
+            min_flips,
+        } => {
+            let query = FlakyStagesQuery {
+                days: Some(days),
+                min_flips: Some(min_flips),
+            };
+            flaky_stages(&client, &id, &query).await
+        }
+        PipelineCommands::Webhook { command } => match command {
+            WebhookCommands::List { id } => {
+                list_webhook_triggers(&client, &config.orchestrator_url, &id).await
+            }
+            WebhookCommands::Add {
+                id,
+                provider,
+                secret,
+                branches,
+                tags,
+                paths,
+                param,
+            } => {
+                let req = CreateWebhookTrigger {
+                    provider,
+                    secret,
+                    filters: TriggerFilters {
+                        branches,
+                        tags,
+                        paths,
+                    },
+                    parameters: HashMap::new(),
+                };
+                add_webhook_trigger(&client, &config.orchestrator_url, &id, req, param).await
+            }
+            WebhookCommands::Remove { id, trigger_id } => {
+                remove_webhook_trigger(&client, &id, trigger_id).await
+            }
+        },
+        PipelineCommands::Schedule { command } => match command {
+            ScheduleCommands::List { id } => list_schedules(&client, &id).await,
+            ScheduleCommands::Add {
+                id,
+                cron,
+                timezone,
+                catch_up,
+                param,
+            } => {
+                let req = CreateSchedule {
+                    cron,
+                    timezone: Some(timezone),
+                    catch_up,
+                    parameters: HashMap::new(),
+                };
+                add_schedule(&client, &id, req, param).await
+            }
+            ScheduleCommands::Remove { id, schedule_id } => {
+                remove_schedule(&client, &id, schedule_id).await
+            }
+        },
+        PipelineCommands::Preset { command } => match command {
+            PresetCommands::List { id } => list_presets(&client, &id).await,
+            PresetCommands::Save { id, name, param } => {
+                save_preset(&client, &id, &name, param).await
+            }
+            PresetCommands::Remove { id, name } => remove_preset(&client, &id, &name).await,
+        },
     }
 }
 
@@ -105,6 +663,10 @@ async fn create_pipeline(client: &OrchestratorClient, script_path: &str) -> Resu
     };
 
     let pipeline = client.create_pipeline(req).await?;
+    if output::is_plain() {
+        pipeline_record(&pipeline);
+        return Ok(());
+    }
 
     println!("{}", "✓ Pipeline created successfully!".green().bold());
     println!("  ID:     {}", pipeline.id.to_string().cyan());
@@ -128,7 +690,7 @@ async fn create_pipeline(client: &OrchestratorClient, script_path: &str) -> Resu
                 "    - {}{}: {} {}",
                 key.cyan(),
                 required.red(),
-                input_def.input_type.dimmed(),
+                input_def.input_type.as_str().dimmed(),
                 input_def
                     .description
                     .as_ref()
@@ -179,7 +741,7 @@ async fn check_pipeline(script_path: &str) -> Result<()> {
                 "  - {}{}: {}",
                 key.cyan(),
                 required.red(),
-                input_def.input_type.dimmed()
+                input_def.input_type.as_str().dimmed()
             );
             if let Some(desc) = &input_def.description {
                 println!("      {}", desc.dimmed());
@@ -214,11 +776,238 @@ async fn check_pipeline(script_path: &str) -> Result<()> {
     Ok(())
 }
 
-/// List all pipelines
-async fn list_pipelines(client: &OrchestratorClient) -> Result<()> {
-    let pipelines = client.list_pipelines().await?;
+/// Run a pipeline's tests and report each outcome
+///
+/// Fails if any test fails, so that CI can gate on it.
+async fn test_pipeline(script_path: &str, tests_path: &str) -> Result<()> {
+    let script_content = std::fs::read_to_string(script_path)
+        .map_err(|e| anyhow::anyhow!("Failed to read script file '{}': {}", script_path, e))?;
+    let tests_content = std::fs::read_to_string(tests_path)
+        .map_err(|e| anyhow::anyhow!("Failed to read tests file '{}': {}", tests_path, e))?;
+
+    let outcomes = rivet_lua::run_pipeline_tests(&script_content, &tests_content)?;
+    if outcomes.is_empty() {
+        println!("{}", "No tests registered".yellow());
+        return Ok(());
+    }
+
+    for outcome in &outcomes {
+        match &outcome.failure {
+            None => println!("  {} {}", "✓".green(), outcome.name),
+            Some(failure) => {
+                println!("  {} {}", "✗".red(), outcome.name.bold());
+                for line in failure.lines() {
+                    println!("      {}", line.dimmed());
+                }
+            }
+        }
+    }
+
+    let failed = outcomes.iter().filter(|o| !o.passed()).count();
+    println!();
+    if failed > 0 {
+        anyhow::bail!("{} of {} tests failed", failed, outcomes.len());
+    }
+    println!(
+        "{}",
+        format!("✓ {} tests passed", outcomes.len()).green().bold()
+    );
+    Ok(())
+}
+
+/// Convert a CI configuration into a Lua pipeline
+fn import_pipeline(
+    path: &str,
+    from: GitProvider,
+    output: Option<&Path>,
+    force: bool,
+) -> Result<()> {
+    let source = std::fs::read_to_string(path)
+        .map_err(|e| anyhow::anyhow!("Failed to read CI file '{}': {}", path, e))?;
+    let default_name = Path::new(path)
+        .file_stem()
+        .and_then(|stem| stem.to_str())
+        .map(|stem| stem.trim_start_matches('.'))
+        .unwrap_or("imported");
+    let conversion = crate::import::convert(from, &source, path, default_name)?;
+
+    let Some(output) = output else {
+        print!("{}", conversion.script);
+        return Ok(());
+    };
+    if output.exists() && !force {
+        anyhow::bail!(
+            "'{}' already exists (use --force to overwrite)",
+            output.display()
+        );
+    }
+    std::fs::write(output, &conversion.script)
+        .map_err(|e| anyhow::anyhow!("Failed to write '{}': {}", output.display(), e))?;
+
+    println!(
+        "{}",
+        format!(
+            "✓ Converted {} stage(s) into {}",
+            conversion.stages,
+            output.display()
+        )
+        .green()
+        .bold()
+    );
+    if conversion.todos > 0 {
+        println!(
+            "  {}",
+            format!(
+                "{} TODO comment(s) mark features that were not converted",
+                conversion.todos
+            )
+            .yellow()
+        );
+    }
+    println!(
+        "  Next: {}",
+        format!("rivet pipeline check {}", output.display()).cyan()
+    );
+    Ok(())
+}
+
+/// Show the stage graph of a stored pipeline, or of a local script when
+/// `target` is a file
+async fn show_pipeline_graph(
+    client: &OrchestratorClient,
+    target: &str,
+    format: GraphFormat,
+) -> Result<()> {
+    let (name, graph) = if Path::new(target).is_file() {
+        let script_content = std::fs::read_to_string(target)
+            .map_err(|e| anyhow::anyhow!("Failed to read script file '{}': {}", target, e))?;
+        let lua = rivet_lua::create_sandbox()
+            .map_err(|e| anyhow::anyhow!("Failed to create sandbox: {}", e))?;
+        let definition = rivet_lua::parse_pipeline_definition(&lua, &script_content)?;
+        let stages: Vec<_> = definition.stages.iter().map(|s| s.to_info()).collect();
+        (definition.name, PipelineGraph::from_stages(&stages))
+    } else {
+        let uuid = resolve_pipeline_id(client, &IdOrPrefix::parse(target)).await?;
+        let pipeline = client.get_pipeline(uuid).await?;
+        (pipeline.name, client.get_pipeline_graph(uuid).await?)
+    };
+
+    match format {
+        GraphFormat::Ascii => print_graph(&name, &graph),
+        GraphFormat::Dot => print!("{}", graph_to_dot(&name, &graph)),
+    }
+    Ok(())
+}
+
+/// Print a stage graph level by level
+fn print_graph(name: &str, graph: &PipelineGraph) {
+    let levels = graph.nodes.iter().map(|n| n.level + 1).max().unwrap_or(0);
+    println!(
+        "{}",
+        format!("{} ({} stages, {} levels)", name, graph.nodes.len(), levels).bold()
+    );
+
+    for level in 0..levels {
+        println!();
+        println!("{}", format!("Level {}", level).bold());
+        let nodes: Vec<_> = graph.nodes.iter().filter(|n| n.level == level).collect();
+        for (idx, node) in nodes.iter().enumerate() {
+            let branch = if idx + 1 == nodes.len() {
+                "└─"
+            } else {
+                "├─"
+            };
+            let mut line = format!("  {} {}", branch.dimmed(), node.name.cyan());
+            if let Some(container) = &node.container {
+                line.push_str(&format!(" {}", format!("[{}]", container).dimmed()));
+            }
+
+            let needs: Vec<&str> = graph
+                .edges
+                .iter()
+                .filter(|e| e.to == node.name)
+                .map(|e| e.from.as_str())
+                .collect();
+            if !needs.is_empty() {
+                line.push_str(&format!(" ← {}", needs.join(", ")));
+            }
+
+            let mut flags = Vec::new();
+            if node.when != "on_success" {
+                flags.push(node.when.clone());
+            }
+            if node.has_condition {
+                flags.push("conditional".to_string());
+            }
+            if node.allow_failure {
+                flags.push("allow failure".to_string());
+            }
+            if !flags.is_empty() {
+                line.push_str(&format!(" {}", format!("({})", flags.join(", ")).yellow()));
+            }
+            println!("{}", line);
+        }
+    }
+}
+
+/// Render a stage graph in Graphviz DOT
+///
+/// Conditional stages are dashed, stages allowed to fail grey, and stages
+/// that run on failure or always say so in their label.
+fn graph_to_dot(name: &str, graph: &PipelineGraph) -> String {
+    let quote = |s: &str| {
+        let escaped = s
+            .replace('\\', "\\\\")
+            .replace('"', "\\\"")
+            .replace('\n', "\\n");
+        format!("\"{}\"", escaped)
+    };
+
+    let mut dot = format!("digraph {} {{\n", quote(name));
+    dot.push_str("    rankdir=LR;\n");
+    dot.push_str("    node [shape=box, style=rounded];\n");
+    for node in &graph.nodes {
+        let mut label = node.name.clone();
+        if let Some(container) = &node.container {
+            label.push_str(&format!("\n{}", container));
+        }
+        if node.when != "on_success" {
+            label.push_str(&format!("\n({})", node.when));
+        }
+        let mut attributes = vec![format!("label={}", quote(&label))];
+        if node.has_condition {
+            attributes.push("style=\"rounded,dashed\"".to_string());
+        }
+        if node.allow_failure {
+            attributes.push("color=gray".to_string());
+        }
+        dot.push_str(&format!(
+            "    {} [{}];\n",
+            quote(&node.name),
+            attributes.join(", ")
+        ));
+    }
+    for edge in &graph.edges {
+        dot.push_str(&format!(
+            "    {} -> {};\n",
+            quote(&edge.from),
+            quote(&edge.to)
+        ));
+    }
+    dot.push_str("}\n");
+    dot
+}
+
+/// List all active pipelines, or the archived ones
+async fn list_pipelines(client: &OrchestratorClient, archived: bool) -> Result<()> {
+    let pipelines = match archived {
+        true => client.list_archived_pipelines().await?,
+        false => client.list_pipelines().await?,
+    };
 
-    if pipelines.is_empty() {
+    if output::is_plain() {
+        pipelines.iter().for_each(pipeline_record);
+    } else if pipelines.is_empty() {
         println!("{}", "No pipelines found.".yellow());
     } else {
         println!(
@@ -246,16 +1035,73 @@ async fn get_pipeline(client: &OrchestratorClient, id: &str) -> Result<()> {
     Ok(())
 }
 
-/// Delete a pipeline
-async fn delete_pipeline(client: &OrchestratorClient, id: &str) -> Result<()> {
+/// Delete a pipeline, or archive it
+async fn delete_pipeline(
+    client: &OrchestratorClient,
+    id: &str,
+    query: DeletePipelineQuery,
+) -> Result<()> {
+    let id_or_prefix = IdOrPrefix::parse(id);
+    let uuid = resolve_pipeline_id(client, &id_or_prefix).await?;
+
+    match client.delete_pipeline(uuid, &query).await? {
+        Some(_) => {
+            println!("{}", format!("✓ Pipeline {} archived", uuid).green().bold());
+            if !query.archive {
+                println!(
+                    "  {}",
+                    "Jobs reference it; use --force to delete it with its jobs".dimmed()
+                );
+            }
+        }
+        None => println!(
+            "{}",
+            format!("✓ Pipeline {} deleted successfully!", uuid)
+                .green()
+                .bold()
+        ),
+    }
+
+    Ok(())
+}
+
+/// Make an archived pipeline active again
+async fn unarchive_pipeline(client: &OrchestratorClient, id: &str) -> Result<()> {
+    // Archived pipelines are left out of the listing names are resolved in
+    let archived = client.list_archived_pipelines().await?;
+    let uuid = resolve_pipeline_in(&archived, id)?;
+
+    let pipeline = client.unarchive_pipeline(uuid).await?;
+
+    println!(
+        "{}",
+        format!("✓ Pipeline {} ({}) unarchived", pipeline.name, uuid)
+            .green()
+            .bold()
+    );
+
+    Ok(())
+}
+
+/// Disable or enable a pipeline
+async fn set_pipeline_disabled(
+    client: &OrchestratorClient,
+    id: &str,
+    disabled: bool,
+) -> Result<()> {
     let id_or_prefix = IdOrPrefix::parse(id);
     let uuid = resolve_pipeline_id(client, &id_or_prefix).await?;
 
-    client.delete_pipeline(uuid).await?;
+    let pipeline = client.set_pipeline_disabled(uuid, disabled).await?;
 
+    let state = if pipeline.disabled {
+        "disabled; new jobs are refused"
+    } else {
+        "enabled"
+    };
     println!(
         "{}",
-        format!("✓ Pipeline {} deleted successfully!", uuid)
+        format!("✓ Pipeline {} ({}) {}", pipeline.name, uuid, state)
             .green()
             .bold()
     );
@@ -263,12 +1109,18 @@ async fn delete_pipeline(client: &OrchestratorClient, id: &str) -> Result<()> {
     Ok(())
 }
 
-/// Launch a job from a pipeline
+/// Launch a job from a pipeline, or only show its plan with `dry_run`
+#[allow(clippy::too_many_arguments)]
 async fn launch_job(
     client: &OrchestratorClient,
     id: &str,
     params: Vec<(String, String)>,
+    preset: Option<&str>,
     no_interactive: bool,
+    dry_run: bool,
+    commit: Option<CommitRef>,
+    override_blackout: bool,
+    labels: BTreeMap<String, String>,
 ) -> Result<()> {
     let id_or_prefix = IdOrPrefix::parse(id);
     let uuid = resolve_pipeline_id(client, &id_or_prefix).await?;
@@ -284,21 +1136,41 @@ async fn launch_job(
     // Convert CLI params to HashMap
     let mut provided_params: HashMap<String, String> = params.into_iter().collect();
 
+    // Values of the preset, under those given on the command line
+    let preset_params = match preset {
+        Some(name) => client.get_preset(uuid, name).await?.parameters,
+        None => HashMap::new(),
+    };
+
     // Collect and validate inputs
     let parameters = if no_interactive {
         // Non-interactive mode: validate and apply defaults
-        collect_params_non_interactive(&definition, provided_params)?
+        collect_params_non_interactive(&definition, provided_params, &preset_params)?
     } else {
         // Interactive mode: prompt for missing inputs
-        collect_params_interactive(&definition, &mut provided_params)?
+        collect_params_interactive(&definition, &mut provided_params, &preset_params)?
     };
 
     let req = CreateJob {
         pipeline_id: uuid,
         parameters,
+        plan: false,
+        commit,
+        override_blackout,
+        labels,
     };
 
-    let job = client.launch_job(req).await?;
+    if dry_run {
+        let plan = client.plan_job(req).await?;
+        print_job_plan(&plan);
+        return Ok(());
+    }
+
+    let job = client.launch_job(req).await?;
+    if output::is_plain() {
+        job_record(&job);
+        return Ok(());
+    }
 
     println!("{}", "✓ Job launched successfully!".green().bold());
     println!("  Job ID:      {}", job.id.to_string().cyan());
@@ -312,31 +1184,763 @@ async fn launch_job(
     Ok(())
 }
 
+/// Show the input defaults of a pipeline, changing them first if asked
+///
+/// Values are converted to the types the pipeline's inputs declare.
+async fn pipeline_defaults(
+    client: &OrchestratorClient,
+    id: &str,
+    defaults: Vec<(String, String)>,
+    overrides: Vec<(String, String)>,
+    unset: Vec<String>,
+    clear: bool,
+) -> Result<()> {
+    let id_or_prefix = IdOrPrefix::parse(id);
+    let uuid = resolve_pipeline_id(client, &id_or_prefix).await?;
+
+    let mut current = client.get_pipeline_defaults(uuid).await?;
+
+    if clear || !defaults.is_empty() || !overrides.is_empty() || !unset.is_empty() {
+        let pipeline = client.get_pipeline(uuid).await?;
+        let lua = rivet_lua::create_sandbox()
+            .map_err(|e| anyhow::anyhow!("Failed to create sandbox: {}", e))?;
+        let definition = rivet_lua::parse_pipeline_definition(&lua, &pipeline.script)?;
+
+        let convert = |(key, value): (String, String)| -> Result<(String, JsonValue)> {
+            let input = definition
+                .inputs
+                .get(&key)
+                .ok_or_else(|| anyhow::anyhow!("Pipeline has no input '{}'", key))?;
+            let value = validate_and_convert_input(&key, &value, input)?;
+            Ok((key, value))
+        };
+
+        if clear {
+            current = PipelineDefaults::default();
+        }
+        for key in &unset {
+            current.defaults.remove(key);
+            current.overrides.remove(key);
+        }
+        for pair in defaults {
+            let (key, value) = convert(pair)?;
+            current.defaults.insert(key, value);
+        }
+        for pair in overrides {
+            let (key, value) = convert(pair)?;
+            current.overrides.insert(key, value);
+        }
+
+        current = client.set_pipeline_defaults(uuid, &current).await?;
+        println!("{}", "✓ Pipeline defaults updated!".green().bold());
+    }
+
+    if current.defaults.is_empty() && current.overrides.is_empty() {
+        println!("{}", "No defaults set on the orchestrator.".yellow());
+        return Ok(());
+    }
+    for (title, values) in [
+        ("Defaults:", &current.defaults),
+        ("Overrides:", &current.overrides),
+    ] {
+        if values.is_empty() {
+            continue;
+        }
+        println!("{}", title.bold());
+        let mut names: Vec<&String> = values.keys().collect();
+        names.sort();
+        for name in names {
+            let value = match &values[name] {
+                JsonValue::String(s) => s.clone(),
+                other => other.to_string(),
+            };
+            println!("  {} = {}", name.cyan(), value);
+        }
+    }
+
+    Ok(())
+}
+
+/// Show the artifact retention limits of a pipeline, changing them first if asked
+///
+/// Limits left out keep their current value unless `clear` is set.
+async fn pipeline_retention(
+    client: &OrchestratorClient,
+    id: &str,
+    limits: ArtifactRetention,
+    clear: bool,
+) -> Result<()> {
+    let id_or_prefix = IdOrPrefix::parse(id);
+    let uuid = resolve_pipeline_id(client, &id_or_prefix).await?;
+
+    let mut current = client.get_artifact_retention(uuid).await?;
+
+    if clear || !limits.is_unlimited() {
+        if clear {
+            current = ArtifactRetention::default();
+        }
+        current.keep_last_jobs = limits.keep_last_jobs.or(current.keep_last_jobs);
+        current.max_total_bytes = limits.max_total_bytes.or(current.max_total_bytes);
+        current.ttl_seconds = limits.ttl_seconds.or(current.ttl_seconds);
+
+        current = client.set_artifact_retention(uuid, &current).await?;
+        println!("{}", "✓ Artifact retention updated!".green().bold());
+    }
+
+    if current.is_unlimited() {
+        println!("{}", "Artifacts are kept forever.".yellow());
+        return Ok(());
+    }
+    println!("{}", "Artifact retention:".bold());
+    if let Some(jobs) = current.keep_last_jobs {
+        println!("  {} {} most recent jobs", "Keep:".cyan(), jobs);
+    }
+    if let Some(bytes) = current.max_total_bytes {
+        println!("  {} {}", "Max size:".cyan(), format_size(bytes));
+    }
+    if let Some(seconds) = current.ttl_seconds {
+        println!("  {} {}s", "TTL:".cyan(), seconds);
+    }
+
+    Ok(())
+}
+
+/// Commit status settings given on the command line
+struct CommitStatusChanges {
+    provider: Option<GitProvider>,
+    token_secret: Option<String>,
+    context: Option<String>,
+    api_url: Option<String>,
+    target_url: Option<String>,
+}
+
+/// Show how a pipeline reports commit statuses, changing it first if asked
+///
+/// Settings left out keep their current value; a pipeline that reports no
+/// commit status yet needs at least a provider and a token secret.
+async fn pipeline_commit_status(
+    client: &OrchestratorClient,
+    id: &str,
+    changes: CommitStatusChanges,
+    disable: bool,
+) -> Result<()> {
+    let id_or_prefix = IdOrPrefix::parse(id);
+    let uuid = resolve_pipeline_id(client, &id_or_prefix).await?;
+
+    let mut current = client.get_commit_status(uuid).await?;
+
+    if disable {
+        client.clear_commit_status(uuid).await?;
+        println!("{}", "✓ Commit status reporting disabled!".green().bold());
+        return Ok(());
+    }
+
+    let changed = changes.provider.is_some()
+        || changes.token_secret.is_some()
+        || changes.context.is_some()
+        || changes.api_url.is_some()
+        || changes.target_url.is_some();
+    if changed {
+        let config = match current {
+            Some(config) => CommitStatusConfig {
+                provider: changes.provider.unwrap_or(config.provider),
+                token_secret: changes.token_secret.unwrap_or(config.token_secret),
+                context: changes.context.or(config.context),
+                api_url: changes.api_url.or(config.api_url),
+                target_url: changes.target_url.or(config.target_url),
+            },
+            None => CommitStatusConfig {
+                provider: changes.provider.ok_or_else(|| {
+                    anyhow::anyhow!("--provider is required to start reporting commit statuses")
+                })?,
+                token_secret: changes.token_secret.ok_or_else(|| {
+                    anyhow::anyhow!("--token-secret is required to start reporting commit statuses")
+                })?,
+                context: changes.context,
+                api_url: changes.api_url,
+                target_url: changes.target_url,
+            },
+        };
+
+        current = client.set_commit_status(uuid, &config).await?;
+        println!("{}", "✓ Commit status reporting updated!".green().bold());
+    }
+
+    let Some(config) = current else {
+        println!("{}", "Commit statuses are not reported.".yellow());
+        return Ok(());
+    };
+    println!("{}", "Commit status reporting:".bold());
+    println!("  {} {}", "Provider:".cyan(), config.provider.as_str());
+    println!("  {} {}", "Token secret:".cyan(), config.token_secret);
+    if let Some(context) = &config.context {
+        println!("  {} {}", "Context:".cyan(), context);
+    }
+    if let Some(api_url) = &config.api_url {
+        println!("  {} {}", "API URL:".cyan(), api_url);
+    }
+    if let Some(target_url) = &config.target_url {
+        println!("  {} {}", "Target URL:".cyan(), target_url);
+    }
+
+    Ok(())
+}
+
+/// Show the success rate, durations, failing stages and trend of a pipeline
+async fn pipeline_stats(
+    client: &OrchestratorClient,
+    id: &str,
+    query: &PipelineStatsQuery,
+) -> Result<()> {
+    let id_or_prefix = IdOrPrefix::parse(id);
+    let uuid = resolve_pipeline_id(client, &id_or_prefix).await?;
+
+    let stats = client.pipeline_stats(uuid, query).await?;
+    print_pipeline_stats(&stats);
+
+    Ok(())
+}
+
+/// Print pipeline stats as a summary, a stage failure table and a sparkline
+fn print_pipeline_stats(stats: &PipelineStats) {
+    println!(
+        "{}",
+        format!("Pipeline stats since {}:", stats.since.format("%Y-%m-%d")).bold()
+    );
+    println!("  {:<14}{}", "Jobs:".cyan(), stats.jobs);
+    println!("  {:<14}{}", "Succeeded:".cyan(), stats.succeeded);
+    println!("  {:<14}{}", "Failed:".cyan(), stats.failed);
+    println!("  {:<14}{}", "Cancelled:".cyan(), stats.cancelled);
+    match stats.success_rate {
+        Some(rate) => {
+            let rate = format!("{:.1}%", rate * 100.0);
+            let rate = if stats.failed == 0 {
+                rate.green()
+            } else {
+                rate.yellow()
+            };
+            println!("  {:<14}{}", "Success rate:".cyan(), rate);
+        }
+        None => println!("  {:<14}{}", "Success rate:".cyan(), "-".dimmed()),
+    }
+
+    if let Some(duration) = &stats.duration {
+        println!();
+        println!("{}", "Durations:".bold());
+        println!(
+            "  {:>8} {:>8} {:>8} {:>8} {:>8}",
+            "avg", "p50", "p90", "p95", "max"
+        );
+        println!(
+            "  {:>8} {:>8} {:>8} {:>8} {:>8}",
+            format!("{:.1}s", duration.average),
+            format!("{:.1}s", duration.p50),
+            format!("{:.1}s", duration.p90),
+            format!("{:.1}s", duration.p95),
+            format!("{:.1}s", duration.max),
+        );
+    }
+
+    if !stats.stage_failures.is_empty() {
+        println!();
+        println!("{}", "Failing stages:".bold());
+        let width = stats
+            .stage_failures
+            .iter()
+            .map(|s| s.stage.len())
+            .max()
+            .unwrap_or(0);
+        for stage in &stats.stage_failures {
+            println!(
+                "  {:<width$}  {} of {} runs failed",
+                stage.stage.cyan(),
+                stage.failures.to_string().red(),
+                stage.runs,
+                width = width
+            );
+        }
+    }
+
+    if !stats.trend.is_empty() {
+        let most = stats.trend.iter().map(|b| b.jobs).max().unwrap_or(0);
+        let sparkline: String = stats
+            .trend
+            .iter()
+            .map(|bucket| match bucket.jobs {
+                0 => ' ',
+                jobs => SPARKS[(jobs * 7 / most) as usize],
+            })
+            .collect();
+        println!();
+        println!(
+            "{} {} {}",
+            "Trend:".bold(),
+            sparkline,
+            format!("(max {} per bucket)", most).dimmed()
+        );
+    }
+}
+
+/// List the stages of a pipeline whose outcome keeps flipping
+async fn flaky_stages(
+    client: &OrchestratorClient,
+    id: &str,
+    query: &FlakyStagesQuery,
+) -> Result<()> {
+    let id_or_prefix = IdOrPrefix::parse(id);
+    let uuid = resolve_pipeline_id(client, &id_or_prefix).await?;
+
+    let stages = client.flaky_stages(uuid, query).await?;
+    if stages.is_empty() {
+        println!("{}", "No flaky stages found.".green());
+        return Ok(());
+    }
+
+    println!("{}", format!("Flaky stages ({}):", stages.len()).bold());
+    for stage in &stages {
+        println!(
+            "  {} {}",
+            stage.stage.cyan(),
+            format!("(version {})", &stage.version[..stage.version.len().min(8)]).dimmed()
+        );
+        println!(
+            "      Flips:       {} in {} runs ({:.0}%), {} failed",
+            stage.flips.to_string().yellow(),
+            stage.runs,
+            stage.flip_rate * 100.0,
+            stage.failures
+        );
+        if stage.retry_flips > 0 {
+            println!(
+                "      Retry flips: {} (failed, then passed on retry)",
+                stage.retry_flips
+            );
+        }
+        println!(
+            "      Last flip:   {}",
+            stage.last_flip_at.format("%Y-%m-%d %H:%M:%S")
+        );
+    }
+
+    Ok(())
+}
+
+/// List the webhook triggers of a pipeline
+async fn list_webhook_triggers(
+    client: &OrchestratorClient,
+    orchestrator_url: &str,
+    id: &str,
+) -> Result<()> {
+    let id_or_prefix = IdOrPrefix::parse(id);
+    let uuid = resolve_pipeline_id(client, &id_or_prefix).await?;
+
+    let triggers = client.list_webhook_triggers(uuid).await?;
+    if triggers.is_empty() {
+        println!("{}", "No webhook triggers.".yellow());
+        return Ok(());
+    }
+
+    println!(
+        "{}",
+        format!("Webhook triggers ({}):", triggers.len()).bold()
+    );
+    for trigger in &triggers {
+        print_webhook_trigger(orchestrator_url, trigger);
+    }
+
+    Ok(())
+}
+
+/// Add a webhook trigger to a pipeline
+///
+/// Parameters are converted to the types of the pipeline's inputs.
+async fn add_webhook_trigger(
+    client: &OrchestratorClient,
+    orchestrator_url: &str,
+    id: &str,
+    mut req: CreateWebhookTrigger,
+    params: Vec<(String, String)>,
+) -> Result<()> {
+    let id_or_prefix = IdOrPrefix::parse(id);
+    let uuid = resolve_pipeline_id(client, &id_or_prefix).await?;
+
+    req.parameters = convert_parameters(client, uuid, params).await?;
+    let trigger = client.create_webhook_trigger(uuid, &req).await?;
+
+    println!("{}", "✓ Webhook trigger added!".green().bold());
+    print_webhook_trigger(orchestrator_url, &trigger);
+    println!(
+        "{}",
+        format!(
+            "Deliver {} push events to the URL above, signed with the value of secret '{}'.",
+            trigger.provider.as_str(),
+            trigger.secret
+        )
+        .dimmed()
+    );
+
+    Ok(())
+}
+
+/// Remove a webhook trigger from a pipeline
+async fn remove_webhook_trigger(
+    client: &OrchestratorClient,
+    id: &str,
+    trigger_id: Uuid,
+) -> Result<()> {
+    let id_or_prefix = IdOrPrefix::parse(id);
+    let uuid = resolve_pipeline_id(client, &id_or_prefix).await?;
+
+    client.delete_webhook_trigger(uuid, trigger_id).await?;
+    println!("{}", "✓ Webhook trigger removed!".green().bold());
+
+    Ok(())
+}
+
+/// Convert key=value parameters to the types of the pipeline's inputs
+async fn convert_parameters(
+    client: &OrchestratorClient,
+    pipeline_id: Uuid,
+    params: Vec<(String, String)>,
+) -> Result<HashMap<String, JsonValue>> {
+    let mut parameters = HashMap::new();
+    if params.is_empty() {
+        return Ok(parameters);
+    }
+
+    let pipeline = client.get_pipeline(pipeline_id).await?;
+    let lua = rivet_lua::create_sandbox()
+        .map_err(|e| anyhow::anyhow!("Failed to create sandbox: {}", e))?;
+    let definition = rivet_lua::parse_pipeline_definition(&lua, &pipeline.script)?;
+
+    for (key, value) in params {
+        let input = definition
+            .inputs
+            .get(&key)
+            .ok_or_else(|| anyhow::anyhow!("Pipeline has no input '{}'", key))?;
+        let value = validate_and_convert_input(&key, &value, input)?;
+        parameters.insert(key, value);
+    }
+
+    Ok(parameters)
+}
+
+/// Print a webhook trigger and the URL its provider delivers to
+fn print_webhook_trigger(orchestrator_url: &str, trigger: &WebhookTrigger) {
+    println!(
+        "  {} {}",
+        trigger.id.to_string().cyan(),
+        format!("({})", trigger.provider.as_str()).dimmed()
+    );
+    println!(
+        "      URL:      {}{}/webhooks/{}",
+        orchestrator_url.trim_end_matches('/'),
+        API_PREFIX,
+        trigger.id
+    );
+    println!("      Secret:   {}", trigger.secret);
+    let filters = &trigger.filters;
+    for (label, patterns) in [
+        ("Branches:", &filters.branches),
+        ("Tags:", &filters.tags),
+        ("Paths:", &filters.paths),
+    ] {
+        if !patterns.is_empty() {
+            println!("      {:<9} {}", label, patterns.join(", "));
+        }
+    }
+    let mut names: Vec<&String> = trigger.parameters.keys().collect();
+    names.sort();
+    for name in names {
+        let value = match &trigger.parameters[name] {
+            JsonValue::String(s) => s.clone(),
+            other => other.to_string(),
+        };
+        println!("      {} = {}", name.cyan(), value);
+    }
+}
+
+/// List the cron schedules of a pipeline
+async fn list_schedules(client: &OrchestratorClient, id: &str) -> Result<()> {
+    let id_or_prefix = IdOrPrefix::parse(id);
+    let uuid = resolve_pipeline_id(client, &id_or_prefix).await?;
+
+    let schedules = client.list_schedules(uuid).await?;
+    if schedules.is_empty() {
+        println!("{}", "No schedules.".yellow());
+        return Ok(());
+    }
+
+    println!("{}", format!("Schedules ({}):", schedules.len()).bold());
+    for schedule in &schedules {
+        print_schedule(schedule);
+    }
+
+    Ok(())
+}
+
+/// Add a cron schedule to a pipeline
+///
+/// Parameters are converted to the types of the pipeline's inputs.
+async fn add_schedule(
+    client: &OrchestratorClient,
+    id: &str,
+    mut req: CreateSchedule,
+    params: Vec<(String, String)>,
+) -> Result<()> {
+    let id_or_prefix = IdOrPrefix::parse(id);
+    let uuid = resolve_pipeline_id(client, &id_or_prefix).await?;
+
+    req.parameters = convert_parameters(client, uuid, params).await?;
+    let schedule = client.create_schedule(uuid, &req).await?;
+
+    println!("{}", "✓ Schedule added!".green().bold());
+    print_schedule(&schedule);
+
+    Ok(())
+}
+
+/// Remove a cron schedule from a pipeline
+async fn remove_schedule(client: &OrchestratorClient, id: &str, schedule_id: Uuid) -> Result<()> {
+    let id_or_prefix = IdOrPrefix::parse(id);
+    let uuid = resolve_pipeline_id(client, &id_or_prefix).await?;
+
+    client.delete_schedule(uuid, schedule_id).await?;
+    println!("{}", "✓ Schedule removed!".green().bold());
+
+    Ok(())
+}
+
+/// Print a cron schedule and when it runs next
+fn print_schedule(schedule: &Schedule) {
+    println!(
+        "  {} {} {}",
+        schedule.id.to_string().cyan(),
+        schedule.cron.bold(),
+        format!("({})", schedule.timezone).dimmed()
+    );
+    println!("      Catch-up: {}", schedule.catch_up.as_str());
+    println!(
+        "      Next run: {}",
+        schedule.next_run_at.format("%Y-%m-%d %H:%M:%S UTC")
+    );
+    if let Some(last_run_at) = schedule.last_run_at {
+        println!(
+            "      Last run: {}",
+            last_run_at.format("%Y-%m-%d %H:%M:%S UTC")
+        );
+    }
+    let mut names: Vec<&String> = schedule.parameters.keys().collect();
+    names.sort();
+    for name in names {
+        let value = match &schedule.parameters[name] {
+            JsonValue::String(s) => s.clone(),
+            other => other.to_string(),
+        };
+        println!("      {} = {}", name.cyan(), value);
+    }
+}
+
+/// List the parameter presets of a pipeline
+async fn list_presets(client: &OrchestratorClient, id: &str) -> Result<()> {
+    let id_or_prefix = IdOrPrefix::parse(id);
+    let uuid = resolve_pipeline_id(client, &id_or_prefix).await?;
+
+    let presets = client.list_presets(uuid).await?;
+    if presets.is_empty() {
+        println!("{}", "No presets.".yellow());
+        return Ok(());
+    }
+
+    println!("{}", format!("Presets ({}):", presets.len()).bold());
+    for preset in &presets {
+        print_preset(preset);
+    }
+
+    Ok(())
+}
+
+/// Save a parameter preset of a pipeline
+///
+/// Parameters are converted to the types of the pipeline's inputs.
+async fn save_preset(
+    client: &OrchestratorClient,
+    id: &str,
+    name: &str,
+    params: Vec<(String, String)>,
+) -> Result<()> {
+    let id_or_prefix = IdOrPrefix::parse(id);
+    let uuid = resolve_pipeline_id(client, &id_or_prefix).await?;
+
+    let req = SavePreset {
+        parameters: convert_parameters(client, uuid, params).await?,
+    };
+    let preset = client.save_preset(uuid, name, &req).await?;
+
+    println!("{}", "✓ Preset saved!".green().bold());
+    print_preset(&preset);
+
+    Ok(())
+}
+
+/// Remove a parameter preset from a pipeline
+async fn remove_preset(client: &OrchestratorClient, id: &str, name: &str) -> Result<()> {
+    let id_or_prefix = IdOrPrefix::parse(id);
+    let uuid = resolve_pipeline_id(client, &id_or_prefix).await?;
+
+    client.delete_preset(uuid, name).await?;
+    println!("{}", "✓ Preset removed!".green().bold());
+
+    Ok(())
+}
+
+/// Print a parameter preset and its values
+fn print_preset(preset: &ParameterPreset) {
+    println!(
+        "  {} {}",
+        preset.name.bold(),
+        format!(
+            "(updated {})",
+            preset.updated_at.format("%Y-%m-%d %H:%M:%S UTC")
+        )
+        .dimmed()
+    );
+    let mut names: Vec<&String> = preset.parameters.keys().collect();
+    names.sort();
+    for name in names {
+        let value = match &preset.parameters[name] {
+            JsonValue::String(s) => s.clone(),
+            other => other.to_string(),
+        };
+        println!("      {} = {}", name.cyan(), value);
+    }
+}
+
+/// Print which stages a job would run
+fn print_job_plan(plan: &JobPlan) {
+    println!("{}", "Dry run: no job was launched.".yellow().bold());
+    println!("  Pipeline ID: {}", plan.pipeline_id.to_string().dimmed());
+
+    if !plan.parameters.is_empty() {
+        println!();
+        println!("{}", "Parameters:".bold());
+        let mut names: Vec<&String> = plan.parameters.keys().collect();
+        names.sort();
+        for name in names {
+            let value = match &plan.parameters[name] {
+                JsonValue::String(s) => s.clone(),
+                other => other.to_string(),
+            };
+            println!("  {} = {}", name.cyan(), value);
+        }
+    }
+
+    println!();
+    println!("{}", format!("Stages ({}):", plan.stages.len()).bold());
+    for (idx, stage) in plan.stages.iter().enumerate() {
+        let decision = match stage.decision {
+            StageDecision::Run => "run".green(),
+            StageDecision::Skip => "skip".dimmed(),
+            StageDecision::Undecided => "decided at run time".yellow(),
+        };
+        println!("  {}. {} ({})", idx + 1, stage.name.cyan(), decision);
+        let container = stage.container.as_deref().unwrap_or("runner default");
+        println!("      Container: {}", container.yellow());
+        if !stage.needs.is_empty() {
+            println!("      Needs:     {}", stage.needs.join(", "));
+        }
+        if let Some(reason) = &stage.reason {
+            println!("      {}", reason.dimmed());
+        }
+    }
+}
+
+/// Launch one job per parameter set read from a JSON file
+///
+/// The orchestrator validates every set and applies input defaults; the
+/// jobs are only created if all sets are valid.
+async fn launch_job_batch(client: &OrchestratorClient, id: &str, params_file: &Path) -> Result<()> {
+    let id_or_prefix = IdOrPrefix::parse(id);
+    let uuid = resolve_pipeline_id(client, &id_or_prefix).await?;
+
+    let content = std::fs::read_to_string(params_file).map_err(|e| {
+        anyhow::anyhow!(
+            "Failed to read params file '{}': {}",
+            params_file.display(),
+            e
+        )
+    })?;
+    let parameter_sets: Vec<HashMap<String, JsonValue>> =
+        serde_json::from_str(&content).map_err(|e| {
+            anyhow::anyhow!(
+                "Params file '{}' must be a JSON array of objects: {}",
+                params_file.display(),
+                e
+            )
+        })?;
+
+    let job_ids = client.launch_job_batch(uuid, &parameter_sets).await?;
+    if output::is_plain() {
+        for job_id in job_ids {
+            output::record(&job_id.to_string(), &[]);
+        }
+        return Ok(());
+    }
+
+    println!(
+        "{}",
+        format!("✓ {} job(s) launched successfully!", job_ids.len())
+            .green()
+            .bold()
+    );
+    for job_id in job_ids {
+        println!("  {}", job_id.to_string().cyan());
+    }
+
+    Ok(())
+}
+
 /// Collect parameters in non-interactive mode (validate and apply defaults)
+///
+/// Every invalid or missing input is reported at once.
 fn collect_params_non_interactive(
     definition: &rivet_lua::PipelineDefinition,
     provided: HashMap<String, String>,
+    preset: &HashMap<String, JsonValue>,
 ) -> Result<HashMap<String, JsonValue>> {
     let mut parameters = HashMap::new();
+    let mut errors = Vec::new();
 
-    for (key, input_def) in &definition.inputs {
+    let mut inputs: Vec<_> = definition.inputs.iter().collect();
+    inputs.sort_by_key(|(key, _)| *key);
+
+    for (key, input_def) in inputs {
         if let Some(value) = provided.get(key) {
             // Validate and convert type
-            let json_value = validate_and_convert_input(key, value, &input_def.input_type)?;
-            parameters.insert(key.clone(), json_value);
+            match validate_and_convert_input(key, value, input_def) {
+                Ok(json_value) => {
+                    parameters.insert(key.clone(), json_value);
+                }
+                Err(e) => errors.push(e.to_string()),
+            }
+        } else if let Some(value) = preset.get(key) {
+            // Use the preset's value, checked when the preset was saved
+            parameters.insert(key.clone(), value.clone());
         } else if let Some(default) = &input_def.default {
             // Use default value
             parameters.insert(key.clone(), default.clone());
         } else if input_def.required {
-            return Err(anyhow::anyhow!(
+            errors.push(format!(
                 "Missing required input '{}' ({}). Use -p {}=<value> or run without --no-interactive",
-                key,
-                input_def.input_type,
-                key
+                key, input_def.input_type, key
             ));
         }
     }
 
+    if !errors.is_empty() {
+        return Err(anyhow::anyhow!(errors.join("\n")));
+    }
+
     Ok(parameters)
 }
 
@@ -344,6 +1948,7 @@ fn collect_params_non_interactive(
 fn collect_params_interactive(
     definition: &rivet_lua::PipelineDefinition,
     provided: &mut HashMap<String, String>,
+    preset: &HashMap<String, JsonValue>,
 ) -> Result<HashMap<String, JsonValue>> {
     let mut parameters = HashMap::new();
 
@@ -358,130 +1963,265 @@ fn collect_params_interactive(
     for (key, input_def) in &definition.inputs {
         // Check if already provided via CLI
         if let Some(value) = provided.get(key) {
-            let json_value = validate_and_convert_input(key, value, &input_def.input_type)?;
+            let json_value = validate_and_convert_input(key, value, input_def)?;
             parameters.insert(key.clone(), json_value);
+            let shown = match input_def.input_type {
+                InputType::Secret => MASKED_INPUT,
+                _ => value.as_str(),
+            };
             println!(
                 "  {} {} (from CLI: {})",
                 "✓".green(),
                 key.cyan(),
-                value.dimmed()
+                shown.dimmed()
             );
             continue;
         }
 
-        // Show input information
-        let required_mark = if input_def.required { "*" } else { "" };
-        print!(
-            "  {}{} ({}):",
-            key.cyan(),
-            required_mark.red(),
-            input_def.input_type.dimmed()
-        );
+        if let Some(value) = preset.get(key) {
+            parameters.insert(key.clone(), value.clone());
+            let shown = match value {
+                JsonValue::String(s) => s.clone(),
+                other => other.to_string(),
+            };
+            println!(
+                "  {} {} (from preset: {})",
+                "✓".green(),
+                key.cyan(),
+                shown.dimmed()
+            );
+            continue;
+        }
 
-        if let Some(desc) = &input_def.description {
-            print!(" {}", desc.dimmed());
+        if let Some(value) = prompt_for_input(key, input_def)? {
+            parameters.insert(key.clone(), value);
         }
         println!();
+    }
 
-        // Show default if available
-        if let Some(default) = &input_def.default {
-            let default_str = match default {
-                JsonValue::String(s) => s.clone(),
-                JsonValue::Number(n) => n.to_string(),
-                JsonValue::Bool(b) => b.to_string(),
-                _ => format!("{:?}", default),
-            };
-            println!("    Default: {}", default_str.dimmed());
-        }
+    Ok(parameters)
+}
 
-        // Show options if available
-        if let Some(options) = &input_def.options {
+/// Prompt the user for a single pipeline input
+///
+/// Enum inputs are picked from a numbered list, secret inputs are read
+/// without echo and file inputs are asked for as a path.
+///
+/// # Returns
+/// The validated value, or None when an optional input was skipped
+pub(crate) fn prompt_for_input(
+    key: &str,
+    input_def: &InputDefinition,
+) -> Result<Option<JsonValue>> {
+    // Show input information
+    let required_mark = if input_def.required { "*" } else { "" };
+    print!(
+        "  {}{} ({}):",
+        key.cyan(),
+        required_mark.red(),
+        input_def.input_type.as_str().dimmed()
+    );
+
+    if let Some(desc) = &input_def.description {
+        print!(" {}", desc.dimmed());
+    }
+    println!();
+
+    // Show default if available
+    if let Some(default) = &input_def.default {
+        println!("    Default: {}", input_label(default).dimmed());
+    }
+
+    // Show options if available
+    if let Some(options) = &input_def.options {
+        if input_def.input_type == InputType::Enum {
+            for (index, option) in options.iter().enumerate() {
+                println!("    {}) {}", index + 1, input_label(option));
+            }
+        } else {
             println!(
                 "    Options: {}",
                 options
                     .iter()
-                    .map(|v| match v {
-                        JsonValue::String(s) => s.clone(),
-                        JsonValue::Number(n) => n.to_string(),
-                        JsonValue::Bool(b) => b.to_string(),
-                        _ => format!("{:?}", v),
-                    })
+                    .map(input_label)
                     .collect::<Vec<_>>()
                     .join(", ")
                     .dimmed()
             );
         }
+    }
 
-        // Prompt for input
-        print!("    Enter value");
-        if !input_def.required {
-            print!(" (or press Enter to skip)");
-        }
-        print!(": ");
+    // Prompt for input
+    let mut prompt = match input_def.input_type {
+        InputType::Enum => "    Enter a number or value".to_string(),
+        InputType::Array => "    Enter values, comma-separated".to_string(),
+        InputType::File => "    Enter a file path".to_string(),
+        _ => "    Enter value".to_string(),
+    };
+    if !input_def.required {
+        prompt.push_str(" (or press Enter to skip)");
+    }
+    prompt.push_str(": ");
+
+    let input = if input_def.input_type == InputType::Secret {
+        rpassword::prompt_password(&prompt)?
+    } else {
+        print!("{}", prompt);
         io::stdout().flush()?;
 
         let mut input = String::new();
         io::stdin().read_line(&mut input)?;
-        let input = input.trim();
+        input
+    };
+    let input = input.trim();
 
-        if input.is_empty() {
-            if let Some(default) = &input_def.default {
-                // Use default
-                parameters.insert(key.clone(), default.clone());
-                println!("    {} Using default", "→".dimmed());
-            } else if input_def.required {
-                return Err(anyhow::anyhow!("Input '{}' is required", key));
-            }
-        } else {
-            // Validate and convert
-            let json_value = validate_and_convert_input(key, input, &input_def.input_type)?;
-
-            // Validate options if provided
-            if let Some(options) = &input_def.options {
-                let value_matches = options.iter().any(|opt| match (&json_value, opt) {
-                    (JsonValue::Number(a), JsonValue::Number(b)) => a.as_f64() == b.as_f64(),
-                    (JsonValue::String(a), JsonValue::String(b)) => a == b,
-                    (JsonValue::Bool(a), JsonValue::Bool(b)) => a == b,
-                    _ => false,
-                });
-
-                if !value_matches {
-                    return Err(anyhow::anyhow!(
-                        "Invalid value for '{}'. Must be one of: {}",
-                        key,
+    if input.is_empty() {
+        if let Some(default) = &input_def.default {
+            // Use default
+            println!("    {} Using default", "→".dimmed());
+            return Ok(Some(default.clone()));
+        } else if input_def.required {
+            return Err(anyhow::anyhow!("Input '{}' is required", key));
+        }
+        return Ok(None);
+    }
+
+    // An enum option may be picked by its number in the list
+    let options = input_def.options.as_deref().unwrap_or_default();
+    let picked = match input.parse::<usize>() {
+        Ok(number)
+            if input_def.input_type == InputType::Enum
+                && (1..=options.len()).contains(&number)
+                && !options.iter().any(|opt| input_label(opt) == input) =>
+        {
+            input_label(&options[number - 1])
+        }
+        _ => input.to_string(),
+    };
+
+    // Validate and convert
+    let json_value = validate_and_convert_input(key, &picked, input_def)?;
+
+    // Validate options if provided (enum values are checked when converted)
+    if let Some(options) = &input_def.options {
+        let values = match &json_value {
+            JsonValue::Array(items) => items.as_slice(),
+            value => std::slice::from_ref(value),
+        };
+        let value_matches = |value: &JsonValue| {
+            options.iter().any(|opt| match (value, opt) {
+                (JsonValue::Number(a), JsonValue::Number(b)) => a.as_f64() == b.as_f64(),
+                (JsonValue::String(a), JsonValue::String(b)) => a == b,
+                (JsonValue::Bool(a), JsonValue::Bool(b)) => a == b,
+                _ => false,
+            })
+        };
+
+        if !values.iter().all(value_matches) {
+            return Err(anyhow::anyhow!(
+                "Invalid value for '{}'. Must be one of: {}",
+                key,
+                options
+                    .iter()
+                    .map(input_label)
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ));
+        }
+    }
+
+    Ok(Some(json_value))
+}
+
+/// Validate and convert input string to the JSON value the input expects
+///
+/// Array values are comma-separated, and file values are paths to the file
+/// to upload. Every constraint the value breaks is reported at once.
+pub(crate) fn validate_and_convert_input(
+    name: &str,
+    value: &str,
+    input_def: &InputDefinition,
+) -> Result<JsonValue> {
+    let json_value = convert_input(name, value, input_def)?;
+
+    let violations = input_def.constraint_violations(&json_value);
+    if !violations.is_empty() {
+        return Err(anyhow::anyhow!(
+            "Invalid value for '{}':\n  - {}",
+            name,
+            violations.join("\n  - ")
+        ));
+    }
+
+    Ok(json_value)
+}
+
+/// Convert an input string to the JSON value the input expects
+fn convert_input(name: &str, value: &str, input_def: &InputDefinition) -> Result<JsonValue> {
+    match input_def.input_type {
+        InputType::Enum => {
+            let options = input_def.options.as_deref().unwrap_or_default();
+            options
+                .iter()
+                .find(|opt| input_label(opt) == value)
+                .cloned()
+                .ok_or_else(|| {
+                    anyhow::anyhow!(
+                        "Input '{}' must be one of: {}, got: {}",
+                        name,
                         options
                             .iter()
-                            .map(|v| match v {
-                                JsonValue::String(s) => s.clone(),
-                                JsonValue::Number(n) => n.to_string(),
-                                JsonValue::Bool(b) => b.to_string(),
-                                _ => format!("{:?}", v),
-                            })
+                            .map(input_label)
                             .collect::<Vec<_>>()
-                            .join(", ")
-                    ));
-                }
+                            .join(", "),
+                        value
+                    )
+                })
+        }
+        InputType::Array => {
+            let items = input_def.items.unwrap_or(InputType::String);
+            value
+                .split(',')
+                .map(str::trim)
+                .filter(|item| !item.is_empty())
+                .map(|item| convert_scalar_input(name, item, items))
+                .collect::<Result<Vec<_>>>()
+                .map(JsonValue::Array)
+        }
+        InputType::File => {
+            let path = Path::new(value);
+            let content = std::fs::read(path).map_err(|e| {
+                anyhow::anyhow!("Input '{}': failed to read file '{}': {}", name, value, e)
+            })?;
+            if content.len() > MAX_INPUT_FILE_BYTES {
+                return Err(anyhow::anyhow!(
+                    "Input '{}': file '{}' is {} bytes, the limit is {}",
+                    name,
+                    value,
+                    content.len(),
+                    MAX_INPUT_FILE_BYTES
+                ));
             }
-
-            parameters.insert(key.clone(), json_value);
+            let file_name = path
+                .file_name()
+                .and_then(|file_name| file_name.to_str())
+                .ok_or_else(|| anyhow::anyhow!("Input '{}' must be a file path", name))?;
+            Ok(serde_json::to_value(InputFile::new(file_name, &content))?)
         }
-        println!();
+        input_type => convert_scalar_input(name, value, input_type),
     }
-
-    Ok(parameters)
 }
 
-/// Validate and convert input string to appropriate JSON type
-fn validate_and_convert_input(name: &str, value: &str, input_type: &str) -> Result<JsonValue> {
+/// Convert an input string to a JSON string, number or bool
+fn convert_scalar_input(name: &str, value: &str, input_type: InputType) -> Result<JsonValue> {
     match input_type {
-        "string" => Ok(JsonValue::String(value.to_string())),
-        "number" => {
+        InputType::Number => {
             let num: f64 = value.parse().map_err(|_| {
                 anyhow::anyhow!("Input '{}' must be a number, got: {}", name, value)
             })?;
             Ok(serde_json::json!(num))
         }
-        "bool" => {
+        InputType::Bool => {
             let bool_val = match value.to_lowercase().as_str() {
                 "true" | "yes" | "1" | "y" => true,
                 "false" | "no" | "0" | "n" => false,
@@ -495,13 +2235,41 @@ fn validate_and_convert_input(name: &str, value: &str, input_type: &str) -> Resu
             };
             Ok(JsonValue::Bool(bool_val))
         }
-        _ => Err(anyhow::anyhow!("Unknown input type: {}", input_type)),
+        _ => Ok(JsonValue::String(value.to_string())),
+    }
+}
+
+/// Show an input value (a default or an option) as typed on the command line
+fn input_label(value: &JsonValue) -> String {
+    match value {
+        JsonValue::String(s) => s.clone(),
+        JsonValue::Number(n) => n.to_string(),
+        JsonValue::Bool(b) => b.to_string(),
+        _ => format!("{:?}", value),
     }
 }
 
 /// Print a pipeline summary
+/// Print a pipeline as a plain record: ID, name, created at
+fn pipeline_record(pipeline: &Pipeline) {
+    output::record(
+        &pipeline.id.to_string(),
+        &[&pipeline.name, &pipeline.created_at.to_rfc3339()],
+    );
+}
+
 fn print_pipeline_summary(pipeline: &Pipeline) {
-    println!("  {} {}", "▸".cyan(), pipeline.name.bold());
+    let state = match (pipeline.archived_at, pipeline.disabled) {
+        (Some(_), _) => " (archived)",
+        (None, true) => " (disabled)",
+        (None, false) => "",
+    };
+    println!(
+        "  {} {}{}",
+        "▸".cyan(),
+        pipeline.name.bold(),
+        state.yellow()
+    );
     println!("    ID:      {}", pipeline.id.to_string().dimmed());
     println!(
         "    Created: {}",
@@ -545,6 +2313,15 @@ fn print_pipeline_details(pipeline: &Pipeline) {
         "  Updated:     {}",
         pipeline.updated_at.format("%Y-%m-%d %H:%M:%S")
     );
+    if pipeline.disabled {
+        println!("  Disabled:    {}", "yes, new jobs are refused".yellow());
+    }
+    if let Some(archived) = pipeline.archived_at {
+        println!(
+            "  Archived:    {}",
+            archived.format("%Y-%m-%d %H:%M:%S").to_string().yellow()
+        );
+    }
     if !pipeline.tags.is_empty() {
         println!("  Tags:        {} tags", pipeline.tags.len());
     }
@@ -0,0 +1,129 @@
+//! Project command handlers
+//!
+//! Handles project management. Other commands are scoped to a project with
+//! the global `--project` flag (or `RIVET_PROJECT`).
+
+use anyhow::Result;
+use clap::Subcommand;
+use colored::*;
+use rivet_core::dto::project::CreateProject;
+
+use crate::config::Config;
+use crate::output;
+use rivet_client::OrchestratorClient;
+
+/// Project subcommands
+#[derive(Subcommand)]
+pub enum ProjectCommands {
+    /// List all projects
+    List,
+    /// Create a project
+    Create {
+        /// Project name (lowercase letters, digits and '-')
+        name: String,
+
+        /// Project description
+        #[arg(short, long)]
+        description: Option<String>,
+    },
+    /// Delete a project with all its pipelines, jobs and secrets
+    Delete {
+        /// Project name or ID
+        project: String,
+    },
+}
+
+/// Handle project commands
+///
+/// # Arguments
+/// * `command` - The project command to execute
+/// * `config` - The CLI configuration
+pub async fn handle_project_command(command: ProjectCommands, config: &Config) -> Result<()> {
+    let client = config.client()?;
+
+    match command {
+        ProjectCommands::List => list_projects(&client, config.project.as_deref()).await,
+        ProjectCommands::Create { name, description } => {
+            create_project(&client, name, description).await
+        }
+        ProjectCommands::Delete { project } => delete_project(&client, &project).await,
+    }
+}
+
+/// List all projects, marking the one commands are scoped to
+async fn list_projects(client: &OrchestratorClient, current: Option<&str>) -> Result<()> {
+    let projects = client.list_projects().await?;
+    if output::is_plain() {
+        for project in &projects {
+            output::record(&project.id.to_string(), &[&project.name]);
+        }
+        return Ok(());
+    }
+
+    if projects.is_empty() {
+        println!("{}", "No projects found.".yellow());
+        return Ok(());
+    }
+
+    println!("{}", format!("Found {} project(s):", projects.len()).bold());
+    println!();
+    for project in projects {
+        let is_current = current.is_some_and(|p| p == project.name || p == project.id.to_string());
+        let marker = if is_current {
+            "*".green()
+        } else {
+            "▸".cyan()
+        };
+
+        println!(
+            "  {} {}  {}",
+            marker,
+            project.name.bold(),
+            project.id.to_string().dimmed()
+        );
+        if let Some(description) = &project.description {
+            println!("    {}", description);
+        }
+    }
+
+    Ok(())
+}
+
+/// Create a project
+async fn create_project(
+    client: &OrchestratorClient,
+    name: String,
+    description: Option<String>,
+) -> Result<()> {
+    let project = client
+        .create_project(CreateProject { name, description })
+        .await?;
+    if output::is_plain() {
+        output::record(&project.id.to_string(), &[&project.name]);
+        return Ok(());
+    }
+
+    println!(
+        "{}",
+        format!("✓ Project {} created successfully!", project.name)
+            .green()
+            .bold()
+    );
+    println!("  ID: {}", project.id.to_string().dimmed());
+
+    Ok(())
+}
+
+/// Delete a project
+async fn delete_project(client: &OrchestratorClient, project: &str) -> Result<()> {
+    client.delete_project(project).await?;
+
+    println!(
+        "{}",
+        format!("✓ Project {} deleted successfully!", project)
+            .green()
+            .bold()
+    );
+
+    Ok(())
+}
@@ -1,13 +1,16 @@
 //! Runner command handlers
 //!
-//! Handles all runner-related CLI commands including listing runners.
+//! Handles all runner-related CLI commands including listing runners and
+//! managing registration tokens (which require the admin token, `--token`).
 
 use anyhow::Result;
 use clap::Subcommand;
 use colored::*;
 use rivet_core::domain::runner::{Runner, RunnerStatus};
+use uuid::Uuid;
 
 use crate::config::Config;
+use crate::output;
 use rivet_client::OrchestratorClient;
 
 /// Runner subcommands
@@ -15,6 +18,29 @@ use rivet_client::OrchestratorClient;
 pub enum RunnerCommands {
     /// List all registered runners
     List,
+    /// Manage registration tokens
+    Token {
+        #[command(subcommand)]
+        command: TokenCommands,
+    },
+}
+
+/// Registration token subcommands
+#[derive(Subcommand)]
+pub enum TokenCommands {
+    /// List registration tokens
+    List,
+    /// Mint a registration token (shown only once)
+    Create {
+        /// What the token is for
+        #[arg(short, long)]
+        description: Option<String>,
+    },
+    /// Revoke a registration token
+    Revoke {
+        /// Token ID
+        id: Uuid,
+    },
 }
 
 /// Handle runner commands
@@ -25,16 +51,29 @@ pub enum RunnerCommands {
 /// * `command` - The runner command to execute
 /// * `config` - The CLI configuration
 pub async fn handle_runner_command(command: RunnerCommands, config: &Config) -> Result<()> {
-    let client = OrchestratorClient::new(&config.orchestrator_url);
+    let client = config.client()?;
 
     match command {
         RunnerCommands::List => list_runners(&client).await,
+        RunnerCommands::Token { command } => match command {
+            TokenCommands::List => list_tokens(&client).await,
+            TokenCommands::Create { description } => {
+                create_token(&client, description.as_deref()).await
+            }
+            TokenCommands::Revoke { id } => revoke_token(&client, id).await,
+        },
     }
 }
 
 /// List all registered runners
 async fn list_runners(client: &OrchestratorClient) -> Result<()> {
     let runners = client.list_runners().await?;
+    if output::is_plain() {
+        for runner in &runners {
+            output::record(&runner.id, &[&format!("{:?}", runner.status)]);
+        }
+        return Ok(());
+    }
 
     if runners.is_empty() {
         println!("{}", "No runners registered.".yellow());
@@ -52,12 +91,85 @@ async fn list_runners(client: &OrchestratorClient) -> Result<()> {
     Ok(())
 }
 
+/// List registration tokens
+async fn list_tokens(client: &OrchestratorClient) -> Result<()> {
+    let tokens = client.list_registration_tokens().await?;
+
+    if tokens.is_empty() {
+        println!("{}", "No registration tokens.".yellow());
+        return Ok(());
+    }
+
+    println!(
+        "{}",
+        format!("Found {} registration token(s):", tokens.len()).bold()
+    );
+    println!();
+    for token in tokens {
+        println!(
+            "  {} {}  {}",
+            "▸".cyan(),
+            token.id.to_string().bold(),
+            token
+                .created_at
+                .format("%Y-%m-%d %H:%M:%S")
+                .to_string()
+                .dimmed()
+        );
+        if let Some(description) = &token.description {
+            println!("    {}", description);
+        }
+    }
+
+    Ok(())
+}
+
+/// Mint a registration token and print its secret
+async fn create_token(client: &OrchestratorClient, description: Option<&str>) -> Result<()> {
+    let token = client.create_registration_token(description).await?;
+
+    println!(
+        "{} Created registration token {}",
+        "✓".green(),
+        token.info.id.to_string().bold()
+    );
+    println!();
+    println!("  {}", token.token);
+    println!();
+    println!(
+        "{}",
+        "Store it now, it will not be shown again. Runners pass it as RUNNER_REGISTRATION_TOKEN."
+            .dimmed()
+    );
+
+    Ok(())
+}
+
+/// Revoke a registration token
+async fn revoke_token(client: &OrchestratorClient, id: Uuid) -> Result<()> {
+    client.delete_registration_token(id).await?;
+
+    println!("{} Revoked registration token {}", "✓".green(), id);
+
+    Ok(())
+}
+
 /// Print a runner summary
 fn print_runner_summary(runner: &Runner) {
     let status_colored = colorize_status(&runner.status);
 
     println!("  {} Runner {}", "▸".cyan(), runner.id.bold());
     println!("    Status:       {}", status_colored);
+    match &runner.info {
+        Some(info) => {
+            println!(
+                "    Version:      {} ({}/{}, protocol {})",
+                info.version, info.os, info.arch, info.protocol_version
+            );
+            println!("    Host:         {}", info.hostname);
+        }
+        None => println!("    Version:      {}", "unknown".dimmed()),
+    }
     println!(
         "    Registered:   {}",
         runner
@@ -74,6 +186,30 @@ fn print_runner_summary(runner: &Runner) {
             .to_string()
             .dimmed()
     );
+    if !runner.tags.is_empty() {
+        let tags: Vec<String> = runner
+            .tags
+            .iter()
+            .map(|t| format!("{}={}", t.key, t.value))
+            .collect();
+        println!("    Tags:         {}", tags.join(", "));
+    }
+    if !runner.plugins.is_empty() {
+        println!("    Plugins:      {}", runner.plugins.join(", "));
+    }
+    if let Some(load) = &runner.load {
+        let mut parts = vec![format!(
+            "{}/{} jobs",
+            load.running_jobs, load.max_parallel_jobs
+        )];
+        if let Some(cpu) = load.cpu_load {
+            parts.push(format!("CPU {:.0}%", cpu * 100.0));
+        }
+        if let Some(memory) = load.memory_load {
+            parts.push(format!("memory {:.0}%", memory * 100.0));
+        }
+        println!("    Load:         {}", parts.join(", "));
+    }
     println!();
 }
 
@@ -0,0 +1,207 @@
+//! Secret command handlers
+//!
+//! Handles secret management. Secret values are only ever read from an
+//! interactive prompt (without echo) or from stdin, never from argv, so they
+//! don't end up in shell history or process listings.
+
+use std::io::{IsTerminal, Read};
+
+use anyhow::{Context, Result, bail};
+use clap::Subcommand;
+use colored::*;
+use rivet_core::dto::secret::SetSecret;
+use uuid::Uuid;
+
+use crate::config::Config;
+use crate::id_resolver::resolve_pipeline_id;
+use crate::output;
+use crate::types::IdOrPrefix;
+use rivet_client::OrchestratorClient;
+
+/// Secret subcommands
+#[derive(Subcommand)]
+pub enum SecretCommands {
+    /// Create or replace a secret (value is prompted, or read from stdin when piped)
+    Set {
+        /// Secret name
+        name: String,
+
+        /// Scope the secret to a pipeline (ID or unambiguous prefix)
+        #[arg(short, long)]
+        pipeline: Option<String>,
+    },
+    /// List secret names
+    List {
+        /// List secrets scoped to a pipeline instead of project-wide ones
+        #[arg(short, long)]
+        pipeline: Option<String>,
+    },
+    /// Delete a secret
+    Delete {
+        /// Secret name
+        name: String,
+
+        /// Pipeline scope of the secret
+        #[arg(short, long)]
+        pipeline: Option<String>,
+    },
+}
+
+/// Handle secret commands
+///
+/// # Arguments
+/// * `command` - The secret command to execute
+/// * `config` - The CLI configuration
+pub async fn handle_secret_command(command: SecretCommands, config: &Config) -> Result<()> {
+    let client = config.client()?;
+
+    match command {
+        SecretCommands::Set { name, pipeline } => {
+            let pipeline_id = resolve_scope(&client, pipeline).await?;
+            set_secret(&client, name, pipeline_id).await
+        }
+        SecretCommands::List { pipeline } => {
+            let pipeline_id = resolve_scope(&client, pipeline).await?;
+            list_secrets(&client, pipeline_id).await
+        }
+        SecretCommands::Delete { name, pipeline } => {
+            let pipeline_id = resolve_scope(&client, pipeline).await?;
+            delete_secret(&client, &name, pipeline_id).await
+        }
+    }
+}
+
+/// Resolve the optional `--pipeline` flag
+async fn resolve_scope(
+    client: &OrchestratorClient,
+    pipeline: Option<String>,
+) -> Result<Option<Uuid>> {
+    match pipeline {
+        Some(id) => Ok(Some(
+            resolve_pipeline_id(client, &IdOrPrefix::parse(&id)).await?,
+        )),
+        None => Ok(None),
+    }
+}
+
+/// Create or replace a secret
+async fn set_secret(
+    client: &OrchestratorClient,
+    name: String,
+    pipeline_id: Option<Uuid>,
+) -> Result<()> {
+    let value = read_secret_value(&name)?;
+
+    let secret = client
+        .set_secret(SetSecret {
+            name,
+            value,
+            pipeline_id,
+        })
+        .await?;
+
+    println!(
+        "{} Secret {} set ({})",
+        "✓".green(),
+        secret.name.bold(),
+        scope_label(secret.pipeline_id)
+    );
+
+    Ok(())
+}
+
+/// List secrets of a scope
+async fn list_secrets(client: &OrchestratorClient, pipeline_id: Option<Uuid>) -> Result<()> {
+    let secrets = client.list_secrets(pipeline_id).await?;
+    if output::is_plain() {
+        for secret in &secrets {
+            output::record(&secret.name, &[&secret.updated_at.to_rfc3339()]);
+        }
+        return Ok(());
+    }
+
+    if secrets.is_empty() {
+        println!(
+            "{}",
+            format!("No secrets found ({}).", scope_label(pipeline_id)).yellow()
+        );
+        return Ok(());
+    }
+
+    println!(
+        "{}",
+        format!(
+            "Found {} secret(s) ({}):",
+            secrets.len(),
+            scope_label(pipeline_id)
+        )
+        .bold()
+    );
+    println!();
+    for secret in secrets {
+        println!(
+            "  {} {}  {}",
+            "▸".cyan(),
+            secret.name.bold(),
+            format!("updated {}", secret.updated_at.format("%Y-%m-%d %H:%M:%S")).dimmed()
+        );
+    }
+
+    Ok(())
+}
+
+/// Delete a secret
+async fn delete_secret(
+    client: &OrchestratorClient,
+    name: &str,
+    pipeline_id: Option<Uuid>,
+) -> Result<()> {
+    client.delete_secret(name, pipeline_id).await?;
+
+    println!(
+        "{} Secret {} deleted ({})",
+        "✓".green(),
+        name.bold(),
+        scope_label(pipeline_id)
+    );
+
+    Ok(())
+}
+
+/// Read a secret value without echoing it
+///
+/// Prompts twice on a terminal; reads the whole of stdin when it is piped.
+fn read_secret_value(name: &str) -> Result<String> {
+    if !std::io::stdin().is_terminal() {
+        let mut value = String::new();
+        std::io::stdin()
+            .read_to_string(&mut value)
+            .context("Failed to read secret value from stdin")?;
+        let value = value.trim_end_matches(['\r', '\n']).to_string();
+        if value.is_empty() {
+            bail!("No secret value received on stdin");
+        }
+        return Ok(value);
+    }
+
+    let value = rpassword::prompt_password(format!("Value for {}: ", name))
+        .context("Failed to read secret value")?;
+    if value.is_empty() {
+        bail!("Secret value cannot be empty");
+    }
+
+    let confirmation =
+        rpassword::prompt_password("Confirm value: ").context("Failed to read secret value")?;
+    if value != confirmation {
+        bail!("Values do not match");
+    }
+
+    Ok(value)
+}
+
+fn scope_label(pipeline_id: Option<Uuid>) -> String {
+    match pipeline_id {
+        Some(id) => format!("pipeline {}", id),
+        None => "project-wide".to_string(),
+    }
+}
@@ -2,9 +2,69 @@
 //!
 //! Handles CLI configuration including orchestrator URL and other settings.
 
+use std::time::Duration;
+
+use anyhow::Result;
+use rivet_client::{ClientBuilder, OrchestratorClient, RetryPolicy};
+
 /// CLI configuration
 #[derive(Debug, Clone)]
 pub struct Config {
     /// URL of the orchestrator service
     pub orchestrator_url: String,
+
+    /// Bearer token for secured orchestrators
+    pub token: Option<String>,
+
+    /// Extra headers sent with every request (e.g., tenant or trace headers)
+    pub headers: Vec<(String, String)>,
+
+    /// Project (name or ID) pipeline, job and secret commands are scoped to
+    pub project: Option<String>,
+
+    /// Time a request may take, `None` to wait forever
+    pub timeout: Option<Duration>,
+
+    /// Retries of failed idempotent requests
+    pub retries: u32,
+}
+
+impl Config {
+    /// Build an orchestrator client from this configuration
+    pub fn client(&self) -> Result<OrchestratorClient> {
+        let mut builder = self.builder();
+        if let Some(timeout) = self.timeout {
+            builder = builder.timeout(timeout);
+        }
+        Ok(builder.build()?)
+    }
+
+    /// Build a client for long responses (event streams, artifact
+    /// downloads), which only times out while connecting
+    pub fn streaming_client(&self) -> Result<OrchestratorClient> {
+        Ok(self.builder().build()?)
+    }
+
+    fn builder(&self) -> ClientBuilder {
+        let mut builder = OrchestratorClient::builder()
+            .base_url(&self.orchestrator_url)
+            .retry_policy(RetryPolicy {
+                max_retries: self.retries,
+                ..RetryPolicy::default()
+            });
+        if let Some(timeout) = self.timeout {
+            builder = builder.connect_timeout(timeout);
+        }
+
+        if let Some(token) = &self.token {
+            builder = builder.token(token);
+        }
+        for (name, value) in &self.headers {
+            builder = builder.header(name, value);
+        }
+        if let Some(project) = &self.project {
+            builder = builder.project(project);
+        }
+        builder
+    }
 }
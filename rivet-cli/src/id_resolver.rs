@@ -2,32 +2,39 @@
 //!
 //! Handles resolution of UUID prefixes to full UUIDs by querying the API.
 //! This allows users to specify short, unambiguous prefixes instead of full UUIDs.
+//! Pipelines may also be given by name, e.g. `rivet pipeline launch deploy-frontend`,
+//! and jobs by pipeline and number, e.g. `rivet job get deploy-frontend/42`.
 
 use anyhow::{Context, Result, anyhow};
+use rivet_core::domain::pipeline::Pipeline;
 use uuid::Uuid;
 
+use crate::picker;
 use crate::types::IdOrPrefix;
-use rivet_client::OrchestratorClient;
+use rivet_client::OrchestratorApi;
 
-/// Resolve a pipeline ID or prefix to a full UUID
+/// Resolve a pipeline ID, prefix or name to a full UUID
 ///
 /// If the input is already a full UUID, returns it immediately.
-/// Otherwise, fetches all pipelines and finds the one matching the prefix.
+/// Otherwise, fetches all pipelines and finds those named exactly like the
+/// input, or failing that those whose ID starts with it. When several
+/// match and the CLI runs in a terminal, the user picks one of them (see
+/// `picker`).
 ///
 /// # Arguments
 /// * `client` - The API client to use for fetching pipelines
-/// * `id_or_prefix` - The ID or prefix to resolve
+/// * `id_or_prefix` - The ID, prefix or name to resolve
 ///
 /// # Returns
 /// The resolved UUID
 ///
 /// # Errors
 /// Returns an error if:
-/// - No pipeline matches the prefix
-/// - Multiple pipelines match the prefix (ambiguous)
+/// - No pipeline has the name or matches the prefix
+/// - Multiple pipelines match and there is no terminal to ask in (ambiguous)
 /// - API call fails
 pub async fn resolve_pipeline_id(
-    client: &OrchestratorClient,
+    client: &dyn OrchestratorApi,
     id_or_prefix: &IdOrPrefix,
 ) -> Result<Uuid> {
     // If it's already a full UUID, return it
@@ -35,56 +42,84 @@ pub async fn resolve_pipeline_id(
         return Ok(uuid);
     }
 
-    let prefix = id_or_prefix.as_str().to_lowercase();
-
     // Fetch all pipelines
     let pipelines = client
         .list_pipelines()
         .await
         .context("Failed to fetch pipelines for ID resolution")?;
 
-    // Find matching pipelines
-    let matches: Vec<_> = pipelines
-        .iter()
-        .filter(|p| p.id.to_string().to_lowercase().starts_with(&prefix))
-        .collect();
+    resolve_pipeline_in(&pipelines, &id_or_prefix.as_str())
+}
+
+/// Resolve a pipeline ID prefix or name among `pipelines`
+///
+/// Like `resolve_pipeline_id`, for callers that fetched the pipelines
+/// themselves, e.g. the archived ones.
+pub fn resolve_pipeline_in(pipelines: &[Pipeline], input: &str) -> Result<Uuid> {
+    if let Ok(uuid) = Uuid::parse_str(input) {
+        return Ok(uuid);
+    }
 
+    let matches = matching_pipelines(pipelines, input);
     match matches.len() {
         0 => Err(anyhow!(
-            "No pipeline found with ID starting with '{}'",
-            prefix
+            "No pipeline found named '{}' or with ID starting with '{}'",
+            input,
+            input.to_lowercase()
         )),
         1 => Ok(matches[0].id),
+        n if picker::can_pick() => {
+            picker::pick_among_pipelines(&format!("'{}' matches {} pipelines", input, n), &matches)
+        }
         _ => {
-            let ids: Vec<String> = matches.iter().map(|p| p.id.to_string()).collect();
+            let ids: Vec<String> = matches
+                .iter()
+                .map(|p| picker::describe_pipeline(p))
+                .collect();
             Err(anyhow!(
-                "Ambiguous prefix '{}' matches multiple pipelines: {}",
-                prefix,
+                "Ambiguous pipeline '{}' matches multiple pipelines: {}",
+                input,
                 ids.join(", ")
             ))
         }
     }
 }
 
-/// Resolve a job ID or prefix to a full UUID
+/// Pipelines named `input`, or if none is, those whose ID starts with it
+fn matching_pipelines<'a>(pipelines: &'a [Pipeline], input: &str) -> Vec<&'a Pipeline> {
+    let named: Vec<_> = pipelines.iter().filter(|p| p.name == input).collect();
+    if !named.is_empty() {
+        return named;
+    }
+
+    let prefix = input.to_lowercase();
+    pipelines
+        .iter()
+        .filter(|p| p.id.to_string().starts_with(&prefix))
+        .collect()
+}
+
+/// Resolve a job ID, prefix or `<pipeline>/<number>` to a full UUID
 ///
 /// If the input is already a full UUID, returns it immediately.
-/// Otherwise, fetches all scheduled jobs and finds the one matching the prefix.
+/// `<pipeline>/<number>` names the job by its number within a pipeline,
+/// given like in `resolve_pipeline_id`. Otherwise, fetches all scheduled
+/// jobs and finds the one matching the prefix.
 ///
 /// # Arguments
 /// * `client` - The API client to use for fetching jobs
-/// * `id_or_prefix` - The ID or prefix to resolve
+/// * `id_or_prefix` - The ID, prefix or pipeline and number to resolve
 ///
 /// # Returns
 /// The resolved UUID
 ///
 /// # Errors
 /// Returns an error if:
-/// - No job matches the prefix
+/// - No job matches the prefix, or the pipeline has no job with the number
 /// - Multiple jobs match the prefix (ambiguous)
 /// - API call fails
 pub async fn resolve_job_id(
-    client: &OrchestratorClient,
+    client: &dyn OrchestratorApi,
     id_or_prefix: &IdOrPrefix,
 ) -> Result<Uuid> {
     // If it's already a full UUID, return it
@@ -92,6 +127,10 @@ pub async fn resolve_job_id(
         return Ok(uuid);
     }
 
+    if let Some((pipeline, number)) = parse_job_number(&id_or_prefix.as_str()) {
+        return resolve_job_number(client, &IdOrPrefix::parse(pipeline), number).await;
+    }
+
     let prefix = id_or_prefix.as_str().to_lowercase();
 
     // Fetch all scheduled jobs
@@ -120,6 +159,34 @@ pub async fn resolve_job_id(
     }
 }
 
+/// Split `<pipeline>/<number>` into the pipeline and the job number
+///
+/// The number follows the last `/`, with an optional `#` (`build/#42`).
+fn parse_job_number(input: &str) -> Option<(&str, u64)> {
+    let (pipeline, number) = input.rsplit_once('/')?;
+    let number = number.strip_prefix('#').unwrap_or(number).parse().ok()?;
+    (!pipeline.is_empty()).then_some((pipeline, number))
+}
+
+/// Resolve job number `number` of a pipeline to the job's UUID
+async fn resolve_job_number(
+    client: &dyn OrchestratorApi,
+    pipeline: &IdOrPrefix,
+    number: u64,
+) -> Result<Uuid> {
+    let pipeline_id = resolve_pipeline_id(client, pipeline).await?;
+
+    let jobs = client
+        .list_jobs_by_pipeline(pipeline_id)
+        .await
+        .context("Failed to fetch pipeline jobs for ID resolution")?;
+
+    jobs.iter()
+        .find(|j| j.number == number)
+        .map(|j| j.id)
+        .ok_or_else(|| anyhow!("No job #{} in pipeline '{}'", number, pipeline))
+}
+
 /// Resolve a job ID or prefix within a specific pipeline
 ///
 /// Similar to `resolve_job_id` but only searches within jobs of a specific pipeline.
@@ -138,7 +205,7 @@ pub async fn resolve_job_id(
 /// - Multiple jobs match the prefix (ambiguous)
 /// - API call fails
 pub async fn resolve_job_id_in_pipeline(
-    client: &OrchestratorClient,
+    client: &dyn OrchestratorApi,
     pipeline_id: Uuid,
     id_or_prefix: &IdOrPrefix,
 ) -> Result<Uuid> {
@@ -179,3 +246,119 @@ pub async fn resolve_job_id_in_pipeline(
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rivet_client::MockOrchestrator;
+
+    #[tokio::test]
+    async fn test_resolve_pipeline_prefix() {
+        let mock = MockOrchestrator::new();
+        let pipeline = mock.add_pipeline("build", "return {}");
+        let prefix = &pipeline.id.to_string()[..8];
+
+        let resolved = resolve_pipeline_id(&mock, &IdOrPrefix::parse(prefix))
+            .await
+            .unwrap();
+        assert_eq!(resolved, pipeline.id);
+
+        assert!(
+            resolve_pipeline_id(&mock, &IdOrPrefix::parse("zzzz"))
+                .await
+                .is_err()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_resolve_ambiguous_job_prefix() {
+        let mock = MockOrchestrator::new();
+        let pipeline = mock.add_pipeline("build", "return {}");
+        for _ in 0..2 {
+            mock.launch_job(rivet_core::dto::job::CreateJob {
+                pipeline_id: pipeline.id,
+                parameters: Default::default(),
+                plan: false,
+                commit: None,
+                override_blackout: false,
+                labels: Default::default(),
+            })
+            .await
+            .unwrap();
+        }
+
+        // The empty prefix matches every job
+        let err = resolve_job_id(&mock, &IdOrPrefix::parse(""))
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("Ambiguous"));
+    }
+
+    #[tokio::test]
+    async fn test_resolve_pipeline_name() {
+        let mock = MockOrchestrator::new();
+        let frontend = mock.add_pipeline("deploy-frontend", "return {}");
+        mock.add_pipeline("deploy-backend", "return {}");
+
+        let resolved = resolve_pipeline_id(&mock, &IdOrPrefix::parse("deploy-frontend"))
+            .await
+            .unwrap();
+        assert_eq!(resolved, frontend.id);
+
+        // Names must match exactly
+        assert!(
+            resolve_pipeline_id(&mock, &IdOrPrefix::parse("deploy"))
+                .await
+                .is_err()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_resolve_job_number() {
+        let mock = MockOrchestrator::new();
+        let pipeline = mock.add_pipeline("deploy-frontend", "return {}");
+        let mut jobs = Vec::new();
+        for _ in 0..2 {
+            let job = mock
+                .launch_job(rivet_core::dto::job::CreateJob {
+                    pipeline_id: pipeline.id,
+                    parameters: Default::default(),
+                    plan: false,
+                    commit: None,
+                    override_blackout: false,
+                    labels: Default::default(),
+                })
+                .await
+                .unwrap();
+            jobs.push(job);
+        }
+
+        let resolved = resolve_job_id(&mock, &IdOrPrefix::parse("deploy-frontend/2"))
+            .await
+            .unwrap();
+        assert_eq!(resolved, jobs[1].id);
+
+        let resolved = resolve_job_id(&mock, &IdOrPrefix::parse("deploy-frontend/#1"))
+            .await
+            .unwrap();
+        assert_eq!(resolved, jobs[0].id);
+
+        let err = resolve_job_id(&mock, &IdOrPrefix::parse("deploy-frontend/3"))
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("No job #3"));
+    }
+
+    #[test]
+    fn test_names_take_precedence_over_prefixes() {
+        let mock = MockOrchestrator::new();
+        let first = mock.add_pipeline("build", "return {}");
+        let prefix = first.id.to_string()[..4].to_string();
+        let named = mock.add_pipeline(&prefix, "return {}");
+        let pipelines = vec![first, named.clone()];
+
+        let matches = matching_pipelines(&pipelines, &prefix);
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].id, named.id);
+    }
+}
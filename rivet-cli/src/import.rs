@@ -0,0 +1,1458 @@
+//! CI configuration import
+//!
+//! Converts the common subset of a GitHub Actions workflow or a GitLab CI
+//! configuration (jobs, steps, images, env, matrix) into a Rivet Lua
+//! pipeline. Jobs become stages, matrix jobs one stage per combination, and
+//! steps shell scripts run with `process.run`. Anything without a Rivet
+//! equivalent (actions, services, caches, artifacts, rules) is left as a
+//! `-- TODO:` comment where it appeared.
+
+use anyhow::{Result, bail};
+use rivet_core::dto::commit_status::GitProvider;
+use serde_yaml::{Mapping, Value};
+
+/// A converted pipeline script
+pub struct Conversion {
+    pub script: String,
+    pub stages: usize,
+    /// TODO comments left in the script
+    pub todos: usize,
+}
+
+/// Converts a CI configuration into a Rivet pipeline script
+///
+/// # Arguments
+/// * `provider` - Whose CI format `source` is in
+/// * `source` - The workflow (GitHub) or `.gitlab-ci.yml` (GitLab) contents
+/// * `origin` - Where `source` came from, named in the script's header
+/// * `default_name` - Pipeline name when the configuration has none
+///
+/// # Returns
+/// The script, with the number of stages and TODO comments in it
+pub fn convert(
+    provider: GitProvider,
+    source: &str,
+    origin: &str,
+    default_name: &str,
+) -> Result<Conversion> {
+    let document: Value = serde_yaml::from_str(source)
+        .map_err(|e| anyhow::anyhow!("Invalid YAML in '{}': {}", origin, e))?;
+    if !document.is_mapping() {
+        bail!(
+            "'{}' is not a CI configuration (expected a mapping)",
+            origin
+        );
+    }
+
+    let (pipeline, system) = match provider {
+        GitProvider::Github => (convert_github(&document, default_name)?, "GitHub Actions"),
+        GitProvider::Gitlab => (convert_gitlab(&document, default_name)?, "GitLab CI"),
+    };
+    if pipeline.stages.is_empty() {
+        bail!("'{}' declares no jobs", origin);
+    }
+
+    let script = render(&pipeline, origin, system);
+    Ok(Conversion {
+        stages: pipeline.stages.len(),
+        todos: script.matches("-- TODO:").count(),
+        script,
+    })
+}
+
+// =============================================================================
+// Pipeline Model
+// =============================================================================
+
+struct Pipeline {
+    name: String,
+    inputs: Vec<Input>,
+    stages: Vec<Stage>,
+    todos: Vec<String>,
+}
+
+impl Pipeline {
+    /// Declares a secret input for a secret the configuration reads, once
+    fn add_secret(&mut self, name: &str) {
+        if !self.inputs.iter().any(|input| input.name == name) {
+            self.inputs.push(Input {
+                name: name.to_string(),
+                kind: "secret",
+                description: Some(format!("Was the CI secret {}", name)),
+                default: None,
+                options: Vec::new(),
+                required: true,
+            });
+        }
+    }
+}
+
+struct Input {
+    name: String,
+    kind: &'static str,
+    description: Option<String>,
+    default: Option<String>,
+    options: Vec<String>,
+    required: bool,
+}
+
+struct Stage {
+    name: String,
+    container: Option<String>,
+    needs: Vec<String>,
+    allow_failure: bool,
+    when: Option<&'static str>,
+    todos: Vec<String>,
+    steps: Vec<Step>,
+    /// Steps run whether or not `steps` failed (GitLab's `after_script`)
+    after: Vec<Step>,
+}
+
+struct Step {
+    name: String,
+    /// Shell script, `None` for steps only kept as TODO comments
+    script: Option<String>,
+    shell: Option<String>,
+    env: Vec<(String, EnvValue)>,
+    cwd: Option<EnvValue>,
+    /// Timeout in seconds
+    timeout: Option<u64>,
+    todos: Vec<String>,
+}
+
+impl Step {
+    fn new(name: impl Into<String>, script: Option<String>) -> Self {
+        Self {
+            name: name.into(),
+            script,
+            shell: None,
+            env: Vec::new(),
+            cwd: None,
+            timeout: None,
+            todos: Vec::new(),
+        }
+    }
+}
+
+/// Part of a value that may use pipeline inputs
+#[derive(Clone)]
+enum Part {
+    Text(String),
+    /// A pipeline input, set in scripts as the environment variable
+    Input {
+        name: String,
+        variable: String,
+    },
+    /// A variable of the step's environment
+    Env(String),
+}
+
+/// Value of an environment variable or option of a step, evaluated when
+/// the stage runs (text and inputs only)
+#[derive(Clone)]
+struct EnvValue(Vec<Part>);
+
+impl EnvValue {
+    fn literal(value: impl Into<String>) -> Self {
+        Self(vec![Part::Text(value.into())])
+    }
+
+    /// Lua expression of the value
+    fn to_lua(&self) -> String {
+        let parts: Vec<String> = self
+            .0
+            .iter()
+            .filter_map(|part| match part {
+                Part::Text(text) => Some(lua_string(text)),
+                Part::Input { name, .. } => Some(format!("input.get({}, \"\")", lua_string(name))),
+                Part::Env(_) => None,
+            })
+            .collect();
+        if parts.is_empty() {
+            "\"\"".to_string()
+        } else {
+            parts.join(" .. ")
+        }
+    }
+}
+
+/// Matrix values of one stage, as `(key, value)` pairs
+type Combination = Vec<(String, String)>;
+
+// =============================================================================
+// GitHub Actions
+// =============================================================================
+
+fn convert_github(document: &Value, default_name: &str) -> Result<Pipeline> {
+    let mut pipeline = Pipeline {
+        name: document
+            .get("name")
+            .and_then(scalar)
+            .unwrap_or_else(|| default_name.to_string()),
+        inputs: Vec::new(),
+        stages: Vec::new(),
+        todos: Vec::new(),
+    };
+
+    // `on` is read by YAML 1.1 parsers as `true`; serde_yaml keeps it a string
+    if let Some(triggers) = document.get("on") {
+        github_triggers(triggers, &mut pipeline);
+    }
+
+    let workflow_env = env_pairs(document.get("env"));
+    let Some(jobs) = document.get("jobs").and_then(Value::as_mapping) else {
+        bail!("The workflow has no 'jobs' mapping");
+    };
+
+    // Stage names of every job, to expand the needs of matrix jobs
+    let mut expanded: Vec<(String, Vec<String>)> = Vec::new();
+    for (id, job) in jobs {
+        let Some(id) = scalar(id) else { continue };
+        let combinations = github_matrix(job, &mut pipeline.todos, &id);
+        let names = stage_names(&id, &combinations);
+
+        for (combination, name) in combinations.iter().zip(&names) {
+            let stage = github_stage(
+                &mut pipeline,
+                name,
+                job,
+                combination,
+                &workflow_env,
+                &expanded,
+            );
+            pipeline.stages.push(stage);
+        }
+        expanded.push((id, names));
+    }
+
+    Ok(pipeline)
+}
+
+/// Turns `workflow_dispatch` inputs into pipeline inputs; other triggers
+/// become webhook triggers or schedules on the orchestrator
+fn github_triggers(triggers: &Value, pipeline: &mut Pipeline) {
+    let names: Vec<String> = match triggers {
+        Value::Mapping(map) => map.keys().filter_map(scalar).collect(),
+        other => strings(Some(other)),
+    };
+    let other: Vec<&String> = names
+        .iter()
+        .filter(|name| name.as_str() != "workflow_dispatch")
+        .collect();
+    if !other.is_empty() {
+        pipeline.todos.push(format!(
+            "triggers ({}) are not converted; set up webhook triggers \
+             (`rivet pipeline webhook add`) or schedules (`rivet pipeline schedule add`)",
+            other
+                .iter()
+                .map(|name| name.as_str())
+                .collect::<Vec<_>>()
+                .join(", ")
+        ));
+    }
+
+    let Some(inputs) = triggers
+        .get("workflow_dispatch")
+        .and_then(|dispatch| dispatch.get("inputs"))
+        .and_then(Value::as_mapping)
+    else {
+        return;
+    };
+
+    for (name, spec) in inputs {
+        let Some(name) = scalar(name) else { continue };
+        let kind = match spec.get("type").and_then(scalar).as_deref() {
+            Some("boolean") => "bool",
+            Some("number") => "number",
+            Some("choice") => "enum",
+            Some("environment") | Some("string") | None => "string",
+            Some(other) => {
+                pipeline.todos.push(format!(
+                    "input '{}' has type '{}', converted to a string",
+                    name, other
+                ));
+                "string"
+            }
+        };
+        let default = spec.get("default").and_then(scalar);
+        let required = spec
+            .get("required")
+            .and_then(Value::as_bool)
+            .unwrap_or(false);
+
+        pipeline.inputs.push(Input {
+            name,
+            kind,
+            description: spec.get("description").and_then(scalar),
+            required: required && default.is_none(),
+            default,
+            options: strings(spec.get("options")),
+        });
+    }
+}
+
+/// Combinations of a job's `strategy.matrix`, a single empty one without
+fn github_matrix(job: &Value, todos: &mut Vec<String>, id: &str) -> Vec<Combination> {
+    let Some(matrix) = job.get("strategy").and_then(|s| s.get("matrix")) else {
+        return vec![Vec::new()];
+    };
+    let Some(matrix) = matrix.as_mapping() else {
+        todos.push(format!(
+            "the matrix of job '{}' is an expression and was not expanded",
+            id
+        ));
+        return vec![Vec::new()];
+    };
+
+    let axes: Vec<(String, Vec<String>)> = matrix
+        .iter()
+        .filter_map(|(key, values)| {
+            let key = scalar(key)?;
+            (key != "include" && key != "exclude").then(|| (key, strings(Some(values))))
+        })
+        .collect();
+    let mut combinations = cartesian(&axes);
+
+    let rows = |key: &str| -> Vec<Combination> {
+        matrix
+            .get(key)
+            .and_then(Value::as_sequence)
+            .map(|rows| rows.iter().map(|row| env_pairs(Some(row))).collect())
+            .unwrap_or_default()
+    };
+
+    for exclude in rows("exclude") {
+        combinations.retain(|combination| !matches_all(combination, &exclude));
+    }
+
+    // An include extends the combinations its original keys match, or is
+    // a combination of its own when it matches none
+    for include in rows("include") {
+        let original: Combination = include
+            .iter()
+            .filter(|(key, _)| axes.iter().any(|(axis, _)| axis == key))
+            .cloned()
+            .collect();
+        let mut matched = false;
+        for combination in combinations.iter_mut() {
+            if !axes.is_empty() && matches_all(combination, &original) {
+                matched = true;
+                for (key, value) in &include {
+                    if !combination.iter().any(|(k, _)| k == key) {
+                        combination.push((key.clone(), value.clone()));
+                    }
+                }
+            }
+        }
+        if !matched {
+            combinations.push(include);
+        }
+    }
+
+    if combinations.is_empty() {
+        vec![Vec::new()]
+    } else {
+        combinations
+    }
+}
+
+fn github_stage(
+    pipeline: &mut Pipeline,
+    name: &str,
+    job: &Value,
+    combination: &Combination,
+    workflow_env: &[(String, String)],
+    expanded: &[(String, Vec<String>)],
+) -> Stage {
+    let mut todos = Vec::new();
+    let mut secrets = Vec::new();
+    let mut parts =
+        |text: &str, todos: &mut Vec<String>| github_parts(text, combination, &mut secrets, todos);
+
+    let mut job_env: Vec<(String, EnvValue)> = Vec::new();
+    for (key, value) in workflow_env
+        .iter()
+        .cloned()
+        .chain(env_pairs(job.get("env")))
+    {
+        let value = env_value(parts(&value, &mut todos), &job_env, &mut todos);
+        set_env(&mut job_env, key, value);
+    }
+
+    let container = match job.get("container") {
+        Some(container) => container
+            .get("image")
+            .or(Some(container))
+            .and_then(scalar)
+            .map(|image| fixed_text(&image, parts(&image, &mut todos), &mut todos)),
+        None => job.get("runs-on").and_then(|runs_on| {
+            let label = strings(Some(runs_on)).join(", ");
+            let label = fixed_text(&label, parts(&label, &mut todos), &mut todos);
+            let image = runner_image(&label);
+            if image.is_none() {
+                todos.push(format!(
+                    "runs-on '{}' has no container image equivalent; set `container`",
+                    label
+                ));
+            }
+            image
+        }),
+    };
+    if job
+        .get("container")
+        .and_then(|c| c.get("options"))
+        .is_some()
+    {
+        todos.push("container options are not converted".to_string());
+    }
+
+    let when = match job.get("if").and_then(scalar) {
+        Some(condition) => github_when(&condition, &mut todos),
+        None => None,
+    };
+    for (key, what) in [
+        ("services", "services"),
+        ("outputs", "job outputs"),
+        (
+            "environment",
+            "the deployment environment (see `deploys_to`)",
+        ),
+        ("concurrency", "concurrency limits"),
+        ("permissions", "permissions"),
+    ] {
+        if job.get(key).is_some() {
+            todos.push(format!("{} are not converted", what));
+        }
+    }
+    if let Some(uses) = job.get("uses").and_then(scalar) {
+        todos.push(format!("reusable workflow '{}' is not converted", uses));
+    }
+
+    let timeout = job
+        .get("timeout-minutes")
+        .and_then(Value::as_u64)
+        .map(|minutes| minutes * 60);
+    let default_shell = job
+        .get("defaults")
+        .and_then(|d| d.get("run"))
+        .and_then(|r| r.get("shell"))
+        .and_then(scalar);
+    let default_cwd = job
+        .get("defaults")
+        .and_then(|d| d.get("run"))
+        .and_then(|r| r.get("working-directory"))
+        .and_then(scalar);
+
+    let mut steps = Vec::new();
+    for (index, step) in job
+        .get("steps")
+        .and_then(Value::as_sequence)
+        .into_iter()
+        .flatten()
+        .enumerate()
+    {
+        let run = step.get("run").and_then(scalar);
+        let step_name = step
+            .get("name")
+            .and_then(scalar)
+            .or_else(|| step.get("uses").and_then(scalar))
+            .or_else(|| {
+                let first_line = run.as_deref()?.lines().find(|l| !l.trim().is_empty())?;
+                Some(first_line.trim().to_string())
+            })
+            .unwrap_or_else(|| format!("Step {}", index + 1));
+        let mut converted = Step::new(step_name, None);
+
+        converted.env = job_env.clone();
+        for (key, value) in env_pairs(step.get("env")) {
+            let value = env_value(
+                parts(&value, &mut converted.todos),
+                &converted.env,
+                &mut converted.todos,
+            );
+            set_env(&mut converted.env, key, value);
+        }
+        match run {
+            Some(run) => {
+                let run = parts(&run, &mut converted.todos);
+                converted.script = Some(shell_text(run, &mut converted.env));
+            }
+            None => {
+                if let Some(uses) = step.get("uses").and_then(scalar) {
+                    converted.todos.push(action_todo(&uses, step.get("with")));
+                }
+            }
+        }
+
+        converted.shell = step
+            .get("shell")
+            .and_then(scalar)
+            .or_else(|| default_shell.clone());
+        if let Some(shell) = &converted.shell
+            && !matches!(shell.as_str(), "sh" | "bash")
+        {
+            converted
+                .todos
+                .push(format!("shell '{}' is not supported, sh is used", shell));
+            converted.shell = None;
+        }
+        converted.cwd = step
+            .get("working-directory")
+            .and_then(scalar)
+            .or_else(|| default_cwd.clone())
+            .map(|cwd| {
+                let cwd = parts(&cwd, &mut converted.todos);
+                env_value(cwd, &converted.env, &mut converted.todos)
+            });
+        converted.timeout = step
+            .get("timeout-minutes")
+            .and_then(Value::as_u64)
+            .map(|minutes| minutes * 60)
+            .or(timeout);
+        if let Some(condition) = step.get("if").and_then(scalar) {
+            converted
+                .todos
+                .push(format!("the step only ran if `{}`", condition));
+        }
+        if step.get("continue-on-error").and_then(Value::as_bool) == Some(true) {
+            converted
+                .todos
+                .push("the step's failure was ignored (continue-on-error)".to_string());
+        }
+        steps.push(converted);
+    }
+
+    for secret in secrets {
+        pipeline.add_secret(&secret);
+    }
+
+    Stage {
+        name: name.to_string(),
+        container,
+        needs: expand_needs(&strings(job.get("needs")), expanded, &mut todos),
+        allow_failure: job.get("continue-on-error").and_then(Value::as_bool) == Some(true),
+        when,
+        todos,
+        steps,
+        after: Vec::new(),
+    }
+}
+
+/// Stage `when` of a job's `if`, for the status functions Rivet has
+fn github_when(condition: &str, todos: &mut Vec<String>) -> Option<&'static str> {
+    let condition = condition
+        .trim()
+        .trim_start_matches("${{")
+        .trim_end_matches("}}")
+        .trim();
+    match condition {
+        "always()" => Some("always"),
+        "failure()" => Some("on_failure"),
+        "success()" => None,
+        other => {
+            todos.push(format!(
+                "the job only ran if `{}`; write a stage `condition`",
+                other
+            ));
+            None
+        }
+    }
+}
+
+/// Splits text at its `${{ }}` expressions, replaced with what Rivet
+/// provides
+///
+/// Matrix values are written in, inputs and secrets read from pipeline
+/// inputs and env values from the step's environment.
+fn github_parts(
+    text: &str,
+    combination: &Combination,
+    secrets: &mut Vec<String>,
+    todos: &mut Vec<String>,
+) -> Vec<Part> {
+    let mut parts = Vec::new();
+    let mut rest = text;
+    while let Some(start) = rest.find("${{") {
+        let Some(end) = rest[start..].find("}}") else {
+            break;
+        };
+        parts.push(Part::Text(rest[..start].to_string()));
+        let expression = rest[start + 3..start + end].trim();
+        let original = &rest[start..start + end + 2];
+        rest = &rest[start + end + 2..];
+
+        let path: Vec<&str> = expression.split('.').collect();
+        let part = match path.as_slice() {
+            ["matrix", key] => match combination.iter().find(|(k, _)| k == key) {
+                Some((_, value)) => Part::Text(value.clone()),
+                None => {
+                    todos.push(format!("`{}` is not a matrix value", expression));
+                    Part::Text(original.to_string())
+                }
+            },
+            ["env", key] => Part::Env(key.to_string()),
+            ["inputs", key] | ["github", "event", "inputs", key] => Part::Input {
+                name: key.to_string(),
+                variable: format!("INPUT_{}", env_name(key)),
+            },
+            ["secrets", "GITHUB_TOKEN"] => {
+                todos.push("GITHUB_TOKEN is not provided; add a secret input".to_string());
+                Part::Text(original.to_string())
+            }
+            ["secrets", key] => {
+                if !secrets.iter().any(|s| s == key) {
+                    secrets.push(key.to_string());
+                }
+                Part::Input {
+                    name: key.to_string(),
+                    variable: env_name(key),
+                }
+            }
+            _ => {
+                todos.push(format!("expression `{}` is not converted", expression));
+                Part::Text(original.to_string())
+            }
+        };
+        parts.push(part);
+    }
+    parts.push(Part::Text(rest.to_string()));
+    parts.retain(|part| !matches!(part, Part::Text(text) if text.is_empty()));
+    parts
+}
+
+/// Shell script text of parts, reading inputs from environment variables
+/// it adds to `env`
+fn shell_text(parts: Vec<Part>, env: &mut Vec<(String, EnvValue)>) -> String {
+    let mut text = String::new();
+    for part in parts {
+        match part {
+            Part::Text(t) => text.push_str(&t),
+            Part::Env(key) => text.push_str(&format!("${{{}}}", key)),
+            Part::Input { name, variable } => {
+                text.push_str(&format!("${{{}}}", variable));
+                let value = EnvValue(vec![Part::Input {
+                    name,
+                    variable: variable.clone(),
+                }]);
+                set_env(env, variable, value);
+            }
+        }
+    }
+    text
+}
+
+/// Value of parts outside of a script, with env values looked up in `env`
+fn env_value(parts: Vec<Part>, env: &[(String, EnvValue)], todos: &mut Vec<String>) -> EnvValue {
+    let mut value = Vec::new();
+    for part in parts {
+        match part {
+            Part::Env(key) => match env.iter().find(|(k, _)| *k == key) {
+                Some((_, set)) => value.extend(set.0.iter().cloned()),
+                None => todos.push(format!("`env.{}` is not set by the workflow", key)),
+            },
+            part => value.push(part),
+        }
+    }
+    EnvValue(value)
+}
+
+/// Text of parts that must be known when the pipeline is defined, kept as
+/// written when it reads inputs
+fn fixed_text(original: &str, parts: Vec<Part>, todos: &mut Vec<String>) -> String {
+    let mut text = String::new();
+    for part in parts {
+        match part {
+            Part::Text(t) => text.push_str(&t),
+            _ => {
+                todos.push(format!("`{}` depends on inputs or env values", original));
+                return original.to_string();
+            }
+        }
+    }
+    text
+}
+
+/// TODO comment replacing an action step
+fn action_todo(uses: &str, with: Option<&Value>) -> String {
+    let action = uses.split('@').next().unwrap_or(uses);
+    let hint = match action {
+        "actions/checkout" => "check out the repository, e.g. with `git clone`".to_string(),
+        "actions/cache" => "caches are not converted".to_string(),
+        "actions/upload-artifact" | "actions/download-artifact" => {
+            "artifacts are not converted".to_string()
+        }
+        action if action.starts_with("actions/setup-") => format!(
+            "use a container image providing {}",
+            action.trim_start_matches("actions/setup-")
+        ),
+        _ => "actions have no Rivet equivalent".to_string(),
+    };
+    let with = with
+        .and_then(Value::as_mapping)
+        .map(|with| {
+            with.iter()
+                .filter_map(|(key, value)| Some(format!("{}={}", scalar(key)?, scalar(value)?)))
+                .collect::<Vec<_>>()
+        })
+        .filter(|with| !with.is_empty())
+        .map(|with| format!(" (with {})", with.join(", ")))
+        .unwrap_or_default();
+    format!("action {}{}: {}", uses, with, hint)
+}
+
+/// Container image of a GitHub-hosted runner label
+fn runner_image(label: &str) -> Option<String> {
+    let version = label.strip_prefix("ubuntu-")?;
+    Some(format!("ubuntu:{}", version))
+}
+
+// =============================================================================
+// GitLab CI
+// =============================================================================
+
+/// Top-level keys of `.gitlab-ci.yml` that are not jobs
+const GITLAB_KEYWORDS: &[&str] = &[
+    "image",
+    "services",
+    "stages",
+    "variables",
+    "before_script",
+    "after_script",
+    "cache",
+    "default",
+    "include",
+    "workflow",
+];
+
+/// Stages of a GitLab configuration that does not list its own
+const GITLAB_DEFAULT_STAGES: &[&str] = &[".pre", "build", "test", "deploy", ".post"];
+
+fn convert_gitlab(document: &Value, default_name: &str) -> Result<Pipeline> {
+    let mut pipeline = Pipeline {
+        name: default_name.to_string(),
+        inputs: Vec::new(),
+        stages: Vec::new(),
+        todos: Vec::new(),
+    };
+
+    for (key, what) in [
+        ("include", "included files"),
+        ("workflow", "workflow rules"),
+        ("services", "services"),
+        ("cache", "caches"),
+    ] {
+        if gitlab_global(document, key).is_some() {
+            pipeline.todos.push(format!("{} are not converted", what));
+        }
+    }
+
+    let mut stage_order: Vec<String> = strings(document.get("stages"));
+    if stage_order.is_empty() {
+        stage_order = GITLAB_DEFAULT_STAGES
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+    }
+    let variables = env_pairs(document.get("variables"));
+    let Some(jobs_map) = document.as_mapping() else {
+        bail!("The configuration is not a mapping");
+    };
+
+    // Jobs with their GitLab stage, in stage order then file order
+    let mut jobs: Vec<(usize, String, &Value)> = Vec::new();
+    for (name, job) in jobs_map {
+        let Some(name) = scalar(name) else { continue };
+        if GITLAB_KEYWORDS.contains(&name.as_str()) {
+            continue;
+        }
+        if name.starts_with('.') {
+            pipeline.todos.push(format!(
+                "hidden job '{}' is not converted (see `extends`)",
+                name
+            ));
+            continue;
+        }
+        if !job.is_mapping() {
+            continue;
+        }
+        let stage = job
+            .get("stage")
+            .and_then(scalar)
+            .unwrap_or_else(|| "test".to_string());
+        let Some(order) = stage_order.iter().position(|s| *s == stage) else {
+            bail!(
+                "Job '{}' is in stage '{}', which 'stages' does not list",
+                name,
+                stage
+            );
+        };
+        jobs.push((order, name, job));
+    }
+    jobs.sort_by_key(|(order, _, _)| *order);
+
+    // Stage names per job, to expand needs, and per GitLab stage, for
+    // jobs without needs, which wait for the whole previous stage
+    let mut expanded: Vec<(String, Vec<String>)> = Vec::new();
+    let mut previous_stage: Vec<String> = Vec::new();
+    let mut current_stage: (Option<usize>, Vec<String>) = (None, Vec::new());
+
+    for (order, name, job) in jobs {
+        if current_stage.0 != Some(order) {
+            if !current_stage.1.is_empty() {
+                previous_stage = std::mem::take(&mut current_stage.1);
+            }
+            current_stage.0 = Some(order);
+        }
+
+        let combinations = gitlab_matrix(job);
+        let names = stage_names(&name, &combinations);
+        for (combination, stage_name) in combinations.iter().zip(&names) {
+            let mut stage = gitlab_stage(stage_name, job, combination, &variables, document);
+            stage.needs = match job.get("needs") {
+                Some(needs) => {
+                    let needs: Vec<String> = match needs.as_sequence() {
+                        Some(needs) => needs
+                            .iter()
+                            .filter_map(|need| {
+                                scalar(need).or_else(|| need.get("job").and_then(scalar))
+                            })
+                            .collect(),
+                        None => Vec::new(),
+                    };
+                    expand_needs(&needs, &expanded, &mut stage.todos)
+                }
+                None => previous_stage.clone(),
+            };
+            pipeline.stages.push(stage);
+        }
+        current_stage.1.extend(names.iter().cloned());
+        expanded.push((name, names));
+    }
+
+    Ok(pipeline)
+}
+
+/// A setting every job inherits, from `default` or the top level
+fn gitlab_global<'a>(document: &'a Value, key: &str) -> Option<&'a Value> {
+    document
+        .get("default")
+        .and_then(|default| default.get(key))
+        .or_else(|| document.get(key))
+}
+
+/// Combinations of a job's `parallel.matrix`, a single empty one without
+fn gitlab_matrix(job: &Value) -> Vec<Combination> {
+    let Some(rows) = job
+        .get("parallel")
+        .and_then(|parallel| parallel.get("matrix"))
+        .and_then(Value::as_sequence)
+    else {
+        return vec![Vec::new()];
+    };
+
+    let combinations: Vec<Combination> = rows
+        .iter()
+        .filter_map(Value::as_mapping)
+        .flat_map(|row| {
+            let axes: Vec<(String, Vec<String>)> = row
+                .iter()
+                .filter_map(|(key, values)| Some((scalar(key)?, strings(Some(values)))))
+                .collect();
+            cartesian(&axes)
+        })
+        .collect();
+
+    if combinations.is_empty() {
+        vec![Vec::new()]
+    } else {
+        combinations
+    }
+}
+
+fn gitlab_stage(
+    name: &str,
+    job: &Value,
+    combination: &Combination,
+    variables: &[(String, String)],
+    document: &Value,
+) -> Stage {
+    let mut todos = Vec::new();
+    let inherited = |key: &str| job.get(key).or_else(|| gitlab_global(document, key));
+
+    let container =
+        inherited("image").and_then(|image| image.get("name").or(Some(image)).and_then(scalar));
+
+    let mut env: Vec<(String, EnvValue)> = Vec::new();
+    for (key, value) in variables
+        .iter()
+        .cloned()
+        .chain(env_pairs(job.get("variables")))
+        .chain(combination.iter().cloned())
+    {
+        set_env(&mut env, key, EnvValue::literal(value));
+    }
+
+    let when = match job.get("when").and_then(scalar).as_deref() {
+        Some("always") => Some("always"),
+        Some("on_failure") => Some("on_failure"),
+        Some("on_success") | None => None,
+        Some(other) => {
+            todos.push(format!("the job ran `when: {}`", other));
+            None
+        }
+    };
+    for (key, what) in [
+        ("rules", "rules"),
+        ("only", "only/except filters"),
+        ("except", "only/except filters"),
+        ("services", "services"),
+        ("cache", "caches"),
+        ("artifacts", "artifacts"),
+        ("extends", "extended jobs"),
+        (
+            "environment",
+            "the deployment environment (see `deploys_to`)",
+        ),
+        ("trigger", "downstream pipelines"),
+        ("tags", "runner tags (see `runner`)"),
+    ] {
+        let message = format!("{} are not converted", what);
+        if job.get(key).is_some() && !todos.contains(&message) {
+            todos.push(message);
+        }
+    }
+
+    let timeout = job
+        .get("timeout")
+        .and_then(scalar)
+        .and_then(|t| parse_gitlab_timeout(&t));
+    let step = |label: &str, lines: Option<&Value>, todos: &mut Vec<String>| -> Option<Step> {
+        let lines = strings(lines);
+        if lines.is_empty() {
+            return None;
+        }
+        let script = lines.join("\n");
+        if script.contains("$CI_") || script.contains("${CI_") {
+            todos.push("GitLab predefined variables (CI_*) are not set by Rivet".to_string());
+        }
+        let mut step = Step::new(label, Some(script));
+        step.env = env.clone();
+        step.timeout = timeout;
+        Some(step)
+    };
+
+    let mut steps = Vec::new();
+    steps.extend(step(
+        "before_script",
+        inherited("before_script"),
+        &mut todos,
+    ));
+    steps.extend(step("script", job.get("script"), &mut todos));
+    let after: Vec<Step> = step("after_script", inherited("after_script"), &mut todos)
+        .into_iter()
+        .collect();
+    todos.dedup();
+
+    Stage {
+        name: name.to_string(),
+        container,
+        needs: Vec::new(),
+        allow_failure: job.get("allow_failure").and_then(Value::as_bool) == Some(true)
+            || job.get("allow_failure").is_some_and(Value::is_mapping),
+        when,
+        todos,
+        steps,
+        after,
+    }
+}
+
+/// Seconds of a GitLab timeout such as `1h 30m` or `45 minutes`
+fn parse_gitlab_timeout(timeout: &str) -> Option<u64> {
+    let mut seconds = 0;
+    let mut number = String::new();
+    let mut unit = String::new();
+    let mut flush = |number: &mut String, unit: &mut String| -> Option<()> {
+        if number.is_empty() {
+            return (unit.is_empty()).then_some(());
+        }
+        let value: u64 = number.parse().ok()?;
+        seconds += value
+            * match unit.as_str() {
+                "s" | "sec" | "secs" | "second" | "seconds" => 1,
+                "m" | "min" | "mins" | "minute" | "minutes" | "" => 60,
+                "h" | "hr" | "hrs" | "hour" | "hours" => 3600,
+                "d" | "day" | "days" => 86400,
+                _ => return None,
+            };
+        number.clear();
+        unit.clear();
+        Some(())
+    };
+    for c in timeout.chars() {
+        if c.is_ascii_digit() {
+            if !unit.is_empty() {
+                flush(&mut number, &mut unit)?;
+            }
+            number.push(c);
+        } else if c.is_alphabetic() {
+            unit.push(c);
+        }
+    }
+    flush(&mut number, &mut unit)?;
+    (seconds > 0).then_some(seconds)
+}
+
+// =============================================================================
+// Shared Helpers
+// =============================================================================
+
+/// String form of a scalar YAML value
+fn scalar(value: &Value) -> Option<String> {
+    match value {
+        Value::String(s) => Some(s.clone()),
+        Value::Number(n) => Some(n.to_string()),
+        Value::Bool(b) => Some(b.to_string()),
+        _ => None,
+    }
+}
+
+/// A scalar or a sequence of scalars, as strings
+fn strings(value: Option<&Value>) -> Vec<String> {
+    match value {
+        Some(Value::Sequence(items)) => items.iter().filter_map(scalar).collect(),
+        Some(value) => scalar(value).into_iter().collect(),
+        None => Vec::new(),
+    }
+}
+
+/// Scalar entries of a mapping, in order
+fn env_pairs(value: Option<&Value>) -> Vec<(String, String)> {
+    value
+        .and_then(Value::as_mapping)
+        .map(Mapping::iter)
+        .into_iter()
+        .flatten()
+        .filter_map(|(key, value)| {
+            // GitLab variables may be { value, description }
+            let value = value.get("value").unwrap_or(value);
+            Some((scalar(key)?, scalar(value)?))
+        })
+        .collect()
+}
+
+/// Sets a variable, replacing an earlier value
+fn set_env(env: &mut Vec<(String, EnvValue)>, key: String, value: EnvValue) {
+    match env.iter_mut().find(|(k, _)| *k == key) {
+        Some(entry) => entry.1 = value,
+        None => env.push((key, value)),
+    }
+}
+
+/// Environment variable name for an input name
+fn env_name(name: &str) -> String {
+    name.chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() {
+                c.to_ascii_uppercase()
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+/// Every combination of the values of the axes, in order
+fn cartesian(axes: &[(String, Vec<String>)]) -> Vec<Combination> {
+    let mut combinations: Vec<Combination> = vec![Vec::new()];
+    for (key, values) in axes {
+        combinations = combinations
+            .into_iter()
+            .flat_map(|combination| {
+                values.iter().map(move |value| {
+                    let mut combination = combination.clone();
+                    combination.push((key.clone(), value.clone()));
+                    combination
+                })
+            })
+            .collect();
+    }
+    if axes.is_empty() {
+        Vec::new()
+    } else {
+        combinations
+    }
+}
+
+fn matches_all(combination: &Combination, pattern: &Combination) -> bool {
+    pattern
+        .iter()
+        .all(|(key, value)| combination.iter().any(|(k, v)| k == key && v == value))
+}
+
+/// Stage name of each combination of a job, e.g. `test (stable, linux)`
+fn stage_names(job: &str, combinations: &[Combination]) -> Vec<String> {
+    combinations
+        .iter()
+        .map(|combination| {
+            if combination.is_empty() {
+                job.to_string()
+            } else {
+                let values: Vec<&str> = combination.iter().map(|(_, v)| v.as_str()).collect();
+                format!("{} ({})", job, values.join(", "))
+            }
+        })
+        .collect()
+}
+
+/// Stage names of the jobs a job needs, all combinations of matrix jobs
+fn expand_needs(
+    needs: &[String],
+    expanded: &[(String, Vec<String>)],
+    todos: &mut Vec<String>,
+) -> Vec<String> {
+    let mut stages = Vec::new();
+    for need in needs {
+        match expanded.iter().find(|(job, _)| job == need) {
+            Some((_, names)) => stages.extend(names.iter().cloned()),
+            None => todos.push(format!(
+                "needs job '{}', which is declared later or missing",
+                need
+            )),
+        }
+    }
+    stages
+}
+
+// =============================================================================
+// Lua Rendering
+// =============================================================================
+
+/// Runs each step's shell script and fails the stage when one fails
+const SH_HELPER: &str = r#"-- Runs a shell script, failing the stage when it fails
+local function sh(step, script, options)
+    options = options or {}
+    options.cmd = options.shell or "sh"
+    options.shell = nil
+    options.args = { "-e", "-c", script }
+    local result = process.run(options)
+    if result.exit_code ~= 0 then
+        error(string.format("Step '%s' failed with exit code %d", step, result.exit_code))
+    end
+end
+"#;
+
+fn render(pipeline: &Pipeline, origin: &str, system: &str) -> String {
+    let mut out = String::new();
+    out.push_str(&format!(
+        "-- Converted from {} ({}) by `rivet pipeline import`.\n",
+        origin, system
+    ));
+    out.push_str("-- Review the TODO comments, then run `rivet pipeline check` on this file.\n\n");
+    out.push_str(SH_HELPER);
+    out.push_str("\nreturn pipeline.define({\n");
+    out.push_str(&format!("    name = {},\n", lua_string(&pipeline.name)));
+    for todo in &pipeline.todos {
+        out.push_str(&format!("    -- TODO: {}\n", todo));
+    }
+    if pipeline.inputs.iter().any(|input| input.kind == "secret") {
+        out.push_str("    -- TODO: CI secrets became secret inputs, supply them at launch\n");
+    }
+
+    if !pipeline.inputs.is_empty() {
+        out.push_str("    inputs = {\n");
+        for input in &pipeline.inputs {
+            let mut fields = vec![format!("type = {}", lua_string(input.kind))];
+            if let Some(description) = &input.description {
+                fields.push(format!("description = {}", lua_string(description)));
+            }
+            if !input.required {
+                fields.push("required = false".to_string());
+            }
+            if let Some(default) = &input.default {
+                fields.push(format!("default = {}", lua_literal(input.kind, default)));
+            }
+            if !input.options.is_empty() {
+                let options: Vec<String> = input.options.iter().map(|o| lua_string(o)).collect();
+                fields.push(format!("options = {{ {} }}", options.join(", ")));
+            }
+            out.push_str(&format!(
+                "        [{}] = {{ {} }},\n",
+                lua_string(&input.name),
+                fields.join(", ")
+            ));
+        }
+        out.push_str("    },\n");
+    }
+
+    out.push_str("    stages = {\n");
+    for stage in &pipeline.stages {
+        render_stage(&mut out, stage);
+    }
+    out.push_str("    }\n})\n");
+    out
+}
+
+fn render_stage(out: &mut String, stage: &Stage) {
+    out.push_str("        {\n");
+    out.push_str(&format!(
+        "            name = {},\n",
+        lua_string(&stage.name)
+    ));
+    for todo in &stage.todos {
+        out.push_str(&format!("            -- TODO: {}\n", todo));
+    }
+    if let Some(container) = &stage.container {
+        out.push_str(&format!(
+            "            container = {},\n",
+            lua_string(container)
+        ));
+    }
+    let needs: Vec<String> = stage.needs.iter().map(|n| lua_string(n)).collect();
+    if needs.is_empty() {
+        out.push_str("            needs = {},\n");
+    } else {
+        out.push_str(&format!(
+            "            needs = {{ {} }},\n",
+            needs.join(", ")
+        ));
+    }
+    if stage.allow_failure {
+        out.push_str("            allow_failure = true,\n");
+    }
+    if let Some(when) = stage.when {
+        out.push_str(&format!("            when = {},\n", lua_string(when)));
+    }
+
+    out.push_str("            script = function()\n");
+    if stage.after.is_empty() {
+        render_steps(out, &stage.steps, 4);
+    } else {
+        out.push_str("                local ok, err = pcall(function()\n");
+        render_steps(out, &stage.steps, 5);
+        out.push_str("                end)\n");
+        render_steps(out, &stage.after, 4);
+        out.push_str("                if not ok then\n");
+        out.push_str("                    error(err, 0)\n");
+        out.push_str("                end\n");
+    }
+    out.push_str("            end\n");
+    out.push_str("        },\n");
+}
+
+fn render_steps(out: &mut String, steps: &[Step], depth: usize) {
+    let indent = "    ".repeat(depth);
+    for step in steps {
+        out.push_str(&format!("{}-- {}\n", indent, step.name));
+        for todo in &step.todos {
+            out.push_str(&format!("{}-- TODO: {}\n", indent, todo));
+        }
+        let Some(script) = &step.script else {
+            continue;
+        };
+
+        let mut options = Vec::new();
+        if let Some(shell) = &step.shell {
+            options.push(format!("shell = {}", lua_string(shell)));
+        }
+        if !step.env.is_empty() {
+            let env: Vec<String> = step
+                .env
+                .iter()
+                .map(|(key, value)| format!("[{}] = {}", lua_string(key), value.to_lua()))
+                .collect();
+            options.push(format!("env = {{ {} }}", env.join(", ")));
+        }
+        if let Some(cwd) = &step.cwd {
+            options.push(format!("cwd = {}", cwd.to_lua()));
+        }
+        if let Some(timeout) = step.timeout {
+            options.push(format!("timeout = {}", timeout));
+        }
+
+        let script = if script.contains('\n') {
+            lua_long_string(script)
+        } else {
+            lua_string(script)
+        };
+        if options.is_empty() {
+            out.push_str(&format!(
+                "{}sh({}, {})\n",
+                indent,
+                lua_string(&step.name),
+                script
+            ));
+        } else {
+            out.push_str(&format!(
+                "{}sh({}, {}, {{ {} }})\n",
+                indent,
+                lua_string(&step.name),
+                script,
+                options.join(", ")
+            ));
+        }
+    }
+}
+
+/// Literal of an input default of the given type
+fn lua_literal(kind: &str, value: &str) -> String {
+    match kind {
+        "bool" if value == "true" || value == "false" => value.to_string(),
+        "number" if value.parse::<f64>().is_ok() => value.to_string(),
+        _ => lua_string(value),
+    }
+}
+
+/// Quoted Lua string literal of `s`
+fn lua_string(s: &str) -> String {
+    let mut quoted = String::with_capacity(s.len() + 2);
+    quoted.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            c if c.is_control() => quoted.push_str(&format!("\\u{{{:x}}}", c as u32)),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+/// Long bracket literal of a multi-line script, `[[...]]` with as many `=`
+/// as needed not to be closed by the script itself
+fn lua_long_string(s: &str) -> String {
+    let mut level = 0;
+    while s.contains(&format!("]{}]", "=".repeat(level))) {
+        level += 1;
+    }
+    let equals = "=".repeat(level);
+    // Lua skips a newline right after the opening bracket
+    format!("[{}[\n{}]{}]", equals, s, equals)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_github_workflow() {
+        let workflow = r#"
+name: CI
+on:
+  push:
+  workflow_dispatch:
+    inputs:
+      level:
+        type: choice
+        options: [debug, release]
+        default: debug
+env:
+  CARGO_TERM_COLOR: always
+jobs:
+  lint:
+    runs-on: ubuntu-22.04
+    steps:
+      - uses: actions/checkout@v4
+      - run: cargo clippy
+  test:
+    needs: lint
+    runs-on: ubuntu-latest
+    container: rust:1.80
+    strategy:
+      matrix:
+        toolchain: [stable, beta]
+        include:
+          - toolchain: nightly
+    steps:
+      - name: Test
+        run: |
+          rustup default ${{ matrix.toolchain }}
+          cargo test --profile ${{ inputs.level }}
+        env:
+          TOKEN: ${{ secrets.DEPLOY_TOKEN }}
+"#;
+        let conversion = convert(GitProvider::Github, workflow, "ci.yml", "ci").unwrap();
+        let script = &conversion.script;
+
+        assert_eq!(conversion.stages, 4);
+        assert!(script.contains(r#"name = "CI","#));
+        assert!(script.contains(
+            r#"["level"] = { type = "enum", required = false, default = "debug", options = { "debug", "release" } },"#
+        ));
+        assert!(script.contains(r#"["DEPLOY_TOKEN"] = { type = "secret""#));
+        assert!(script.contains(r#"container = "ubuntu:22.04","#));
+        assert!(script.contains("-- TODO: action actions/checkout@v4: check out the repository"));
+        assert!(script.contains(
+            r#"sh("cargo clippy", "cargo clippy", { env = { ["CARGO_TERM_COLOR"] = "always" } })"#
+        ));
+        assert!(script.contains(r#"name = "test (beta)","#));
+        assert!(script.contains(r#"name = "test (nightly)","#));
+        assert!(script.contains(r#"needs = { "lint" },"#));
+        assert!(script.contains("rustup default beta\ncargo test --profile ${INPUT_LEVEL}\n"));
+        assert!(script.contains(r#"["INPUT_LEVEL"] = input.get("level", "")"#));
+        assert!(script.contains(r#"["TOKEN"] = input.get("DEPLOY_TOKEN", "")"#));
+        assert!(script.contains("-- TODO: triggers (push) are not converted"));
+    }
+
+    #[test]
+    fn test_gitlab_config() {
+        let config = r#"
+image: alpine:3.20
+stages: [build, test]
+variables:
+  APP: api
+build:
+  stage: build
+  script:
+    - make $APP
+test:
+  stage: test
+  image: { name: "rust:1.80" }
+  parallel:
+    matrix:
+      - RUST: [stable, beta]
+  before_script: [cargo fetch]
+  script: [cargo test]
+  after_script: [echo done]
+  allow_failure: true
+  only: [main]
+lint:
+  stage: test
+  needs: []
+  script: echo lint
+"#;
+        let conversion = convert(GitProvider::Gitlab, config, ".gitlab-ci.yml", "api").unwrap();
+        let script = &conversion.script;
+
+        assert_eq!(conversion.stages, 4);
+        assert!(script.contains(r#"name = "api","#));
+        assert!(script.contains(r#"container = "alpine:3.20","#));
+        assert!(script.contains(r#"sh("script", "make $APP", { env = { ["APP"] = "api" } })"#));
+        assert!(script.contains(r#"name = "test (stable)","#));
+        assert!(script.contains(r#"container = "rust:1.80","#));
+        assert!(script.contains(r#"needs = { "build" },"#));
+        assert!(script.contains(r#"["RUST"] = "beta""#));
+        assert!(script.contains("local ok, err = pcall(function()"));
+        assert!(script.contains("allow_failure = true,"));
+        assert!(script.contains("-- TODO: only/except filters are not converted"));
+        assert!(script.contains(
+            "name = \"lint\",\n            container = \"alpine:3.20\",\n            needs = {},"
+        ));
+    }
+
+    #[test]
+    fn test_gitlab_timeouts() {
+        assert_eq!(parse_gitlab_timeout("1h 30m"), Some(5400));
+        assert_eq!(parse_gitlab_timeout("45 minutes"), Some(2700));
+        assert_eq!(parse_gitlab_timeout("3 fortnights"), None);
+    }
+
+    #[test]
+    fn test_long_strings() {
+        assert_eq!(lua_long_string("a\nb\n"), "[[\na\nb\n]]");
+        assert_eq!(lua_long_string("x[1]]\n"), "[=[\nx[1]]\n]=]");
+    }
+}
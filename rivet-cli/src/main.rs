@@ -5,12 +5,17 @@
 mod commands;
 mod config;
 mod id_resolver;
+mod import;
+mod output;
+mod picker;
 mod types;
 
 use anyhow::Result;
 use clap::Parser;
 use commands::{Commands, handle_command};
 use config::Config;
+use rivet_client::ClientError;
+use std::time::Duration;
 
 #[derive(Parser)]
 #[command(name = "rivet")]
@@ -24,6 +29,44 @@ struct Cli {
     )]
     orchestrator_url: String,
 
+    /// API token for secured orchestrators
+    #[arg(long, env = "RIVET_TOKEN", hide_env_values = true)]
+    token: Option<String>,
+
+    /// Extra header sent with every request, as Name=value (repeatable)
+    #[arg(short = 'H', long = "header", value_parser = commands::parse_key_val)]
+    headers: Vec<(String, String)>,
+
+    /// Project (name or ID) to scope pipelines, jobs and secrets to
+    ///
+    /// Without a project, listings span all projects and new pipelines and
+    /// secrets are created in the default project.
+    #[arg(long, env = "RIVET_PROJECT", global = true)]
+    project: Option<String>,
+
+    /// Seconds before a request to the orchestrator fails (0 waits forever);
+    /// event streams and artifact downloads only wait this long to connect
+    #[arg(long, env = "RIVET_TIMEOUT", default_value_t = 30, global = true)]
+    timeout: u64,
+
+    /// Times a failed request that is safe to repeat is retried
+    #[arg(long, env = "RIVET_RETRIES", default_value_t = 3, global = true)]
+    retries: u32,
+
+    /// Print only the IDs of listed or created resources
+    #[arg(short, long, global = true)]
+    quiet: bool,
+
+    /// Plain output for scripts and CI logs: no colours or decorations,
+    /// one tab-separated record per line (also set by RIVET_CI=1)
+    #[arg(
+        long,
+        env = "RIVET_CI",
+        global = true,
+        value_parser = clap::builder::FalseyValueParser::new()
+    )]
+    no_color: bool,
+
     #[command(subcommand)]
     command: Commands,
 }
@@ -31,10 +74,35 @@ struct Cli {
 #[tokio::main]
 async fn main() -> Result<()> {
     let cli = Cli::parse();
+    output::init(output::OutputMode::from_flags(cli.quiet, cli.no_color));
 
     let config = Config {
         orchestrator_url: cli.orchestrator_url,
+        token: cli.token,
+        headers: cli.headers,
+        project: cli.project,
+        timeout: (cli.timeout > 0).then(|| Duration::from_secs(cli.timeout)),
+        retries: cli.retries,
     };
 
-    handle_command(cli.command, &config).await
+    let result = handle_command(cli.command, &config).await;
+
+    if let Err(e) = &result {
+        match e.downcast_ref::<ClientError>() {
+            Some(ClientError::Unauthorized(_)) => {
+                eprintln!("hint: pass an API token with --token or RIVET_TOKEN");
+            }
+            Some(ClientError::RequestFailed(e)) if e.is_timeout() => {
+                eprintln!(
+                    "hint: the orchestrator did not answer in time; check --orchestrator-url or raise --timeout"
+                );
+            }
+            Some(ClientError::RateLimited { .. }) => {
+                eprintln!("hint: the orchestrator is throttling requests, try again shortly");
+            }
+            _ => {}
+        }
+    }
+
+    result
 }
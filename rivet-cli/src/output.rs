@@ -0,0 +1,89 @@
+//! Output modes
+//!
+//! Commands print coloured, decorated output for people by default. Plain
+//! mode (`--no-color` or `RIVET_CI=1`) is for shell scripts and CI logs: no
+//! colours or progress bars, and listings and created resources printed as
+//! tab-separated records, one per line, whose fields keep their order.
+//! Quiet mode (`--quiet`) prints only the IDs of those records.
+
+use std::io::{ErrorKind, Write};
+use std::sync::OnceLock;
+
+/// How commands print their results
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputMode {
+    /// Coloured and decorated, for terminals
+    Human,
+    /// Uncoloured, one tab-separated record per line
+    Plain,
+    /// Record IDs only
+    Quiet,
+}
+
+impl OutputMode {
+    /// Pick the mode from the global flags
+    ///
+    /// # Arguments
+    /// * `quiet` - `--quiet` was given
+    /// * `plain` - `--no-color` was given or `RIVET_CI` is set
+    pub fn from_flags(quiet: bool, plain: bool) -> Self {
+        if quiet {
+            OutputMode::Quiet
+        } else if plain {
+            OutputMode::Plain
+        } else {
+            OutputMode::Human
+        }
+    }
+}
+
+static MODE: OnceLock<OutputMode> = OnceLock::new();
+
+/// Set the output mode of the process, turning colours off unless human
+pub fn init(mode: OutputMode) {
+    if mode != OutputMode::Human {
+        colored::control::set_override(false);
+    }
+    let _ = MODE.set(mode);
+}
+
+/// The output mode of the process
+pub fn mode() -> OutputMode {
+    MODE.get().copied().unwrap_or(OutputMode::Human)
+}
+
+/// Whether output is read by scripts rather than people
+pub fn is_plain() -> bool {
+    mode() != OutputMode::Human
+}
+
+/// Print a record of a listing or of a created resource
+///
+/// Prints the ID alone in quiet mode, otherwise the ID and the fields
+/// separated by tabs. Tabs and newlines inside fields become spaces, so a
+/// record is always one line. Exits quietly once the reader of a pipe
+/// (e.g. `head`) has gone.
+///
+/// # Arguments
+/// * `id` - Identifier of the resource (UUID, or name when it has none)
+/// * `fields` - Other fields, in the order documented for the command
+pub fn record(id: &str, fields: &[&str]) {
+    let mut line = sanitize(id);
+    if mode() != OutputMode::Quiet {
+        for field in fields {
+            line.push('\t');
+            line.push_str(&sanitize(field));
+        }
+    }
+
+    if let Err(e) = writeln!(std::io::stdout(), "{}", line) {
+        if e.kind() == ErrorKind::BrokenPipe {
+            std::process::exit(0);
+        }
+        panic!("failed printing to stdout: {}", e);
+    }
+}
+
+fn sanitize(field: &str) -> String {
+    field.replace(['\t', '\n', '\r'], " ")
+}
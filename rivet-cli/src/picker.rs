@@ -0,0 +1,197 @@
+//! Interactive picker module
+//!
+//! Lets commands that need a pipeline or a job be run without one, e.g.
+//! `rivet job logs`: in a terminal, the user picks it from a fuzzy-searchable
+//! list fetched from the orchestrator, typing any part of a name, number or
+//! status to narrow it down. Without a terminal the ID stays required.
+//!
+//! The same list settles pipeline names or prefixes matching several
+//! pipelines (see `id_resolver`).
+
+use anyhow::{Context, Result, bail};
+use dialoguer::FuzzySelect;
+use dialoguer::console::Term;
+use dialoguer::theme::{ColorfulTheme, SimpleTheme, Theme};
+use rivet_core::domain::job::Job;
+use rivet_core::domain::pipeline::Pipeline;
+use std::collections::HashMap;
+use std::io::{self, IsTerminal};
+use uuid::Uuid;
+
+use crate::output;
+use rivet_client::OrchestratorApi;
+
+/// Most recent jobs offered by the job picker
+const MAX_PICKED_JOBS: u32 = 200;
+
+/// Rows of the list shown at once
+const VISIBLE_ROWS: usize = 15;
+
+/// Whether the user can be asked to pick, i.e. the CLI runs in a terminal
+pub fn can_pick() -> bool {
+    io::stdin().is_terminal() && io::stderr().is_terminal()
+}
+
+/// Use the pipeline given on the command line, or let the user pick one
+///
+/// # Arguments
+/// * `client` - The API client to fetch pipelines with
+/// * `id` - The pipeline ID, prefix or name given, if any
+///
+/// # Returns
+/// The given pipeline, or the ID of the picked one
+pub async fn pipeline_or_pick(client: &dyn OrchestratorApi, id: Option<String>) -> Result<String> {
+    match id {
+        Some(id) => Ok(id),
+        None if can_pick() => Ok(pick_pipeline(client).await?.to_string()),
+        None => bail!("A pipeline ID, prefix or name is required when not run in a terminal"),
+    }
+}
+
+/// Use the job given on the command line, or let the user pick one
+///
+/// # Arguments
+/// * `client` - The API client to fetch jobs with
+/// * `id` - The job ID, prefix or `<pipeline>/<number>` given, if any
+///
+/// # Returns
+/// The given job, or the ID of the picked one
+pub async fn job_or_pick(client: &dyn OrchestratorApi, id: Option<String>) -> Result<String> {
+    match id {
+        Some(id) => Ok(id),
+        None if can_pick() => Ok(pick_job(client).await?.to_string()),
+        None => {
+            bail!("A job ID, prefix or <pipeline>/<number> is required when not run in a terminal")
+        }
+    }
+}
+
+/// Let the user pick one of the pipelines, by name
+pub async fn pick_pipeline(client: &dyn OrchestratorApi) -> Result<Uuid> {
+    let mut pipelines = client
+        .list_pipelines()
+        .await
+        .context("Failed to fetch pipelines to pick from")?;
+    if pipelines.is_empty() {
+        bail!("No pipelines to pick from");
+    }
+    pipelines.sort_by(|a, b| a.name.cmp(&b.name));
+
+    let pipelines: Vec<&Pipeline> = pipelines.iter().collect();
+    pick_among_pipelines("Pipeline", &pipelines)
+}
+
+/// Let the user pick one of `pipelines`, listed in the given order
+pub fn pick_among_pipelines(prompt: &str, pipelines: &[&Pipeline]) -> Result<Uuid> {
+    let items: Vec<String> = pipelines.iter().map(|p| describe_pipeline(p)).collect();
+    let index = pick(prompt, "pipeline", &items)?;
+    Ok(pipelines[index].id)
+}
+
+/// Let the user pick one of the most recent jobs
+pub async fn pick_job(client: &dyn OrchestratorApi) -> Result<Uuid> {
+    let jobs = client
+        .list_jobs_page(MAX_PICKED_JOBS, 0)
+        .await
+        .context("Failed to fetch jobs to pick from")?;
+    if jobs.is_empty() {
+        bail!("No jobs to pick from");
+    }
+
+    // Jobs are listed by pipeline name; a failed lookup only costs the names
+    let names: HashMap<Uuid, String> = client
+        .list_pipelines()
+        .await
+        .map(|pipelines| pipelines.into_iter().map(|p| (p.id, p.name)).collect())
+        .unwrap_or_default();
+
+    let items: Vec<String> = jobs.iter().map(|job| describe_job(job, &names)).collect();
+    let index = pick("Job", "job", &items)?;
+    Ok(jobs[index].id)
+}
+
+/// A pipeline as listed in the picker and in ambiguity errors: `<name>  <id>`
+///
+/// The whole ID is shown, as pipelines matching the same prefix may share
+/// their first characters.
+pub fn describe_pipeline(pipeline: &Pipeline) -> String {
+    format!("{}  {}", pipeline.name, pipeline.id)
+}
+
+/// A job as listed in the picker:
+/// `<pipeline>/#<number>  <status>  <requested at>  <short id>`
+fn describe_job(job: &Job, pipeline_names: &HashMap<Uuid, String>) -> String {
+    let pipeline = pipeline_names
+        .get(&job.pipeline_id)
+        .cloned()
+        .unwrap_or_else(|| short_id(job.pipeline_id));
+    format!(
+        "{}/#{}  {:?}  {}  {}",
+        pipeline,
+        job.number,
+        job.status,
+        job.requested_at.format("%Y-%m-%d %H:%M"),
+        short_id(job.id)
+    )
+}
+
+/// First 8 characters of an ID, as shown in listings
+fn short_id(id: Uuid) -> String {
+    id.to_string()[..8].to_string()
+}
+
+/// Show a fuzzy-searchable list of `what` on stderr and return the picked item
+///
+/// The list goes to stderr, so that it does not mix with plain output.
+fn pick(prompt: &str, what: &str, items: &[String]) -> Result<usize> {
+    let colorful = ColorfulTheme::default();
+    let theme: &dyn Theme = if output::is_plain() {
+        &SimpleTheme
+    } else {
+        &colorful
+    };
+
+    FuzzySelect::with_theme(theme)
+        .with_prompt(prompt)
+        .items(items)
+        .default(0)
+        .max_length(VISIBLE_ROWS)
+        .interact_on_opt(&Term::stderr())?
+        .ok_or_else(|| anyhow::anyhow!("No {} picked", what))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rivet_client::MockOrchestrator;
+
+    #[tokio::test]
+    async fn test_describe_job_names_pipeline_and_number() {
+        let mock = MockOrchestrator::new();
+        let pipeline = mock.add_pipeline("deploy", "return {}");
+        let job = mock
+            .launch_job(rivet_core::dto::job::CreateJob {
+                pipeline_id: pipeline.id,
+                parameters: Default::default(),
+                plan: false,
+                commit: None,
+                override_blackout: false,
+                labels: Default::default(),
+            })
+            .await
+            .unwrap();
+
+        let names = HashMap::from([(pipeline.id, pipeline.name.clone())]);
+        let described = describe_job(&job, &names);
+        assert!(
+            described.starts_with("deploy/#1  Queued  "),
+            "{}",
+            described
+        );
+        assert!(described.ends_with(&short_id(job.id)));
+
+        // Pipelines whose name is unknown are shown by their short ID
+        let described = describe_job(&job, &HashMap::new());
+        assert!(described.starts_with(&format!("{}/#1", short_id(pipeline.id))));
+    }
+}
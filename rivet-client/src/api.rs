@@ -0,0 +1,258 @@
+//! Orchestrator API trait
+//!
+//! Abstracts the orchestrator surface used by the CLI and the runner so that
+//! callers can be exercised against `MockOrchestrator` instead of a live server.
+
+use async_trait::async_trait;
+use rivet_core::domain::job::{Job, JobResult, JobStatus};
+use rivet_core::domain::log::LogEntry;
+use rivet_core::domain::pipeline::Pipeline;
+use rivet_core::domain::runner::{Runner, RunnerLoad};
+use rivet_core::dto::job::{CreateJob, JobExecutionInfo, JobLease, StageUpdate};
+use rivet_core::dto::pipeline::{CreatePipeline, DeletePipelineQuery};
+use std::time::Duration;
+use uuid::Uuid;
+
+use crate::OrchestratorClient;
+use crate::error::Result;
+
+/// Operations exposed by a Rivet orchestrator
+///
+/// Implemented by `OrchestratorClient` (HTTP) and `MockOrchestrator` (in-memory).
+/// Code that only needs these operations should take `&dyn OrchestratorApi`
+/// or `Arc<dyn OrchestratorApi>` rather than the concrete HTTP client.
+#[async_trait]
+pub trait OrchestratorApi: Send + Sync {
+    // =============================================================================
+    // Pipelines
+    // =============================================================================
+
+    /// Create a new pipeline
+    async fn create_pipeline(&self, req: CreatePipeline) -> Result<Pipeline>;
+
+    /// List all pipelines
+    async fn list_pipelines(&self) -> Result<Vec<Pipeline>>;
+
+    /// Get a pipeline by ID
+    async fn get_pipeline(&self, pipeline_id: Uuid) -> Result<Pipeline>;
+
+    /// Delete a pipeline, or archive it when jobs reference it
+    ///
+    /// # Returns
+    /// The archived pipeline, or `None` if it was deleted
+    async fn delete_pipeline(
+        &self,
+        pipeline_id: Uuid,
+        query: &DeletePipelineQuery,
+    ) -> Result<Option<Pipeline>>;
+
+    // =============================================================================
+    // Jobs
+    // =============================================================================
+
+    /// Launch a new job from a pipeline
+    async fn launch_job(&self, req: CreateJob) -> Result<Job>;
+
+    /// Get a job by ID
+    async fn get_job(&self, job_id: Uuid) -> Result<Job>;
+
+    /// List all jobs
+    async fn list_all_jobs(&self) -> Result<Vec<Job>>;
+
+    /// List one page of jobs, newest first
+    async fn list_jobs_page(&self, limit: u32, offset: u32) -> Result<Vec<Job>>;
+
+    /// List jobs waiting to be executed
+    async fn list_scheduled_jobs(&self) -> Result<Vec<Job>>;
+
+    /// List jobs for a pipeline
+    async fn list_jobs_by_pipeline(&self, pipeline_id: Uuid) -> Result<Vec<Job>>;
+
+    /// Claim a queued job for execution
+    async fn claim_job(&self, job_id: Uuid, runner_id: &str) -> Result<JobExecutionInfo>;
+
+    /// Wait up to `wait` for the next queued job, claimed for `runner_id`
+    async fn next_job(&self, runner_id: &str, wait: Duration) -> Result<Option<JobExecutionInfo>>;
+
+    /// Update the status of a job
+    async fn update_job_status(&self, job_id: Uuid, status: JobStatus) -> Result<()>;
+
+    /// Renew the lease a runner holds on a running job
+    async fn renew_job_lease(
+        &self,
+        job_id: Uuid,
+        runner_id: &str,
+        ttl: Duration,
+    ) -> Result<JobLease>;
+
+    /// Complete a job with its final result
+    ///
+    /// `claim_token` is the token the job was claimed with, rejecting the
+    /// completion once the job was claimed again.
+    async fn complete_job(
+        &self,
+        job_id: Uuid,
+        claim_token: Option<u64>,
+        result: JobResult,
+    ) -> Result<()>;
+
+    /// Report the progress of a job's stages, in order
+    async fn send_stage_updates(&self, job_id: Uuid, updates: Vec<StageUpdate>) -> Result<()>;
+
+    // =============================================================================
+    // Logs
+    // =============================================================================
+
+    /// Get logs for a job
+    async fn get_job_logs(&self, job_id: Uuid) -> Result<Vec<LogEntry>>;
+
+    /// Append log entries to a job, logged under the claim `claim_token`
+    async fn send_logs(
+        &self,
+        job_id: Uuid,
+        claim_token: Option<u64>,
+        entries: Vec<LogEntry>,
+    ) -> Result<()>;
+
+    /// Wait until the log entries sent for a job are stored
+    ///
+    /// Only needed for logs sent outside of a job run, which `complete_job`
+    /// otherwise waits for. Transports storing each batch as it is sent have
+    /// nothing to wait for.
+    async fn flush_logs(&self, _job_id: Uuid) -> Result<()> {
+        Ok(())
+    }
+
+    // =============================================================================
+    // Runners
+    // =============================================================================
+
+    /// Register a runner
+    async fn register_runner(&self, runner_id: &str) -> Result<Runner>;
+
+    /// Send a heartbeat for a runner, reporting its load if known
+    async fn send_heartbeat(&self, runner_id: &str, load: Option<RunnerLoad>) -> Result<()>;
+
+    /// List all runners
+    async fn list_runners(&self) -> Result<Vec<Runner>>;
+
+    /// Get a runner by ID
+    async fn get_runner(&self, runner_id: &str) -> Result<Runner>;
+
+    /// Delete a runner
+    async fn delete_runner(&self, runner_id: &str) -> Result<()>;
+}
+
+#[async_trait]
+impl OrchestratorApi for OrchestratorClient {
+    async fn create_pipeline(&self, req: CreatePipeline) -> Result<Pipeline> {
+        OrchestratorClient::create_pipeline(self, req).await
+    }
+
+    async fn list_pipelines(&self) -> Result<Vec<Pipeline>> {
+        OrchestratorClient::list_pipelines(self).await
+    }
+
+    async fn get_pipeline(&self, pipeline_id: Uuid) -> Result<Pipeline> {
+        OrchestratorClient::get_pipeline(self, pipeline_id).await
+    }
+
+    async fn delete_pipeline(
+        &self,
+        pipeline_id: Uuid,
+        query: &DeletePipelineQuery,
+    ) -> Result<Option<Pipeline>> {
+        OrchestratorClient::delete_pipeline(self, pipeline_id, query).await
+    }
+
+    async fn launch_job(&self, req: CreateJob) -> Result<Job> {
+        OrchestratorClient::launch_job(self, req).await
+    }
+
+    async fn get_job(&self, job_id: Uuid) -> Result<Job> {
+        OrchestratorClient::get_job(self, job_id).await
+    }
+
+    async fn list_all_jobs(&self) -> Result<Vec<Job>> {
+        OrchestratorClient::list_all_jobs(self).await
+    }
+
+    async fn list_jobs_page(&self, limit: u32, offset: u32) -> Result<Vec<Job>> {
+        OrchestratorClient::list_jobs_page(self, limit, offset).await
+    }
+
+    async fn list_scheduled_jobs(&self) -> Result<Vec<Job>> {
+        OrchestratorClient::list_scheduled_jobs(self).await
+    }
+
+    async fn list_jobs_by_pipeline(&self, pipeline_id: Uuid) -> Result<Vec<Job>> {
+        OrchestratorClient::list_jobs_by_pipeline(self, pipeline_id).await
+    }
+
+    async fn claim_job(&self, job_id: Uuid, runner_id: &str) -> Result<JobExecutionInfo> {
+        OrchestratorClient::claim_job(self, job_id, runner_id).await
+    }
+
+    async fn next_job(&self, runner_id: &str, wait: Duration) -> Result<Option<JobExecutionInfo>> {
+        OrchestratorClient::next_job(self, runner_id, wait).await
+    }
+
+    async fn update_job_status(&self, job_id: Uuid, status: JobStatus) -> Result<()> {
+        OrchestratorClient::update_job_status(self, job_id, status).await
+    }
+
+    async fn renew_job_lease(
+        &self,
+        job_id: Uuid,
+        runner_id: &str,
+        ttl: Duration,
+    ) -> Result<JobLease> {
+        OrchestratorClient::renew_job_lease(self, job_id, runner_id, ttl).await
+    }
+
+    async fn complete_job(
+        &self,
+        job_id: Uuid,
+        claim_token: Option<u64>,
+        result: JobResult,
+    ) -> Result<()> {
+        OrchestratorClient::complete_job(self, job_id, claim_token, result).await
+    }
+
+    async fn send_stage_updates(&self, job_id: Uuid, updates: Vec<StageUpdate>) -> Result<()> {
+        OrchestratorClient::send_stage_updates(self, job_id, updates).await
+    }
+
+    async fn get_job_logs(&self, job_id: Uuid) -> Result<Vec<LogEntry>> {
+        OrchestratorClient::get_job_logs(self, job_id).await
+    }
+
+    async fn send_logs(
+        &self,
+        job_id: Uuid,
+        claim_token: Option<u64>,
+        entries: Vec<LogEntry>,
+    ) -> Result<()> {
+        OrchestratorClient::send_logs(self, job_id, claim_token, entries).await
+    }
+
+    async fn register_runner(&self, runner_id: &str) -> Result<Runner> {
+        OrchestratorClient::register_runner(self, runner_id).await
+    }
+
+    async fn send_heartbeat(&self, runner_id: &str, load: Option<RunnerLoad>) -> Result<()> {
+        OrchestratorClient::send_heartbeat(self, runner_id, load).await
+    }
+
+    async fn list_runners(&self) -> Result<Vec<Runner>> {
+        OrchestratorClient::list_runners(self).await
+    }
+
+    async fn get_runner(&self, runner_id: &str) -> Result<Runner> {
+        OrchestratorClient::get_runner(self, runner_id).await
+    }
+
+    async fn delete_runner(&self, runner_id: &str) -> Result<()> {
+        OrchestratorClient::delete_runner(self, runner_id).await
+    }
+}
@@ -0,0 +1,141 @@
+//! Artifact-related API endpoints
+
+use crate::error::{ClientError, Result};
+use crate::{OrchestratorClient, error_from_response};
+use rivet_core::domain::artifact::Artifact;
+use rivet_core::dto::artifact::{ArtifactGcReport, ArtifactUsage};
+use uuid::Uuid;
+
+/// Header carrying the hex encoded SHA-256 of a downloaded artifact
+const CHECKSUM_HEADER: &str = "x-checksum-sha256";
+
+/// An artifact download in progress
+///
+/// The body is read chunk by chunk so callers can report progress and hash
+/// the content while writing it to disk.
+#[derive(Debug)]
+pub struct ArtifactDownload {
+    response: reqwest::Response,
+    sha256: Option<String>,
+}
+
+impl ArtifactDownload {
+    /// Total size in bytes, if the orchestrator announced it
+    pub fn content_length(&self) -> Option<u64> {
+        self.response.content_length()
+    }
+
+    /// Hex encoded SHA-256 announced by the orchestrator
+    pub fn sha256(&self) -> Option<&str> {
+        self.sha256.as_deref()
+    }
+
+    /// Read the next chunk of the body, `None` once the download is complete
+    pub async fn chunk(&mut self) -> Result<Option<bytes::Bytes>> {
+        Ok(self.response.chunk().await?)
+    }
+}
+
+impl OrchestratorClient {
+    // =============================================================================
+    // Job Artifacts
+    // =============================================================================
+
+    /// List the artifacts of a job
+    ///
+    /// # Arguments
+    /// * `job_id` - The job UUID
+    ///
+    /// # Returns
+    /// Artifact metadata (name, size, checksum)
+    pub async fn list_job_artifacts(&self, job_id: Uuid) -> Result<Vec<Artifact>> {
+        let url = self.project_url(&format!("/jobs/{}/artifacts", job_id));
+        let response = self.send_idempotent(self.client.get(&url)).await?;
+
+        self.handle_response(response).await
+    }
+
+    /// Upload an artifact for a job
+    ///
+    /// # Arguments
+    /// * `job_id` - The job UUID
+    /// * `name` - Artifact name (a plain file name, no path separators)
+    /// * `content` - Artifact content
+    ///
+    /// # Returns
+    /// The stored artifact metadata
+    pub async fn upload_artifact(
+        &self,
+        job_id: Uuid,
+        name: &str,
+        content: Vec<u8>,
+    ) -> Result<Artifact> {
+        let url = self.artifact_url(format!("{}/api/v1", self.base_url), job_id, name)?;
+        let response = self.send(self.client.post(url).body(content)).await?;
+
+        self.handle_response(response).await
+    }
+
+    /// Start downloading an artifact
+    ///
+    /// # Arguments
+    /// * `job_id` - The job UUID
+    /// * `name` - Artifact name
+    ///
+    /// # Returns
+    /// A handle to stream the artifact content
+    pub async fn download_artifact(&self, job_id: Uuid, name: &str) -> Result<ArtifactDownload> {
+        let url = self.artifact_url(self.project_url(""), job_id, name)?;
+        let response = self.send_idempotent(self.client.get(url)).await?;
+
+        let status = response.status();
+        if !status.is_success() {
+            return Err(error_from_response(response).await);
+        }
+
+        let sha256 = response
+            .headers()
+            .get(CHECKSUM_HEADER)
+            .and_then(|v| v.to_str().ok())
+            .map(|v| v.to_string());
+
+        Ok(ArtifactDownload { response, sha256 })
+    }
+
+    /// Count the stored artifacts and their bytes, per pipeline (requires the admin token)
+    pub async fn artifact_usage(&self) -> Result<ArtifactUsage> {
+        let url = format!("{}/api/v1/artifacts/usage", self.base_url);
+        let response = self.send_idempotent(self.client.get(&url)).await?;
+
+        self.handle_response(response).await
+    }
+
+    /// Collect artifacts past their pipeline's retention limits (requires the admin token)
+    ///
+    /// # Arguments
+    /// * `dry_run` - Only report what would be deleted
+    ///
+    /// # Returns
+    /// The expired artifacts and the storage usage before collection
+    pub async fn artifact_gc(&self, dry_run: bool) -> Result<ArtifactGcReport> {
+        let url = format!("{}/api/v1/artifacts/gc", self.base_url);
+        let response = match dry_run {
+            true => self.send_idempotent(self.client.get(&url)).await?,
+            false => self.send(self.client.post(&url)).await?,
+        };
+
+        self.handle_response(response).await
+    }
+
+    /// Build the URL of an artifact below `api_url`, escaping the name as a path segment
+    fn artifact_url(&self, api_url: String, job_id: Uuid, name: &str) -> Result<reqwest::Url> {
+        let mut url = reqwest::Url::parse(&format!("{}/jobs/{}/artifacts", api_url, job_id))
+            .map_err(|e| ClientError::InvalidRequest(format!("Invalid URL: {}", e)))?;
+
+        url.path_segments_mut()
+            .map_err(|_| ClientError::InvalidRequest("Invalid base URL".to_string()))?
+            .push(name);
+
+        Ok(url)
+    }
+}
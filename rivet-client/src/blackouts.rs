@@ -0,0 +1,43 @@
+//! Blackout window API endpoints
+
+use crate::OrchestratorClient;
+use crate::error::Result;
+use rivet_core::domain::blackout::BlackoutWindow;
+use rivet_core::dto::blackout::CreateBlackoutWindow;
+use uuid::Uuid;
+
+impl OrchestratorClient {
+    // =============================================================================
+    // Blackout Windows
+    // =============================================================================
+
+    /// List the blackout windows that have not ended
+    pub async fn list_blackouts(&self) -> Result<Vec<BlackoutWindow>> {
+        let url = self.project_url("/blackouts");
+        let response = self.send_idempotent(self.client.get(&url)).await?;
+
+        self.handle_response(response).await
+    }
+
+    /// Add a blackout window for a pipeline or an environment (admin)
+    ///
+    /// # Arguments
+    /// * `req` - Target, period and reason of the window
+    pub async fn create_blackout(&self, req: &CreateBlackoutWindow) -> Result<BlackoutWindow> {
+        let url = self.project_url("/blackouts");
+        let response = self.send(self.client.post(&url).json(req)).await?;
+
+        self.handle_response(response).await
+    }
+
+    /// Remove a blackout window (admin)
+    ///
+    /// # Arguments
+    /// * `id` - Blackout window ID
+    pub async fn delete_blackout(&self, id: Uuid) -> Result<()> {
+        let url = self.project_url(&format!("/blackouts/{}", id));
+        let response = self.send(self.client.delete(&url)).await?;
+
+        self.handle_empty_response(response).await
+    }
+}
@@ -0,0 +1,224 @@
+//! Builder for `OrchestratorClient`
+//!
+//! Configures authentication, extra headers (tenant, tracing, ...), TLS
+//! client certificates, timeouts and the retry policy before creating the client.
+
+use std::time::Duration;
+
+use reqwest::header::{AUTHORIZATION, HeaderMap, HeaderName, HeaderValue};
+use rivet_core::dto::runner::RUNNER_CREDENTIAL_HEADER;
+
+use crate::error::{ClientError, Result};
+use crate::{OrchestratorClient, RetryPolicy};
+
+/// Builder for `OrchestratorClient`
+///
+/// # Example
+/// ```
+/// use rivet_client::OrchestratorClient;
+///
+/// let client = OrchestratorClient::builder()
+///     .base_url("https://rivet.example.com")
+///     .token("my-api-token")
+///     .header("X-Tenant", "team-a")
+///     .build()
+///     .unwrap();
+/// ```
+#[derive(Default)]
+pub struct ClientBuilder {
+    base_url: Option<String>,
+    token: Option<String>,
+    runner_credential: Option<String>,
+    headers: Vec<(String, String)>,
+    client_identity: Option<(Vec<u8>, Vec<u8>)>,
+    ca_certificates: Vec<Vec<u8>>,
+    timeout: Option<Duration>,
+    connect_timeout: Option<Duration>,
+    retry: Option<RetryPolicy>,
+    project: Option<String>,
+    disable_compression: bool,
+}
+
+impl std::fmt::Debug for ClientBuilder {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ClientBuilder")
+            .field("base_url", &self.base_url)
+            .field("token", &self.token.as_ref().map(|_| "<redacted>"))
+            .field(
+                "runner_credential",
+                &self.runner_credential.as_ref().map(|_| "<redacted>"),
+            )
+            .field("headers", &self.headers)
+            .field(
+                "client_identity",
+                &self.client_identity.as_ref().map(|_| "<redacted>"),
+            )
+            .field("ca_certificates", &self.ca_certificates.len())
+            .field("timeout", &self.timeout)
+            .field("connect_timeout", &self.connect_timeout)
+            .field("retry", &self.retry)
+            .field("project", &self.project)
+            .field("disable_compression", &self.disable_compression)
+            .finish()
+    }
+}
+
+impl ClientBuilder {
+    /// Sets the orchestrator base URL (required)
+    pub fn base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = Some(base_url.into());
+        self
+    }
+
+    /// Authenticates every request with `Authorization: Bearer <token>`
+    pub fn token(mut self, token: impl Into<String>) -> Self {
+        self.token = Some(token.into());
+        self
+    }
+
+    /// Identifies a runner on job calls with the credential it received on registration
+    pub fn runner_credential(mut self, credential: impl Into<String>) -> Self {
+        self.runner_credential = Some(credential.into());
+        self
+    }
+
+    /// Adds a header sent with every request
+    pub fn header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.headers.push((name.into(), value.into()));
+        self
+    }
+
+    /// Presents a client certificate for mutual TLS
+    ///
+    /// # Arguments
+    /// * `cert_pem` - PEM certificate chain, leaf first
+    /// * `key_pem` - PEM private key in PKCS#8 format (`BEGIN PRIVATE KEY`)
+    pub fn client_identity_pem(
+        mut self,
+        cert_pem: impl Into<Vec<u8>>,
+        key_pem: impl Into<Vec<u8>>,
+    ) -> Self {
+        self.client_identity = Some((cert_pem.into(), key_pem.into()));
+        self
+    }
+
+    /// Trusts an additional CA certificate (PEM) when verifying the orchestrator
+    ///
+    /// Use it when the orchestrator certificate is issued by a private CA.
+    pub fn ca_certificate_pem(mut self, ca_pem: impl Into<Vec<u8>>) -> Self {
+        self.ca_certificates.push(ca_pem.into());
+        self
+    }
+
+    /// Sets a timeout for every request
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Sets a timeout for connecting to the orchestrator
+    ///
+    /// Unlike `timeout`, it leaves long responses (event streams, artifact
+    /// downloads) alone once connected, while an unreachable host still
+    /// fails fast. WebSocket handshakes are bounded by it too, or by
+    /// `timeout` when it is not set.
+    pub fn connect_timeout(mut self, timeout: Duration) -> Self {
+        self.connect_timeout = Some(timeout);
+        self
+    }
+
+    /// Sets the retry policy used for idempotent requests
+    pub fn retry_policy(mut self, policy: RetryPolicy) -> Self {
+        self.retry = Some(policy);
+        self
+    }
+
+    /// Scopes pipeline, job and secret requests to a project (name or id)
+    pub fn project(mut self, project: impl Into<String>) -> Self {
+        self.project = Some(project.into());
+        self
+    }
+
+    /// Asks for gzip or brotli compressed responses and decompresses them
+    /// transparently (default: enabled)
+    ///
+    /// Disable it to see responses exactly as the orchestrator sends them.
+    pub fn compression(mut self, enabled: bool) -> Self {
+        self.disable_compression = !enabled;
+        self
+    }
+
+    /// Builds the client
+    ///
+    /// # Errors
+    /// Returns `ClientError::InvalidRequest` if the base URL is missing, a
+    /// header name/value is invalid or a certificate/key cannot be parsed.
+    pub fn build(self) -> Result<OrchestratorClient> {
+        let base_url = self
+            .base_url
+            .ok_or_else(|| ClientError::InvalidRequest("Base URL is required".to_string()))?;
+
+        let mut headers = HeaderMap::new();
+
+        if let Some(token) = self.token {
+            let mut value = HeaderValue::from_str(&format!("Bearer {}", token))
+                .map_err(|_| ClientError::InvalidRequest("Invalid API token".to_string()))?;
+            value.set_sensitive(true);
+            headers.insert(AUTHORIZATION, value);
+        }
+
+        if let Some(credential) = self.runner_credential {
+            let mut value = HeaderValue::from_str(&credential).map_err(|_| {
+                ClientError::InvalidRequest("Invalid runner credential".to_string())
+            })?;
+            value.set_sensitive(true);
+            headers.insert(RUNNER_CREDENTIAL_HEADER, value);
+        }
+
+        for (name, value) in self.headers {
+            let header_name = HeaderName::from_bytes(name.as_bytes()).map_err(|_| {
+                ClientError::InvalidRequest(format!("Invalid header name '{}'", name))
+            })?;
+            let header_value = HeaderValue::from_str(&value).map_err(|_| {
+                ClientError::InvalidRequest(format!("Invalid value for header '{}'", name))
+            })?;
+            headers.append(header_name, header_value);
+        }
+
+        let mut http = reqwest::Client::builder().default_headers(headers.clone());
+
+        if let Some((cert, key)) = self.client_identity {
+            let identity = reqwest::Identity::from_pkcs8_pem(&cert, &key).map_err(|e| {
+                ClientError::InvalidRequest(format!("Invalid client certificate or key: {}", e))
+            })?;
+            http = http.identity(identity);
+        }
+        for ca in self.ca_certificates {
+            let certificate = reqwest::Certificate::from_pem(&ca).map_err(|e| {
+                ClientError::InvalidRequest(format!("Invalid CA certificate: {}", e))
+            })?;
+            http = http.add_root_certificate(certificate);
+        }
+        if let Some(timeout) = self.timeout {
+            http = http.timeout(timeout);
+        }
+        if let Some(timeout) = self.connect_timeout {
+            http = http.connect_timeout(timeout);
+        }
+        if self.disable_compression {
+            http = http.no_gzip().no_brotli();
+        }
+        let http = http.build().map_err(|e| {
+            ClientError::InternalError(format!("Failed to build HTTP client: {}", e))
+        })?;
+
+        let mut client = OrchestratorClient::with_client(base_url, http);
+        client.headers = headers;
+        client.connect_timeout = self.connect_timeout.or(self.timeout);
+        client.project = self.project;
+        Ok(match self.retry {
+            Some(policy) => client.with_retry_policy(policy),
+            None => client,
+        })
+    }
+}
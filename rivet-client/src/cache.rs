@@ -0,0 +1,101 @@
+//! Response cache for conditional GETs
+//!
+//! The orchestrator tags pipeline and job reads with an `ETag`. The client
+//! keeps the last body seen for each URL along with its ETag and sends it
+//! back as `If-None-Match`; a `304 Not Modified` answer is then served from
+//! the cache, so polling an unchanged job costs a few bytes instead of the
+//! whole body. The cache is small and shared by clones of a client.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use bytes::Bytes;
+
+/// Number of responses kept; the least recently used one is evicted first
+const CACHE_CAPACITY: usize = 64;
+
+/// A cached response body and the ETag it was served with
+#[derive(Debug, Clone)]
+pub(crate) struct CachedResponse {
+    pub etag: String,
+    pub body: Bytes,
+}
+
+#[derive(Debug, Default)]
+struct Entries {
+    responses: HashMap<String, (CachedResponse, u64)>,
+    /// Incremented on every access, to find the least recently used entry
+    clock: u64,
+}
+
+/// Cloneable handle to the cached responses, keyed by URL
+#[derive(Debug, Clone, Default)]
+pub(crate) struct ResponseCache {
+    entries: Arc<Mutex<Entries>>,
+}
+
+impl ResponseCache {
+    /// Looks up the response cached for a URL
+    pub fn get(&self, url: &str) -> Option<CachedResponse> {
+        let mut entries = self.entries.lock().unwrap();
+        entries.clock += 1;
+        let clock = entries.clock;
+
+        let (response, used) = entries.responses.get_mut(url)?;
+        *used = clock;
+        Some(response.clone())
+    }
+
+    /// Caches the response for a URL, evicting the least recently used one
+    /// when full
+    pub fn insert(&self, url: String, response: CachedResponse) {
+        let mut entries = self.entries.lock().unwrap();
+        entries.clock += 1;
+        let clock = entries.clock;
+
+        if entries.responses.len() >= CACHE_CAPACITY && !entries.responses.contains_key(&url) {
+            let oldest = entries
+                .responses
+                .iter()
+                .min_by_key(|(_, (_, used))| *used)
+                .map(|(url, _)| url.clone());
+            if let Some(oldest) = oldest {
+                entries.responses.remove(&oldest);
+            }
+        }
+        entries.responses.insert(url, (response, clock));
+    }
+
+    /// Forgets the response cached for a URL
+    pub fn remove(&self, url: &str) {
+        self.entries.lock().unwrap().responses.remove(url);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn response(etag: &str) -> CachedResponse {
+        CachedResponse {
+            etag: etag.to_string(),
+            body: Bytes::from_static(b"{}"),
+        }
+    }
+
+    #[test]
+    fn test_least_recently_used_is_evicted() {
+        let cache = ResponseCache::default();
+        for i in 0..CACHE_CAPACITY {
+            cache.insert(format!("/jobs/{}", i), response("a"));
+        }
+
+        // Touch the oldest entry so the second oldest goes instead
+        assert!(cache.get("/jobs/0").is_some());
+        cache.insert("/jobs/new".to_string(), response("b"));
+
+        assert!(cache.get("/jobs/0").is_some());
+        assert!(cache.get("/jobs/1").is_none());
+        assert_eq!(cache.get("/jobs/new").unwrap().etag, "b");
+    }
+}
@@ -0,0 +1,180 @@
+//! Debug session endpoints
+//!
+//! Users open an interactive shell in the environment of a failed job with
+//! `debug_job`; the runner that ran the job learns about it through
+//! `next_debug_request` and serves it over `attach_debug_session`. Both ends
+//! exchange `DebugFrame`s relayed by the orchestrator.
+
+use futures_util::stream::{SplitSink, SplitStream};
+use futures_util::{SinkExt, StreamExt};
+use rivet_core::dto::debug::DebugRequest;
+use rivet_core::dto::job::NextJobQuery;
+use std::time::Duration;
+use tokio_tungstenite::tungstenite::Message;
+use uuid::Uuid;
+
+use crate::OrchestratorClient;
+use crate::error::{ClientError, Result};
+use crate::events::WebSocket;
+
+/// Extra time allowed on top of the long-poll wait before `next_debug_request` times out
+const NEXT_DEBUG_TIMEOUT_MARGIN: Duration = Duration::from_secs(10);
+
+/// A frame of a debug session
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DebugFrame {
+    /// Terminal bytes: input from the user, output from the shell
+    Data(Vec<u8>),
+    /// Human-readable notice from the runner or the orchestrator
+    Notice(String),
+}
+
+/// Sending half of a debug session
+pub struct DebugSender(SplitSink<WebSocket, Message>);
+
+/// Receiving half of a debug session
+pub struct DebugReceiver(SplitStream<WebSocket>);
+
+impl DebugSender {
+    /// Send a frame to the other end of the session
+    pub async fn send(&mut self, frame: DebugFrame) -> Result<()> {
+        let message = match frame {
+            DebugFrame::Data(data) => Message::Binary(data.into()),
+            DebugFrame::Notice(text) => Message::Text(text.into()),
+        };
+        self.0
+            .send(message)
+            .await
+            .map_err(|e| ClientError::WebSocket(e.to_string()))
+    }
+
+    /// End the session
+    pub async fn close(&mut self) -> Result<()> {
+        self.0
+            .close()
+            .await
+            .map_err(|e| ClientError::WebSocket(e.to_string()))
+    }
+}
+
+impl DebugReceiver {
+    /// Receive the next frame from the other end of the session
+    ///
+    /// # Returns
+    /// The frame, or `None` once the session has ended
+    pub async fn next(&mut self) -> Option<Result<DebugFrame>> {
+        while let Some(message) = self.0.next().await {
+            match message {
+                Ok(Message::Binary(data)) => return Some(Ok(DebugFrame::Data(data.to_vec()))),
+                Ok(Message::Text(text)) => return Some(Ok(DebugFrame::Notice(text.to_string()))),
+                Ok(Message::Close(_)) => return None,
+                Ok(_) => {}
+                Err(e) => return Some(Err(ClientError::WebSocket(e.to_string()))),
+            }
+        }
+        None
+    }
+}
+
+impl OrchestratorClient {
+    /// Open an interactive shell in the environment of a failed job
+    ///
+    /// The runner that ran the job restores the job's container from the
+    /// kept workspace; this requires the runner to keep failed workspaces.
+    ///
+    /// # Arguments
+    /// * `job_id` - The failed job
+    /// * `image` - Container image of the job to restore (the default container when `None`)
+    ///
+    /// # Returns
+    /// Both halves of the session
+    pub async fn debug_job(
+        &self,
+        job_id: Uuid,
+        image: Option<&str>,
+    ) -> Result<(DebugSender, DebugReceiver)> {
+        let mut path = self.project_path(&format!("/jobs/{}/debug", job_id));
+        if let Some(image) = image {
+            path = format!("{}?image={}", path, encode_query_value(image));
+        }
+
+        let socket = self.connect_websocket(&path).await?;
+        let (sink, stream) = socket.split();
+        Ok((DebugSender(sink), DebugReceiver(stream)))
+    }
+
+    /// Long-poll for a debug session this runner is asked to serve
+    ///
+    /// # Arguments
+    /// * `runner_id` - The ID of the runner
+    /// * `wait` - How long the orchestrator may hold the request
+    ///
+    /// # Returns
+    /// The requested session, or `None` if none was requested in time
+    pub async fn next_debug_request(
+        &self,
+        runner_id: &str,
+        wait: Duration,
+    ) -> Result<Option<DebugRequest>> {
+        let url = format!("{}/api/v1/debug/next", self.base_url);
+        let query = NextJobQuery {
+            runner_id: runner_id.to_string(),
+            wait: Some(format!("{}ms", wait.as_millis())),
+        };
+        let request = self
+            .client
+            .get(&url)
+            .query(&query)
+            .timeout(wait + NEXT_DEBUG_TIMEOUT_MARGIN);
+        let response = self.send(request).await?;
+
+        if response.status() == reqwest::StatusCode::NO_CONTENT {
+            return Ok(None);
+        }
+        self.handle_response(response).await.map(Some)
+    }
+
+    /// Attach to a debug session as the runner serving it
+    ///
+    /// # Arguments
+    /// * `session_id` - The session from the `DebugRequest`
+    ///
+    /// # Returns
+    /// Both halves of the session
+    pub async fn attach_debug_session(
+        &self,
+        session_id: Uuid,
+    ) -> Result<(DebugSender, DebugReceiver)> {
+        let socket = self
+            .connect_websocket(&format!("/api/v1/debug/{}/ws", session_id))
+            .await?;
+        let (sink, stream) = socket.split();
+        Ok((DebugSender(sink), DebugReceiver(stream)))
+    }
+}
+
+/// Percent-encodes a query parameter value
+fn encode_query_value(value: &str) -> String {
+    value
+        .bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                (b as char).to_string()
+            }
+            b => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encode_query_value() {
+        assert_eq!(
+            encode_query_value("registry.example.com:5000/app:1.0"),
+            "registry.example.com%3A5000%2Fapp%3A1.0"
+        );
+    }
+}
@@ -0,0 +1,134 @@
+//! Environment-related API endpoints
+
+use crate::OrchestratorClient;
+use crate::error::Result;
+use rivet_core::domain::environment::Deployment;
+use rivet_core::dto::environment::{
+    ApproveJob, DeploymentQuery, EnvironmentLockStatus, EnvironmentSummary, JobApprovals,
+    SetEnvironment,
+};
+use uuid::Uuid;
+
+impl OrchestratorClient {
+    // =============================================================================
+    // Environment Management
+    // =============================================================================
+
+    /// List environments with their current and latest deployments
+    pub async fn list_environments(&self) -> Result<Vec<EnvironmentSummary>> {
+        let url = self.project_url("/environments");
+        let response = self.send_idempotent(self.client.get(&url)).await?;
+
+        self.handle_response(response).await
+    }
+
+    /// Get an environment with its current and latest deployments
+    ///
+    /// # Arguments
+    /// * `name` - Environment name
+    pub async fn get_environment(&self, name: &str) -> Result<EnvironmentSummary> {
+        let url = self.project_url(&format!("/environments/{}", name));
+        let response = self.send_idempotent(self.client.get(&url)).await?;
+
+        self.handle_response(response).await
+    }
+
+    /// Create or change an environment (admin)
+    ///
+    /// # Arguments
+    /// * `name` - Environment name
+    /// * `req` - Description and required approvals
+    pub async fn set_environment(
+        &self,
+        name: &str,
+        req: &SetEnvironment,
+    ) -> Result<EnvironmentSummary> {
+        let url = self.project_url(&format!("/environments/{}", name));
+        let response = self.send(self.client.put(&url).json(req)).await?;
+
+        self.handle_response(response).await
+    }
+
+    /// Delete an environment with its deployment history (admin)
+    ///
+    /// # Arguments
+    /// * `name` - Environment name
+    pub async fn delete_environment(&self, name: &str) -> Result<()> {
+        let url = self.project_url(&format!("/environments/{}", name));
+        let response = self.send(self.client.delete(&url)).await?;
+
+        self.handle_empty_response(response).await
+    }
+
+    /// List the deployments to an environment, newest first
+    ///
+    /// # Arguments
+    /// * `name` - Environment name
+    /// * `limit` - Deployments to list (server default when `None`)
+    pub async fn list_deployments(
+        &self,
+        name: &str,
+        limit: Option<u32>,
+    ) -> Result<Vec<Deployment>> {
+        let url = self.project_url(&format!("/environments/{}/deployments", name));
+        let response = self
+            .send_idempotent(self.client.get(&url).query(&DeploymentQuery { limit }))
+            .await?;
+
+        self.handle_response(response).await
+    }
+
+    /// Job holding the lock of an environment, and the jobs waiting for it
+    ///
+    /// # Arguments
+    /// * `name` - Environment name
+    pub async fn get_environment_lock(&self, name: &str) -> Result<EnvironmentLockStatus> {
+        let url = self.project_url(&format!("/environments/{}/lock", name));
+        let response = self.send_idempotent(self.client.get(&url)).await?;
+
+        self.handle_response(response).await
+    }
+
+    /// Force-release the lock of an environment (admin)
+    ///
+    /// The job holding it keeps running; the jobs it blocked are queued again.
+    ///
+    /// # Arguments
+    /// * `name` - Environment name
+    pub async fn release_environment_lock(&self, name: &str) -> Result<()> {
+        let url = self.project_url(&format!("/environments/{}/lock", name));
+        let response = self.send(self.client.delete(&url)).await?;
+
+        self.handle_empty_response(response).await
+    }
+
+    // =============================================================================
+    // Job Approvals
+    // =============================================================================
+
+    /// Approvals of a job, for each environment it deploys to
+    ///
+    /// # Arguments
+    /// * `job_id` - The job ID
+    pub async fn get_job_approvals(&self, job_id: Uuid) -> Result<Vec<JobApprovals>> {
+        let url = self.project_url(&format!("/jobs/{}/approvals", job_id));
+        let response = self.send_idempotent(self.client.get(&url)).await?;
+
+        self.handle_response(response).await
+    }
+
+    /// Approve a queued job's deployments to an environment (admin)
+    ///
+    /// # Arguments
+    /// * `job_id` - The job ID
+    /// * `req` - Environment, approver and optional comment
+    ///
+    /// # Returns
+    /// The approvals of the job, for each environment it deploys to
+    pub async fn approve_job(&self, job_id: Uuid, req: &ApproveJob) -> Result<Vec<JobApprovals>> {
+        let url = self.project_url(&format!("/jobs/{}/approvals", job_id));
+        let response = self.send(self.client.post(&url).json(req)).await?;
+
+        self.handle_response(response).await
+    }
+}
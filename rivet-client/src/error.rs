@@ -1,5 +1,10 @@
 //! Error types for the Rivet client
 
+use std::time::Duration;
+
+use rivet_core::dto::problem::{Problem, ProblemType};
+use rivet_core::dto::validation::FieldError;
+use serde::Deserialize;
 use thiserror::Error;
 
 /// Result type alias for client operations
@@ -25,10 +30,40 @@ pub enum ClientError {
     #[error("Failed to parse response: {0}")]
     ParseError(String),
 
-    /// Resource not found
+    /// Resource not found (404)
     #[error("Resource not found: {0}")]
     NotFound(String),
 
+    /// Request conflicts with the current state of the resource (409)
+    #[error("Conflict: {0}")]
+    Conflict(String),
+
+    /// Request was rejected by server-side validation (400, 422)
+    #[error("{}", validation_message(.field, .message))]
+    Validation {
+        /// Offending field, when the orchestrator reports one
+        field: Option<String>,
+        /// Validation message
+        message: String,
+        /// Every invalid field, when the orchestrator lists them
+        fields: Vec<FieldError>,
+    },
+
+    /// Missing or invalid credentials (401, 403)
+    #[error("Unauthorized: {0}")]
+    Unauthorized(String),
+
+    /// Too many requests (429)
+    #[error("{}", rate_limited_message(.retry_after))]
+    RateLimited {
+        /// Delay requested by the orchestrator via `Retry-After`
+        retry_after: Option<Duration>,
+    },
+
+    /// WebSocket connection failed or was interrupted
+    #[error("WebSocket error: {0}")]
+    WebSocket(String),
+
     /// Invalid request
     #[error("Invalid request: {0}")]
     InvalidRequest(String),
@@ -47,6 +82,113 @@ impl ClientError {
         }
     }
 
+    /// Build a typed error from a failed response
+    ///
+    /// The orchestrator answers errors with problem details
+    /// (`application/problem+json`), whose `type` picks the error variant
+    /// and whose `detail` becomes the message. Field validation failures
+    /// list every invalid field; a lone field is reported as the offending
+    /// one. Older orchestrators answer `{ "error": "...", "field": "..." }`,
+    /// and a body that is neither is used verbatim as the message.
+    ///
+    /// # Arguments
+    /// * `status` - HTTP status code
+    /// * `body` - Response body
+    /// * `retry_after` - Parsed `Retry-After` header, if any
+    pub fn from_response(status: u16, body: &str, retry_after: Option<Duration>) -> Self {
+        #[derive(Deserialize)]
+        struct LegacyBody {
+            error: String,
+            field: Option<String>,
+        }
+
+        let (problem_type, message, field, fields) =
+            if let Ok(problem) = serde_json::from_str::<Problem>(body) {
+                let message = if problem.detail.is_empty() {
+                    problem.title
+                } else {
+                    problem.detail
+                };
+                let field = match problem.fields.as_slice() {
+                    [only] => Some(only.field.clone()),
+                    _ => None,
+                };
+                (problem.problem_type, message, field, problem.fields)
+            } else if let Ok(legacy) = serde_json::from_str::<LegacyBody>(body) {
+                (ProblemType::Other, legacy.error, legacy.field, Vec::new())
+            } else {
+                (
+                    ProblemType::Other,
+                    body.trim().to_string(),
+                    None,
+                    Vec::new(),
+                )
+            };
+
+        match (problem_type, status) {
+            (ProblemType::BadRequest | ProblemType::InvalidFields, _)
+            | (ProblemType::Other, 400 | 422) => Self::Validation {
+                field,
+                message,
+                fields,
+            },
+            (ProblemType::Unauthorized, _) | (ProblemType::Other, 401 | 403) => {
+                Self::Unauthorized(message)
+            }
+            (ProblemType::NotFound, _) | (ProblemType::Other, 404) => Self::NotFound(message),
+            (ProblemType::Conflict, _) | (ProblemType::Other, 409) => Self::Conflict(message),
+            (ProblemType::RateLimited, _) | (ProblemType::Other, 429) => {
+                Self::RateLimited { retry_after }
+            }
+            _ => Self::api_error(status, message),
+        }
+    }
+
+    /// Append the orchestrator's request ID to the message
+    ///
+    /// Operators find the failed call in the orchestrator logs by this ID,
+    /// so it is shown wherever the error is printed. Errors without a
+    /// message (rate limiting, transport failures) are returned unchanged.
+    ///
+    /// # Arguments
+    /// * `request_id` - Value of the `X-Request-Id` response header
+    pub fn with_request_id(self, request_id: &str) -> Self {
+        let tag = |message: String| format!("{} (request ID: {})", message, request_id);
+        match self {
+            Self::ApiError { status, message } => Self::ApiError {
+                status,
+                message: tag(message),
+            },
+            Self::NotFound(message) => Self::NotFound(tag(message)),
+            Self::Conflict(message) => Self::Conflict(tag(message)),
+            Self::Validation {
+                field,
+                message,
+                fields,
+            } => Self::Validation {
+                field,
+                message: tag(message),
+                fields,
+            },
+            Self::Unauthorized(message) => Self::Unauthorized(tag(message)),
+            other => other,
+        }
+    }
+
+    /// HTTP status code associated with this error, if it came from a response
+    pub fn status(&self) -> Option<u16> {
+        match self {
+            Self::ApiError { status, .. } => Some(*status),
+            Self::NotFound(_) => Some(404),
+            Self::Conflict(_) => Some(409),
+            Self::Validation { .. } => Some(400),
+            Self::Unauthorized(_) => Some(401),
+            Self::RateLimited { .. } => Some(429),
+            Self::RequestFailed(e) => e.status().map(|s| s.as_u16()),
+            _ => None,
+        }
+    }
+
     /// Check if this error is a "not found" error
     pub fn is_not_found(&self) -> bool {
         matches!(self, Self::NotFound(_)) || matches!(self, Self::ApiError { status: 404, .. })
@@ -54,11 +196,121 @@ impl ClientError {
 
     /// Check if this error is a client error (4xx status)
     pub fn is_client_error(&self) -> bool {
-        matches!(self, Self::ApiError { status, .. } if *status >= 400 && *status < 500)
+        matches!(self.status(), Some(status) if (400..500).contains(&status))
     }
 
     /// Check if this error is a server error (5xx status)
     pub fn is_server_error(&self) -> bool {
-        matches!(self, Self::ApiError { status, .. } if *status >= 500)
+        matches!(self.status(), Some(status) if status >= 500)
+    }
+}
+
+fn validation_message(field: &Option<String>, message: &str) -> String {
+    match field {
+        Some(field) => format!("Validation failed for '{}': {}", field, message),
+        None => format!("Validation failed: {}", message),
+    }
+}
+
+fn rate_limited_message(retry_after: &Option<Duration>) -> String {
+    match retry_after {
+        Some(delay) => format!("Rate limited, retry after {}s", delay.as_secs()),
+        None => "Rate limited".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_response_parses_error_body() {
+        let err = ClientError::from_response(
+            400,
+            r#"{"error": "must not be empty", "field": "name"}"#,
+            None,
+        );
+        assert!(matches!(
+            err,
+            ClientError::Validation { field: Some(ref f), ref message, .. }
+                if f == "name" && message == "must not be empty"
+        ));
+
+        let err = ClientError::from_response(404, r#"{"error": "Job x not found"}"#, None);
+        assert!(err.is_not_found());
+        assert_eq!(err.to_string(), "Resource not found: Job x not found");
+    }
+
+    #[test]
+    fn test_from_response_parses_problem_details() {
+        let err = ClientError::from_response(
+            422,
+            r#"{"type": "urn:rivet:problem:invalid-fields", "title": "Invalid request fields",
+                "status": 422, "detail": "Invalid request: script: cannot be empty",
+                "fields": [{"field": "script", "message": "cannot be empty"}]}"#,
+            None,
+        );
+        assert!(matches!(
+            err,
+            ClientError::Validation { field: Some(ref f), ref fields, .. }
+                if f == "script" && fields.len() == 1
+        ));
+
+        // The type wins over the status
+        let err = ClientError::from_response(
+            400,
+            r#"{"type": "urn:rivet:problem:not-found", "title": "Not found", "status": 400,
+                "detail": "Pipeline x not found"}"#,
+            None,
+        );
+        assert!(matches!(err, ClientError::NotFound(ref m) if m == "Pipeline x not found"));
+
+        let err = ClientError::from_response(
+            413,
+            r#"{"type": "urn:rivet:problem:payload-too-large", "title": "Payload too large",
+                "status": 413, "detail": ""}"#,
+            None,
+        );
+        assert!(
+            matches!(err, ClientError::ApiError { status: 413, ref message } if message == "Payload too large")
+        );
+    }
+
+    #[test]
+    fn test_from_response_maps_status() {
+        let err = ClientError::from_response(429, "", Some(Duration::from_secs(3)));
+        assert!(matches!(
+            err,
+            ClientError::RateLimited { retry_after: Some(d) } if d.as_secs() == 3
+        ));
+
+        assert!(matches!(
+            ClientError::from_response(401, "denied", None),
+            ClientError::Unauthorized(ref m) if m == "denied"
+        ));
+        assert!(matches!(
+            ClientError::from_response(409, "taken", None),
+            ClientError::Conflict(_)
+        ));
+
+        let err = ClientError::from_response(503, "plain text", None);
+        assert!(err.is_server_error());
+        assert!(
+            matches!(err, ClientError::ApiError { status: 503, ref message } if message == "plain text")
+        );
+    }
+
+    #[test]
+    fn test_with_request_id_tags_message() {
+        let err = ClientError::from_response(404, r#"{"error": "Job x not found"}"#, None)
+            .with_request_id("8fc69dd5");
+        assert!(err.is_not_found());
+        assert_eq!(
+            err.to_string(),
+            "Resource not found: Job x not found (request ID: 8fc69dd5)"
+        );
+
+        let err = ClientError::from_response(429, "", None).with_request_id("8fc69dd5");
+        assert_eq!(err.to_string(), "Rate limited");
     }
 }
@@ -0,0 +1,162 @@
+//! Event subscription endpoint
+
+use std::pin::Pin;
+
+use futures_util::{Stream, StreamExt};
+use rivet_core::dto::event::Event;
+use rivet_core::dto::version::{API_VERSION, API_VERSION_HEADER};
+use tokio_tungstenite::tungstenite::{self, Message, client::IntoClientRequest};
+
+use crate::OrchestratorClient;
+use crate::error::{ClientError, Result};
+
+/// WebSocket connection to the orchestrator
+pub(crate) type WebSocket =
+    tokio_tungstenite::WebSocketStream<tokio_tungstenite::MaybeTlsStream<tokio::net::TcpStream>>;
+
+/// Stream of orchestrator events, ending when the connection closes
+pub type EventStream = Pin<Box<dyn Stream<Item = Result<Event>> + Send>>;
+
+impl OrchestratorClient {
+    /// Subscribe to job, pipeline and runner events over a WebSocket
+    ///
+    /// Only events published after the subscription are delivered. The
+    /// bearer token and custom headers configured on the client are sent with
+    /// the handshake; client certificates are not used for this connection.
+    ///
+    /// # Returns
+    /// A stream of events
+    ///
+    /// # Example
+    /// ```no_run
+    /// # use rivet_client::OrchestratorClient;
+    /// use futures_util::StreamExt;
+    /// # async fn example() -> anyhow::Result<()> {
+    /// let client = OrchestratorClient::new("http://localhost:8080");
+    /// let mut events = client.subscribe_events().await?;
+    /// while let Some(event) = events.next().await {
+    ///     println!("{:?}", event?);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn subscribe_events(&self) -> Result<EventStream> {
+        let socket = self.connect_websocket("/api/v1/events/ws").await?;
+
+        let events = socket.filter_map(|message| async move {
+            match message {
+                Ok(Message::Text(text)) => {
+                    Some(serde_json::from_str::<Event>(&text).map_err(|e| {
+                        ClientError::ParseError(format!("Failed to parse event: {}", e))
+                    }))
+                }
+                Ok(_) => None,
+                Err(e) => Some(Err(ClientError::WebSocket(e.to_string()))),
+            }
+        });
+
+        Ok(Box::pin(events))
+    }
+
+    /// Open a WebSocket to an API path
+    ///
+    /// The handshake carries the client's bearer token and custom headers;
+    /// an HTTP error response is reported like for any other request. The
+    /// connection fails once the client's connect timeout has elapsed.
+    pub(crate) async fn connect_websocket(&self, path: &str) -> Result<WebSocket> {
+        let url = self.websocket_url(path)?;
+
+        let mut request = url
+            .into_client_request()
+            .map_err(|e| ClientError::InvalidRequest(format!("Invalid WebSocket URL: {}", e)))?;
+        request.headers_mut().extend(self.headers.clone());
+        request
+            .headers_mut()
+            .insert(API_VERSION_HEADER, API_VERSION.into());
+
+        let connect = tokio_tungstenite::connect_async(request);
+        let connected = match self.connect_timeout {
+            Some(timeout) => tokio::time::timeout(timeout, connect).await.map_err(|_| {
+                ClientError::WebSocket(format!(
+                    "Timed out connecting to {} after {:?}",
+                    self.base_url, timeout
+                ))
+            })?,
+            None => connect.await,
+        };
+        let (socket, _) = connected.map_err(|e| match e {
+            tungstenite::Error::Http(response) => {
+                let body = response
+                    .body()
+                    .as_ref()
+                    .map(|b| String::from_utf8_lossy(b).into_owned())
+                    .unwrap_or_default();
+                let error = ClientError::from_response(response.status().as_u16(), &body, None);
+                match response
+                    .headers()
+                    .get(crate::trace::REQUEST_ID_HEADER)
+                    .and_then(|v| v.to_str().ok())
+                {
+                    Some(request_id) => error.with_request_id(request_id),
+                    None => error,
+                }
+            }
+            e => ClientError::WebSocket(e.to_string()),
+        })?;
+
+        Ok(socket)
+    }
+
+    /// Build a `ws://` / `wss://` URL for an API path
+    fn websocket_url(&self, path: &str) -> Result<String> {
+        let url = if let Some(rest) = self.base_url.strip_prefix("https://") {
+            format!("wss://{}", rest)
+        } else if let Some(rest) = self.base_url.strip_prefix("http://") {
+            format!("ws://{}", rest)
+        } else {
+            return Err(ClientError::InvalidRequest(format!(
+                "Unsupported URL scheme in {}",
+                self.base_url
+            )));
+        };
+
+        Ok(format!("{}{}", url, path))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_websocket_url() {
+        let client = OrchestratorClient::new("https://rivet.example.com/");
+        assert_eq!(
+            client.websocket_url("/api/v1/events/ws").unwrap(),
+            "wss://rivet.example.com/api/v1/events/ws"
+        );
+
+        let client = OrchestratorClient::new("http://localhost:8080");
+        assert_eq!(
+            client.websocket_url("/api/v1/events/ws").unwrap(),
+            "ws://localhost:8080/api/v1/events/ws"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_websocket_connect_times_out() {
+        // Accepts connections but never answers the handshake
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let client = OrchestratorClient::builder()
+            .base_url(format!("http://{}", listener.local_addr().unwrap()))
+            .connect_timeout(std::time::Duration::from_millis(100))
+            .build()
+            .unwrap();
+
+        let err = client
+            .connect_websocket("/api/v1/events/ws")
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("Timed out connecting"), "{}", err);
+    }
+}
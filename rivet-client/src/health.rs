@@ -0,0 +1,39 @@
+//! Health check endpoint
+
+use crate::OrchestratorClient;
+use crate::error::{ClientError, Result};
+use rivet_core::dto::health::HealthStatus;
+
+impl OrchestratorClient {
+    /// Query the orchestrator health endpoint
+    ///
+    /// A degraded orchestrator answers with 503 but still sends a report;
+    /// the report is returned in that case too so callers can show details.
+    ///
+    /// # Returns
+    /// The orchestrator health report
+    pub async fn health_check(&self) -> Result<HealthStatus> {
+        let url = format!("{}/api/v1/health", self.base_url);
+        let response = self.send_idempotent(self.client.get(&url)).await?;
+
+        let status = response.status();
+        let retry_after = crate::retry_after(&response);
+        let body = response
+            .text()
+            .await
+            .unwrap_or_else(|_| "Unknown error".to_string());
+
+        match serde_json::from_str::<HealthStatus>(&body) {
+            Ok(health) => Ok(health),
+            Err(_) if !status.is_success() => Err(ClientError::from_response(
+                status.as_u16(),
+                &body,
+                retry_after,
+            )),
+            Err(e) => Err(ClientError::ParseError(format!(
+                "Failed to parse health response: {}",
+                e
+            ))),
+        }
+    }
+}
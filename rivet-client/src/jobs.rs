@@ -1,14 +1,27 @@
 //! Job-related API endpoints
 
 use crate::OrchestratorClient;
-use crate::error::Result;
-use rivet_core::domain::job::{Job, JobResult, JobStatus};
+use crate::error::{ClientError, Result};
+use futures_util::stream::{self, Stream, TryStreamExt};
+use rivet_core::domain::job::{Job, JobResult, JobStatus, StageRun};
 use rivet_core::domain::log::LogEntry;
 use rivet_core::dto::job::{
-    CompleteJobRequest, CreateJob, ExecuteJobRequest, JobExecutionInfo, UpdateStatusRequest,
+    CompleteJobRequest, CreateJob, ExecuteJobRequest, JobExecutionInfo, JobLease, JobListQuery,
+    JobPlan, LaunchBatchResponse, NextJobQuery, RenewLeaseRequest, StageUpdate,
+    UpdateStatusRequest,
 };
+use rivet_core::dto::log::{AddLogsQuery, LogQuery, LogSearchHit, LogSearchQuery};
+use rivet_core::dto::pagination::{MAX_PAGE_SIZE, PageQuery};
+use std::collections::{BTreeMap, HashMap};
+use std::time::Duration;
 use uuid::Uuid;
 
+/// Page size used by `list_jobs_paged` when none is given
+pub const DEFAULT_PAGE_SIZE: u32 = 100;
+
+/// Extra time allowed on top of the long-poll wait before `next_job` times out
+const NEXT_JOB_TIMEOUT_MARGIN: Duration = Duration::from_secs(10);
+
 impl OrchestratorClient {
     // =============================================================================
     // Job Lifecycle
@@ -32,17 +45,58 @@ impl OrchestratorClient {
     /// let job = client.launch_job(CreateJob {
     ///     pipeline_id: Uuid::new_v4(),
     ///     parameters: Default::default(),
+    ///     plan: false,
+    ///     commit: None,
+    ///     override_blackout: false,
+    ///     labels: Default::default(),
     /// }).await?;
     /// # Ok(())
     /// # }
     /// ```
     pub async fn launch_job(&self, req: CreateJob) -> Result<Job> {
-        let url = format!("{}/api/pipeline/launch", self.base_url);
-        let response = self.client.post(&url).json(&req).send().await?;
+        let url = self.project_url("/pipeline/launch");
+        let response = self.send(self.client.post(&url).json(&req)).await?;
 
         self.handle_response(response).await
     }
 
+    /// Plan a job: which stages it would run, without launching it
+    ///
+    /// # Arguments
+    /// * `req` - The job creation request (`plan` is set for you)
+    ///
+    /// # Returns
+    /// The parameters the job would get and the decision for each stage
+    pub async fn plan_job(&self, mut req: CreateJob) -> Result<JobPlan> {
+        req.plan = true;
+        let url = self.project_url("/pipeline/launch");
+        let response = self.send(self.client.post(&url).json(&req)).await?;
+
+        self.handle_response(response).await
+    }
+
+    /// Launch one job per parameter set, all or nothing
+    ///
+    /// # Arguments
+    /// * `pipeline_id` - The pipeline UUID
+    /// * `parameter_sets` - Parameters of each job (at most 100)
+    ///
+    /// # Returns
+    /// The ids of the created jobs, in the order of `parameter_sets`
+    pub async fn launch_job_batch(
+        &self,
+        pipeline_id: Uuid,
+        parameter_sets: &[HashMap<String, serde_json::Value>],
+    ) -> Result<Vec<Uuid>> {
+        let url = self.project_url(&format!("/pipeline/{}/launch-batch", pipeline_id));
+        let response = self
+            .send(self.client.post(&url).json(parameter_sets))
+            .await?;
+
+        let batch: LaunchBatchResponse = self.handle_response(response).await?;
+        Ok(batch.job_ids)
+    }
+
     /// Get a job by ID
     ///
     /// # Arguments
@@ -51,10 +105,8 @@ impl OrchestratorClient {
     /// # Returns
     /// The job details
     pub async fn get_job(&self, job_id: Uuid) -> Result<Job> {
-        let url = format!("{}/api/jobs/{}", self.base_url, job_id);
-        let response = self.client.get(&url).send().await?;
-
-        self.handle_response(response).await
+        let url = self.project_url(&format!("/jobs/{}", job_id));
+        self.get_cached(self.client.get(&url)).await
     }
 
     /// List all jobs
@@ -62,10 +114,114 @@ impl OrchestratorClient {
     /// # Returns
     /// A list of all jobs
     pub async fn list_all_jobs(&self) -> Result<Vec<Job>> {
-        let url = format!("{}/api/jobs", self.base_url);
-        let response = self.client.get(&url).send().await?;
+        let url = self.project_url("/jobs");
+        self.get_cached(self.client.get(&url)).await
+    }
 
-        self.handle_response(response).await
+    /// List one page of jobs, newest first
+    ///
+    /// # Arguments
+    /// * `limit` - Maximum number of jobs to return (1..=500)
+    /// * `offset` - Number of jobs to skip
+    ///
+    /// # Returns
+    /// Up to `limit` jobs; fewer means this is the last page
+    pub async fn list_jobs_page(&self, limit: u32, offset: u32) -> Result<Vec<Job>> {
+        self.list_labeled_jobs_page(&BTreeMap::new(), limit, offset)
+            .await
+    }
+
+    /// List one page of the jobs carrying all the given labels, newest first
+    ///
+    /// # Arguments
+    /// * `labels` - Labels the jobs must carry (all jobs when empty)
+    /// * `limit` - Maximum number of jobs to return (1..=500)
+    /// * `offset` - Number of jobs to skip
+    ///
+    /// # Returns
+    /// Up to `limit` jobs; fewer means this is the last page
+    pub async fn list_labeled_jobs_page(
+        &self,
+        labels: &BTreeMap<String, String>,
+        limit: u32,
+        offset: u32,
+    ) -> Result<Vec<Job>> {
+        let url = self.project_url("/jobs");
+        let page = PageQuery {
+            limit: Some(limit),
+            offset: Some(offset),
+        };
+        let filter = JobListQuery {
+            label: (!labels.is_empty()).then(|| {
+                labels
+                    .iter()
+                    .map(|(key, value)| format!("{}={}", key, value))
+                    .collect::<Vec<_>>()
+                    .join(",")
+            }),
+        };
+        self.get_cached(self.client.get(&url).query(&page).query(&filter))
+            .await
+    }
+
+    /// Stream all jobs, newest first, fetching pages on demand
+    ///
+    /// The next page is only requested once the current one has been consumed.
+    /// Offsets are used, so jobs launched while iterating may shift pages and
+    /// appear twice; deduplicate by id if that matters.
+    ///
+    /// # Arguments
+    /// * `page_size` - Jobs per request (clamped to 1..=500)
+    ///
+    /// # Example
+    /// ```no_run
+    /// # use rivet_client::OrchestratorClient;
+    /// use futures_util::TryStreamExt;
+    /// # async fn example() -> anyhow::Result<()> {
+    /// let client = OrchestratorClient::new("http://localhost:8080");
+    /// let mut jobs = std::pin::pin!(client.list_jobs_paged(100));
+    /// while let Some(job) = jobs.try_next().await? {
+    ///     println!("{}", job.id);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn list_jobs_paged(&self, page_size: u32) -> impl Stream<Item = Result<Job>> + '_ {
+        self.list_labeled_jobs_paged(BTreeMap::new(), page_size)
+    }
+
+    /// Stream the jobs carrying all the given labels, newest first, fetching
+    /// pages on demand
+    ///
+    /// As [`OrchestratorClient::list_jobs_paged`], only jobs carrying every
+    /// label in `labels` are returned.
+    ///
+    /// # Arguments
+    /// * `labels` - Labels the jobs must carry (all jobs when empty)
+    /// * `page_size` - Jobs per request (clamped to 1..=500)
+    pub fn list_labeled_jobs_paged(
+        &self,
+        labels: BTreeMap<String, String>,
+        page_size: u32,
+    ) -> impl Stream<Item = Result<Job>> + '_ {
+        let page_size = page_size.clamp(1, MAX_PAGE_SIZE);
+
+        stream::try_unfold(Some(0u32), move |offset| {
+            let labels = labels.clone();
+            async move {
+                let Some(offset) = offset else {
+                    return Ok(None);
+                };
+
+                let page = self
+                    .list_labeled_jobs_page(&labels, page_size, offset)
+                    .await?;
+                let next = (page.len() as u32 == page_size).then(|| offset + page_size);
+
+                Ok::<_, ClientError>(Some((stream::iter(page.into_iter().map(Ok)), next)))
+            }
+        })
+        .try_flatten()
     }
 
     /// List all scheduled (queued) jobs
@@ -73,8 +229,65 @@ impl OrchestratorClient {
     /// # Returns
     /// A list of scheduled jobs
     pub async fn list_scheduled_jobs(&self) -> Result<Vec<Job>> {
-        let url = format!("{}/api/jobs/scheduled", self.base_url);
-        let response = self.client.get(&url).send().await?;
+        let url = format!("{}/api/v1/jobs/scheduled", self.base_url);
+        let response = self.send_idempotent(self.client.get(&url)).await?;
+
+        self.handle_response(response).await
+    }
+
+    /// List dead-lettered jobs, newest first
+    ///
+    /// # Returns
+    /// Jobs that gave up after repeated failures, with their `dead_letter_reason`
+    pub async fn list_dead_lettered_jobs(&self) -> Result<Vec<Job>> {
+        let url = self.project_url("/jobs/deadletter");
+        let response = self.send_idempotent(self.client.get(&url)).await?;
+
+        self.handle_response(response).await
+    }
+
+    /// Put a dead-lettered job back in the queue
+    ///
+    /// # Arguments
+    /// * `job_id` - The job UUID
+    ///
+    /// # Returns
+    /// The requeued job
+    pub async fn requeue_job(&self, job_id: Uuid) -> Result<Job> {
+        let url = self.project_url(&format!("/jobs/{}/requeue", job_id));
+        let response = self.send(self.client.post(&url)).await?;
+
+        self.handle_response(response).await
+    }
+
+    /// Create a job resuming one that did not succeed
+    ///
+    /// The new job skips the stages that succeeded in the original and
+    /// starts with its artifacts (and its workspace, when it lands on the
+    /// runner that kept it).
+    ///
+    /// # Arguments
+    /// * `job_id` - The job to resume
+    ///
+    /// # Returns
+    /// The created job
+    pub async fn resume_job(&self, job_id: Uuid) -> Result<Job> {
+        let url = self.project_url(&format!("/jobs/{}/resume", job_id));
+        let response = self.send(self.client.post(&url)).await?;
+
+        self.handle_response(response).await
+    }
+
+    /// Get the stages of a job
+    ///
+    /// # Arguments
+    /// * `job_id` - The job UUID
+    ///
+    /// # Returns
+    /// The stages of the job's current attempt, in the order they started
+    pub async fn get_job_stages(&self, job_id: Uuid) -> Result<Vec<StageRun>> {
+        let url = self.project_url(&format!("/jobs/{}/stages", job_id));
+        let response = self.send_idempotent(self.client.get(&url)).await?;
 
         self.handle_response(response).await
     }
@@ -87,10 +300,8 @@ impl OrchestratorClient {
     /// # Returns
     /// A list of jobs for the pipeline
     pub async fn list_jobs_by_pipeline(&self, pipeline_id: Uuid) -> Result<Vec<Job>> {
-        let url = format!("{}/api/jobs/pipeline/{}", self.base_url, pipeline_id);
-        let response = self.client.get(&url).send().await?;
-
-        self.handle_response(response).await
+        let url = self.project_url(&format!("/jobs/pipeline/{}", pipeline_id));
+        self.get_cached(self.client.get(&url)).await
     }
 
     // =============================================================================
@@ -106,43 +317,104 @@ impl OrchestratorClient {
     /// # Returns
     /// Information needed to execute the job
     pub async fn claim_job(&self, job_id: Uuid, runner_id: &str) -> Result<JobExecutionInfo> {
-        let url = format!("{}/api/jobs/execute/{}", self.base_url, job_id);
+        let url = format!("{}/api/v1/jobs/execute/{}", self.base_url, job_id);
         let response = self
-            .client
-            .post(&url)
-            .json(&ExecuteJobRequest {
+            .send(self.client.post(&url).json(&ExecuteJobRequest {
                 runner_id: runner_id.to_string(),
-            })
-            .send()
+            }))
             .await?;
 
         self.handle_response(response).await
     }
 
+    /// Wait for the orchestrator to assign the next queued job (long polling)
+    ///
+    /// The request stays open until a job is assigned or `wait` elapses. The
+    /// job is already claimed for `runner_id` when it is returned. Not retried:
+    /// a lost response leaves the assigned job running until its lease expires.
+    ///
+    /// # Arguments
+    /// * `runner_id` - The ID of the runner asking for work
+    /// * `wait` - How long the orchestrator may hold the request (capped at 60s)
+    ///
+    /// # Returns
+    /// The assigned job, or `None` if no job was queued in time
+    pub async fn next_job(
+        &self,
+        runner_id: &str,
+        wait: Duration,
+    ) -> Result<Option<JobExecutionInfo>> {
+        let url = format!("{}/api/v1/jobs/next", self.base_url);
+        let query = NextJobQuery {
+            runner_id: runner_id.to_string(),
+            wait: Some(format!("{}ms", wait.as_millis())),
+        };
+        let request = self
+            .client
+            .get(&url)
+            .query(&query)
+            .timeout(wait + NEXT_JOB_TIMEOUT_MARGIN);
+        let response = self.send(request).await?;
+
+        if response.status() == reqwest::StatusCode::NO_CONTENT {
+            return Ok(None);
+        }
+        self.handle_response(response).await.map(Some)
+    }
+
     /// Update the status of a job
     ///
     /// # Arguments
     /// * `job_id` - The ID of the job to update
     /// * `status` - The new status
     pub async fn update_job_status(&self, job_id: Uuid, status: JobStatus) -> Result<()> {
-        let url = format!("{}/api/jobs/{}/status", self.base_url, job_id);
+        let url = format!("{}/api/v1/jobs/{}/status", self.base_url, job_id);
         let response = self
-            .client
-            .put(&url)
-            .json(&UpdateStatusRequest { status })
-            .send()
+            .send(self.client.put(&url).json(&UpdateStatusRequest { status }))
             .await?;
 
         self.handle_empty_response(response).await
     }
 
+    /// Renew the lease this runner holds on a running job
+    ///
+    /// The orchestrator requeues running jobs whose lease expires, so a
+    /// runner renews the lease of each job it runs well before `ttl` elapses.
+    ///
+    /// # Arguments
+    /// * `job_id` - The ID of the running job
+    /// * `runner_id` - The ID of the runner executing it
+    /// * `ttl` - How long the lease stays valid (whole seconds)
+    pub async fn renew_job_lease(
+        &self,
+        job_id: Uuid,
+        runner_id: &str,
+        ttl: Duration,
+    ) -> Result<JobLease> {
+        let url = format!("{}/api/v1/jobs/{}/lease", self.base_url, job_id);
+        let response = self
+            .send(self.client.post(&url).json(&RenewLeaseRequest {
+                runner_id: runner_id.to_string(),
+                ttl_seconds: Some(ttl.as_secs().max(1)),
+            }))
+            .await?;
+
+        self.handle_response(response).await
+    }
+
     /// Complete a job with the execution result
     ///
     /// # Arguments
     /// * `job_id` - The ID of the job that completed
+    /// * `claim_token` - Token the job was claimed with, if any
     /// * `result` - The execution result (success/failure)
-    pub async fn complete_job(&self, job_id: Uuid, result: JobResult) -> Result<()> {
-        let url = format!("{}/api/jobs/{}/complete", self.base_url, job_id);
+    pub async fn complete_job(
+        &self,
+        job_id: Uuid,
+        claim_token: Option<u64>,
+        result: JobResult,
+    ) -> Result<()> {
+        let url = format!("{}/api/v1/jobs/{}/complete", self.base_url, job_id);
 
         let status = if result.success {
             JobStatus::Succeeded
@@ -151,13 +423,29 @@ impl OrchestratorClient {
         };
 
         let response = self
-            .client
-            .post(&url)
-            .json(&CompleteJobRequest {
+            .send(self.client.post(&url).json(&CompleteJobRequest {
                 status,
                 result: Some(result),
-            })
-            .send()
+                claim_token,
+            }))
+            .await?;
+
+        self.handle_empty_response(response).await
+    }
+
+    /// Report the progress of a job's stages
+    ///
+    /// # Arguments
+    /// * `job_id` - The ID of the running job
+    /// * `updates` - Stage updates, in the order they happened
+    pub async fn send_stage_updates(&self, job_id: Uuid, updates: Vec<StageUpdate>) -> Result<()> {
+        if updates.is_empty() {
+            return Ok(());
+        }
+
+        let url = format!("{}/api/v1/jobs/{}/stages", self.base_url, job_id);
+        let response = self
+            .send_idempotent(self.client.post(&url).json(&updates))
             .await?;
 
         self.handle_empty_response(response).await
@@ -175,8 +463,66 @@ impl OrchestratorClient {
     /// # Returns
     /// A list of log entries for the job
     pub async fn get_job_logs(&self, job_id: Uuid) -> Result<Vec<LogEntry>> {
-        let url = format!("{}/api/jobs/{}/logs", self.base_url, job_id);
-        let response = self.client.get(&url).send().await?;
+        let url = self.project_url(&format!("/jobs/{}/logs", job_id));
+        let response = self.send_idempotent(self.client.get(&url)).await?;
+
+        self.handle_response(response).await
+    }
+
+    /// Get the log entries of a job matching a query
+    ///
+    /// Used to follow a running job without downloading its whole log on
+    /// every poll (`after`), or to cut a noisy log down (`tail`, `level`).
+    ///
+    /// # Arguments
+    /// * `job_id` - The job UUID
+    /// * `query` - Sequence to start after, tail length and minimum level
+    ///
+    /// # Returns
+    /// The matching log entries, oldest first
+    pub async fn query_job_logs(&self, job_id: Uuid, query: &LogQuery) -> Result<Vec<LogEntry>> {
+        let url = self.project_url(&format!("/jobs/{}/logs", job_id));
+        let response = self
+            .send_idempotent(self.client.get(&url).query(query))
+            .await?;
+
+        self.handle_response(response).await
+    }
+
+    /// Search the log entries of a job
+    ///
+    /// # Arguments
+    /// * `job_id` - The job UUID
+    /// * `query` - Search text, minimum level and maximum number of entries
+    ///
+    /// # Returns
+    /// The matching log entries, oldest first
+    pub async fn search_job_logs(
+        &self,
+        job_id: Uuid,
+        query: &LogSearchQuery,
+    ) -> Result<Vec<LogEntry>> {
+        let url = self.project_url(&format!("/jobs/{}/logs/search", job_id));
+        let response = self
+            .send_idempotent(self.client.get(&url).query(query))
+            .await?;
+
+        self.handle_response(response).await
+    }
+
+    /// Search the log entries of all jobs
+    ///
+    /// # Arguments
+    /// * `query` - Search text, pipeline, minimum level and maximum number
+    ///   of entries
+    ///
+    /// # Returns
+    /// The matching log entries with their job, most recent first
+    pub async fn search_logs(&self, query: &LogSearchQuery) -> Result<Vec<LogSearchHit>> {
+        let url = self.project_url("/logs/search");
+        let response = self
+            .send_idempotent(self.client.get(&url).query(query))
+            .await?;
 
         self.handle_response(response).await
     }
@@ -185,14 +531,23 @@ impl OrchestratorClient {
     ///
     /// # Arguments
     /// * `job_id` - The ID of the job these logs belong to
+    /// * `claim_token` - Token of the claim the entries were logged under, if any
     /// * `entries` - The log entries to send
-    pub async fn send_logs(&self, job_id: Uuid, entries: Vec<LogEntry>) -> Result<()> {
+    pub async fn send_logs(
+        &self,
+        job_id: Uuid,
+        claim_token: Option<u64>,
+        entries: Vec<LogEntry>,
+    ) -> Result<()> {
         if entries.is_empty() {
             return Ok(());
         }
 
-        let url = format!("{}/api/jobs/{}/logs", self.base_url, job_id);
-        let response = self.client.post(&url).json(&entries).send().await?;
+        let url = format!("{}/api/v1/jobs/{}/logs", self.base_url, job_id);
+        let query = AddLogsQuery { claim_token };
+        let response = self
+            .send_idempotent(self.client.post(&url).query(&query).json(&entries))
+            .await?;
 
         self.handle_empty_response(response).await
     }
@@ -5,6 +5,9 @@
 //! This crate provides a unified interface for both CLI and runner components to interact
 //! with the orchestrator, eliminating code duplication and ensuring consistency.
 //!
+//! The core operations are also available through the `OrchestratorApi` trait, implemented
+//! by both `OrchestratorClient` and the in-memory `MockOrchestrator` used in tests.
+//!
 //! # Example
 //!
 //! ```no_run
@@ -25,32 +28,72 @@
 //! }
 //! ```
 
+mod api;
+mod artifacts;
+mod blackouts;
+mod builder;
+mod cache;
+mod debug;
+mod environments;
 pub mod error;
+mod events;
+mod health;
 mod jobs;
+mod mock;
 mod pipelines;
+mod projects;
+mod retry;
 mod runners;
+mod secrets;
+mod trace;
 
 // Re-export commonly used types
+pub use api::OrchestratorApi;
+pub use artifacts::ArtifactDownload;
+pub use builder::ClientBuilder;
+pub use debug::{DebugFrame, DebugReceiver, DebugSender};
 pub use error::{ClientError, Result};
+pub use events::EventStream;
+pub use jobs::DEFAULT_PAGE_SIZE;
+pub use mock::MockOrchestrator;
+pub use retry::RetryPolicy;
 pub use rivet_core::dto::job::JobExecutionInfo;
+pub use trace::{REQUEST_ID_HEADER, TRACEPARENT_HEADER};
 
+use cache::{CachedResponse, ResponseCache};
 use reqwest::Client;
+use rivet_core::dto::version::{API_VERSION, API_VERSION_HEADER};
 use serde::de::DeserializeOwned;
 
 /// HTTP client for the Rivet orchestrator API
 ///
 /// This client provides methods for all orchestrator API endpoints, organized
 /// into logical groups:
+/// - Project management
 /// - Pipeline management (create, list, get, delete)
 /// - Job lifecycle (launch, claim, complete, status updates)
 /// - Runner registration and heartbeats
 /// - Log streaming
+/// - Job artifacts
+/// - Secret management
+/// - Event subscriptions
+/// - Debug sessions of failed jobs
 #[derive(Debug, Clone)]
 pub struct OrchestratorClient {
     /// Base URL of the orchestrator (e.g., "http://localhost:8080")
     base_url: String,
     /// HTTP client instance
     client: Client,
+    /// Retry policy for idempotent requests
+    retry: RetryPolicy,
+    /// Headers set by the builder, replayed on WebSocket handshakes
+    headers: reqwest::header::HeaderMap,
+    /// Time a WebSocket may take to connect, `None` to wait forever
+    connect_timeout: Option<std::time::Duration>,
+    /// Project (name or id) user-facing requests are scoped to
+    project: Option<String>,
+    /// Bodies of tagged responses, revalidated with `If-None-Match`
+    cache: ResponseCache,
 }
 
 impl OrchestratorClient {
@@ -70,6 +113,11 @@ impl OrchestratorClient {
         Self {
             base_url: base_url.trim_end_matches('/').to_string(),
             client: Client::new(),
+            retry: RetryPolicy::default(),
+            headers: Default::default(),
+            connect_timeout: None,
+            project: None,
+            cache: ResponseCache::default(),
         }
     }
 
@@ -99,14 +147,220 @@ impl OrchestratorClient {
         Self {
             base_url: base_url.trim_end_matches('/').to_string(),
             client,
+            retry: RetryPolicy::default(),
+            headers: Default::default(),
+            connect_timeout: None,
+            project: None,
+            cache: ResponseCache::default(),
         }
     }
 
+    /// Create a builder to configure authentication, headers and timeouts
+    ///
+    /// # Example
+    /// ```
+    /// use rivet_client::OrchestratorClient;
+    ///
+    /// let client = OrchestratorClient::builder()
+    ///     .base_url("http://localhost:8080")
+    ///     .token("secret-token")
+    ///     .build()
+    ///     .unwrap();
+    /// ```
+    pub fn builder() -> ClientBuilder {
+        ClientBuilder::default()
+    }
+
     /// Get the base URL of the orchestrator
     pub fn base_url(&self) -> &str {
         &self.base_url
     }
 
+    /// Scope pipeline, job and secret requests to a project
+    ///
+    /// Without a project, requests use the unscoped routes: listings span all
+    /// projects and new pipelines and secrets go to the default project.
+    /// Runner-facing requests are never scoped.
+    ///
+    /// # Example
+    /// ```
+    /// use rivet_client::OrchestratorClient;
+    ///
+    /// let client = OrchestratorClient::new("http://localhost:8080").with_project("team-a");
+    /// assert_eq!(client.project(), Some("team-a"));
+    /// ```
+    pub fn with_project(mut self, project: impl Into<String>) -> Self {
+        self.project = Some(project.into());
+        self
+    }
+
+    /// Get the project requests are scoped to, if any
+    pub fn project(&self) -> Option<&str> {
+        self.project.as_deref()
+    }
+
+    /// Build the URL of a user-facing endpoint, scoped to the project if one is set
+    ///
+    /// # Arguments
+    /// * `path` - Path below `/api/v1` (e.g., "/pipeline/list")
+    fn project_url(&self, path: &str) -> String {
+        format!("{}{}", self.base_url, self.project_path(path))
+    }
+
+    /// Path of a user-facing endpoint, scoped to the project if one is set
+    fn project_path(&self, path: &str) -> String {
+        match &self.project {
+            Some(project) => format!("/api/v1/projects/{}{}", project, path),
+            None => format!("/api/v1{}", path),
+        }
+    }
+
+    /// Replace the retry policy used for idempotent requests
+    ///
+    /// # Example
+    /// ```
+    /// use rivet_client::{OrchestratorClient, RetryPolicy};
+    /// use std::time::Duration;
+    ///
+    /// let client = OrchestratorClient::new("http://localhost:8080").with_retry_policy(RetryPolicy {
+    ///     max_retries: 5,
+    ///     initial_backoff: Duration::from_millis(100),
+    ///     ..RetryPolicy::default()
+    /// });
+    /// ```
+    pub fn with_retry_policy(mut self, policy: RetryPolicy) -> Self {
+        self.retry = policy;
+        self
+    }
+
+    /// Get a copy of this client that never retries
+    ///
+    /// Use it to opt out of retries for a single call:
+    /// `client.without_retry().get_job(id).await`
+    pub fn without_retry(&self) -> Self {
+        self.clone().with_retry_policy(RetryPolicy::none())
+    }
+
+    /// Get the retry policy used for idempotent requests
+    pub fn retry_policy(&self) -> &RetryPolicy {
+        &self.retry
+    }
+
+    // =============================================================================
+    // Request Sending
+    // =============================================================================
+
+    /// Send an idempotent request, retrying transient failures per the retry policy
+    ///
+    /// Requests whose body cannot be cloned (streams) are sent once.
+    async fn send_idempotent(&self, request: reqwest::RequestBuilder) -> Result<reqwest::Response> {
+        self.send_with_retry(request, true).await
+    }
+
+    /// Send a non-idempotent request
+    ///
+    /// Only `429 Too Many Requests` is retried: the orchestrator rejected the
+    /// request without processing it, so sending it again cannot duplicate work.
+    async fn send(&self, request: reqwest::RequestBuilder) -> Result<reqwest::Response> {
+        self.send_with_retry(request, false).await
+    }
+
+    /// Send an idempotent GET for JSON, revalidating a cached copy
+    ///
+    /// When the URL was fetched before with an `ETag`, the request carries
+    /// `If-None-Match` and a `304 Not Modified` answer is served from the
+    /// cache.
+    async fn get_cached<T: DeserializeOwned>(&self, request: reqwest::RequestBuilder) -> Result<T> {
+        let url = request
+            .try_clone()
+            .and_then(|r| r.build().ok())
+            .map(|r| r.url().to_string());
+        let Some(url) = url else {
+            let response = self.send_idempotent(request).await?;
+            return self.handle_response(response).await;
+        };
+
+        let cached = self.cache.get(&url);
+        let request = match &cached {
+            Some(cached) => request.header(reqwest::header::IF_NONE_MATCH, &cached.etag),
+            None => request,
+        };
+        let response = self.send_idempotent(request).await?;
+
+        if response.status() == reqwest::StatusCode::NOT_MODIFIED
+            && let Some(cached) = cached
+        {
+            return parse_json(&cached.body);
+        }
+        if !response.status().is_success() {
+            self.cache.remove(&url);
+            return Err(error_from_response(response).await);
+        }
+
+        let etag = response
+            .headers()
+            .get(reqwest::header::ETAG)
+            .and_then(|v| v.to_str().ok())
+            .map(str::to_string);
+        let body = response.bytes().await?;
+        let value = parse_json(&body)?;
+        if let Some(etag) = etag {
+            self.cache.insert(url, CachedResponse { etag, body });
+        }
+
+        Ok(value)
+    }
+
+    async fn send_with_retry(
+        &self,
+        request: reqwest::RequestBuilder,
+        idempotent: bool,
+    ) -> Result<reqwest::Response> {
+        let context = trace::RequestContext::new();
+        let request = context
+            .apply(request)
+            .header(API_VERSION_HEADER, API_VERSION);
+        let mut attempt = 0;
+
+        loop {
+            let Some(this_try) = request.try_clone() else {
+                return Ok(request.send().await?);
+            };
+
+            let delay = match this_try.send().await {
+                Ok(response)
+                    if attempt < self.retry.max_retries
+                        && (response.status() == reqwest::StatusCode::TOO_MANY_REQUESTS
+                            || (idempotent
+                                && RetryPolicy::is_retryable_status(response.status()))) =>
+                {
+                    tracing::debug!(
+                        "Request {} to {} returned {}, retrying",
+                        context.request_id,
+                        response.url(),
+                        response.status()
+                    );
+                    retry_after(&response)
+                        .map(|d| d.min(self.retry.max_backoff))
+                        .unwrap_or_else(|| self.retry.backoff(attempt))
+                }
+                Ok(response) => return Ok(response),
+                Err(e)
+                    if idempotent
+                        && attempt < self.retry.max_retries
+                        && RetryPolicy::is_retryable_error(&e) =>
+                {
+                    tracing::debug!("Request {} failed ({}), retrying", context.request_id, e);
+                    self.retry.backoff(attempt)
+                }
+                Err(e) => return Err(e.into()),
+            };
+
+            attempt += 1;
+            tokio::time::sleep(delay).await;
+        }
+    }
+
     // =============================================================================
     // Response Handlers
     // =============================================================================
@@ -119,11 +373,7 @@ impl OrchestratorClient {
         let status = response.status();
 
         if !status.is_success() {
-            let error_text = response
-                .text()
-                .await
-                .unwrap_or_else(|_| "Unknown error".to_string());
-            return Err(ClientError::api_error(status.as_u16(), error_text));
+            return Err(error_from_response(response).await);
         }
 
         response
@@ -139,17 +389,54 @@ impl OrchestratorClient {
         let status = response.status();
 
         if !status.is_success() {
-            let error_text = response
-                .text()
-                .await
-                .unwrap_or_else(|_| "Unknown error".to_string());
-            return Err(ClientError::api_error(status.as_u16(), error_text));
+            return Err(error_from_response(response).await);
         }
 
         Ok(())
     }
 }
 
+/// Convert a failed response into a typed `ClientError`
+///
+/// The request ID echoed by the orchestrator is appended to the message.
+async fn error_from_response(response: reqwest::Response) -> ClientError {
+    let status = response.status().as_u16();
+    let retry_after = retry_after(&response);
+    let request_id = response
+        .headers()
+        .get(trace::REQUEST_ID_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .map(str::to_string);
+    let body = response
+        .text()
+        .await
+        .unwrap_or_else(|_| "Unknown error".to_string());
+
+    let error = ClientError::from_response(status, &body, retry_after);
+    match request_id {
+        Some(request_id) => error.with_request_id(&request_id),
+        None => error,
+    }
+}
+
+/// Deserialize a JSON response body
+fn parse_json<T: DeserializeOwned>(body: &[u8]) -> Result<T> {
+    serde_json::from_slice(body)
+        .map_err(|e| ClientError::ParseError(format!("Failed to parse JSON response: {}", e)))
+}
+
+/// Parse a `Retry-After` header given in seconds
+fn retry_after(response: &reqwest::Response) -> Option<std::time::Duration> {
+    response
+        .headers()
+        .get(reqwest::header::RETRY_AFTER)?
+        .to_str()
+        .ok()?
+        .parse::<u64>()
+        .ok()
+        .map(std::time::Duration::from_secs)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -166,6 +453,97 @@ mod tests {
         assert_eq!(client.base_url(), "http://localhost:8080");
     }
 
+    #[test]
+    fn test_project_url_scoping() {
+        let client = OrchestratorClient::new("http://localhost:8080");
+        assert_eq!(
+            client.project_url("/pipeline/list"),
+            "http://localhost:8080/api/v1/pipeline/list"
+        );
+
+        let client = client.with_project("team-a");
+        assert_eq!(
+            client.project_url("/pipeline/list"),
+            "http://localhost:8080/api/v1/projects/team-a/pipeline/list"
+        );
+    }
+
+    #[test]
+    fn test_retry_backoff_grows_and_caps() {
+        let policy = RetryPolicy {
+            max_retries: 10,
+            initial_backoff: std::time::Duration::from_millis(100),
+            max_backoff: std::time::Duration::from_millis(1000),
+            jitter: false,
+        };
+        assert_eq!(policy.backoff(0).as_millis(), 100);
+        assert_eq!(policy.backoff(1).as_millis(), 200);
+        assert_eq!(policy.backoff(3).as_millis(), 800);
+        assert_eq!(policy.backoff(4).as_millis(), 1000);
+        assert_eq!(policy.backoff(30).as_millis(), 1000);
+    }
+
+    #[test]
+    fn test_retry_backoff_jitter_bounds() {
+        let policy = RetryPolicy::default();
+        for attempt in 0..6 {
+            let full = RetryPolicy {
+                jitter: false,
+                ..policy.clone()
+            }
+            .backoff(attempt);
+            let delay = policy.backoff(attempt);
+            assert!(delay >= full / 2 && delay <= full);
+        }
+    }
+
+    #[test]
+    fn test_without_retry() {
+        let client = OrchestratorClient::new("http://localhost:8080");
+        assert_eq!(client.without_retry().retry_policy().max_retries, 0);
+        assert_eq!(client.retry_policy().max_retries, 3);
+    }
+
+    #[test]
+    fn test_builder() {
+        let client = OrchestratorClient::builder()
+            .base_url("http://localhost:8080/")
+            .token("abc")
+            .header("X-Tenant", "team-a")
+            .build()
+            .unwrap();
+        assert_eq!(client.base_url(), "http://localhost:8080");
+    }
+
+    #[test]
+    fn test_builder_rejects_invalid_input() {
+        assert!(OrchestratorClient::builder().build().is_err());
+        assert!(
+            OrchestratorClient::builder()
+                .base_url("http://localhost:8080")
+                .header("bad header", "value")
+                .build()
+                .is_err()
+        );
+        assert!(
+            OrchestratorClient::builder()
+                .base_url("https://localhost:8443")
+                .client_identity_pem("not a cert", "not a key")
+                .build()
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_builder_debug_redacts_secrets() {
+        let builder = OrchestratorClient::builder()
+            .token("super-secret")
+            .client_identity_pem("cert", "private-key");
+        let debug = format!("{:?}", builder);
+        assert!(!debug.contains("super-secret"));
+        assert!(!debug.contains("private-key"));
+    }
+
     #[test]
     fn test_client_with_custom_client() {
         let http_client = Client::new();
@@ -0,0 +1,736 @@
+//! In-memory orchestrator
+//!
+//! `MockOrchestrator` implements `OrchestratorApi` on top of plain maps so that
+//! runner and CLI code can be unit tested without a database or HTTP server.
+//! Errors mirror what the HTTP client returns for the same situations
+//! (404 for unknown resources, 400 for invalid state transitions, 409 for
+//! stale claims).
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use chrono::Utc;
+use rivet_core::domain::job::{Job, JobResult, JobStatus};
+use rivet_core::domain::log::LogEntry;
+use rivet_core::domain::pipeline::Pipeline;
+use rivet_core::domain::project::DEFAULT_PROJECT_ID;
+use rivet_core::domain::runner::{Runner, RunnerLoad, RunnerStatus};
+use rivet_core::dto::job::{CreateJob, JobExecutionInfo, JobLease, StageUpdate};
+use rivet_core::dto::pipeline::{CreatePipeline, DeletePipelineQuery};
+use uuid::Uuid;
+
+use crate::api::OrchestratorApi;
+use crate::error::{ClientError, Result};
+
+/// Name given to pipelines created through `create_pipeline`
+///
+/// The mock does not evaluate Lua, use `add_pipeline` to pick a name.
+const MOCK_PIPELINE_NAME: &str = "mock-pipeline";
+
+/// In-memory implementation of `OrchestratorApi`
+///
+/// # Example
+/// ```
+/// # use rivet_client::{MockOrchestrator, OrchestratorApi};
+/// # use rivet_core::dto::job::CreateJob;
+/// # async fn example() -> rivet_client::Result<()> {
+/// let mock = MockOrchestrator::new();
+/// let pipeline = mock.add_pipeline("build", "return {}");
+/// let job = mock.launch_job(CreateJob {
+///     pipeline_id: pipeline.id,
+///     parameters: Default::default(),
+///     plan: false,
+///     commit: None,
+///     override_blackout: false,
+///     labels: Default::default(),
+/// }).await?;
+///
+/// assert_eq!(mock.list_scheduled_jobs().await?.len(), 1);
+/// # let _ = job;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Default)]
+pub struct MockOrchestrator {
+    state: Mutex<MockState>,
+}
+
+#[derive(Debug, Default)]
+struct MockState {
+    pipelines: Vec<Pipeline>,
+    jobs: Vec<Job>,
+    logs: HashMap<Uuid, Vec<LogEntry>>,
+    stages: HashMap<Uuid, Vec<StageUpdate>>,
+    runners: Vec<Runner>,
+    /// Token of the current claim of each job ever claimed
+    claim_tokens: HashMap<Uuid, u64>,
+}
+
+impl MockOrchestrator {
+    /// Creates an empty mock orchestrator
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Inserts a pipeline with the given name and script
+    ///
+    /// # Returns
+    /// The stored pipeline
+    pub fn add_pipeline(&self, name: impl Into<String>, script: impl Into<String>) -> Pipeline {
+        let now = Utc::now();
+        let pipeline = Pipeline {
+            id: Uuid::new_v4(),
+            project_id: DEFAULT_PROJECT_ID,
+            name: name.into(),
+            description: None,
+            script: script.into(),
+            created_at: now,
+            updated_at: now,
+            tags: Vec::new(),
+            plugins: Vec::new(),
+            archived_at: None,
+            disabled: false,
+        };
+
+        self.state.lock().unwrap().pipelines.push(pipeline.clone());
+        pipeline
+    }
+
+    /// Returns every log entry received for a job so far
+    pub fn logs(&self, job_id: Uuid) -> Vec<LogEntry> {
+        self.state
+            .lock()
+            .unwrap()
+            .logs
+            .get(&job_id)
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// Returns every stage update received for a job so far
+    pub fn stage_updates(&self, job_id: Uuid) -> Vec<StageUpdate> {
+        self.state
+            .lock()
+            .unwrap()
+            .stages
+            .get(&job_id)
+            .cloned()
+            .unwrap_or_default()
+    }
+}
+
+// =============================================================================
+// Helpers
+// =============================================================================
+
+fn not_found(kind: &str, id: impl std::fmt::Display) -> ClientError {
+    ClientError::NotFound(format!("{} {} not found", kind, id))
+}
+
+fn stale_claim(job_id: Uuid) -> ClientError {
+    ClientError::Conflict(format!("Claim on job {} is no longer current", job_id))
+}
+
+fn bad_request(message: String) -> ClientError {
+    ClientError::Validation {
+        field: None,
+        message,
+        fields: Vec::new(),
+    }
+}
+
+impl MockState {
+    fn pipeline(&self, pipeline_id: Uuid) -> Result<&Pipeline> {
+        self.pipelines
+            .iter()
+            .find(|p| p.id == pipeline_id)
+            .ok_or_else(|| not_found("Pipeline", pipeline_id))
+    }
+
+    fn job_mut(&mut self, job_id: Uuid) -> Result<&mut Job> {
+        self.jobs
+            .iter_mut()
+            .find(|j| j.id == job_id)
+            .ok_or_else(|| not_found("Job", job_id))
+    }
+
+    /// Fails unless `claim_token` is given and is the current claim of the job
+    fn verify_claim(&self, job_id: Uuid, claim_token: Option<u64>) -> Result<()> {
+        match claim_token {
+            None => Err(bad_request("claim_token is required".to_string())),
+            Some(token) if self.claim_tokens.get(&job_id) != Some(&token) => {
+                Err(stale_claim(job_id))
+            }
+            Some(_) => Ok(()),
+        }
+    }
+
+    /// Stages whose last reported status lets a resumed job skip them
+    fn done_stages(&self, job_id: Uuid) -> Vec<String> {
+        let mut last: Vec<&StageUpdate> = Vec::new();
+        for update in self.stages.get(&job_id).into_iter().flatten() {
+            last.retain(|u| u.name != update.name);
+            last.push(update);
+        }
+        last.into_iter()
+            .filter(|u| u.status.is_done())
+            .map(|u| u.name.clone())
+            .collect()
+    }
+
+    fn runner_mut(&mut self, runner_id: &str) -> Result<&mut Runner> {
+        self.runners
+            .iter_mut()
+            .find(|r| r.id == runner_id)
+            .ok_or_else(|| not_found("Runner", runner_id))
+    }
+}
+
+#[async_trait]
+impl OrchestratorApi for MockOrchestrator {
+    // =============================================================================
+    // Pipelines
+    // =============================================================================
+
+    async fn create_pipeline(&self, req: CreatePipeline) -> Result<Pipeline> {
+        Ok(self.add_pipeline(MOCK_PIPELINE_NAME, req.script))
+    }
+
+    async fn list_pipelines(&self) -> Result<Vec<Pipeline>> {
+        let state = self.state.lock().unwrap();
+        Ok(state
+            .pipelines
+            .iter()
+            .filter(|p| p.archived_at.is_none())
+            .cloned()
+            .collect())
+    }
+
+    async fn get_pipeline(&self, pipeline_id: Uuid) -> Result<Pipeline> {
+        self.state.lock().unwrap().pipeline(pipeline_id).cloned()
+    }
+
+    async fn delete_pipeline(
+        &self,
+        pipeline_id: Uuid,
+        query: &DeletePipelineQuery,
+    ) -> Result<Option<Pipeline>> {
+        let mut state = self.state.lock().unwrap();
+        state.pipeline(pipeline_id)?;
+
+        let has_jobs = state.jobs.iter().any(|j| j.pipeline_id == pipeline_id);
+        if query.archive || (has_jobs && !query.force) {
+            let pipeline = state
+                .pipelines
+                .iter_mut()
+                .find(|p| p.id == pipeline_id)
+                .expect("pipeline checked above");
+            pipeline.archived_at.get_or_insert_with(Utc::now);
+            return Ok(Some(pipeline.clone()));
+        }
+
+        state.pipelines.retain(|p| p.id != pipeline_id);
+        state.jobs.retain(|j| j.pipeline_id != pipeline_id);
+        Ok(None)
+    }
+
+    // =============================================================================
+    // Jobs
+    // =============================================================================
+
+    async fn launch_job(&self, req: CreateJob) -> Result<Job> {
+        let mut state = self.state.lock().unwrap();
+        let pipeline = state.pipeline(req.pipeline_id)?;
+        if pipeline.archived_at.is_some() || pipeline.disabled {
+            return Err(bad_request(format!(
+                "Pipeline {} is archived or disabled",
+                req.pipeline_id
+            )));
+        }
+
+        let last_number = state
+            .jobs
+            .iter()
+            .filter(|j| j.pipeline_id == req.pipeline_id)
+            .map(|j| j.number)
+            .max()
+            .unwrap_or(0);
+
+        let job = Job {
+            id: Uuid::new_v4(),
+            number: last_number + 1,
+            pipeline_id: req.pipeline_id,
+            status: JobStatus::Queued,
+            requested_at: Utc::now(),
+            started_at: None,
+            completed_at: None,
+            runner_id: None,
+            parameters: req.parameters,
+            result: None,
+            attempt: 1,
+            max_attempts: 1,
+            dead_letter_reason: None,
+            resumed_from: None,
+            commit: req.commit,
+            labels: req.labels,
+        };
+
+        state.jobs.push(job.clone());
+        Ok(job)
+    }
+
+    async fn get_job(&self, job_id: Uuid) -> Result<Job> {
+        self.state
+            .lock()
+            .unwrap()
+            .job_mut(job_id)
+            .map(|j| j.clone())
+    }
+
+    async fn list_all_jobs(&self) -> Result<Vec<Job>> {
+        Ok(self.state.lock().unwrap().jobs.clone())
+    }
+
+    async fn list_jobs_page(&self, limit: u32, offset: u32) -> Result<Vec<Job>> {
+        let state = self.state.lock().unwrap();
+        Ok(state
+            .jobs
+            .iter()
+            .rev()
+            .skip(offset as usize)
+            .take(limit as usize)
+            .cloned()
+            .collect())
+    }
+
+    async fn list_scheduled_jobs(&self) -> Result<Vec<Job>> {
+        let state = self.state.lock().unwrap();
+        Ok(state
+            .jobs
+            .iter()
+            .filter(|j| j.status == JobStatus::Queued)
+            .cloned()
+            .collect())
+    }
+
+    async fn list_jobs_by_pipeline(&self, pipeline_id: Uuid) -> Result<Vec<Job>> {
+        let state = self.state.lock().unwrap();
+        state.pipeline(pipeline_id)?;
+        Ok(state
+            .jobs
+            .iter()
+            .filter(|j| j.pipeline_id == pipeline_id)
+            .cloned()
+            .collect())
+    }
+
+    async fn claim_job(&self, job_id: Uuid, runner_id: &str) -> Result<JobExecutionInfo> {
+        let mut state = self.state.lock().unwrap();
+
+        let job = state.job_mut(job_id)?;
+        if job.status != JobStatus::Queued {
+            return Err(bad_request(format!(
+                "Job {} is not in Queued state (current: {:?})",
+                job_id, job.status
+            )));
+        }
+
+        job.status = JobStatus::Running;
+        job.started_at = Some(Utc::now());
+        job.runner_id = Some(runner_id.to_string());
+
+        let (pipeline_id, parameters) = (job.pipeline_id, job.parameters.clone());
+        let (number, attempt) = (job.number, job.attempt);
+        let (commit, resumed_from) = (job.commit.clone(), job.resumed_from);
+        let pipeline = state.pipeline(pipeline_id)?;
+        let (pipeline_name, pipeline_source) = (pipeline.name.clone(), pipeline.script.clone());
+        let claim_token = state.claim_tokens.entry(job_id).or_default();
+        *claim_token += 1;
+        let claim_token = Some(*claim_token);
+        let skip_stages = resumed_from
+            .map(|original| state.done_stages(original))
+            .unwrap_or_default();
+        let ctx = resumed_from.and_then(|original| {
+            let original = state.jobs.iter().find(|j| j.id == original)?;
+            original
+                .result
+                .as_ref()?
+                .output
+                .as_ref()?
+                .get("ctx")
+                .cloned()
+        });
+
+        Ok(JobExecutionInfo {
+            job_id,
+            pipeline_id,
+            pipeline_name,
+            number,
+            attempt,
+            commit,
+            schedule: None,
+            pipeline_source,
+            parameters,
+            secrets: HashMap::new(),
+            secret_inputs: Vec::new(),
+            resumed_from,
+            skip_stages,
+            traceparent: None,
+            claim_token,
+            default_container: None,
+            default_container_variants: HashMap::new(),
+            env: HashMap::new(),
+            ctx,
+        })
+    }
+
+    /// Claims the oldest queued job right away; the mock never waits
+    async fn next_job(&self, runner_id: &str, _wait: Duration) -> Result<Option<JobExecutionInfo>> {
+        let next = self
+            .state
+            .lock()
+            .unwrap()
+            .jobs
+            .iter()
+            .find(|j| j.status == JobStatus::Queued)
+            .map(|j| j.id);
+
+        match next {
+            Some(job_id) => self.claim_job(job_id, runner_id).await.map(Some),
+            None => Ok(None),
+        }
+    }
+
+    async fn update_job_status(&self, job_id: Uuid, status: JobStatus) -> Result<()> {
+        self.state.lock().unwrap().job_mut(job_id)?.status = status;
+        Ok(())
+    }
+
+    async fn renew_job_lease(
+        &self,
+        job_id: Uuid,
+        runner_id: &str,
+        ttl: Duration,
+    ) -> Result<JobLease> {
+        let mut state = self.state.lock().unwrap();
+
+        let job = state.job_mut(job_id)?;
+        if job.status != JobStatus::Running || job.runner_id.as_deref() != Some(runner_id) {
+            return Err(bad_request(format!(
+                "Job {} is not running on runner {} (current: {:?})",
+                job_id, runner_id, job.status
+            )));
+        }
+
+        Ok(JobLease {
+            job_id,
+            runner_id: runner_id.to_string(),
+            expires_at: Utc::now() + ttl,
+        })
+    }
+
+    async fn complete_job(
+        &self,
+        job_id: Uuid,
+        claim_token: Option<u64>,
+        result: JobResult,
+    ) -> Result<()> {
+        let mut state = self.state.lock().unwrap();
+        state.job_mut(job_id)?;
+        state.verify_claim(job_id, claim_token)?;
+
+        let job = state.job_mut(job_id)?;
+        if claim_token.is_some() && job.status != JobStatus::Running {
+            return Err(stale_claim(job_id));
+        }
+
+        job.status = if result.success {
+            JobStatus::Succeeded
+        } else {
+            JobStatus::Failed
+        };
+        job.completed_at = Some(Utc::now());
+        job.result = Some(result);
+        Ok(())
+    }
+
+    // =============================================================================
+    // Logs
+    // =============================================================================
+
+    async fn get_job_logs(&self, job_id: Uuid) -> Result<Vec<LogEntry>> {
+        let mut state = self.state.lock().unwrap();
+        state.job_mut(job_id)?;
+        Ok(state.logs.get(&job_id).cloned().unwrap_or_default())
+    }
+
+    async fn send_logs(
+        &self,
+        job_id: Uuid,
+        claim_token: Option<u64>,
+        entries: Vec<LogEntry>,
+    ) -> Result<()> {
+        let mut state = self.state.lock().unwrap();
+        state.job_mut(job_id)?;
+        state.verify_claim(job_id, claim_token)?;
+        state.logs.entry(job_id).or_default().extend(entries);
+        Ok(())
+    }
+
+    async fn send_stage_updates(&self, job_id: Uuid, updates: Vec<StageUpdate>) -> Result<()> {
+        let mut state = self.state.lock().unwrap();
+        state.job_mut(job_id)?;
+        state.stages.entry(job_id).or_default().extend(updates);
+        Ok(())
+    }
+
+    // =============================================================================
+    // Runners
+    // =============================================================================
+
+    async fn register_runner(&self, runner_id: &str) -> Result<Runner> {
+        let mut state = self.state.lock().unwrap();
+        let now = Utc::now();
+
+        if let Ok(runner) = state.runner_mut(runner_id) {
+            runner.status = RunnerStatus::Online;
+            runner.last_heartbeat_at = now;
+            return Ok(runner.clone());
+        }
+
+        let runner = Runner {
+            id: runner_id.to_string(),
+            registered_at: now,
+            last_heartbeat_at: now,
+            status: RunnerStatus::Online,
+            tags: Vec::new(),
+            plugins: Vec::new(),
+            load: None,
+            info: None,
+        };
+        state.runners.push(runner.clone());
+        Ok(runner)
+    }
+
+    async fn send_heartbeat(&self, runner_id: &str, load: Option<RunnerLoad>) -> Result<()> {
+        let mut state = self.state.lock().unwrap();
+        let runner = state.runner_mut(runner_id)?;
+        runner.last_heartbeat_at = Utc::now();
+        runner.status = RunnerStatus::Online;
+        if load.is_some() {
+            runner.load = load;
+        }
+        Ok(())
+    }
+
+    async fn list_runners(&self) -> Result<Vec<Runner>> {
+        Ok(self.state.lock().unwrap().runners.clone())
+    }
+
+    async fn get_runner(&self, runner_id: &str) -> Result<Runner> {
+        self.state
+            .lock()
+            .unwrap()
+            .runner_mut(runner_id)
+            .map(|r| r.clone())
+    }
+
+    async fn delete_runner(&self, runner_id: &str) -> Result<()> {
+        let mut state = self.state.lock().unwrap();
+        state.runner_mut(runner_id)?;
+        state.runners.retain(|r| r.id != runner_id);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_job_lifecycle() {
+        let mock = MockOrchestrator::new();
+        let pipeline = mock.add_pipeline("build", "return {}");
+
+        let job = mock
+            .launch_job(CreateJob {
+                pipeline_id: pipeline.id,
+                parameters: Default::default(),
+                plan: false,
+                commit: None,
+                override_blackout: false,
+                labels: Default::default(),
+            })
+            .await
+            .unwrap();
+        assert_eq!(mock.list_scheduled_jobs().await.unwrap().len(), 1);
+
+        let info = mock.claim_job(job.id, "runner-1").await.unwrap();
+        assert_eq!(info.pipeline_source, "return {}");
+        assert!(mock.list_scheduled_jobs().await.unwrap().is_empty());
+
+        // A job can only be claimed once
+        let err = mock.claim_job(job.id, "runner-2").await.unwrap_err();
+        assert!(err.is_client_error());
+        assert!(
+            mock.next_job("runner-2", Duration::ZERO)
+                .await
+                .unwrap()
+                .is_none()
+        );
+
+        mock.renew_job_lease(job.id, "runner-1", Duration::from_secs(30))
+            .await
+            .unwrap();
+        assert!(
+            mock.renew_job_lease(job.id, "runner-2", Duration::from_secs(30))
+                .await
+                .is_err()
+        );
+
+        mock.complete_job(job.id, info.claim_token, JobResult::success())
+            .await
+            .unwrap();
+        let job = mock.get_job(job.id).await.unwrap();
+        assert_eq!(job.status, JobStatus::Succeeded);
+        assert_eq!(job.runner_id.as_deref(), Some("runner-1"));
+
+        // A job is completed only once
+        let err = mock
+            .complete_job(
+                job.id,
+                info.claim_token,
+                JobResult::failed("again".to_string()),
+            )
+            .await
+            .unwrap_err();
+        assert_eq!(err.status(), Some(409));
+    }
+
+    #[tokio::test]
+    async fn test_stale_claims_are_rejected() {
+        let mock = MockOrchestrator::new();
+        let pipeline = mock.add_pipeline("build", "return {}");
+        let job = mock
+            .launch_job(CreateJob {
+                pipeline_id: pipeline.id,
+                parameters: Default::default(),
+                plan: false,
+                commit: None,
+                override_blackout: false,
+                labels: Default::default(),
+            })
+            .await
+            .unwrap();
+
+        // The job is requeued (e.g. its lease expired) and claimed again
+        let first = mock.claim_job(job.id, "runner-1").await.unwrap();
+        mock.update_job_status(job.id, JobStatus::Queued)
+            .await
+            .unwrap();
+        let second = mock.claim_job(job.id, "runner-2").await.unwrap();
+        assert_ne!(first.claim_token, second.claim_token);
+
+        let err = mock
+            .send_logs(job.id, first.claim_token, Vec::new())
+            .await
+            .unwrap_err();
+        assert_eq!(err.status(), Some(409));
+        let err = mock.send_logs(job.id, None, Vec::new()).await.unwrap_err();
+        assert_eq!(err.status(), Some(400));
+        let err = mock
+            .complete_job(job.id, None, JobResult::success())
+            .await
+            .unwrap_err();
+        assert_eq!(err.status(), Some(400));
+        let err = mock
+            .complete_job(job.id, first.claim_token, JobResult::success())
+            .await
+            .unwrap_err();
+        assert_eq!(err.status(), Some(409));
+        assert_eq!(
+            mock.get_job(job.id).await.unwrap().status,
+            JobStatus::Running
+        );
+
+        mock.send_logs(job.id, second.claim_token, Vec::new())
+            .await
+            .unwrap();
+        mock.complete_job(job.id, second.claim_token, JobResult::success())
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_pipelines_with_jobs_are_archived() {
+        let mock = MockOrchestrator::new();
+        let pipeline = mock.add_pipeline("build", "return {}");
+        let launch = CreateJob {
+            pipeline_id: pipeline.id,
+            parameters: Default::default(),
+            plan: false,
+            commit: None,
+            override_blackout: false,
+            labels: Default::default(),
+        };
+        mock.launch_job(launch.clone()).await.unwrap();
+
+        let archived = mock
+            .delete_pipeline(pipeline.id, &DeletePipelineQuery::default())
+            .await
+            .unwrap();
+        assert!(archived.is_some_and(|p| p.archived_at.is_some()));
+        assert!(mock.list_pipelines().await.unwrap().is_empty());
+        assert_eq!(
+            mock.list_jobs_by_pipeline(pipeline.id).await.unwrap().len(),
+            1
+        );
+        assert!(mock.launch_job(launch).await.unwrap_err().is_client_error());
+
+        let force = DeletePipelineQuery {
+            archive: false,
+            force: true,
+        };
+        let deleted = mock.delete_pipeline(pipeline.id, &force).await.unwrap();
+        assert!(deleted.is_none());
+        assert!(
+            mock.get_pipeline(pipeline.id)
+                .await
+                .unwrap_err()
+                .is_not_found()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_unknown_resources_are_not_found() {
+        let mock = MockOrchestrator::new();
+
+        assert!(
+            mock.get_job(Uuid::new_v4())
+                .await
+                .unwrap_err()
+                .is_not_found()
+        );
+        assert!(
+            mock.send_heartbeat("missing", None)
+                .await
+                .unwrap_err()
+                .is_not_found()
+        );
+        assert!(
+            mock.launch_job(CreateJob {
+                pipeline_id: Uuid::new_v4(),
+                parameters: Default::default(),
+                plan: false,
+                commit: None,
+                override_blackout: false,
+                labels: Default::default(),
+            })
+            .await
+            .unwrap_err()
+            .is_not_found()
+        );
+    }
+}
@@ -3,7 +3,18 @@
 use crate::OrchestratorClient;
 use crate::error::Result;
 use rivet_core::domain::pipeline::Pipeline;
-use rivet_core::dto::pipeline::CreatePipeline;
+use rivet_core::domain::preset::ParameterPreset;
+use rivet_core::domain::schedule::Schedule;
+use rivet_core::domain::webhook::WebhookTrigger;
+use rivet_core::dto::artifact::ArtifactRetention;
+use rivet_core::dto::commit_status::CommitStatusConfig;
+use rivet_core::dto::pipeline::{
+    CreatePipeline, DeletePipelineQuery, FlakyStage, FlakyStagesQuery, PipelineDefaults,
+    PipelineDefinitionInfo, PipelineGraph, PipelineListQuery, PipelineStats, PipelineStatsQuery,
+};
+use rivet_core::dto::preset::SavePreset;
+use rivet_core::dto::schedule::CreateSchedule;
+use rivet_core::dto::webhook::CreateWebhookTrigger;
 use uuid::Uuid;
 
 impl OrchestratorClient {
@@ -22,7 +33,7 @@ impl OrchestratorClient {
     /// # Example
     /// ```no_run
     /// # use rivet_client::OrchestratorClient;
-    /// # use rivet_core::dto::pipeline::CreatePipeline;
+    /// # use rivet_core::dto::pipeline::{CreatePipeline, PipelineDefaults};
     /// # async fn example() -> anyhow::Result<()> {
     /// let client = OrchestratorClient::new("http://localhost:8080");
     /// let pipeline = client.create_pipeline(CreatePipeline {
@@ -32,8 +43,8 @@ impl OrchestratorClient {
     /// # }
     /// ```
     pub async fn create_pipeline(&self, req: CreatePipeline) -> Result<Pipeline> {
-        let url = format!("{}/api/pipeline/create", self.base_url);
-        let response = self.client.post(&url).json(&req).send().await?;
+        let url = self.project_url("/pipeline/create");
+        let response = self.send(self.client.post(&url).json(&req)).await?;
 
         self.handle_response(response).await
     }
@@ -41,12 +52,20 @@ impl OrchestratorClient {
     /// List all pipelines
     ///
     /// # Returns
-    /// A list of all pipelines
+    /// A list of all active pipelines
     pub async fn list_pipelines(&self) -> Result<Vec<Pipeline>> {
-        let url = format!("{}/api/pipeline/list", self.base_url);
-        let response = self.client.get(&url).send().await?;
+        let url = self.project_url("/pipeline/list");
+        self.get_cached(self.client.get(&url)).await
+    }
 
-        self.handle_response(response).await
+    /// List the archived pipelines
+    ///
+    /// # Returns
+    /// A list of all archived pipelines
+    pub async fn list_archived_pipelines(&self) -> Result<Vec<Pipeline>> {
+        let url = self.project_url("/pipeline/list");
+        let query = PipelineListQuery { archived: true };
+        self.get_cached(self.client.get(&url).query(&query)).await
     }
 
     /// Get a pipeline by ID
@@ -57,19 +76,379 @@ impl OrchestratorClient {
     /// # Returns
     /// The pipeline details
     pub async fn get_pipeline(&self, pipeline_id: Uuid) -> Result<Pipeline> {
-        let url = format!("{}/api/pipeline/{}", self.base_url, pipeline_id);
-        let response = self.client.get(&url).send().await?;
+        let url = self.project_url(&format!("/pipeline/{}", pipeline_id));
+        self.get_cached(self.client.get(&url)).await
+    }
 
-        self.handle_response(response).await
+    /// Get the parsed definition of a pipeline script: inputs, stages,
+    /// runner tags and plugins
+    ///
+    /// # Arguments
+    /// * `pipeline_id` - The pipeline UUID
+    pub async fn get_pipeline_definition(
+        &self,
+        pipeline_id: Uuid,
+    ) -> Result<PipelineDefinitionInfo> {
+        let url = self.project_url(&format!("/pipeline/{}/definition", pipeline_id));
+        self.get_cached(self.client.get(&url)).await
+    }
+
+    /// Get the stage dependency graph of a pipeline
+    ///
+    /// # Arguments
+    /// * `pipeline_id` - The pipeline UUID
+    pub async fn get_pipeline_graph(&self, pipeline_id: Uuid) -> Result<PipelineGraph> {
+        let url = self.project_url(&format!("/pipeline/{}/graph", pipeline_id));
+        self.get_cached(self.client.get(&url)).await
     }
 
-    /// Delete a pipeline
+    /// Delete a pipeline, or archive it
+    ///
+    /// Without `query.archive` or `query.force`, the pipeline is archived
+    /// when jobs reference it and deleted otherwise.
     ///
     /// # Arguments
     /// * `pipeline_id` - The pipeline UUID to delete
-    pub async fn delete_pipeline(&self, pipeline_id: Uuid) -> Result<()> {
-        let url = format!("{}/api/pipeline/{}", self.base_url, pipeline_id);
-        let response = self.client.delete(&url).send().await?;
+    /// * `query` - Whether to always archive it, or delete it with its jobs
+    ///
+    /// # Returns
+    /// The archived pipeline, or `None` if it was deleted
+    pub async fn delete_pipeline(
+        &self,
+        pipeline_id: Uuid,
+        query: &DeletePipelineQuery,
+    ) -> Result<Option<Pipeline>> {
+        let url = self.project_url(&format!("/pipeline/{}", pipeline_id));
+        let response = self.send(self.client.delete(&url).query(query)).await?;
+
+        if response.status() == reqwest::StatusCode::NO_CONTENT {
+            return self.handle_empty_response(response).await.map(|_| None);
+        }
+        self.handle_response(response).await.map(Some)
+    }
+
+    /// Pause (`disabled`) or resume the launches of a pipeline
+    ///
+    /// # Arguments
+    /// * `pipeline_id` - The pipeline UUID
+    /// * `disabled` - Whether new jobs are refused
+    ///
+    /// # Returns
+    /// The updated pipeline
+    pub async fn set_pipeline_disabled(
+        &self,
+        pipeline_id: Uuid,
+        disabled: bool,
+    ) -> Result<Pipeline> {
+        let action = if disabled { "disable" } else { "enable" };
+        let url = self.project_url(&format!("/pipeline/{}/{}", pipeline_id, action));
+        let response = self.send(self.client.post(&url)).await?;
+
+        self.handle_response(response).await
+    }
+
+    /// Make an archived pipeline active again
+    ///
+    /// # Arguments
+    /// * `pipeline_id` - The pipeline UUID
+    ///
+    /// # Returns
+    /// The unarchived pipeline
+    pub async fn unarchive_pipeline(&self, pipeline_id: Uuid) -> Result<Pipeline> {
+        let url = self.project_url(&format!("/pipeline/{}/unarchive", pipeline_id));
+        let response = self.send(self.client.post(&url)).await?;
+
+        self.handle_response(response).await
+    }
+
+    /// Get the input defaults and overrides the orchestrator holds for a pipeline
+    ///
+    /// # Arguments
+    /// * `pipeline_id` - The pipeline UUID
+    pub async fn get_pipeline_defaults(&self, pipeline_id: Uuid) -> Result<PipelineDefaults> {
+        let url = self.project_url(&format!("/pipeline/{}/defaults", pipeline_id));
+        let response = self.send_idempotent(self.client.get(&url)).await?;
+
+        self.handle_response(response).await
+    }
+
+    /// Replace the input defaults and overrides of a pipeline (admin)
+    ///
+    /// # Arguments
+    /// * `pipeline_id` - The pipeline UUID
+    /// * `defaults` - The new values; empty maps clear them
+    ///
+    /// # Returns
+    /// The values now stored
+    pub async fn set_pipeline_defaults(
+        &self,
+        pipeline_id: Uuid,
+        defaults: &PipelineDefaults,
+    ) -> Result<PipelineDefaults> {
+        let url = self.project_url(&format!("/pipeline/{}/defaults", pipeline_id));
+        let response = self.send(self.client.put(&url).json(defaults)).await?;
+
+        self.handle_response(response).await
+    }
+
+    /// Get the limits past which a pipeline's artifacts are garbage collected
+    ///
+    /// # Arguments
+    /// * `pipeline_id` - The pipeline UUID
+    pub async fn get_artifact_retention(&self, pipeline_id: Uuid) -> Result<ArtifactRetention> {
+        let url = self.project_url(&format!("/pipeline/{}/artifact-retention", pipeline_id));
+        let response = self.send_idempotent(self.client.get(&url)).await?;
+
+        self.handle_response(response).await
+    }
+
+    /// Replace the artifact retention limits of a pipeline (admin)
+    ///
+    /// # Arguments
+    /// * `pipeline_id` - The pipeline UUID
+    /// * `retention` - The new limits; unset limits keep artifacts forever
+    ///
+    /// # Returns
+    /// The limits now stored
+    pub async fn set_artifact_retention(
+        &self,
+        pipeline_id: Uuid,
+        retention: &ArtifactRetention,
+    ) -> Result<ArtifactRetention> {
+        let url = self.project_url(&format!("/pipeline/{}/artifact-retention", pipeline_id));
+        let response = self.send(self.client.put(&url).json(retention)).await?;
+
+        self.handle_response(response).await
+    }
+
+    /// Get how a pipeline reports the status of the commits its jobs build
+    ///
+    /// # Arguments
+    /// * `pipeline_id` - The pipeline UUID
+    ///
+    /// # Returns
+    /// `None` if the pipeline reports no commit status
+    pub async fn get_commit_status(&self, pipeline_id: Uuid) -> Result<Option<CommitStatusConfig>> {
+        let url = self.project_url(&format!("/pipeline/{}/commit-status", pipeline_id));
+        let response = self.send_idempotent(self.client.get(&url)).await?;
+
+        self.handle_response(response).await
+    }
+
+    /// Report the status of the commits a pipeline's jobs build (admin)
+    ///
+    /// # Arguments
+    /// * `pipeline_id` - The pipeline UUID
+    /// * `config` - Provider, token secret and how statuses are shown
+    pub async fn set_commit_status(
+        &self,
+        pipeline_id: Uuid,
+        config: &CommitStatusConfig,
+    ) -> Result<Option<CommitStatusConfig>> {
+        let url = self.project_url(&format!("/pipeline/{}/commit-status", pipeline_id));
+        let response = self.send(self.client.put(&url).json(config)).await?;
+
+        self.handle_response(response).await
+    }
+
+    /// Stop reporting commit statuses for a pipeline (admin)
+    ///
+    /// # Arguments
+    /// * `pipeline_id` - The pipeline UUID
+    pub async fn clear_commit_status(&self, pipeline_id: Uuid) -> Result<()> {
+        let url = self.project_url(&format!("/pipeline/{}/commit-status", pipeline_id));
+        let response = self.send(self.client.delete(&url)).await?;
+
+        self.handle_empty_response(response).await
+    }
+
+    /// Get the success rate, durations, failing stages and trend of a pipeline's recent jobs
+    ///
+    /// # Arguments
+    /// * `pipeline_id` - The pipeline UUID
+    /// * `query` - Days of history to cover and width of the trend buckets
+    pub async fn pipeline_stats(
+        &self,
+        pipeline_id: Uuid,
+        query: &PipelineStatsQuery,
+    ) -> Result<PipelineStats> {
+        let url = self.project_url(&format!("/pipeline/{}/stats", pipeline_id));
+        let response = self
+            .send_idempotent(self.client.get(&url).query(query))
+            .await?;
+
+        self.handle_response(response).await
+    }
+
+    /// List the stages whose outcome keeps flipping on the same pipeline version
+    ///
+    /// # Arguments
+    /// * `pipeline_id` - The pipeline UUID
+    /// * `query` - Days of history to cover and flips needed to flag a stage
+    pub async fn flaky_stages(
+        &self,
+        pipeline_id: Uuid,
+        query: &FlakyStagesQuery,
+    ) -> Result<Vec<FlakyStage>> {
+        let url = self.project_url(&format!("/pipeline/{}/flaky-stages", pipeline_id));
+        let response = self
+            .send_idempotent(self.client.get(&url).query(query))
+            .await?;
+
+        self.handle_response(response).await
+    }
+
+    // =============================================================================
+    // Webhook Triggers
+    // =============================================================================
+
+    /// List the webhook triggers of a pipeline
+    ///
+    /// # Arguments
+    /// * `pipeline_id` - The pipeline UUID
+    pub async fn list_webhook_triggers(&self, pipeline_id: Uuid) -> Result<Vec<WebhookTrigger>> {
+        let url = self.project_url(&format!("/pipeline/{}/webhooks", pipeline_id));
+        let response = self.send_idempotent(self.client.get(&url)).await?;
+
+        self.handle_response(response).await
+    }
+
+    /// Add a webhook trigger to a pipeline (admin)
+    ///
+    /// # Arguments
+    /// * `pipeline_id` - The pipeline UUID
+    /// * `req` - Provider, secret, filters and parameters of the trigger
+    ///
+    /// # Returns
+    /// The created trigger; its provider delivers to `/api/v1/webhooks/{id}`
+    pub async fn create_webhook_trigger(
+        &self,
+        pipeline_id: Uuid,
+        req: &CreateWebhookTrigger,
+    ) -> Result<WebhookTrigger> {
+        let url = self.project_url(&format!("/pipeline/{}/webhooks", pipeline_id));
+        let response = self.send(self.client.post(&url).json(req)).await?;
+
+        self.handle_response(response).await
+    }
+
+    /// Remove a webhook trigger from a pipeline (admin)
+    ///
+    /// # Arguments
+    /// * `pipeline_id` - The pipeline UUID
+    /// * `trigger_id` - The trigger UUID
+    pub async fn delete_webhook_trigger(&self, pipeline_id: Uuid, trigger_id: Uuid) -> Result<()> {
+        let url = self.project_url(&format!(
+            "/pipeline/{}/webhooks/{}",
+            pipeline_id, trigger_id
+        ));
+        let response = self.send(self.client.delete(&url)).await?;
+
+        self.handle_empty_response(response).await
+    }
+
+    // =============================================================================
+    // Schedules
+    // =============================================================================
+
+    /// List the cron schedules of a pipeline
+    ///
+    /// # Arguments
+    /// * `pipeline_id` - The pipeline UUID
+    pub async fn list_schedules(&self, pipeline_id: Uuid) -> Result<Vec<Schedule>> {
+        let url = self.project_url(&format!("/pipeline/{}/schedules", pipeline_id));
+        let response = self.send_idempotent(self.client.get(&url)).await?;
+
+        self.handle_response(response).await
+    }
+
+    /// Add a cron schedule to a pipeline (admin)
+    ///
+    /// # Arguments
+    /// * `pipeline_id` - The pipeline UUID
+    /// * `req` - Cron expression, time zone, catch-up policy and parameters
+    pub async fn create_schedule(
+        &self,
+        pipeline_id: Uuid,
+        req: &CreateSchedule,
+    ) -> Result<Schedule> {
+        let url = self.project_url(&format!("/pipeline/{}/schedules", pipeline_id));
+        let response = self.send(self.client.post(&url).json(req)).await?;
+
+        self.handle_response(response).await
+    }
+
+    /// Remove a cron schedule from a pipeline (admin)
+    ///
+    /// # Arguments
+    /// * `pipeline_id` - The pipeline UUID
+    /// * `schedule_id` - The schedule UUID
+    pub async fn delete_schedule(&self, pipeline_id: Uuid, schedule_id: Uuid) -> Result<()> {
+        let url = self.project_url(&format!(
+            "/pipeline/{}/schedules/{}",
+            pipeline_id, schedule_id
+        ));
+        let response = self.send(self.client.delete(&url)).await?;
+
+        self.handle_empty_response(response).await
+    }
+
+    // =============================================================================
+    // Presets
+    // =============================================================================
+
+    /// List the parameter presets of a pipeline
+    ///
+    /// # Arguments
+    /// * `pipeline_id` - The pipeline UUID
+    pub async fn list_presets(&self, pipeline_id: Uuid) -> Result<Vec<ParameterPreset>> {
+        let url = self.project_url(&format!("/pipeline/{}/presets", pipeline_id));
+        let response = self.send_idempotent(self.client.get(&url)).await?;
+
+        self.handle_response(response).await
+    }
+
+    /// Get a parameter preset of a pipeline
+    ///
+    /// # Arguments
+    /// * `pipeline_id` - The pipeline UUID
+    /// * `name` - Name of the preset
+    pub async fn get_preset(&self, pipeline_id: Uuid, name: &str) -> Result<ParameterPreset> {
+        let url = self.project_url(&format!("/pipeline/{}/presets/{}", pipeline_id, name));
+        let response = self.send_idempotent(self.client.get(&url)).await?;
+
+        self.handle_response(response).await
+    }
+
+    /// Save a parameter preset of a pipeline, replacing one with the same name
+    ///
+    /// # Arguments
+    /// * `pipeline_id` - The pipeline UUID
+    /// * `name` - Name of the preset
+    /// * `req` - Parameters of the preset
+    ///
+    /// # Returns
+    /// The preset as stored
+    pub async fn save_preset(
+        &self,
+        pipeline_id: Uuid,
+        name: &str,
+        req: &SavePreset,
+    ) -> Result<ParameterPreset> {
+        let url = self.project_url(&format!("/pipeline/{}/presets/{}", pipeline_id, name));
+        let response = self.send(self.client.put(&url).json(req)).await?;
+
+        self.handle_response(response).await
+    }
+
+    /// Remove a parameter preset from a pipeline
+    ///
+    /// # Arguments
+    /// * `pipeline_id` - The pipeline UUID
+    /// * `name` - Name of the preset
+    pub async fn delete_preset(&self, pipeline_id: Uuid, name: &str) -> Result<()> {
+        let url = self.project_url(&format!("/pipeline/{}/presets/{}", pipeline_id, name));
+        let response = self.send(self.client.delete(&url)).await?;
 
         self.handle_empty_response(response).await
     }
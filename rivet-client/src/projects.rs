@@ -0,0 +1,62 @@
+//! Project-related API endpoints
+
+use crate::OrchestratorClient;
+use crate::error::Result;
+use rivet_core::domain::project::Project;
+use rivet_core::dto::project::CreateProject;
+
+impl OrchestratorClient {
+    // =============================================================================
+    // Project Management
+    // =============================================================================
+
+    /// Create a new project
+    ///
+    /// # Arguments
+    /// * `req` - Project name and optional description
+    ///
+    /// # Returns
+    /// The created project
+    pub async fn create_project(&self, req: CreateProject) -> Result<Project> {
+        let url = format!("{}/api/v1/projects", self.base_url);
+        let response = self.send(self.client.post(&url).json(&req)).await?;
+
+        self.handle_response(response).await
+    }
+
+    /// List all projects
+    ///
+    /// # Returns
+    /// Every project, by name
+    pub async fn list_projects(&self) -> Result<Vec<Project>> {
+        let url = format!("{}/api/v1/projects", self.base_url);
+        let response = self.send_idempotent(self.client.get(&url)).await?;
+
+        self.handle_response(response).await
+    }
+
+    /// Get a project
+    ///
+    /// # Arguments
+    /// * `project` - Project name or UUID
+    ///
+    /// # Returns
+    /// The project details
+    pub async fn get_project(&self, project: &str) -> Result<Project> {
+        let url = format!("{}/api/v1/projects/{}", self.base_url, project);
+        let response = self.send_idempotent(self.client.get(&url)).await?;
+
+        self.handle_response(response).await
+    }
+
+    /// Delete a project with all its pipelines, jobs and secrets
+    ///
+    /// # Arguments
+    /// * `project` - Project name or UUID
+    pub async fn delete_project(&self, project: &str) -> Result<()> {
+        let url = format!("{}/api/v1/projects/{}", self.base_url, project);
+        let response = self.send(self.client.delete(&url)).await?;
+
+        self.handle_empty_response(response).await
+    }
+}
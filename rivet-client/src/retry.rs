@@ -0,0 +1,89 @@
+//! Retry policy for idempotent requests
+//!
+//! Reads, heartbeats and log uploads are retried on transient failures
+//! (connection errors, timeouts, 429 and 502/503/504 responses) with
+//! exponential backoff and jitter. Non-idempotent calls are only retried on
+//! 429, which the orchestrator answers before doing any work.
+
+use reqwest::StatusCode;
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::time::Duration;
+
+/// Retry configuration for an `OrchestratorClient`
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    /// Retries after the first attempt (0 disables retrying)
+    pub max_retries: u32,
+    /// Delay before the first retry
+    pub initial_backoff: Duration,
+    /// Upper bound for a single delay
+    pub max_backoff: Duration,
+    /// Randomize delays so many clients don't retry in lockstep
+    pub jitter: bool,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_retries: 3,
+            initial_backoff: Duration::from_millis(200),
+            max_backoff: Duration::from_secs(5),
+            jitter: true,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// A policy that never retries
+    pub fn none() -> Self {
+        Self {
+            max_retries: 0,
+            ..Self::default()
+        }
+    }
+
+    /// Delay before retry number `attempt` (starting at 0)
+    ///
+    /// Doubles every attempt up to `max_backoff`. With jitter, the delay is
+    /// picked uniformly between half and all of that value.
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        let factor = 2u32.saturating_pow(attempt);
+        let delay = self
+            .initial_backoff
+            .saturating_mul(factor)
+            .min(self.max_backoff);
+
+        if !self.jitter {
+            return delay;
+        }
+
+        let half = delay / 2;
+        let spread = (delay - half).as_millis() as u64;
+        if spread == 0 {
+            return delay;
+        }
+        half + Duration::from_millis(random_u64() % (spread + 1))
+    }
+
+    /// Whether a response status is worth retrying
+    pub(crate) fn is_retryable_status(status: StatusCode) -> bool {
+        matches!(
+            status,
+            StatusCode::TOO_MANY_REQUESTS
+                | StatusCode::BAD_GATEWAY
+                | StatusCode::SERVICE_UNAVAILABLE
+                | StatusCode::GATEWAY_TIMEOUT
+        )
+    }
+
+    /// Whether a transport error is worth retrying
+    pub(crate) fn is_retryable_error(err: &reqwest::Error) -> bool {
+        err.is_connect() || err.is_timeout() || err.is_request()
+    }
+}
+
+/// Cheap randomness for jitter, without pulling in an RNG crate
+fn random_u64() -> u64 {
+    RandomState::new().build_hasher().finish()
+}
@@ -2,8 +2,11 @@
 
 use crate::OrchestratorClient;
 use crate::error::Result;
-use rivet_core::domain::runner::Runner;
-use rivet_core::dto::runner::RegisterRunner;
+use rivet_core::domain::runner::{RegistrationToken, Runner, RunnerLoad};
+use rivet_core::dto::runner::{
+    CreateRegistrationToken, NewRegistrationToken, RegisterRunner, RunnerRegistration,
+};
+use uuid::Uuid;
 
 impl OrchestratorClient {
     // =============================================================================
@@ -31,14 +34,54 @@ impl OrchestratorClient {
     /// # }
     /// ```
     pub async fn register_runner(&self, runner_id: &str) -> Result<Runner> {
-        let url = format!("{}/api/runners/register", self.base_url);
+        self.register_runner_with_token(runner_id, None)
+            .await
+            .map(|registration| registration.runner)
+    }
+
+    /// Register a runner, presenting a registration token
+    ///
+    /// # Arguments
+    /// * `runner_id` - Unique identifier for this runner
+    /// * `registration_token` - Token minted by an admin, required by
+    ///   orchestrators that enforce runner authentication
+    ///
+    /// # Returns
+    /// The registered runner and the credential to send on job calls
+    /// (see `ClientBuilder::runner_credential`)
+    pub async fn register_runner_with_token(
+        &self,
+        runner_id: &str,
+        registration_token: Option<&str>,
+    ) -> Result<RunnerRegistration> {
+        self.register_runner_with(&RegisterRunner {
+            runner_id: runner_id.to_string(),
+            registration_token: registration_token.map(str::to_string),
+            tags: Vec::new(),
+            plugins: Vec::new(),
+            info: None,
+        })
+        .await
+    }
+
+    /// Register a runner along with the tags and plugins it provides
+    ///
+    /// Jobs of pipelines requiring tags or plugins are only handed to runners
+    /// that registered all of them.
+    ///
+    /// # Arguments
+    /// * `registration` - The runner ID, token and capabilities
+    ///
+    /// # Returns
+    /// The registered runner and the credential to send on job calls
+    pub async fn register_runner_with(
+        &self,
+        registration: &RegisterRunner,
+    ) -> Result<RunnerRegistration> {
+        let url = format!("{}/api/v1/runners/register", self.base_url);
+        // Registration is an upsert, so it is safe to retry
         let response = self
-            .client
-            .post(&url)
-            .json(&RegisterRunner {
-                runner_id: runner_id.to_string(),
-            })
-            .send()
+            .send_idempotent(self.client.post(&url).json(registration))
             .await?;
 
         self.handle_response(response).await
@@ -51,9 +94,15 @@ impl OrchestratorClient {
     ///
     /// # Arguments
     /// * `runner_id` - The ID of the runner sending the heartbeat
-    pub async fn send_heartbeat(&self, runner_id: &str) -> Result<()> {
-        let url = format!("{}/api/runners/{}/heartbeat", self.base_url, runner_id);
-        let response = self.client.post(&url).send().await?;
+    /// * `load` - The runner's current load, used to send jobs to the least
+    ///   loaded runners
+    pub async fn send_heartbeat(&self, runner_id: &str, load: Option<RunnerLoad>) -> Result<()> {
+        let url = format!("{}/api/v1/runners/{}/heartbeat", self.base_url, runner_id);
+        let mut request = self.client.post(&url);
+        if let Some(load) = &load {
+            request = request.json(load);
+        }
+        let response = self.send_idempotent(request).await?;
 
         self.handle_empty_response(response).await
     }
@@ -67,8 +116,8 @@ impl OrchestratorClient {
     /// # Returns
     /// A list of all runners
     pub async fn list_runners(&self) -> Result<Vec<Runner>> {
-        let url = format!("{}/api/runners", self.base_url);
-        let response = self.client.get(&url).send().await?;
+        let url = format!("{}/api/v1/runners", self.base_url);
+        let response = self.send_idempotent(self.client.get(&url)).await?;
 
         self.handle_response(response).await
     }
@@ -81,19 +130,63 @@ impl OrchestratorClient {
     /// # Returns
     /// The runner details
     pub async fn get_runner(&self, runner_id: &str) -> Result<Runner> {
-        let url = format!("{}/api/runners/{}", self.base_url, runner_id);
-        let response = self.client.get(&url).send().await?;
+        let url = format!("{}/api/v1/runners/{}", self.base_url, runner_id);
+        let response = self.send_idempotent(self.client.get(&url)).await?;
 
         self.handle_response(response).await
     }
 
-    /// Delete a runner registration
+    /// Delete a runner registration (requires the admin token)
     ///
     /// # Arguments
     /// * `runner_id` - The runner ID to delete
     pub async fn delete_runner(&self, runner_id: &str) -> Result<()> {
-        let url = format!("{}/api/runners/{}", self.base_url, runner_id);
-        let response = self.client.delete(&url).send().await?;
+        let url = format!("{}/api/v1/runners/{}", self.base_url, runner_id);
+        let response = self.send(self.client.delete(&url)).await?;
+
+        self.handle_empty_response(response).await
+    }
+
+    // =============================================================================
+    // Registration Tokens
+    // =============================================================================
+
+    /// Mint a runner registration token (requires the admin token)
+    ///
+    /// # Arguments
+    /// * `description` - What the token is for
+    ///
+    /// # Returns
+    /// The token, including its secret; the secret cannot be retrieved again
+    pub async fn create_registration_token(
+        &self,
+        description: Option<&str>,
+    ) -> Result<NewRegistrationToken> {
+        let url = format!("{}/api/v1/runners/tokens", self.base_url);
+        let response = self
+            .send(self.client.post(&url).json(&CreateRegistrationToken {
+                description: description.map(str::to_string),
+            }))
+            .await?;
+
+        self.handle_response(response).await
+    }
+
+    /// List runner registration tokens (requires the admin token)
+    pub async fn list_registration_tokens(&self) -> Result<Vec<RegistrationToken>> {
+        let url = format!("{}/api/v1/runners/tokens", self.base_url);
+        let response = self.send_idempotent(self.client.get(&url)).await?;
+
+        self.handle_response(response).await
+    }
+
+    /// Revoke a runner registration token (requires the admin token)
+    ///
+    /// # Arguments
+    /// * `token_id` - ID of the token to revoke
+    pub async fn delete_registration_token(&self, token_id: Uuid) -> Result<()> {
+        let url = format!("{}/api/v1/runners/tokens/{}", self.base_url, token_id);
+        let response = self.send(self.client.delete(&url)).await?;
 
         self.handle_empty_response(response).await
     }
@@ -0,0 +1,57 @@
+//! Secret-related API endpoints
+
+use crate::OrchestratorClient;
+use crate::error::Result;
+use rivet_core::domain::secret::Secret;
+use rivet_core::dto::secret::{SecretScope, SetSecret};
+use uuid::Uuid;
+
+impl OrchestratorClient {
+    // =============================================================================
+    // Secret Management
+    // =============================================================================
+
+    /// Create or replace a secret (requires the admin token)
+    ///
+    /// # Arguments
+    /// * `req` - Secret name, value and optional pipeline scope
+    ///
+    /// # Returns
+    /// The secret metadata (the value is never returned)
+    pub async fn set_secret(&self, req: SetSecret) -> Result<Secret> {
+        let url = self.project_url("/secrets");
+        let response = self.send(self.client.put(&url).json(&req)).await?;
+
+        self.handle_response(response).await
+    }
+
+    /// List secrets in a scope
+    ///
+    /// # Arguments
+    /// * `pipeline_id` - Pipeline scope, `None` for project-wide secrets
+    ///
+    /// # Returns
+    /// Secret metadata
+    pub async fn list_secrets(&self, pipeline_id: Option<Uuid>) -> Result<Vec<Secret>> {
+        let url = self.project_url("/secrets");
+        let response = self
+            .send_idempotent(self.client.get(&url).query(&SecretScope { pipeline_id }))
+            .await?;
+
+        self.handle_response(response).await
+    }
+
+    /// Delete a secret (requires the admin token)
+    ///
+    /// # Arguments
+    /// * `name` - Secret name
+    /// * `pipeline_id` - Pipeline scope, `None` for project-wide secrets
+    pub async fn delete_secret(&self, name: &str, pipeline_id: Option<Uuid>) -> Result<()> {
+        let url = self.project_url(&format!("/secrets/{}", name));
+        let response = self
+            .send(self.client.delete(&url).query(&SecretScope { pipeline_id }))
+            .await?;
+
+        self.handle_empty_response(response).await
+    }
+}
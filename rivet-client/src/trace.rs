@@ -0,0 +1,68 @@
+//! Request correlation headers
+//!
+//! Every request carries an `X-Request-Id` and a W3C `traceparent` header so
+//! CLI and runner logs can be matched with orchestrator logs and traces.
+//! Both are generated once per logical call and reused across retries.
+//!
+//! When the calling span is exported to OpenTelemetry, `traceparent` names it,
+//! so the orchestrator's spans join the caller's trace.
+
+use uuid::Uuid;
+
+/// Header carrying the per-request correlation id
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+
+/// W3C Trace Context header
+pub const TRACEPARENT_HEADER: &str = "traceparent";
+
+/// Correlation identifiers attached to a single logical request
+#[derive(Debug, Clone)]
+pub(crate) struct RequestContext {
+    /// Value of the `X-Request-Id` header
+    pub request_id: String,
+    /// Value of the `traceparent` header
+    pub traceparent: String,
+}
+
+impl RequestContext {
+    /// Creates a context continuing the current span's trace, or starting a
+    /// new trace (version 00, sampled) if the span is not exported
+    pub fn new() -> Self {
+        let traceparent = rivet_core::telemetry::traceparent(&tracing::Span::current())
+            .unwrap_or_else(|| {
+                let trace_id = Uuid::new_v4().simple().to_string();
+                let span_id = Uuid::new_v4().simple().to_string()[..16].to_string();
+                format!("00-{}-{}-01", trace_id, span_id)
+            });
+
+        Self {
+            request_id: Uuid::new_v4().to_string(),
+            traceparent,
+        }
+    }
+
+    /// Adds the correlation headers to a request
+    pub fn apply(&self, request: reqwest::RequestBuilder) -> reqwest::RequestBuilder {
+        request
+            .header(REQUEST_ID_HEADER, &self.request_id)
+            .header(TRACEPARENT_HEADER, &self.traceparent)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_traceparent_format() {
+        let ctx = RequestContext::new();
+        let parts: Vec<&str> = ctx.traceparent.split('-').collect();
+
+        assert_eq!(parts.len(), 4);
+        assert_eq!(parts[0], "00");
+        assert_eq!(parts[1].len(), 32);
+        assert_eq!(parts[2].len(), 16);
+        assert_eq!(parts[3], "01");
+        assert!(parts[1].chars().all(|c| c.is_ascii_hexdigit()));
+    }
+}
@@ -0,0 +1,18 @@
+//! Artifact domain types
+
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// A file produced by a job and stored by the orchestrator
+///
+/// Only metadata travels in this struct; the content is downloaded separately.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Artifact {
+    pub id: Uuid,
+    pub job_id: Uuid,
+    pub name: String,
+    pub size_bytes: i64,
+    /// Hex encoded SHA-256 of the content
+    pub sha256: String,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
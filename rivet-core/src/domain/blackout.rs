@@ -0,0 +1,34 @@
+//! Blackout window domain types
+
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// Period during which the jobs of a pipeline, or the jobs deploying to an
+/// environment, are not started
+///
+/// Jobs launched during the window stay queued and start once it closes,
+/// unless they were launched with `override_blackout`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BlackoutWindow {
+    pub id: Uuid,
+    pub project_id: Uuid,
+    /// Pipeline whose jobs are deferred
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pipeline_id: Option<Uuid>,
+    /// Environment the deferred jobs deploy to
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub environment: Option<String>,
+    pub starts_at: chrono::DateTime<chrono::Utc>,
+    /// End of the window, exclusive
+    pub ends_at: chrono::DateTime<chrono::Utc>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+impl BlackoutWindow {
+    /// Whether the window is open at `now`
+    pub fn is_active(&self, now: chrono::DateTime<chrono::Utc>) -> bool {
+        self.starts_at <= now && now < self.ends_at
+    }
+}
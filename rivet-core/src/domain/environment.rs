@@ -0,0 +1,87 @@
+//! Environment domain types
+
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::domain::job::{CommitRef, StageStatus};
+
+/// Where a project's pipelines deploy (e.g. dev, staging, prod)
+///
+/// Environments are created when a stage first deploys to them, or ahead of
+/// time to require approvals.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Environment {
+    pub name: String,
+    /// Project the environment belongs to
+    pub project_id: Uuid,
+    pub description: Option<String>,
+    /// Distinct approvals a job needs before it may run, when one of its
+    /// stages deploys to the environment
+    pub required_approvals: u32,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+/// Status of a deployment, following the stage that performs it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DeploymentStatus {
+    Running,
+    Succeeded,
+    Failed,
+}
+
+impl DeploymentStatus {
+    /// Status of the deployment a deploying stage performs
+    ///
+    /// # Returns
+    /// `None` if the stage deploys nothing (skipped, or reused from the job
+    /// a resumed job resumes)
+    pub fn of_stage(status: StageStatus) -> Option<Self> {
+        match status {
+            StageStatus::Running => Some(DeploymentStatus::Running),
+            StageStatus::Succeeded => Some(DeploymentStatus::Succeeded),
+            StageStatus::Failed | StageStatus::AllowedFailure => Some(DeploymentStatus::Failed),
+            StageStatus::Skipped | StageStatus::Reused => None,
+        }
+    }
+}
+
+/// A run of a stage deploying to an environment
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Deployment {
+    pub id: Uuid,
+    pub environment: String,
+    pub pipeline_id: Uuid,
+    pub job_id: Uuid,
+    /// Attempt of the job that deployed
+    pub attempt: u32,
+    pub stage: String,
+    pub status: DeploymentStatus,
+    /// Commit the job built, if it was launched for one
+    #[serde(default)]
+    pub commit: Option<CommitRef>,
+    pub started_at: chrono::DateTime<chrono::Utc>,
+    pub completed_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+/// Approval of a job's deployments to an environment
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Approval {
+    pub job_id: Uuid,
+    pub environment: String,
+    pub approver: String,
+    pub comment: Option<String>,
+    pub approved_at: chrono::DateTime<chrono::Utc>,
+}
+
+/// Lock of an environment, held by the job deploying to it
+///
+/// Only one job deploying to an environment runs at a time; the others wait
+/// in the `Blocked` state. The lock is held until the job finishes, across
+/// its retries.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EnvironmentLock {
+    pub environment: String,
+    pub job_id: Uuid,
+    pub pipeline_id: Uuid,
+    pub acquired_at: chrono::DateTime<chrono::Utc>,
+}
@@ -1,6 +1,7 @@
 //! Job domain types
 
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use uuid::Uuid;
 
 /// Job execution record
@@ -9,6 +10,9 @@ use uuid::Uuid;
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Job {
     pub id: Uuid,
+    /// Number of the job within its pipeline, starting at 1 (e.g. `#42`)
+    #[serde(default)]
+    pub number: u64,
     pub pipeline_id: Uuid,
     pub status: JobStatus,
     pub requested_at: chrono::DateTime<chrono::Utc>,
@@ -17,6 +21,78 @@ pub struct Job {
     pub runner_id: Option<String>,
     pub parameters: std::collections::HashMap<String, serde_json::Value>,
     pub result: Option<JobResult>,
+    /// Current attempt, starting at 1
+    #[serde(default = "first_attempt")]
+    pub attempt: u32,
+    /// Attempts allowed before giving up (the pipeline's `max_retries` + 1)
+    #[serde(default = "first_attempt")]
+    pub max_attempts: u32,
+    /// Why the job was moved to the dead-letter queue
+    #[serde(default)]
+    pub dead_letter_reason: Option<String>,
+    /// Job this one resumes, skipping the stages that succeeded there
+    #[serde(default)]
+    pub resumed_from: Option<Uuid>,
+    /// Commit the job builds, whose status is reported to its Git provider
+    #[serde(default)]
+    pub commit: Option<CommitRef>,
+    /// Free-form `key=value` labels, given at launch or set by the pipeline
+    /// through the `annotations` module
+    #[serde(default)]
+    pub labels: BTreeMap<String, String>,
+}
+
+/// Most labels a job may carry
+pub const MAX_JOB_LABELS: usize = 32;
+
+/// Longest label key, in characters
+pub const MAX_LABEL_KEY_LENGTH: usize = 63;
+
+/// Longest label value, in characters
+pub const MAX_LABEL_VALUE_LENGTH: usize = 255;
+
+/// Check a job label
+///
+/// Keys are 1 to 63 letters, digits, `-`, `_`, `.` or `/` (e.g. `team`,
+/// `acme.io/cost-center`); values are at most 255 characters, without commas,
+/// which separate the labels of a filter, or control characters.
+///
+/// # Returns
+/// Why the label is invalid, if it is
+pub fn validate_label(key: &str, value: &str) -> Result<(), String> {
+    let key_chars = |c: char| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.' | '/');
+    if key.is_empty() || key.chars().count() > MAX_LABEL_KEY_LENGTH || !key.chars().all(key_chars) {
+        return Err(format!(
+            "Label key '{}' must be 1 to {} letters, digits, '-', '_', '.' or '/'",
+            key, MAX_LABEL_KEY_LENGTH
+        ));
+    }
+    if value.chars().count() > MAX_LABEL_VALUE_LENGTH {
+        return Err(format!(
+            "Value of label '{}' is longer than {} characters",
+            key, MAX_LABEL_VALUE_LENGTH
+        ));
+    }
+    if value.chars().any(|c| c == ',' || c.is_control()) {
+        return Err(format!(
+            "Value of label '{}' must not contain commas or control characters",
+            key
+        ));
+    }
+    Ok(())
+}
+
+/// A commit of a repository hosted on GitHub or GitLab
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CommitRef {
+    /// Repository path, e.g. `owner/repo` (GitLab: the project's full path)
+    pub repository: String,
+    /// Full commit SHA
+    pub sha: String,
+}
+
+fn first_attempt() -> u32 {
+    1
 }
 
 /// Job execution status
@@ -28,6 +104,70 @@ pub enum JobStatus {
     Failed,
     Cancelled,
     TimedOut,
+    /// Gave up after repeated failures, waiting for an operator to requeue it
+    DeadLettered,
+    /// Queued behind the job holding the lock of an environment it deploys to
+    Blocked,
+}
+
+/// Execution status of a single stage of a job
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum StageStatus {
+    Running,
+    Succeeded,
+    Failed,
+    /// The stage's condition was not met, or it does not run after how the
+    /// stages it waits for ended
+    Skipped,
+    /// Not run again: it succeeded in the job this one resumes
+    Reused,
+    /// Failed, but the stage allows failure so the job does not fail
+    AllowedFailure,
+}
+
+impl StageStatus {
+    /// Whether a resumed job may skip a stage that ended with this status
+    pub fn is_done(self) -> bool {
+        matches!(self, StageStatus::Succeeded | StageStatus::Reused)
+    }
+}
+
+/// Execution record of a single stage of a job
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StageRun {
+    pub name: String,
+    pub status: StageStatus,
+    pub started_at: chrono::DateTime<chrono::Utc>,
+    pub completed_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub error_message: Option<String>,
+    /// Stages this one declared it needs
+    #[serde(default)]
+    pub needs: Vec<String>,
+    /// What the stage's script returned, when it succeeded
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub result: Option<StageResult>,
+}
+
+/// Result a stage script returns:
+/// `{ artifacts = {...}, metrics = { tests = 124 }, summary = "..." }`
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct StageResult {
+    /// Files the stage produced, as the script names them
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub artifacts: Vec<String>,
+    /// Numbers the stage measured, by name
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub metrics: BTreeMap<String, f64>,
+    /// One-line account of what the stage did
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub summary: Option<String>,
+}
+
+impl StageResult {
+    /// Whether the script returned nothing worth keeping
+    pub fn is_empty(&self) -> bool {
+        self.artifacts.is_empty() && self.metrics.is_empty() && self.summary.is_none()
+    }
 }
 
 /// Result of a job execution
@@ -4,7 +4,15 @@
 //! These types represent the fundamental business entities and are shared between
 //! orchestrator (for persistence) and runner (for execution).
 
+pub mod artifact;
+pub mod blackout;
+pub mod environment;
 pub mod job;
 pub mod log;
 pub mod pipeline;
+pub mod preset;
+pub mod project;
 pub mod runner;
+pub mod schedule;
+pub mod secret;
+pub mod webhook;
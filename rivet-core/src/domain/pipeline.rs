@@ -1,9 +1,12 @@
 //! Pipeline domain types
 
+use std::fmt;
+use std::str::FromStr;
+
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Tag {
     pub key: String,
     pub value: String,
@@ -15,10 +18,88 @@ pub struct Tag {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Pipeline {
     pub id: Uuid,
+    /// Project the pipeline belongs to
+    pub project_id: Uuid,
     pub name: String,
     pub description: Option<String>,
     pub script: String,
     pub created_at: chrono::DateTime<chrono::Utc>,
     pub updated_at: chrono::DateTime<chrono::Utc>,
     pub tags: Vec<Tag>,
+    /// Plugins a runner must provide to run the pipeline
+    #[serde(default)]
+    pub plugins: Vec<String>,
+    /// When the pipeline was archived; archived pipelines keep their jobs
+    /// but are left out of listings and launch no new jobs
+    #[serde(default)]
+    pub archived_at: Option<chrono::DateTime<chrono::Utc>>,
+    /// Whether launching new jobs of the pipeline is paused
+    #[serde(default)]
+    pub disabled: bool,
+}
+
+/// Type of a pipeline input, as written in pipeline scripts
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum InputType {
+    String,
+    Number,
+    Bool,
+    /// One of the input's `options`
+    Enum,
+    /// A list of strings, numbers or bools (of the input's `items` type if set)
+    Array,
+    /// A string kept out of job records and masked in logs
+    Secret,
+    /// A file sent with the launch and placed in the job's workspace
+    File,
+}
+
+impl InputType {
+    /// Name of the type as written in pipeline scripts
+    pub fn as_str(self) -> &'static str {
+        match self {
+            InputType::String => "string",
+            InputType::Number => "number",
+            InputType::Bool => "bool",
+            InputType::Enum => "enum",
+            InputType::Array => "array",
+            InputType::Secret => "secret",
+            InputType::File => "file",
+        }
+    }
+
+    /// Whether values are a single string, number or bool
+    pub fn is_scalar(self) -> bool {
+        matches!(
+            self,
+            InputType::String | InputType::Number | InputType::Bool
+        )
+    }
+}
+
+impl FromStr for InputType {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "string" => Ok(InputType::String),
+            "number" => Ok(InputType::Number),
+            "bool" => Ok(InputType::Bool),
+            "enum" => Ok(InputType::Enum),
+            "array" => Ok(InputType::Array),
+            "secret" => Ok(InputType::Secret),
+            "file" => Ok(InputType::File),
+            other => Err(format!(
+                "Unknown input type '{}' (expected string, number, bool, enum, array, secret or file)",
+                other
+            )),
+        }
+    }
+}
+
+impl fmt::Display for InputType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
 }
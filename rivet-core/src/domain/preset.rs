@@ -0,0 +1,41 @@
+//! Parameter preset domain types
+
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// Longest preset name, in characters
+pub const MAX_PRESET_NAME_LENGTH: usize = 64;
+
+/// Parameters saved under a name for a pipeline's launches (e.g. `nightly`),
+/// merged into a launch before it prompts for the inputs left out
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ParameterPreset {
+    pub pipeline_id: Uuid,
+    pub name: String,
+    pub parameters: HashMap<String, serde_json::Value>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    pub updated_at: chrono::DateTime<chrono::Utc>,
+}
+
+/// Check a preset name
+///
+/// Names are 1 to 64 letters, digits, `-`, `_` or `.`, so that they can be
+/// written in URLs as they are.
+///
+/// # Returns
+/// Why the name is invalid, if it is
+pub fn validate_preset_name(name: &str) -> Result<(), String> {
+    let name_chars = |c: char| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.');
+    if name.is_empty()
+        || name.chars().count() > MAX_PRESET_NAME_LENGTH
+        || !name.chars().all(name_chars)
+    {
+        return Err(format!(
+            "Must be 1 to {} letters, digits, '-', '_' or '.'",
+            MAX_PRESET_NAME_LENGTH
+        ));
+    }
+    Ok(())
+}
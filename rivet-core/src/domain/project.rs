@@ -0,0 +1,23 @@
+//! Project domain types
+
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// Name of the project that owns resources created without an explicit project
+pub const DEFAULT_PROJECT_NAME: &str = "default";
+
+/// Id of the default project, fixed so existing data can be backfilled into it
+pub const DEFAULT_PROJECT_ID: Uuid = Uuid::from_u128(1);
+
+/// Project (namespace)
+///
+/// Pipelines, their jobs and secrets belong to exactly one project.
+/// Projects are addressed in the API either by id or by name.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Project {
+    pub id: Uuid,
+    /// Unique, URL-safe name (lowercase letters, digits and '-')
+    pub name: String,
+    pub description: Option<String>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
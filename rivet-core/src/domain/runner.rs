@@ -4,6 +4,9 @@
 
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::domain::pipeline::Tag;
 
 /// A runner that can execute jobs
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -19,6 +22,84 @@ pub struct Runner {
 
     /// Current status of the runner
     pub status: RunnerStatus,
+
+    /// Tags the runner provides, matched against the `runner` tags pipelines require
+    #[serde(default)]
+    pub tags: Vec<Tag>,
+
+    /// Plugins the runner provides, matched against the `plugins` pipelines require
+    #[serde(default)]
+    pub plugins: Vec<String>,
+
+    /// Load reported in the runner's last heartbeat, if it reported any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub load: Option<RunnerLoad>,
+
+    /// What the runner reported about itself when it registered; absent for
+    /// runners older than the version handshake
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub info: Option<RunnerInfo>,
+}
+
+impl Runner {
+    /// Whether the runner provides every tag and plugin a pipeline requires
+    pub fn provides(&self, tags: &[Tag], plugins: &[String]) -> bool {
+        tags.iter().all(|tag| self.tags.contains(tag))
+            && plugins.iter().all(|plugin| self.plugins.contains(plugin))
+    }
+}
+
+/// Build and host of a runner, reported when it registers
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct RunnerInfo {
+    /// Release of the runner (e.g. `0.1.0`)
+    pub version: String,
+
+    /// Operating system, as in `std::env::consts::OS` (e.g. `linux`)
+    pub os: String,
+
+    /// CPU architecture, as in `std::env::consts::ARCH` (e.g. `x86_64`)
+    pub arch: String,
+
+    /// Host the runner runs on
+    pub hostname: String,
+
+    /// Version of the runner protocol the runner speaks
+    /// (see `dto::runner::RUNNER_PROTOCOL_VERSION`)
+    pub protocol_version: u32,
+}
+
+/// Load of a runner, reported with each heartbeat
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct RunnerLoad {
+    /// Jobs the runner is executing
+    pub running_jobs: u32,
+
+    /// Jobs the runner executes at most at once
+    pub max_parallel_jobs: u32,
+
+    /// One-minute load average divided by the number of CPUs
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cpu_load: Option<f64>,
+
+    /// Fraction of memory in use, between 0 and 1
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub memory_load: Option<f64>,
+}
+
+/// A token that lets runners register with the orchestrator
+///
+/// Minted by an admin; the secret itself is only shown once, at creation.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RegistrationToken {
+    /// Unique identifier, used to revoke the token
+    pub id: Uuid,
+
+    /// What the token is for (e.g. "build farm")
+    pub description: Option<String>,
+
+    /// When the token was minted
+    pub created_at: DateTime<Utc>,
 }
 
 /// Status of a runner
@@ -0,0 +1,69 @@
+//! Cron schedule domain types
+
+use std::collections::HashMap;
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// Launches a pipeline's jobs at the times a cron expression matches
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Schedule {
+    pub id: Uuid,
+    pub pipeline_id: Uuid,
+    /// Cron expression (`minute hour day-of-month month day-of-week`, or a
+    /// nickname such as `@daily`)
+    pub cron: String,
+    /// IANA time zone the expression is evaluated in (e.g. `Europe/Madrid`),
+    /// so its times follow daylight saving changes
+    pub timezone: String,
+    pub catch_up: CatchUpPolicy,
+    /// Parameters of the launched jobs
+    pub parameters: HashMap<String, serde_json::Value>,
+    /// Next time the schedule launches a job
+    pub next_run_at: chrono::DateTime<chrono::Utc>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_run_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+/// What a schedule does about the times it missed while the orchestrator
+/// was down
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CatchUpPolicy {
+    /// Launch nothing for missed times
+    Skip,
+    /// Launch a single job for all the missed times
+    #[default]
+    RunOnce,
+    /// Launch a job for every missed time
+    RunAll,
+}
+
+impl CatchUpPolicy {
+    /// Name of the policy as written in requests
+    pub fn as_str(self) -> &'static str {
+        match self {
+            CatchUpPolicy::Skip => "skip",
+            CatchUpPolicy::RunOnce => "run_once",
+            CatchUpPolicy::RunAll => "run_all",
+        }
+    }
+}
+
+impl FromStr for CatchUpPolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "skip" => Ok(CatchUpPolicy::Skip),
+            "run_once" => Ok(CatchUpPolicy::RunOnce),
+            "run_all" => Ok(CatchUpPolicy::RunAll),
+            other => Err(format!(
+                "Unknown catch-up policy '{}' (expected skip, run_once or run_all)",
+                other
+            )),
+        }
+    }
+}
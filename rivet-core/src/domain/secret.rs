@@ -0,0 +1,19 @@
+//! Secret domain types
+
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// Secret metadata
+///
+/// The value is write-only: it is never part of this struct nor of any API response.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Secret {
+    pub id: Uuid,
+    pub name: String,
+    /// Project the secret belongs to
+    pub project_id: Uuid,
+    /// Pipeline the secret is scoped to, `None` for global secrets
+    pub pipeline_id: Option<Uuid>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    pub updated_at: chrono::DateTime<chrono::Utc>,
+}
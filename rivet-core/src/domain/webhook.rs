@@ -0,0 +1,41 @@
+//! Webhook trigger domain types
+
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::dto::commit_status::GitProvider;
+
+/// Launches a pipeline's jobs from the push events a Git provider delivers
+/// to `/api/v1/webhooks/{id}`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WebhookTrigger {
+    pub id: Uuid,
+    pub pipeline_id: Uuid,
+    pub provider: GitProvider,
+    /// Name of the secret deliveries are verified with, among the secrets
+    /// visible to the pipeline
+    pub secret: String,
+    pub filters: TriggerFilters,
+    /// Parameters of the launched jobs; in string values, `{branch}`, `{tag}`,
+    /// `{ref}`, `{sha}` and `{repository}` are replaced from the push
+    pub parameters: HashMap<String, serde_json::Value>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+/// Pushes a webhook trigger launches jobs for
+///
+/// Patterns are globs (`*` stays within a path segment, `**` spans segments).
+/// Without branch nor tag patterns, every push matches; with only one kind,
+/// pushes of the other kind never match. Path patterns only apply to branch
+/// pushes, which then match when at least one changed file matches.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TriggerFilters {
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub branches: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub paths: Vec<String>,
+}
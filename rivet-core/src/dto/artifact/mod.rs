@@ -0,0 +1,187 @@
+//! Artifact DTOs
+//!
+//! Retention limits of a pipeline's artifacts, and what garbage collection
+//! found or removed.
+
+use std::collections::HashSet;
+
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::domain::artifact::Artifact;
+
+/// Limits on the artifacts kept for a pipeline
+/// (`/api/v1/pipeline/{id}/artifact-retention`)
+///
+/// Unset limits do not apply; a pipeline without any keeps its artifacts
+/// until its jobs are deleted. Artifacts of queued and running jobs are
+/// never collected.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ArtifactRetention {
+    /// Keep the artifacts of this many most recent jobs only
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub keep_last_jobs: Option<u32>,
+    /// Keep at most this many bytes of artifacts, newest jobs first
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_total_bytes: Option<u64>,
+    /// Delete artifacts older than this many seconds
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ttl_seconds: Option<u64>,
+}
+
+impl ArtifactRetention {
+    /// Whether no limit is set
+    pub fn is_unlimited(&self) -> bool {
+        self == &Self::default()
+    }
+
+    /// Pick the artifacts of a pipeline these limits expire
+    ///
+    /// # Arguments
+    /// * `artifacts` - Artifacts of the pipeline's finished jobs, newest job
+    ///   first, and each job's newest artifact first
+    /// * `now` - Time the TTL is measured from
+    ///
+    /// # Returns
+    /// The expired artifacts, each with the first limit it breaks
+    pub fn expire<'a>(
+        &self,
+        artifacts: &'a [Artifact],
+        now: chrono::DateTime<chrono::Utc>,
+    ) -> Vec<(&'a Artifact, ExpiryReason)> {
+        let mut jobs = HashSet::new();
+        let mut kept_bytes: u64 = 0;
+        let mut expired = Vec::new();
+
+        for artifact in artifacts {
+            jobs.insert(artifact.job_id);
+            let size = artifact.size_bytes.max(0) as u64;
+
+            let reason = if self
+                .keep_last_jobs
+                .is_some_and(|keep| jobs.len() > keep as usize)
+            {
+                Some(ExpiryReason::KeepLastJobs)
+            } else if self.ttl_seconds.is_some_and(|ttl| {
+                now.signed_duration_since(artifact.created_at).num_seconds() > ttl as i64
+            }) {
+                Some(ExpiryReason::Ttl)
+            } else if self
+                .max_total_bytes
+                .is_some_and(|max| kept_bytes + size > max)
+            {
+                Some(ExpiryReason::MaxTotalBytes)
+            } else {
+                None
+            };
+
+            match reason {
+                Some(reason) => expired.push((artifact, reason)),
+                None => kept_bytes += size,
+            }
+        }
+
+        expired
+    }
+}
+
+/// Which retention limit expired an artifact
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ExpiryReason {
+    /// Its job is older than the `keep_last_jobs` most recent ones
+    KeepLastJobs,
+    /// Keeping it would exceed `max_total_bytes`
+    MaxTotalBytes,
+    /// It is older than `ttl_seconds`
+    Ttl,
+}
+
+/// An artifact retention limits expire
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExpiredArtifact {
+    pub pipeline_id: Uuid,
+    #[serde(flatten)]
+    pub artifact: Artifact,
+    pub reason: ExpiryReason,
+}
+
+/// Artifacts stored by the orchestrator (`GET /api/v1/artifacts/usage`)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ArtifactUsage {
+    pub artifacts: u64,
+    pub bytes: u64,
+    /// Usage of each pipeline with artifacts, largest first
+    pub pipelines: Vec<PipelineArtifactUsage>,
+}
+
+/// Artifacts stored for one pipeline's jobs
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PipelineArtifactUsage {
+    pub pipeline_id: Uuid,
+    pub artifacts: u64,
+    pub bytes: u64,
+}
+
+/// Outcome of an artifact garbage collection (`/api/v1/artifacts/gc`)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ArtifactGcReport {
+    /// Whether the expired artifacts were only reported, not deleted
+    pub dry_run: bool,
+    pub expired: Vec<ExpiredArtifact>,
+    /// Bytes taken by the expired artifacts
+    pub expired_bytes: u64,
+    /// Usage before the collection
+    pub usage: ArtifactUsage,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn artifact(job_id: Uuid, size_bytes: i64, age_seconds: i64) -> Artifact {
+        Artifact {
+            id: Uuid::new_v4(),
+            job_id,
+            name: "build.tar.gz".to_string(),
+            size_bytes,
+            sha256: String::new(),
+            created_at: chrono::Utc::now() - chrono::Duration::seconds(age_seconds),
+        }
+    }
+
+    #[test]
+    fn test_expire_applies_every_limit() {
+        let (newest, middle, oldest) = (Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4());
+        let artifacts = vec![
+            artifact(newest, 60, 10),
+            artifact(middle, 50, 20),
+            artifact(middle, 10, 5000),
+            artifact(oldest, 1, 30),
+        ];
+        let retention = ArtifactRetention {
+            keep_last_jobs: Some(2),
+            max_total_bytes: Some(100),
+            ttl_seconds: Some(3600),
+        };
+
+        let reasons: Vec<ExpiryReason> = retention
+            .expire(&artifacts, chrono::Utc::now())
+            .into_iter()
+            .map(|(_, reason)| reason)
+            .collect();
+
+        assert_eq!(
+            reasons,
+            [
+                ExpiryReason::MaxTotalBytes,
+                ExpiryReason::Ttl,
+                ExpiryReason::KeepLastJobs
+            ]
+        );
+        assert!(
+            ArtifactRetention::default()
+                .expire(&artifacts, chrono::Utc::now())
+                .is_empty()
+        );
+    }
+}
@@ -0,0 +1,21 @@
+//! Blackout window DTOs
+//!
+//! Data transfer objects for the blackout windows deferring jobs.
+
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// Request to add a blackout window, for either a pipeline or an environment
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CreateBlackoutWindow {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pipeline_id: Option<Uuid>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub environment: Option<String>,
+    /// Start of the window, now if omitted
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub starts_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub ends_at: chrono::DateTime<chrono::Utc>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+}
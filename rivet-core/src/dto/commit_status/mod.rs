@@ -0,0 +1,72 @@
+//! Commit status DTOs
+//!
+//! How a pipeline reports the status of the commits its jobs build back to
+//! GitHub or GitLab.
+
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+/// Git hosting provider a commit status is reported to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum GitProvider {
+    Github,
+    Gitlab,
+}
+
+impl GitProvider {
+    /// Name of the provider as written in configs
+    pub fn as_str(self) -> &'static str {
+        match self {
+            GitProvider::Github => "github",
+            GitProvider::Gitlab => "gitlab",
+        }
+    }
+
+    /// API base URL of the provider's public instance
+    pub fn default_api_url(self) -> &'static str {
+        match self {
+            GitProvider::Github => "https://api.github.com",
+            GitProvider::Gitlab => "https://gitlab.com/api/v4",
+        }
+    }
+}
+
+impl FromStr for GitProvider {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "github" => Ok(GitProvider::Github),
+            "gitlab" => Ok(GitProvider::Gitlab),
+            other => Err(format!(
+                "Unknown Git provider '{}' (expected github or gitlab)",
+                other
+            )),
+        }
+    }
+}
+
+/// Commit status reporting of a pipeline (`/api/v1/pipeline/{id}/commit-status`)
+///
+/// Jobs launched with a commit report it as pending when queued and running,
+/// then as succeeded or failed when they finish.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CommitStatusConfig {
+    pub provider: GitProvider,
+    /// Name of the secret holding the provider token, among the secrets
+    /// visible to the pipeline
+    pub token_secret: String,
+    /// Name of the status on the commit (default: `rivet/<pipeline name>`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub context: Option<String>,
+    /// API base URL, for GitHub Enterprise or self-hosted GitLab
+    /// (default: the provider's public instance)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub api_url: Option<String>,
+    /// Link shown with the status, `{job_id}` and `{job_number}` replaced by
+    /// the job's id and number
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub target_url: Option<String>,
+}
@@ -0,0 +1,33 @@
+//! Debug session DTOs
+//!
+//! A debug session opens an interactive shell in the environment a failed
+//! job ran in. The user connects to the orchestrator, which asks the runner
+//! that ran the job to restore the job's container and relays the shell
+//! between both WebSockets:
+//! - binary frames carry terminal bytes (stdin towards the runner, output
+//!   towards the user)
+//! - text frames carry human-readable notices from the runner or orchestrator
+
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// A debug session a runner is asked to serve
+/// (`GET /api/debug/next`, 200 response)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DebugRequest {
+    /// Session to attach the shell to (`/api/debug/{session_id}/ws`)
+    pub session_id: Uuid,
+    /// The failed job whose environment is restored
+    pub job_id: Uuid,
+    /// Container image of the job to restore (the default container when absent)
+    #[serde(default)]
+    pub image: Option<String>,
+}
+
+/// Query of the debug session endpoint (`GET /api/jobs/{id}/debug`)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DebugQuery {
+    /// Container image of the job to restore (the default container when absent)
+    #[serde(default)]
+    pub image: Option<String>,
+}
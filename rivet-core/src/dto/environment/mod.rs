@@ -0,0 +1,84 @@
+//! Environment DTOs
+//!
+//! Data transfer objects for environments, their deployments and the
+//! approvals jobs deploying to them need.
+
+use serde::{Deserialize, Serialize};
+
+use uuid::Uuid;
+
+use crate::domain::environment::{Approval, Deployment, Environment, EnvironmentLock};
+
+/// Most approvals an environment may require
+pub const MAX_REQUIRED_APPROVALS: u32 = 10;
+
+/// Deployments listed per environment by default
+pub const DEFAULT_DEPLOYMENT_LIMIT: u32 = 20;
+
+/// Request to create or change an environment (`PUT /api/v1/environments/{name}`)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SetEnvironment {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// Distinct approvals a job deploying to the environment needs (0..=`MAX_REQUIRED_APPROVALS`)
+    #[serde(default)]
+    pub required_approvals: u32,
+}
+
+/// An environment with what is deployed to it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EnvironmentSummary {
+    #[serde(flatten)]
+    pub environment: Environment,
+    /// Latest successful deployment
+    pub current: Option<Deployment>,
+    /// Latest deployment, whatever its status
+    pub latest: Option<Deployment>,
+    /// Job currently holding the environment's lock
+    #[serde(default)]
+    pub lock: Option<EnvironmentLock>,
+}
+
+/// Lock of an environment with the jobs waiting for it
+/// (`GET /api/v1/environments/{name}/lock`)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EnvironmentLockStatus {
+    pub environment: String,
+    /// `None` while no job holds the lock
+    pub lock: Option<EnvironmentLock>,
+    /// Blocked jobs deploying to the environment, oldest first
+    pub waiting: Vec<Uuid>,
+}
+
+/// Query of `GET /api/v1/environments/{name}/deployments`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DeploymentQuery {
+    /// Deployments to list, newest first (1..=`MAX_PAGE_SIZE`, default `DEFAULT_DEPLOYMENT_LIMIT`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub limit: Option<u32>,
+}
+
+/// Request to approve a job's deployments to an environment
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApproveJob {
+    pub environment: String,
+    /// Who approves; each approver counts once
+    pub approver: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub comment: Option<String>,
+}
+
+/// Approvals of a job for one environment it deploys to
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JobApprovals {
+    pub environment: String,
+    pub required: u32,
+    pub approvals: Vec<Approval>,
+}
+
+impl JobApprovals {
+    /// Whether the job has the approvals the environment requires
+    pub fn is_satisfied(&self) -> bool {
+        self.approvals.len() as u32 >= self.required
+    }
+}
@@ -0,0 +1,40 @@
+//! Event DTOs
+//!
+//! Notifications broadcast by the orchestrator when jobs, pipelines and
+//! runners change state. Sent as JSON frames over `/api/events/ws`.
+
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::domain::job::JobStatus;
+
+/// A state change observed by the orchestrator
+///
+/// Serialized with a `type` tag, e.g.
+/// `{"type":"job_queued","job_id":"...","pipeline_id":"..."}`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Event {
+    /// A job was launched and is waiting for a runner
+    JobQueued { job_id: Uuid, pipeline_id: Uuid },
+    /// A runner claimed a job and started executing it
+    JobStarted {
+        job_id: Uuid,
+        pipeline_id: Uuid,
+        runner_id: String,
+    },
+    /// A job reached a final status
+    JobCompleted {
+        job_id: Uuid,
+        pipeline_id: Uuid,
+        status: JobStatus,
+    },
+    /// A pipeline was created
+    PipelineCreated { pipeline_id: Uuid, name: String },
+    /// A pipeline was deleted
+    PipelineDeleted { pipeline_id: Uuid },
+    /// A runner registered (or re-registered) with the orchestrator
+    RunnerRegistered { runner_id: String },
+    /// A runner stopped sending heartbeats and was marked offline
+    RunnerOffline { runner_id: String },
+}
@@ -0,0 +1,93 @@
+//! Health DTOs
+//!
+//! Responses of the orchestrator health, liveness and readiness endpoints.
+
+use serde::{Deserialize, Serialize};
+
+/// Orchestrator health report
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HealthStatus {
+    /// "ok" when every dependency is healthy, "degraded" otherwise
+    pub status: String,
+
+    /// Orchestrator version (crate version)
+    pub version: String,
+
+    /// Database connectivity: "ok" or the error message
+    pub database: String,
+}
+
+impl HealthStatus {
+    /// Whether the orchestrator reported itself fully healthy
+    pub fn is_ok(&self) -> bool {
+        self.status == "ok"
+    }
+}
+
+/// Liveness of the orchestrator process (`/healthz`)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LivenessStatus {
+    /// Always "ok": a process that can answer is alive
+    pub status: String,
+
+    /// Orchestrator version (crate version)
+    pub version: String,
+}
+
+/// Readiness of the orchestrator to serve traffic (`/readyz`)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReadinessStatus {
+    /// "ready" when every component is ok, "unready" otherwise
+    pub status: String,
+
+    /// Orchestrator version (crate version)
+    pub version: String,
+
+    /// Database, schema and background workers, each with its own status
+    pub components: Vec<ComponentStatus>,
+}
+
+impl ReadinessStatus {
+    /// Whether the orchestrator reported itself ready
+    pub fn is_ready(&self) -> bool {
+        self.status == "ready"
+    }
+}
+
+/// Status of one component the orchestrator depends on
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ComponentStatus {
+    /// Component name (`database`, `migrations`, `worker:<name>`)
+    pub name: String,
+
+    /// "ok" or "failing"
+    pub status: String,
+
+    /// What was observed, or why the component is failing
+    pub detail: String,
+}
+
+impl ComponentStatus {
+    /// A healthy component
+    pub fn ok(name: impl Into<String>, detail: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            status: "ok".to_string(),
+            detail: detail.into(),
+        }
+    }
+
+    /// A failing component
+    pub fn failing(name: impl Into<String>, detail: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            status: "failing".to_string(),
+            detail: detail.into(),
+        }
+    }
+
+    /// Whether the component is healthy
+    pub fn is_ok(&self) -> bool {
+        self.status == "ok"
+    }
+}
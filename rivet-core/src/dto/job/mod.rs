@@ -1,15 +1,189 @@
 //! Job DTOs for inter-service communication
 
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
-use crate::domain::job::{JobResult, JobStatus};
+use crate::domain::job::{
+    CommitRef, JobResult, JobStatus, StageResult, StageStatus, validate_label,
+};
 
 /// Request to create/trigger a new job
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CreateJob {
     pub pipeline_id: Uuid,
     pub parameters: std::collections::HashMap<String, serde_json::Value>,
+    /// Only plan the job: answer a [`JobPlan`] instead of queueing it
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub plan: bool,
+    /// Commit the job builds; its status is reported if the pipeline sets up
+    /// commit status reporting
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub commit: Option<CommitRef>,
+    /// Run the job even during a blackout window of its pipeline or of an
+    /// environment it deploys to, for emergencies
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub override_blackout: bool,
+    /// Labels of the job, see [`crate::domain::job::validate_label`]
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub labels: BTreeMap<String, String>,
+}
+
+/// What a job would do if launched with the given parameters
+///
+/// Answered to a `CreateJob` with `plan` set; nothing is queued or executed.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JobPlan {
+    pub pipeline_id: Uuid,
+    /// Parameters the job would get, input defaults applied
+    pub parameters: std::collections::HashMap<String, serde_json::Value>,
+    /// Every stage of the pipeline, in declaration order
+    pub stages: Vec<PlannedStage>,
+}
+
+/// A stage of a [`JobPlan`]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlannedStage {
+    pub name: String,
+    /// Container image the stage declares (`None`: the runner's default)
+    pub container: Option<String>,
+    /// Stages this one waits for
+    pub needs: Vec<String>,
+    pub decision: StageDecision,
+    /// Why the stage would be skipped or cannot be decided
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+}
+
+/// Whether a planned stage would run, assuming every stage before it succeeds
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum StageDecision {
+    Run,
+    Skip,
+    /// The stage's condition needs more than its parameters to be evaluated
+    /// (e.g. it runs a process), so it is only known once the job runs
+    Undecided,
+}
+
+/// Stands for the value of a `secret` input wherever the job is shown
+pub const MASKED_INPUT: &str = "***";
+
+/// Largest file a `file` input accepts, in bytes
+///
+/// The content travels base64-encoded in the launch request, which has to
+/// stay under the orchestrator's request size limit.
+pub const MAX_INPUT_FILE_BYTES: usize = 128 * 1024;
+
+/// Value of a `file` input: the file's name and base64-encoded content
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct InputFile {
+    pub name: String,
+    pub content: String,
+}
+
+impl InputFile {
+    /// Builds the value of a file input from the file's name and content
+    pub fn new(name: impl Into<String>, content: &[u8]) -> Self {
+        Self {
+            name: name.into(),
+            content: BASE64.encode(content),
+        }
+    }
+
+    /// Reads a file input value, checking its name and size
+    ///
+    /// # Returns
+    /// The input value and the decoded file content
+    pub fn parse(value: &serde_json::Value) -> Result<(Self, Vec<u8>), String> {
+        let file: InputFile = serde_json::from_value(value.clone()).map_err(|_| {
+            "Expected a file ({ \"name\": ..., \"content\": <base64> })".to_string()
+        })?;
+
+        if file.name.is_empty()
+            || file.name.len() > 255
+            || file.name == "."
+            || file.name == ".."
+            || file.name.contains(['/', '\\', '\0'])
+        {
+            return Err(format!("Invalid file name '{}'", file.name));
+        }
+
+        let content = BASE64
+            .decode(&file.content)
+            .map_err(|_| "File content is not valid base64".to_string())?;
+        if content.len() > MAX_INPUT_FILE_BYTES {
+            return Err(format!(
+                "File is {} bytes, the limit is {}",
+                content.len(),
+                MAX_INPUT_FILE_BYTES
+            ));
+        }
+
+        Ok((file, content))
+    }
+}
+
+/// Largest number of jobs a single batch launch may create
+pub const MAX_LAUNCH_BATCH: usize = 100;
+
+/// Jobs created by a batch launch (`POST /api/pipeline/{id}/launch-batch`)
+///
+/// The request body is a JSON array with one parameter object per job.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LaunchBatchResponse {
+    /// Ids of the created jobs, in the order of the parameter sets
+    pub job_ids: Vec<Uuid>,
+}
+
+/// Optional criteria for listing jobs
+///
+/// Unset fields do not restrict the result.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct JobFilter {
+    pub status: Option<JobStatus>,
+    pub pipeline_id: Option<Uuid>,
+    pub runner_id: Option<String>,
+    /// Only jobs of pipelines in this project
+    pub project_id: Option<Uuid>,
+    /// Only jobs carrying all these labels
+    #[serde(default)]
+    pub labels: BTreeMap<String, String>,
+}
+
+/// Query of `GET /jobs`, besides its page
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct JobListQuery {
+    /// Labels jobs must carry, as `key=value` pairs separated by commas
+    /// (e.g. `team=payments,env=prod`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+}
+
+impl JobListQuery {
+    /// The labels of the query, by key
+    ///
+    /// # Errors
+    /// Returns why a pair is not `key=value` or not a valid label
+    pub fn labels(&self) -> Result<BTreeMap<String, String>, String> {
+        let Some(label) = self.label.as_deref() else {
+            return Ok(BTreeMap::new());
+        };
+
+        let mut labels = BTreeMap::new();
+        for pair in label.split(',').map(str::trim).filter(|p| !p.is_empty()) {
+            let (key, value) = pair
+                .split_once('=')
+                .ok_or_else(|| format!("Label filter '{}' must be key=value", pair))?;
+            validate_label(key.trim(), value.trim())?;
+            labels.insert(key.trim().to_string(), value.trim().to_string());
+        }
+        Ok(labels)
+    }
 }
 
 /// Job status update from runner to orchestrator
@@ -27,17 +201,188 @@ pub struct ExecuteJobRequest {
     pub runner_id: String,
 }
 
-/// Information needed to execute a job
+/// Longest a runner may wait for a job assignment in one long-poll request
+pub const MAX_NEXT_JOB_WAIT: Duration = Duration::from_secs(60);
+
+/// Query of the long-poll job assignment endpoint (`GET /api/jobs/next`)
 #[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NextJobQuery {
+    pub runner_id: String,
+    /// How long to wait for a job, e.g. "30s", "500ms" or "30" (seconds).
+    /// Returns immediately when absent.
+    #[serde(default)]
+    pub wait: Option<String>,
+}
+
+impl NextJobQuery {
+    /// Parses `wait`, capped at `MAX_NEXT_JOB_WAIT`
+    ///
+    /// # Returns
+    /// The wait duration (zero when absent), or `None` if it is malformed
+    pub fn wait_duration(&self) -> Option<Duration> {
+        let Some(wait) = self.wait.as_deref().map(str::trim) else {
+            return Some(Duration::ZERO);
+        };
+
+        let duration = if let Some(millis) = wait.strip_suffix("ms") {
+            Duration::from_millis(millis.parse().ok()?)
+        } else {
+            Duration::from_secs(wait.strip_suffix('s').unwrap_or(wait).parse().ok()?)
+        };
+
+        Some(duration.min(MAX_NEXT_JOB_WAIT))
+    }
+}
+
+/// Lease lifetime used when a runner does not ask for one
+pub const DEFAULT_JOB_LEASE_TTL: Duration = Duration::from_secs(60);
+
+/// Longest lease a runner may take in one renewal
+pub const MAX_JOB_LEASE_TTL: Duration = Duration::from_secs(600);
+
+/// Request to renew the lease on a running job (`POST /api/jobs/{id}/lease`)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RenewLeaseRequest {
+    pub runner_id: String,
+    /// Seconds until the lease expires, `DEFAULT_JOB_LEASE_TTL` when absent
+    #[serde(default)]
+    pub ttl_seconds: Option<u64>,
+}
+
+/// Lease a runner holds on a running job
+///
+/// A job whose lease expires is requeued, so it is not lost when its runner
+/// dies mid-job.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JobLease {
+    pub job_id: Uuid,
+    pub runner_id: String,
+    pub expires_at: chrono::DateTime<chrono::Utc>,
+}
+
+/// Information needed to execute a job
+#[derive(Clone, Serialize, Deserialize)]
 pub struct JobExecutionInfo {
     /// The job ID
     pub job_id: Uuid,
     /// The pipeline ID
     pub pipeline_id: Uuid,
+    /// The pipeline name
+    #[serde(default)]
+    pub pipeline_name: String,
+    /// Number of the job within its pipeline
+    #[serde(default)]
+    pub number: u64,
+    /// Attempt of the job this claim runs, starting at 1
+    #[serde(default)]
+    pub attempt: u32,
+    /// Commit the job builds, set for jobs a webhook launched
+    #[serde(default)]
+    pub commit: Option<CommitRef>,
+    /// Schedule that launched the job
+    #[serde(default)]
+    pub schedule: Option<ScheduleRef>,
     /// The pipeline Lua source code
     pub pipeline_source: String,
     /// Job parameters to inject as environment variables
     pub parameters: std::collections::HashMap<String, serde_json::Value>,
+    /// Secret values visible to the job, by name
+    #[serde(default)]
+    pub secrets: std::collections::HashMap<String, String>,
+    /// Parameters of `secret` inputs, whose values are masked in logs
+    #[serde(default)]
+    pub secret_inputs: Vec<String>,
+    /// Job this one resumes, whose kept workspace it continues from
+    #[serde(default)]
+    pub resumed_from: Option<Uuid>,
+    /// Stages that succeeded in the resumed job and are not run again
+    #[serde(default)]
+    pub skip_stages: Vec<String>,
+    /// `traceparent` of the span that launched the job, continued by the runner
+    #[serde(default)]
+    pub traceparent: Option<String>,
+    /// Fencing token of this claim, sent back with the job's logs and
+    /// completion so that they are rejected once the job is claimed again
+    #[serde(default)]
+    pub claim_token: Option<u64>,
+    /// Image of the pipeline's default container, in place of the runner's
+    #[serde(default)]
+    pub default_container: Option<String>,
+    /// Images of the pipeline's default container by architecture,
+    /// preferred over `default_container`
+    #[serde(default)]
+    pub default_container_variants: std::collections::HashMap<String, String>,
+    /// Environment variables the pipeline sets in the job's containers
+    #[serde(default)]
+    pub env: std::collections::HashMap<String, String>,
+    /// `ctx` table the resumed job ended with, restored since its reused
+    /// stages do not run again
+    #[serde(default)]
+    pub ctx: Option<serde_json::Value>,
+}
+
+/// Cron schedule of a pipeline, as seen by the jobs it launches
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ScheduleRef {
+    pub id: Uuid,
+    /// Cron expression of the schedule
+    pub cron: String,
+}
+
+impl std::fmt::Debug for JobExecutionInfo {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut secret_names: Vec<_> = self.secrets.keys().collect();
+        secret_names.sort();
+        let parameters: std::collections::HashMap<_, _> = self
+            .parameters
+            .iter()
+            .map(|(name, value)| match self.secret_inputs.contains(name) {
+                true => (name, serde_json::Value::from(MASKED_INPUT)),
+                false => (name, value.clone()),
+            })
+            .collect();
+
+        f.debug_struct("JobExecutionInfo")
+            .field("job_id", &self.job_id)
+            .field("pipeline_id", &self.pipeline_id)
+            .field("pipeline_name", &self.pipeline_name)
+            .field("number", &self.number)
+            .field("attempt", &self.attempt)
+            .field("commit", &self.commit)
+            .field("schedule", &self.schedule)
+            .field("pipeline_source", &self.pipeline_source)
+            .field("parameters", &parameters)
+            .field("secrets", &secret_names)
+            .field("secret_inputs", &self.secret_inputs)
+            .field("resumed_from", &self.resumed_from)
+            .field("skip_stages", &self.skip_stages)
+            .field("traceparent", &self.traceparent)
+            .field("default_container", &self.default_container)
+            .field(
+                "default_container_variants",
+                &self.default_container_variants,
+            )
+            .field("env", &self.env)
+            .field("ctx", &self.ctx)
+            .finish()
+    }
+}
+
+/// Stage progress reported by the runner (`POST /api/jobs/{id}/stages`)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StageUpdate {
+    pub name: String,
+    pub status: StageStatus,
+    /// When the stage reached `status`
+    pub timestamp: chrono::DateTime<chrono::Utc>,
+    #[serde(default)]
+    pub error_message: Option<String>,
+    /// Stages this one declared it needs
+    #[serde(default)]
+    pub needs: Vec<String>,
+    /// What the stage's script returned, sent with `Succeeded`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub result: Option<StageResult>,
 }
 
 /// Request to update job status
@@ -51,4 +396,98 @@ pub struct UpdateStatusRequest {
 pub struct CompleteJobRequest {
     pub status: JobStatus,
     pub result: Option<JobResult>,
+    /// Token of the claim the job ran under, see `JobExecutionInfo::claim_token`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub claim_token: Option<u64>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn query(wait: Option<&str>) -> NextJobQuery {
+        NextJobQuery {
+            runner_id: "runner-1".to_string(),
+            wait: wait.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn test_next_job_wait_duration() {
+        assert_eq!(query(None).wait_duration(), Some(Duration::ZERO));
+        assert_eq!(
+            query(Some("30s")).wait_duration(),
+            Some(Duration::from_secs(30))
+        );
+        assert_eq!(
+            query(Some("15")).wait_duration(),
+            Some(Duration::from_secs(15))
+        );
+        assert_eq!(
+            query(Some("500ms")).wait_duration(),
+            Some(Duration::from_millis(500))
+        );
+        assert_eq!(query(Some("1h")).wait_duration(), None);
+        assert_eq!(query(Some("-1s")).wait_duration(), None);
+        assert_eq!(
+            query(Some("3600s")).wait_duration(),
+            Some(MAX_NEXT_JOB_WAIT)
+        );
+    }
+
+    #[test]
+    fn test_create_job_plan_defaults_to_launch() {
+        let req: CreateJob = serde_json::from_str(
+            r#"{"pipeline_id": "6f1c2f0e-8a52-4a8b-9a43-1d2b7c3e4f50", "parameters": {}}"#,
+        )
+        .unwrap();
+        assert!(!req.plan);
+
+        // Launch requests serialize as they did before the flag existed
+        let json = serde_json::to_value(&req).unwrap();
+        assert!(json.get("plan").is_none());
+    }
+
+    #[test]
+    fn test_stage_update_result() {
+        // Runners that predate stage results
+        let update: StageUpdate = serde_json::from_str(
+            r#"{"name": "test", "status": "Succeeded", "timestamp": "2026-01-01T00:00:00Z"}"#,
+        )
+        .unwrap();
+        assert_eq!(update.result, None);
+
+        let update: StageUpdate = serde_json::from_str(
+            r#"{"name": "test", "status": "Succeeded", "timestamp": "2026-01-01T00:00:00Z",
+                "result": {"metrics": {"tests": 124}, "summary": "124 passed"}}"#,
+        )
+        .unwrap();
+        let result = update.result.unwrap();
+        assert_eq!(result.metrics.get("tests"), Some(&124.0));
+        assert_eq!(result.summary.as_deref(), Some("124 passed"));
+        assert!(result.artifacts.is_empty());
+        assert_eq!(
+            serde_json::to_value(&result).unwrap(),
+            serde_json::json!({"metrics": {"tests": 124.0}, "summary": "124 passed"})
+        );
+    }
+
+    #[test]
+    fn test_job_list_query_labels() {
+        let query = JobListQuery {
+            label: Some("team=payments, env=prod".to_string()),
+        };
+        let labels = query.labels().unwrap();
+        assert_eq!(labels.get("team").map(String::as_str), Some("payments"));
+        assert_eq!(labels.get("env").map(String::as_str), Some("prod"));
+
+        assert!(JobListQuery::default().labels().unwrap().is_empty());
+
+        for invalid in ["team", "=payments", "team name=payments"] {
+            let query = JobListQuery {
+                label: Some(invalid.to_string()),
+            };
+            assert!(query.labels().is_err(), "{} should be rejected", invalid);
+        }
+    }
 }
@@ -3,7 +3,28 @@
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
-use crate::domain::log::LogEntry;
+use crate::domain::log::{LogEntry, LogLevel};
+
+/// Largest number of entries accepted in one log batch
+pub const MAX_LOG_BATCH_ENTRIES: usize = 1000;
+
+/// Largest message accepted in a single log entry, in bytes
+pub const MAX_LOG_MESSAGE_LENGTH: usize = 10_000;
+
+/// Largest total message size accepted in one log batch, in bytes
+pub const MAX_LOG_BATCH_BYTES: usize = 1024 * 1024;
+
+/// Largest log upload request body accepted, in bytes
+///
+/// Leaves room beyond `MAX_LOG_BATCH_BYTES` for entry metadata and JSON
+/// escaping of the messages.
+pub const MAX_LOG_REQUEST_BYTES: usize = 4 * MAX_LOG_BATCH_BYTES;
+
+/// Largest log search query accepted, in bytes
+pub const MAX_LOG_SEARCH_QUERY_LENGTH: usize = 256;
+
+/// Entries returned by a log search without a `limit`
+pub const DEFAULT_LOG_SEARCH_LIMIT: u32 = 100;
 
 /// Log batch sent from runner to orchestrator
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -11,3 +32,160 @@ pub struct LogBatch {
     pub job_id: Uuid,
     pub entries: Vec<LogEntry>,
 }
+
+/// Query of the log upload endpoint (`POST /api/jobs/{id}/logs`)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AddLogsQuery {
+    /// Token of the claim the entries were logged under, see
+    /// `JobExecutionInfo::claim_token`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub claim_token: Option<u64>,
+}
+
+/// Query of the job log endpoint (`GET /api/jobs/{id}/logs`)
+///
+/// Without parameters the whole log is returned.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct LogQuery {
+    /// Only return entries with a greater `sequence`, i.e. those stored
+    /// after the last entry the caller already has
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub after: Option<u64>,
+    /// Only return the last `tail` entries (1..=`MAX_PAGE_SIZE`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tail: Option<u32>,
+    /// Only return entries of this level and above, e.g. "warning"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub level: Option<String>,
+}
+
+impl LogQuery {
+    /// Whether any filter is set; without one the whole log is returned
+    pub fn is_filtered(&self) -> bool {
+        self.after.is_some() || self.tail.is_some() || self.level.is_some()
+    }
+
+    /// Parses `level`
+    ///
+    /// # Returns
+    /// The minimum level (`None` when absent), or an error if it is unknown
+    pub fn min_level(&self) -> Result<Option<LogLevel>, String> {
+        self.level.as_deref().map(str::parse).transpose()
+    }
+}
+
+/// Query of the log search endpoints (`GET /api/jobs/{id}/logs/search` and
+/// `GET /api/logs/search`)
+///
+/// Matching is full-text and case-insensitive on whole words: every word of
+/// `q` must appear in an entry, `"quoted phrases"` must appear in order and
+/// `-word` excludes entries containing the word.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct LogSearchQuery {
+    pub q: String,
+    /// Only return entries of this level and above, e.g. "warning"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub level: Option<String>,
+    /// Only search the jobs of this pipeline (search across jobs only)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pipeline_id: Option<Uuid>,
+    /// Most entries returned (1..=`MAX_PAGE_SIZE`, default
+    /// `DEFAULT_LOG_SEARCH_LIMIT`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub limit: Option<u32>,
+}
+
+impl LogSearchQuery {
+    /// Parses `level`
+    ///
+    /// # Returns
+    /// The minimum level (`None` when absent), or an error if it is unknown
+    pub fn min_level(&self) -> Result<Option<LogLevel>, String> {
+        self.level.as_deref().map(str::parse).transpose()
+    }
+}
+
+/// A log entry found by a search across jobs
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LogSearchHit {
+    pub job_id: Uuid,
+    pub pipeline_id: Uuid,
+    pub entry: LogEntry,
+}
+
+/// Splits entries into batches the orchestrator accepts
+///
+/// Each batch holds at most `MAX_LOG_BATCH_ENTRIES` entries and
+/// `MAX_LOG_BATCH_BYTES` of messages. Order is preserved; messages longer
+/// than `MAX_LOG_MESSAGE_LENGTH` are truncated rather than failing the batch.
+pub fn split_log_batches(entries: Vec<LogEntry>) -> Vec<Vec<LogEntry>> {
+    let mut batches = Vec::new();
+    let mut current = Vec::new();
+    let mut current_bytes = 0;
+
+    for mut entry in entries {
+        if entry.message.len() > MAX_LOG_MESSAGE_LENGTH {
+            let mut end = MAX_LOG_MESSAGE_LENGTH;
+            while !entry.message.is_char_boundary(end) {
+                end -= 1;
+            }
+            entry.message.truncate(end);
+        }
+
+        let full = current.len() == MAX_LOG_BATCH_ENTRIES
+            || current_bytes + entry.message.len() > MAX_LOG_BATCH_BYTES;
+        if full && !current.is_empty() {
+            batches.push(std::mem::take(&mut current));
+            current_bytes = 0;
+        }
+
+        current_bytes += entry.message.len();
+        current.push(entry);
+    }
+
+    if !current.is_empty() {
+        batches.push(current);
+    }
+
+    batches
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(message: String) -> LogEntry {
+        LogEntry {
+            timestamp: chrono::Utc::now(),
+            level: LogLevel::Info,
+            message,
+            stage: None,
+            stream: None,
+            sequence: None,
+            fields: None,
+        }
+    }
+
+    #[test]
+    fn test_split_log_batches_respects_limits() {
+        let entries = (0..2500).map(|i| entry(i.to_string())).collect();
+        let sizes: Vec<_> = split_log_batches(entries).iter().map(Vec::len).collect();
+        assert_eq!(sizes, vec![1000, 1000, 500]);
+
+        let entries = (0..300)
+            .map(|_| entry("x".repeat(MAX_LOG_MESSAGE_LENGTH)))
+            .collect();
+        let batches = split_log_batches(entries);
+        assert_eq!(batches.len(), 3);
+        assert!(
+            batches.iter().all(|b| {
+                b.iter().map(|e| e.message.len()).sum::<usize>() <= MAX_LOG_BATCH_BYTES
+            })
+        );
+
+        let batches = split_log_batches(vec![entry("é".repeat(MAX_LOG_MESSAGE_LENGTH))]);
+        assert!(batches[0][0].message.len() <= MAX_LOG_MESSAGE_LENGTH);
+
+        assert!(split_log_batches(Vec::new()).is_empty());
+    }
+}
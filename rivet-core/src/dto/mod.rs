@@ -4,8 +4,24 @@
 //! (orchestrator, runner, etc.). DTOs are lightweight representations of
 //! domain entities optimized for network transfer.
 
+pub mod artifact;
+pub mod blackout;
+pub mod commit_status;
+pub mod debug;
+pub mod environment;
+pub mod event;
+pub mod health;
 pub mod job;
 pub mod log;
 pub mod module;
+pub mod pagination;
 pub mod pipeline;
+pub mod preset;
+pub mod problem;
+pub mod project;
 pub mod runner;
+pub mod schedule;
+pub mod secret;
+pub mod validation;
+pub mod version;
+pub mod webhook;
@@ -0,0 +1,22 @@
+//! Pagination DTOs
+//!
+//! Query parameters shared by paginated list endpoints.
+
+use serde::{Deserialize, Serialize};
+
+/// Largest page size accepted by list endpoints
+pub const MAX_PAGE_SIZE: u32 = 500;
+
+/// Offset-based page selection
+///
+/// Endpoints return every item when `limit` is absent, for compatibility
+/// with clients that predate pagination.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+pub struct PageQuery {
+    /// Maximum number of items to return (1..=`MAX_PAGE_SIZE`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub limit: Option<u32>,
+    /// Number of items to skip
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub offset: Option<u32>,
+}
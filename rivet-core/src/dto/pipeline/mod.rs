@@ -1,9 +1,477 @@
 //! Pipeline DTOs for inter-service communication
 
+use std::collections::{BTreeMap, HashMap};
+
 use serde::{Deserialize, Serialize};
 
+use crate::domain::pipeline::{InputType, Tag};
+
+/// Largest pipeline script accepted, in bytes
+pub const MAX_PIPELINE_SCRIPT_BYTES: usize = 256 * 1024;
+
 /// Request to create a new pipeline
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CreatePipeline {
     pub script: String,
 }
+
+/// Summary of a stage declared by a pipeline script
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StageInfo {
+    pub name: String,
+    pub container: Option<String>,
+    /// Images of the stage's container by architecture, preferred over
+    /// `container` on runners of those architectures
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub container_variants: BTreeMap<String, String>,
+    /// Stages this one needs (`None` waits for the previous stage)
+    pub needs: Option<Vec<String>>,
+    /// Whether a failure of the stage leaves the job successful
+    pub allow_failure: bool,
+    /// Environment the stage deploys to
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub deploys_to: Option<String>,
+    /// When the stage runs: `on_success`, `on_failure` or `always`
+    pub when: String,
+    /// Whether the stage runs only if its condition function passes
+    #[serde(default)]
+    pub has_condition: bool,
+}
+
+/// Serializable parts of a pipeline script's definition
+/// (`GET /api/v1/pipeline/{id}/definition`), so that the structure of a
+/// pipeline can be shown without evaluating Lua
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PipelineDefinitionInfo {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// Inputs by name
+    pub inputs: BTreeMap<String, InputInfo>,
+    /// Runner tags required by the pipeline
+    pub runner: Vec<Tag>,
+    pub plugins: Vec<String>,
+    /// Times a failed job is automatically retried (0 = never)
+    pub max_retries: u32,
+    /// Image of the container stages run in unless they declare their own
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default_container: Option<String>,
+    /// Images of the default container by architecture
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub default_container_variants: BTreeMap<String, String>,
+    /// Environment variables set in the job's containers
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub env: BTreeMap<String, String>,
+    /// Whether a `before_each` hook runs before every stage script
+    #[serde(default)]
+    pub before_each: bool,
+    /// Whether an `after_each` hook runs after every stage script
+    #[serde(default)]
+    pub after_each: bool,
+    /// Stages in declaration order
+    pub stages: Vec<StageInfo>,
+}
+
+/// An input declared by a pipeline script, with its constraints
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InputInfo {
+    #[serde(rename = "type")]
+    pub input_type: InputType,
+    /// Type of the elements of an `array` input (any scalar when unset)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub items: Option<InputType>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    pub required: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default: Option<serde_json::Value>,
+    /// Values an `enum` input (or the elements of an array) may take
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub options: Option<Vec<serde_json::Value>>,
+    /// Regular expression string values must match
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pattern: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max: Option<f64>,
+    /// Fewest characters of a string, or elements of an array
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_length: Option<usize>,
+    /// Most characters of a string, or elements of an array
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_length: Option<usize>,
+}
+
+/// Stage dependency graph of a pipeline (`GET /api/v1/pipeline/{id}/graph`)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PipelineGraph {
+    /// Stages in declaration order
+    pub nodes: Vec<GraphNode>,
+    /// One edge per stage a stage waits for, implicit ones included
+    pub edges: Vec<GraphEdge>,
+}
+
+/// A stage of a pipeline graph
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GraphNode {
+    pub name: String,
+    /// Position in execution order: stages of a level only wait for stages
+    /// of lower levels, so those of one level can run side by side
+    pub level: usize,
+    pub container: Option<String>,
+    /// When the stage runs: `on_success`, `on_failure` or `always`
+    pub when: String,
+    pub allow_failure: bool,
+    /// Whether the stage runs only if its condition function passes
+    pub has_condition: bool,
+}
+
+/// A dependency between two stages: `to` waits for `from`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GraphEdge {
+    pub from: String,
+    pub to: String,
+}
+
+impl PipelineGraph {
+    /// Builds the graph of a pipeline's stages
+    ///
+    /// A stage without `needs` waits for the stage declared before it, as
+    /// when the pipeline runs. Needs of unknown stages are left out; parsed
+    /// definitions have none, nor cycles.
+    ///
+    /// # Arguments
+    /// * `stages` - Stages in declaration order
+    pub fn from_stages(stages: &[StageInfo]) -> Self {
+        let index = |name: &str| stages.iter().position(|s| s.name == name);
+        let dependencies: Vec<Vec<usize>> = stages
+            .iter()
+            .enumerate()
+            .map(|(idx, stage)| match &stage.needs {
+                Some(needs) => needs.iter().filter_map(|need| index(need)).collect(),
+                None => idx.checked_sub(1).into_iter().collect(),
+            })
+            .collect();
+
+        // Longest path from a stage without dependencies; settles within
+        // one pass per stage on an acyclic graph
+        let mut levels = vec![0; stages.len()];
+        for _ in 0..stages.len() {
+            let mut changed = false;
+            for (idx, deps) in dependencies.iter().enumerate() {
+                let level = deps.iter().map(|&dep| levels[dep] + 1).max().unwrap_or(0);
+                if level > levels[idx] {
+                    levels[idx] = level;
+                    changed = true;
+                }
+            }
+            if !changed {
+                break;
+            }
+        }
+
+        let nodes = stages
+            .iter()
+            .zip(&levels)
+            .map(|(stage, &level)| GraphNode {
+                name: stage.name.clone(),
+                level,
+                container: stage.container.clone(),
+                when: stage.when.clone(),
+                allow_failure: stage.allow_failure,
+                has_condition: stage.has_condition,
+            })
+            .collect();
+        let edges = dependencies
+            .iter()
+            .enumerate()
+            .flat_map(|(idx, deps)| {
+                deps.iter().map(move |&dep| GraphEdge {
+                    from: stages[dep].name.clone(),
+                    to: stages[idx].name.clone(),
+                })
+            })
+            .collect();
+
+        Self { nodes, edges }
+    }
+}
+
+/// Values the orchestrator supplies for a pipeline's inputs
+/// (`/api/v1/pipeline/{id}/defaults`), e.g. a registry URL per environment
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct PipelineDefaults {
+    /// Used when a launch leaves the input out, ahead of the script's default
+    #[serde(default)]
+    pub defaults: HashMap<String, serde_json::Value>,
+    /// Always used, replacing whatever a launch asks for
+    #[serde(default)]
+    pub overrides: HashMap<String, serde_json::Value>,
+}
+
+impl PipelineDefaults {
+    /// Merges these values with the parameters of a launch
+    pub fn apply(
+        &self,
+        mut parameters: HashMap<String, serde_json::Value>,
+    ) -> HashMap<String, serde_json::Value> {
+        for (name, value) in &self.defaults {
+            parameters
+                .entry(name.clone())
+                .or_insert_with(|| value.clone());
+        }
+        for (name, value) in &self.overrides {
+            parameters.insert(name.clone(), value.clone());
+        }
+        parameters
+    }
+}
+
+/// Days of history pipeline stats cover by default
+pub const DEFAULT_STATS_DAYS: u32 = 30;
+
+/// Most days of history pipeline stats may cover
+pub const MAX_STATS_DAYS: u32 = 365;
+
+/// Query of `GET /api/v1/pipeline/list`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PipelineListQuery {
+    /// List the archived pipelines instead of the active ones
+    #[serde(default)]
+    pub archived: bool,
+}
+
+/// Query of `DELETE /api/v1/pipeline/{id}`
+///
+/// Without either flag, a pipeline is archived when jobs reference it and
+/// deleted otherwise.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DeletePipelineQuery {
+    /// Archive the pipeline even if no job references it
+    #[serde(default)]
+    pub archive: bool,
+    /// Delete the pipeline along with its jobs
+    #[serde(default)]
+    pub force: bool,
+}
+
+/// Query of `GET /api/v1/pipeline/{id}/stats`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PipelineStatsQuery {
+    /// Days of history to cover (1..=`MAX_STATS_DAYS`, default `DEFAULT_STATS_DAYS`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub days: Option<u32>,
+    /// Width of the trend buckets (default: a day)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bucket: Option<StatsBucket>,
+}
+
+/// Width of the buckets of a pipeline's trend
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StatsBucket {
+    Day,
+    Week,
+}
+
+impl StatsBucket {
+    /// Name of the bucket as PostgreSQL's `date_trunc` and intervals spell it
+    pub fn as_str(self) -> &'static str {
+        match self {
+            StatsBucket::Day => "day",
+            StatsBucket::Week => "week",
+        }
+    }
+}
+
+/// How a pipeline's jobs fared over a period (`GET /api/v1/pipeline/{id}/stats`)
+///
+/// Jobs are counted by when they were requested. `failed` includes timed
+/// out and dead-lettered jobs; queued and running jobs only count in `jobs`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PipelineStats {
+    pub pipeline_id: uuid::Uuid,
+    /// Start of the period covered
+    pub since: chrono::DateTime<chrono::Utc>,
+    pub jobs: u64,
+    pub succeeded: u64,
+    pub failed: u64,
+    pub cancelled: u64,
+    /// Share of succeeded jobs among succeeded and failed ones (0.0 to 1.0),
+    /// `None` if none finished
+    pub success_rate: Option<f64>,
+    /// Durations of the succeeded and failed jobs, `None` if none finished
+    pub duration: Option<DurationStats>,
+    /// Stages that failed, most failures first
+    pub stage_failures: Vec<StageFailures>,
+    /// One bucket per day or week of the period, oldest first
+    pub trend: Vec<TrendBucket>,
+}
+
+/// Job durations, in seconds
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DurationStats {
+    pub average: f64,
+    pub p50: f64,
+    pub p90: f64,
+    pub p95: f64,
+    pub max: f64,
+}
+
+/// How often a stage failed over the period
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StageFailures {
+    pub stage: String,
+    pub failures: u64,
+    /// Times the stage ran to completion, failures included
+    pub runs: u64,
+}
+
+/// Jobs requested during one bucket of a trend
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TrendBucket {
+    pub start: chrono::DateTime<chrono::Utc>,
+    pub jobs: u64,
+    pub succeeded: u64,
+    pub failed: u64,
+    /// Average duration of the bucket's finished jobs, in seconds
+    pub average_duration: Option<f64>,
+}
+
+/// Outcome flips a stage needs on one pipeline version to be flagged flaky by default
+pub const DEFAULT_MIN_FLIPS: u32 = 2;
+
+/// Query of `GET /api/v1/pipeline/{id}/flaky-stages`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct FlakyStagesQuery {
+    /// Days of history to cover (1..=`MAX_STATS_DAYS`, default `DEFAULT_STATS_DAYS`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub days: Option<u32>,
+    /// Flips needed to flag a stage (at least 1, default `DEFAULT_MIN_FLIPS`);
+    /// a stage that failed, then passed on a retry, is flagged regardless
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_flips: Option<u32>,
+}
+
+/// A stage whose outcome flipped between pass and fail on one pipeline version
+///
+/// Runs of every attempt count, in the order they started; a stage allowed
+/// to fail counts as failed when it does. Since the script did not change,
+/// flips point at flaky tests or infrastructure rather than at the pipeline.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FlakyStage {
+    pub stage: String,
+    /// MD5 of the pipeline script the runs used
+    pub version: String,
+    pub runs: u64,
+    pub failures: u64,
+    /// Runs whose outcome differs from the previous run's
+    pub flips: u64,
+    /// Flips from a failed attempt of a job to a passing retry of it
+    pub retry_flips: u64,
+    /// Share of consecutive runs that flipped (0.0 to 1.0)
+    pub flip_rate: f64,
+    pub last_flip_at: chrono::DateTime<chrono::Utc>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_defaults_yield_to_launch_overrides_do_not() {
+        let defaults = PipelineDefaults {
+            defaults: HashMap::from([
+                ("branch".to_string(), json!("main")),
+                ("env".to_string(), json!("staging")),
+            ]),
+            overrides: HashMap::from([("registry".to_string(), json!("registry.internal"))]),
+        };
+
+        let parameters = defaults.apply(HashMap::from([
+            ("branch".to_string(), json!("feature")),
+            ("registry".to_string(), json!("docker.io")),
+        ]));
+
+        assert_eq!(parameters["branch"], "feature");
+        assert_eq!(parameters["env"], "staging");
+        assert_eq!(parameters["registry"], "registry.internal");
+    }
+
+    fn stage(name: &str, needs: Option<&[&str]>) -> StageInfo {
+        StageInfo {
+            name: name.to_string(),
+            container: None,
+            container_variants: BTreeMap::new(),
+            needs: needs.map(|needs| needs.iter().map(|n| n.to_string()).collect()),
+            allow_failure: false,
+            deploys_to: None,
+            when: "on_success".to_string(),
+            has_condition: false,
+        }
+    }
+
+    #[test]
+    fn test_graph_levels_and_edges() {
+        let graph = PipelineGraph::from_stages(&[
+            stage("build", None),
+            stage("lint", Some(&[])),
+            stage("test", None),
+            stage("deploy", Some(&["test", "lint"])),
+        ]);
+
+        let levels: Vec<(&str, usize)> = graph
+            .nodes
+            .iter()
+            .map(|node| (node.name.as_str(), node.level))
+            .collect();
+        assert_eq!(
+            levels,
+            [("build", 0), ("lint", 0), ("test", 1), ("deploy", 2)]
+        );
+
+        let edge = |from: &str, to: &str| GraphEdge {
+            from: from.to_string(),
+            to: to.to_string(),
+        };
+        assert_eq!(
+            graph.edges,
+            [
+                edge("lint", "test"),
+                edge("test", "deploy"),
+                edge("lint", "deploy")
+            ]
+        );
+    }
+
+    #[test]
+    fn test_input_info_json() {
+        let input = InputInfo {
+            input_type: InputType::Array,
+            items: Some(InputType::String),
+            description: None,
+            required: false,
+            default: Some(json!(["api"])),
+            options: None,
+            pattern: Some("^[a-z]+$".to_string()),
+            min: None,
+            max: None,
+            min_length: Some(1),
+            max_length: None,
+        };
+
+        assert_eq!(
+            serde_json::to_value(&input).unwrap(),
+            json!({
+                "type": "array",
+                "items": "string",
+                "required": false,
+                "default": ["api"],
+                "pattern": "^[a-z]+$",
+                "min_length": 1
+            })
+        );
+    }
+}
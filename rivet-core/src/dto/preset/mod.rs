@@ -0,0 +1,14 @@
+//! Parameter preset DTOs
+//!
+//! Data transfer objects for the parameter sets saved for pipelines.
+
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+/// Request to save the parameters of a preset, replacing any it had
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SavePreset {
+    #[serde(default)]
+    pub parameters: HashMap<String, serde_json::Value>,
+}
@@ -0,0 +1,124 @@
+//! Problem DTOs
+//!
+//! Errors returned by the orchestrator API, as RFC 7807 problem details
+//! (`application/problem+json`). `type` tells clients what went wrong
+//! without parsing `detail`, which is meant for humans.
+
+use serde::{Deserialize, Serialize};
+
+use crate::dto::validation::FieldError;
+
+/// Media type of problem details
+pub const PROBLEM_JSON: &str = "application/problem+json";
+
+/// Kind of problem, serialized as the problem `type` URI
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ProblemType {
+    #[serde(rename = "urn:rivet:problem:bad-request")]
+    BadRequest,
+    /// Some request fields are invalid, listed in `fields`
+    #[serde(rename = "urn:rivet:problem:invalid-fields")]
+    InvalidFields,
+    #[serde(rename = "urn:rivet:problem:unauthorized")]
+    Unauthorized,
+    #[serde(rename = "urn:rivet:problem:not-found")]
+    NotFound,
+    #[serde(rename = "urn:rivet:problem:conflict")]
+    Conflict,
+    #[serde(rename = "urn:rivet:problem:payload-too-large")]
+    PayloadTooLarge,
+    /// The requested API version is not served
+    #[serde(rename = "urn:rivet:problem:unsupported-api-version")]
+    UnsupportedApiVersion,
+    #[serde(rename = "urn:rivet:problem:rate-limited")]
+    RateLimited,
+    #[serde(rename = "urn:rivet:problem:internal")]
+    Internal,
+    /// Any other problem, identified by its status alone (`about:blank`)
+    #[serde(rename = "about:blank", other)]
+    Other,
+}
+
+impl ProblemType {
+    /// Short summary of the problem type
+    pub fn title(&self) -> &'static str {
+        match self {
+            ProblemType::BadRequest => "Bad request",
+            ProblemType::InvalidFields => "Invalid request fields",
+            ProblemType::Unauthorized => "Unauthorized",
+            ProblemType::NotFound => "Not found",
+            ProblemType::Conflict => "Conflict",
+            ProblemType::PayloadTooLarge => "Payload too large",
+            ProblemType::UnsupportedApiVersion => "Unsupported API version",
+            ProblemType::RateLimited => "Too many requests",
+            ProblemType::Internal => "Internal server error",
+            ProblemType::Other => "Request failed",
+        }
+    }
+}
+
+/// Problem details of a failed request
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Problem {
+    #[serde(rename = "type", default = "default_problem_type")]
+    pub problem_type: ProblemType,
+    pub title: String,
+    /// HTTP status code
+    pub status: u16,
+    /// What went wrong with this request
+    #[serde(default)]
+    pub detail: String,
+    /// Invalid request fields, for `InvalidFields`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub fields: Vec<FieldError>,
+    /// Request id to quote when reporting the problem
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub request_id: Option<String>,
+}
+
+fn default_problem_type() -> ProblemType {
+    ProblemType::Other
+}
+
+impl Problem {
+    /// Creates a problem titled after its type
+    pub fn new(problem_type: ProblemType, status: u16, detail: impl Into<String>) -> Self {
+        Self {
+            problem_type,
+            title: problem_type.title().to_string(),
+            status,
+            detail: detail.into(),
+            fields: Vec::new(),
+            request_id: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_problem_round_trip() {
+        let mut problem = Problem::new(ProblemType::InvalidFields, 422, "script: empty");
+        problem.fields = vec![FieldError::new("script", "empty")];
+
+        let json = serde_json::to_value(&problem).unwrap();
+        assert_eq!(json["type"], "urn:rivet:problem:invalid-fields");
+        assert_eq!(json["title"], "Invalid request fields");
+        assert!(json.get("request_id").is_none());
+
+        let parsed: Problem = serde_json::from_value(json).unwrap();
+        assert_eq!(parsed, problem);
+    }
+
+    #[test]
+    fn test_unknown_problem_type() {
+        let problem: Problem = serde_json::from_str(
+            r#"{"type": "https://example.com/teapot", "title": "Teapot", "status": 418}"#,
+        )
+        .unwrap();
+        assert_eq!(problem.problem_type, ProblemType::Other);
+        assert_eq!(problem.detail, "");
+    }
+}
@@ -0,0 +1,11 @@
+//! Project DTOs
+
+use serde::{Deserialize, Serialize};
+
+/// Request to create a new project
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CreateProject {
+    pub name: String,
+    #[serde(default)]
+    pub description: Option<String>,
+}
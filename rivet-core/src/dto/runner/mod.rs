@@ -4,9 +4,65 @@
 
 use serde::{Deserialize, Serialize};
 
+use crate::domain::pipeline::Tag;
+use crate::domain::runner::{RegistrationToken, Runner, RunnerInfo};
+
+/// Header carrying the credential a runner received on registration
+pub const RUNNER_CREDENTIAL_HEADER: &str = "x-runner-credential";
+
+/// Version of the protocol between runners and the orchestrator
+///
+/// Bump it when a change to the job lifecycle calls or DTOs breaks runners
+/// built before it, and raise `MIN_RUNNER_PROTOCOL_VERSION` once the
+/// orchestrator stops serving older runners.
+pub const RUNNER_PROTOCOL_VERSION: u32 = 1;
+
+/// Oldest runner protocol version the orchestrator accepts
+pub const MIN_RUNNER_PROTOCOL_VERSION: u32 = 1;
+
 /// Request to register a runner with the orchestrator
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RegisterRunner {
     /// Unique identifier for the runner
     pub runner_id: String,
+    /// Registration token minted by an admin; required when the orchestrator
+    /// enforces runner authentication
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub registration_token: Option<String>,
+    /// Tags the runner provides, replacing those of an earlier registration
+    #[serde(default)]
+    pub tags: Vec<Tag>,
+    /// Plugins the runner provides, replacing those of an earlier registration
+    #[serde(default)]
+    pub plugins: Vec<String>,
+    /// Build and host of the runner, checked against the protocol versions
+    /// the orchestrator accepts; older runners do not send it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub info: Option<RunnerInfo>,
+}
+
+/// Result of registering a runner
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RunnerRegistration {
+    #[serde(flatten)]
+    pub runner: Runner,
+    /// Credential to send in `RUNNER_CREDENTIAL_HEADER` on job lifecycle
+    /// calls; replaced on every registration. Absent on older orchestrators.
+    #[serde(default)]
+    pub credential: Option<String>,
+}
+
+/// Request to mint a runner registration token
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CreateRegistrationToken {
+    #[serde(default)]
+    pub description: Option<String>,
+}
+
+/// A freshly minted registration token, including its secret
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NewRegistrationToken {
+    #[serde(flatten)]
+    pub info: RegistrationToken,
+    pub token: String,
 }
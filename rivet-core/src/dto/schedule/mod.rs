@@ -0,0 +1,22 @@
+//! Schedule DTOs
+//!
+//! Data transfer objects for the cron schedules of pipelines.
+
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::domain::schedule::CatchUpPolicy;
+
+/// Request to add a cron schedule to a pipeline
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CreateSchedule {
+    pub cron: String,
+    /// IANA time zone, UTC if omitted
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timezone: Option<String>,
+    #[serde(default)]
+    pub catch_up: CatchUpPolicy,
+    #[serde(default)]
+    pub parameters: HashMap<String, serde_json::Value>,
+}
@@ -0,0 +1,34 @@
+//! Secret DTOs
+//!
+//! Data transfer objects for secret management.
+
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// Request to create or replace a secret
+#[derive(Clone, Serialize, Deserialize)]
+pub struct SetSecret {
+    pub name: String,
+    pub value: String,
+    /// Scope the secret to a single pipeline (global when absent)
+    #[serde(default)]
+    pub pipeline_id: Option<Uuid>,
+}
+
+impl std::fmt::Debug for SetSecret {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SetSecret")
+            .field("name", &self.name)
+            .field("value", &"<redacted>")
+            .field("pipeline_id", &self.pipeline_id)
+            .finish()
+    }
+}
+
+/// Query selecting the scope of secret operations
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SecretScope {
+    /// Pipeline scope (global secrets when absent)
+    #[serde(default)]
+    pub pipeline_id: Option<Uuid>,
+}
@@ -0,0 +1,39 @@
+//! Validation DTOs
+//!
+//! Field-level errors the orchestrator returns with `422 Unprocessable
+//! Entity` when a request is well-formed but some of its fields are invalid.
+
+use serde::{Deserialize, Serialize};
+
+/// An invalid field of a request
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FieldError {
+    /// Path of the field in the request body (e.g. `script`, `parameters.version`)
+    pub field: String,
+    /// What is wrong with the field
+    pub message: String,
+}
+
+impl FieldError {
+    pub fn new(field: impl Into<String>, message: impl Into<String>) -> Self {
+        Self {
+            field: field.into(),
+            message: message.into(),
+        }
+    }
+}
+
+impl std::fmt::Display for FieldError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.field, self.message)
+    }
+}
+
+/// Describe a list of invalid fields in one line
+pub fn describe_field_errors(errors: &[FieldError]) -> String {
+    errors
+        .iter()
+        .map(FieldError::to_string)
+        .collect::<Vec<_>>()
+        .join("; ")
+}
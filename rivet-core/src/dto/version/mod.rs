@@ -0,0 +1,18 @@
+//! API Versioning
+//!
+//! The HTTP API is served under `/api/v<version>`. Clients state the version
+//! they speak in the `X-Rivet-Api-Version` header, and the orchestrator
+//! answers with the version it served, so breaking DTO changes can ship as a
+//! new version while deployed runners and CLIs keep using the old one.
+
+/// Version of the HTTP API spoken by this build
+pub const API_VERSION: u32 = 1;
+
+/// Versions of the HTTP API the orchestrator serves
+pub const SUPPORTED_API_VERSIONS: &[u32] = &[1];
+
+/// Path prefix of the current API version
+pub const API_PREFIX: &str = "/api/v1";
+
+/// Header carrying the API version of a request or response
+pub const API_VERSION_HEADER: &str = "x-rivet-api-version";
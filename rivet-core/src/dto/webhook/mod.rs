@@ -0,0 +1,34 @@
+//! Webhook DTOs
+//!
+//! Data transfer objects for webhook triggers and their deliveries.
+
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::domain::webhook::TriggerFilters;
+use crate::dto::commit_status::GitProvider;
+
+/// Request to add a webhook trigger to a pipeline
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CreateWebhookTrigger {
+    pub provider: GitProvider,
+    /// Name of the secret deliveries are verified with
+    pub secret: String,
+    #[serde(default)]
+    pub filters: TriggerFilters,
+    #[serde(default)]
+    pub parameters: HashMap<String, serde_json::Value>,
+}
+
+/// Outcome of a webhook delivery
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WebhookDelivery {
+    /// Job launched for the delivery
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub job_id: Option<Uuid>,
+    /// Why no job was launched
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub skipped: Option<String>,
+}
@@ -5,6 +5,10 @@
 //! This crate contains:
 //! - Domain types: Core business entities (Pipeline, Job, etc.)
 //! - DTOs: Data transfer objects for inter-service communication
+//! - Telemetry: OpenTelemetry trace propagation and export (`telemetry` and
+//!   `otlp` features)
 
 pub mod domain;
 pub mod dto;
+#[cfg(feature = "telemetry")]
+pub mod telemetry;
@@ -0,0 +1,176 @@
+//! OpenTelemetry tracing
+//!
+//! Services pass trace context to each other as W3C `traceparent` values:
+//! clients send the current span's in a header, the orchestrator stores the
+//! launching request's with each job and the runner continues that trace, so
+//! a single trace shows a job from launch to completion.
+//!
+//! With the `otlp` feature, `Telemetry` exports `tracing` spans over
+//! OTLP/HTTP, configured by the standard `OTEL_*` environment variables.
+
+use opentelemetry::Context;
+use opentelemetry::trace::{SpanContext, SpanId, TraceContextExt, TraceFlags, TraceId, TraceState};
+use tracing::Span;
+use tracing_opentelemetry::OpenTelemetrySpanExt;
+
+/// `traceparent` value identifying a span
+///
+/// # Returns
+/// `None` if the span is not recorded by an OpenTelemetry layer
+pub fn traceparent(span: &Span) -> Option<String> {
+    let context = span.context();
+    let span_context = context.span().span_context().clone();
+    if !span_context.is_valid() {
+        return None;
+    }
+
+    Some(format!(
+        "00-{}-{}-{:02x}",
+        span_context.trace_id(),
+        span_context.span_id(),
+        span_context.trace_flags().to_u8()
+    ))
+}
+
+/// Makes `span` a child of the span a `traceparent` value identifies
+///
+/// Malformed values are ignored, leaving the span where it was.
+pub fn set_parent(span: &Span, traceparent: &str) {
+    if let Some(parent) = parse_traceparent(traceparent) {
+        // Only fails when no OpenTelemetry layer records the span
+        let _ = span.set_parent(Context::new().with_remote_span_context(parent));
+    }
+}
+
+/// Parses a version 00 `traceparent` value
+fn parse_traceparent(traceparent: &str) -> Option<SpanContext> {
+    let mut parts = traceparent.trim().split('-');
+    let (version, trace_id, span_id, flags) =
+        (parts.next()?, parts.next()?, parts.next()?, parts.next()?);
+    if version != "00" || parts.next().is_some() {
+        return None;
+    }
+    if trace_id.len() != 32 || span_id.len() != 16 || flags.len() != 2 {
+        return None;
+    }
+
+    let span_context = SpanContext::new(
+        TraceId::from_hex(trace_id).ok()?,
+        SpanId::from_hex(span_id).ok()?,
+        TraceFlags::new(u8::from_str_radix(flags, 16).ok()?),
+        true,
+        TraceState::default(),
+    );
+    span_context.is_valid().then_some(span_context)
+}
+
+// =============================================================================
+// Export
+// =============================================================================
+
+#[cfg(feature = "otlp")]
+pub use export::Telemetry;
+
+#[cfg(feature = "otlp")]
+mod export {
+    use opentelemetry::trace::TracerProvider;
+    use opentelemetry_otlp::SpanExporter;
+    use opentelemetry_sdk::Resource;
+    use opentelemetry_sdk::trace::{SdkTracer, SdkTracerProvider};
+    use tracing_opentelemetry::OpenTelemetryLayer;
+    use tracing_subscriber::registry::LookupSpan;
+
+    /// Environment variables that enable span export
+    const ENDPOINT_VARS: [&str; 2] = [
+        "OTEL_EXPORTER_OTLP_ENDPOINT",
+        "OTEL_EXPORTER_OTLP_TRACES_ENDPOINT",
+    ];
+
+    /// Span export to an OTLP collector
+    #[derive(Debug, Clone)]
+    pub struct Telemetry {
+        provider: SdkTracerProvider,
+    }
+
+    impl Telemetry {
+        /// Sets up span export if an OTLP endpoint is configured
+        ///
+        /// Export is enabled by `OTEL_EXPORTER_OTLP_ENDPOINT` (or
+        /// `OTEL_EXPORTER_OTLP_TRACES_ENDPOINT`); the other `OTEL_*` variables
+        /// (headers, timeout, `OTEL_SERVICE_NAME`, `OTEL_RESOURCE_ATTRIBUTES`)
+        /// are honored as usual.
+        ///
+        /// # Arguments
+        /// * `service_name` - Service name used when `OTEL_SERVICE_NAME` is unset
+        ///
+        /// # Returns
+        /// `None` when no endpoint is configured
+        pub fn from_env(service_name: &'static str) -> Result<Option<Self>, String> {
+            let configured = ENDPOINT_VARS
+                .iter()
+                .any(|var| std::env::var(var).is_ok_and(|v| !v.trim().is_empty()));
+            if !configured {
+                return Ok(None);
+            }
+
+            let exporter = SpanExporter::builder()
+                .with_http()
+                .build()
+                .map_err(|e| format!("Failed to build OTLP exporter: {}", e))?;
+
+            let mut resource = Resource::builder();
+            if std::env::var("OTEL_SERVICE_NAME").is_err() {
+                resource = resource.with_service_name(service_name);
+            }
+
+            let provider = SdkTracerProvider::builder()
+                .with_batch_exporter(exporter)
+                .with_resource(resource.build())
+                .build();
+
+            Ok(Some(Self { provider }))
+        }
+
+        /// `tracing` layer exporting the spans it records
+        pub fn layer<S>(&self) -> OpenTelemetryLayer<S, SdkTracer>
+        where
+            S: tracing::Subscriber + for<'span> LookupSpan<'span>,
+        {
+            tracing_opentelemetry::layer().with_tracer(self.provider.tracer("rivet"))
+        }
+
+        /// Exports the spans still buffered and stops exporting
+        pub fn shutdown(&self) {
+            if let Err(e) = self.provider.shutdown() {
+                eprintln!("Failed to flush traces: {}", e);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_traceparent() {
+        let parsed =
+            parse_traceparent("00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01").unwrap();
+        assert_eq!(
+            parsed.trace_id().to_string(),
+            "4bf92f3577b34da6a3ce929d0e0e4736"
+        );
+        assert_eq!(parsed.span_id().to_string(), "00f067aa0ba902b7");
+        assert!(parsed.is_sampled());
+        assert!(parsed.is_remote());
+
+        assert!(
+            parse_traceparent("00-00000000000000000000000000000000-00f067aa0ba902b7-01").is_none()
+        );
+        assert!(
+            parse_traceparent("01-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01").is_none()
+        );
+        assert!(parse_traceparent("00-4bf92f35-00f067aa0ba902b7-01").is_none());
+        assert!(parse_traceparent("garbage").is_none());
+    }
+}
@@ -0,0 +1,104 @@
+//! Annotations module
+//!
+//! Lets a pipeline label the job it runs in, so that jobs can be found by
+//! what they did rather than only by how they were launched:
+//!
+//! ```lua
+//! annotations.set("release", version)
+//! annotations.set("deployed-to", "prod")
+//! ```
+//!
+//! Annotations are added to the job's labels when it ends, replacing labels
+//! with the same key; they are saved in the job result like `ctx`. Keys and
+//! values follow the rules of labels. Shared by the runner and pipeline unit
+//! tests.
+
+use mlua::prelude::*;
+use rivet_core::domain::job::validate_label;
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+
+/// Annotations a job's scripts set, by key
+pub type Annotations = Arc<Mutex<BTreeMap<String, String>>>;
+
+/// Register the annotations module into a Lua context
+///
+/// Creates an `annotations` global table with functions: set, get, all
+///
+/// # Arguments
+/// * `lua` - The Lua context to register into
+/// * `annotations` - Where the set annotations are kept
+///
+/// # Example
+/// ```no_run
+/// use rivet_lua::annotations::{Annotations, register_annotations_module};
+/// use rivet_lua::create_sandbox;
+///
+/// let lua = create_sandbox()?;
+/// let annotations = Annotations::default();
+/// register_annotations_module(&lua, annotations.clone())?;
+///
+/// lua.load(r#"annotations.set("release", "1.4.0")"#).exec()?;
+/// assert_eq!(annotations.lock().unwrap()["release"], "1.4.0");
+/// # Ok::<(), mlua::Error>(())
+/// ```
+pub fn register_annotations_module(lua: &Lua, annotations: Annotations) -> LuaResult<()> {
+    let table = lua.create_table()?;
+
+    // annotations.set(key, value)
+    {
+        let annotations = annotations.clone();
+        table.set(
+            "set",
+            lua.create_function(move |_, (key, value): (String, LuaValue)| {
+                let value = match value {
+                    LuaValue::String(s) => s.to_str()?.to_string(),
+                    LuaValue::Integer(n) => n.to_string(),
+                    LuaValue::Number(n) => n.to_string(),
+                    LuaValue::Boolean(b) => b.to_string(),
+                    other => {
+                        return Err(LuaError::RuntimeError(format!(
+                            "annotations.set expects a string, number or boolean value, got {}",
+                            other.type_name()
+                        )));
+                    }
+                };
+                validate_label(&key, &value).map_err(LuaError::RuntimeError)?;
+                lock(&annotations).insert(key, value);
+                Ok(())
+            })?,
+        )?;
+    }
+
+    // annotations.get(key)
+    {
+        let annotations = annotations.clone();
+        table.set(
+            "get",
+            lua.create_function(move |_, key: String| Ok(lock(&annotations).get(&key).cloned()))?,
+        )?;
+    }
+
+    // annotations.all()
+    {
+        let annotations = annotations.clone();
+        table.set(
+            "all",
+            lua.create_function(move |lua, ()| {
+                let table = lua.create_table()?;
+                for (key, value) in lock(&annotations).iter() {
+                    table.set(key.as_str(), value.as_str())?;
+                }
+                Ok(table)
+            })?,
+        )?;
+    }
+
+    lua.globals().set("annotations", table)?;
+    Ok(())
+}
+
+/// Locks the annotations, even if a panic poisoned the lock
+fn lock(annotations: &Annotations) -> std::sync::MutexGuard<'_, BTreeMap<String, String>> {
+    annotations.lock().unwrap_or_else(|e| e.into_inner())
+}
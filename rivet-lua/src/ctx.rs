@@ -0,0 +1,66 @@
+//! Ctx module
+//!
+//! Provides the `ctx` table, shared by all stages of one job, so a stage can
+//! hand values to the stages after it (a build stage the image tag it
+//! pushed, a deploy stage reading it) without global variables:
+//!
+//! ```lua
+//! ctx.image = "ghcr.io/acme/app:" .. sha
+//! -- in a later stage
+//! process.run({ cmd = "kubectl", args = { "set", "image", "deploy/app", "app=" .. ctx.image } })
+//! ```
+//!
+//! The table lives in memory while the job runs and is saved in the job
+//! result when it ends, so values must be strings, numbers, booleans or
+//! tables of them. Shared by the runner and pipeline unit tests.
+
+use mlua::prelude::*;
+
+/// Register the ctx module into a Lua context
+///
+/// Creates a `ctx` global table, holding `initial` when given
+///
+/// # Arguments
+/// * `lua` - The Lua context to register into
+/// * `initial` - Values to start from (e.g. those of the job a job resumes);
+///   anything but a JSON object starts an empty table
+///
+/// # Example
+/// ```no_run
+/// use rivet_lua::ctx::register_ctx_module;
+/// use rivet_lua::create_sandbox;
+///
+/// let lua = create_sandbox()?;
+/// register_ctx_module(&lua, None)?;
+///
+/// lua.load(r#"ctx.image = "app:1.2.0""#).exec()?;
+/// # Ok::<(), mlua::Error>(())
+/// ```
+pub fn register_ctx_module(lua: &Lua, initial: Option<&serde_json::Value>) -> LuaResult<()> {
+    let ctx = match initial {
+        Some(values) if values.is_object() => lua.to_value(values)?,
+        _ => LuaValue::Table(lua.create_table()?),
+    };
+    lua.globals().set("ctx", ctx)
+}
+
+/// The `ctx` table as JSON
+///
+/// # Arguments
+/// * `lua` - The Lua context the module was registered into
+///
+/// # Returns
+/// The table's values, `None` when it is empty or was replaced by something
+/// that is not a table, or an error for values JSON cannot hold (functions,
+/// userdata, ...)
+pub fn ctx_to_json(lua: &Lua) -> LuaResult<Option<serde_json::Value>> {
+    let ctx = match lua.globals().get::<LuaValue>("ctx")? {
+        LuaValue::Table(ctx) => ctx,
+        _ => return Ok(None),
+    };
+    if ctx.pairs::<LuaValue, LuaValue>().next().is_none() {
+        return Ok(None);
+    }
+
+    lua.from_value(LuaValue::Table(ctx)).map(Some)
+}
@@ -6,7 +6,11 @@
 
 use anyhow::Result;
 use mlua::{Function, Lua, Table, Value};
-use std::collections::HashMap;
+use regex::Regex;
+use rivet_core::domain::job::StageResult;
+use rivet_core::domain::pipeline::{self, InputType};
+use rivet_core::dto::pipeline::{InputInfo, PipelineDefinitionInfo, StageInfo};
+use std::collections::{BTreeMap, HashMap, HashSet};
 
 #[derive(Debug, Clone)]
 pub struct Tag {
@@ -16,11 +20,117 @@ pub struct Tag {
 
 #[derive(Debug, Clone)]
 pub struct InputDefinition {
-    pub input_type: String,
+    pub input_type: InputType,
+    /// Type of the elements of an `array` input (any scalar when unset)
+    pub items: Option<InputType>,
     pub description: Option<String>,
     pub required: bool,
     pub default: Option<serde_json::Value>,
     pub options: Option<Vec<serde_json::Value>>,
+    /// Regular expression string values must match (anywhere, as in JSON
+    /// Schema; anchor it with `^...$` to match the whole value)
+    pub pattern: Option<Regex>,
+    /// Smallest number accepted
+    pub min: Option<f64>,
+    /// Largest number accepted
+    pub max: Option<f64>,
+    /// Fewest characters of a string, or elements of an array
+    pub min_length: Option<usize>,
+    /// Most characters of a string, or elements of an array
+    pub max_length: Option<usize>,
+}
+
+impl InputDefinition {
+    /// Check a value of the input's type against the input's constraints
+    ///
+    /// Constraints on strings and numbers apply to each element of an array,
+    /// lengths to the array itself.
+    ///
+    /// # Returns
+    /// Every constraint the value breaks, empty when it meets them all
+    pub fn constraint_violations(&self, value: &serde_json::Value) -> Vec<String> {
+        let mut violations = Vec::new();
+
+        match value {
+            serde_json::Value::Array(items) => {
+                self.check_length(items.len(), "items", &mut violations);
+                for (index, item) in items.iter().enumerate() {
+                    let mut item_violations = Vec::new();
+                    self.check_scalar(item, &mut item_violations);
+                    violations.extend(
+                        item_violations
+                            .into_iter()
+                            .map(|violation| format!("Item {}: {}", index, violation)),
+                    );
+                }
+            }
+            serde_json::Value::String(s) => {
+                self.check_length(s.chars().count(), "characters", &mut violations);
+                self.check_scalar(value, &mut violations);
+            }
+            _ => self.check_scalar(value, &mut violations),
+        }
+
+        violations
+    }
+
+    /// Check the pattern of a string, or the bounds of a number
+    fn check_scalar(&self, value: &serde_json::Value, violations: &mut Vec<String>) {
+        match value {
+            serde_json::Value::String(s) => {
+                if let Some(pattern) = &self.pattern
+                    && !pattern.is_match(s)
+                {
+                    violations.push(format!("Must match pattern '{}'", pattern.as_str()));
+                }
+            }
+            serde_json::Value::Number(n) => {
+                let n = n.as_f64().unwrap_or_default();
+                if let Some(min) = self.min
+                    && n < min
+                {
+                    violations.push(format!("Must be at least {}", min));
+                }
+                if let Some(max) = self.max
+                    && n > max
+                {
+                    violations.push(format!("Must be at most {}", max));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Check a length against `min_length` and `max_length`
+    fn check_length(&self, length: usize, unit: &str, violations: &mut Vec<String>) {
+        if let Some(min_length) = self.min_length
+            && length < min_length
+        {
+            violations.push(format!("Must have at least {} {}", min_length, unit));
+        }
+        if let Some(max_length) = self.max_length
+            && length > max_length
+        {
+            violations.push(format!("Must have at most {} {}", max_length, unit));
+        }
+    }
+
+    /// Serializable form of the input
+    pub fn to_info(&self) -> InputInfo {
+        InputInfo {
+            input_type: self.input_type,
+            items: self.items,
+            description: self.description.clone(),
+            required: self.required,
+            default: self.default.clone(),
+            options: self.options.clone(),
+            pattern: self.pattern.as_ref().map(|p| p.as_str().to_string()),
+            min: self.min,
+            max: self.max,
+            min_length: self.min_length,
+            max_length: self.max_length,
+        }
+    }
 }
 
 /// Full pipeline definition with executable Lua functions
@@ -33,6 +143,21 @@ pub struct PipelineDefinition {
     pub inputs: HashMap<String, InputDefinition>,
     pub runner: Vec<Tag>,
     pub plugins: Vec<String>,
+    /// Times a failed job is automatically retried (0 = never)
+    pub max_retries: u32,
+    /// Image of the container stages run in unless they declare their own
+    /// (`None` = the runner's default image)
+    pub default_container: Option<String>,
+    /// Images of the default container by architecture, preferred over
+    /// `default_container`
+    pub default_container_variants: BTreeMap<String, String>,
+    /// Environment variables set in the job's containers and shown by the
+    /// `env` module
+    pub env: BTreeMap<String, String>,
+    /// Called with the stage name before every stage script
+    pub before_each: Option<Function>,
+    /// Called with the stage name after every stage script, even a failed one
+    pub after_each: Option<Function>,
     pub stages: Vec<StageDefinition>,
 }
 
@@ -40,10 +165,100 @@ pub struct PipelineDefinition {
 pub struct StageDefinition {
     pub name: String,
     pub container: Option<String>,
+    /// Images of the stage's container by architecture, preferred over
+    /// `container`
+    pub container_variants: BTreeMap<String, String>,
+    /// Stages this one needs, as declared (`None` waits for the previous stage)
+    pub needs: Option<Vec<String>>,
+    /// Indices of the stages this one waits for, resolved from `needs`
+    pub dependencies: Vec<usize>,
+    /// Whether a failure of this stage leaves the job successful
+    pub allow_failure: bool,
+    /// Environment the stage deploys to, whose deployments it records
+    pub deploys_to: Option<String>,
+    pub when: StageWhen,
     pub condition: Option<Function>,
     pub script: Function,
 }
 
+impl PipelineDefinition {
+    /// Serializable parts of the definition, without its functions
+    pub fn to_info(&self) -> PipelineDefinitionInfo {
+        PipelineDefinitionInfo {
+            name: self.name.clone(),
+            description: self.description.clone(),
+            inputs: self
+                .inputs
+                .iter()
+                .map(|(name, input)| (name.clone(), input.to_info()))
+                .collect(),
+            runner: self
+                .runner
+                .iter()
+                .map(|tag| pipeline::Tag {
+                    key: tag.key.clone(),
+                    value: tag.value.clone(),
+                })
+                .collect(),
+            plugins: self.plugins.clone(),
+            max_retries: self.max_retries,
+            default_container: self.default_container.clone(),
+            default_container_variants: self.default_container_variants.clone(),
+            env: self.env.clone(),
+            before_each: self.before_each.is_some(),
+            after_each: self.after_each.is_some(),
+            stages: self.stages.iter().map(StageDefinition::to_info).collect(),
+        }
+    }
+}
+
+impl StageDefinition {
+    /// Image of the stage's container on a runner of an architecture
+    /// (`None` = the job's default container)
+    pub fn container_for(&self, arch: &str) -> Option<&str> {
+        image_for(self.container.as_deref(), &self.container_variants, arch)
+    }
+
+    /// Serializable parts of the stage, without its functions
+    pub fn to_info(&self) -> StageInfo {
+        StageInfo {
+            name: self.name.clone(),
+            container: self.container.clone(),
+            container_variants: self.container_variants.clone(),
+            needs: self.needs.clone(),
+            allow_failure: self.allow_failure,
+            deploys_to: self.deploys_to.clone(),
+            when: self.when.as_str().to_string(),
+            has_condition: self.condition.is_some(),
+        }
+    }
+}
+
+/// When a stage runs, depending on the stages it waits for
+///
+/// "Waits for" is transitive: a stage waiting for a cleanup stage still sees
+/// the failure the cleanup stage ran after.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StageWhen {
+    /// Only if none of the stages it waits for failed (default)
+    OnSuccess,
+    /// Only if one of the stages it waits for failed
+    OnFailure,
+    /// Whether or not the stages it waits for failed
+    Always,
+}
+
+impl StageWhen {
+    /// Name of the policy as written in pipeline scripts
+    pub fn as_str(self) -> &'static str {
+        match self {
+            StageWhen::OnSuccess => "on_success",
+            StageWhen::OnFailure => "on_failure",
+            StageWhen::Always => "always",
+        }
+    }
+}
+
 /// Parse a pipeline definition from Lua source code in an execution sandbox
 ///
 /// This function evaluates the pipeline in a Lua execution sandbox and extracts
@@ -85,6 +300,20 @@ pub fn parse_pipeline_definition(lua: &Lua, source: &str) -> Result<PipelineDefi
     // Extract plugins
     let plugins = parse_plugins_from_table(&pipeline)?;
 
+    // Extract retry policy
+    let max_retries = parse_max_retries_from_table(&pipeline)?;
+
+    // Extract default container image
+    let (default_container, default_container_variants) =
+        parse_default_container_from_table(&pipeline)?;
+
+    // Extract environment variables
+    let env = parse_env_from_table(&pipeline)?;
+
+    // Extract stage hooks
+    let before_each = parse_hook_from_table(&pipeline, "before_each")?;
+    let after_each = parse_hook_from_table(&pipeline, "after_each")?;
+
     // Extract stages with functions
     let stages = parse_stages_from_table(&pipeline)?;
 
@@ -94,6 +323,12 @@ pub fn parse_pipeline_definition(lua: &Lua, source: &str) -> Result<PipelineDefi
         inputs,
         runner,
         plugins,
+        max_retries,
+        default_container,
+        default_container_variants,
+        env,
+        before_each,
+        after_each,
         stages,
     })
 }
@@ -114,6 +349,33 @@ fn parse_inputs_from_table(pipeline: &Table) -> Result<HashMap<String, InputDefi
                 let input_type: String = input_table.get("type").map_err(|e| {
                     anyhow::anyhow!("Input '{}' must have a 'type' field: {}", key, e)
                 })?;
+                let input_type: InputType = input_type
+                    .parse()
+                    .map_err(|e| anyhow::anyhow!("Input '{}': {}", key, e))?;
+
+                let items = match input_table.get::<Option<String>>("items") {
+                    Ok(Some(items)) => {
+                        let items: InputType = items
+                            .parse()
+                            .map_err(|e| anyhow::anyhow!("Input '{}' items: {}", key, e))?;
+                        if input_type != InputType::Array || !items.is_scalar() {
+                            return Err(anyhow::anyhow!(
+                                "Input '{}': 'items' is only allowed on array inputs, \
+                                 as string, number or bool",
+                                key
+                            ));
+                        }
+                        Some(items)
+                    }
+                    Ok(None) => None,
+                    Err(e) => {
+                        return Err(anyhow::anyhow!(
+                            "Input '{}' items must be a type name: {}",
+                            key,
+                            e
+                        ));
+                    }
+                };
 
                 let description: Option<String> = input_table.get("description").ok();
                 let required: bool = input_table.get("required").unwrap_or(true);
@@ -151,16 +413,36 @@ fn parse_inputs_from_table(pipeline: &Table) -> Result<HashMap<String, InputDefi
                     _ => return Err(anyhow::anyhow!("Input '{}' options must be an array", key)),
                 };
 
-                inputs.insert(
-                    key,
-                    InputDefinition {
-                        input_type,
-                        description,
-                        required,
-                        default,
-                        options,
-                    },
-                );
+                if input_type == InputType::Enum && options.as_ref().is_none_or(Vec::is_empty) {
+                    return Err(anyhow::anyhow!(
+                        "Input '{}' is an enum and must list its options",
+                        key
+                    ));
+                }
+                if matches!(input_type, InputType::Secret | InputType::File) && default.is_some() {
+                    return Err(anyhow::anyhow!(
+                        "Input '{}' is a {} and cannot have a default",
+                        key,
+                        input_type
+                    ));
+                }
+
+                let mut input = InputDefinition {
+                    input_type,
+                    items,
+                    description,
+                    required,
+                    default,
+                    options,
+                    pattern: None,
+                    min: None,
+                    max: None,
+                    min_length: None,
+                    max_length: None,
+                };
+                parse_input_constraints(&key, &input_table, &mut input)?;
+
+                inputs.insert(key, input);
             }
 
             Ok(inputs)
@@ -171,6 +453,114 @@ fn parse_inputs_from_table(pipeline: &Table) -> Result<HashMap<String, InputDefi
     }
 }
 
+/// Parse the constraints of an input: pattern, min, max, min_length, max_length
+///
+/// Each constraint is only allowed on the inputs it can apply to, e.g. `min`
+/// on numbers and arrays of numbers.
+fn parse_input_constraints(
+    key: &str,
+    input_table: &Table,
+    input: &mut InputDefinition,
+) -> Result<()> {
+    let field = |name: &str| match input_table.get::<Value>(name) {
+        Ok(Value::Nil) | Err(_) => None,
+        Ok(value) => Some(value),
+    };
+    let number = |name: &str| -> Result<Option<f64>> {
+        match field(name) {
+            None => Ok(None),
+            Some(Value::Integer(n)) => Ok(Some(n as f64)),
+            Some(Value::Number(n)) => Ok(Some(n)),
+            Some(_) => Err(anyhow::anyhow!("Input '{}' {} must be a number", key, name)),
+        }
+    };
+    let length = |name: &str| -> Result<Option<usize>> {
+        match field(name) {
+            None => Ok(None),
+            Some(Value::Integer(n)) if n >= 0 => Ok(Some(n as usize)),
+            Some(_) => Err(anyhow::anyhow!(
+                "Input '{}' {} must be a non-negative integer",
+                key,
+                name
+            )),
+        }
+    };
+    let allow = |constraints: &str, allowed: bool, inputs: &str| -> Result<()> {
+        match allowed {
+            true => Ok(()),
+            false => Err(anyhow::anyhow!(
+                "Input '{}': {} only apply to {}",
+                key,
+                constraints,
+                inputs
+            )),
+        }
+    };
+
+    let is_array = input.input_type == InputType::Array;
+    let strings = matches!(input.input_type, InputType::String | InputType::Secret)
+        || (is_array && matches!(input.items, None | Some(InputType::String)));
+    let numbers = input.input_type == InputType::Number
+        || (is_array && matches!(input.items, None | Some(InputType::Number)));
+
+    input.pattern =
+        match field("pattern") {
+            None => None,
+            Some(Value::String(pattern)) => {
+                let pattern = pattern.to_str().map_err(|e| {
+                    anyhow::anyhow!("Input '{}' pattern is not valid UTF-8: {}", key, e)
+                })?;
+                Some(Regex::new(&pattern).map_err(|e| {
+                    anyhow::anyhow!("Input '{}' has an invalid pattern: {}", key, e)
+                })?)
+            }
+            Some(_) => return Err(anyhow::anyhow!("Input '{}' pattern must be a string", key)),
+        };
+    if input.pattern.is_some() {
+        allow(
+            "patterns",
+            strings,
+            "string and secret inputs and arrays of strings",
+        )?;
+    }
+
+    input.min = number("min")?;
+    input.max = number("max")?;
+    if input.min.is_some() || input.max.is_some() {
+        allow(
+            "min and max",
+            numbers,
+            "number inputs and arrays of numbers",
+        )?;
+    }
+    if let (Some(min), Some(max)) = (input.min, input.max)
+        && min > max
+    {
+        return Err(anyhow::anyhow!("Input '{}' min is above its max", key));
+    }
+
+    input.min_length = length("min_length")?;
+    input.max_length = length("max_length")?;
+    if input.min_length.is_some() || input.max_length.is_some() {
+        let lengths = is_array || matches!(input.input_type, InputType::String | InputType::Secret);
+        allow(
+            "min_length and max_length",
+            lengths,
+            "string, secret and array inputs",
+        )?;
+    }
+    if let (Some(min_length), Some(max_length)) = (input.min_length, input.max_length)
+        && min_length > max_length
+    {
+        return Err(anyhow::anyhow!(
+            "Input '{}' min_length is above its max_length",
+            key
+        ));
+    }
+
+    Ok(())
+}
+
 /// Parse runner tags from pipeline table
 fn parse_runner_tags_from_table(pipeline: &Table) -> Result<Vec<Tag>> {
     let runner_value: Value = pipeline.get("runner").unwrap_or(Value::Nil);
@@ -222,6 +612,215 @@ fn parse_plugins_from_table(pipeline: &Table) -> Result<Vec<String>> {
     }
 }
 
+/// Parse max_retries from pipeline table
+fn parse_max_retries_from_table(pipeline: &Table) -> Result<u32> {
+    match pipeline.get::<Value>("max_retries").unwrap_or(Value::Nil) {
+        Value::Nil => Ok(0),
+        Value::Integer(n) if n >= 0 => {
+            u32::try_from(n).map_err(|_| anyhow::anyhow!("Field 'max_retries' is too large: {}", n))
+        }
+        Value::Number(n) if n >= 0.0 && n.fract() == 0.0 && n <= u32::MAX as f64 => Ok(n as u32),
+        _ => Err(anyhow::anyhow!(
+            "Field 'max_retries' must be a non-negative integer"
+        )),
+    }
+}
+
+/// Parse default_container from pipeline table
+fn parse_default_container_from_table(
+    pipeline: &Table,
+) -> Result<(Option<String>, BTreeMap<String, String>)> {
+    parse_container(
+        pipeline
+            .get::<Value>("default_container")
+            .unwrap_or(Value::Nil),
+        "Field 'default_container'",
+    )
+}
+
+/// Parse env from pipeline table
+///
+/// Values may be strings, numbers or booleans, the latter two set as text.
+fn parse_env_from_table(pipeline: &Table) -> Result<BTreeMap<String, String>> {
+    let table = match pipeline.get::<Value>("env").unwrap_or(Value::Nil) {
+        Value::Nil => return Ok(BTreeMap::new()),
+        Value::Table(table) => table,
+        _ => {
+            return Err(anyhow::anyhow!(
+                "Field 'env' must map variable names to values"
+            ));
+        }
+    };
+
+    let mut env = BTreeMap::new();
+    for pair in table.pairs::<Value, Value>() {
+        let (name, value) = pair?;
+        let name = match name {
+            Value::String(name) if is_env_name(&name.to_str()?) => name.to_str()?.to_string(),
+            _ => {
+                return Err(anyhow::anyhow!(
+                    "Field 'env' keys must be variable names (letters, digits and '_', not starting with a digit)"
+                ));
+            }
+        };
+        let value = match value {
+            Value::String(value) => value.to_str()?.to_string(),
+            Value::Integer(value) => value.to_string(),
+            Value::Number(value) => value.to_string(),
+            Value::Boolean(value) => value.to_string(),
+            _ => {
+                return Err(anyhow::anyhow!(
+                    "Field 'env.{}' must be a string, number or boolean",
+                    name
+                ));
+            }
+        };
+        env.insert(name, value);
+    }
+    Ok(env)
+}
+
+/// Whether `name` is a portable environment variable name
+fn is_env_name(name: &str) -> bool {
+    !name.is_empty()
+        && !name.starts_with(|c: char| c.is_ascii_digit())
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// Parse a container image, or images by architecture
+///
+/// A string is the image for every architecture. A table maps architectures
+/// (`amd64`, `arm64`, ...) to images, with the image for the others under
+/// `default`.
+///
+/// # Arguments
+/// * `value` - The `container` or `default_container` field
+/// * `field` - The field, for error messages
+///
+/// # Returns
+/// The image for every architecture, and the images by architecture
+fn parse_container(
+    value: Value,
+    field: &str,
+) -> Result<(Option<String>, BTreeMap<String, String>)> {
+    let image = |value: Value| match value {
+        Value::String(image) if !image.to_str()?.trim().is_empty() => {
+            Ok(image.to_str()?.trim().to_string())
+        }
+        _ => Err(anyhow::anyhow!("{} must name a non-empty image", field)),
+    };
+
+    match value {
+        Value::Nil => Ok((None, BTreeMap::new())),
+        Value::String(_) => Ok((Some(image(value)?), BTreeMap::new())),
+        Value::Table(table) => {
+            let mut default = None;
+            let mut variants = BTreeMap::new();
+            for pair in table.pairs::<String, Value>() {
+                let (arch, value) = pair
+                    .map_err(|_| anyhow::anyhow!("{} must map architectures to images", field))?;
+                if arch == "default" {
+                    default = Some(image(value)?);
+                } else {
+                    variants.insert(arch, image(value)?);
+                }
+            }
+            Ok((default, variants))
+        }
+        _ => Err(anyhow::anyhow!(
+            "{} must be an image or a table of images by architecture",
+            field
+        )),
+    }
+}
+
+/// Picks the image for an architecture
+///
+/// # Arguments
+/// * `image` - Image for every architecture
+/// * `variants` - Images by architecture, preferred over `image`
+/// * `arch` - Architecture of the runner (`amd64`, `arm64`, ...)
+pub fn image_for<'a>(
+    image: Option<&'a str>,
+    variants: &'a BTreeMap<String, String>,
+    arch: &str,
+) -> Option<&'a str> {
+    variants.get(arch).map(String::as_str).or(image)
+}
+
+/// Parse the value a stage script returned
+///
+/// Scripts may return `{ artifacts = {...}, metrics = {...}, summary = "..." }`;
+/// other fields of the table are ignored, so returning e.g. the result of
+/// `process.run` is harmless.
+///
+/// # Arguments
+/// * `value` - The script's return value
+///
+/// # Returns
+/// The stage result, `None` when the script returned nothing to keep
+pub fn parse_stage_result(value: Value) -> Result<Option<StageResult>> {
+    let table = match value {
+        Value::Nil => return Ok(None),
+        Value::Table(table) => table,
+        other => {
+            return Err(anyhow::anyhow!(
+                "Stage returned a {}, expected a table with artifacts, metrics and summary",
+                other.type_name()
+            ));
+        }
+    };
+
+    let mut result = StageResult::default();
+
+    match table.get::<Value>("artifacts")? {
+        Value::Nil => {}
+        Value::Table(artifacts) => {
+            for artifact in artifacts.sequence_values::<String>() {
+                result.artifacts.push(artifact.map_err(|_| {
+                    anyhow::anyhow!("Stage result 'artifacts' must be an array of strings")
+                })?);
+            }
+        }
+        _ => anyhow::bail!("Stage result 'artifacts' must be an array of strings"),
+    }
+
+    match table.get::<Value>("metrics")? {
+        Value::Nil => {}
+        Value::Table(metrics) => {
+            for pair in metrics.pairs::<String, Value>() {
+                let metric = pair.ok().and_then(|(name, value)| match value {
+                    Value::Integer(n) => Some((name, n as f64)),
+                    Value::Number(n) if n.is_finite() => Some((name, n)),
+                    _ => None,
+                });
+                let (name, value) = metric.ok_or_else(|| {
+                    anyhow::anyhow!("Stage result 'metrics' must map names to numbers")
+                })?;
+                result.metrics.insert(name, value);
+            }
+        }
+        _ => anyhow::bail!("Stage result 'metrics' must map names to numbers"),
+    }
+
+    match table.get::<Value>("summary")? {
+        Value::Nil => {}
+        Value::String(summary) => result.summary = Some(summary.to_str()?.to_string()),
+        _ => anyhow::bail!("Stage result 'summary' must be a string"),
+    }
+
+    Ok((!result.is_empty()).then_some(result))
+}
+
+/// Parse a stage hook function from pipeline table
+fn parse_hook_from_table(pipeline: &Table, field: &str) -> Result<Option<Function>> {
+    match pipeline.get::<Value>(field).unwrap_or(Value::Nil) {
+        Value::Nil => Ok(None),
+        Value::Function(hook) => Ok(Some(hook)),
+        _ => Err(anyhow::anyhow!("Field '{}' must be a function", field)),
+    }
+}
+
 /// Parse stages from pipeline table
 fn parse_stages_from_table(pipeline: &Table) -> Result<Vec<StageDefinition>> {
     let stages_table: Table = pipeline
@@ -237,7 +836,59 @@ fn parse_stages_from_table(pipeline: &Table) -> Result<Vec<StageDefinition>> {
             .get("name")
             .map_err(|e| anyhow::anyhow!("Stage must have a 'name' field: {}", e))?;
 
-        let container: Option<String> = stage_table.get("container").ok();
+        let (container, container_variants) = parse_container(
+            stage_table.get::<Value>("container").unwrap_or(Value::Nil),
+            &format!("Stage '{}' field 'container'", name),
+        )?;
+
+        let needs = match stage_table.get::<Value>("needs").unwrap_or(Value::Nil) {
+            Value::Nil => None,
+            Value::Table(table) => {
+                let mut needs = Vec::new();
+                for pair in table.sequence_values::<String>() {
+                    let need = pair.map_err(|e| {
+                        anyhow::anyhow!("Failed to read needs entry of stage '{}': {}", name, e)
+                    })?;
+                    needs.push(need);
+                }
+                Some(needs)
+            }
+            _ => {
+                return Err(anyhow::anyhow!(
+                    "Stage '{}' field 'needs' must be an array of stage names",
+                    name
+                ));
+            }
+        };
+
+        let allow_failure = match stage_table
+            .get::<Value>("allow_failure")
+            .unwrap_or(Value::Nil)
+        {
+            Value::Nil => false,
+            Value::Boolean(allow) => allow,
+            _ => {
+                return Err(anyhow::anyhow!(
+                    "Stage '{}' field 'allow_failure' must be a boolean",
+                    name
+                ));
+            }
+        };
+
+        let deploys_to = match stage_table.get::<Value>("deploys_to").unwrap_or(Value::Nil) {
+            Value::Nil => None,
+            Value::String(environment) if !environment.to_str()?.trim().is_empty() => {
+                Some(environment.to_str()?.to_string())
+            }
+            _ => {
+                return Err(anyhow::anyhow!(
+                    "Stage '{}' field 'deploys_to' must be a non-empty environment name",
+                    name
+                ));
+            }
+        };
+
+        let when = parse_stage_when(&stage_table, &name)?;
 
         let condition: Option<Function> = stage_table.get("condition").ok();
 
@@ -248,6 +899,12 @@ fn parse_stages_from_table(pipeline: &Table) -> Result<Vec<StageDefinition>> {
         stages.push(StageDefinition {
             name,
             container,
+            container_variants,
+            needs,
+            dependencies: Vec::new(),
+            allow_failure,
+            deploys_to,
+            when,
             condition,
             script,
         });
@@ -257,9 +914,147 @@ fn parse_stages_from_table(pipeline: &Table) -> Result<Vec<StageDefinition>> {
         return Err(anyhow::anyhow!("Pipeline must have at least one stage"));
     }
 
+    let declared: Vec<(&str, Option<&[String]>)> = stages
+        .iter()
+        .map(|s| (s.name.as_str(), s.needs.as_deref()))
+        .collect();
+    let dependencies = resolve_dependencies(&declared)?;
+    for (stage, dependencies) in stages.iter_mut().zip(dependencies) {
+        stage.dependencies = dependencies;
+    }
+
     Ok(stages)
 }
 
+/// Parse when a stage runs from its `when` and `always` fields
+///
+/// `always = true` is shorthand for `when = "always"`.
+fn parse_stage_when(stage_table: &Table, name: &str) -> Result<StageWhen> {
+    let when = match stage_table.get::<Value>("when").unwrap_or(Value::Nil) {
+        Value::Nil => None,
+        Value::String(when) => match &*when.to_str()? {
+            "on_success" => Some(StageWhen::OnSuccess),
+            "on_failure" => Some(StageWhen::OnFailure),
+            "always" => Some(StageWhen::Always),
+            other => {
+                return Err(anyhow::anyhow!(
+                    "Stage '{}' has invalid 'when' value '{}' (expected on_success, on_failure or always)",
+                    name,
+                    other
+                ));
+            }
+        },
+        _ => {
+            return Err(anyhow::anyhow!(
+                "Stage '{}' field 'when' must be a string",
+                name
+            ));
+        }
+    };
+
+    let always = match stage_table.get::<Value>("always").unwrap_or(Value::Nil) {
+        Value::Nil => false,
+        Value::Boolean(always) => always,
+        _ => {
+            return Err(anyhow::anyhow!(
+                "Stage '{}' field 'always' must be a boolean",
+                name
+            ));
+        }
+    };
+
+    match (when, always) {
+        (Some(when), true) if when != StageWhen::Always => Err(anyhow::anyhow!(
+            "Stage '{}' sets both 'always' and 'when = \"{}\"'",
+            name,
+            when.as_str()
+        )),
+        (_, true) => Ok(StageWhen::Always),
+        (when, false) => Ok(when.unwrap_or(StageWhen::OnSuccess)),
+    }
+}
+
+/// Resolve the stages each stage waits for
+///
+/// A stage without `needs` waits for the stage declared before it, so
+/// pipelines that never use `needs` keep running their stages in order.
+///
+/// # Arguments
+/// * `stages` - Name and declared `needs` of each stage, in declaration order
+///
+/// # Returns
+/// For each stage, the indices of the stages it waits for
+///
+/// # Errors
+/// Returns an error if:
+/// - Two stages share a name
+/// - A stage needs itself or a stage that does not exist
+/// - The stages form a dependency cycle
+pub fn resolve_dependencies(stages: &[(&str, Option<&[String]>)]) -> Result<Vec<Vec<usize>>> {
+    let mut indices = HashMap::new();
+    for (idx, (name, _)) in stages.iter().enumerate() {
+        if indices.insert(*name, idx).is_some() {
+            return Err(anyhow::anyhow!(
+                "Stage name '{}' is used more than once",
+                name
+            ));
+        }
+    }
+
+    let mut dependencies = Vec::with_capacity(stages.len());
+    for (idx, (name, needs)) in stages.iter().enumerate() {
+        let Some(needs) = needs else {
+            dependencies.push(idx.checked_sub(1).into_iter().collect());
+            continue;
+        };
+
+        let mut resolved = Vec::new();
+        for need in needs.iter() {
+            let dependency = *indices.get(need.as_str()).ok_or_else(|| {
+                anyhow::anyhow!("Stage '{}' needs unknown stage '{}'", name, need)
+            })?;
+            if dependency == idx {
+                return Err(anyhow::anyhow!("Stage '{}' cannot need itself", name));
+            }
+            if !resolved.contains(&dependency) {
+                resolved.push(dependency);
+            }
+        }
+        dependencies.push(resolved);
+    }
+
+    // Kahn's algorithm: whatever cannot be ordered is part of a cycle
+    let mut remaining: Vec<usize> = dependencies.iter().map(Vec::len).collect();
+    let mut ready: Vec<usize> = (0..stages.len()).filter(|&i| remaining[i] == 0).collect();
+    let mut ordered = HashSet::new();
+    while let Some(idx) = ready.pop() {
+        ordered.insert(idx);
+        for (dependent, deps) in dependencies.iter().enumerate() {
+            if deps.contains(&idx) {
+                remaining[dependent] -= 1;
+                if remaining[dependent] == 0 {
+                    ready.push(dependent);
+                }
+            }
+        }
+    }
+
+    if ordered.len() < stages.len() {
+        let cycle: Vec<&str> = stages
+            .iter()
+            .enumerate()
+            .filter(|(idx, _)| !ordered.contains(idx))
+            .map(|(_, (name, _))| *name)
+            .collect();
+        return Err(anyhow::anyhow!(
+            "Stages form a dependency cycle: {}",
+            cycle.join(", ")
+        ));
+    }
+
+    Ok(dependencies)
+}
+
 /// Convert mlua Value to serde_json Value
 fn lua_value_to_json(val: &Value) -> Result<serde_json::Value> {
     match val {
@@ -279,3 +1074,74 @@ fn lua_value_to_json(val: &Value) -> Result<serde_json::Value> {
         )),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_image_for_prefers_arch_variant() {
+        let variants = BTreeMap::from([("arm64".to_string(), "arm64v8/rust:1".to_string())]);
+
+        assert_eq!(
+            image_for(Some("rust:1"), &variants, "arm64"),
+            Some("arm64v8/rust:1")
+        );
+        assert_eq!(
+            image_for(Some("rust:1"), &variants, "amd64"),
+            Some("rust:1")
+        );
+        assert_eq!(image_for(None, &variants, "amd64"), None);
+    }
+
+    fn needs(names: &[&str]) -> Vec<String> {
+        names.iter().map(|n| n.to_string()).collect()
+    }
+
+    #[test]
+    fn test_env_names() {
+        assert!(is_env_name("RUST_LOG"));
+        assert!(is_env_name("_private2"));
+        assert!(!is_env_name(""));
+        assert!(!is_env_name("2FA"));
+        assert!(!is_env_name("NODE-ENV"));
+        assert!(!is_env_name("A=B"));
+    }
+
+    #[test]
+    fn test_stages_without_needs_run_in_order() {
+        let deps = resolve_dependencies(&[("a", None), ("b", None), ("c", None)]).unwrap();
+        assert_eq!(deps, vec![vec![], vec![0], vec![1]]);
+    }
+
+    #[test]
+    fn test_needs_resolve_to_indices() {
+        let root = needs(&[]);
+        let test = needs(&["build", "lint"]);
+        let deps = resolve_dependencies(&[
+            ("test", Some(&test)),
+            ("build", Some(&root)),
+            ("lint", Some(&root)),
+            ("deploy", None),
+        ])
+        .unwrap();
+        assert_eq!(deps, vec![vec![1, 2], vec![], vec![], vec![2]]);
+    }
+
+    #[test]
+    fn test_invalid_needs_are_rejected() {
+        let unknown = needs(&["missing"]);
+        let err = resolve_dependencies(&[("a", Some(&unknown))]).unwrap_err();
+        assert!(err.to_string().contains("unknown stage 'missing'"));
+
+        let err = resolve_dependencies(&[("a", None), ("a", None)]).unwrap_err();
+        assert!(err.to_string().contains("more than once"));
+
+        let a = needs(&["b"]);
+        let b = needs(&["a"]);
+        let c = needs(&[]);
+        let err =
+            resolve_dependencies(&[("a", Some(&a)), ("b", Some(&b)), ("c", Some(&c))]).unwrap_err();
+        assert_eq!(err.to_string(), "Stages form a dependency cycle: a, b");
+    }
+}
@@ -0,0 +1,79 @@
+//! Env module
+//!
+//! Provides read access to the environment variables a pipeline declares in
+//! its `env` table, the same ones set in every container of the job:
+//!
+//! ```lua
+//! local mode = env.get("BUILD_MODE", "debug")
+//! ```
+//!
+//! Shared by the runner and pipeline unit tests.
+
+use mlua::prelude::*;
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+/// Register the env module into a Lua context
+///
+/// Creates an `env` global table with functions: get, has, all
+///
+/// # Arguments
+/// * `lua` - The Lua context to register into
+/// * `vars` - Environment variables of the pipeline
+///
+/// # Example
+/// ```no_run
+/// use rivet_lua::env::register_env_module;
+/// use rivet_lua::create_sandbox;
+/// use std::collections::BTreeMap;
+///
+/// let lua = create_sandbox()?;
+/// let mut vars = BTreeMap::new();
+/// vars.insert("BUILD_MODE".to_string(), "release".to_string());
+/// register_env_module(&lua, vars)?;
+///
+/// lua.load(r#"local mode = env.get("BUILD_MODE", "debug")"#).exec()?;
+/// # Ok::<(), mlua::Error>(())
+/// ```
+pub fn register_env_module(lua: &Lua, vars: BTreeMap<String, String>) -> LuaResult<()> {
+    let vars = Arc::new(vars);
+    let env_table = lua.create_table()?;
+
+    // env.get(name, default?)
+    {
+        let vars = vars.clone();
+        env_table.set(
+            "get",
+            lua.create_function(move |_, (name, default): (String, Option<String>)| {
+                Ok(vars.get(&name).cloned().or(default))
+            })?,
+        )?;
+    }
+
+    // env.has(name)
+    {
+        let vars = vars.clone();
+        env_table.set(
+            "has",
+            lua.create_function(move |_, name: String| Ok(vars.contains_key(&name)))?,
+        )?;
+    }
+
+    // env.all()
+    {
+        let vars = vars.clone();
+        env_table.set(
+            "all",
+            lua.create_function(move |lua, ()| {
+                let table = lua.create_table()?;
+                for (key, value) in vars.iter() {
+                    table.set(key.as_str(), value.as_str())?;
+                }
+                Ok(table)
+            })?,
+        )?;
+    }
+
+    lua.globals().set("env", env_table)?;
+    Ok(())
+}
@@ -1,13 +1,15 @@
-//! Input module implementation for the runner
+//! Input module
 //!
-//! Provides access to job input parameters in Lua scripts.
+//! Provides access to job input parameters in Lua scripts. Shared by the
+//! runner, which executes stages, and the orchestrator, which evaluates
+//! stage conditions when planning a job.
 
 use mlua::prelude::*;
 use std::collections::HashMap;
 
 /// Register the input module into a Lua context
 ///
-/// Creates an `input` global table with functions: get, require, has, all, keys
+/// Creates an `input` global table with functions: get, require, has, all, keys, list
 ///
 /// # Arguments
 /// * `lua` - The Lua context to register into
@@ -15,11 +17,11 @@ use std::collections::HashMap;
 ///
 /// # Example
 /// ```no_run
-/// use rivet_runner::lua::modules::register_input_module;
-/// use rivet_lua::create_execution_sandbox;
+/// use rivet_lua::input::register_input_module;
+/// use rivet_lua::create_sandbox;
 /// use std::collections::HashMap;
 ///
-/// let lua = create_execution_sandbox()?;
+/// let lua = create_sandbox()?;
 /// let mut params = HashMap::new();
 /// params.insert("branch".to_string(), serde_json::Value::String("main".to_string()));
 /// register_input_module(&lua, params)?;
@@ -31,6 +33,18 @@ pub fn register_input_module(
     lua: &Lua,
     parameters: HashMap<String, serde_json::Value>,
 ) -> LuaResult<()> {
+    // Elements of array inputs, as strings, for input.list
+    let lists: HashMap<String, Vec<String>> = parameters
+        .iter()
+        .filter_map(|(key, value)| {
+            let items = value.as_array()?.iter().map(|item| match item {
+                serde_json::Value::String(s) => s.clone(),
+                other => other.to_string(),
+            });
+            Some((key.clone(), items.collect()))
+        })
+        .collect();
+
     // Convert JSON values to strings for Lua consumption
     let vars: HashMap<String, String> = parameters
         .into_iter()
@@ -116,6 +130,22 @@ pub fn register_input_module(
         )?;
     }
 
+    // input.list(name): elements of an array input (empty when unset)
+    {
+        let vars = vars.clone();
+        input_table.set(
+            "list",
+            lua.create_function(move |lua, name: String| {
+                let items = match (lists.get(&name), vars.get(&name)) {
+                    (Some(items), _) => items.clone(),
+                    (None, Some(value)) => vec![value.clone()],
+                    (None, None) => Vec::new(),
+                };
+                lua.create_sequence_from(items)
+            })?,
+        )?;
+    }
+
     lua.globals().set("input", input_table)?;
     Ok(())
 }
@@ -256,6 +286,22 @@ mod tests {
         assert_eq!(count, 3);
     }
 
+    #[test]
+    fn test_input_list() {
+        let lua = Lua::new();
+        let mut params = create_test_params();
+        params.insert("targets".to_string(), serde_json::json!(["linux", 32]));
+
+        register_input_module(&lua, params).unwrap();
+
+        let script = r#"
+            local targets = input.list("targets")
+            return #targets, targets[1], targets[2], #input.list("branch"), #input.list("missing")
+        "#;
+        let result: (i32, String, String, i32, i32) = lua.load(script).eval().unwrap();
+        assert_eq!(result, (2, "linux".to_string(), "32".to_string(), 1, 0));
+    }
+
     #[test]
     fn test_input_empty() {
         let lua = Lua::new();
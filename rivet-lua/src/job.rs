@@ -0,0 +1,105 @@
+//! Job module
+//!
+//! Provides the read-only `job` table, describing the job a script runs in
+//! so that images, releases and notifications can be traced back to it:
+//!
+//! ```lua
+//! local tag = job.commit and job.commit.sha or job.id
+//! log.info(job.pipeline_name .. " #" .. job.number .. " attempt " .. job.attempt)
+//! ```
+//!
+//! `job.commit` is set for jobs launched by a webhook (or with a commit),
+//! `job.schedule` for jobs launched by a cron schedule. Shared by the runner
+//! and pipeline unit tests.
+
+use mlua::prelude::*;
+use rivet_core::domain::job::CommitRef;
+use rivet_core::dto::job::ScheduleRef;
+use serde::Serialize;
+use uuid::Uuid;
+
+/// What the `job` table shows
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct JobMetadata {
+    pub id: Uuid,
+    pub pipeline_id: Uuid,
+    pub pipeline_name: String,
+    /// Number of the job within its pipeline, starting at 1
+    pub number: u64,
+    /// Attempt of the job, starting at 1
+    pub attempt: u32,
+    /// Runner running the job
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub runner_id: Option<String>,
+    /// Commit the job builds
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub commit: Option<CommitRef>,
+    /// Schedule that launched the job
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub schedule: Option<ScheduleRef>,
+}
+
+/// Register the job module into a Lua context
+///
+/// Creates a `job` global table; assigning to it, or to the tables it
+/// holds, raises an error
+///
+/// # Arguments
+/// * `lua` - The Lua context to register into
+/// * `job` - The job the scripts run in
+///
+/// # Example
+/// ```no_run
+/// use rivet_lua::create_sandbox;
+/// use rivet_lua::job::{JobMetadata, register_job_module};
+///
+/// let lua = create_sandbox()?;
+/// register_job_module(&lua, &JobMetadata::default())?;
+///
+/// lua.load(r#"local tag = "app:" .. job.id"#).exec()?;
+/// # Ok::<(), mlua::Error>(())
+/// ```
+pub fn register_job_module(lua: &Lua, job: &JobMetadata) -> LuaResult<()> {
+    let values = serde_json::to_value(job).map_err(LuaError::external)?;
+    register_job_values(lua, &values)
+}
+
+/// Register `values`, a JSON object, as the `job` table
+///
+/// Lets pipeline unit tests register metadata they scripted.
+pub(crate) fn register_job_values(lua: &Lua, values: &serde_json::Value) -> LuaResult<()> {
+    let job = read_only(lua, lua.to_value(values)?)?;
+    lua.globals().set("job", job)
+}
+
+/// `value` behind a read-only proxy, tables nested in it too
+fn read_only(lua: &Lua, value: LuaValue) -> LuaResult<LuaValue> {
+    let LuaValue::Table(table) = value else {
+        return Ok(value);
+    };
+
+    let nested: Vec<(LuaValue, LuaValue)> = table
+        .pairs::<LuaValue, LuaValue>()
+        .filter(|pair| matches!(pair, Ok((_, LuaValue::Table(_)))))
+        .collect::<LuaResult<_>>()?;
+    for (key, value) in nested {
+        table.raw_set(key, read_only(lua, value)?)?;
+    }
+
+    let metatable = lua.create_table()?;
+    metatable.set("__index", table)?;
+    metatable.set(
+        "__newindex",
+        lua.create_function(|_, (_, key): (LuaValue, LuaValue)| {
+            Err::<(), _>(LuaError::RuntimeError(format!(
+                "job is read-only (assigning '{}')",
+                key.to_string()?
+            )))
+        })?,
+    )?;
+    metatable.set("__metatable", false)?;
+
+    let proxy = lua.create_table()?;
+    proxy.set_metatable(Some(metatable))?;
+    Ok(LuaValue::Table(proxy))
+}
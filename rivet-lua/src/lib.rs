@@ -4,12 +4,30 @@
 //! It includes:
 //! - Two sandbox types: metadata evaluation and full execution
 //! - Pipeline parsing and manifest extraction
+//! - Job planning (which stages a job would run)
+//! - Unit testing of stage functions against mock modules
 //!
 //! Module implementations live in rivet-runner where they have access to
-//! runtime dependencies (container runtime, orchestrator connection, etc.).
+//! runtime dependencies (container runtime, orchestrator connection, etc.),
+//! except the input module, which the orchestrator needs for planning, and
+//! the annotations, ctx, env and job modules, which pipeline unit tests need
+//! too, and the error of `process.run { check = true }`.
 
+pub mod annotations;
+pub mod ctx;
 pub mod definition;
+pub mod env;
+pub mod input;
+pub mod job;
+pub mod plan;
+pub mod process;
 pub mod sandbox;
+pub mod testing;
 
-pub use definition::{PipelineDefinition, StageDefinition, parse_pipeline_definition};
+pub use definition::{
+    PipelineDefinition, StageDefinition, StageWhen, image_for, parse_pipeline_definition,
+    parse_stage_result,
+};
+pub use plan::plan_stages;
 pub use sandbox::create_sandbox;
+pub use testing::{TestOutcome, run_pipeline_tests};
@@ -0,0 +1,58 @@
+//! Job planning
+//!
+//! Works out which stages of a pipeline a job would run without executing
+//! any of them. Plans assume every stage succeeds: stages that only run
+//! after a failure are skipped, and conditions are evaluated against the
+//! job's parameters. A condition needing more than the `input` module (a
+//! process, a container) cannot be decided ahead of the job.
+
+use rivet_core::dto::job::{PlannedStage, StageDecision};
+
+use crate::definition::{PipelineDefinition, StageWhen};
+
+/// Plans the stages of a pipeline
+///
+/// # Arguments
+/// * `definition` - The pipeline, parsed in a sandbox where the `input`
+///   module holds the job's parameters
+///
+/// # Returns
+/// Every stage, in declaration order, with whether it would run
+pub fn plan_stages(definition: &PipelineDefinition) -> Vec<PlannedStage> {
+    definition
+        .stages
+        .iter()
+        .map(|stage| {
+            let (decision, reason) = match (&stage.condition, stage.when) {
+                (_, StageWhen::OnFailure) => (
+                    StageDecision::Skip,
+                    Some("Runs only after a failure".to_string()),
+                ),
+                (None, _) => (StageDecision::Run, None),
+                (Some(condition), _) => match condition.call::<bool>(()) {
+                    Ok(true) => (StageDecision::Run, None),
+                    Ok(false) => (StageDecision::Skip, Some("Condition not met".to_string())),
+                    Err(e) => (
+                        StageDecision::Undecided,
+                        Some(format!(
+                            "Condition cannot be evaluated before the job runs: {}",
+                            e
+                        )),
+                    ),
+                },
+            };
+
+            PlannedStage {
+                name: stage.name.clone(),
+                container: stage.container.clone(),
+                needs: stage
+                    .dependencies
+                    .iter()
+                    .map(|&d| definition.stages[d].name.clone())
+                    .collect(),
+                decision,
+                reason,
+            }
+        })
+        .collect()
+}
@@ -0,0 +1,65 @@
+//! Process helpers
+//!
+//! The error `process.run { check = true }` raises for a failed or timed out
+//! command, shared by the runner and pipeline unit tests so that scripts
+//! asserting on it behave the same under both.
+
+use mlua::prelude::*;
+
+/// Lines of stderr ending the error of a command run with `check`
+pub const CHECK_STDERR_LINES: usize = 20;
+
+/// Turns a failed or timed out command run with `check` into a Lua error
+///
+/// The error ends with the last `CHECK_STDERR_LINES` non-blank lines of the
+/// command's stderr, captured or not.
+pub fn check_exit(cmd: &str, exit_code: i64, timed_out: bool, stderr: &str) -> LuaResult<()> {
+    let failure = if timed_out {
+        format!("Command '{}' timed out", cmd)
+    } else if exit_code != 0 {
+        format!("Command '{}' failed with exit code {}", cmd, exit_code)
+    } else {
+        return Ok(());
+    };
+
+    let lines: Vec<&str> = stderr
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .collect();
+    let tail = &lines[lines.len().saturating_sub(CHECK_STDERR_LINES)..];
+    if tail.is_empty() {
+        Err(LuaError::RuntimeError(failure))
+    } else {
+        Err(LuaError::RuntimeError(format!(
+            "{}:\n{}",
+            failure,
+            tail.join("\n")
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_exit_keeps_last_stderr_lines() {
+        assert!(check_exit("true", 0, false, "warning").is_ok());
+
+        let stderr: String = (1..=30).map(|n| format!("line {}\n\n", n)).collect();
+        let message = check_exit("make", 2, false, &stderr)
+            .unwrap_err()
+            .to_string();
+        assert!(
+            message
+                .starts_with("runtime error: Command 'make' failed with exit code 2:\nline 11\n")
+        );
+        assert!(message.ends_with("line 30"));
+        assert!(!message.contains("line 10\n"));
+
+        let message = check_exit("sleep", 124, true, " \n")
+            .unwrap_err()
+            .to_string();
+        assert_eq!(message, "runtime error: Command 'sleep' timed out");
+    }
+}
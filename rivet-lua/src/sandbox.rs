@@ -7,7 +7,7 @@
 //! Core modules (log, input, process, container, etc.) are registered by the caller
 //! after creating the sandbox, typically in the runner.
 
-use mlua::{Lua, LuaOptions, Result as LuaResult, StdLib, Table};
+use mlua::{Lua, LuaOptions, Result as LuaResult, StdLib, Table, Value};
 
 /// Create a restricted Lua sandbox
 ///
@@ -171,6 +171,30 @@ fn create_pipeline_builder(lua: &Lua) -> LuaResult<Table> {
     })?;
     metatable.set("plugin", plugin_fn)?;
 
+    let max_retries_fn = lua.create_function(|_, (builder, retries): (Table, Value)| {
+        builder.set("_max_retries", retries)?;
+        Ok(builder)
+    })?;
+    metatable.set("max_retries", max_retries_fn)?;
+
+    let default_container_fn = lua.create_function(|_, (builder, image): (Table, Value)| {
+        builder.set("_default_container", image)?;
+        Ok(builder)
+    })?;
+    metatable.set("default_container", default_container_fn)?;
+
+    let before_each_fn = lua.create_function(|_, (builder, hook): (Table, Value)| {
+        builder.set("_before_each", hook)?;
+        Ok(builder)
+    })?;
+    metatable.set("before_each", before_each_fn)?;
+
+    let after_each_fn = lua.create_function(|_, (builder, hook): (Table, Value)| {
+        builder.set("_after_each", hook)?;
+        Ok(builder)
+    })?;
+    metatable.set("after_each", after_each_fn)?;
+
     let stage_fn = lua.create_function(|lua, (builder, stage): (Table, Table)| {
         let stages: Table = match builder.get("_stages") {
             Ok(t) => t,
@@ -205,6 +229,13 @@ fn create_pipeline_builder(lua: &Lua) -> LuaResult<Table> {
         if let Ok(plugins) = builder.get::<Table>("_plugins") {
             definition.set("plugins", plugins)?;
         }
+        definition.set("max_retries", builder.get::<Value>("_max_retries")?)?;
+        definition.set(
+            "default_container",
+            builder.get::<Value>("_default_container")?,
+        )?;
+        definition.set("before_each", builder.get::<Value>("_before_each")?)?;
+        definition.set("after_each", builder.get::<Value>("_after_each")?)?;
         if let Ok(stages) = builder.get::<Table>("_stages") {
             definition.set("stages", stages)?;
         }
@@ -0,0 +1,968 @@
+//! Pipeline unit testing
+//!
+//! Runs a pipeline's stage functions against mock `log`, `process` and
+//! `container` modules, so that pipeline logic can be tested without
+//! containers or an orchestrator. Used by `rivet pipeline test`.
+//!
+//! A test file registers tests with `test(name, fn)`. Tests script the
+//! mocks, run stages and check what the stages did:
+//!
+//! ```lua
+//! test("release builds are optimized", function()
+//!     mock.inputs({ profile = "release" })
+//!     mock.process("cargo", { stdout = "Finished" })
+//!     run_stage("build")
+//!     expect.ran("cargo", { "build", "--release" })
+//!     expect.logged("Finished")
+//! end)
+//! ```
+//!
+//! Every test starts from fresh mocks, the inputs' defaults, no annotations,
+//! an empty `ctx` table, which tests may fill to stand in for earlier
+//! stages, and a `job` table of a first attempt with nil ids, which
+//! `mock.job` overrides. The `env` module holds the pipeline's `env` table in
+//! every test. The mocks behave like the runner's modules: output that is not
+//! captured is logged, and failed builds and pushes raise errors.
+
+use anyhow::Result;
+use mlua::{
+    Error as LuaError, FromLua, Function, Lua, LuaSerdeExt, MultiValue, Result as LuaResult, Table,
+    Value,
+};
+use rivet_core::domain::log::LogLevel;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use crate::annotations::register_annotations_module;
+use crate::ctx::register_ctx_module;
+use crate::definition::{PipelineDefinition, parse_pipeline_definition};
+use crate::env::register_env_module;
+use crate::input::register_input_module;
+use crate::job::{JobMetadata, register_job_values};
+use crate::process::check_exit;
+use crate::sandbox::create_sandbox;
+
+/// Outcome of one test
+#[derive(Debug, Clone)]
+pub struct TestOutcome {
+    pub name: String,
+    /// Why the test failed, `None` if it passed
+    pub failure: Option<String>,
+}
+
+impl TestOutcome {
+    pub fn passed(&self) -> bool {
+        self.failure.is_none()
+    }
+}
+
+/// Runs the tests of a pipeline
+///
+/// # Arguments
+/// * `pipeline_source` - The Lua source code defining the pipeline
+/// * `tests_source` - The Lua source code registering the tests
+///
+/// # Returns
+/// The outcome of every test, in registration order
+///
+/// # Errors
+/// Returns an error if the pipeline or the test file cannot be evaluated
+pub fn run_pipeline_tests(pipeline_source: &str, tests_source: &str) -> Result<Vec<TestOutcome>> {
+    let lua = create_sandbox().map_err(|e| anyhow::anyhow!("Failed to create sandbox: {}", e))?;
+    let mocks = Mocks::default();
+
+    register_mock_modules(&lua, &mocks)
+        .map_err(|e| anyhow::anyhow!("Failed to register mock modules: {}", e))?;
+    register_input_module(&lua, HashMap::new())
+        .map_err(|e| anyhow::anyhow!("Failed to register input module: {}", e))?;
+
+    let definition = parse_pipeline_definition(&lua, pipeline_source)?;
+    register_env_module(&lua, definition.env.clone())
+        .map_err(|e| anyhow::anyhow!("Failed to register env module: {}", e))?;
+    let defaults: HashMap<String, serde_json::Value> = definition
+        .inputs
+        .iter()
+        .filter_map(|(name, input)| Some((name.clone(), input.default.clone()?)))
+        .collect();
+    let job = serde_json::to_value(JobMetadata {
+        pipeline_name: definition.name.clone(),
+        number: 1,
+        attempt: 1,
+        runner_id: Some("test-runner".to_string()),
+        ..Default::default()
+    })?;
+
+    let tests = Arc::new(Mutex::new(Vec::new()));
+    register_test_globals(&lua, &mocks, Arc::new(definition), &defaults, &job, &tests)
+        .map_err(|e| anyhow::anyhow!("Failed to register test functions: {}", e))?;
+
+    lua.load(tests_source)
+        .set_name("tests")
+        .exec()
+        .map_err(|e| anyhow::anyhow!("Failed to evaluate tests: {}", e))?;
+
+    let tests: Vec<(String, Function)> = std::mem::take(&mut *lock(&tests));
+    let mut outcomes = Vec::with_capacity(tests.len());
+    for (name, test) in tests {
+        *lock(&mocks) = MockState::default();
+        let result = register_input_module(&lua, defaults.clone())
+            .and_then(|_| register_ctx_module(&lua, None))
+            .and_then(|_| register_annotations_module(&lua, Default::default()))
+            .and_then(|_| register_job_values(&lua, &job))
+            .and_then(|_| test.call::<()>(()));
+
+        outcomes.push(TestOutcome {
+            name,
+            failure: result.err().map(|e| failure_message(&e)),
+        });
+    }
+
+    Ok(outcomes)
+}
+
+// =============================================================================
+// Mock State
+// =============================================================================
+
+/// Command a stage ran through `process.run`
+#[derive(Debug, Clone)]
+struct ProcessCall {
+    cmd: String,
+    args: Vec<String>,
+    /// Image of the innermost `container.with`, if any
+    container: Option<String>,
+}
+
+/// Scripted result of a process, build or push (success by default)
+#[derive(Debug, Clone, Default)]
+struct MockOutput {
+    exit_code: i64,
+    stdout: String,
+    stderr: String,
+    timed_out: bool,
+}
+
+impl MockOutput {
+    fn from_table(table: &Table) -> LuaResult<Self> {
+        Ok(Self {
+            exit_code: table.get::<Option<i64>>("exit_code")?.unwrap_or(0),
+            stdout: table.get::<Option<String>>("stdout")?.unwrap_or_default(),
+            stderr: table.get::<Option<String>>("stderr")?.unwrap_or_default(),
+            timed_out: table.get::<Option<bool>>("timed_out")?.unwrap_or(false),
+        })
+    }
+}
+
+/// What the mocks were told to return, and what the stages did with them
+#[derive(Default)]
+struct MockState {
+    /// Scripted outputs per command, used in order; the last one repeats
+    outputs: HashMap<String, Vec<MockOutput>>,
+    /// Scripted builds and pushes per image tag
+    builds: HashMap<String, MockOutput>,
+    pushes: HashMap<String, MockOutput>,
+    calls: Vec<ProcessCall>,
+    /// Images of the enclosing `container.with` calls, innermost last
+    containers: Vec<String>,
+    built: Vec<String>,
+    pushed: Vec<String>,
+    /// Log lines with the structured fields of their logger
+    logs: Vec<(LogLevel, String, LogFields)>,
+}
+
+/// Structured fields attached to log lines by `log.with`
+type LogFields = BTreeMap<String, serde_json::Value>;
+
+impl MockState {
+    fn log(&mut self, level: LogLevel, message: impl Into<String>) {
+        self.logs.push((level, message.into(), LogFields::new()));
+    }
+
+    /// Logs every non-blank line of output
+    fn log_output(&mut self, level: LogLevel, output: &str) {
+        for line in output.lines().filter(|line| !line.trim().is_empty()) {
+            self.log(level, line);
+        }
+    }
+
+    /// Next scripted output of `cmd`
+    fn next_output(&mut self, cmd: &str) -> MockOutput {
+        match self.outputs.get_mut(cmd) {
+            Some(outputs) if outputs.len() > 1 => outputs.remove(0),
+            Some(outputs) => outputs.first().cloned().unwrap_or_default(),
+            None => MockOutput::default(),
+        }
+    }
+}
+
+type Mocks = Arc<Mutex<MockState>>;
+
+/// Locks a mutex, recovering it if a previous holder panicked
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+// =============================================================================
+// Mock Modules
+// =============================================================================
+
+/// Registers the mock `log`, `process` and `container` modules
+fn register_mock_modules(lua: &Lua, mocks: &Mocks) -> LuaResult<()> {
+    let string: Table = lua.globals().get("string")?;
+    let log_table = create_mock_logger(lua, mocks, string.get("format")?, LogFields::new())?;
+    lua.globals().set("log", log_table)?;
+
+    let process_table = lua.create_table()?;
+    {
+        let mocks = mocks.clone();
+        process_table.set(
+            "run",
+            lua.create_function(move |lua, options: Table| process_run(lua, &mocks, &options))?,
+        )?;
+    }
+    lua.globals().set("process", process_table)?;
+
+    let container_table = lua.create_table()?;
+    {
+        let mocks = mocks.clone();
+        container_table.set(
+            "with",
+            lua.create_function(
+                move |_, (image, second, third): (String, Value, Option<Function>)| {
+                    let func = match (second, third) {
+                        (Value::Function(func), None) | (Value::Table(_), Some(func)) => func,
+                        _ => {
+                            return Err(LuaError::RuntimeError(
+                                "container.with expects (image, fn) or (image, options, fn)"
+                                    .to_string(),
+                            ));
+                        }
+                    };
+
+                    lock(&mocks).containers.push(image);
+                    let result = func.call::<()>(());
+                    lock(&mocks).containers.pop();
+                    result
+                },
+            )?,
+        )?;
+    }
+    {
+        let mocks = mocks.clone();
+        container_table.set(
+            "build",
+            lua.create_function(move |_, options: Table| {
+                let tag: String = options.get("tag").map_err(|_| {
+                    LuaError::RuntimeError("container.build requires 'tag' field".to_string())
+                })?;
+                let mut state = lock(&mocks);
+                state.log(LogLevel::Info, format!("Building image {}", tag));
+                let output = state.builds.get(&tag).cloned().unwrap_or_default();
+                state.log_output(LogLevel::Info, &output.stdout);
+                state.log_output(LogLevel::Info, &output.stderr);
+                check_output(&output, &format!("Build of image {}", tag))?;
+                state.log(LogLevel::Info, format!("Built image {}", tag));
+                state.built.push(tag.clone());
+                Ok(tag)
+            })?,
+        )?;
+    }
+    {
+        let mocks = mocks.clone();
+        container_table.set(
+            "push",
+            lua.create_function(move |_, options: Table| {
+                let tag: String = options.get("tag").map_err(|_| {
+                    LuaError::RuntimeError("container.push requires 'tag' field".to_string())
+                })?;
+                let mut state = lock(&mocks);
+                state.log(LogLevel::Info, format!("Pushing image {}", tag));
+                let output = state.pushes.get(&tag).cloned().unwrap_or_default();
+                state.log_output(LogLevel::Info, &output.stdout);
+                state.log_output(LogLevel::Info, &output.stderr);
+                check_output(&output, &format!("Push of image {}", tag))?;
+                state.log(LogLevel::Info, format!("Pushed image {}", tag));
+                state.pushed.push(tag);
+                Ok(())
+            })?,
+        )?;
+    }
+    lua.globals().set("container", container_table)?;
+
+    Ok(())
+}
+
+/// `process.run(options)`, answered with the command's next scripted output
+fn process_run(lua: &Lua, mocks: &Mocks, options: &Table) -> LuaResult<Table> {
+    let cmd: String = options
+        .get("cmd")
+        .map_err(|_| LuaError::RuntimeError("process.run requires 'cmd' field".to_string()))?;
+    let args: Vec<String> = options
+        .get::<Option<Vec<String>>>("args")?
+        .unwrap_or_default();
+    let capture_stdout: bool = options
+        .get::<Option<bool>>("capture_stdout")?
+        .unwrap_or(false);
+    let capture_stderr: bool = options
+        .get::<Option<bool>>("capture_stderr")?
+        .unwrap_or(false);
+    let level = |field: &str, default: LogLevel| -> LuaResult<LogLevel> {
+        Ok(options
+            .get::<Option<String>>(field)?
+            .and_then(|level| level.parse().ok())
+            .unwrap_or(default))
+    };
+    let stdout_level = level("stdout_level", LogLevel::Info)?;
+    let stderr_level = level("stderr_level", LogLevel::Error)?;
+    let check: bool = options.get::<Option<bool>>("check")?.unwrap_or(false);
+
+    let mut state = lock(mocks);
+    let output = state.next_output(&cmd);
+    let container = state.containers.last().cloned();
+    state.calls.push(ProcessCall {
+        cmd: cmd.clone(),
+        args,
+        container,
+    });
+
+    if !capture_stdout {
+        state.log_output(stdout_level, &output.stdout);
+    }
+    if !capture_stderr {
+        state.log_output(stderr_level, &output.stderr);
+    }
+    if output.timed_out {
+        state.log(
+            LogLevel::Error,
+            format!("Command '{}' timed out and was killed", cmd),
+        );
+    }
+    if check {
+        check_exit(&cmd, output.exit_code, output.timed_out, &output.stderr)?;
+    }
+
+    let result = lua.create_table()?;
+    result.set("exit_code", output.exit_code)?;
+    result.set("timed_out", output.timed_out)?;
+    if capture_stdout {
+        result.set("stdout", output.stdout)?;
+    }
+    if capture_stderr {
+        result.set("stderr", output.stderr)?;
+    }
+    Ok(result)
+}
+
+/// Turns a failed or timed out build/push into a Lua error, as the runner does
+fn check_output(output: &MockOutput, what: &str) -> LuaResult<()> {
+    if output.timed_out {
+        return Err(LuaError::RuntimeError(format!("{} timed out", what)));
+    }
+    if output.exit_code != 0 {
+        return Err(LuaError::RuntimeError(format!(
+            "{} failed with exit code {}",
+            what, output.exit_code
+        )));
+    }
+    Ok(())
+}
+
+/// The mock `log` table, or a logger `log.with` returned, recording its
+/// lines with `fields`
+fn create_mock_logger(
+    lua: &Lua,
+    mocks: &Mocks,
+    format: Function,
+    fields: LogFields,
+) -> LuaResult<Table> {
+    let logger = lua.create_table()?;
+    // A leading table argument is the logger of a method call
+    let arguments = |args: MultiValue| -> Vec<Value> {
+        let mut args: Vec<Value> = args.into_iter().collect();
+        if matches!(args.first(), Some(Value::Table(_))) {
+            args.remove(0);
+        }
+        args
+    };
+
+    for (name, level) in [
+        ("debug", LogLevel::Debug),
+        ("info", LogLevel::Info),
+        ("warning", LogLevel::Warning),
+        ("error", LogLevel::Error),
+    ] {
+        {
+            let mocks = mocks.clone();
+            let fields = fields.clone();
+            logger.set(
+                name,
+                lua.create_function(move |lua, args: MultiValue| {
+                    let message = match arguments(args).into_iter().next() {
+                        Some(message) => String::from_lua(message, lua)?,
+                        None => String::new(),
+                    };
+                    lock(&mocks).logs.push((level, message, fields.clone()));
+                    Ok(())
+                })?,
+            )?;
+        }
+        {
+            let mocks = mocks.clone();
+            let format = format.clone();
+            let fields = fields.clone();
+            logger.set(
+                format!("{}f", name),
+                lua.create_function(move |_, args: MultiValue| {
+                    let message: String = format.call(MultiValue::from_iter(arguments(args)))?;
+                    lock(&mocks).logs.push((level, message, fields.clone()));
+                    Ok(())
+                })?,
+            )?;
+        }
+    }
+
+    {
+        let mocks = mocks.clone();
+        logger.set(
+            "with",
+            lua.create_function(move |lua, args: MultiValue| {
+                let mut merged = fields.clone();
+                if let Some(extra) = args.into_iter().last() {
+                    merged.extend(lua.from_value::<LogFields>(extra)?);
+                }
+                create_mock_logger(lua, &mocks, format.clone(), merged)
+            })?,
+        )?;
+    }
+
+    Ok(logger)
+}
+
+// =============================================================================
+// Test Functions
+// =============================================================================
+
+/// Registers `test`, `run_stage`, `stage_runs`, `mock` and `expect`
+fn register_test_globals(
+    lua: &Lua,
+    mocks: &Mocks,
+    definition: Arc<PipelineDefinition>,
+    defaults: &HashMap<String, serde_json::Value>,
+    job: &serde_json::Value,
+    tests: &Arc<Mutex<Vec<(String, Function)>>>,
+) -> LuaResult<()> {
+    let globals = lua.globals();
+
+    // test(name, fn)
+    {
+        let tests = tests.clone();
+        globals.set(
+            "test",
+            lua.create_function(move |_, (name, func): (String, Function)| {
+                lock(&tests).push((name, func));
+                Ok(())
+            })?,
+        )?;
+    }
+
+    // run_stage(name): runs the stage between the pipeline's hooks, in its
+    // container, returning what the stage returned
+    {
+        let mocks = mocks.clone();
+        let definition = definition.clone();
+        globals.set(
+            "run_stage",
+            lua.create_function(move |_, name: String| {
+                let stage = find_stage(&definition, &name)?;
+                if let Some(image) = &stage.container {
+                    lock(&mocks).containers.push(image.clone());
+                }
+
+                let mut returned = Value::Nil;
+                let mut result = match &definition.before_each {
+                    Some(hook) => hook.call::<()>(name.as_str()),
+                    None => Ok(()),
+                };
+                if result.is_ok() {
+                    result = stage.script.call::<Value>(()).map(|value| returned = value);
+                }
+                if let Some(hook) = &definition.after_each {
+                    let after = hook.call::<()>(name.as_str());
+                    result = result.and(after);
+                }
+
+                if stage.container.is_some() {
+                    lock(&mocks).containers.pop();
+                }
+                result.map(|()| returned)
+            })?,
+        )?;
+    }
+
+    // stage_runs(name): whether the stage's condition passes
+    globals.set(
+        "stage_runs",
+        lua.create_function(move |_, name: String| {
+            match &find_stage(&definition, &name)?.condition {
+                Some(condition) => condition.call::<bool>(()),
+                None => Ok(true),
+            }
+        })?,
+    )?;
+
+    globals.set("mock", create_mock_table(lua, mocks, defaults, job)?)?;
+    globals.set("expect", create_expect_table(lua, mocks)?)?;
+
+    Ok(())
+}
+
+fn find_stage<'a>(
+    definition: &'a PipelineDefinition,
+    name: &str,
+) -> LuaResult<&'a crate::definition::StageDefinition> {
+    definition
+        .stages
+        .iter()
+        .find(|stage| stage.name == name)
+        .ok_or_else(|| LuaError::RuntimeError(format!("Pipeline has no stage '{}'", name)))
+}
+
+/// The `mock` table, scripting the mocks and exposing what they recorded
+fn create_mock_table(
+    lua: &Lua,
+    mocks: &Mocks,
+    defaults: &HashMap<String, serde_json::Value>,
+    job: &serde_json::Value,
+) -> LuaResult<Table> {
+    let mock = lua.create_table()?;
+
+    // mock.inputs(values): job parameters, over the inputs' defaults
+    {
+        let defaults = defaults.clone();
+        mock.set(
+            "inputs",
+            lua.create_function(move |lua, values: Table| {
+                let mut parameters = defaults.clone();
+                for pair in values.pairs::<String, Value>() {
+                    let (name, value) = pair?;
+                    parameters.insert(name, lua.from_value(value)?);
+                }
+                register_input_module(lua, parameters)
+            })?,
+        )?;
+    }
+
+    // mock.job(values): job metadata, over the test job's
+    {
+        let job = job.clone();
+        mock.set(
+            "job",
+            lua.create_function(move |lua, values: Table| {
+                let mut metadata = job.clone();
+                for pair in values.pairs::<String, Value>() {
+                    let (name, value) = pair?;
+                    metadata[name] = lua.from_value(value)?;
+                }
+                register_job_values(lua, &metadata)
+            })?,
+        )?;
+    }
+
+    // mock.process(cmd, output): queues an output of `cmd`
+    {
+        let mocks = mocks.clone();
+        mock.set(
+            "process",
+            lua.create_function(move |_, (cmd, output): (String, Table)| {
+                let output = MockOutput::from_table(&output)?;
+                lock(&mocks).outputs.entry(cmd).or_default().push(output);
+                Ok(())
+            })?,
+        )?;
+    }
+
+    // mock.build(tag, output) and mock.push(tag, output)
+    {
+        let mocks = mocks.clone();
+        mock.set(
+            "build",
+            lua.create_function(move |_, (tag, output): (String, Table)| {
+                let output = MockOutput::from_table(&output)?;
+                lock(&mocks).builds.insert(tag, output);
+                Ok(())
+            })?,
+        )?;
+    }
+    {
+        let mocks = mocks.clone();
+        mock.set(
+            "push",
+            lua.create_function(move |_, (tag, output): (String, Table)| {
+                let output = MockOutput::from_table(&output)?;
+                lock(&mocks).pushes.insert(tag, output);
+                Ok(())
+            })?,
+        )?;
+    }
+
+    // mock.calls(): every process run, as { cmd, args, container }
+    {
+        let mocks = mocks.clone();
+        mock.set(
+            "calls",
+            lua.create_function(move |lua, ()| {
+                let calls = lock(&mocks).calls.clone();
+                let table = lua.create_table()?;
+                for call in calls {
+                    let entry = lua.create_table()?;
+                    entry.set("cmd", call.cmd)?;
+                    entry.set("args", call.args)?;
+                    entry.set("container", call.container)?;
+                    table.push(entry)?;
+                }
+                Ok(table)
+            })?,
+        )?;
+    }
+
+    // mock.logs(): every log line, as { level, message, fields? }
+    {
+        let mocks = mocks.clone();
+        mock.set(
+            "logs",
+            lua.create_function(move |lua, ()| {
+                let logs = lock(&mocks).logs.clone();
+                let table = lua.create_table()?;
+                for (level, message, fields) in logs {
+                    let entry = lua.create_table()?;
+                    entry.set("level", level.to_string())?;
+                    entry.set("message", message)?;
+                    if !fields.is_empty() {
+                        entry.set("fields", lua.to_value(&fields)?)?;
+                    }
+                    table.push(entry)?;
+                }
+                Ok(table)
+            })?,
+        )?;
+    }
+
+    Ok(mock)
+}
+
+/// The `expect` table of assertions, raising an error when they fail
+fn create_expect_table(lua: &Lua, mocks: &Mocks) -> LuaResult<Table> {
+    let expect = lua.create_table()?;
+
+    // expect.eq(actual, expected, message?): compares tables by content
+    expect.set(
+        "eq",
+        lua.create_function(
+            |lua, (actual, expected, message): (Value, Value, Option<String>)| {
+                if values_equal(&actual, &expected)? {
+                    return Ok(());
+                }
+                fail(
+                    message,
+                    format!(
+                        "expected {}, got {}",
+                        describe(lua, &expected),
+                        describe(lua, &actual)
+                    ),
+                )
+            },
+        )?,
+    )?;
+
+    // expect.truthy(value, message?) and expect.falsy(value, message?)
+    expect.set(
+        "truthy",
+        lua.create_function(|lua, (value, message): (Value, Option<String>)| {
+            if truthy(&value) {
+                return Ok(());
+            }
+            fail(
+                message,
+                format!("expected a truthy value, got {}", describe(lua, &value)),
+            )
+        })?,
+    )?;
+    expect.set(
+        "falsy",
+        lua.create_function(|lua, (value, message): (Value, Option<String>)| {
+            if !truthy(&value) {
+                return Ok(());
+            }
+            fail(
+                message,
+                format!("expected a falsy value, got {}", describe(lua, &value)),
+            )
+        })?,
+    )?;
+
+    // expect.ran(cmd, args?): some run of `cmd` had exactly these arguments
+    {
+        let mocks = mocks.clone();
+        expect.set(
+            "ran",
+            lua.create_function(move |_, (cmd, args): (String, Option<Vec<String>>)| {
+                let state = lock(&mocks);
+                let runs: Vec<&ProcessCall> =
+                    state.calls.iter().filter(|call| call.cmd == cmd).collect();
+                match &args {
+                    _ if runs.is_empty() => fail(None, format!("'{}' was not run", cmd)),
+                    Some(args) if !runs.iter().any(|call| &call.args == args) => fail(
+                        None,
+                        format!(
+                            "'{}' was not run with {:?}; it was run with {}",
+                            cmd,
+                            args,
+                            runs.iter()
+                                .map(|call| format!("{:?}", call.args))
+                                .collect::<Vec<_>>()
+                                .join(", ")
+                        ),
+                    ),
+                    _ => Ok(()),
+                }
+            })?,
+        )?;
+    }
+
+    // expect.not_ran(cmd)
+    {
+        let mocks = mocks.clone();
+        expect.set(
+            "not_ran",
+            lua.create_function(move |_, cmd: String| {
+                let runs = lock(&mocks).calls.iter().filter(|c| c.cmd == cmd).count();
+                if runs == 0 {
+                    return Ok(());
+                }
+                fail(None, format!("'{}' was run {} time(s)", cmd, runs))
+            })?,
+        )?;
+    }
+
+    // expect.built(tag) and expect.pushed(tag)
+    {
+        let mocks = mocks.clone();
+        expect.set(
+            "built",
+            lua.create_function(move |_, tag: String| {
+                if lock(&mocks).built.contains(&tag) {
+                    return Ok(());
+                }
+                fail(None, format!("image {} was not built", tag))
+            })?,
+        )?;
+    }
+    {
+        let mocks = mocks.clone();
+        expect.set(
+            "pushed",
+            lua.create_function(move |_, tag: String| {
+                if lock(&mocks).pushed.contains(&tag) {
+                    return Ok(());
+                }
+                fail(None, format!("image {} was not pushed", tag))
+            })?,
+        )?;
+    }
+
+    // expect.logged(text): some log line contains `text`
+    {
+        let mocks = mocks.clone();
+        expect.set(
+            "logged",
+            lua.create_function(move |_, text: String| {
+                if lock(&mocks)
+                    .logs
+                    .iter()
+                    .any(|(_, line, _)| line.contains(&text))
+                {
+                    return Ok(());
+                }
+                fail(None, format!("no log line contains {:?}", text))
+            })?,
+        )?;
+    }
+
+    // expect.fails(fn, text?): calling `fn` raises an error containing `text`
+    expect.set(
+        "fails",
+        lua.create_function(|_, (func, text): (Function, Option<String>)| {
+            let error = match func.call::<()>(()) {
+                Ok(()) => return fail(None, "expected an error, none was raised".to_string()),
+                Err(e) => failure_message(&e),
+            };
+            match text {
+                Some(text) if !error.contains(&text) => fail(
+                    None,
+                    format!("expected an error containing {:?}, got: {}", text, error),
+                ),
+                _ => Ok(()),
+            }
+        })?,
+    )?;
+
+    Ok(expect)
+}
+
+/// Raises an assertion failure, prefixed with the test's own message
+fn fail(message: Option<String>, reason: String) -> LuaResult<()> {
+    Err(LuaError::RuntimeError(match message {
+        Some(message) => format!("{}: {}", message, reason),
+        None => reason,
+    }))
+}
+
+fn truthy(value: &Value) -> bool {
+    !matches!(value, Value::Nil | Value::Boolean(false))
+}
+
+/// Compares two values, tables by content
+fn values_equal(a: &Value, b: &Value) -> LuaResult<bool> {
+    match (a, b) {
+        (Value::Table(a), Value::Table(b)) => Ok(contains_table(a, b)? && contains_table(b, a)?),
+        _ => Ok(a == b),
+    }
+}
+
+/// Whether every entry of `a` is in `b`
+fn contains_table(a: &Table, b: &Table) -> LuaResult<bool> {
+    for pair in a.pairs::<Value, Value>() {
+        let (key, value) = pair?;
+        if !values_equal(&value, &b.get::<Value>(key)?)? {
+            return Ok(false);
+        }
+    }
+    Ok(true)
+}
+
+/// Readable form of a value for failure messages
+fn describe(lua: &Lua, value: &Value) -> String {
+    match value {
+        Value::String(s) => format!("{:?}", s.to_string_lossy()),
+        Value::Table(_) => lua
+            .from_value::<serde_json::Value>(value.clone())
+            .map(|json| json.to_string())
+            .unwrap_or_else(|_| "a table".to_string()),
+        other => other
+            .to_string()
+            .unwrap_or_else(|_| other.type_name().to_string()),
+    }
+}
+
+/// Message of an error raised in a test, without Lua's traceback
+fn failure_message(error: &LuaError) -> String {
+    match error {
+        LuaError::CallbackError { cause, .. } => failure_message(cause),
+        LuaError::RuntimeError(message) => message.clone(),
+        other => other.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PIPELINE: &str = r#"
+        return pipeline.define({
+            name = "build",
+            inputs = { profile = { type = "string", default = "debug" } },
+            stages = {
+                {
+                    name = "compile",
+                    container = "rust:1",
+                    script = function()
+                        local args = { "build" }
+                        if input.get("profile") == "release" then
+                            table.insert(args, "--release")
+                        end
+                        local result = process.run({ cmd = "cargo", args = args })
+                        if result.exit_code ~= 0 then
+                            error("cargo failed")
+                        end
+                    end
+                },
+                {
+                    name = "publish",
+                    condition = function() return input.get("profile") == "release" end,
+                    script = function()
+                        container.push({ tag = container.build({ tag = "app:latest" }) })
+                    end
+                }
+            }
+        })
+    "#;
+
+    #[test]
+    fn test_passing_tests() {
+        let tests = r#"
+            test("debug by default", function()
+                mock.process("cargo", { stdout = "Finished" })
+                run_stage("compile")
+                expect.ran("cargo", { "build" })
+                expect.logged("Finished")
+                expect.eq(mock.calls()[1].container, "rust:1")
+                expect.falsy(stage_runs("publish"))
+            end)
+
+            test("release publishes", function()
+                mock.inputs({ profile = "release" })
+                run_stage("compile")
+                run_stage("publish")
+                expect.ran("cargo", { "build", "--release" })
+                expect.built("app:latest")
+                expect.pushed("app:latest")
+            end)
+
+            test("failures are raised", function()
+                mock.process("cargo", { exit_code = 101 })
+                expect.fails(function() run_stage("compile") end, "cargo failed")
+                mock.build("app:latest", { exit_code = 1 })
+                expect.fails(function() run_stage("publish") end, "exit code 1")
+            end)
+        "#;
+
+        let outcomes = run_pipeline_tests(PIPELINE, tests).unwrap();
+        let names: Vec<&str> = outcomes.iter().map(|o| o.name.as_str()).collect();
+        assert_eq!(
+            names,
+            [
+                "debug by default",
+                "release publishes",
+                "failures are raised"
+            ]
+        );
+        for outcome in &outcomes {
+            assert!(outcome.passed(), "{}: {:?}", outcome.name, outcome.failure);
+        }
+    }
+
+    #[test]
+    fn test_failing_tests() {
+        let tests = r#"
+            test("wrong arguments", function()
+                run_stage("compile")
+                expect.ran("cargo", { "test" })
+            end)
+
+            test("mocks are reset", function()
+                expect.not_ran("cargo")
+                expect.eq(input.get("profile"), "release", "profile")
+            end)
+        "#;
+
+        let outcomes = run_pipeline_tests(PIPELINE, tests).unwrap();
+        assert_eq!(
+            outcomes[0].failure.as_deref(),
+            Some(r#"'cargo' was not run with ["test"]; it was run with ["build"]"#)
+        );
+        assert_eq!(
+            outcomes[1].failure.as_deref(),
+            Some(r#"profile: expected "release", got "debug""#)
+        );
+    }
+}
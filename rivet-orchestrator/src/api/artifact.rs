@@ -0,0 +1,160 @@
+//! Artifact API Handlers
+//!
+//! HTTP endpoints for uploading, listing and downloading job artifacts.
+
+use axum::{
+    Json,
+    body::Bytes,
+    extract::{Path, State},
+    http::{StatusCode, header},
+    response::IntoResponse,
+};
+use rivet_core::domain::artifact::Artifact;
+use rivet_core::dto::artifact::{ArtifactGcReport, ArtifactUsage};
+use serde::Deserialize;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::api::auth::{AdminAuth, RunnerIdentity};
+use crate::api::error::{ApiError, ApiResult};
+use crate::api::project::{IdPath, ProjectScope};
+use crate::service::artifact_service;
+
+/// Header carrying the hex encoded SHA-256 of a downloaded artifact
+pub const CHECKSUM_HEADER: &str = "x-checksum-sha256";
+
+/// `{id}/artifacts/{name}` path parameters
+#[derive(Debug, Deserialize)]
+pub struct ArtifactPath {
+    pub id: Uuid,
+    pub name: String,
+}
+
+/// POST /jobs/{id}/artifacts/{name}
+/// Upload an artifact (raw request body)
+pub async fn upload_artifact(
+    State(pool): State<PgPool>,
+    identity: RunnerIdentity,
+    Path((id, name)): Path<(Uuid, String)>,
+    body: Bytes,
+) -> ApiResult<(StatusCode, Json<Artifact>)> {
+    tracing::info!("Uploading artifact '{}' for job: {}", name, id);
+
+    identity.ensure_job(&pool, id).await?;
+
+    let artifact = artifact_service::upload_artifact(&pool, id, &name, &body)
+        .await
+        .map_err(map_artifact_error)?;
+
+    Ok((StatusCode::CREATED, Json(artifact)))
+}
+
+/// GET /jobs/{id}/artifacts
+/// List artifacts of a job
+pub async fn list_artifacts(
+    State(pool): State<PgPool>,
+    scope: ProjectScope,
+    Path(IdPath { id }): Path<IdPath>,
+) -> ApiResult<Json<Vec<Artifact>>> {
+    tracing::debug!("Listing artifacts for job: {}", id);
+
+    scope.ensure_job(&pool, id).await?;
+
+    let artifacts = artifact_service::list_artifacts(&pool, id)
+        .await
+        .map_err(map_artifact_error)?;
+
+    Ok(Json(artifacts))
+}
+
+/// GET /jobs/{id}/artifacts/{name}
+/// Download an artifact
+pub async fn download_artifact(
+    State(pool): State<PgPool>,
+    scope: ProjectScope,
+    Path(ArtifactPath { id, name }): Path<ArtifactPath>,
+) -> ApiResult<impl IntoResponse> {
+    tracing::debug!("Downloading artifact '{}' for job: {}", name, id);
+
+    scope.ensure_job(&pool, id).await?;
+
+    let (artifact, content) = artifact_service::get_artifact(&pool, id, &name)
+        .await
+        .map_err(map_artifact_error)?;
+
+    Ok((
+        [
+            (header::CONTENT_TYPE, "application/octet-stream".to_string()),
+            (
+                header::CONTENT_DISPOSITION,
+                format!(
+                    "attachment; filename=\"{}\"",
+                    artifact.name.replace('"', "")
+                ),
+            ),
+            (
+                header::HeaderName::from_static(CHECKSUM_HEADER),
+                artifact.sha256,
+            ),
+        ],
+        content,
+    ))
+}
+
+/// GET /artifacts/usage
+/// Count the stored artifacts and their bytes, per pipeline (admin)
+pub async fn artifact_usage(
+    State(pool): State<PgPool>,
+    _admin: AdminAuth,
+) -> ApiResult<Json<ArtifactUsage>> {
+    tracing::debug!("Getting artifact usage");
+
+    let usage = artifact_service::get_usage(&pool)
+        .await
+        .map_err(map_artifact_error)?;
+
+    Ok(Json(usage))
+}
+
+/// GET /artifacts/gc
+/// Report what garbage collection would delete, without deleting it (admin)
+pub async fn plan_artifact_gc(
+    State(pool): State<PgPool>,
+    _admin: AdminAuth,
+) -> ApiResult<Json<ArtifactGcReport>> {
+    tracing::debug!("Planning artifact garbage collection");
+
+    let report = artifact_service::collect_garbage(&pool, true)
+        .await
+        .map_err(map_artifact_error)?;
+
+    Ok(Json(report))
+}
+
+/// POST /artifacts/gc
+/// Delete the artifacts retention limits expire now (admin)
+pub async fn run_artifact_gc(
+    State(pool): State<PgPool>,
+    _admin: AdminAuth,
+) -> ApiResult<Json<ArtifactGcReport>> {
+    tracing::info!("Running artifact garbage collection");
+
+    let report = artifact_service::collect_garbage(&pool, false)
+        .await
+        .map_err(map_artifact_error)?;
+
+    Ok(Json(report))
+}
+
+fn map_artifact_error(e: artifact_service::ArtifactError) -> ApiError {
+    match e {
+        artifact_service::ArtifactError::NotFound(name) => {
+            ApiError::NotFound(format!("Artifact '{}' not found", name))
+        }
+        artifact_service::ArtifactError::JobNotFound(id) => {
+            ApiError::NotFound(format!("Job {} not found", id))
+        }
+        artifact_service::ArtifactError::ValidationError(msg) => ApiError::BadRequest(msg),
+        artifact_service::ArtifactError::DatabaseError(err) => ApiError::DatabaseError(err),
+    }
+}
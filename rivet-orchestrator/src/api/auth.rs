@@ -0,0 +1,190 @@
+//! Authentication
+//!
+//! Extractors guarding admin and runner-facing endpoints:
+//! - `AdminAuth` requires `Authorization: Bearer <admin token>` when an admin
+//!   token is configured.
+//! - `RunnerIdentity` resolves the runner credential sent in
+//!   `X-Runner-Credential`, and is mandatory when runner authentication is
+//!   enforced.
+
+use axum::{
+    extract::{FromRef, FromRequestParts},
+    http::{header, request::Parts},
+};
+use rivet_core::dto::runner::RUNNER_CREDENTIAL_HEADER;
+use sha2::{Digest, Sha256};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::api::error::{ApiError, ApiResult};
+use crate::service::{job_service, runner_service};
+
+/// Authentication settings of the orchestrator
+#[derive(Debug, Clone, Default)]
+pub struct AuthConfig {
+    /// Token required to manage registration tokens; management is open when unset
+    pub admin_token: Option<String>,
+    /// Whether runners must register with a registration token and send
+    /// their credential on job calls
+    pub require_runner_auth: bool,
+}
+
+impl AuthConfig {
+    /// Whether `presented` matches the configured admin token
+    ///
+    /// Always true when no admin token is configured.
+    pub fn is_admin(&self, presented: Option<&str>) -> bool {
+        let Some(expected) = &self.admin_token else {
+            return true;
+        };
+
+        // Compare digests so the comparison time does not depend on the token
+        presented.is_some_and(|token| {
+            Sha256::digest(token.as_bytes()) == Sha256::digest(expected.as_bytes())
+        })
+    }
+}
+
+// =============================================================================
+// Admin
+// =============================================================================
+
+/// Proof that the request carries the admin token
+#[derive(Debug, Clone, Copy)]
+pub struct AdminAuth;
+
+impl<S> FromRequestParts<S> for AdminAuth
+where
+    AuthConfig: FromRef<S>,
+    S: Send + Sync,
+{
+    type Rejection = ApiError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let presented = parts
+            .headers
+            .get(header::AUTHORIZATION)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.strip_prefix("Bearer "));
+
+        if !AuthConfig::from_ref(state).is_admin(presented) {
+            return Err(ApiError::Unauthorized("Admin token required".to_string()));
+        }
+
+        Ok(AdminAuth)
+    }
+}
+
+// =============================================================================
+// Runners
+// =============================================================================
+
+/// Runner that sent the request, identified by its credential
+///
+/// `None` when the request carries no credential and runner authentication
+/// is not enforced.
+#[derive(Debug, Clone)]
+pub struct RunnerIdentity(pub Option<String>);
+
+impl<S> FromRequestParts<S> for RunnerIdentity
+where
+    AuthConfig: FromRef<S>,
+    PgPool: FromRef<S>,
+    S: Send + Sync,
+{
+    type Rejection = ApiError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let credential = parts
+            .headers
+            .get(RUNNER_CREDENTIAL_HEADER)
+            .and_then(|v| v.to_str().ok());
+
+        let pool = PgPool::from_ref(state);
+        let auth = AuthConfig::from_ref(state);
+        resolve_runner(&pool, &auth, credential)
+            .await
+            .map(RunnerIdentity)
+    }
+}
+
+impl RunnerIdentity {
+    /// Fails unless the caller may act as `runner_id`
+    pub fn ensure_runner(&self, runner_id: &str) -> ApiResult<()> {
+        match &self.0 {
+            Some(id) if id != runner_id => Err(ApiError::Unauthorized(format!(
+                "Credential does not belong to runner {}",
+                runner_id
+            ))),
+            _ => Ok(()),
+        }
+    }
+
+    /// Fails unless the job is assigned to the calling runner
+    pub async fn ensure_job(&self, pool: &PgPool, job_id: Uuid) -> ApiResult<()> {
+        let Some(runner_id) = &self.0 else {
+            return Ok(());
+        };
+
+        let job = job_service::get_job(pool, job_id)
+            .await
+            .map_err(|e| match e {
+                job_service::JobError::DatabaseError(err) => ApiError::DatabaseError(err),
+                _ => ApiError::NotFound(format!("Job {} not found", job_id)),
+            })?;
+
+        if job.runner_id.as_deref() != Some(runner_id.as_str()) {
+            return Err(ApiError::Unauthorized(format!(
+                "Job {} is not assigned to runner {}",
+                job_id, runner_id
+            )));
+        }
+
+        Ok(())
+    }
+}
+
+/// Resolves the runner behind an optional credential
+///
+/// Shared with the gRPC service, which reads the credential from metadata.
+pub async fn resolve_runner(
+    pool: &PgPool,
+    auth: &AuthConfig,
+    credential: Option<&str>,
+) -> ApiResult<Option<String>> {
+    let Some(credential) = credential else {
+        if auth.require_runner_auth {
+            return Err(ApiError::Unauthorized(
+                "Runner credential required".to_string(),
+            ));
+        }
+        return Ok(None);
+    };
+
+    runner_service::authenticate_runner(pool, credential)
+        .await
+        .map(Some)
+        .map_err(|e| match e {
+            runner_service::RunnerError::DatabaseError(err) => ApiError::DatabaseError(err),
+            _ => ApiError::Unauthorized("Invalid runner credential".to_string()),
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_admin_token_check() {
+        let open = AuthConfig::default();
+        assert!(open.is_admin(None));
+
+        let secured = AuthConfig {
+            admin_token: Some("s3cret".to_string()),
+            require_runner_auth: false,
+        };
+        assert!(secured.is_admin(Some("s3cret")));
+        assert!(!secured.is_admin(Some("guess")));
+        assert!(!secured.is_admin(None));
+    }
+}
@@ -0,0 +1,148 @@
+//! Badge API Handlers
+//!
+//! SVG status badges for embedding pipeline status in READMEs.
+
+use axum::{
+    extract::{Path, Query, State},
+    http::header,
+    response::IntoResponse,
+};
+use rivet_core::domain::job::JobStatus;
+use serde::Deserialize;
+use sqlx::PgPool;
+
+use crate::api::error::{ApiError, ApiResult};
+use crate::api::project::{IdPath, ProjectScope};
+use crate::service::{job_service, pipeline_service};
+
+/// Query parameters for the badge endpoint
+#[derive(Debug, Deserialize)]
+pub struct BadgeQuery {
+    /// Only consider jobs launched with this `branch` parameter
+    pub branch: Option<String>,
+    /// Text for the left side of the badge (defaults to the pipeline name)
+    pub label: Option<String>,
+}
+
+/// GET /pipeline/{id}/badge.svg
+/// Render the status of the latest job as an SVG badge
+pub async fn pipeline_badge(
+    State(pool): State<PgPool>,
+    scope: ProjectScope,
+    Path(IdPath { id }): Path<IdPath>,
+    Query(query): Query<BadgeQuery>,
+) -> ApiResult<impl IntoResponse> {
+    tracing::debug!("Rendering badge for pipeline: {}", id);
+
+    scope.ensure_pipeline(&pool, id).await?;
+
+    let pipeline = pipeline_service::get_pipeline(&pool, id)
+        .await
+        .map_err(|e| match e {
+            pipeline_service::PipelineError::NotFound(id) => {
+                ApiError::NotFound(format!("Pipeline {} not found", id))
+            }
+            pipeline_service::PipelineError::DatabaseError(err) => ApiError::DatabaseError(err),
+            pipeline_service::PipelineError::ValidationError(msg) => ApiError::BadRequest(msg),
+            pipeline_service::PipelineError::InvalidFields(fields) => {
+                ApiError::InvalidFields(fields)
+            }
+        })?;
+
+    let job = job_service::get_latest_job_for_pipeline(&pool, id, query.branch.as_deref())
+        .await
+        .map_err(|e| match e {
+            job_service::JobError::PipelineNotFound(id) => {
+                ApiError::NotFound(format!("Pipeline {} not found", id))
+            }
+            job_service::JobError::DatabaseError(err) => ApiError::DatabaseError(err),
+            job_service::JobError::NotFound(id) => {
+                ApiError::NotFound(format!("Job {} not found", id))
+            }
+            job_service::JobError::ValidationError(msg) => ApiError::BadRequest(msg),
+            job_service::JobError::InvalidFields(fields) => ApiError::InvalidFields(fields),
+            job_service::JobError::InvalidState(msg) => ApiError::BadRequest(msg),
+            job_service::JobError::StaleClaim(id) => {
+                ApiError::Conflict(format!("Claim on job {} is no longer current", id))
+            }
+        })?;
+
+    let label = query.label.unwrap_or(pipeline.name);
+    let (status, color) = match job.as_ref().map(|j| j.status) {
+        Some(JobStatus::Succeeded) => ("passing", "#4c1"),
+        Some(JobStatus::Failed) | Some(JobStatus::TimedOut) | Some(JobStatus::DeadLettered) => {
+            ("failing", "#e05d44")
+        }
+        Some(JobStatus::Running) => ("running", "#007ec6"),
+        Some(JobStatus::Queued) | Some(JobStatus::Blocked) => ("queued", "#dfb317"),
+        Some(JobStatus::Cancelled) => ("cancelled", "#9f9f9f"),
+        None => ("no runs", "#9f9f9f"),
+    };
+    // Name the job by number, e.g. "passing #42"
+    let message = match &job {
+        Some(job) => format!("{} #{}", status, job.number),
+        None => status.to_string(),
+    };
+
+    Ok((
+        [
+            (header::CONTENT_TYPE, "image/svg+xml"),
+            // Badges must reflect the latest run, keep image proxies from caching them
+            (header::CACHE_CONTROL, "no-cache, no-store, must-revalidate"),
+        ],
+        render_badge(&label, &message, color),
+    ))
+}
+
+// =============================================================================
+// Rendering
+// =============================================================================
+
+/// Renders a flat two-part badge
+fn render_badge(label: &str, message: &str, color: &str) -> String {
+    let label_width = text_width(label);
+    let message_width = text_width(message);
+    let total_width = label_width + message_width;
+
+    let label_x = label_width / 2;
+    let message_x = label_width + message_width / 2;
+
+    let label = escape_xml(label);
+    let message = escape_xml(message);
+
+    format!(
+        r##"<svg xmlns="http://www.w3.org/2000/svg" width="{total_width}" height="20" role="img" aria-label="{label}: {message}">
+  <title>{label}: {message}</title>
+  <linearGradient id="s" x2="0" y2="100%">
+    <stop offset="0" stop-color="#bbb" stop-opacity=".1"/>
+    <stop offset="1" stop-opacity=".1"/>
+  </linearGradient>
+  <clipPath id="r"><rect width="{total_width}" height="20" rx="3" fill="#fff"/></clipPath>
+  <g clip-path="url(#r)">
+    <rect width="{label_width}" height="20" fill="#555"/>
+    <rect x="{label_width}" width="{message_width}" height="20" fill="{color}"/>
+    <rect width="{total_width}" height="20" fill="url(#s)"/>
+  </g>
+  <g fill="#fff" text-anchor="middle" font-family="Verdana,Geneva,DejaVu Sans,sans-serif" font-size="11">
+    <text x="{label_x}" y="15" fill="#010101" fill-opacity=".3">{label}</text>
+    <text x="{label_x}" y="14">{label}</text>
+    <text x="{message_x}" y="15" fill="#010101" fill-opacity=".3">{message}</text>
+    <text x="{message_x}" y="14">{message}</text>
+  </g>
+</svg>
+"##
+    )
+}
+
+/// Approximate rendered width of a badge segment (Verdana 11px plus padding)
+fn text_width(text: &str) -> usize {
+    text.chars().count() * 7 + 10
+}
+
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
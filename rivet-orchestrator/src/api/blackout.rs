@@ -0,0 +1,81 @@
+//! Blackout API Handlers
+//!
+//! HTTP endpoints for the blackout windows deferring the jobs of pipelines
+//! and of environments. Unscoped routes manage the windows of the default
+//! project.
+
+use axum::{
+    Json,
+    extract::{Path, State},
+    http::StatusCode,
+};
+use rivet_core::domain::blackout::BlackoutWindow;
+use rivet_core::dto::blackout::CreateBlackoutWindow;
+use sqlx::PgPool;
+
+use crate::api::auth::AdminAuth;
+use crate::api::error::{ApiError, ApiResult};
+use crate::api::project::{IdPath, ProjectScope};
+use crate::service::blackout_service;
+
+/// GET /blackouts
+/// List the blackout windows that have not ended
+pub async fn list_blackouts(
+    State(pool): State<PgPool>,
+    scope: ProjectScope,
+) -> ApiResult<Json<Vec<BlackoutWindow>>> {
+    tracing::debug!("Listing blackout windows");
+
+    let windows = blackout_service::list_windows(&pool, scope.id_or_default())
+        .await
+        .map_err(map_blackout_error)?;
+
+    Ok(Json(windows))
+}
+
+/// POST /blackouts
+/// Add a blackout window for a pipeline or an environment (admin)
+pub async fn create_blackout(
+    State(pool): State<PgPool>,
+    _admin: AdminAuth,
+    scope: ProjectScope,
+    Json(req): Json<CreateBlackoutWindow>,
+) -> ApiResult<(StatusCode, Json<BlackoutWindow>)> {
+    tracing::info!("Adding blackout window until {}", req.ends_at);
+
+    let window = blackout_service::create_window(&pool, scope.id_or_default(), req)
+        .await
+        .map_err(map_blackout_error)?;
+
+    Ok((StatusCode::CREATED, Json(window)))
+}
+
+/// DELETE /blackouts/{id}
+/// Remove a blackout window (admin)
+pub async fn delete_blackout(
+    State(pool): State<PgPool>,
+    _admin: AdminAuth,
+    scope: ProjectScope,
+    Path(IdPath { id }): Path<IdPath>,
+) -> ApiResult<StatusCode> {
+    tracing::info!("Removing blackout window: {}", id);
+
+    blackout_service::delete_window(&pool, scope.id_or_default(), id)
+        .await
+        .map_err(map_blackout_error)?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+fn map_blackout_error(e: blackout_service::BlackoutError) -> ApiError {
+    match e {
+        blackout_service::BlackoutError::NotFound(id) => {
+            ApiError::NotFound(format!("Blackout window {} not found", id))
+        }
+        blackout_service::BlackoutError::PipelineNotFound(id) => {
+            ApiError::NotFound(format!("Pipeline {} not found", id))
+        }
+        blackout_service::BlackoutError::InvalidFields(fields) => ApiError::InvalidFields(fields),
+        blackout_service::BlackoutError::DatabaseError(err) => ApiError::DatabaseError(err),
+    }
+}
@@ -0,0 +1,143 @@
+//! Cross-Origin Resource Sharing
+//!
+//! Lets browser dashboards served from other origins call the API. CORS is
+//! disabled unless allowed origins are configured, in which case browsers
+//! only see the responses of requests from those origins. Credentials are
+//! sent as headers (`Authorization`), never cookies, so responses do not
+//! allow credentials.
+
+use std::str::FromStr;
+use std::time::Duration;
+
+use axum::http::{HeaderName, HeaderValue, Method, header};
+use tower_http::cors::{AllowOrigin, CorsLayer};
+
+use rivet_core::dto::version::API_VERSION_HEADER;
+
+use crate::api::request_context::REQUEST_ID_HEADER;
+
+/// How long browsers may cache the answer to a preflight request
+const PREFLIGHT_MAX_AGE: Duration = Duration::from_secs(3600);
+
+/// Origins allowed to call the API from a browser
+#[derive(Debug, Clone, PartialEq)]
+pub enum CorsOrigins {
+    /// Any origin (`*`)
+    Any,
+    /// Only these origins, e.g. `https://ci.example.com`
+    List(Vec<HeaderValue>),
+}
+
+impl FromStr for CorsOrigins {
+    type Err = String;
+
+    /// Parses `*` or a comma-separated list of origins
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.trim() == "*" {
+            return Ok(CorsOrigins::Any);
+        }
+
+        let origins = s
+            .split(',')
+            .map(str::trim)
+            .filter(|origin| !origin.is_empty())
+            .map(|origin| {
+                // Browsers send the origin without a trailing slash
+                let origin = origin.trim_end_matches('/');
+                if !origin.starts_with("http://") && !origin.starts_with("https://") {
+                    return Err(format!(
+                        "Invalid origin '{}' (expected e.g. https://ci.example.com)",
+                        origin
+                    ));
+                }
+                HeaderValue::from_str(origin).map_err(|_| format!("Invalid origin '{}'", origin))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        if origins.is_empty() {
+            return Err("No origin given".to_string());
+        }
+        Ok(CorsOrigins::List(origins))
+    }
+}
+
+/// CORS settings; CORS is disabled without allowed origins
+#[derive(Debug, Clone, Default)]
+pub struct CorsConfig {
+    /// Origins allowed to call the API (default: none, CORS disabled)
+    pub allowed_origins: Option<CorsOrigins>,
+    /// Methods allowed in cross-origin requests
+    /// (default: `GET`, `POST`, `PUT`, `DELETE`)
+    pub allowed_methods: Vec<Method>,
+    /// Request headers allowed in cross-origin requests
+    /// (default: `Authorization`, `Content-Type`, `X-Request-Id`,
+    /// `X-Rivet-Api-Version`)
+    pub allowed_headers: Vec<HeaderName>,
+}
+
+impl CorsConfig {
+    /// Whether cross-origin requests are allowed at all
+    pub fn is_enabled(&self) -> bool {
+        self.allowed_origins.is_some()
+    }
+
+    /// Builds the layer answering preflight requests and tagging responses
+    ///
+    /// # Returns
+    /// `None` when CORS is disabled
+    pub fn layer(&self) -> Option<CorsLayer> {
+        let allow_origin = match self.allowed_origins.as_ref()? {
+            CorsOrigins::Any => AllowOrigin::any(),
+            CorsOrigins::List(origins) => AllowOrigin::list(origins.iter().cloned()),
+        };
+
+        let methods = if self.allowed_methods.is_empty() {
+            vec![Method::GET, Method::POST, Method::PUT, Method::DELETE]
+        } else {
+            self.allowed_methods.clone()
+        };
+        let headers = if self.allowed_headers.is_empty() {
+            vec![
+                header::AUTHORIZATION,
+                header::CONTENT_TYPE,
+                REQUEST_ID_HEADER,
+                HeaderName::from_static(API_VERSION_HEADER),
+            ]
+        } else {
+            self.allowed_headers.clone()
+        };
+
+        Some(
+            CorsLayer::new()
+                .allow_origin(allow_origin)
+                .allow_methods(methods)
+                .allow_headers(headers)
+                .expose_headers([
+                    REQUEST_ID_HEADER,
+                    header::RETRY_AFTER,
+                    HeaderName::from_static(API_VERSION_HEADER),
+                ])
+                .max_age(PREFLIGHT_MAX_AGE),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_origins() {
+        assert_eq!("*".parse::<CorsOrigins>(), Ok(CorsOrigins::Any));
+        assert_eq!(
+            "https://ci.example.com/, http://localhost:3000".parse::<CorsOrigins>(),
+            Ok(CorsOrigins::List(vec![
+                HeaderValue::from_static("https://ci.example.com"),
+                HeaderValue::from_static("http://localhost:3000"),
+            ]))
+        );
+
+        assert!("ci.example.com".parse::<CorsOrigins>().is_err());
+        assert!(" , ".parse::<CorsOrigins>().is_err());
+    }
+}
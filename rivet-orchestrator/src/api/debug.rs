@@ -0,0 +1,213 @@
+//! Debug API Handlers
+//!
+//! Interactive shells in the environment of failed jobs. The user's and the
+//! runner's WebSockets are paired by the `DebugRelay` and relayed frame by
+//! frame: binary frames carry terminal bytes, text frames notices.
+
+use axum::{
+    extract::{
+        Path, Query, State,
+        ws::{Message, WebSocket, WebSocketUpgrade},
+    },
+    http::StatusCode,
+    response::{IntoResponse, Response},
+};
+use rivet_core::domain::job::JobStatus;
+use rivet_core::dto::debug::{DebugQuery, DebugRequest};
+use rivet_core::dto::job::NextJobQuery;
+use sqlx::PgPool;
+use tokio::sync::oneshot;
+use uuid::Uuid;
+
+use crate::api::auth::{AdminAuth, RunnerIdentity};
+use crate::api::error::{ApiError, ApiResult};
+use crate::api::project::{IdPath, ProjectScope};
+use crate::debug::{DEBUG_ATTACH_TIMEOUT, DebugRelay};
+use crate::service::job_service;
+
+// =============================================================================
+// User Endpoints
+// =============================================================================
+
+/// GET /jobs/{id}/debug?image={image}
+/// Upgrade to a WebSocket relaying a shell in the restored container of a
+/// failed job, served by the runner that ran it
+pub async fn debug_job(
+    State(pool): State<PgPool>,
+    State(relay): State<DebugRelay>,
+    _admin: AdminAuth,
+    scope: ProjectScope,
+    Path(IdPath { id }): Path<IdPath>,
+    Query(query): Query<DebugQuery>,
+    ws: WebSocketUpgrade,
+) -> ApiResult<Response> {
+    scope.ensure_job(&pool, id).await?;
+
+    let job = job_service::get_job(&pool, id).await.map_err(|e| match e {
+        job_service::JobError::NotFound(id) => ApiError::NotFound(format!("Job {} not found", id)),
+        job_service::JobError::DatabaseError(err) => ApiError::DatabaseError(err),
+        job_service::JobError::PipelineNotFound(id) => {
+            ApiError::NotFound(format!("Pipeline {} not found", id))
+        }
+        job_service::JobError::ValidationError(msg) => ApiError::BadRequest(msg),
+        job_service::JobError::InvalidFields(fields) => ApiError::InvalidFields(fields),
+        job_service::JobError::InvalidState(msg) => ApiError::BadRequest(msg),
+        job_service::JobError::StaleClaim(id) => {
+            ApiError::Conflict(format!("Claim on job {} is no longer current", id))
+        }
+    })?;
+
+    if !matches!(job.status, JobStatus::Failed | JobStatus::DeadLettered) {
+        return Err(ApiError::BadRequest(format!(
+            "Job {} has not failed (status: {:?})",
+            id, job.status
+        )));
+    }
+    let Some(runner_id) = job.runner_id else {
+        return Err(ApiError::BadRequest(format!(
+            "Job {} never ran on a runner",
+            id
+        )));
+    };
+
+    let request = DebugRequest {
+        session_id: Uuid::new_v4(),
+        job_id: id,
+        image: query.image,
+    };
+    tracing::info!(
+        "Opening debug session {} for job {} on runner {}",
+        request.session_id,
+        id,
+        runner_id
+    );
+
+    let session_id = request.session_id;
+    let attached = relay.open(&runner_id, request);
+    Ok(ws.on_upgrade(move |socket| serve_user(socket, relay, session_id, runner_id, attached)))
+}
+
+/// Waits for the runner to attach, then relays the session
+async fn serve_user(
+    mut user: WebSocket,
+    relay: DebugRelay,
+    session_id: Uuid,
+    runner_id: String,
+    attached: oneshot::Receiver<WebSocket>,
+) {
+    let notice = format!("Waiting for runner {}...", runner_id);
+    let _ = user.send(Message::Text(notice.into())).await;
+
+    let runner = tokio::select! {
+        runner = tokio::time::timeout(DEBUG_ATTACH_TIMEOUT, attached) => runner,
+        // The user left before the runner attached
+        _ = wait_for_close(&mut user) => {
+            relay.close(session_id);
+            return;
+        }
+    };
+
+    match runner {
+        Ok(Ok(runner)) => {
+            relay_session(user, runner).await;
+            tracing::info!("Debug session {} ended", session_id);
+        }
+        _ => {
+            relay.close(session_id);
+            let notice = format!(
+                "Runner {} did not answer within {:?}",
+                runner_id, DEBUG_ATTACH_TIMEOUT
+            );
+            let _ = user.send(Message::Text(notice.into())).await;
+            let _ = user.send(Message::Close(None)).await;
+        }
+    }
+}
+
+/// Resolves once the peer closes the socket, ignoring anything it sends
+async fn wait_for_close(socket: &mut WebSocket) {
+    while let Some(Ok(message)) = socket.recv().await {
+        if let Message::Close(_) = message {
+            break;
+        }
+    }
+}
+
+/// Relays frames between the user and the runner until either side leaves
+async fn relay_session(mut user: WebSocket, mut runner: WebSocket) {
+    loop {
+        tokio::select! {
+            incoming = user.recv() => match incoming {
+                // Users only send terminal input
+                Some(Ok(message @ Message::Binary(_))) => {
+                    if runner.send(message).await.is_err() {
+                        break;
+                    }
+                }
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                Some(Ok(_)) => {}
+            },
+            incoming = runner.recv() => match incoming {
+                Some(Ok(message @ (Message::Binary(_) | Message::Text(_)))) => {
+                    if user.send(message).await.is_err() {
+                        break;
+                    }
+                }
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                Some(Ok(_)) => {}
+            },
+        }
+    }
+
+    let _ = user.send(Message::Close(None)).await;
+    let _ = runner.send(Message::Close(None)).await;
+}
+
+// =============================================================================
+// Runner Endpoints
+// =============================================================================
+
+/// GET /debug/next?runner_id={id}&wait=30s
+/// Long-poll for a debug session: blocks until a user asks the runner for
+/// one (200 with `DebugRequest`) or `wait` elapses (204 No Content)
+pub async fn next_debug_request(
+    State(relay): State<DebugRelay>,
+    identity: RunnerIdentity,
+    Query(query): Query<NextJobQuery>,
+) -> ApiResult<Response> {
+    identity.ensure_runner(&query.runner_id)?;
+
+    let wait = query.wait_duration().ok_or_else(|| {
+        ApiError::BadRequest(format!(
+            "Invalid wait '{}' (expected e.g. '30s' or '500ms')",
+            query.wait.as_deref().unwrap_or_default()
+        ))
+    })?;
+
+    match relay.next_request(&query.runner_id, wait).await {
+        Some(request) => Ok(axum::Json(request).into_response()),
+        None => Ok(StatusCode::NO_CONTENT.into_response()),
+    }
+}
+
+/// GET /debug/{id}/ws
+/// Upgrade to the runner side of a pending debug session
+pub async fn attach_debug_session(
+    State(relay): State<DebugRelay>,
+    identity: RunnerIdentity,
+    Path(IdPath { id }): Path<IdPath>,
+    ws: WebSocketUpgrade,
+) -> ApiResult<Response> {
+    let runner_id = relay
+        .session_runner(id)
+        .ok_or_else(|| ApiError::NotFound(format!("Debug session {} not found", id)))?;
+    identity.ensure_runner(&runner_id)?;
+
+    Ok(ws.on_upgrade(move |socket| async move {
+        if let Some(mut socket) = relay.attach(id, socket) {
+            let notice = "The user left the debug session";
+            let _ = socket.send(Message::Text(notice.into())).await;
+            let _ = socket.send(Message::Close(None)).await;
+        }
+    }))
+}
@@ -0,0 +1,212 @@
+//! Environment API Handlers
+//!
+//! HTTP endpoints for environments, their deployment history and locks, and
+//! the approvals of jobs deploying to them. Unscoped routes manage the
+//! environments of the default project.
+
+use axum::{
+    Json,
+    extract::{Path, Query, State},
+    http::StatusCode,
+};
+use rivet_core::domain::environment::Deployment;
+use rivet_core::dto::environment::{
+    ApproveJob, DeploymentQuery, EnvironmentLockStatus, EnvironmentSummary, JobApprovals,
+    SetEnvironment,
+};
+use rivet_core::dto::event::Event;
+use serde::Deserialize;
+use sqlx::PgPool;
+
+use crate::api::auth::AdminAuth;
+use crate::api::error::{ApiError, ApiResult};
+use crate::api::project::{IdPath, ProjectScope};
+use crate::events::EventBus;
+use crate::service::environment_service;
+
+/// `{name}` path parameter
+#[derive(Debug, Deserialize)]
+pub struct EnvironmentPath {
+    pub name: String,
+}
+
+/// GET /environments
+/// List environments with their current and latest deployments
+pub async fn list_environments(
+    State(pool): State<PgPool>,
+    scope: ProjectScope,
+) -> ApiResult<Json<Vec<EnvironmentSummary>>> {
+    tracing::debug!("Listing environments");
+
+    let environments = environment_service::list_environments(&pool, scope.id_or_default())
+        .await
+        .map_err(map_environment_error)?;
+
+    Ok(Json(environments))
+}
+
+/// GET /environments/{name}
+/// Get an environment with its current and latest deployments
+pub async fn get_environment(
+    State(pool): State<PgPool>,
+    scope: ProjectScope,
+    Path(EnvironmentPath { name }): Path<EnvironmentPath>,
+) -> ApiResult<Json<EnvironmentSummary>> {
+    tracing::debug!("Getting environment: {}", name);
+
+    let environment = environment_service::get_environment(&pool, scope.id_or_default(), &name)
+        .await
+        .map_err(map_environment_error)?;
+
+    Ok(Json(environment))
+}
+
+/// PUT /environments/{name}
+/// Create or change an environment (admin)
+pub async fn set_environment(
+    State(pool): State<PgPool>,
+    _admin: AdminAuth,
+    scope: ProjectScope,
+    Path(EnvironmentPath { name }): Path<EnvironmentPath>,
+    Json(req): Json<SetEnvironment>,
+) -> ApiResult<Json<EnvironmentSummary>> {
+    tracing::info!("Setting environment: {}", name);
+
+    let environment =
+        environment_service::set_environment(&pool, scope.id_or_default(), &name, req)
+            .await
+            .map_err(map_environment_error)?;
+
+    Ok(Json(environment))
+}
+
+/// DELETE /environments/{name}
+/// Delete an environment with its deployment history (admin)
+pub async fn delete_environment(
+    State(pool): State<PgPool>,
+    _admin: AdminAuth,
+    scope: ProjectScope,
+    Path(EnvironmentPath { name }): Path<EnvironmentPath>,
+) -> ApiResult<StatusCode> {
+    tracing::info!("Deleting environment: {}", name);
+
+    environment_service::delete_environment(&pool, scope.id_or_default(), &name)
+        .await
+        .map_err(map_environment_error)?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// GET /environments/{name}/deployments?limit={n}
+/// List the deployments to an environment, newest first
+pub async fn list_deployments(
+    State(pool): State<PgPool>,
+    scope: ProjectScope,
+    Path(EnvironmentPath { name }): Path<EnvironmentPath>,
+    Query(query): Query<DeploymentQuery>,
+) -> ApiResult<Json<Vec<Deployment>>> {
+    tracing::debug!("Listing deployments to environment: {}", name);
+
+    let deployments =
+        environment_service::list_deployments(&pool, scope.id_or_default(), &name, query)
+            .await
+            .map_err(map_environment_error)?;
+
+    Ok(Json(deployments))
+}
+
+/// GET /environments/{name}/lock
+/// Job holding the lock of an environment, and the jobs waiting for it
+pub async fn get_environment_lock(
+    State(pool): State<PgPool>,
+    scope: ProjectScope,
+    Path(EnvironmentPath { name }): Path<EnvironmentPath>,
+) -> ApiResult<Json<EnvironmentLockStatus>> {
+    tracing::debug!("Getting lock of environment: {}", name);
+
+    let lock = environment_service::get_lock(&pool, scope.id_or_default(), &name)
+        .await
+        .map_err(map_environment_error)?;
+
+    Ok(Json(lock))
+}
+
+/// DELETE /environments/{name}/lock
+/// Force-release the lock of an environment (admin)
+pub async fn release_environment_lock(
+    State(pool): State<PgPool>,
+    State(events): State<EventBus>,
+    _admin: AdminAuth,
+    scope: ProjectScope,
+    Path(EnvironmentPath { name }): Path<EnvironmentPath>,
+) -> ApiResult<StatusCode> {
+    tracing::info!("Force-releasing lock of environment: {}", name);
+
+    let unblocked = environment_service::force_release_lock(&pool, scope.id_or_default(), &name)
+        .await
+        .map_err(map_environment_error)?;
+
+    for (job_id, pipeline_id) in unblocked {
+        events.publish(Event::JobQueued {
+            job_id,
+            pipeline_id,
+        });
+    }
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// GET /jobs/{id}/approvals
+/// Approvals of a job, for each environment it deploys to
+pub async fn get_job_approvals(
+    State(pool): State<PgPool>,
+    scope: ProjectScope,
+    Path(IdPath { id }): Path<IdPath>,
+) -> ApiResult<Json<Vec<JobApprovals>>> {
+    tracing::debug!("Getting approvals of job: {}", id);
+
+    scope.ensure_job(&pool, id).await?;
+
+    let approvals = environment_service::job_approvals(&pool, id)
+        .await
+        .map_err(map_environment_error)?;
+
+    Ok(Json(approvals))
+}
+
+/// POST /jobs/{id}/approvals
+/// Approve a queued or blocked job's deployments to an environment (admin)
+pub async fn approve_job(
+    State(pool): State<PgPool>,
+    _admin: AdminAuth,
+    scope: ProjectScope,
+    Path(IdPath { id }): Path<IdPath>,
+    Json(req): Json<ApproveJob>,
+) -> ApiResult<Json<Vec<JobApprovals>>> {
+    tracing::info!("Approving job {} for {}", id, req.environment);
+
+    scope.ensure_job(&pool, id).await?;
+
+    let approvals = environment_service::approve_job(&pool, id, req)
+        .await
+        .map_err(map_environment_error)?;
+
+    Ok(Json(approvals))
+}
+
+fn map_environment_error(e: environment_service::EnvironmentError) -> ApiError {
+    match e {
+        environment_service::EnvironmentError::NotFound(name) => {
+            ApiError::NotFound(format!("Environment '{}' not found", name))
+        }
+        environment_service::EnvironmentError::JobNotFound(id) => {
+            ApiError::NotFound(format!("Job {} not found", id))
+        }
+        environment_service::EnvironmentError::ValidationError(msg) => ApiError::BadRequest(msg),
+        environment_service::EnvironmentError::InvalidFields(fields) => {
+            ApiError::InvalidFields(fields)
+        }
+        environment_service::EnvironmentError::InvalidState(msg) => ApiError::BadRequest(msg),
+        environment_service::EnvironmentError::DatabaseError(err) => ApiError::DatabaseError(err),
+    }
+}
@@ -1,42 +1,144 @@
 //! API Error Handling
 //!
-//! Unified error types and conversion for API responses.
+//! Unified error types and conversion for API responses. Every error is
+//! answered with RFC 7807 problem details (`application/problem+json`).
 
 use axum::{
-    Json,
-    http::StatusCode,
+    body::to_bytes,
+    extract::Request,
+    http::{HeaderValue, StatusCode, header},
+    middleware::Next,
     response::{IntoResponse, Response},
 };
 
+use rivet_core::dto::problem::{PROBLEM_JSON, Problem, ProblemType};
+use rivet_core::dto::validation::{FieldError, describe_field_errors};
+
+use crate::api::request_context::current_request_id;
+
+/// Largest plain-text error body turned into problem details
+const MAX_PLAIN_ERROR_BYTES: usize = 64 * 1024;
+
 /// API error type
 #[derive(Debug)]
 pub enum ApiError {
     NotFound(String),
     BadRequest(String),
+    Unauthorized(String),
+    /// The request conflicts with the orchestrator's state, answered with 409
+    Conflict(String),
+    /// Fields of the request that failed validation, answered with 422
+    InvalidFields(Vec<FieldError>),
     DatabaseError(sqlx::Error),
     InternalError(String),
 }
 
 impl IntoResponse for ApiError {
     fn into_response(self) -> Response {
-        let (status, message) = match self {
-            ApiError::NotFound(msg) => (StatusCode::NOT_FOUND, msg),
-            ApiError::BadRequest(msg) => (StatusCode::BAD_REQUEST, msg),
+        let mut fields = Vec::new();
+        let (status, problem_type, detail) = match self {
+            ApiError::NotFound(msg) => (StatusCode::NOT_FOUND, ProblemType::NotFound, msg),
+            ApiError::BadRequest(msg) => (StatusCode::BAD_REQUEST, ProblemType::BadRequest, msg),
+            ApiError::Unauthorized(msg) => {
+                (StatusCode::UNAUTHORIZED, ProblemType::Unauthorized, msg)
+            }
+            ApiError::Conflict(msg) => (StatusCode::CONFLICT, ProblemType::Conflict, msg),
+            ApiError::InvalidFields(errors) => {
+                let detail = format!("Invalid request: {}", describe_field_errors(&errors));
+                fields = errors;
+                (
+                    StatusCode::UNPROCESSABLE_ENTITY,
+                    ProblemType::InvalidFields,
+                    detail,
+                )
+            }
             ApiError::DatabaseError(err) => {
                 tracing::error!("Database error: {:?}", err);
                 (
                     StatusCode::INTERNAL_SERVER_ERROR,
+                    ProblemType::Internal,
                     "Internal server error".to_string(),
                 )
             }
             ApiError::InternalError(msg) => {
                 tracing::error!("Internal error: {}", msg);
-                (StatusCode::INTERNAL_SERVER_ERROR, msg)
+                (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    ProblemType::Internal,
+                    msg,
+                )
             }
         };
 
-        (status, Json(serde_json::json!({ "error": message }))).into_response()
+        let mut problem = Problem::new(problem_type, status.as_u16(), detail);
+        problem.fields = fields;
+        problem_response(problem)
+    }
+}
+
+/// Answers problem details, tagged with the id of the current request
+pub fn problem_response(mut problem: Problem) -> Response {
+    problem.request_id = current_request_id();
+    let status = StatusCode::from_u16(problem.status).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR);
+    let body = serde_json::to_vec(&problem).expect("problem details serialize");
+
+    (
+        status,
+        [(header::CONTENT_TYPE, HeaderValue::from_static(PROBLEM_JSON))],
+        body,
+    )
+        .into_response()
+}
+
+/// Middleware turning errors answered outside the handlers (rejected
+/// request bodies, unknown routes, ...) into problem details
+pub async fn plain_errors_as_problems(req: Request, next: Next) -> Response {
+    let response = next.run(req).await;
+    let status = response.status();
+    if !(status.is_client_error() || status.is_server_error()) {
+        return response;
+    }
+
+    let content_type = response
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or("");
+    if content_type.starts_with(PROBLEM_JSON) || content_type.starts_with("application/json") {
+        return response;
+    }
+
+    let (parts, body) = response.into_parts();
+    let detail = match to_bytes(body, MAX_PLAIN_ERROR_BYTES).await {
+        Ok(bytes) if !bytes.is_empty() => String::from_utf8_lossy(&bytes).trim().to_string(),
+        _ => status
+            .canonical_reason()
+            .unwrap_or("Request failed")
+            .to_string(),
+    };
+
+    let problem_type = match status {
+        // Bodies the JSON extractor rejected
+        StatusCode::BAD_REQUEST
+        | StatusCode::UNSUPPORTED_MEDIA_TYPE
+        | StatusCode::UNPROCESSABLE_ENTITY => ProblemType::BadRequest,
+        StatusCode::NOT_FOUND => ProblemType::NotFound,
+        StatusCode::PAYLOAD_TOO_LARGE => ProblemType::PayloadTooLarge,
+        s if s.is_server_error() => ProblemType::Internal,
+        _ => ProblemType::Other,
+    };
+
+    let mut problem = problem_response(Problem::new(problem_type, status.as_u16(), detail));
+    // Keep headers such as `Allow` on 405
+    for (name, value) in parts.headers.iter() {
+        if name != header::CONTENT_TYPE
+            && name != header::CONTENT_LENGTH
+            && !problem.headers().contains_key(name)
+        {
+            problem.headers_mut().append(name, value.clone());
+        }
     }
+    problem
 }
 
 impl From<sqlx::Error> for ApiError {
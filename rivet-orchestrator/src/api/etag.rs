@@ -0,0 +1,152 @@
+//! Conditional GET
+//!
+//! Pipeline and job reads carry an `ETag` so polling clients can ask
+//! "has this changed?" with `If-None-Match` and get an empty
+//! `304 Not Modified` when it has not. The ETag is a digest of the JSON
+//! body, so it changes whenever anything a client can see changes (jobs
+//! have no modification time to derive it from). Bodies are written with
+//! object keys sorted, so maps such as job parameters give the same ETag
+//! whatever their iteration order. ETags are weak since responses may be
+//! compressed in transit.
+
+use std::convert::Infallible;
+
+use axum::{
+    extract::FromRequestParts,
+    http::{HeaderValue, StatusCode, header, request::Parts},
+    response::{IntoResponse, Response},
+};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+
+use crate::api::error::ApiError;
+
+/// The `If-None-Match` header of a request, if any
+#[derive(Debug, Default)]
+pub struct IfNoneMatch(Option<String>);
+
+impl<S> FromRequestParts<S> for IfNoneMatch
+where
+    S: Send + Sync,
+{
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let value = parts
+            .headers
+            .get(header::IF_NONE_MATCH)
+            .and_then(|v| v.to_str().ok())
+            .map(str::to_string);
+        Ok(IfNoneMatch(value))
+    }
+}
+
+impl IfNoneMatch {
+    /// Answers `value` as JSON tagged with its ETag, or `304 Not Modified`
+    /// when the client already holds that version
+    pub fn respond<T: Serialize>(&self, value: &T) -> Response {
+        // Going through `Value` sorts object keys
+        let body = match serde_json::to_value(value).and_then(|value| serde_json::to_vec(&value)) {
+            Ok(body) => body,
+            Err(e) => {
+                return ApiError::InternalError(format!("Failed to serialize response: {}", e))
+                    .into_response();
+            }
+        };
+        let etag = etag_of(&body);
+
+        let mut response = if self.matches(&etag) {
+            StatusCode::NOT_MODIFIED.into_response()
+        } else {
+            ([(header::CONTENT_TYPE, "application/json")], body).into_response()
+        };
+
+        let headers = response.headers_mut();
+        headers.insert(
+            header::ETAG,
+            HeaderValue::from_str(&etag).expect("ETag is a valid header value"),
+        );
+        // Caches must check back before reusing a response
+        headers.insert(header::CACHE_CONTROL, HeaderValue::from_static("no-cache"));
+        response
+    }
+
+    /// Whether the header names `etag`, using the weak comparison
+    /// `If-None-Match` calls for
+    fn matches(&self, etag: &str) -> bool {
+        let Some(header) = &self.0 else {
+            return false;
+        };
+        header
+            .split(',')
+            .map(str::trim)
+            .any(|tag| tag == "*" || opaque_tag(tag) == opaque_tag(etag))
+    }
+}
+
+/// Weak ETag of a response body
+fn etag_of(body: &[u8]) -> String {
+    let digest = Sha256::digest(body);
+    format!("W/\"{}\"", hex::encode(&digest[..16]))
+}
+
+/// An ETag without its weakness indicator
+fn opaque_tag(tag: &str) -> &str {
+    tag.strip_prefix("W/").unwrap_or(tag)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_etag_matching() {
+        let etag = etag_of(b"{\"id\":1}");
+        assert!(etag.starts_with("W/\""));
+        assert_ne!(etag, etag_of(b"{\"id\":2}"));
+
+        let strong = opaque_tag(&etag).to_string();
+        assert!(IfNoneMatch(Some(etag.clone())).matches(&etag));
+        assert!(IfNoneMatch(Some(format!("\"other\", {}", strong))).matches(&etag));
+        assert!(IfNoneMatch(Some("*".to_string())).matches(&etag));
+        assert!(!IfNoneMatch(Some("\"other\"".to_string())).matches(&etag));
+        assert!(!IfNoneMatch(None).matches(&etag));
+    }
+
+    #[test]
+    fn test_not_modified_has_no_body() {
+        let value = serde_json::json!({ "id": 1 });
+        let response = IfNoneMatch::default().respond(&value);
+        assert_eq!(response.status(), StatusCode::OK);
+        let etag = response.headers()[header::ETAG]
+            .to_str()
+            .unwrap()
+            .to_string();
+
+        let response = IfNoneMatch(Some(etag.clone())).respond(&value);
+        assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+        assert_eq!(response.headers()[header::ETAG], etag.as_str());
+    }
+
+    #[test]
+    fn test_etag_ignores_map_order() {
+        let parameters = |keys: &[&str]| {
+            keys.iter()
+                .map(|key| (key.to_string(), serde_json::json!(key)))
+                .collect::<std::collections::HashMap<_, _>>()
+        };
+        let etag = |value| {
+            IfNoneMatch::default().respond(&value).headers()[header::ETAG]
+                .to_str()
+                .unwrap()
+                .to_string()
+        };
+
+        // Maps built in different orders may iterate differently
+        let keys = ["alpha", "beta", "gamma", "delta", "epsilon"];
+        let mut reversed = keys;
+        reversed.reverse();
+        assert_eq!(etag(parameters(&keys)), etag(parameters(&reversed)));
+        assert_ne!(etag(parameters(&keys)), etag(parameters(&keys[1..])));
+    }
+}
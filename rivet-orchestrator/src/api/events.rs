@@ -0,0 +1,65 @@
+//! Event API Handlers
+//!
+//! WebSocket endpoint streaming orchestrator events as JSON text frames.
+
+use axum::{
+    extract::{
+        State,
+        ws::{Message, WebSocket, WebSocketUpgrade},
+    },
+    response::Response,
+};
+use tokio::sync::broadcast::error::RecvError;
+
+use crate::events::EventBus;
+
+/// GET /events/ws
+/// Upgrade to a WebSocket and stream events until the client disconnects
+pub async fn events_ws(State(events): State<EventBus>, ws: WebSocketUpgrade) -> Response {
+    // Subscribe before the upgrade so no event is missed in between
+    let receiver = events.subscribe();
+    ws.on_upgrade(move |socket| stream_events(socket, receiver))
+}
+
+async fn stream_events(
+    mut socket: WebSocket,
+    mut receiver: tokio::sync::broadcast::Receiver<rivet_core::dto::event::Event>,
+) {
+    tracing::debug!("Event subscriber connected");
+
+    loop {
+        tokio::select! {
+            event = receiver.recv() => {
+                let event = match event {
+                    Ok(event) => event,
+                    Err(RecvError::Lagged(skipped)) => {
+                        tracing::warn!("Event subscriber lagged, skipped {} event(s)", skipped);
+                        continue;
+                    }
+                    Err(RecvError::Closed) => break,
+                };
+
+                let frame = match serde_json::to_string(&event) {
+                    Ok(frame) => frame,
+                    Err(e) => {
+                        tracing::error!("Failed to serialize event: {}", e);
+                        continue;
+                    }
+                };
+
+                if socket.send(Message::Text(frame.into())).await.is_err() {
+                    break;
+                }
+            }
+            incoming = socket.recv() => {
+                // Clients only listen; stop on close or transport error
+                match incoming {
+                    Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                    Some(Ok(_)) => {}
+                }
+            }
+        }
+    }
+
+    tracing::debug!("Event subscriber disconnected");
+}
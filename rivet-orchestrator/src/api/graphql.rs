@@ -0,0 +1,29 @@
+//! GraphQL API Handlers
+//!
+//! HTTP transport for the GraphQL schema defined in `crate::graphql`.
+
+use async_graphql::http::{GraphQLPlaygroundConfig, playground_source};
+use axum::{
+    Json,
+    extract::State,
+    response::{Html, IntoResponse},
+};
+
+use crate::graphql::RivetSchema;
+
+/// POST /api/v1/v1/graphql
+/// Execute a GraphQL query
+pub async fn graphql_query(
+    State(schema): State<RivetSchema>,
+    Json(request): Json<async_graphql::Request>,
+) -> Json<async_graphql::Response> {
+    Json(schema.execute(request).await)
+}
+
+/// GET /api/v1/v1/graphql
+/// Interactive GraphQL playground
+pub async fn graphql_playground() -> impl IntoResponse {
+    Html(playground_source(GraphQLPlaygroundConfig::new(
+        "/api/v1/graphql",
+    )))
+}
@@ -1,11 +1,127 @@
-//! Health Check API Handler
+//! Health Check API Handlers
 //!
-//! Simple health check endpoint for monitoring.
+//! Health check endpoint for monitoring and `rivet doctor`, and the liveness
+//! and readiness probes of orchestrator deployments (e.g. on Kubernetes).
 
-use axum::{http::StatusCode, response::IntoResponse};
+use std::time::Instant;
+
+use axum::{Json, extract::State, http::StatusCode, response::IntoResponse};
+use rivet_core::dto::health::{ComponentStatus, HealthStatus, LivenessStatus, ReadinessStatus};
+use sqlx::PgPool;
+
+use crate::db;
+use crate::leader::Leadership;
+use crate::workers::WorkerMonitor;
 
 /// GET /health
 /// Health check endpoint
-pub async fn health_check() -> impl IntoResponse {
-    (StatusCode::OK, "OK")
+///
+/// Reports the orchestrator version and database connectivity. Responds with
+/// 503 Service Unavailable when the database cannot be reached.
+pub async fn health_check(State(pool): State<PgPool>) -> impl IntoResponse {
+    let database = match sqlx::query("SELECT 1").execute(&pool).await {
+        Ok(_) => "ok".to_string(),
+        Err(e) => {
+            tracing::warn!("Health check database probe failed: {}", e);
+            e.to_string()
+        }
+    };
+
+    let healthy = database == "ok";
+    let status = if healthy {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+
+    (
+        status,
+        Json(HealthStatus {
+            status: if healthy { "ok" } else { "degraded" }.to_string(),
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            database,
+        }),
+    )
+}
+
+/// GET /healthz
+/// Liveness probe: answers as long as the process serves requests
+///
+/// Checks no dependency, so an unreachable database never gets a healthy
+/// orchestrator restarted.
+pub async fn liveness() -> Json<LivenessStatus> {
+    Json(LivenessStatus {
+        status: "ok".to_string(),
+        version: env!("CARGO_PKG_VERSION").to_string(),
+    })
+}
+
+/// GET /readyz
+/// Readiness probe: whether the orchestrator can serve traffic
+///
+/// Checks that the database is reachable, that its schema is the one this
+/// orchestrator migrates to, and that the background workers complete their
+/// passes. Responds with 503 Service Unavailable when any of them fails.
+/// Standby replicas are ready: the `leader` component only tells which
+/// replica runs the background duties.
+pub async fn readiness(
+    State(pool): State<PgPool>,
+    State(workers): State<WorkerMonitor>,
+    State(leadership): State<Leadership>,
+) -> impl IntoResponse {
+    let mut components = Vec::new();
+
+    match sqlx::query("SELECT 1").execute(&pool).await {
+        Ok(_) => {
+            components.push(ComponentStatus::ok("database", "reachable"));
+            components.push(match db::schema_version(&pool).await {
+                Ok(Some(version)) if version >= db::SCHEMA_VERSION => {
+                    ComponentStatus::ok("migrations", format!("schema version {}", version))
+                }
+                Ok(Some(version)) => ComponentStatus::failing(
+                    "migrations",
+                    format!(
+                        "schema version {}, expected {}",
+                        version,
+                        db::SCHEMA_VERSION
+                    ),
+                ),
+                Ok(None) => ComponentStatus::failing("migrations", "database was never migrated"),
+                Err(e) => ComponentStatus::failing("migrations", e.to_string()),
+            });
+        }
+        Err(e) => {
+            tracing::warn!("Readiness database probe failed: {}", e);
+            components.push(ComponentStatus::failing("database", e.to_string()));
+            components.push(ComponentStatus::failing(
+                "migrations",
+                "database unreachable",
+            ));
+        }
+    }
+    components.push(ComponentStatus::ok(
+        "leader",
+        if leadership.is_leader() {
+            "leader"
+        } else {
+            "standby"
+        },
+    ));
+    components.extend(workers.report(Instant::now()));
+
+    let ready = components.iter().all(ComponentStatus::is_ok);
+    let status = if ready {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+
+    (
+        status,
+        Json(ReadinessStatus {
+            status: if ready { "ready" } else { "unready" }.to_string(),
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            components,
+        }),
+    )
 }
@@ -4,17 +4,31 @@
 
 use axum::{
     Json,
-    extract::{Path, State},
+    extract::{Path, Query, State},
     http::StatusCode,
+    response::{IntoResponse, Response},
 };
-use rivet_core::domain::job::{Job, JobStatus};
+use rivet_core::domain::job::{Job, JobStatus, StageRun};
 use rivet_core::domain::log::LogEntry;
-use rivet_core::dto::job::{CompleteJobRequest, CreateJob, ExecuteJobRequest, JobExecutionInfo};
+use rivet_core::dto::event::Event;
+use rivet_core::dto::job::{
+    CompleteJobRequest, CreateJob, ExecuteJobRequest, JobExecutionInfo, JobFilter, JobLease,
+    JobListQuery, LaunchBatchResponse, NextJobQuery, RenewLeaseRequest, StageUpdate,
+};
+use rivet_core::dto::log::{AddLogsQuery, LogQuery, LogSearchHit, LogSearchQuery};
+use rivet_core::dto::pagination::{MAX_PAGE_SIZE, PageQuery};
 
 use sqlx::PgPool;
+use std::collections::HashMap;
 use uuid::Uuid;
 
+use crate::api::auth::RunnerIdentity;
 use crate::api::error::{ApiError, ApiResult};
+use crate::api::etag::IfNoneMatch;
+use crate::api::project::{IdPath, ProjectScope};
+use crate::events::EventBus;
+use crate::log_archive::LogArchive;
+use crate::scheduling::SchedulingPolicy;
 use crate::service::{job_service, log_service};
 
 // =============================================================================
@@ -23,34 +37,104 @@ use crate::service::{job_service, log_service};
 
 /// POST /pipeline/launch
 /// Create and launch a new job for a pipeline
+///
+/// With `plan` set, answers the `JobPlan` of the job instead of queueing it.
 pub async fn launch_job(
     State(pool): State<PgPool>,
+    State(events): State<EventBus>,
+    scope: ProjectScope,
     Json(req): Json<CreateJob>,
-) -> ApiResult<Json<Job>> {
+) -> ApiResult<Response> {
+    scope.ensure_pipeline(&pool, req.pipeline_id).await?;
+
+    if req.plan {
+        tracing::info!("Planning job for pipeline: {}", req.pipeline_id);
+
+        let plan = job_service::plan_job(&pool, req)
+            .await
+            .map_err(map_launch_error)?;
+        return Ok(Json(plan).into_response());
+    }
+
     tracing::info!("Launching job for pipeline: {}", req.pipeline_id);
 
     let job = job_service::launch_job(&pool, req)
         .await
-        .map_err(|e| match e {
-            job_service::JobError::PipelineNotFound(id) => {
-                ApiError::NotFound(format!("Pipeline {} not found", id))
-            }
-            job_service::JobError::ValidationError(msg) => ApiError::BadRequest(msg),
-            job_service::JobError::DatabaseError(err) => ApiError::DatabaseError(err),
-            job_service::JobError::NotFound(id) => {
-                ApiError::NotFound(format!("Job {} not found", id))
-            }
-            job_service::JobError::InvalidState(msg) => ApiError::BadRequest(msg),
-        })?;
+        .map_err(map_launch_error)?;
 
-    Ok(Json(job))
+    events.publish(Event::JobQueued {
+        job_id: job.id,
+        pipeline_id: job.pipeline_id,
+    });
+
+    Ok(Json(job).into_response())
+}
+
+/// Maps the errors of launching (or planning) jobs
+pub(crate) fn map_launch_error(e: job_service::JobError) -> ApiError {
+    match e {
+        job_service::JobError::PipelineNotFound(id) => {
+            ApiError::NotFound(format!("Pipeline {} not found", id))
+        }
+        job_service::JobError::ValidationError(msg) => ApiError::BadRequest(msg),
+        job_service::JobError::InvalidFields(fields) => ApiError::InvalidFields(fields),
+        job_service::JobError::DatabaseError(err) => ApiError::DatabaseError(err),
+        job_service::JobError::NotFound(id) => ApiError::NotFound(format!("Job {} not found", id)),
+        job_service::JobError::InvalidState(msg) => ApiError::BadRequest(msg),
+        job_service::JobError::StaleClaim(id) => {
+            ApiError::Conflict(format!("Claim on job {} is no longer current", id))
+        }
+    }
+}
+
+/// POST /pipeline/{id}/launch-batch
+/// Launch one job per parameter set in the body (a JSON array), all or nothing
+pub async fn launch_job_batch(
+    State(pool): State<PgPool>,
+    State(events): State<EventBus>,
+    scope: ProjectScope,
+    Path(IdPath { id }): Path<IdPath>,
+    Json(parameter_sets): Json<Vec<HashMap<String, serde_json::Value>>>,
+) -> ApiResult<(StatusCode, Json<LaunchBatchResponse>)> {
+    tracing::info!(
+        "Launching {} jobs for pipeline: {}",
+        parameter_sets.len(),
+        id
+    );
+
+    scope.ensure_pipeline(&pool, id).await?;
+
+    let jobs = job_service::launch_job_batch(&pool, id, parameter_sets)
+        .await
+        .map_err(map_launch_error)?;
+
+    for job in &jobs {
+        events.publish(Event::JobQueued {
+            job_id: job.id,
+            pipeline_id: job.pipeline_id,
+        });
+    }
+
+    Ok((
+        StatusCode::CREATED,
+        Json(LaunchBatchResponse {
+            job_ids: jobs.into_iter().map(|job| job.id).collect(),
+        }),
+    ))
 }
 
 /// GET /job/{id}
 /// Get job details by ID
-pub async fn get_job(State(pool): State<PgPool>, Path(id): Path<Uuid>) -> ApiResult<Json<Job>> {
+pub async fn get_job(
+    State(pool): State<PgPool>,
+    scope: ProjectScope,
+    if_none_match: IfNoneMatch,
+    Path(IdPath { id }): Path<IdPath>,
+) -> ApiResult<Response> {
     tracing::debug!("Getting job: {}", id);
 
+    scope.ensure_job(&pool, id).await?;
+
     let job = job_service::get_job(&pool, id).await.map_err(|e| match e {
         job_service::JobError::NotFound(id) => ApiError::NotFound(format!("Job {} not found", id)),
         job_service::JobError::DatabaseError(err) => ApiError::DatabaseError(err),
@@ -58,40 +142,73 @@ pub async fn get_job(State(pool): State<PgPool>, Path(id): Path<Uuid>) -> ApiRes
             ApiError::NotFound(format!("Pipeline {} not found", id))
         }
         job_service::JobError::ValidationError(msg) => ApiError::BadRequest(msg),
+        job_service::JobError::InvalidFields(fields) => ApiError::InvalidFields(fields),
         job_service::JobError::InvalidState(msg) => ApiError::BadRequest(msg),
+        job_service::JobError::StaleClaim(id) => {
+            ApiError::Conflict(format!("Claim on job {} is no longer current", id))
+        }
     })?;
 
-    Ok(Json(job))
+    Ok(if_none_match.respond(&job))
 }
 
-/// GET /jobs
-/// List all jobs
-pub async fn list_all_jobs(State(pool): State<PgPool>) -> ApiResult<Json<Vec<Job>>> {
-    tracing::debug!("Listing all jobs");
+/// GET /jobs?limit=&offset=&label=
+/// List the jobs of the project (see `ProjectScope::listed_project_id`), or a
+/// single page when `limit` is given
+///
+/// `label=team=payments,env=prod` only lists jobs carrying all these labels,
+/// a page of at most `MAX_PAGE_SIZE` jobs when `limit` is not given.
+pub async fn list_all_jobs(
+    State(pool): State<PgPool>,
+    scope: ProjectScope,
+    if_none_match: IfNoneMatch,
+    Query(page): Query<PageQuery>,
+    Query(query): Query<JobListQuery>,
+) -> ApiResult<Response> {
+    tracing::debug!("Listing jobs: {:?} {:?}", page, query);
+
+    let labels = query.labels().map_err(ApiError::BadRequest)?;
+    let paged = page.limit.is_some() || !labels.is_empty();
+    let jobs = match (paged, scope.listed_project_id()) {
+        (true, project_id) => {
+            let filter = JobFilter {
+                project_id,
+                labels,
+                ..JobFilter::default()
+            };
+            let limit = page.limit.unwrap_or(MAX_PAGE_SIZE);
+            job_service::list_jobs_page(&pool, &filter, limit, page.offset.unwrap_or(0)).await
+        }
+        (false, Some(project_id)) => job_service::list_jobs_by_project(&pool, project_id).await,
+        (false, None) => job_service::list_all_jobs(&pool).await,
+    };
 
-    let jobs = job_service::list_all_jobs(&pool)
-        .await
-        .map_err(|e| match e {
-            job_service::JobError::DatabaseError(err) => ApiError::DatabaseError(err),
-            job_service::JobError::NotFound(id) => {
-                ApiError::NotFound(format!("Job {} not found", id))
-            }
-            job_service::JobError::PipelineNotFound(id) => {
-                ApiError::NotFound(format!("Pipeline {} not found", id))
-            }
-            job_service::JobError::ValidationError(msg) => ApiError::BadRequest(msg),
-            job_service::JobError::InvalidState(msg) => ApiError::BadRequest(msg),
-        })?;
+    let jobs = jobs.map_err(|e| match e {
+        job_service::JobError::DatabaseError(err) => ApiError::DatabaseError(err),
+        job_service::JobError::NotFound(id) => ApiError::NotFound(format!("Job {} not found", id)),
+        job_service::JobError::PipelineNotFound(id) => {
+            ApiError::NotFound(format!("Pipeline {} not found", id))
+        }
+        job_service::JobError::ValidationError(msg) => ApiError::BadRequest(msg),
+        job_service::JobError::InvalidFields(fields) => ApiError::InvalidFields(fields),
+        job_service::JobError::InvalidState(msg) => ApiError::BadRequest(msg),
+        job_service::JobError::StaleClaim(id) => {
+            ApiError::Conflict(format!("Claim on job {} is no longer current", id))
+        }
+    })?;
 
-    Ok(Json(jobs))
+    Ok(if_none_match.respond(&jobs))
 }
 
 /// GET /jobs/scheduled
-/// List all scheduled (queued) jobs
-pub async fn list_scheduled_jobs(State(pool): State<PgPool>) -> ApiResult<Json<Vec<Job>>> {
+/// List all scheduled (queued) jobs, in the order they will be assigned
+pub async fn list_scheduled_jobs(
+    State(pool): State<PgPool>,
+    State(policy): State<SchedulingPolicy>,
+) -> ApiResult<Json<Vec<Job>>> {
     tracing::debug!("Listing all scheduled jobs");
 
-    let jobs = job_service::list_jobs_by_status(&pool, JobStatus::Queued)
+    let jobs = job_service::list_queued_jobs(&pool, policy)
         .await
         .map_err(|e| match e {
             job_service::JobError::DatabaseError(err) => ApiError::DatabaseError(err),
@@ -102,20 +219,28 @@ pub async fn list_scheduled_jobs(State(pool): State<PgPool>) -> ApiResult<Json<V
                 ApiError::NotFound(format!("Pipeline {} not found", id))
             }
             job_service::JobError::ValidationError(msg) => ApiError::BadRequest(msg),
+            job_service::JobError::InvalidFields(fields) => ApiError::InvalidFields(fields),
             job_service::JobError::InvalidState(msg) => ApiError::BadRequest(msg),
+            job_service::JobError::StaleClaim(id) => {
+                ApiError::Conflict(format!("Claim on job {} is no longer current", id))
+            }
         })?;
 
     Ok(Json(jobs))
 }
 
-/// GET /job/pipeline/{pipeline_id}
+/// GET /job/pipeline/{id}
 /// List all jobs for a specific pipeline
 pub async fn list_jobs_by_pipeline(
     State(pool): State<PgPool>,
-    Path(pipeline_id): Path<Uuid>,
-) -> ApiResult<Json<Vec<Job>>> {
+    scope: ProjectScope,
+    if_none_match: IfNoneMatch,
+    Path(IdPath { id: pipeline_id }): Path<IdPath>,
+) -> ApiResult<Response> {
     tracing::debug!("Listing jobs for pipeline: {}", pipeline_id);
 
+    scope.ensure_pipeline(&pool, pipeline_id).await?;
+
     let jobs = job_service::list_jobs_by_pipeline(&pool, pipeline_id)
         .await
         .map_err(|e| match e {
@@ -127,21 +252,29 @@ pub async fn list_jobs_by_pipeline(
                 ApiError::NotFound(format!("Job {} not found", id))
             }
             job_service::JobError::ValidationError(msg) => ApiError::BadRequest(msg),
+            job_service::JobError::InvalidFields(fields) => ApiError::InvalidFields(fields),
             job_service::JobError::InvalidState(msg) => ApiError::BadRequest(msg),
+            job_service::JobError::StaleClaim(id) => {
+                ApiError::Conflict(format!("Claim on job {} is no longer current", id))
+            }
         })?;
 
-    Ok(Json(jobs))
+    Ok(if_none_match.respond(&jobs))
 }
 
 /// POST /job/execute/{id}
 /// Reserve a job for execution by a runner
 pub async fn execute_job(
     State(pool): State<PgPool>,
+    State(events): State<EventBus>,
+    identity: RunnerIdentity,
     Path(id): Path<Uuid>,
     Json(req): Json<ExecuteJobRequest>,
 ) -> ApiResult<Json<JobExecutionInfo>> {
     tracing::info!("Runner {} executing job: {}", req.runner_id, id);
 
+    identity.ensure_runner(&req.runner_id)?;
+
     let (job, pipeline) = job_service::reserve_job_for_execution(&pool, id, req.runner_id)
         .await
         .map_err(|e| match e {
@@ -152,43 +285,366 @@ pub async fn execute_job(
                 ApiError::NotFound(format!("Pipeline {} not found", id))
             }
             job_service::JobError::InvalidState(msg) => ApiError::BadRequest(msg),
+            job_service::JobError::StaleClaim(id) => {
+                ApiError::Conflict(format!("Claim on job {} is no longer current", id))
+            }
             job_service::JobError::DatabaseError(err) => ApiError::DatabaseError(err),
             job_service::JobError::ValidationError(msg) => ApiError::BadRequest(msg),
+            job_service::JobError::InvalidFields(fields) => ApiError::InvalidFields(fields),
         })?;
 
-    let response = JobExecutionInfo {
+    events.publish(Event::JobStarted {
         job_id: job.id,
-        pipeline_id: pipeline.id,
-        pipeline_source: pipeline.script,
-        parameters: job.parameters,
-    };
+        pipeline_id: job.pipeline_id,
+        runner_id: job.runner_id.clone().unwrap_or_default(),
+    });
+
+    let response = job_service::execution_info(&pool, job, pipeline)
+        .await
+        .map_err(|e| match e {
+            job_service::JobError::NotFound(id) => {
+                ApiError::NotFound(format!("Job {} not found", id))
+            }
+            job_service::JobError::PipelineNotFound(id) => {
+                ApiError::NotFound(format!("Pipeline {} not found", id))
+            }
+            job_service::JobError::InvalidState(msg) => ApiError::BadRequest(msg),
+            job_service::JobError::StaleClaim(id) => {
+                ApiError::Conflict(format!("Claim on job {} is no longer current", id))
+            }
+            job_service::JobError::DatabaseError(err) => ApiError::DatabaseError(err),
+            job_service::JobError::ValidationError(msg) => ApiError::BadRequest(msg),
+            job_service::JobError::InvalidFields(fields) => ApiError::InvalidFields(fields),
+        })?;
 
     Ok(Json(response))
 }
 
+/// GET /jobs/deadletter?limit=&offset=
+/// List dead-lettered jobs of the project (see
+/// `ProjectScope::listed_project_id`), newest first
+pub async fn list_dead_lettered_jobs(
+    State(pool): State<PgPool>,
+    scope: ProjectScope,
+    Query(page): Query<PageQuery>,
+) -> ApiResult<Json<Vec<Job>>> {
+    tracing::debug!("Listing dead-lettered jobs: {:?}", page);
+
+    let filter = JobFilter {
+        status: Some(JobStatus::DeadLettered),
+        project_id: scope.listed_project_id(),
+        ..JobFilter::default()
+    };
+    let jobs = job_service::list_jobs_page(
+        &pool,
+        &filter,
+        page.limit.unwrap_or(MAX_PAGE_SIZE),
+        page.offset.unwrap_or(0),
+    )
+    .await
+    .map_err(|e| match e {
+        job_service::JobError::DatabaseError(err) => ApiError::DatabaseError(err),
+        job_service::JobError::NotFound(id) => ApiError::NotFound(format!("Job {} not found", id)),
+        job_service::JobError::PipelineNotFound(id) => {
+            ApiError::NotFound(format!("Pipeline {} not found", id))
+        }
+        job_service::JobError::ValidationError(msg) => ApiError::BadRequest(msg),
+        job_service::JobError::InvalidFields(fields) => ApiError::InvalidFields(fields),
+        job_service::JobError::InvalidState(msg) => ApiError::BadRequest(msg),
+        job_service::JobError::StaleClaim(id) => {
+            ApiError::Conflict(format!("Claim on job {} is no longer current", id))
+        }
+    })?;
+
+    Ok(Json(jobs))
+}
+
+/// POST /jobs/{id}/requeue
+/// Put a dead-lettered job back in the queue
+pub async fn requeue_job(
+    State(pool): State<PgPool>,
+    State(events): State<EventBus>,
+    scope: ProjectScope,
+    Path(IdPath { id }): Path<IdPath>,
+) -> ApiResult<Json<Job>> {
+    tracing::info!("Requeueing dead-lettered job: {}", id);
+
+    scope.ensure_job(&pool, id).await?;
+
+    let job = job_service::requeue_dead_lettered_job(&pool, id)
+        .await
+        .map_err(|e| match e {
+            job_service::JobError::NotFound(id) => {
+                ApiError::NotFound(format!("Job {} not found", id))
+            }
+            job_service::JobError::PipelineNotFound(id) => {
+                ApiError::NotFound(format!("Pipeline {} not found", id))
+            }
+            job_service::JobError::InvalidState(msg) => ApiError::BadRequest(msg),
+            job_service::JobError::StaleClaim(id) => {
+                ApiError::Conflict(format!("Claim on job {} is no longer current", id))
+            }
+            job_service::JobError::DatabaseError(err) => ApiError::DatabaseError(err),
+            job_service::JobError::ValidationError(msg) => ApiError::BadRequest(msg),
+            job_service::JobError::InvalidFields(fields) => ApiError::InvalidFields(fields),
+        })?;
+
+    events.publish(Event::JobQueued {
+        job_id: job.id,
+        pipeline_id: job.pipeline_id,
+    });
+
+    Ok(Json(job))
+}
+
+/// POST /jobs/{id}/resume
+/// Create a job continuing a failed one, skipping its succeeded stages
+pub async fn resume_job(
+    State(pool): State<PgPool>,
+    State(events): State<EventBus>,
+    scope: ProjectScope,
+    Path(IdPath { id }): Path<IdPath>,
+) -> ApiResult<(StatusCode, Json<Job>)> {
+    tracing::info!("Resuming job: {}", id);
+
+    scope.ensure_job(&pool, id).await?;
+
+    let job = job_service::resume_job(&pool, id)
+        .await
+        .map_err(|e| match e {
+            job_service::JobError::NotFound(id) => {
+                ApiError::NotFound(format!("Job {} not found", id))
+            }
+            job_service::JobError::PipelineNotFound(id) => {
+                ApiError::NotFound(format!("Pipeline {} not found", id))
+            }
+            job_service::JobError::InvalidState(msg) => ApiError::BadRequest(msg),
+            job_service::JobError::StaleClaim(id) => {
+                ApiError::Conflict(format!("Claim on job {} is no longer current", id))
+            }
+            job_service::JobError::DatabaseError(err) => ApiError::DatabaseError(err),
+            job_service::JobError::ValidationError(msg) => ApiError::BadRequest(msg),
+            job_service::JobError::InvalidFields(fields) => ApiError::InvalidFields(fields),
+        })?;
+
+    events.publish(Event::JobQueued {
+        job_id: job.id,
+        pipeline_id: job.pipeline_id,
+    });
+
+    Ok((StatusCode::CREATED, Json(job)))
+}
+
+/// GET /jobs/next?runner_id={id}&wait=30s
+/// Long-poll for a job: blocks until a queued job is assigned to the runner
+/// (200 with `JobExecutionInfo`) or `wait` elapses (204 No Content)
+pub async fn next_job(
+    State(pool): State<PgPool>,
+    State(events): State<EventBus>,
+    State(policy): State<SchedulingPolicy>,
+    identity: RunnerIdentity,
+    Query(query): Query<NextJobQuery>,
+) -> ApiResult<Response> {
+    identity.ensure_runner(&query.runner_id)?;
+
+    let wait = query.wait_duration().ok_or_else(|| {
+        ApiError::BadRequest(format!(
+            "Invalid wait '{}' (expected e.g. '30s' or '500ms')",
+            query.wait.as_deref().unwrap_or_default()
+        ))
+    })?;
+    tracing::debug!(
+        "Runner {} waiting up to {:?} for a job",
+        query.runner_id,
+        wait
+    );
+
+    let assigned = job_service::wait_for_next_job(&pool, &events, &query.runner_id, wait, policy)
+        .await
+        .map_err(|e| match e {
+            job_service::JobError::NotFound(id) => {
+                ApiError::NotFound(format!("Job {} not found", id))
+            }
+            job_service::JobError::PipelineNotFound(id) => {
+                ApiError::NotFound(format!("Pipeline {} not found", id))
+            }
+            job_service::JobError::InvalidState(msg) => ApiError::BadRequest(msg),
+            job_service::JobError::StaleClaim(id) => {
+                ApiError::Conflict(format!("Claim on job {} is no longer current", id))
+            }
+            job_service::JobError::DatabaseError(err) => ApiError::DatabaseError(err),
+            job_service::JobError::ValidationError(msg) => ApiError::BadRequest(msg),
+            job_service::JobError::InvalidFields(fields) => ApiError::InvalidFields(fields),
+        })?;
+
+    let Some((job, pipeline)) = assigned else {
+        return Ok(StatusCode::NO_CONTENT.into_response());
+    };
+
+    events.publish(Event::JobStarted {
+        job_id: job.id,
+        pipeline_id: job.pipeline_id,
+        runner_id: job.runner_id.clone().unwrap_or_default(),
+    });
+
+    let response = job_service::execution_info(&pool, job, pipeline)
+        .await
+        .map_err(|e| match e {
+            job_service::JobError::NotFound(id) => {
+                ApiError::NotFound(format!("Job {} not found", id))
+            }
+            job_service::JobError::PipelineNotFound(id) => {
+                ApiError::NotFound(format!("Pipeline {} not found", id))
+            }
+            job_service::JobError::InvalidState(msg) => ApiError::BadRequest(msg),
+            job_service::JobError::StaleClaim(id) => {
+                ApiError::Conflict(format!("Claim on job {} is no longer current", id))
+            }
+            job_service::JobError::DatabaseError(err) => ApiError::DatabaseError(err),
+            job_service::JobError::ValidationError(msg) => ApiError::BadRequest(msg),
+            job_service::JobError::InvalidFields(fields) => ApiError::InvalidFields(fields),
+        })?;
+
+    Ok(Json(response).into_response())
+}
+
+/// POST /job/{id}/lease
+/// Renew the lease a runner holds on a running job
+pub async fn renew_job_lease(
+    State(pool): State<PgPool>,
+    identity: RunnerIdentity,
+    Path(id): Path<Uuid>,
+    Json(req): Json<RenewLeaseRequest>,
+) -> ApiResult<Json<JobLease>> {
+    tracing::debug!("Runner {} renewing lease on job: {}", req.runner_id, id);
+
+    identity.ensure_runner(&req.runner_id)?;
+
+    let lease = job_service::renew_job_lease(&pool, id, req)
+        .await
+        .map_err(|e| match e {
+            job_service::JobError::NotFound(id) => {
+                ApiError::NotFound(format!("Job {} not found", id))
+            }
+            job_service::JobError::ValidationError(msg) => ApiError::BadRequest(msg),
+            job_service::JobError::InvalidFields(fields) => ApiError::InvalidFields(fields),
+            job_service::JobError::InvalidState(msg) => ApiError::BadRequest(msg),
+            job_service::JobError::StaleClaim(id) => {
+                ApiError::Conflict(format!("Claim on job {} is no longer current", id))
+            }
+            job_service::JobError::DatabaseError(err) => ApiError::DatabaseError(err),
+            job_service::JobError::PipelineNotFound(id) => {
+                ApiError::NotFound(format!("Pipeline {} not found", id))
+            }
+        })?;
+
+    Ok(Json(lease))
+}
+
 /// POST /job/{id}/complete
 /// Mark a job as complete with final status and result
 pub async fn complete_job(
     State(pool): State<PgPool>,
+    State(events): State<EventBus>,
+    identity: RunnerIdentity,
     Path(id): Path<Uuid>,
     Json(req): Json<CompleteJobRequest>,
 ) -> ApiResult<StatusCode> {
     tracing::info!("Completing job: {} with status {:?}", id, req.status);
 
-    job_service::complete_job(&pool, id, req.status, req.result)
+    identity.ensure_job(&pool, id).await?;
+
+    let job = job_service::complete_job(&pool, id, req.status, req.result, req.claim_token)
         .await
         .map_err(|e| match e {
             job_service::JobError::NotFound(id) => {
                 ApiError::NotFound(format!("Job {} not found", id))
             }
             job_service::JobError::ValidationError(msg) => ApiError::BadRequest(msg),
+            job_service::JobError::InvalidFields(fields) => ApiError::InvalidFields(fields),
             job_service::JobError::InvalidState(msg) => ApiError::BadRequest(msg),
+            job_service::JobError::StaleClaim(id) => {
+                ApiError::Conflict(format!("Claim on job {} is no longer current", id))
+            }
             job_service::JobError::DatabaseError(err) => ApiError::DatabaseError(err),
             job_service::JobError::PipelineNotFound(id) => {
                 ApiError::NotFound(format!("Pipeline {} not found", id))
             }
         })?;
 
+    events.publish(job_service::completion_event(&job));
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+// =============================================================================
+// Stage Endpoints
+// =============================================================================
+
+/// GET /jobs/{id}/stages
+/// Get the stages of the current attempt of a job, in the order they started
+pub async fn get_job_stages(
+    State(pool): State<PgPool>,
+    scope: ProjectScope,
+    Path(IdPath { id }): Path<IdPath>,
+) -> ApiResult<Json<Vec<StageRun>>> {
+    tracing::debug!("Getting stages for job: {}", id);
+
+    scope.ensure_job(&pool, id).await?;
+
+    let stages = job_service::list_stages(&pool, id)
+        .await
+        .map_err(|e| match e {
+            job_service::JobError::NotFound(id) => {
+                ApiError::NotFound(format!("Job {} not found", id))
+            }
+            job_service::JobError::PipelineNotFound(id) => {
+                ApiError::NotFound(format!("Pipeline {} not found", id))
+            }
+            job_service::JobError::InvalidState(msg) => ApiError::BadRequest(msg),
+            job_service::JobError::StaleClaim(id) => {
+                ApiError::Conflict(format!("Claim on job {} is no longer current", id))
+            }
+            job_service::JobError::DatabaseError(err) => ApiError::DatabaseError(err),
+            job_service::JobError::ValidationError(msg) => ApiError::BadRequest(msg),
+            job_service::JobError::InvalidFields(fields) => ApiError::InvalidFields(fields),
+        })?;
+
+    Ok(Json(stages))
+}
+
+/// POST /jobs/{id}/stages
+/// Record stage progress reported by the runner, in order
+pub async fn add_job_stages(
+    State(pool): State<PgPool>,
+    identity: RunnerIdentity,
+    Path(id): Path<Uuid>,
+    Json(updates): Json<Vec<StageUpdate>>,
+) -> ApiResult<StatusCode> {
+    tracing::debug!("Recording {} stage updates for job: {}", updates.len(), id);
+
+    identity.ensure_job(&pool, id).await?;
+
+    for update in updates {
+        job_service::record_stage(&pool, id, update)
+            .await
+            .map_err(|e| match e {
+                job_service::JobError::NotFound(id) => {
+                    ApiError::NotFound(format!("Job {} not found", id))
+                }
+                job_service::JobError::PipelineNotFound(id) => {
+                    ApiError::NotFound(format!("Pipeline {} not found", id))
+                }
+                job_service::JobError::InvalidState(msg) => ApiError::BadRequest(msg),
+                job_service::JobError::StaleClaim(id) => {
+                    ApiError::Conflict(format!("Claim on job {} is no longer current", id))
+                }
+                job_service::JobError::DatabaseError(err) => ApiError::DatabaseError(err),
+                job_service::JobError::ValidationError(msg) => ApiError::BadRequest(msg),
+                job_service::JobError::InvalidFields(fields) => ApiError::InvalidFields(fields),
+            })?;
+    }
+
     Ok(StatusCode::NO_CONTENT)
 }
 
@@ -197,12 +653,19 @@ pub async fn complete_job(
 // =============================================================================
 
 /// GET /job/{id}/logs
-/// Get all logs for a job
+/// Get the logs of a job, optionally only those after a sequence number
+/// (`?after=`), the last few entries (`?tail=`) or those of a level and
+/// above (`?level=`)
 pub async fn get_job_logs(
     State(pool): State<PgPool>,
-    Path(id): Path<Uuid>,
+    State(archive): State<LogArchive>,
+    scope: ProjectScope,
+    Path(IdPath { id }): Path<IdPath>,
+    Query(query): Query<LogQuery>,
 ) -> ApiResult<Json<Vec<LogEntry>>> {
-    tracing::debug!("Getting logs for job: {}", id);
+    tracing::debug!("Getting logs for job: {} ({:?})", id, query);
+
+    scope.ensure_job(&pool, id).await?;
 
     // Verify job exists first
     job_service::get_job(&pool, id).await.map_err(|e| match e {
@@ -211,29 +674,100 @@ pub async fn get_job_logs(
         _ => ApiError::InternalError("Failed to verify job".to_string()),
     })?;
 
-    let logs = log_service::get_job_logs(&pool, id)
+    let logs = if query.is_filtered() {
+        log_service::query_job_logs(&pool, &archive, id, &query).await
+    } else {
+        log_service::get_job_logs(&pool, &archive, id).await
+    };
+
+    let logs = logs.map_err(|e| match e {
+        log_service::LogError::DatabaseError(err) => ApiError::DatabaseError(err),
+        log_service::LogError::JobNotFound(id) => {
+            ApiError::NotFound(format!("Job {} not found", id))
+        }
+        log_service::LogError::StaleClaim(id) => {
+            ApiError::Conflict(format!("Claim on job {} is no longer current", id))
+        }
+        log_service::LogError::ValidationError(msg) => ApiError::BadRequest(msg),
+        log_service::LogError::ArchiveError(err) => ApiError::InternalError(err.to_string()),
+    })?;
+
+    Ok(Json(logs))
+}
+
+/// GET /jobs/{id}/logs/search
+/// Search the logs of a job (`?q=`), oldest first, optionally only entries
+/// of a level and above (`?level=`)
+pub async fn search_job_logs(
+    State(pool): State<PgPool>,
+    State(archive): State<LogArchive>,
+    scope: ProjectScope,
+    Path(IdPath { id }): Path<IdPath>,
+    Query(query): Query<LogSearchQuery>,
+) -> ApiResult<Json<Vec<LogEntry>>> {
+    tracing::debug!("Searching logs of job: {} ({:?})", id, query);
+
+    scope.ensure_job(&pool, id).await?;
+
+    job_service::get_job(&pool, id).await.map_err(|e| match e {
+        job_service::JobError::NotFound(id) => ApiError::NotFound(format!("Job {} not found", id)),
+        job_service::JobError::DatabaseError(err) => ApiError::DatabaseError(err),
+        _ => ApiError::InternalError("Failed to verify job".to_string()),
+    })?;
+
+    let logs = log_service::search_job_logs(&pool, &archive, id, &query)
         .await
-        .map_err(|e| match e {
-            log_service::LogError::DatabaseError(err) => ApiError::DatabaseError(err),
-            log_service::LogError::JobNotFound(id) => {
-                ApiError::NotFound(format!("Job {} not found", id))
-            }
-            log_service::LogError::ValidationError(msg) => ApiError::BadRequest(msg),
-        })?;
+        .map_err(map_log_error)?;
 
     Ok(Json(logs))
 }
 
+/// GET /logs/search
+/// Search the logs of the project's jobs (`?q=`, see
+/// `ProjectScope::listed_project_id`), most recent first, optionally only
+/// those of a pipeline (`?pipeline_id=`) or of a level and above (`?level=`)
+pub async fn search_logs(
+    State(pool): State<PgPool>,
+    scope: ProjectScope,
+    Query(query): Query<LogSearchQuery>,
+) -> ApiResult<Json<Vec<LogSearchHit>>> {
+    tracing::debug!("Searching logs ({:?})", query);
+
+    let hits = log_service::search_logs(&pool, scope.listed_project_id(), &query)
+        .await
+        .map_err(map_log_error)?;
+
+    Ok(Json(hits))
+}
+
+fn map_log_error(e: log_service::LogError) -> ApiError {
+    match e {
+        log_service::LogError::DatabaseError(err) => ApiError::DatabaseError(err),
+        log_service::LogError::JobNotFound(id) => {
+            ApiError::NotFound(format!("Job {} not found", id))
+        }
+        log_service::LogError::StaleClaim(id) => {
+            ApiError::Conflict(format!("Claim on job {} is no longer current", id))
+        }
+        log_service::LogError::ValidationError(msg) => ApiError::BadRequest(msg),
+        log_service::LogError::ArchiveError(err) => ApiError::InternalError(err.to_string()),
+    }
+}
+
 /// POST /job/{id}/logs
 /// Add log entries to a job
 pub async fn add_job_logs(
     State(pool): State<PgPool>,
+    identity: RunnerIdentity,
     Path(id): Path<Uuid>,
+    Query(query): Query<AddLogsQuery>,
     Json(logs): Json<Vec<LogEntry>>,
 ) -> ApiResult<StatusCode> {
     tracing::debug!("Adding {} log entries for job: {}", logs.len(), id);
 
-    log_service::add_log_entries(&pool, id, logs)
+    identity.ensure_job(&pool, id).await?;
+
+    log_service::add_log_entries(&pool, id, logs, query.claim_token)
         .await
         .map_err(|e| match e {
             log_service::LogError::ValidationError(msg) => ApiError::BadRequest(msg),
@@ -241,6 +775,10 @@ pub async fn add_job_logs(
             log_service::LogError::JobNotFound(id) => {
                 ApiError::NotFound(format!("Job {} not found", id))
             }
+            log_service::LogError::StaleClaim(id) => {
+                ApiError::Conflict(format!("Claim on job {} is no longer current", id))
+            }
+            log_service::LogError::ArchiveError(err) => ApiError::InternalError(err.to_string()),
         })?;
 
     Ok(StatusCode::CREATED)
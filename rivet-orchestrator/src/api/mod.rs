@@ -3,56 +3,456 @@
 //! HTTP API layer for the orchestrator.
 //! Each submodule handles endpoints for a specific domain.
 
+pub mod artifact;
+pub mod auth;
+pub mod badge;
+pub mod blackout;
+pub mod cors;
+pub mod debug;
+pub mod environment;
 pub mod error;
+pub mod etag;
+pub mod events;
+pub mod graphql;
 pub mod health;
 pub mod job;
 pub mod pipeline;
+pub mod preset;
+pub mod project;
+pub mod rate_limit;
+pub mod request_context;
 pub mod runner;
+pub mod schedule;
+pub mod secret;
 pub mod stubs;
+pub mod version;
+pub mod webhook;
 
 use axum::{
     Router,
+    extract::{DefaultBodyLimit, FromRef},
+    middleware,
     routing::{delete, get, post},
 };
+use rivet_core::dto::log::MAX_LOG_REQUEST_BYTES;
+use rivet_core::dto::pipeline::MAX_PIPELINE_SCRIPT_BYTES;
+use rivet_core::dto::version::API_PREFIX;
 use sqlx::PgPool;
 use tower_http::trace::TraceLayer;
 
-/// Create the main API router with all endpoints
-pub fn create_router(pool: PgPool) -> Router {
+use crate::api::auth::AuthConfig;
+use crate::api::rate_limit::RateLimiter;
+use crate::api::version::LEGACY_API_PREFIX;
+use crate::debug::DebugRelay;
+use crate::events::EventBus;
+use crate::graphql::RivetSchema;
+use crate::leader::Leadership;
+use crate::log_archive::LogArchive;
+use crate::scheduling::SchedulingPolicy;
+use crate::workers::WorkerMonitor;
+
+/// Largest request body accepted by routes without a limit of their own
+///
+/// Larger bodies are rejected with `413 Payload Too Large`.
+pub const MAX_REQUEST_BYTES: usize = 256 * 1024;
+
+/// Largest pipeline creation request body, leaving room for the JSON
+/// escaping of a script of `MAX_PIPELINE_SCRIPT_BYTES`
+const MAX_PIPELINE_REQUEST_BYTES: usize = 2 * MAX_PIPELINE_SCRIPT_BYTES;
+
+/// Shared state available to every handler
+///
+/// Handlers extract only what they need (`State<PgPool>`, `State<EventBus>`, ...).
+#[derive(Clone)]
+pub struct AppState {
+    pub pool: PgPool,
+    pub events: EventBus,
+    pub debug: DebugRelay,
+    pub schema: RivetSchema,
+    pub auth: AuthConfig,
+    pub scheduling: SchedulingPolicy,
+    pub rate_limiter: RateLimiter,
+    pub workers: WorkerMonitor,
+    pub leadership: Leadership,
+    pub log_archive: LogArchive,
+}
+
+impl FromRef<AppState> for PgPool {
+    fn from_ref(state: &AppState) -> Self {
+        state.pool.clone()
+    }
+}
+
+impl FromRef<AppState> for EventBus {
+    fn from_ref(state: &AppState) -> Self {
+        state.events.clone()
+    }
+}
+
+impl FromRef<AppState> for DebugRelay {
+    fn from_ref(state: &AppState) -> Self {
+        state.debug.clone()
+    }
+}
+
+impl FromRef<AppState> for AuthConfig {
+    fn from_ref(state: &AppState) -> Self {
+        state.auth.clone()
+    }
+}
+
+impl FromRef<AppState> for SchedulingPolicy {
+    fn from_ref(state: &AppState) -> Self {
+        state.scheduling
+    }
+}
+
+impl FromRef<AppState> for WorkerMonitor {
+    fn from_ref(state: &AppState) -> Self {
+        state.workers.clone()
+    }
+}
+
+impl FromRef<AppState> for Leadership {
+    fn from_ref(state: &AppState) -> Self {
+        state.leadership.clone()
+    }
+}
+
+impl FromRef<AppState> for LogArchive {
+    fn from_ref(state: &AppState) -> Self {
+        state.log_archive.clone()
+    }
+}
+
+impl FromRef<AppState> for RivetSchema {
+    fn from_ref(state: &AppState) -> Self {
+        state.schema.clone()
+    }
+}
+
+/// User-facing routes scoped to a project, nested under `/api/v1/projects/{project}`
+///
+/// The same handlers serve the unscoped `/api/v1/...` routes, where they act
+/// across all projects. Runner-facing routes are never project-scoped.
+fn project_routes() -> Router<AppState> {
+    Router::new()
+        .route(
+            "/pipeline/create",
+            post(pipeline::create_pipeline)
+                .layer(DefaultBodyLimit::max(MAX_PIPELINE_REQUEST_BYTES)),
+        )
+        .route("/pipeline/launch", post(job::launch_job))
+        .route("/pipeline/list", get(pipeline::list_pipelines))
+        .route(
+            "/pipeline/{id}",
+            get(pipeline::get_pipeline).delete(pipeline::delete_pipeline),
+        )
+        .route(
+            "/pipeline/{id}/unarchive",
+            post(pipeline::unarchive_pipeline),
+        )
+        .route("/pipeline/{id}/disable", post(pipeline::disable_pipeline))
+        .route("/pipeline/{id}/enable", post(pipeline::enable_pipeline))
+        .route("/pipeline/{id}/badge.svg", get(badge::pipeline_badge))
+        .route("/pipeline/{id}/launch-batch", post(job::launch_job_batch))
+        .route(
+            "/pipeline/{id}/definition",
+            get(pipeline::get_pipeline_definition),
+        )
+        .route("/pipeline/{id}/graph", get(pipeline::get_pipeline_graph))
+        .route(
+            "/pipeline/{id}/defaults",
+            get(pipeline::get_pipeline_defaults).put(pipeline::set_pipeline_defaults),
+        )
+        .route(
+            "/pipeline/{id}/artifact-retention",
+            get(pipeline::get_artifact_retention).put(pipeline::set_artifact_retention),
+        )
+        .route(
+            "/pipeline/{id}/commit-status",
+            get(pipeline::get_commit_status)
+                .put(pipeline::set_commit_status)
+                .delete(pipeline::delete_commit_status),
+        )
+        .route("/pipeline/{id}/stats", get(pipeline::get_pipeline_stats))
+        .route(
+            "/pipeline/{id}/flaky-stages",
+            get(pipeline::get_flaky_stages),
+        )
+        .route(
+            "/pipeline/{id}/webhooks",
+            get(webhook::list_webhook_triggers).post(webhook::create_webhook_trigger),
+        )
+        .route(
+            "/pipeline/{id}/webhooks/{trigger_id}",
+            delete(webhook::delete_webhook_trigger),
+        )
+        .route(
+            "/pipeline/{id}/schedules",
+            get(schedule::list_schedules).post(schedule::create_schedule),
+        )
+        .route(
+            "/pipeline/{id}/schedules/{schedule_id}",
+            delete(schedule::delete_schedule),
+        )
+        .route("/pipeline/{id}/presets", get(preset::list_presets))
+        .route(
+            "/pipeline/{id}/presets/{name}",
+            get(preset::get_preset)
+                .put(preset::save_preset)
+                .delete(preset::delete_preset),
+        )
+        .route("/jobs", get(job::list_all_jobs))
+        .route("/jobs/deadletter", get(job::list_dead_lettered_jobs))
+        .route("/jobs/{id}", get(job::get_job))
+        .route("/jobs/{id}/requeue", post(job::requeue_job))
+        .route("/jobs/{id}/resume", post(job::resume_job))
+        .route("/jobs/{id}/stages", get(job::get_job_stages))
+        .route("/jobs/{id}/logs", get(job::get_job_logs))
+        .route("/jobs/{id}/logs/search", get(job::search_job_logs))
+        .route("/logs/search", get(job::search_logs))
+        .route("/jobs/{id}/artifacts", get(artifact::list_artifacts))
+        .route("/jobs/{id}/debug", get(debug::debug_job))
+        .route(
+            "/jobs/{id}/approvals",
+            get(environment::get_job_approvals).post(environment::approve_job),
+        )
+        .route(
+            "/jobs/{id}/artifacts/{name}",
+            get(artifact::download_artifact),
+        )
+        .route("/jobs/pipeline/{id}", get(job::list_jobs_by_pipeline))
+        .route(
+            "/secrets",
+            get(secret::list_secrets).put(secret::set_secret),
+        )
+        .route("/secrets/{name}", delete(secret::delete_secret))
+        .route("/environments", get(environment::list_environments))
+        .route(
+            "/environments/{name}",
+            get(environment::get_environment)
+                .put(environment::set_environment)
+                .delete(environment::delete_environment),
+        )
+        .route(
+            "/environments/{name}/deployments",
+            get(environment::list_deployments),
+        )
+        .route(
+            "/environments/{name}/lock",
+            get(environment::get_environment_lock).delete(environment::release_environment_lock),
+        )
+        .route(
+            "/blackouts",
+            get(blackout::list_blackouts).post(blackout::create_blackout),
+        )
+        .route("/blackouts/{id}", delete(blackout::delete_blackout))
+}
+
+/// Every API route, below the `/api/v1` (or legacy `/api`) prefix
+fn api_routes() -> Router<AppState> {
     Router::new()
         // Health check
-        .route("/api/health", get(health::health_check))
+        .route("/health", get(health::health_check))
         // Runner endpoints
-        .route("/api/runners/register", post(runner::register_runner))
+        .route("/runners/register", post(runner::register_runner))
+        .route("/runners/{id}/heartbeat", post(runner::runner_heartbeat))
+        .route(
+            "/runners/tokens",
+            get(runner::list_registration_tokens).post(runner::create_registration_token),
+        )
         .route(
-            "/api/runners/{id}/heartbeat",
-            post(runner::runner_heartbeat),
+            "/runners/tokens/{id}",
+            delete(runner::delete_registration_token),
         )
-        .route("/api/runners", get(runner::list_runners))
-        .route("/api/runners/{id}", get(runner::get_runner))
-        .route("/api/runners/{id}", delete(runner::delete_runner))
+        .route("/runners", get(runner::list_runners))
+        .route("/runners/{id}", get(runner::get_runner))
+        .route("/runners/{id}", delete(runner::delete_runner))
         // Pipeline endpoints
-        .route("/api/pipeline/create", post(pipeline::create_pipeline))
-        .route("/api/pipeline/launch", post(job::launch_job))
-        .route("/api/pipeline/list", get(pipeline::list_pipelines))
-        .route("/api/pipeline/{id}", get(pipeline::get_pipeline))
-        .route("/api/pipeline/{id}", delete(pipeline::delete_pipeline))
+        .route(
+            "/pipeline/create",
+            post(pipeline::create_pipeline)
+                .layer(DefaultBodyLimit::max(MAX_PIPELINE_REQUEST_BYTES)),
+        )
+        .route("/pipeline/launch", post(job::launch_job))
+        .route("/pipeline/list", get(pipeline::list_pipelines))
+        .route("/pipeline/{id}", get(pipeline::get_pipeline))
+        .route("/pipeline/{id}", delete(pipeline::delete_pipeline))
+        .route(
+            "/pipeline/{id}/unarchive",
+            post(pipeline::unarchive_pipeline),
+        )
+        .route("/pipeline/{id}/disable", post(pipeline::disable_pipeline))
+        .route("/pipeline/{id}/enable", post(pipeline::enable_pipeline))
+        .route("/pipeline/{id}/badge.svg", get(badge::pipeline_badge))
+        .route("/pipeline/{id}/launch-batch", post(job::launch_job_batch))
+        .route(
+            "/pipeline/{id}/definition",
+            get(pipeline::get_pipeline_definition),
+        )
+        .route("/pipeline/{id}/graph", get(pipeline::get_pipeline_graph))
+        .route(
+            "/pipeline/{id}/defaults",
+            get(pipeline::get_pipeline_defaults).put(pipeline::set_pipeline_defaults),
+        )
+        .route(
+            "/pipeline/{id}/artifact-retention",
+            get(pipeline::get_artifact_retention).put(pipeline::set_artifact_retention),
+        )
+        .route(
+            "/pipeline/{id}/commit-status",
+            get(pipeline::get_commit_status)
+                .put(pipeline::set_commit_status)
+                .delete(pipeline::delete_commit_status),
+        )
+        .route("/pipeline/{id}/stats", get(pipeline::get_pipeline_stats))
+        .route(
+            "/pipeline/{id}/flaky-stages",
+            get(pipeline::get_flaky_stages),
+        )
+        .route(
+            "/pipeline/{id}/webhooks",
+            get(webhook::list_webhook_triggers).post(webhook::create_webhook_trigger),
+        )
+        .route(
+            "/pipeline/{id}/webhooks/{trigger_id}",
+            delete(webhook::delete_webhook_trigger),
+        )
+        .route(
+            "/pipeline/{id}/schedules",
+            get(schedule::list_schedules).post(schedule::create_schedule),
+        )
+        .route(
+            "/pipeline/{id}/schedules/{schedule_id}",
+            delete(schedule::delete_schedule),
+        )
+        .route("/pipeline/{id}/presets", get(preset::list_presets))
+        .route(
+            "/pipeline/{id}/presets/{name}",
+            get(preset::get_preset)
+                .put(preset::save_preset)
+                .delete(preset::delete_preset),
+        )
         // Job endpoints
-        .route("/api/jobs", get(job::list_all_jobs))
-        .route("/api/jobs/scheduled", get(job::list_scheduled_jobs))
-        .route("/api/jobs/execute/{id}", post(job::execute_job))
-        .route("/api/jobs/{id}", get(job::get_job))
-        .route("/api/jobs/{id}/complete", post(job::complete_job))
-        .route("/api/jobs/{id}/logs", get(job::get_job_logs))
-        .route("/api/jobs/{id}/logs", post(job::add_job_logs))
+        .route("/jobs", get(job::list_all_jobs))
+        .route("/jobs/scheduled", get(job::list_scheduled_jobs))
+        .route("/jobs/next", get(job::next_job))
+        .route("/jobs/deadletter", get(job::list_dead_lettered_jobs))
+        .route("/jobs/execute/{id}", post(job::execute_job))
+        .route("/jobs/{id}", get(job::get_job))
+        .route("/jobs/{id}/complete", post(job::complete_job))
+        .route("/jobs/{id}/lease", post(job::renew_job_lease))
+        .route("/jobs/{id}/requeue", post(job::requeue_job))
+        .route("/jobs/{id}/resume", post(job::resume_job))
+        .route(
+            "/jobs/{id}/stages",
+            get(job::get_job_stages).post(job::add_job_stages),
+        )
+        .route(
+            "/jobs/{id}/logs",
+            get(job::get_job_logs)
+                .post(job::add_job_logs)
+                .layer(DefaultBodyLimit::max(MAX_LOG_REQUEST_BYTES)),
+        )
+        .route("/jobs/{id}/logs/search", get(job::search_job_logs))
+        .route("/logs/search", get(job::search_logs))
+        .route("/jobs/{id}/artifacts", get(artifact::list_artifacts))
+        .route("/jobs/{id}/debug", get(debug::debug_job))
+        .route(
+            "/jobs/{id}/approvals",
+            get(environment::get_job_approvals).post(environment::approve_job),
+        )
+        .route(
+            "/jobs/{id}/artifacts/{name}",
+            get(artifact::download_artifact)
+                .post(artifact::upload_artifact)
+                .layer(DefaultBodyLimit::max(
+                    crate::service::artifact_service::MAX_ARTIFACT_SIZE,
+                )),
+        )
+        .route("/jobs/pipeline/{id}", get(job::list_jobs_by_pipeline))
+        // Artifact storage endpoints
+        .route("/artifacts/usage", get(artifact::artifact_usage))
+        .route(
+            "/artifacts/gc",
+            get(artifact::plan_artifact_gc).post(artifact::run_artifact_gc),
+        )
+        // Webhook deliveries (verified against the trigger's secret)
+        .route("/webhooks/{id}", post(webhook::receive_webhook))
+        // Secret endpoints
+        .route(
+            "/secrets",
+            get(secret::list_secrets).put(secret::set_secret),
+        )
+        .route("/secrets/{name}", delete(secret::delete_secret))
+        .route("/environments", get(environment::list_environments))
+        .route(
+            "/environments/{name}",
+            get(environment::get_environment)
+                .put(environment::set_environment)
+                .delete(environment::delete_environment),
+        )
+        .route(
+            "/environments/{name}/deployments",
+            get(environment::list_deployments),
+        )
+        .route(
+            "/environments/{name}/lock",
+            get(environment::get_environment_lock).delete(environment::release_environment_lock),
+        )
+        .route(
+            "/blackouts",
+            get(blackout::list_blackouts).post(blackout::create_blackout),
+        )
+        .route("/blackouts/{id}", delete(blackout::delete_blackout))
+        // Project endpoints
         .route(
-            "/api/jobs/pipeline/{pipeline_id}",
-            get(job::list_jobs_by_pipeline),
+            "/projects",
+            get(project::list_projects).post(project::create_project),
+        )
+        .route(
+            "/projects/{project}",
+            get(project::get_project).delete(project::delete_project),
+        )
+        .nest("/projects/{project}", project_routes())
+        // Event stream
+        .route("/events/ws", get(events::events_ws))
+        // Debug sessions (runner side)
+        .route("/debug/next", get(debug::next_debug_request))
+        .route("/debug/{id}/ws", get(debug::attach_debug_session))
+        // GraphQL
+        .route(
+            "/graphql",
+            get(graphql::graphql_playground).post(graphql::graphql_query),
         )
         // Stubs endpoints
-        .route("/api/stubs", get(stubs::list_stubs))
-        .route("/api/stubs/{name}", get(stubs::get_stub))
+        .route("/stubs", get(stubs::list_stubs))
+        .route("/stubs/{name}", get(stubs::get_stub))
+}
+
+/// Create the main API router with all endpoints
+///
+/// Routes are served under `/api/v1`, and under the unversioned `/api`
+/// prefix for clients that predate versioning. See [`version`].
+pub fn create_router(state: AppState) -> Router {
+    Router::new()
+        // Probes, outside the versioned API
+        .route("/healthz", get(health::liveness))
+        .route("/readyz", get(health::readiness))
+        .nest(API_PREFIX, api_routes())
+        .nest(LEGACY_API_PREFIX, api_routes())
         // Add state and middleware
-        .with_state(pool)
-        .layer(TraceLayer::new_for_http())
+        .layer(DefaultBodyLimit::max(MAX_REQUEST_BYTES))
+        .layer(middleware::from_fn_with_state(
+            state.clone(),
+            rate_limit::limit_requests,
+        ))
+        .with_state(state)
+        .layer(middleware::from_fn(version::negotiate_version))
+        .layer(middleware::from_fn(error::plain_errors_as_problems))
+        .layer(TraceLayer::new_for_http().make_span_with(request_context::make_request_span))
+        .layer(middleware::from_fn(
+            request_context::propagate_request_context,
+        ))
 }
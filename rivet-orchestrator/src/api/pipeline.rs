@@ -4,65 +4,188 @@
 
 use axum::{
     Json,
-    extract::{Path, State},
+    extract::{Path, Query, State},
     http::StatusCode,
+    response::{IntoResponse, Response},
 };
 use rivet_core::domain::pipeline::Pipeline;
-use rivet_core::dto::pipeline::CreatePipeline;
+use rivet_core::dto::artifact::ArtifactRetention;
+use rivet_core::dto::commit_status::CommitStatusConfig;
+use rivet_core::dto::event::Event;
+use rivet_core::dto::pipeline::{
+    CreatePipeline, DeletePipelineQuery, FlakyStage, FlakyStagesQuery, PipelineDefaults,
+    PipelineDefinitionInfo, PipelineGraph, PipelineListQuery, PipelineStats, PipelineStatsQuery,
+};
 use sqlx::PgPool;
 use uuid::Uuid;
 
+use crate::api::auth::AdminAuth;
 use crate::api::error::{ApiError, ApiResult};
+use crate::api::etag::IfNoneMatch;
+use crate::api::project::{IdPath, ProjectScope};
+use crate::events::EventBus;
 use crate::service::pipeline_service;
 
 /// POST /pipeline/create
 /// Create a new pipeline
 pub async fn create_pipeline(
     State(pool): State<PgPool>,
+    State(events): State<EventBus>,
+    scope: ProjectScope,
     Json(req): Json<CreatePipeline>,
 ) -> ApiResult<Json<Pipeline>> {
     tracing::info!("Creating pipeline from script");
 
-    let pipeline = pipeline_service::create_pipeline(&pool, req)
+    let pipeline = pipeline_service::create_pipeline(&pool, scope.id_or_default(), req)
         .await
         .map_err(|e| match e {
             pipeline_service::PipelineError::ValidationError(msg) => ApiError::BadRequest(msg),
+            pipeline_service::PipelineError::InvalidFields(fields) => {
+                ApiError::InvalidFields(fields)
+            }
             pipeline_service::PipelineError::DatabaseError(err) => ApiError::DatabaseError(err),
             pipeline_service::PipelineError::NotFound(id) => {
                 ApiError::NotFound(format!("Pipeline {} not found", id))
             }
         })?;
 
+    events.publish(Event::PipelineCreated {
+        pipeline_id: pipeline.id,
+        name: pipeline.name.clone(),
+    });
+
     Ok(Json(pipeline))
 }
 
 /// GET /pipeline/list
-/// List all pipelines
-pub async fn list_pipelines(State(pool): State<PgPool>) -> ApiResult<Json<Vec<Pipeline>>> {
+/// List the active pipelines of the project (see
+/// `ProjectScope::listed_project_id`), or with `?archived=true` the archived
+/// ones
+pub async fn list_pipelines(
+    State(pool): State<PgPool>,
+    scope: ProjectScope,
+    if_none_match: IfNoneMatch,
+    Query(query): Query<PipelineListQuery>,
+) -> ApiResult<Response> {
     tracing::debug!("Listing all pipelines");
 
-    let pipelines = pipeline_service::list_pipelines(&pool)
+    let pipelines = match scope.listed_project_id() {
+        Some(project_id) => {
+            pipeline_service::list_pipelines_by_project(&pool, project_id, query.archived).await
+        }
+        None => pipeline_service::list_pipelines(&pool, query.archived).await,
+    };
+
+    let pipelines = pipelines.map_err(|e| match e {
+        pipeline_service::PipelineError::DatabaseError(err) => ApiError::DatabaseError(err),
+        pipeline_service::PipelineError::NotFound(id) => {
+            ApiError::NotFound(format!("Pipeline {} not found", id))
+        }
+        pipeline_service::PipelineError::ValidationError(msg) => ApiError::BadRequest(msg),
+        pipeline_service::PipelineError::InvalidFields(fields) => ApiError::InvalidFields(fields),
+    })?;
+
+    Ok(if_none_match.respond(&pipelines))
+}
+
+/// GET /pipeline/{id}
+/// Get pipeline by ID
+pub async fn get_pipeline(
+    State(pool): State<PgPool>,
+    scope: ProjectScope,
+    if_none_match: IfNoneMatch,
+    Path(IdPath { id }): Path<IdPath>,
+) -> ApiResult<Response> {
+    tracing::debug!("Getting pipeline: {}", id);
+
+    scope.ensure_pipeline(&pool, id).await?;
+
+    let pipeline = pipeline_service::get_pipeline(&pool, id)
         .await
         .map_err(|e| match e {
+            pipeline_service::PipelineError::NotFound(id) => {
+                ApiError::NotFound(format!("Pipeline {} not found", id))
+            }
             pipeline_service::PipelineError::DatabaseError(err) => ApiError::DatabaseError(err),
+            pipeline_service::PipelineError::ValidationError(msg) => ApiError::BadRequest(msg),
+            pipeline_service::PipelineError::InvalidFields(fields) => {
+                ApiError::InvalidFields(fields)
+            }
+        })?;
+
+    Ok(if_none_match.respond(&pipeline))
+}
+
+/// GET /pipeline/{id}/definition
+/// Get the parsed definition of a pipeline script (inputs, stages, runner
+/// tags, plugins) as JSON
+pub async fn get_pipeline_definition(
+    State(pool): State<PgPool>,
+    scope: ProjectScope,
+    if_none_match: IfNoneMatch,
+    Path(IdPath { id }): Path<IdPath>,
+) -> ApiResult<Response> {
+    tracing::debug!("Getting definition of pipeline: {}", id);
+
+    scope.ensure_pipeline(&pool, id).await?;
+
+    let definition: PipelineDefinitionInfo = pipeline_service::get_definition(&pool, id)
+        .await
+        .map_err(|e| match e {
             pipeline_service::PipelineError::NotFound(id) => {
                 ApiError::NotFound(format!("Pipeline {} not found", id))
             }
+            pipeline_service::PipelineError::DatabaseError(err) => ApiError::DatabaseError(err),
             pipeline_service::PipelineError::ValidationError(msg) => ApiError::BadRequest(msg),
+            pipeline_service::PipelineError::InvalidFields(fields) => {
+                ApiError::InvalidFields(fields)
+            }
         })?;
 
-    Ok(Json(pipelines))
+    Ok(if_none_match.respond(&definition))
 }
 
-/// GET /pipeline/{id}
-/// Get pipeline by ID
-pub async fn get_pipeline(
+/// GET /pipeline/{id}/graph
+/// Get the stage dependency graph of a pipeline as JSON
+pub async fn get_pipeline_graph(
     State(pool): State<PgPool>,
-    Path(id): Path<Uuid>,
-) -> ApiResult<Json<Pipeline>> {
-    tracing::debug!("Getting pipeline: {}", id);
+    scope: ProjectScope,
+    if_none_match: IfNoneMatch,
+    Path(IdPath { id }): Path<IdPath>,
+) -> ApiResult<Response> {
+    tracing::debug!("Getting graph of pipeline: {}", id);
 
-    let pipeline = pipeline_service::get_pipeline(&pool, id)
+    scope.ensure_pipeline(&pool, id).await?;
+
+    let graph: PipelineGraph =
+        pipeline_service::get_graph(&pool, id)
+            .await
+            .map_err(|e| match e {
+                pipeline_service::PipelineError::NotFound(id) => {
+                    ApiError::NotFound(format!("Pipeline {} not found", id))
+                }
+                pipeline_service::PipelineError::DatabaseError(err) => ApiError::DatabaseError(err),
+                pipeline_service::PipelineError::ValidationError(msg) => ApiError::BadRequest(msg),
+                pipeline_service::PipelineError::InvalidFields(fields) => {
+                    ApiError::InvalidFields(fields)
+                }
+            })?;
+
+    Ok(if_none_match.respond(&graph))
+}
+
+/// GET /pipeline/{id}/defaults
+/// Get the input defaults and overrides the orchestrator holds for a pipeline
+pub async fn get_pipeline_defaults(
+    State(pool): State<PgPool>,
+    scope: ProjectScope,
+    Path(IdPath { id }): Path<IdPath>,
+) -> ApiResult<Json<PipelineDefaults>> {
+    tracing::debug!("Getting input defaults of pipeline: {}", id);
+
+    scope.ensure_pipeline(&pool, id).await?;
+
+    let defaults = pipeline_service::get_defaults(&pool, id)
         .await
         .map_err(|e| match e {
             pipeline_service::PipelineError::NotFound(id) => {
@@ -70,20 +193,256 @@ pub async fn get_pipeline(
             }
             pipeline_service::PipelineError::DatabaseError(err) => ApiError::DatabaseError(err),
             pipeline_service::PipelineError::ValidationError(msg) => ApiError::BadRequest(msg),
+            pipeline_service::PipelineError::InvalidFields(fields) => {
+                ApiError::InvalidFields(fields)
+            }
         })?;
 
-    Ok(Json(pipeline))
+    Ok(Json(defaults))
+}
+
+/// PUT /pipeline/{id}/defaults
+/// Replace the input defaults and overrides of a pipeline (admin)
+pub async fn set_pipeline_defaults(
+    State(pool): State<PgPool>,
+    _admin: AdminAuth,
+    scope: ProjectScope,
+    Path(IdPath { id }): Path<IdPath>,
+    Json(defaults): Json<PipelineDefaults>,
+) -> ApiResult<Json<PipelineDefaults>> {
+    tracing::info!("Setting input defaults of pipeline: {}", id);
+
+    scope.ensure_pipeline(&pool, id).await?;
+
+    let defaults = pipeline_service::set_defaults(&pool, id, defaults)
+        .await
+        .map_err(|e| match e {
+            pipeline_service::PipelineError::NotFound(id) => {
+                ApiError::NotFound(format!("Pipeline {} not found", id))
+            }
+            pipeline_service::PipelineError::DatabaseError(err) => ApiError::DatabaseError(err),
+            pipeline_service::PipelineError::ValidationError(msg) => ApiError::BadRequest(msg),
+            pipeline_service::PipelineError::InvalidFields(fields) => {
+                ApiError::InvalidFields(fields)
+            }
+        })?;
+
+    Ok(Json(defaults))
+}
+
+/// GET /pipeline/{id}/artifact-retention
+/// Get the limits past which a pipeline's artifacts are garbage collected
+pub async fn get_artifact_retention(
+    State(pool): State<PgPool>,
+    scope: ProjectScope,
+    Path(IdPath { id }): Path<IdPath>,
+) -> ApiResult<Json<ArtifactRetention>> {
+    tracing::debug!("Getting artifact retention of pipeline: {}", id);
+
+    scope.ensure_pipeline(&pool, id).await?;
+
+    let retention = pipeline_service::get_artifact_retention(&pool, id)
+        .await
+        .map_err(|e| match e {
+            pipeline_service::PipelineError::NotFound(id) => {
+                ApiError::NotFound(format!("Pipeline {} not found", id))
+            }
+            pipeline_service::PipelineError::DatabaseError(err) => ApiError::DatabaseError(err),
+            pipeline_service::PipelineError::ValidationError(msg) => ApiError::BadRequest(msg),
+            pipeline_service::PipelineError::InvalidFields(fields) => {
+                ApiError::InvalidFields(fields)
+            }
+        })?;
+
+    Ok(Json(retention))
+}
+
+/// PUT /pipeline/{id}/artifact-retention
+/// Replace the artifact retention limits of a pipeline (admin)
+pub async fn set_artifact_retention(
+    State(pool): State<PgPool>,
+    _admin: AdminAuth,
+    scope: ProjectScope,
+    Path(IdPath { id }): Path<IdPath>,
+    Json(retention): Json<ArtifactRetention>,
+) -> ApiResult<Json<ArtifactRetention>> {
+    tracing::info!("Setting artifact retention of pipeline: {}", id);
+
+    scope.ensure_pipeline(&pool, id).await?;
+
+    let retention = pipeline_service::set_artifact_retention(&pool, id, retention)
+        .await
+        .map_err(|e| match e {
+            pipeline_service::PipelineError::NotFound(id) => {
+                ApiError::NotFound(format!("Pipeline {} not found", id))
+            }
+            pipeline_service::PipelineError::DatabaseError(err) => ApiError::DatabaseError(err),
+            pipeline_service::PipelineError::ValidationError(msg) => ApiError::BadRequest(msg),
+            pipeline_service::PipelineError::InvalidFields(fields) => {
+                ApiError::InvalidFields(fields)
+            }
+        })?;
+
+    Ok(Json(retention))
+}
+
+/// GET /pipeline/{id}/commit-status
+/// Get how a pipeline reports the status of the commits its jobs build (`null`: it does not)
+pub async fn get_commit_status(
+    State(pool): State<PgPool>,
+    scope: ProjectScope,
+    Path(IdPath { id }): Path<IdPath>,
+) -> ApiResult<Json<Option<CommitStatusConfig>>> {
+    tracing::debug!("Getting commit status reporting of pipeline: {}", id);
+
+    scope.ensure_pipeline(&pool, id).await?;
+
+    let config = pipeline_service::get_commit_status(&pool, id)
+        .await
+        .map_err(|e| match e {
+            pipeline_service::PipelineError::NotFound(id) => {
+                ApiError::NotFound(format!("Pipeline {} not found", id))
+            }
+            pipeline_service::PipelineError::DatabaseError(err) => ApiError::DatabaseError(err),
+            pipeline_service::PipelineError::ValidationError(msg) => ApiError::BadRequest(msg),
+            pipeline_service::PipelineError::InvalidFields(fields) => {
+                ApiError::InvalidFields(fields)
+            }
+        })?;
+
+    Ok(Json(config))
+}
+
+/// PUT /pipeline/{id}/commit-status
+/// Report the status of the commits a pipeline's jobs build (admin)
+pub async fn set_commit_status(
+    State(pool): State<PgPool>,
+    _admin: AdminAuth,
+    scope: ProjectScope,
+    Path(IdPath { id }): Path<IdPath>,
+    Json(config): Json<CommitStatusConfig>,
+) -> ApiResult<Json<Option<CommitStatusConfig>>> {
+    tracing::info!("Setting commit status reporting of pipeline: {}", id);
+
+    scope.ensure_pipeline(&pool, id).await?;
+
+    let config = pipeline_service::set_commit_status(&pool, id, Some(config))
+        .await
+        .map_err(|e| match e {
+            pipeline_service::PipelineError::NotFound(id) => {
+                ApiError::NotFound(format!("Pipeline {} not found", id))
+            }
+            pipeline_service::PipelineError::DatabaseError(err) => ApiError::DatabaseError(err),
+            pipeline_service::PipelineError::ValidationError(msg) => ApiError::BadRequest(msg),
+            pipeline_service::PipelineError::InvalidFields(fields) => {
+                ApiError::InvalidFields(fields)
+            }
+        })?;
+
+    Ok(Json(config))
+}
+
+/// DELETE /pipeline/{id}/commit-status
+/// Stop reporting commit statuses for a pipeline (admin)
+pub async fn delete_commit_status(
+    State(pool): State<PgPool>,
+    _admin: AdminAuth,
+    scope: ProjectScope,
+    Path(IdPath { id }): Path<IdPath>,
+) -> ApiResult<StatusCode> {
+    tracing::info!("Removing commit status reporting of pipeline: {}", id);
+
+    scope.ensure_pipeline(&pool, id).await?;
+
+    pipeline_service::set_commit_status(&pool, id, None)
+        .await
+        .map_err(|e| match e {
+            pipeline_service::PipelineError::NotFound(id) => {
+                ApiError::NotFound(format!("Pipeline {} not found", id))
+            }
+            pipeline_service::PipelineError::DatabaseError(err) => ApiError::DatabaseError(err),
+            pipeline_service::PipelineError::ValidationError(msg) => ApiError::BadRequest(msg),
+            pipeline_service::PipelineError::InvalidFields(fields) => {
+                ApiError::InvalidFields(fields)
+            }
+        })?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// GET /pipeline/{id}/stats
+/// Get the success rate, durations, failing stages and trend of a pipeline's recent jobs
+pub async fn get_pipeline_stats(
+    State(pool): State<PgPool>,
+    scope: ProjectScope,
+    Path(IdPath { id }): Path<IdPath>,
+    Query(query): Query<PipelineStatsQuery>,
+) -> ApiResult<Json<PipelineStats>> {
+    tracing::debug!("Getting stats of pipeline: {}", id);
+
+    scope.ensure_pipeline(&pool, id).await?;
+
+    let stats = pipeline_service::get_stats(&pool, id, query)
+        .await
+        .map_err(|e| match e {
+            pipeline_service::PipelineError::NotFound(id) => {
+                ApiError::NotFound(format!("Pipeline {} not found", id))
+            }
+            pipeline_service::PipelineError::DatabaseError(err) => ApiError::DatabaseError(err),
+            pipeline_service::PipelineError::ValidationError(msg) => ApiError::BadRequest(msg),
+            pipeline_service::PipelineError::InvalidFields(fields) => {
+                ApiError::InvalidFields(fields)
+            }
+        })?;
+
+    Ok(Json(stats))
+}
+
+/// GET /pipeline/{id}/flaky-stages
+/// List the stages whose outcome keeps flipping on the same pipeline version
+pub async fn get_flaky_stages(
+    State(pool): State<PgPool>,
+    scope: ProjectScope,
+    Path(IdPath { id }): Path<IdPath>,
+    Query(query): Query<FlakyStagesQuery>,
+) -> ApiResult<Json<Vec<FlakyStage>>> {
+    tracing::debug!("Finding flaky stages of pipeline: {}", id);
+
+    scope.ensure_pipeline(&pool, id).await?;
+
+    let stages = pipeline_service::find_flaky_stages(&pool, id, query)
+        .await
+        .map_err(|e| match e {
+            pipeline_service::PipelineError::NotFound(id) => {
+                ApiError::NotFound(format!("Pipeline {} not found", id))
+            }
+            pipeline_service::PipelineError::DatabaseError(err) => ApiError::DatabaseError(err),
+            pipeline_service::PipelineError::ValidationError(msg) => ApiError::BadRequest(msg),
+            pipeline_service::PipelineError::InvalidFields(fields) => {
+                ApiError::InvalidFields(fields)
+            }
+        })?;
+
+    Ok(Json(stages))
 }
 
 /// DELETE /pipeline/{id}
-/// Delete a pipeline
+/// Delete a pipeline, or archive it when jobs reference it
+///
+/// `?archive=true` always archives, `?force=true` deletes the pipeline and
+/// its jobs. Answers the archived pipeline, or no content once deleted.
 pub async fn delete_pipeline(
     State(pool): State<PgPool>,
-    Path(id): Path<Uuid>,
-) -> ApiResult<StatusCode> {
+    State(events): State<EventBus>,
+    scope: ProjectScope,
+    Path(IdPath { id }): Path<IdPath>,
+    Query(query): Query<DeletePipelineQuery>,
+) -> ApiResult<Response> {
     tracing::info!("Deleting pipeline: {}", id);
 
-    pipeline_service::delete_pipeline(&pool, id)
+    scope.ensure_pipeline(&pool, id).await?;
+
+    let archived = pipeline_service::delete_pipeline(&pool, id, query)
         .await
         .map_err(|e| match e {
             pipeline_service::PipelineError::NotFound(id) => {
@@ -91,7 +450,92 @@ pub async fn delete_pipeline(
             }
             pipeline_service::PipelineError::DatabaseError(err) => ApiError::DatabaseError(err),
             pipeline_service::PipelineError::ValidationError(msg) => ApiError::BadRequest(msg),
+            pipeline_service::PipelineError::InvalidFields(fields) => {
+                ApiError::InvalidFields(fields)
+            }
         })?;
 
-    Ok(StatusCode::NO_CONTENT)
+    if let Some(pipeline) = archived {
+        return Ok(Json(pipeline).into_response());
+    }
+
+    events.publish(Event::PipelineDeleted { pipeline_id: id });
+
+    Ok(StatusCode::NO_CONTENT.into_response())
+}
+
+/// POST /pipeline/{id}/disable
+/// Pause the launches of a pipeline
+pub async fn disable_pipeline(
+    State(pool): State<PgPool>,
+    scope: ProjectScope,
+    Path(IdPath { id }): Path<IdPath>,
+) -> ApiResult<Json<Pipeline>> {
+    tracing::info!("Disabling pipeline: {}", id);
+
+    set_pipeline_disabled(&pool, &scope, id, true).await
+}
+
+/// POST /pipeline/{id}/enable
+/// Resume the launches of a disabled pipeline
+pub async fn enable_pipeline(
+    State(pool): State<PgPool>,
+    scope: ProjectScope,
+    Path(IdPath { id }): Path<IdPath>,
+) -> ApiResult<Json<Pipeline>> {
+    tracing::info!("Enabling pipeline: {}", id);
+
+    set_pipeline_disabled(&pool, &scope, id, false).await
+}
+
+/// Disable or enable a pipeline of the scope
+async fn set_pipeline_disabled(
+    pool: &PgPool,
+    scope: &ProjectScope,
+    id: Uuid,
+    disabled: bool,
+) -> ApiResult<Json<Pipeline>> {
+    scope.ensure_pipeline(pool, id).await?;
+
+    let pipeline = pipeline_service::set_pipeline_disabled(pool, id, disabled)
+        .await
+        .map_err(|e| match e {
+            pipeline_service::PipelineError::NotFound(id) => {
+                ApiError::NotFound(format!("Pipeline {} not found", id))
+            }
+            pipeline_service::PipelineError::DatabaseError(err) => ApiError::DatabaseError(err),
+            pipeline_service::PipelineError::ValidationError(msg) => ApiError::BadRequest(msg),
+            pipeline_service::PipelineError::InvalidFields(fields) => {
+                ApiError::InvalidFields(fields)
+            }
+        })?;
+
+    Ok(Json(pipeline))
+}
+
+/// POST /pipeline/{id}/unarchive
+/// Make an archived pipeline active again
+pub async fn unarchive_pipeline(
+    State(pool): State<PgPool>,
+    scope: ProjectScope,
+    Path(IdPath { id }): Path<IdPath>,
+) -> ApiResult<Json<Pipeline>> {
+    tracing::info!("Unarchiving pipeline: {}", id);
+
+    scope.ensure_pipeline(&pool, id).await?;
+
+    let pipeline = pipeline_service::unarchive_pipeline(&pool, id)
+        .await
+        .map_err(|e| match e {
+            pipeline_service::PipelineError::NotFound(id) => {
+                ApiError::NotFound(format!("Pipeline {} not found", id))
+            }
+            pipeline_service::PipelineError::DatabaseError(err) => ApiError::DatabaseError(err),
+            pipeline_service::PipelineError::ValidationError(msg) => ApiError::BadRequest(msg),
+            pipeline_service::PipelineError::InvalidFields(fields) => {
+                ApiError::InvalidFields(fields)
+            }
+        })?;
+
+    Ok(Json(pipeline))
 }
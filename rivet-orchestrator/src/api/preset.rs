@@ -0,0 +1,111 @@
+//! Preset API Handlers
+//!
+//! HTTP endpoints for the parameter presets saved for pipelines.
+
+use axum::{
+    Json,
+    extract::{Path, State},
+    http::StatusCode,
+};
+use rivet_core::domain::preset::ParameterPreset;
+use rivet_core::dto::preset::SavePreset;
+use serde::Deserialize;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::api::error::{ApiError, ApiResult};
+use crate::api::project::{IdPath, ProjectScope};
+use crate::service::preset_service;
+
+/// `{id}/presets/{name}` path parameters
+#[derive(Debug, Deserialize)]
+pub struct PresetPath {
+    pub id: Uuid,
+    pub name: String,
+}
+
+/// GET /pipeline/{id}/presets
+/// List the parameter presets of a pipeline
+pub async fn list_presets(
+    State(pool): State<PgPool>,
+    scope: ProjectScope,
+    Path(IdPath { id }): Path<IdPath>,
+) -> ApiResult<Json<Vec<ParameterPreset>>> {
+    tracing::debug!("Listing presets of pipeline: {}", id);
+
+    scope.ensure_pipeline(&pool, id).await?;
+
+    let presets = preset_service::list_presets(&pool, id)
+        .await
+        .map_err(map_preset_error)?;
+
+    Ok(Json(presets))
+}
+
+/// GET /pipeline/{id}/presets/{name}
+/// Get a parameter preset of a pipeline
+pub async fn get_preset(
+    State(pool): State<PgPool>,
+    scope: ProjectScope,
+    Path(PresetPath { id, name }): Path<PresetPath>,
+) -> ApiResult<Json<ParameterPreset>> {
+    tracing::debug!("Getting preset {} of pipeline: {}", name, id);
+
+    scope.ensure_pipeline(&pool, id).await?;
+
+    let preset = preset_service::get_preset(&pool, id, &name)
+        .await
+        .map_err(map_preset_error)?;
+
+    Ok(Json(preset))
+}
+
+/// PUT /pipeline/{id}/presets/{name}
+/// Save a parameter preset of a pipeline, replacing one with the same name
+pub async fn save_preset(
+    State(pool): State<PgPool>,
+    scope: ProjectScope,
+    Path(PresetPath { id, name }): Path<PresetPath>,
+    Json(req): Json<SavePreset>,
+) -> ApiResult<Json<ParameterPreset>> {
+    tracing::info!("Saving preset {} of pipeline: {}", name, id);
+
+    scope.ensure_pipeline(&pool, id).await?;
+
+    let preset = preset_service::save_preset(&pool, id, &name, req)
+        .await
+        .map_err(map_preset_error)?;
+
+    Ok(Json(preset))
+}
+
+/// DELETE /pipeline/{id}/presets/{name}
+/// Remove a parameter preset from a pipeline
+pub async fn delete_preset(
+    State(pool): State<PgPool>,
+    scope: ProjectScope,
+    Path(PresetPath { id, name }): Path<PresetPath>,
+) -> ApiResult<StatusCode> {
+    tracing::info!("Removing preset {} of pipeline: {}", name, id);
+
+    scope.ensure_pipeline(&pool, id).await?;
+
+    preset_service::delete_preset(&pool, id, &name)
+        .await
+        .map_err(map_preset_error)?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+fn map_preset_error(e: preset_service::PresetError) -> ApiError {
+    match e {
+        preset_service::PresetError::NotFound(name) => {
+            ApiError::NotFound(format!("Preset {} not found", name))
+        }
+        preset_service::PresetError::PipelineNotFound(id) => {
+            ApiError::NotFound(format!("Pipeline {} not found", id))
+        }
+        preset_service::PresetError::InvalidFields(fields) => ApiError::InvalidFields(fields),
+        preset_service::PresetError::DatabaseError(err) => ApiError::DatabaseError(err),
+    }
+}
@@ -0,0 +1,510 @@
+//! Rate Limiting Middleware
+//!
+//! Token buckets per client and route class, so a misbehaving runner or
+//! script cannot take the API down. Clients presenting the admin token or a
+//! runner credential verified in the last minute are identified by it,
+//! others by their IP address, so made-up tokens do not buy fresh buckets.
+//! Requests over the limit get `429 Too Many Requests` with a `Retry-After`
+//! header.
+//!
+//! Buckets live in memory: each orchestrator instance limits on its own.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::net::{IpAddr, SocketAddr};
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use axum::{
+    extract::{ConnectInfo, Request, State},
+    http::{HeaderMap, HeaderValue, Method, StatusCode, header},
+    middleware::Next,
+    response::Response,
+};
+use rivet_core::dto::problem::{Problem, ProblemType};
+use rivet_core::dto::runner::RUNNER_CREDENTIAL_HEADER;
+use sha2::{Digest, Sha256};
+
+use crate::api::AppState;
+use crate::api::auth::AuthConfig;
+use crate::api::error::problem_response;
+use crate::api::version;
+use crate::service::runner_service;
+
+/// Number of buckets above which full (idle) buckets are dropped, and of
+/// verified credentials above which expired ones are
+const PRUNE_THRESHOLD: usize = 10_000;
+
+/// How long a verified runner credential identifies its runner without
+/// being checked against the database again
+const VERIFIED_CREDENTIAL_TTL: Duration = Duration::from_secs(60);
+
+/// Kind of route a request targets, each limited separately
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RouteClass {
+    /// `GET` and `HEAD` requests
+    Read,
+    /// Requests changing state, other than log uploads
+    Write,
+    /// Log batches uploaded by runners
+    LogIngest,
+}
+
+impl RouteClass {
+    /// Classifies a request by method and path
+    pub fn of(method: &Method, path: &str) -> Self {
+        if *method == Method::GET || *method == Method::HEAD {
+            RouteClass::Read
+        } else if *method == Method::POST && path.ends_with("/logs") {
+            RouteClass::LogIngest
+        } else {
+            RouteClass::Write
+        }
+    }
+}
+
+/// A request rate: `requests` per `period`, allowing bursts of `requests`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RateLimit {
+    pub requests: u32,
+    pub period: Duration,
+}
+
+impl RateLimit {
+    /// Requests regained per second
+    fn refill_rate(&self) -> f64 {
+        self.requests as f64 / self.period.as_secs_f64()
+    }
+}
+
+impl fmt::Display for RateLimit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.period.as_secs() {
+            1 => write!(f, "{}/s", self.requests),
+            60 => write!(f, "{}/min", self.requests),
+            3600 => write!(f, "{}/h", self.requests),
+            _ => write!(f, "{} per {:?}", self.requests, self.period),
+        }
+    }
+}
+
+impl FromStr for RateLimit {
+    type Err = String;
+
+    /// Parses `<requests>/<s|min|h>`, e.g. `20/s` or `600/min`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("Invalid rate limit '{}' (expected e.g. 20/s or 600/min)", s);
+
+        let (requests, unit) = s.trim().split_once('/').ok_or_else(invalid)?;
+        let requests: u32 = requests.trim().parse().map_err(|_| invalid())?;
+        let period = match unit.trim() {
+            "s" | "sec" => Duration::from_secs(1),
+            "m" | "min" => Duration::from_secs(60),
+            "h" | "hour" => Duration::from_secs(3600),
+            _ => return Err(invalid()),
+        };
+        if requests == 0 {
+            return Err(invalid());
+        }
+
+        Ok(RateLimit { requests, period })
+    }
+}
+
+/// Rate limits per route class; classes without a limit are not limited
+#[derive(Debug, Clone, Default)]
+pub struct RateLimitConfig {
+    pub read: Option<RateLimit>,
+    pub write: Option<RateLimit>,
+    pub log_ingest: Option<RateLimit>,
+    /// Identify anonymous clients by the first `X-Forwarded-For` address
+    /// instead of the peer address, when behind a trusted reverse proxy
+    pub trust_forwarded_for: bool,
+}
+
+impl RateLimitConfig {
+    fn limit(&self, class: RouteClass) -> Option<RateLimit> {
+        match class {
+            RouteClass::Read => self.read,
+            RouteClass::Write => self.write,
+            RouteClass::LogIngest => self.log_ingest,
+        }
+    }
+
+    /// Whether any route class is limited
+    pub fn is_enabled(&self) -> bool {
+        self.read.is_some() || self.write.is_some() || self.log_ingest.is_some()
+    }
+}
+
+/// A token bucket
+#[derive(Debug)]
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+impl Bucket {
+    fn full(limit: RateLimit, now: Instant) -> Self {
+        Self {
+            tokens: limit.requests as f64,
+            updated: now,
+        }
+    }
+
+    fn refill(&mut self, limit: RateLimit, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.updated).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * limit.refill_rate()).min(limit.requests as f64);
+        self.updated = now;
+    }
+
+    /// Takes a token for a request
+    ///
+    /// # Returns
+    /// How long to wait for the next token when the bucket is empty
+    fn take(&mut self, limit: RateLimit, now: Instant) -> Result<(), Duration> {
+        self.refill(limit, now);
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            Ok(())
+        } else {
+            Err(Duration::from_secs_f64(
+                (1.0 - self.tokens) / limit.refill_rate(),
+            ))
+        }
+    }
+}
+
+/// Client a request is limited as
+#[derive(Debug, PartialEq)]
+struct Client {
+    /// Bucket key of the client
+    key: String,
+    /// Runner credential presented but not verified recently, left to the
+    /// middleware to verify once the request is within its IP's limit
+    unverified: Option<String>,
+}
+
+/// A runner credential known to be valid
+#[derive(Debug)]
+struct VerifiedCredential {
+    runner_id: String,
+    expires: Instant,
+}
+
+/// Cloneable handle to the buckets of every client
+#[derive(Debug, Clone, Default)]
+pub struct RateLimiter {
+    config: Arc<RateLimitConfig>,
+    buckets: Arc<Mutex<HashMap<(RouteClass, String), Bucket>>>,
+    /// Verified runner credentials, by SHA-256 hash
+    credentials: Arc<Mutex<HashMap<String, VerifiedCredential>>>,
+}
+
+impl RateLimiter {
+    /// Creates a limiter enforcing `config`
+    pub fn new(config: RateLimitConfig) -> Self {
+        Self {
+            config: Arc::new(config),
+            buckets: Arc::default(),
+            credentials: Arc::default(),
+        }
+    }
+
+    /// Takes a token from a client's bucket for a route class
+    ///
+    /// # Returns
+    /// How long the client should wait before retrying when over the limit
+    fn check(&self, class: RouteClass, client: String, now: Instant) -> Result<(), Duration> {
+        let Some(limit) = self.config.limit(class) else {
+            return Ok(());
+        };
+
+        let mut buckets = self.buckets.lock().unwrap();
+        if buckets.len() >= PRUNE_THRESHOLD {
+            buckets.retain(|(class, _), bucket| match self.config.limit(*class) {
+                Some(limit) => {
+                    bucket.refill(limit, now);
+                    bucket.tokens < limit.requests as f64
+                }
+                None => false,
+            });
+        }
+
+        buckets
+            .entry((class, client))
+            .or_insert_with(|| Bucket::full(limit, now))
+            .take(limit, now)
+    }
+
+    /// Identifies the client of a request
+    ///
+    /// Clients are limited per token only once it has been verified: the
+    /// configured admin token, or a runner credential verified within
+    /// `VERIFIED_CREDENTIAL_TTL`. Anything else is limited per IP, without
+    /// a database query before the bucket is checked. `None` when the IP is
+    /// not known either (e.g. the router is served without connect info),
+    /// in which case the request is not limited.
+    fn client(
+        &self,
+        headers: &HeaderMap,
+        peer: Option<SocketAddr>,
+        auth: &AuthConfig,
+        now: Instant,
+    ) -> Option<Client> {
+        let bearer = headers
+            .get(header::AUTHORIZATION)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.strip_prefix("Bearer "));
+        if auth.admin_token.is_some() && auth.is_admin(bearer) {
+            return Some(Client {
+                key: "admin".to_string(),
+                unverified: None,
+            });
+        }
+
+        let credential = headers
+            .get(RUNNER_CREDENTIAL_HEADER)
+            .and_then(|v| v.to_str().ok());
+        if let Some(credential) = credential {
+            let credentials = self.credentials.lock().unwrap();
+            match credentials.get(&hash_credential(credential)) {
+                Some(verified) if verified.expires > now => {
+                    return Some(Client {
+                        key: format!("runner:{}", verified.runner_id),
+                        unverified: None,
+                    });
+                }
+                _ => {}
+            }
+        }
+
+        let forwarded = self
+            .config
+            .trust_forwarded_for
+            .then(|| headers.get("x-forwarded-for")?.to_str().ok())
+            .flatten()
+            .and_then(|v| v.split(',').next()?.trim().parse::<IpAddr>().ok());
+        let ip = forwarded.or_else(|| peer.map(|peer| peer.ip()))?;
+
+        Some(Client {
+            key: format!("ip:{}", ip),
+            unverified: credential.map(str::to_string),
+        })
+    }
+
+    /// Identifies requests presenting `credential` as coming from
+    /// `runner_id` until `VERIFIED_CREDENTIAL_TTL` has passed
+    fn remember_credential(&self, credential: &str, runner_id: String, now: Instant) {
+        let mut credentials = self.credentials.lock().unwrap();
+        if credentials.len() >= PRUNE_THRESHOLD {
+            credentials.retain(|_, verified| verified.expires > now);
+        }
+
+        credentials.insert(
+            hash_credential(credential),
+            VerifiedCredential {
+                runner_id,
+                expires: now + VERIFIED_CREDENTIAL_TTL,
+            },
+        );
+    }
+}
+
+/// Key of a runner credential among the verified ones, so they are not
+/// kept in memory in the clear
+fn hash_credential(credential: &str) -> String {
+    hex::encode(Sha256::digest(credential.as_bytes()))
+}
+
+/// Middleware rejecting requests over their client's rate limit
+///
+/// The health check and the probes are never limited, so they keep working
+/// under load.
+pub async fn limit_requests(State(state): State<AppState>, req: Request, next: Next) -> Response {
+    let limiter = &state.rate_limiter;
+    let path = req.uri().path();
+    if !limiter.config.is_enabled()
+        || matches!(path, "/healthz" | "/readyz")
+        || version::route_path(path) == "/health"
+    {
+        return next.run(req).await;
+    }
+    let peer = req
+        .extensions()
+        .get::<ConnectInfo<SocketAddr>>()
+        .map(|info| info.0);
+    let now = Instant::now();
+    let Some(client) = limiter.client(req.headers(), peer, &state.auth, now) else {
+        return next.run(req).await;
+    };
+
+    let class = RouteClass::of(req.method(), req.uri().path());
+    match limiter.check(class, client.key, now) {
+        Ok(()) => {
+            // Within its IP's limit, a runner's credential is checked so its
+            // next requests get the runner's own bucket
+            let runner = match &client.unverified {
+                Some(credential) => runner_service::authenticate_runner(&state.pool, credential)
+                    .await
+                    .ok(),
+                None => None,
+            };
+            if let (Some(credential), Some(runner_id)) = (client.unverified, runner) {
+                limiter.remember_credential(&credential, runner_id, now);
+            }
+            next.run(req).await
+        }
+        Err(retry_after) => {
+            tracing::debug!(
+                "Rate limited {} {} (retry after {:?})",
+                req.method(),
+                req.uri().path(),
+                retry_after
+            );
+            too_many_requests(retry_after)
+        }
+    }
+}
+
+/// Builds the `429 Too Many Requests` response
+fn too_many_requests(retry_after: Duration) -> Response {
+    let problem = Problem::new(
+        ProblemType::RateLimited,
+        StatusCode::TOO_MANY_REQUESTS.as_u16(),
+        "Too many requests, slow down",
+    );
+
+    // Whole seconds, rounded up so retrying right on time succeeds
+    let seconds = retry_after.as_secs() + u64::from(retry_after.subsec_nanos() > 0);
+    let mut response = problem_response(problem);
+    response
+        .headers_mut()
+        .insert(header::RETRY_AFTER, HeaderValue::from(seconds.max(1)));
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_rate_limit() {
+        let limit: RateLimit = "600/min".parse().unwrap();
+        assert_eq!(limit.requests, 600);
+        assert_eq!(limit.period, Duration::from_secs(60));
+        assert_eq!(limit.to_string(), "600/min");
+
+        assert!("0/s".parse::<RateLimit>().is_err());
+        assert!("20".parse::<RateLimit>().is_err());
+        assert!("20/day".parse::<RateLimit>().is_err());
+    }
+
+    #[test]
+    fn test_route_classes() {
+        assert_eq!(
+            RouteClass::of(&Method::GET, "/api/v1/jobs"),
+            RouteClass::Read
+        );
+        assert_eq!(
+            RouteClass::of(&Method::POST, "/api/v1/jobs/1/logs"),
+            RouteClass::LogIngest
+        );
+        assert_eq!(
+            RouteClass::of(&Method::POST, "/api/v1/pipeline/launch"),
+            RouteClass::Write
+        );
+    }
+
+    #[test]
+    fn test_buckets_empty_and_refill_per_client() {
+        let limiter = RateLimiter::new(RateLimitConfig {
+            write: Some("2/s".parse().unwrap()),
+            ..Default::default()
+        });
+        let start = Instant::now();
+        let check = |client: &str, at: Duration| {
+            limiter.check(RouteClass::Write, client.to_string(), start + at)
+        };
+
+        assert!(check("a", Duration::ZERO).is_ok());
+        assert!(check("a", Duration::ZERO).is_ok());
+        let retry_after = check("a", Duration::ZERO).unwrap_err();
+        assert_eq!(retry_after, Duration::from_millis(500));
+
+        // Other clients and unlimited classes are unaffected
+        assert!(check("b", Duration::ZERO).is_ok());
+        assert!(
+            limiter
+                .check(RouteClass::Read, "a".to_string(), start)
+                .is_ok()
+        );
+
+        assert!(check("a", Duration::from_millis(500)).is_ok());
+        assert!(check("a", Duration::from_millis(500)).is_err());
+    }
+
+    #[test]
+    fn test_unverified_tokens_are_limited_per_ip() {
+        let limiter = RateLimiter::default();
+        let auth = AuthConfig {
+            admin_token: Some("s3cret".to_string()),
+            require_runner_auth: false,
+        };
+        let peer = Some(SocketAddr::from(([10, 0, 0, 7], 4000)));
+        let now = Instant::now();
+        let key = |token: &str, auth: &AuthConfig| {
+            let mut headers = HeaderMap::new();
+            headers.insert(
+                header::AUTHORIZATION,
+                format!("Bearer {}", token).parse().unwrap(),
+            );
+            limiter.client(&headers, peer, auth, now).unwrap().key
+        };
+
+        assert_eq!(key("random-1", &auth), "ip:10.0.0.7");
+        assert_eq!(key("random-2", &auth), "ip:10.0.0.7");
+        assert_eq!(key("s3cret", &auth), "admin");
+
+        // Without an admin token every bearer is unverified
+        assert_eq!(key("s3cret", &AuthConfig::default()), "ip:10.0.0.7");
+    }
+
+    #[test]
+    fn test_runner_credentials_are_limited_per_runner_once_verified() {
+        let limiter = RateLimiter::default();
+        let auth = AuthConfig::default();
+        let peer = Some(SocketAddr::from(([10, 0, 0, 7], 4000)));
+        let now = Instant::now();
+        let client = |credential: &str, at: Duration| {
+            let mut headers = HeaderMap::new();
+            headers.insert(RUNNER_CREDENTIAL_HEADER, credential.parse().unwrap());
+            limiter.client(&headers, peer, &auth, now + at).unwrap()
+        };
+
+        // Unknown credentials share their IP's bucket until verified
+        assert_eq!(
+            client("cred-1", Duration::ZERO),
+            Client {
+                key: "ip:10.0.0.7".to_string(),
+                unverified: Some("cred-1".to_string()),
+            }
+        );
+
+        limiter.remember_credential("cred-1", "runner-1".to_string(), now);
+        assert_eq!(
+            client("cred-1", Duration::ZERO),
+            Client {
+                key: "runner:runner-1".to_string(),
+                unverified: None,
+            }
+        );
+        assert_eq!(client("cred-2", Duration::ZERO).key, "ip:10.0.0.7");
+
+        // Verified credentials are checked again once they expire
+        let expired = client("cred-1", VERIFIED_CREDENTIAL_TTL);
+        assert_eq!(expired.key, "ip:10.0.0.7");
+        assert_eq!(expired.unverified.as_deref(), Some("cred-1"));
+    }
+}
@@ -0,0 +1,179 @@
+//! Request Context Middleware
+//!
+//! Extracts the `X-Request-Id` and W3C `traceparent` headers sent by clients
+//! (generating a request id when absent), records them on the request span and
+//! echoes the request id back so CLI/runner logs can be matched with server logs.
+//! The request span continues the client's trace when spans are exported.
+
+use axum::{
+    extract::Request,
+    http::{HeaderValue, header::HeaderName},
+    middleware::Next,
+    response::Response,
+};
+use tracing::Span;
+use uuid::Uuid;
+
+/// Header carrying the per-request correlation id
+pub const REQUEST_ID_HEADER: HeaderName = HeaderName::from_static("x-request-id");
+
+/// W3C Trace Context header
+pub const TRACEPARENT_HEADER: HeaderName = HeaderName::from_static("traceparent");
+
+/// Longest client-provided request id that is accepted as-is
+const MAX_REQUEST_ID_LEN: usize = 128;
+
+tokio::task_local! {
+    static REQUEST_ID: String;
+}
+
+/// Correlation identifiers of the current request
+///
+/// Available to handlers as an `Extension<RequestContext>`.
+#[derive(Debug, Clone)]
+pub struct RequestContext {
+    pub request_id: String,
+    pub trace_id: Option<String>,
+}
+
+/// Returns the id of the request being handled, if any
+///
+/// Used by `ApiError` to include the id in error bodies.
+pub fn current_request_id() -> Option<String> {
+    REQUEST_ID.try_with(|id| id.clone()).ok()
+}
+
+/// Middleware that establishes the request context
+///
+/// Must be the outermost layer so the trace span sees the resolved request id.
+pub async fn propagate_request_context(mut req: Request, next: Next) -> Response {
+    let request_id = req
+        .headers()
+        .get(&REQUEST_ID_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .filter(|id| is_valid_request_id(id))
+        .map(str::to_string)
+        .unwrap_or_else(|| Uuid::new_v4().to_string());
+
+    let trace_id = req
+        .headers()
+        .get(&TRACEPARENT_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .and_then(parse_trace_id);
+
+    // Validated above, so the id is always a valid header value
+    let header_value = HeaderValue::from_str(&request_id).expect("valid request id");
+    req.headers_mut()
+        .insert(REQUEST_ID_HEADER, header_value.clone());
+    req.extensions_mut().insert(RequestContext {
+        request_id: request_id.clone(),
+        trace_id,
+    });
+
+    let mut response = REQUEST_ID.scope(request_id, next.run(req)).await;
+    response
+        .headers_mut()
+        .insert(REQUEST_ID_HEADER, header_value);
+    response
+}
+
+/// Builds the span used by `TraceLayer` for each request
+pub fn make_request_span(req: &Request) -> Span {
+    let context = req.extensions().get::<RequestContext>();
+
+    let span = tracing::info_span!(
+        "request",
+        otel.kind = "server",
+        method = %req.method(),
+        uri = %req.uri(),
+        request_id = context.map(|c| c.request_id.as_str()).unwrap_or_default(),
+        trace_id = context.and_then(|c| c.trace_id.as_deref()).unwrap_or_default(),
+    );
+    continue_trace(&span, req.headers());
+    span
+}
+
+/// Builds the span of each gRPC request (see `tonic::transport::Server::trace_fn`)
+pub fn make_grpc_span(req: &axum::http::Request<()>) -> Span {
+    let header = |name: &HeaderName| req.headers().get(name).and_then(|v| v.to_str().ok());
+
+    let span = tracing::info_span!(
+        "grpc",
+        otel.kind = "server",
+        uri = %req.uri(),
+        request_id = header(&REQUEST_ID_HEADER).unwrap_or_default(),
+        trace_id = header(&TRACEPARENT_HEADER)
+            .and_then(parse_trace_id)
+            .unwrap_or_default(),
+    );
+    continue_trace(&span, req.headers());
+    span
+}
+
+/// Makes `span` a child of the client span named by the `traceparent` header
+fn continue_trace(span: &Span, headers: &axum::http::HeaderMap) {
+    if let Some(traceparent) = headers
+        .get(&TRACEPARENT_HEADER)
+        .and_then(|v| v.to_str().ok())
+    {
+        rivet_core::telemetry::set_parent(span, traceparent);
+    }
+}
+
+// =============================================================================
+// Parsing
+// =============================================================================
+
+fn is_valid_request_id(id: &str) -> bool {
+    !id.is_empty() && id.len() <= MAX_REQUEST_ID_LEN && id.bytes().all(|b| b.is_ascii_graphic())
+}
+
+/// Extracts the trace id from a `traceparent` header
+///
+/// Format: `{version}-{trace-id:32 hex}-{parent-id:16 hex}-{flags:2 hex}`.
+/// An all-zero trace id is invalid per the specification.
+fn parse_trace_id(traceparent: &str) -> Option<String> {
+    let mut parts = traceparent.trim().split('-');
+    let (version, trace_id, parent_id, flags) =
+        (parts.next()?, parts.next()?, parts.next()?, parts.next()?);
+
+    let is_hex = |s: &str, len: usize| s.len() == len && s.bytes().all(|b| b.is_ascii_hexdigit());
+
+    if !is_hex(version, 2)
+        || version == "ff"
+        || !is_hex(trace_id, 32)
+        || !is_hex(parent_id, 16)
+        || !is_hex(flags, 2)
+        || trace_id.bytes().all(|b| b == b'0')
+    {
+        return None;
+    }
+
+    Some(trace_id.to_ascii_lowercase())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_trace_id() {
+        assert_eq!(
+            parse_trace_id("00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01").as_deref(),
+            Some("4bf92f3577b34da6a3ce929d0e0e4736")
+        );
+        assert!(
+            parse_trace_id("00-00000000000000000000000000000000-00f067aa0ba902b7-01").is_none()
+        );
+        assert!(parse_trace_id("00-4bf92f35-00f067aa0ba902b7-01").is_none());
+        assert!(parse_trace_id("garbage").is_none());
+    }
+
+    #[test]
+    fn test_request_id_validation() {
+        assert!(is_valid_request_id("3f2b1c9e-req"));
+        assert!(!is_valid_request_id(""));
+        assert!(!is_valid_request_id("has space"));
+        assert!(!is_valid_request_id(&"a".repeat(MAX_REQUEST_ID_LEN + 1)));
+    }
+}
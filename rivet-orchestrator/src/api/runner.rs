@@ -7,55 +7,60 @@ use axum::{
     extract::{Path, State},
     http::StatusCode,
 };
-use rivet_core::domain::runner::Runner;
-use rivet_core::dto::runner::RegisterRunner;
+use rivet_core::domain::runner::{RegistrationToken, Runner, RunnerLoad};
+use rivet_core::dto::event::Event;
+use rivet_core::dto::runner::{
+    CreateRegistrationToken, NewRegistrationToken, RegisterRunner, RunnerRegistration,
+};
 use sqlx::PgPool;
+use uuid::Uuid;
 
+use crate::api::auth::{AdminAuth, AuthConfig, RunnerIdentity};
 use crate::api::error::{ApiError, ApiResult};
+use crate::events::EventBus;
 use crate::service::runner_service;
 
 // =============================================================================
 // Runner Registration & Lifecycle
 // =============================================================================
 
-/// POST /api/runners/register
+/// POST /api/v1/runners/register
 /// Register a runner with the orchestrator
 pub async fn register_runner(
     State(pool): State<PgPool>,
+    State(events): State<EventBus>,
+    State(auth): State<AuthConfig>,
     Json(req): Json<RegisterRunner>,
-) -> ApiResult<Json<Runner>> {
+) -> ApiResult<Json<RunnerRegistration>> {
     tracing::info!("Registering runner: {}", req.runner_id);
 
-    let runner = runner_service::register_runner(&pool, req)
+    let registration = runner_service::register_runner(&pool, req, auth.require_runner_auth)
         .await
-        .map_err(|e| match e {
-            runner_service::RunnerError::NotFound(id) => {
-                ApiError::NotFound(format!("Runner {} not found", id))
-            }
-            runner_service::RunnerError::ValidationError(msg) => ApiError::BadRequest(msg),
-            runner_service::RunnerError::DatabaseError(err) => ApiError::DatabaseError(err),
-        })?;
+        .map_err(map_runner_error)?;
 
-    Ok(Json(runner))
+    events.publish(Event::RunnerRegistered {
+        runner_id: registration.runner.id.clone(),
+    });
+
+    Ok(Json(registration))
 }
 
-/// POST /api/runners/{id}/heartbeat
-/// Update heartbeat for a runner to keep it marked as online
+/// POST /api/v1/runners/{id}/heartbeat
+/// Update heartbeat for a runner to keep it marked as online, optionally
+/// reporting its load (`RunnerLoad`) in the body
 pub async fn runner_heartbeat(
     State(pool): State<PgPool>,
+    identity: RunnerIdentity,
     Path(id): Path<String>,
+    load: Option<Json<RunnerLoad>>,
 ) -> ApiResult<StatusCode> {
     tracing::debug!("Heartbeat from runner: {}", id);
 
-    runner_service::update_heartbeat(&pool, &id)
+    identity.ensure_runner(&id)?;
+
+    runner_service::update_heartbeat(&pool, &id, load.as_deref())
         .await
-        .map_err(|e| match e {
-            runner_service::RunnerError::NotFound(id) => {
-                ApiError::NotFound(format!("Runner {} not found", id))
-            }
-            runner_service::RunnerError::ValidationError(msg) => ApiError::BadRequest(msg),
-            runner_service::RunnerError::DatabaseError(err) => ApiError::DatabaseError(err),
-        })?;
+        .map_err(map_runner_error)?;
 
     Ok(StatusCode::NO_CONTENT)
 }
@@ -64,25 +69,19 @@ pub async fn runner_heartbeat(
 // Runner Query Endpoints
 // =============================================================================
 
-/// GET /api/runners
+/// GET /api/v1/runners
 /// List all registered runners
 pub async fn list_runners(State(pool): State<PgPool>) -> ApiResult<Json<Vec<Runner>>> {
     tracing::debug!("Listing all runners");
 
     let runners = runner_service::list_runners(&pool)
         .await
-        .map_err(|e| match e {
-            runner_service::RunnerError::DatabaseError(err) => ApiError::DatabaseError(err),
-            runner_service::RunnerError::NotFound(id) => {
-                ApiError::NotFound(format!("Runner {} not found", id))
-            }
-            runner_service::RunnerError::ValidationError(msg) => ApiError::BadRequest(msg),
-        })?;
+        .map_err(map_runner_error)?;
 
     Ok(Json(runners))
 }
 
-/// GET /api/runners/{id}
+/// GET /api/v1/runners/{id}
 /// Get details for a specific runner
 pub async fn get_runner(
     State(pool): State<PgPool>,
@@ -92,20 +91,15 @@ pub async fn get_runner(
 
     let runner = runner_service::get_runner(&pool, &id)
         .await
-        .map_err(|e| match e {
-            runner_service::RunnerError::NotFound(id) => {
-                ApiError::NotFound(format!("Runner {} not found", id))
-            }
-            runner_service::RunnerError::ValidationError(msg) => ApiError::BadRequest(msg),
-            runner_service::RunnerError::DatabaseError(err) => ApiError::DatabaseError(err),
-        })?;
+        .map_err(map_runner_error)?;
 
     Ok(Json(runner))
 }
 
-/// DELETE /api/runners/{id}
-/// Delete a runner registration
+/// DELETE /api/v1/runners/{id}
+/// Delete a runner registration (admin only)
 pub async fn delete_runner(
+    _admin: AdminAuth,
     State(pool): State<PgPool>,
     Path(id): Path<String>,
 ) -> ApiResult<StatusCode> {
@@ -113,13 +107,73 @@ pub async fn delete_runner(
 
     runner_service::delete_runner(&pool, &id)
         .await
-        .map_err(|e| match e {
-            runner_service::RunnerError::NotFound(id) => {
-                ApiError::NotFound(format!("Runner {} not found", id))
-            }
-            runner_service::RunnerError::ValidationError(msg) => ApiError::BadRequest(msg),
-            runner_service::RunnerError::DatabaseError(err) => ApiError::DatabaseError(err),
-        })?;
+        .map_err(map_runner_error)?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+// =============================================================================
+// Registration Tokens
+// =============================================================================
+
+/// POST /api/v1/runners/tokens
+/// Mint a registration token (admin only); the secret is returned only once
+pub async fn create_registration_token(
+    _admin: AdminAuth,
+    State(pool): State<PgPool>,
+    Json(req): Json<CreateRegistrationToken>,
+) -> ApiResult<(StatusCode, Json<NewRegistrationToken>)> {
+    tracing::info!("Creating runner registration token");
+
+    let token = runner_service::create_registration_token(&pool, req)
+        .await
+        .map_err(map_runner_error)?;
+
+    Ok((StatusCode::CREATED, Json(token)))
+}
+
+/// GET /api/v1/runners/tokens
+/// List registration tokens (admin only)
+pub async fn list_registration_tokens(
+    _admin: AdminAuth,
+    State(pool): State<PgPool>,
+) -> ApiResult<Json<Vec<RegistrationToken>>> {
+    tracing::debug!("Listing runner registration tokens");
+
+    let tokens = runner_service::list_registration_tokens(&pool)
+        .await
+        .map_err(map_runner_error)?;
+
+    Ok(Json(tokens))
+}
+
+/// DELETE /api/v1/runners/tokens/{id}
+/// Revoke a registration token (admin only)
+pub async fn delete_registration_token(
+    _admin: AdminAuth,
+    State(pool): State<PgPool>,
+    Path(id): Path<Uuid>,
+) -> ApiResult<StatusCode> {
+    tracing::info!("Revoking runner registration token: {}", id);
+
+    runner_service::delete_registration_token(&pool, id)
+        .await
+        .map_err(map_runner_error)?;
 
     Ok(StatusCode::NO_CONTENT)
 }
+
+fn map_runner_error(e: runner_service::RunnerError) -> ApiError {
+    match e {
+        runner_service::RunnerError::NotFound(id) => {
+            ApiError::NotFound(format!("Runner {} not found", id))
+        }
+        runner_service::RunnerError::TokenNotFound(id) => {
+            ApiError::NotFound(format!("Registration token {} not found", id))
+        }
+        runner_service::RunnerError::Unauthorized(msg) => ApiError::Unauthorized(msg),
+        runner_service::RunnerError::ValidationError(msg) => ApiError::BadRequest(msg),
+        runner_service::RunnerError::IncompatibleVersion(msg) => ApiError::Conflict(msg),
+        runner_service::RunnerError::DatabaseError(err) => ApiError::DatabaseError(err),
+    }
+}
@@ -0,0 +1,96 @@
+//! Schedule API Handlers
+//!
+//! HTTP endpoints for the cron schedules launching the jobs of pipelines.
+
+use axum::{
+    Json,
+    extract::{Path, State},
+    http::StatusCode,
+};
+use rivet_core::domain::schedule::Schedule;
+use rivet_core::dto::schedule::CreateSchedule;
+use serde::Deserialize;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::api::auth::AdminAuth;
+use crate::api::error::{ApiError, ApiResult};
+use crate::api::project::{IdPath, ProjectScope};
+use crate::service::schedule_service;
+
+/// `{id}/schedules/{schedule_id}` path parameters
+#[derive(Debug, Deserialize)]
+pub struct SchedulePath {
+    pub id: Uuid,
+    pub schedule_id: Uuid,
+}
+
+/// POST /pipeline/{id}/schedules
+/// Add a cron schedule to a pipeline (admin)
+pub async fn create_schedule(
+    State(pool): State<PgPool>,
+    _admin: AdminAuth,
+    scope: ProjectScope,
+    Path(IdPath { id }): Path<IdPath>,
+    Json(req): Json<CreateSchedule>,
+) -> ApiResult<(StatusCode, Json<Schedule>)> {
+    tracing::info!("Adding schedule to pipeline: {}", id);
+
+    scope.ensure_pipeline(&pool, id).await?;
+
+    let schedule = schedule_service::create_schedule(&pool, id, req)
+        .await
+        .map_err(map_schedule_error)?;
+
+    Ok((StatusCode::CREATED, Json(schedule)))
+}
+
+/// GET /pipeline/{id}/schedules
+/// List the cron schedules of a pipeline
+pub async fn list_schedules(
+    State(pool): State<PgPool>,
+    scope: ProjectScope,
+    Path(IdPath { id }): Path<IdPath>,
+) -> ApiResult<Json<Vec<Schedule>>> {
+    tracing::debug!("Listing schedules of pipeline: {}", id);
+
+    scope.ensure_pipeline(&pool, id).await?;
+
+    let schedules = schedule_service::list_schedules(&pool, id)
+        .await
+        .map_err(map_schedule_error)?;
+
+    Ok(Json(schedules))
+}
+
+/// DELETE /pipeline/{id}/schedules/{schedule_id}
+/// Remove a cron schedule from a pipeline (admin)
+pub async fn delete_schedule(
+    State(pool): State<PgPool>,
+    _admin: AdminAuth,
+    scope: ProjectScope,
+    Path(SchedulePath { id, schedule_id }): Path<SchedulePath>,
+) -> ApiResult<StatusCode> {
+    tracing::info!("Removing schedule {} of pipeline: {}", schedule_id, id);
+
+    scope.ensure_pipeline(&pool, id).await?;
+
+    schedule_service::delete_schedule(&pool, id, schedule_id)
+        .await
+        .map_err(map_schedule_error)?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+fn map_schedule_error(e: schedule_service::ScheduleError) -> ApiError {
+    match e {
+        schedule_service::ScheduleError::NotFound(id) => {
+            ApiError::NotFound(format!("Schedule {} not found", id))
+        }
+        schedule_service::ScheduleError::PipelineNotFound(id) => {
+            ApiError::NotFound(format!("Pipeline {} not found", id))
+        }
+        schedule_service::ScheduleError::InvalidFields(fields) => ApiError::InvalidFields(fields),
+        schedule_service::ScheduleError::DatabaseError(err) => ApiError::DatabaseError(err),
+    }
+}
@@ -0,0 +1,90 @@
+//! Secret API Handlers
+//!
+//! HTTP endpoints for secret management. Values are accepted but never returned.
+//! Unscoped routes manage the secrets of the default project. Writes require
+//! the admin token.
+
+use axum::{
+    Json,
+    extract::{Path, Query, State},
+    http::StatusCode,
+};
+use rivet_core::domain::secret::Secret;
+use rivet_core::dto::secret::{SecretScope, SetSecret};
+use serde::Deserialize;
+use sqlx::PgPool;
+
+use crate::api::auth::AdminAuth;
+use crate::api::error::{ApiError, ApiResult};
+use crate::api::project::ProjectScope;
+use crate::service::secret_service;
+
+/// `{name}` path parameter
+#[derive(Debug, Deserialize)]
+pub struct SecretPath {
+    pub name: String,
+}
+
+/// PUT /secrets
+/// Create or replace a secret (admin only)
+pub async fn set_secret(
+    _admin: AdminAuth,
+    State(pool): State<PgPool>,
+    scope: ProjectScope,
+    Json(req): Json<SetSecret>,
+) -> ApiResult<Json<Secret>> {
+    tracing::info!("Setting secret: {}", req.name);
+
+    let secret = secret_service::set_secret(&pool, scope.id_or_default(), req)
+        .await
+        .map_err(map_secret_error)?;
+
+    Ok(Json(secret))
+}
+
+/// GET /secrets?pipeline_id={id}
+/// List secrets in a scope (project-wide when no pipeline is given)
+pub async fn list_secrets(
+    State(pool): State<PgPool>,
+    project: ProjectScope,
+    Query(scope): Query<SecretScope>,
+) -> ApiResult<Json<Vec<Secret>>> {
+    tracing::debug!("Listing secrets");
+
+    let secrets = secret_service::list_secrets(&pool, project.id_or_default(), scope.pipeline_id)
+        .await
+        .map_err(map_secret_error)?;
+
+    Ok(Json(secrets))
+}
+
+/// DELETE /secrets/{name}?pipeline_id={id}
+/// Delete a secret (admin only)
+pub async fn delete_secret(
+    _admin: AdminAuth,
+    State(pool): State<PgPool>,
+    project: ProjectScope,
+    Path(SecretPath { name }): Path<SecretPath>,
+    Query(scope): Query<SecretScope>,
+) -> ApiResult<StatusCode> {
+    tracing::info!("Deleting secret: {}", name);
+
+    secret_service::delete_secret(&pool, project.id_or_default(), &name, scope.pipeline_id)
+        .await
+        .map_err(map_secret_error)?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+fn map_secret_error(e: secret_service::SecretError) -> ApiError {
+    match e {
+        secret_service::SecretError::NotFound(name) => {
+            ApiError::NotFound(format!("Secret {} not found", name))
+        }
+        secret_service::SecretError::PipelineNotFound(id) => {
+            ApiError::NotFound(format!("Pipeline {} not found", id))
+        }
+        secret_service::SecretError::ValidationError(msg) => ApiError::BadRequest(msg),
+        secret_service::SecretError::DatabaseError(err) => ApiError::DatabaseError(err),
+    }
+}
@@ -6,11 +6,12 @@
 use axum::{
     Json,
     extract::Path,
-    http::StatusCode,
     response::{IntoResponse, Response},
 };
 use serde::Serialize;
 
+use crate::api::error::ApiError;
+
 /// Response containing a stub file
 #[derive(Serialize)]
 pub struct StubResponse {
@@ -26,6 +27,10 @@ pub async fn list_stubs() -> Json<Vec<String>> {
         "output".to_string(),
         "process".to_string(),
         "container".to_string(),
+        "ctx".to_string(),
+        "job".to_string(),
+        "env".to_string(),
+        "annotations".to_string(),
     ])
 }
 
@@ -37,14 +42,12 @@ pub async fn get_stub(Path(name): Path<String>) -> Response {
         "output" => include_str!("../../stubs/output.lua"),
         "process" => include_str!("../../stubs/process.lua"),
         "container" => include_str!("../../stubs/container.lua"),
+        "ctx" => include_str!("../../stubs/ctx.lua"),
+        "job" => include_str!("../../stubs/job.lua"),
+        "env" => include_str!("../../stubs/env.lua"),
+        "annotations" => include_str!("../../stubs/annotations.lua"),
         _ => {
-            return (
-                StatusCode::NOT_FOUND,
-                Json(serde_json::json!({
-                    "error": format!("Stub '{}' not found", name)
-                })),
-            )
-                .into_response();
+            return ApiError::NotFound(format!("Stub '{}' not found", name)).into_response();
         }
     };
 
@@ -0,0 +1,129 @@
+//! API Version Negotiation
+//!
+//! Routes are served under `/api/v1` and, for clients that predate
+//! versioning, under the bare `/api` prefix. A client may state the version
+//! it speaks in `X-Rivet-Api-Version`; a version this orchestrator does not
+//! serve is refused with `406 Not Acceptable` rather than answered with
+//! DTOs the client would misread. Every response names the version served,
+//! and responses on the legacy prefix carry `Deprecation: true`.
+
+use axum::{
+    extract::Request,
+    http::{HeaderName, HeaderValue, StatusCode},
+    middleware::Next,
+    response::Response,
+};
+use rivet_core::dto::problem::{Problem, ProblemType};
+use rivet_core::dto::version::{API_VERSION, API_VERSION_HEADER, SUPPORTED_API_VERSIONS};
+
+use crate::api::error::problem_response;
+
+/// Unversioned prefix the API was served under before versioning
+pub const LEGACY_API_PREFIX: &str = "/api";
+
+/// `Deprecation` response header (RFC 9745)
+const DEPRECATION_HEADER: HeaderName = HeaderName::from_static("deprecation");
+
+/// Version named by the path of a request, `None` on the legacy prefix
+///
+/// # Returns
+/// `Some(None)` for a `/api/v<N>` path whose version is not a number
+fn path_version(path: &str) -> Option<Option<u32>> {
+    let rest = path.strip_prefix("/api/v")?;
+    let version = rest.split('/').next().unwrap_or(rest);
+    Some(version.parse().ok())
+}
+
+/// Path of a request below its API prefix, e.g. `/health` for both
+/// `/api/v1/health` and `/api/health`
+pub fn route_path(path: &str) -> &str {
+    let Some(rest) = path.strip_prefix(LEGACY_API_PREFIX) else {
+        return path;
+    };
+    match path_version(path) {
+        Some(_) => rest
+            .strip_prefix("/v")
+            .and_then(|r| r.find('/').map(|i| &r[i..]))
+            .unwrap_or(""),
+        None => rest,
+    }
+}
+
+/// Middleware checking the requested API version and naming the one served
+pub async fn negotiate_version(req: Request, next: Next) -> Response {
+    let path_version = path_version(req.uri().path());
+    let header_version = req
+        .headers()
+        .get(API_VERSION_HEADER)
+        .map(|v| v.to_str().ok().and_then(|v| v.trim().parse::<u32>().ok()));
+
+    let requested = match (path_version, header_version) {
+        (Some(Some(path)), Some(Some(header))) if path != header => {
+            return unsupported(format!(
+                "The path asks for API version {} but {} asks for {}",
+                path, API_VERSION_HEADER, header
+            ));
+        }
+        (Some(Some(version)), _) | (None | Some(None), Some(Some(version))) => version,
+        (_, Some(None)) => {
+            return unsupported(format!("Invalid {} header", API_VERSION_HEADER));
+        }
+        // Not an API route, or `/api/v<garbage>`: let routing answer 404
+        (Some(None), None) | (None, None) => API_VERSION,
+    };
+    if !SUPPORTED_API_VERSIONS.contains(&requested) {
+        return unsupported(format!(
+            "API version {} is not supported (supported: {})",
+            requested,
+            SUPPORTED_API_VERSIONS
+                .iter()
+                .map(u32::to_string)
+                .collect::<Vec<_>>()
+                .join(", ")
+        ));
+    }
+
+    let legacy = path_version.is_none() && req.uri().path().starts_with(LEGACY_API_PREFIX);
+    let mut response = next.run(req).await;
+
+    let headers = response.headers_mut();
+    headers.insert(API_VERSION_HEADER, HeaderValue::from(requested));
+    if legacy {
+        headers.insert(DEPRECATION_HEADER, HeaderValue::from_static("true"));
+    }
+    response
+}
+
+/// Builds the `406 Not Acceptable` response
+fn unsupported(detail: String) -> Response {
+    let mut response = problem_response(Problem::new(
+        ProblemType::UnsupportedApiVersion,
+        StatusCode::NOT_ACCEPTABLE.as_u16(),
+        detail,
+    ));
+    response
+        .headers_mut()
+        .insert(API_VERSION_HEADER, HeaderValue::from(API_VERSION));
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_path_versions() {
+        assert_eq!(path_version("/api/v1/jobs"), Some(Some(1)));
+        assert_eq!(path_version("/api/v2"), Some(Some(2)));
+        assert_eq!(path_version("/api/vx/jobs"), Some(None));
+        assert_eq!(path_version("/api/jobs"), None);
+    }
+
+    #[test]
+    fn test_route_path_strips_prefix() {
+        assert_eq!(route_path("/api/v1/health"), "/health");
+        assert_eq!(route_path("/api/health"), "/health");
+        assert_eq!(route_path("/api/v1/jobs/1/logs"), "/jobs/1/logs");
+        assert_eq!(route_path("/other"), "/other");
+    }
+}
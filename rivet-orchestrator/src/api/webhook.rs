@@ -0,0 +1,162 @@
+//! Webhook API Handlers
+//!
+//! HTTP endpoints for webhook triggers, and the endpoint Git providers
+//! deliver push events to. Deliveries carry no API credentials: they are
+//! verified against the trigger's secret instead.
+
+use axum::{
+    Json,
+    body::Bytes,
+    extract::{Path, State},
+    http::{HeaderMap, StatusCode},
+};
+use rivet_core::domain::webhook::WebhookTrigger;
+use rivet_core::dto::event::Event;
+use rivet_core::dto::webhook::{CreateWebhookTrigger, WebhookDelivery};
+use serde::Deserialize;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::api::auth::AdminAuth;
+use crate::api::error::{ApiError, ApiResult};
+use crate::api::job::map_launch_error;
+use crate::api::project::{IdPath, ProjectScope};
+use crate::events::EventBus;
+use crate::service::webhook_service::{self, Delivery, DeliveryOutcome};
+
+/// `{id}/webhooks/{trigger_id}` path parameters
+#[derive(Debug, Deserialize)]
+pub struct TriggerPath {
+    pub id: Uuid,
+    pub trigger_id: Uuid,
+}
+
+/// POST /pipeline/{id}/webhooks
+/// Add a webhook trigger to a pipeline (admin)
+pub async fn create_webhook_trigger(
+    State(pool): State<PgPool>,
+    _admin: AdminAuth,
+    scope: ProjectScope,
+    Path(IdPath { id }): Path<IdPath>,
+    Json(req): Json<CreateWebhookTrigger>,
+) -> ApiResult<(StatusCode, Json<WebhookTrigger>)> {
+    tracing::info!("Adding webhook trigger to pipeline: {}", id);
+
+    scope.ensure_pipeline(&pool, id).await?;
+
+    let trigger = webhook_service::create_trigger(&pool, id, req)
+        .await
+        .map_err(map_webhook_error)?;
+
+    Ok((StatusCode::CREATED, Json(trigger)))
+}
+
+/// GET /pipeline/{id}/webhooks
+/// List the webhook triggers of a pipeline
+pub async fn list_webhook_triggers(
+    State(pool): State<PgPool>,
+    scope: ProjectScope,
+    Path(IdPath { id }): Path<IdPath>,
+) -> ApiResult<Json<Vec<WebhookTrigger>>> {
+    tracing::debug!("Listing webhook triggers of pipeline: {}", id);
+
+    scope.ensure_pipeline(&pool, id).await?;
+
+    let triggers = webhook_service::list_triggers(&pool, id)
+        .await
+        .map_err(map_webhook_error)?;
+
+    Ok(Json(triggers))
+}
+
+/// DELETE /pipeline/{id}/webhooks/{trigger_id}
+/// Remove a webhook trigger from a pipeline (admin)
+pub async fn delete_webhook_trigger(
+    State(pool): State<PgPool>,
+    _admin: AdminAuth,
+    scope: ProjectScope,
+    Path(TriggerPath { id, trigger_id }): Path<TriggerPath>,
+) -> ApiResult<StatusCode> {
+    tracing::info!(
+        "Removing webhook trigger {} of pipeline: {}",
+        trigger_id,
+        id
+    );
+
+    scope.ensure_pipeline(&pool, id).await?;
+
+    webhook_service::delete_trigger(&pool, id, trigger_id)
+        .await
+        .map_err(map_webhook_error)?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// POST /webhooks/{id}
+/// Receive a delivery from GitHub or GitLab
+///
+/// Answers 202 with the launched job, or 200 with the reason a delivery
+/// was skipped (events other than pushes, pushes the filters reject).
+pub async fn receive_webhook(
+    State(pool): State<PgPool>,
+    State(events): State<EventBus>,
+    Path(IdPath { id }): Path<IdPath>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> ApiResult<(StatusCode, Json<WebhookDelivery>)> {
+    let header = |names: [&str; 2]| {
+        names
+            .iter()
+            .find_map(|name| headers.get(*name).and_then(|v| v.to_str().ok()))
+    };
+    let delivery = Delivery {
+        event: header(["X-GitHub-Event", "X-Gitlab-Event"]),
+        signature: header(["X-Hub-Signature-256", "X-Gitlab-Token"]),
+        body: &body,
+    };
+
+    tracing::debug!("Webhook delivery to trigger {}: {:?}", id, delivery.event);
+
+    let outcome = webhook_service::handle_delivery(&pool, id, delivery)
+        .await
+        .map_err(map_webhook_error)?;
+
+    Ok(match outcome {
+        DeliveryOutcome::Launched(job) => {
+            events.publish(Event::JobQueued {
+                job_id: job.id,
+                pipeline_id: job.pipeline_id,
+            });
+            (
+                StatusCode::ACCEPTED,
+                Json(WebhookDelivery {
+                    job_id: Some(job.id),
+                    skipped: None,
+                }),
+            )
+        }
+        DeliveryOutcome::Skipped(reason) => (
+            StatusCode::OK,
+            Json(WebhookDelivery {
+                job_id: None,
+                skipped: Some(reason),
+            }),
+        ),
+    })
+}
+
+fn map_webhook_error(e: webhook_service::WebhookError) -> ApiError {
+    match e {
+        webhook_service::WebhookError::NotFound(id) => {
+            ApiError::NotFound(format!("Webhook trigger {} not found", id))
+        }
+        webhook_service::WebhookError::PipelineNotFound(id) => {
+            ApiError::NotFound(format!("Pipeline {} not found", id))
+        }
+        webhook_service::WebhookError::Unauthorized(msg) => ApiError::Unauthorized(msg),
+        webhook_service::WebhookError::ValidationError(msg) => ApiError::BadRequest(msg),
+        webhook_service::WebhookError::InvalidFields(fields) => ApiError::InvalidFields(fields),
+        webhook_service::WebhookError::LaunchFailed(err) => map_launch_error(err),
+        webhook_service::WebhookError::DatabaseError(err) => ApiError::DatabaseError(err),
+    }
+}
@@ -0,0 +1,295 @@
+//! Commit Status Reporting
+//!
+//! Reports the status of the commits jobs build to GitHub or GitLab, as their
+//! pipeline configures it (`CommitStatusConfig`). Reports follow the job
+//! events of this orchestrator: pending when a job is queued or starts, then
+//! the job's outcome when it completes. A report that fails is logged and not
+//! retried; the job's next event reports again.
+
+use std::time::Duration;
+
+use rivet_core::domain::job::{CommitRef, JobStatus};
+use rivet_core::dto::commit_status::{CommitStatusConfig, GitProvider};
+use rivet_core::dto::event::Event;
+use sqlx::PgPool;
+use tokio::sync::broadcast;
+use uuid::Uuid;
+
+use crate::events::EventBus;
+use crate::repository::{job_repository, pipeline_repository, secret_repository};
+
+/// Time a provider has to accept a status report
+const REPORT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Reports commit statuses for the job events published on `events`, forever
+pub async fn report_commit_statuses(pool: PgPool, events: EventBus) {
+    let http = match reqwest::Client::builder()
+        .timeout(REPORT_TIMEOUT)
+        .user_agent(concat!("rivet-orchestrator/", env!("CARGO_PKG_VERSION")))
+        .build()
+    {
+        Ok(http) => http,
+        Err(e) => {
+            tracing::error!("Commit status reporting disabled: {}", e);
+            return;
+        }
+    };
+
+    let mut receiver = events.subscribe();
+    loop {
+        let (job_id, status) = match receiver.recv().await {
+            Ok(Event::JobQueued { job_id, .. }) => (job_id, JobStatus::Queued),
+            Ok(Event::JobStarted { job_id, .. }) => (job_id, JobStatus::Running),
+            Ok(Event::JobCompleted { job_id, status, .. }) => (job_id, status),
+            Ok(_) => continue,
+            Err(broadcast::error::RecvError::Lagged(missed)) => {
+                tracing::warn!("Commit status reporting missed {} events", missed);
+                continue;
+            }
+            Err(broadcast::error::RecvError::Closed) => return,
+        };
+
+        if let Err(e) = report(&pool, &http, job_id, status).await {
+            tracing::warn!("Failed to report commit status of job {}: {}", job_id, e);
+        }
+    }
+}
+
+/// Reports `status` for the commit of a job, if it has one and its pipeline
+/// reports commit statuses
+async fn report(
+    pool: &PgPool,
+    http: &reqwest::Client,
+    job_id: Uuid,
+    status: JobStatus,
+) -> Result<(), String> {
+    let Some(job) = job_repository::find_by_id(pool, job_id)
+        .await
+        .map_err(|e| e.to_string())?
+    else {
+        return Ok(());
+    };
+    let Some(commit) = job.commit else {
+        return Ok(());
+    };
+    let Some(Some(config)) = pipeline_repository::find_commit_status(pool, job.pipeline_id)
+        .await
+        .map_err(|e| e.to_string())?
+    else {
+        return Ok(());
+    };
+    let Some(pipeline) = pipeline_repository::find_by_id(pool, job.pipeline_id)
+        .await
+        .map_err(|e| e.to_string())?
+    else {
+        return Ok(());
+    };
+
+    let secrets =
+        secret_repository::find_values_for_pipeline(pool, pipeline.project_id, pipeline.id)
+            .await
+            .map_err(|e| e.to_string())?;
+    let token = secrets
+        .get(&config.token_secret)
+        .ok_or_else(|| format!("Secret '{}' not found", config.token_secret))?;
+
+    let request = StatusRequest::new(&config, &pipeline.name, &commit, job_id, job.number, status);
+    let builder = http.post(&request.url).json(&request.body);
+    let builder = match config.provider {
+        GitProvider::Github => builder
+            .bearer_auth(token)
+            .header("Accept", "application/vnd.github+json"),
+        GitProvider::Gitlab => builder.header("PRIVATE-TOKEN", token),
+    };
+
+    let response = builder.send().await.map_err(|e| e.to_string())?;
+    if !response.status().is_success() {
+        return Err(format!(
+            "{} answered {}",
+            config.provider.as_str(),
+            response.status()
+        ));
+    }
+
+    tracing::debug!(
+        "Reported {:?} for commit {} of {}",
+        status,
+        commit.sha,
+        commit.repository
+    );
+
+    Ok(())
+}
+
+/// A commit status report, as the provider's API expects it
+struct StatusRequest {
+    url: String,
+    body: serde_json::Value,
+}
+
+impl StatusRequest {
+    /// Builds the report of `status` for job `job_id`, number `job_number` of
+    /// its pipeline
+    ///
+    /// The description names the job by number, and the `{job_id}` and
+    /// `{job_number}` placeholders of the configured target URL are replaced.
+    fn new(
+        config: &CommitStatusConfig,
+        pipeline_name: &str,
+        commit: &CommitRef,
+        job_id: Uuid,
+        job_number: u64,
+        status: JobStatus,
+    ) -> Self {
+        let api_url = config
+            .api_url
+            .as_deref()
+            .unwrap_or(config.provider.default_api_url())
+            .trim_end_matches('/');
+        let context = config
+            .context
+            .clone()
+            .unwrap_or_else(|| format!("rivet/{}", pipeline_name));
+        let description = format!("#{}: {}", job_number, describe(status));
+
+        let (url, mut body) = match config.provider {
+            GitProvider::Github => (
+                format!(
+                    "{}/repos/{}/statuses/{}",
+                    api_url, commit.repository, commit.sha
+                ),
+                serde_json::json!({
+                    "state": github_state(status),
+                    "context": context,
+                    "description": description,
+                }),
+            ),
+            GitProvider::Gitlab => (
+                format!(
+                    "{}/projects/{}/statuses/{}",
+                    api_url,
+                    commit.repository.replace('/', "%2F"),
+                    commit.sha
+                ),
+                serde_json::json!({
+                    "state": gitlab_state(status),
+                    "name": context,
+                    "description": description,
+                }),
+            ),
+        };
+        if let Some(target_url) = &config.target_url {
+            body["target_url"] = target_url
+                .replace("{job_id}", &job_id.to_string())
+                .replace("{job_number}", &job_number.to_string())
+                .into();
+        }
+
+        Self { url, body }
+    }
+}
+
+/// GitHub commit state of a job status
+fn github_state(status: JobStatus) -> &'static str {
+    match status {
+        JobStatus::Queued | JobStatus::Blocked | JobStatus::Running => "pending",
+        JobStatus::Succeeded => "success",
+        JobStatus::Failed | JobStatus::TimedOut | JobStatus::DeadLettered => "failure",
+        JobStatus::Cancelled => "error",
+    }
+}
+
+/// GitLab commit state of a job status
+fn gitlab_state(status: JobStatus) -> &'static str {
+    match status {
+        JobStatus::Queued | JobStatus::Blocked => "pending",
+        JobStatus::Running => "running",
+        JobStatus::Succeeded => "success",
+        JobStatus::Failed | JobStatus::TimedOut | JobStatus::DeadLettered => "failed",
+        JobStatus::Cancelled => "canceled",
+    }
+}
+
+/// Description shown with a commit status
+fn describe(status: JobStatus) -> &'static str {
+    match status {
+        JobStatus::Queued => "Queued",
+        JobStatus::Running => "Running",
+        JobStatus::Succeeded => "Succeeded",
+        JobStatus::Failed => "Failed",
+        JobStatus::Cancelled => "Cancelled",
+        JobStatus::TimedOut => "Timed out",
+        JobStatus::DeadLettered => "Gave up after repeated failures",
+        JobStatus::Blocked => "Waiting for an environment lock",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn commit() -> CommitRef {
+        CommitRef {
+            repository: "acme/api".to_string(),
+            sha: "4f2b8c1d9e".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_github_status_request() {
+        let config = CommitStatusConfig {
+            provider: GitProvider::Github,
+            token_secret: "GITHUB_TOKEN".to_string(),
+            context: None,
+            api_url: None,
+            target_url: Some(
+                "https://ci.example.com/jobs/{job_id}?number={job_number}".to_string(),
+            ),
+        };
+        let job_id = Uuid::new_v4();
+
+        let request =
+            StatusRequest::new(&config, "build", &commit(), job_id, 42, JobStatus::Running);
+
+        assert_eq!(
+            request.url,
+            "https://api.github.com/repos/acme/api/statuses/4f2b8c1d9e"
+        );
+        assert_eq!(request.body["state"], "pending");
+        assert_eq!(request.body["context"], "rivet/build");
+        assert_eq!(request.body["description"], "#42: Running");
+        assert_eq!(
+            request.body["target_url"],
+            format!("https://ci.example.com/jobs/{}?number=42", job_id)
+        );
+    }
+
+    #[test]
+    fn test_gitlab_status_request() {
+        let config = CommitStatusConfig {
+            provider: GitProvider::Gitlab,
+            token_secret: "GITLAB_TOKEN".to_string(),
+            context: Some("ci/rivet".to_string()),
+            api_url: Some("https://git.example.com/api/v4/".to_string()),
+            target_url: None,
+        };
+
+        let request = StatusRequest::new(
+            &config,
+            "build",
+            &commit(),
+            Uuid::new_v4(),
+            7,
+            JobStatus::TimedOut,
+        );
+
+        assert_eq!(
+            request.url,
+            "https://git.example.com/api/v4/projects/acme%2Fapi/statuses/4f2b8c1d9e"
+        );
+        assert_eq!(request.body["state"], "failed");
+        assert_eq!(request.body["name"], "ci/rivet");
+        assert_eq!(request.body["description"], "#7: Timed out");
+        assert!(request.body.get("target_url").is_none());
+    }
+}
@@ -1,15 +1,559 @@
-use sqlx::{PgPool, postgres::PgPoolOptions};
+use rivet_core::domain::project::{DEFAULT_PROJECT_ID, DEFAULT_PROJECT_NAME};
+use sqlx::{PgConnection, PgPool, postgres::PgPoolOptions};
+use std::fmt;
 use std::time::Duration;
 
+use crate::config::DatabaseConfig;
+
+// =============================================================================
+// Migrations
+// =============================================================================
+
+/// A versioned schema change
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Migration {
+    pub version: i32,
+    pub description: &'static str,
+    /// Whether `migrate_down` can revert it
+    pub reversible: bool,
+}
+
+/// Every migration, oldest first
+///
+/// Schema changes are added as a new migration at the end, with its
+/// statements in `apply` and, when it can be undone, `revert`.
+pub const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        description: "baseline schema",
+        reversible: false,
+    },
+    Migration {
+        version: 2,
+        description: "runner version and platform",
+        reversible: true,
+    },
+    Migration {
+        version: 3,
+        description: "archived job logs",
+        // Dropping the table would lose track of the archived logs
+        reversible: false,
+    },
+    Migration {
+        version: 4,
+        description: "log search index",
+        reversible: true,
+    },
+    Migration {
+        version: 5,
+        description: "job claim tokens",
+        reversible: true,
+    },
+    Migration {
+        version: 6,
+        description: "stage results",
+        reversible: true,
+    },
+    Migration {
+        version: 7,
+        description: "job schedules",
+        reversible: true,
+    },
+    Migration {
+        version: 8,
+        description: "structured log fields",
+        reversible: true,
+    },
+    Migration {
+        version: 9,
+        description: "job labels",
+        reversible: true,
+    },
+    Migration {
+        version: 10,
+        description: "job numbers",
+        reversible: true,
+    },
+    Migration {
+        version: 11,
+        description: "pipeline archiving",
+        reversible: true,
+    },
+    Migration {
+        version: 12,
+        description: "pipeline disabling",
+        reversible: true,
+    },
+    Migration {
+        version: 13,
+        description: "parameter presets",
+        reversible: true,
+    },
+];
+
+/// Version of the schema this orchestrator migrates to, the latest migration
+pub const SCHEMA_VERSION: i32 = MIGRATIONS[MIGRATIONS.len() - 1].version;
+
+/// Advisory lock key held while migrating, so that replicas starting
+/// together migrate one at a time
+const MIGRATION_LOCK_KEY: i64 = 0x7269_7665_745f_6462;
+
+/// Error reverting migrations
+#[derive(Debug)]
+pub enum MigrationError {
+    /// The migration cannot be reverted
+    Irreversible(Migration),
+    /// More migrations were asked to be reverted than are applied
+    TooMany {
+        requested: usize,
+        applied: usize,
+    },
+    /// The database was migrated by a newer orchestrator
+    UnknownVersion(i32),
+    Database(sqlx::Error),
+}
+
+impl fmt::Display for MigrationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MigrationError::Irreversible(migration) => write!(
+                f,
+                "Migration {} ({}) cannot be reverted",
+                migration.version, migration.description
+            ),
+            MigrationError::TooMany { requested, applied } => write!(
+                f,
+                "Cannot revert {} migrations, only {} are applied",
+                requested, applied
+            ),
+            MigrationError::UnknownVersion(version) => write!(
+                f,
+                "Schema version {} is newer than this orchestrator's ({})",
+                version, SCHEMA_VERSION
+            ),
+            MigrationError::Database(e) => write!(f, "Database error: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for MigrationError {}
+
+impl From<sqlx::Error> for MigrationError {
+    fn from(e: sqlx::Error) -> Self {
+        MigrationError::Database(e)
+    }
+}
+
 pub async fn create_pool(database_url: &str) -> Result<PgPool, sqlx::Error> {
+    create_pool_with(&DatabaseConfig {
+        url: database_url.to_string(),
+        ..DatabaseConfig::default()
+    })
+    .await
+}
+
+/// Creates a connection pool sized as configured in `[database]`
+pub async fn create_pool_with(config: &DatabaseConfig) -> Result<PgPool, sqlx::Error> {
     PgPoolOptions::new()
-        .max_connections(10)
-        .acquire_timeout(Duration::from_secs(5))
-        .connect(database_url)
+        .max_connections(config.max_connections)
+        .min_connections(config.min_connections)
+        .acquire_timeout(Duration::from_secs(config.acquire_timeout_secs))
+        .connect(&config.url)
         .await
 }
 
+/// Applies every pending migration
 pub async fn run_migrations(pool: &PgPool) -> Result<(), sqlx::Error> {
+    migrate_up(pool).await?;
+    tracing::info!("Database migrations completed successfully");
+    Ok(())
+}
+
+/// Applies the migrations newer than the database's schema version
+///
+/// Each migration runs in its own transaction along with the version bump,
+/// under an advisory lock so that concurrent callers apply it once.
+///
+/// # Returns
+/// The migrations applied, oldest first
+pub async fn migrate_up(pool: &PgPool) -> Result<Vec<Migration>, sqlx::Error> {
+    let mut applied = Vec::new();
+    let mut current = 0;
+
+    for migration in MIGRATIONS {
+        let mut tx = pool.begin().await?;
+        current = lock_schema(&mut tx).await?;
+        if migration.version <= current {
+            continue;
+        }
+
+        apply(&mut tx, migration.version).await?;
+        set_schema_version(&mut tx, migration.version).await?;
+        tx.commit().await?;
+
+        tracing::info!(
+            "Applied migration {}: {}",
+            migration.version,
+            migration.description
+        );
+        current = migration.version;
+        applied.push(*migration);
+    }
+
+    if current > SCHEMA_VERSION {
+        tracing::warn!(
+            "Schema version {} is newer than this orchestrator's ({})",
+            current,
+            SCHEMA_VERSION
+        );
+    }
+    Ok(applied)
+}
+
+/// Reverts the `count` most recently applied migrations
+///
+/// The migrations are reverted in a single transaction: if one cannot be
+/// reverted, none is.
+///
+/// # Returns
+/// The migrations reverted, newest first
+pub async fn migrate_down(pool: &PgPool, count: usize) -> Result<Vec<Migration>, MigrationError> {
+    let mut tx = pool.begin().await?;
+    let current = lock_schema(&mut tx).await?;
+    if current > SCHEMA_VERSION {
+        return Err(MigrationError::UnknownVersion(current));
+    }
+
+    let applied: Vec<Migration> = MIGRATIONS
+        .iter()
+        .rev()
+        .filter(|m| m.version <= current)
+        .copied()
+        .collect();
+    if count > applied.len() {
+        return Err(MigrationError::TooMany {
+            requested: count,
+            applied: applied.len(),
+        });
+    }
+
+    let (reverted, remaining) = applied.split_at(count);
+    if let Some(migration) = reverted.iter().find(|m| !m.reversible) {
+        return Err(MigrationError::Irreversible(*migration));
+    }
+
+    for migration in reverted {
+        revert(&mut tx, migration.version).await?;
+        tracing::info!(
+            "Reverted migration {}: {}",
+            migration.version,
+            migration.description
+        );
+    }
+    set_schema_version(&mut tx, remaining.first().map_or(0, |m| m.version)).await?;
+    tx.commit().await?;
+
+    Ok(reverted.to_vec())
+}
+
+/// Takes the migration lock for the transaction's duration
+///
+/// # Returns
+/// The schema version, 0 if the database was never migrated
+async fn lock_schema(conn: &mut PgConnection) -> Result<i32, sqlx::Error> {
+    sqlx::query("SELECT pg_advisory_xact_lock($1)")
+        .bind(MIGRATION_LOCK_KEY)
+        .execute(&mut *conn)
+        .await?;
+
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS schema_version (
+            singleton BOOLEAN PRIMARY KEY DEFAULT TRUE CHECK (singleton),
+            version INTEGER NOT NULL,
+            migrated_at TIMESTAMPTZ NOT NULL
+        )
+        "#,
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    let version: Option<i32> = sqlx::query_scalar("SELECT version FROM schema_version")
+        .fetch_optional(&mut *conn)
+        .await?;
+    Ok(version.unwrap_or(0))
+}
+
+async fn set_schema_version(conn: &mut PgConnection, version: i32) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        r#"
+        INSERT INTO schema_version (singleton, version, migrated_at)
+        VALUES (TRUE, $1, NOW())
+        ON CONFLICT (singleton) DO UPDATE
+        SET version = EXCLUDED.version, migrated_at = EXCLUDED.migrated_at
+        "#,
+    )
+    .bind(version)
+    .execute(&mut *conn)
+    .await?;
+    Ok(())
+}
+
+/// Runs the statements of migration `version`
+async fn apply(conn: &mut PgConnection, version: i32) -> Result<(), sqlx::Error> {
+    match version {
+        1 => baseline(conn).await?,
+        2 => {
+            // Version, platform and host runners report when they register
+            sqlx::query("ALTER TABLE runners ADD COLUMN IF NOT EXISTS info JSONB")
+                .execute(&mut *conn)
+                .await?;
+        }
+        3 => {
+            // Logs of completed jobs moved to object storage. No foreign key:
+            // archives of deleted jobs are found, and their objects deleted,
+            // by the log compaction.
+            sqlx::query(
+                r#"
+                CREATE TABLE IF NOT EXISTS job_log_archives (
+                    job_id UUID PRIMARY KEY,
+                    object_key TEXT NOT NULL,
+                    entries BIGINT NOT NULL,
+                    bytes BIGINT NOT NULL,
+                    archived_at TIMESTAMPTZ NOT NULL
+                )
+                "#,
+            )
+            .execute(&mut *conn)
+            .await?;
+        }
+        4 => {
+            // Full-text index for log search; `simple` neither stems nor
+            // drops stop words, which suits log lines
+            sqlx::query(
+                "CREATE INDEX IF NOT EXISTS idx_job_logs_message_search ON job_logs USING GIN (to_tsvector('simple', message))",
+            )
+            .execute(&mut *conn)
+            .await?;
+        }
+        5 => {
+            // Fencing token bumped on every claim of a job, so that a runner
+            // that lost its claim cannot complete the job or log to it
+            sqlx::query(
+                "ALTER TABLE jobs ADD COLUMN IF NOT EXISTS claim_token BIGINT NOT NULL DEFAULT 0",
+            )
+            .execute(&mut *conn)
+            .await?;
+        }
+        6 => {
+            // Result table the stage script returned
+            sqlx::query("ALTER TABLE job_stages ADD COLUMN IF NOT EXISTS result JSONB")
+                .execute(&mut *conn)
+                .await?;
+        }
+        7 => {
+            // Schedule that launched the job, shown to its scripts
+            sqlx::query(
+                "ALTER TABLE jobs ADD COLUMN IF NOT EXISTS schedule_id UUID REFERENCES pipeline_schedules(id) ON DELETE SET NULL",
+            )
+            .execute(&mut *conn)
+            .await?;
+        }
+        8 => {
+            // Structured fields scripts attach to their log entries
+            sqlx::query("ALTER TABLE job_logs ADD COLUMN IF NOT EXISTS fields JSONB")
+                .execute(&mut *conn)
+                .await?;
+        }
+        9 => {
+            // Free-form labels, indexed for `labels @> ...` filters
+            sqlx::query(
+                "ALTER TABLE jobs ADD COLUMN IF NOT EXISTS labels JSONB NOT NULL DEFAULT '{}'",
+            )
+            .execute(&mut *conn)
+            .await?;
+            sqlx::query("CREATE INDEX IF NOT EXISTS idx_jobs_labels ON jobs USING GIN (labels)")
+                .execute(&mut *conn)
+                .await?;
+        }
+        10 => {
+            // Number of each job within its pipeline, existing jobs numbered
+            // in the order they were requested
+            sqlx::query("ALTER TABLE jobs ADD COLUMN IF NOT EXISTS number BIGINT")
+                .execute(&mut *conn)
+                .await?;
+            sqlx::query(
+                r#"
+                UPDATE jobs SET number = numbered.number
+                FROM (
+                    SELECT id, row_number() OVER (PARTITION BY pipeline_id ORDER BY requested_at, id) AS number
+                    FROM jobs
+                ) numbered
+                WHERE jobs.id = numbered.id AND jobs.number IS NULL
+                "#,
+            )
+            .execute(&mut *conn)
+            .await?;
+            sqlx::query("ALTER TABLE jobs ALTER COLUMN number SET NOT NULL")
+                .execute(&mut *conn)
+                .await?;
+            sqlx::query(
+                "CREATE UNIQUE INDEX IF NOT EXISTS idx_jobs_pipeline_number ON jobs (pipeline_id, number)",
+            )
+            .execute(&mut *conn)
+            .await?;
+
+            // Last number given to a job of each pipeline
+            sqlx::query(
+                "ALTER TABLE pipelines ADD COLUMN IF NOT EXISTS last_job_number BIGINT NOT NULL DEFAULT 0",
+            )
+            .execute(&mut *conn)
+            .await?;
+            sqlx::query(
+                r#"
+                UPDATE pipelines SET last_job_number = (
+                    SELECT COALESCE(MAX(number), 0) FROM jobs WHERE jobs.pipeline_id = pipelines.id
+                )
+                "#,
+            )
+            .execute(&mut *conn)
+            .await?;
+        }
+        11 => {
+            // When a pipeline was archived, NULL while it is active
+            sqlx::query("ALTER TABLE pipelines ADD COLUMN IF NOT EXISTS archived_at TIMESTAMPTZ")
+                .execute(&mut *conn)
+                .await?;
+        }
+        12 => {
+            // Pipelines whose new launches are paused
+            sqlx::query(
+                "ALTER TABLE pipelines ADD COLUMN IF NOT EXISTS disabled BOOLEAN NOT NULL DEFAULT FALSE",
+            )
+            .execute(&mut *conn)
+            .await?;
+        }
+        13 => {
+            // Named parameter sets users save to launch a pipeline with
+            sqlx::query(
+                r#"
+                CREATE TABLE IF NOT EXISTS pipeline_presets (
+                    pipeline_id UUID NOT NULL REFERENCES pipelines(id) ON DELETE CASCADE,
+                    name VARCHAR(64) NOT NULL,
+                    parameters JSONB NOT NULL,
+                    created_at TIMESTAMPTZ NOT NULL,
+                    updated_at TIMESTAMPTZ NOT NULL,
+                    PRIMARY KEY (pipeline_id, name)
+                )
+                "#,
+            )
+            .execute(&mut *conn)
+            .await?;
+        }
+        _ => unreachable!("migration {} is not defined", version),
+    }
+    Ok(())
+}
+
+/// Undoes migration `version`, which must be reversible
+async fn revert(conn: &mut PgConnection, version: i32) -> Result<(), sqlx::Error> {
+    match version {
+        2 => {
+            sqlx::query("ALTER TABLE runners DROP COLUMN IF EXISTS info")
+                .execute(&mut *conn)
+                .await?;
+        }
+        4 => {
+            sqlx::query("DROP INDEX IF EXISTS idx_job_logs_message_search")
+                .execute(&mut *conn)
+                .await?;
+        }
+        5 => {
+            sqlx::query("ALTER TABLE jobs DROP COLUMN IF EXISTS claim_token")
+                .execute(&mut *conn)
+                .await?;
+        }
+        6 => {
+            sqlx::query("ALTER TABLE job_stages DROP COLUMN IF EXISTS result")
+                .execute(&mut *conn)
+                .await?;
+        }
+        7 => {
+            sqlx::query("ALTER TABLE jobs DROP COLUMN IF EXISTS schedule_id")
+                .execute(&mut *conn)
+                .await?;
+        }
+        8 => {
+            sqlx::query("ALTER TABLE job_logs DROP COLUMN IF EXISTS fields")
+                .execute(&mut *conn)
+                .await?;
+        }
+        9 => {
+            // Dropping the column drops its index
+            sqlx::query("ALTER TABLE jobs DROP COLUMN IF EXISTS labels")
+                .execute(&mut *conn)
+                .await?;
+        }
+        10 => {
+            sqlx::query("ALTER TABLE pipelines DROP COLUMN IF EXISTS last_job_number")
+                .execute(&mut *conn)
+                .await?;
+            sqlx::query("ALTER TABLE jobs DROP COLUMN IF EXISTS number")
+                .execute(&mut *conn)
+                .await?;
+        }
+        11 => {
+            sqlx::query("ALTER TABLE pipelines DROP COLUMN IF EXISTS archived_at")
+                .execute(&mut *conn)
+                .await?;
+        }
+        12 => {
+            sqlx::query("ALTER TABLE pipelines DROP COLUMN IF EXISTS disabled")
+                .execute(&mut *conn)
+                .await?;
+        }
+        13 => {
+            sqlx::query("DROP TABLE IF EXISTS pipeline_presets")
+                .execute(&mut *conn)
+                .await?;
+        }
+        _ => unreachable!("migration {} is not reversible", version),
+    }
+    Ok(())
+}
+
+/// Migration 1: the schema as it stood before migrations were versioned
+///
+/// Its statements are idempotent so that it also completes databases
+/// created by orchestrators that predate versioning.
+async fn baseline(conn: &mut PgConnection) -> Result<(), sqlx::Error> {
+    // Create projects table
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS projects (
+            id UUID PRIMARY KEY,
+            name VARCHAR(64) NOT NULL UNIQUE,
+            description TEXT,
+            created_at TIMESTAMPTZ NOT NULL
+        )
+        "#,
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    // Seed the default project that owns resources created without a project
+    sqlx::query(
+        r#"
+        INSERT INTO projects (id, name, description, created_at)
+        VALUES ($1, $2, 'Default project', NOW())
+        ON CONFLICT DO NOTHING
+        "#,
+    )
+    .bind(DEFAULT_PROJECT_ID)
+    .bind(DEFAULT_PROJECT_NAME)
+    .execute(&mut *conn)
+    .await?;
+
     // Create pipelines table
     sqlx::query(
         r#"
@@ -24,7 +568,51 @@ pub async fn run_migrations(pool: &PgPool) -> Result<(), sqlx::Error> {
         )
         "#,
     )
-    .execute(pool)
+    .execute(&mut *conn)
+    .await?;
+
+    // Pipelines belong to a project (existing rows move to the default project)
+    sqlx::query(&format!(
+        r#"
+        ALTER TABLE pipelines ADD COLUMN IF NOT EXISTS project_id UUID NOT NULL
+            DEFAULT '{}' REFERENCES projects(id) ON DELETE CASCADE
+        "#,
+        DEFAULT_PROJECT_ID
+    ))
+    .execute(&mut *conn)
+    .await?;
+
+    // Plugins a runner must provide to run the pipeline
+    sqlx::query(
+        "ALTER TABLE pipelines ADD COLUMN IF NOT EXISTS plugins JSONB NOT NULL DEFAULT '[]'",
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    // Input defaults and overrides set on the orchestrator (`PipelineDefaults`)
+    sqlx::query(
+        "ALTER TABLE pipelines ADD COLUMN IF NOT EXISTS input_defaults JSONB NOT NULL DEFAULT '{}'",
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    // Limits on the artifacts kept for the pipeline (`ArtifactRetention`)
+    sqlx::query(
+        "ALTER TABLE pipelines ADD COLUMN IF NOT EXISTS artifact_retention JSONB NOT NULL DEFAULT '{}'",
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    // Commit status reporting of the pipeline (`CommitStatusConfig`), if any
+    sqlx::query("ALTER TABLE pipelines ADD COLUMN IF NOT EXISTS commit_status JSONB")
+        .execute(&mut *conn)
+        .await?;
+
+    // Environment each deploying stage deploys to, by stage name (`deploys_to`)
+    sqlx::query(
+        "ALTER TABLE pipelines ADD COLUMN IF NOT EXISTS deploy_stages JSONB NOT NULL DEFAULT '{}'",
+    )
+    .execute(&mut *conn)
     .await?;
 
     // Create jobs table
@@ -46,7 +634,66 @@ pub async fn run_migrations(pool: &PgPool) -> Result<(), sqlx::Error> {
         )
         "#,
     )
-    .execute(pool)
+    .execute(&mut *conn)
+    .await?;
+
+    // Running jobs hold a lease renewed by their runner; expired leases are requeued
+    sqlx::query("ALTER TABLE jobs ADD COLUMN IF NOT EXISTS lease_expires_at TIMESTAMPTZ")
+        .execute(&mut *conn)
+        .await?;
+
+    // Automatic requeues bump the attempt; jobs that keep failing are dead-lettered
+    sqlx::query("ALTER TABLE jobs ADD COLUMN IF NOT EXISTS attempt INTEGER NOT NULL DEFAULT 1")
+        .execute(&mut *conn)
+        .await?;
+    sqlx::query("ALTER TABLE jobs ADD COLUMN IF NOT EXISTS dead_letter_reason TEXT")
+        .execute(&mut *conn)
+        .await?;
+
+    // Failed jobs are retried up to max_attempts, not before the backoff elapsed
+    sqlx::query(
+        "ALTER TABLE jobs ADD COLUMN IF NOT EXISTS max_attempts INTEGER NOT NULL DEFAULT 1",
+    )
+    .execute(&mut *conn)
+    .await?;
+    sqlx::query("ALTER TABLE jobs ADD COLUMN IF NOT EXISTS not_before TIMESTAMPTZ")
+        .execute(&mut *conn)
+        .await?;
+
+    // Values of secret inputs, kept apart from the parameters shown to users
+    sqlx::query(
+        "ALTER TABLE jobs ADD COLUMN IF NOT EXISTS secret_parameters JSONB NOT NULL DEFAULT '{}'",
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    // Trace context of the request that launched the job, continued by the runner
+    sqlx::query("ALTER TABLE jobs ADD COLUMN IF NOT EXISTS traceparent TEXT")
+        .execute(&mut *conn)
+        .await?;
+
+    // A resumed job skips the stages that succeeded in the job it resumes
+    sqlx::query(
+        "ALTER TABLE jobs ADD COLUMN IF NOT EXISTS resumed_from UUID REFERENCES jobs(id) ON DELETE SET NULL",
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    // Commit the job builds, whose status is reported to its Git provider
+    sqlx::query("ALTER TABLE jobs ADD COLUMN IF NOT EXISTS commit_ref JSONB")
+        .execute(&mut *conn)
+        .await?;
+
+    // Version (MD5 of the script) of the pipeline the job's current attempt runs
+    sqlx::query("ALTER TABLE jobs ADD COLUMN IF NOT EXISTS script_hash TEXT")
+        .execute(&mut *conn)
+        .await?;
+
+    // Jobs launched to run even during blackout windows
+    sqlx::query(
+        "ALTER TABLE jobs ADD COLUMN IF NOT EXISTS override_blackout BOOLEAN NOT NULL DEFAULT FALSE",
+    )
+    .execute(&mut *conn)
     .await?;
 
     // Create logs table
@@ -61,26 +708,140 @@ pub async fn run_migrations(pool: &PgPool) -> Result<(), sqlx::Error> {
         )
         "#,
     )
-    .execute(pool)
+    .execute(&mut *conn)
     .await?;
 
     // Create indexes for better query performance
+    sqlx::query("CREATE INDEX IF NOT EXISTS idx_pipelines_project_id ON pipelines(project_id)")
+        .execute(&mut *conn)
+        .await?;
+
     sqlx::query("CREATE INDEX IF NOT EXISTS idx_jobs_status ON jobs(status)")
-        .execute(pool)
+        .execute(&mut *conn)
         .await?;
 
     sqlx::query("CREATE INDEX IF NOT EXISTS idx_jobs_pipeline_id ON jobs(pipeline_id)")
-        .execute(pool)
+        .execute(&mut *conn)
         .await?;
 
     sqlx::query("CREATE INDEX IF NOT EXISTS idx_jobs_requested_at ON jobs(requested_at DESC)")
-        .execute(pool)
+        .execute(&mut *conn)
         .await?;
 
     sqlx::query("CREATE INDEX IF NOT EXISTS idx_job_logs_job_id ON job_logs(job_id, timestamp)")
-        .execute(pool)
+        .execute(&mut *conn)
+        .await?;
+
+    // Position of each entry in its job's log, for incremental fetches.
+    // Existing rows are numbered in physical order, close enough to insertion.
+    sqlx::query("ALTER TABLE job_logs ADD COLUMN IF NOT EXISTS sequence BIGSERIAL")
+        .execute(&mut *conn)
+        .await?;
+
+    sqlx::query(
+        "CREATE INDEX IF NOT EXISTS idx_job_logs_job_sequence ON job_logs(job_id, sequence)",
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    sqlx::query("ALTER TABLE job_logs ADD COLUMN IF NOT EXISTS stage TEXT")
+        .execute(&mut *conn)
+        .await?;
+
+    sqlx::query("ALTER TABLE job_logs ADD COLUMN IF NOT EXISTS stream VARCHAR(20)")
+        .execute(&mut *conn)
+        .await?;
+
+    // Create stages table, one row per stage of each attempt of a job
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS job_stages (
+            job_id UUID NOT NULL REFERENCES jobs(id) ON DELETE CASCADE,
+            attempt INTEGER NOT NULL,
+            name VARCHAR(255) NOT NULL,
+            status VARCHAR(20) NOT NULL,
+            started_at TIMESTAMPTZ NOT NULL,
+            completed_at TIMESTAMPTZ,
+            error_message TEXT,
+            PRIMARY KEY (job_id, attempt, name)
+        )
+        "#,
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    sqlx::query(
+        r#"
+        ALTER TABLE job_stages ADD COLUMN IF NOT EXISTS needs TEXT[] NOT NULL DEFAULT '{}'
+        "#,
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    // Pipeline version the stage ran, to tell flaky stages from changed ones
+    sqlx::query("ALTER TABLE job_stages ADD COLUMN IF NOT EXISTS script_hash TEXT")
+        .execute(&mut *conn)
         .await?;
 
+    // Create artifacts table
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS job_artifacts (
+            id UUID PRIMARY KEY,
+            job_id UUID NOT NULL REFERENCES jobs(id) ON DELETE CASCADE,
+            name VARCHAR(255) NOT NULL,
+            size_bytes BIGINT NOT NULL,
+            sha256 VARCHAR(64) NOT NULL,
+            content BYTEA NOT NULL,
+            created_at TIMESTAMPTZ NOT NULL,
+            UNIQUE (job_id, name)
+        )
+        "#,
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    // Create secrets table
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS secrets (
+            id UUID PRIMARY KEY,
+            name VARCHAR(255) NOT NULL,
+            pipeline_id UUID REFERENCES pipelines(id) ON DELETE CASCADE,
+            value TEXT NOT NULL,
+            created_at TIMESTAMPTZ NOT NULL,
+            updated_at TIMESTAMPTZ NOT NULL
+        )
+        "#,
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    // Secrets belong to a project (existing rows move to the default project)
+    sqlx::query(&format!(
+        r#"
+        ALTER TABLE secrets ADD COLUMN IF NOT EXISTS project_id UUID NOT NULL
+            DEFAULT '{}' REFERENCES projects(id) ON DELETE CASCADE
+        "#,
+        DEFAULT_PROJECT_ID
+    ))
+    .execute(&mut *conn)
+    .await?;
+
+    // One secret per project, name and scope (NULL pipeline_id = project-wide scope)
+    sqlx::query("DROP INDEX IF EXISTS idx_secrets_name_scope")
+        .execute(&mut *conn)
+        .await?;
+
+    sqlx::query(
+        r#"
+        CREATE UNIQUE INDEX IF NOT EXISTS idx_secrets_project_name_scope
+        ON secrets(project_id, name, COALESCE(pipeline_id, '00000000-0000-0000-0000-000000000000'::uuid))
+        "#,
+    )
+    .execute(&mut *conn)
+    .await?;
+
     // Create runners table
     sqlx::query(
         r#"
@@ -92,20 +853,228 @@ pub async fn run_migrations(pool: &PgPool) -> Result<(), sqlx::Error> {
         )
         "#,
     )
-    .execute(pool)
+    .execute(&mut *conn)
+    .await?;
+
+    // Runners authenticate job calls with the credential issued on registration
+    sqlx::query("ALTER TABLE runners ADD COLUMN IF NOT EXISTS credential_hash VARCHAR(64)")
+        .execute(&mut *conn)
+        .await?;
+
+    // Capabilities runners provide, matched against what pipelines require
+    sqlx::query("ALTER TABLE runners ADD COLUMN IF NOT EXISTS tags JSONB NOT NULL DEFAULT '[]'")
+        .execute(&mut *conn)
+        .await?;
+
+    sqlx::query("ALTER TABLE runners ADD COLUMN IF NOT EXISTS plugins JSONB NOT NULL DEFAULT '[]'")
+        .execute(&mut *conn)
+        .await?;
+
+    // Load reported with each heartbeat, used to spread jobs across runners
+    sqlx::query("ALTER TABLE runners ADD COLUMN IF NOT EXISTS load JSONB")
+        .execute(&mut *conn)
+        .await?;
+
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS runner_registration_tokens (
+            id UUID PRIMARY KEY,
+            description TEXT,
+            token_hash VARCHAR(64) NOT NULL UNIQUE,
+            created_at TIMESTAMPTZ NOT NULL
+        )
+        "#,
+    )
+    .execute(&mut *conn)
     .await?;
 
     // Create indexes for runner queries
     sqlx::query("CREATE INDEX IF NOT EXISTS idx_runners_status ON runners(status)")
-        .execute(pool)
+        .execute(&mut *conn)
         .await?;
 
     sqlx::query(
         "CREATE INDEX IF NOT EXISTS idx_runners_last_heartbeat ON runners(last_heartbeat_at)",
     )
-    .execute(pool)
+    .execute(&mut *conn)
+    .await?;
+
+    // Create environments table
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS environments (
+            project_id UUID NOT NULL REFERENCES projects(id) ON DELETE CASCADE,
+            name VARCHAR(255) NOT NULL,
+            description TEXT,
+            required_approvals INTEGER NOT NULL DEFAULT 0,
+            created_at TIMESTAMPTZ NOT NULL,
+            PRIMARY KEY (project_id, name)
+        )
+        "#,
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    // Create deployments table, one row per run of a deploying stage
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS deployments (
+            id UUID PRIMARY KEY,
+            project_id UUID NOT NULL REFERENCES projects(id) ON DELETE CASCADE,
+            environment VARCHAR(255) NOT NULL,
+            pipeline_id UUID NOT NULL REFERENCES pipelines(id) ON DELETE CASCADE,
+            job_id UUID NOT NULL REFERENCES jobs(id) ON DELETE CASCADE,
+            attempt INTEGER NOT NULL,
+            stage VARCHAR(255) NOT NULL,
+            status VARCHAR(20) NOT NULL,
+            started_at TIMESTAMPTZ NOT NULL,
+            completed_at TIMESTAMPTZ,
+            UNIQUE (job_id, attempt, stage)
+        )
+        "#,
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    sqlx::query(
+        r#"
+        CREATE INDEX IF NOT EXISTS idx_deployments_environment
+        ON deployments(project_id, environment, started_at DESC)
+        "#,
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    // Create approvals table, one row per approver of a job's deployments to an environment
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS job_approvals (
+            job_id UUID NOT NULL REFERENCES jobs(id) ON DELETE CASCADE,
+            environment VARCHAR(255) NOT NULL,
+            approver VARCHAR(255) NOT NULL,
+            comment TEXT,
+            approved_at TIMESTAMPTZ NOT NULL,
+            PRIMARY KEY (job_id, environment, approver)
+        )
+        "#,
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    // Create environment locks table, one row per environment a job deploys to while it runs
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS environment_locks (
+            project_id UUID NOT NULL,
+            environment VARCHAR(255) NOT NULL,
+            job_id UUID NOT NULL REFERENCES jobs(id) ON DELETE CASCADE,
+            acquired_at TIMESTAMPTZ NOT NULL,
+            PRIMARY KEY (project_id, environment)
+        )
+        "#,
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    // Create blackout windows table, during which jobs of a pipeline or deploying to an environment wait
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS blackout_windows (
+            id UUID PRIMARY KEY,
+            project_id UUID NOT NULL REFERENCES projects(id) ON DELETE CASCADE,
+            pipeline_id UUID REFERENCES pipelines(id) ON DELETE CASCADE,
+            environment VARCHAR(255),
+            starts_at TIMESTAMPTZ NOT NULL,
+            ends_at TIMESTAMPTZ NOT NULL,
+            reason TEXT,
+            created_at TIMESTAMPTZ NOT NULL
+        )
+        "#,
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    sqlx::query(
+        "CREATE INDEX IF NOT EXISTS idx_blackout_windows_project ON blackout_windows(project_id, ends_at)",
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    // Create webhook triggers table
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS webhook_triggers (
+            id UUID PRIMARY KEY,
+            pipeline_id UUID NOT NULL REFERENCES pipelines(id) ON DELETE CASCADE,
+            provider VARCHAR(50) NOT NULL,
+            secret_name VARCHAR(255) NOT NULL,
+            filters JSONB NOT NULL,
+            parameters JSONB NOT NULL,
+            created_at TIMESTAMPTZ NOT NULL
+        )
+        "#,
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    sqlx::query(
+        "CREATE INDEX IF NOT EXISTS idx_webhook_triggers_pipeline ON webhook_triggers(pipeline_id)",
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    // Create pipeline schedules table, launching jobs at the times a cron expression matches
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS pipeline_schedules (
+            id UUID PRIMARY KEY,
+            pipeline_id UUID NOT NULL REFERENCES pipelines(id) ON DELETE CASCADE,
+            cron VARCHAR(255) NOT NULL,
+            timezone VARCHAR(64) NOT NULL,
+            catch_up VARCHAR(16) NOT NULL,
+            parameters JSONB NOT NULL,
+            next_run_at TIMESTAMPTZ NOT NULL,
+            last_run_at TIMESTAMPTZ,
+            created_at TIMESTAMPTZ NOT NULL
+        )
+        "#,
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    sqlx::query(
+        "CREATE INDEX IF NOT EXISTS idx_pipeline_schedules_next_run ON pipeline_schedules(next_run_at)",
+    )
+    .execute(&mut *conn)
     .await?;
 
-    tracing::info!("Database migrations completed successfully");
     Ok(())
 }
+
+/// Version of the schema the database was migrated to, `None` if it never was
+pub async fn schema_version(pool: &PgPool) -> Result<Option<i32>, sqlx::Error> {
+    let exists: bool = sqlx::query_scalar("SELECT to_regclass('schema_version') IS NOT NULL")
+        .fetch_one(pool)
+        .await?;
+    if !exists {
+        return Ok(None);
+    }
+
+    sqlx::query_scalar("SELECT version FROM schema_version")
+        .fetch_optional(pool)
+        .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_migrations_are_numbered_in_order() {
+        for (i, migration) in MIGRATIONS.iter().enumerate() {
+            assert_eq!(migration.version, i as i32 + 1);
+        }
+        assert!(!MIGRATIONS[0].reversible);
+        assert_eq!(SCHEMA_VERSION, MIGRATIONS.len() as i32);
+    }
+}
@@ -0,0 +1,195 @@
+//! Debug Session Relay
+//!
+//! Pairs the WebSocket of a user debugging a failed job with the WebSocket
+//! of the runner that ran it. The user's request is queued for the runner,
+//! which long-polls for debug requests and attaches to the session once it
+//! has restored the job's container.
+//!
+//! Sessions live in memory: the user and the runner must reach the same
+//! orchestrator instance.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use axum::extract::ws::WebSocket;
+use rivet_core::dto::debug::DebugRequest;
+use tokio::sync::{Notify, oneshot};
+use uuid::Uuid;
+
+/// How long a user waits for the runner to attach to a debug session
+pub const DEBUG_ATTACH_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Cloneable handle to the pending debug sessions
+#[derive(Clone, Default)]
+pub struct DebugRelay {
+    inner: Arc<RelayInner>,
+}
+
+#[derive(Default)]
+struct RelayInner {
+    state: Mutex<RelayState>,
+    /// Wakes runners waiting for a debug request
+    requested: Notify,
+}
+
+#[derive(Default)]
+struct RelayState {
+    /// Requests not yet picked up, per runner
+    requests: HashMap<String, Vec<DebugRequest>>,
+    /// Sessions waiting for their runner to attach
+    sessions: HashMap<Uuid, PendingSession>,
+}
+
+struct PendingSession {
+    runner_id: String,
+    attach: oneshot::Sender<WebSocket>,
+}
+
+impl DebugRelay {
+    /// Opens a debug session served by a runner
+    ///
+    /// # Arguments
+    /// * `runner_id` - The runner asked to serve the session
+    /// * `request` - What the runner is asked to restore
+    ///
+    /// # Returns
+    /// Receives the runner's WebSocket once it attaches
+    pub fn open(&self, runner_id: &str, request: DebugRequest) -> oneshot::Receiver<WebSocket> {
+        let (attach, attached) = oneshot::channel();
+        {
+            let mut state = self.inner.state.lock().unwrap();
+            state.sessions.insert(
+                request.session_id,
+                PendingSession {
+                    runner_id: runner_id.to_string(),
+                    attach,
+                },
+            );
+            state
+                .requests
+                .entry(runner_id.to_string())
+                .or_default()
+                .push(request);
+        }
+
+        self.inner.requested.notify_waiters();
+        attached
+    }
+
+    /// Forgets a session its user gave up on
+    pub fn close(&self, session_id: Uuid) {
+        let mut state = self.inner.state.lock().unwrap();
+        state.sessions.remove(&session_id);
+        for requests in state.requests.values_mut() {
+            requests.retain(|r| r.session_id != session_id);
+        }
+        state.requests.retain(|_, requests| !requests.is_empty());
+    }
+
+    /// Waits for a debug request for a runner
+    ///
+    /// # Arguments
+    /// * `runner_id` - The waiting runner
+    /// * `wait` - How long to wait
+    ///
+    /// # Returns
+    /// The oldest request for the runner, or `None` if none arrived in time
+    pub async fn next_request(&self, runner_id: &str, wait: Duration) -> Option<DebugRequest> {
+        let deadline = tokio::time::Instant::now() + wait;
+
+        loop {
+            // Register interest before checking, so a request opened in
+            // between still wakes this waiter
+            let requested = self.inner.requested.notified();
+            tokio::pin!(requested);
+            requested.as_mut().enable();
+
+            if let Some(request) = self.take_request(runner_id) {
+                return Some(request);
+            }
+            if tokio::time::timeout_at(deadline, requested).await.is_err() {
+                return None;
+            }
+        }
+    }
+
+    /// Runner a session waits for, if the session is pending
+    pub fn session_runner(&self, session_id: Uuid) -> Option<String> {
+        let state = self.inner.state.lock().unwrap();
+        state.sessions.get(&session_id).map(|s| s.runner_id.clone())
+    }
+
+    /// Hands the runner's WebSocket to the waiting user
+    ///
+    /// # Returns
+    /// The socket back if the session is no longer pending, `None` once handed over
+    pub fn attach(&self, session_id: Uuid, socket: WebSocket) -> Option<WebSocket> {
+        let session = self
+            .inner
+            .state
+            .lock()
+            .unwrap()
+            .sessions
+            .remove(&session_id);
+        match session {
+            Some(session) => session.attach.send(socket).err(),
+            None => Some(socket),
+        }
+    }
+
+    fn take_request(&self, runner_id: &str) -> Option<DebugRequest> {
+        let mut state = self.inner.state.lock().unwrap();
+        let requests = state.requests.get_mut(runner_id)?;
+        let request = requests.remove(0);
+        if requests.is_empty() {
+            state.requests.remove(runner_id);
+        }
+        Some(request)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(job_id: Uuid) -> DebugRequest {
+        DebugRequest {
+            session_id: Uuid::new_v4(),
+            job_id,
+            image: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_requests_reach_their_runner() {
+        let relay = DebugRelay::default();
+
+        let waiting = tokio::spawn({
+            let relay = relay.clone();
+            async move { relay.next_request("runner-a", Duration::from_secs(5)).await }
+        });
+        tokio::task::yield_now().await;
+
+        let other = request(Uuid::new_v4());
+        let _other_attached = relay.open("runner-b", other.clone());
+        let wanted = request(Uuid::new_v4());
+        let _attached = relay.open("runner-a", wanted.clone());
+
+        assert_eq!(waiting.await.unwrap(), Some(wanted.clone()));
+        assert_eq!(
+            relay.session_runner(wanted.session_id).as_deref(),
+            Some("runner-a")
+        );
+
+        // A closed session is no longer handed out
+        relay.close(other.session_id);
+        assert_eq!(
+            relay
+                .next_request("runner-b", Duration::from_millis(10))
+                .await,
+            None
+        );
+        assert_eq!(relay.session_runner(other.session_id), None);
+    }
+}
@@ -0,0 +1,43 @@
+//! Event Bus
+//!
+//! In-process broadcast of state changes (job, pipeline and runner events).
+//! Publishers never block: events are dropped when nobody is subscribed and
+//! slow subscribers skip ahead once they fall more than the capacity behind.
+
+use rivet_core::dto::event::Event;
+use tokio::sync::broadcast;
+
+/// Events buffered per subscriber before it starts missing events
+pub const DEFAULT_EVENT_CAPACITY: usize = 1024;
+
+/// Cloneable handle to the orchestrator event stream
+#[derive(Debug, Clone)]
+pub struct EventBus {
+    sender: broadcast::Sender<Event>,
+}
+
+impl EventBus {
+    /// Creates a bus buffering up to `capacity` events per subscriber
+    pub fn new(capacity: usize) -> Self {
+        let (sender, _) = broadcast::channel(capacity);
+        Self { sender }
+    }
+
+    /// Broadcasts an event to every current subscriber
+    pub fn publish(&self, event: Event) {
+        tracing::debug!("Publishing event: {:?}", event);
+        // An error only means there are no subscribers right now
+        let _ = self.sender.send(event);
+    }
+
+    /// Subscribes to events published from now on
+    pub fn subscribe(&self) -> broadcast::Receiver<Event> {
+        self.sender.subscribe()
+    }
+}
+
+impl Default for EventBus {
+    fn default() -> Self {
+        Self::new(DEFAULT_EVENT_CAPACITY)
+    }
+}
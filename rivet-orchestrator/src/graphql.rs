@@ -0,0 +1,766 @@
+//! GraphQL Query API
+//!
+//! Read-only async-graphql schema over pipelines, jobs and runners. Objects
+//! resolve their relations lazily (a job's pipeline and logs, a pipeline's
+//! stages and jobs), so dashboards fetch exactly the fields they need in one
+//! round trip. Resolvers call the same services as the REST handlers.
+
+use async_graphql::{
+    Context, EmptyMutation, EmptySubscription, Enum, ErrorExtensions, Json, Object, Schema,
+    SimpleObject,
+};
+use chrono::{DateTime, Utc};
+use rivet_core::domain::job::{Job, JobResult};
+use rivet_core::domain::log::LogEntry;
+use rivet_core::domain::pipeline::{Pipeline, Tag};
+use rivet_core::domain::project::Project;
+use rivet_core::domain::runner::Runner;
+use rivet_core::dto::job::{JobFilter, JobListQuery};
+use rivet_core::dto::pipeline::StageInfo;
+use rivet_core::dto::validation::describe_field_errors;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::log_archive::LogArchive;
+use crate::service::{job_service, log_service, pipeline_service, project_service, runner_service};
+
+/// Page size used when a list field is queried without `limit`
+pub const DEFAULT_PAGE_SIZE: u32 = 50;
+
+/// Deepest selection nesting accepted, bounds the cost of a single query
+pub const MAX_QUERY_DEPTH: usize = 8;
+
+/// The orchestrator GraphQL schema
+pub type RivetSchema = Schema<QueryRoot, EmptyMutation, EmptySubscription>;
+
+/// Builds the schema over the given database pool
+pub fn build_schema(pool: PgPool, archive: LogArchive) -> RivetSchema {
+    Schema::build(QueryRoot, EmptyMutation, EmptySubscription)
+        .data(pool)
+        .data(archive)
+        .limit_depth(MAX_QUERY_DEPTH)
+        .finish()
+}
+
+// =============================================================================
+// Errors
+// =============================================================================
+
+type GqlResult<T> = async_graphql::Result<T>;
+
+fn gql_error(code: &str, message: String) -> async_graphql::Error {
+    async_graphql::Error::new(message).extend_with(|_, e| e.set("code", code.to_string()))
+}
+
+/// Database details are logged, never sent to the caller
+fn database_error(err: sqlx::Error) -> async_graphql::Error {
+    tracing::error!("Database error: {:?}", err);
+    gql_error("INTERNAL", "Internal server error".to_string())
+}
+
+fn job_error(e: job_service::JobError) -> async_graphql::Error {
+    match e {
+        job_service::JobError::NotFound(id) => {
+            gql_error("NOT_FOUND", format!("Job {} not found", id))
+        }
+        job_service::JobError::PipelineNotFound(id) => {
+            gql_error("NOT_FOUND", format!("Pipeline {} not found", id))
+        }
+        job_service::JobError::InvalidState(msg) | job_service::JobError::ValidationError(msg) => {
+            gql_error("BAD_REQUEST", msg)
+        }
+        job_service::JobError::StaleClaim(id) => gql_error(
+            "CONFLICT",
+            format!("Claim on job {} is no longer current", id),
+        ),
+        job_service::JobError::InvalidFields(fields) => {
+            gql_error("BAD_REQUEST", describe_field_errors(&fields))
+        }
+        job_service::JobError::DatabaseError(err) => database_error(err),
+    }
+}
+
+fn pipeline_error(e: pipeline_service::PipelineError) -> async_graphql::Error {
+    match e {
+        pipeline_service::PipelineError::NotFound(id) => {
+            gql_error("NOT_FOUND", format!("Pipeline {} not found", id))
+        }
+        pipeline_service::PipelineError::ValidationError(msg) => gql_error("BAD_REQUEST", msg),
+        pipeline_service::PipelineError::InvalidFields(fields) => {
+            gql_error("BAD_REQUEST", describe_field_errors(&fields))
+        }
+        pipeline_service::PipelineError::DatabaseError(err) => database_error(err),
+    }
+}
+
+fn project_error(e: project_service::ProjectError) -> async_graphql::Error {
+    match e {
+        project_service::ProjectError::NotFound(name) => {
+            gql_error("NOT_FOUND", format!("Project {} not found", name))
+        }
+        project_service::ProjectError::ValidationError(msg) => gql_error("BAD_REQUEST", msg),
+        project_service::ProjectError::DatabaseError(err) => database_error(err),
+    }
+}
+
+fn log_error(e: log_service::LogError) -> async_graphql::Error {
+    match e {
+        log_service::LogError::JobNotFound(id) => {
+            gql_error("NOT_FOUND", format!("Job {} not found", id))
+        }
+        log_service::LogError::StaleClaim(id) => gql_error(
+            "CONFLICT",
+            format!("Claim on job {} is no longer current", id),
+        ),
+        log_service::LogError::ValidationError(msg) => gql_error("BAD_REQUEST", msg),
+        log_service::LogError::ArchiveError(err) => {
+            tracing::error!("{}", err);
+            gql_error("INTERNAL", "Internal server error".to_string())
+        }
+        log_service::LogError::DatabaseError(err) => database_error(err),
+    }
+}
+
+fn runner_error(e: runner_service::RunnerError) -> async_graphql::Error {
+    match e {
+        runner_service::RunnerError::NotFound(id) => {
+            gql_error("NOT_FOUND", format!("Runner {} not found", id))
+        }
+        runner_service::RunnerError::TokenNotFound(id) => {
+            gql_error("NOT_FOUND", format!("Registration token {} not found", id))
+        }
+        runner_service::RunnerError::Unauthorized(msg) => gql_error("UNAUTHORIZED", msg),
+        runner_service::RunnerError::ValidationError(msg) => gql_error("BAD_REQUEST", msg),
+        runner_service::RunnerError::IncompatibleVersion(msg) => gql_error("CONFLICT", msg),
+        runner_service::RunnerError::DatabaseError(err) => database_error(err),
+    }
+}
+
+// =============================================================================
+// Pagination
+// =============================================================================
+
+/// Validated `limit`/`offset` arguments of a list field
+#[derive(Debug, Clone, Copy)]
+struct Page {
+    limit: u32,
+    offset: u32,
+}
+
+impl Page {
+    fn new(limit: Option<u32>, offset: Option<u32>) -> GqlResult<Self> {
+        let limit = limit.unwrap_or(DEFAULT_PAGE_SIZE);
+        if limit == 0 || limit > rivet_core::dto::pagination::MAX_PAGE_SIZE {
+            return Err(gql_error(
+                "BAD_REQUEST",
+                format!(
+                    "limit must be between 1 and {}",
+                    rivet_core::dto::pagination::MAX_PAGE_SIZE
+                ),
+            ));
+        }
+        Ok(Self {
+            limit,
+            offset: offset.unwrap_or(0),
+        })
+    }
+
+    /// Applies the page to items that were filtered in memory
+    fn slice<T>(self, items: impl IntoIterator<Item = T>) -> Vec<T> {
+        items
+            .into_iter()
+            .skip(self.offset as usize)
+            .take(self.limit as usize)
+            .collect()
+    }
+}
+
+// =============================================================================
+// Enums
+// =============================================================================
+
+#[derive(Enum, Copy, Clone, Eq, PartialEq)]
+#[graphql(name = "JobStatus", remote = "rivet_core::domain::job::JobStatus")]
+pub enum GqlJobStatus {
+    Queued,
+    Running,
+    Succeeded,
+    Failed,
+    Cancelled,
+    TimedOut,
+    DeadLettered,
+    Blocked,
+}
+
+#[derive(Enum, Copy, Clone, Eq, PartialEq)]
+#[graphql(
+    name = "RunnerStatus",
+    remote = "rivet_core::domain::runner::RunnerStatus"
+)]
+pub enum GqlRunnerStatus {
+    Online,
+    Offline,
+    Busy,
+}
+
+#[derive(Enum, Copy, Clone, Eq, PartialEq)]
+#[graphql(name = "LogLevel", remote = "rivet_core::domain::log::LogLevel")]
+pub enum GqlLogLevel {
+    Debug,
+    Info,
+    Warning,
+    Error,
+}
+
+#[derive(Enum, Copy, Clone, Eq, PartialEq)]
+#[graphql(
+    name = "OutputStream",
+    remote = "rivet_core::domain::log::OutputStream"
+)]
+pub enum GqlOutputStream {
+    Stdout,
+    Stderr,
+}
+
+// =============================================================================
+// Query Root
+// =============================================================================
+
+/// Entry points of the GraphQL API
+pub struct QueryRoot;
+
+#[Object]
+impl QueryRoot {
+    /// All projects, by name
+    async fn projects(&self, ctx: &Context<'_>) -> GqlResult<Vec<ProjectObject>> {
+        let pool = ctx.data::<PgPool>()?;
+        let projects = project_service::list_projects(pool)
+            .await
+            .map_err(project_error)?;
+
+        Ok(projects.into_iter().map(ProjectObject::from).collect())
+    }
+
+    /// Pipelines newest first, optionally filtered by project, name substring and runner tag
+    #[allow(clippy::too_many_arguments)]
+    async fn pipelines(
+        &self,
+        ctx: &Context<'_>,
+        project_id: Option<Uuid>,
+        name_contains: Option<String>,
+        #[graphql(desc = "Runner tag in `key=value` form")] tag: Option<String>,
+        #[graphql(
+            default,
+            desc = "List the archived pipelines instead of the active ones"
+        )]
+        archived: bool,
+        limit: Option<u32>,
+        offset: Option<u32>,
+    ) -> GqlResult<Vec<PipelineObject>> {
+        let page = Page::new(limit, offset)?;
+        let pool = ctx.data::<PgPool>()?;
+
+        let tag = tag
+            .map(|tag| {
+                tag.split_once('=')
+                    .map(|(k, v)| (k.to_string(), v.to_string()))
+                    .ok_or_else(|| gql_error("BAD_REQUEST", "tag must be key=value".to_string()))
+            })
+            .transpose()?;
+
+        let pipelines = pipeline_service::list_pipelines(pool, archived)
+            .await
+            .map_err(pipeline_error)?
+            .into_iter()
+            .filter(|p| project_id.is_none_or(|id| p.project_id == id))
+            .filter(|p| {
+                name_contains
+                    .as_deref()
+                    .is_none_or(|needle| p.name.to_lowercase().contains(&needle.to_lowercase()))
+            })
+            .filter(|p| {
+                tag.as_ref()
+                    .is_none_or(|(k, v)| p.tags.iter().any(|t| &t.key == k && &t.value == v))
+            })
+            .map(PipelineObject);
+
+        Ok(page.slice(pipelines))
+    }
+
+    /// A single pipeline, or null if it does not exist
+    async fn pipeline(&self, ctx: &Context<'_>, id: Uuid) -> GqlResult<Option<PipelineObject>> {
+        let pool = ctx.data::<PgPool>()?;
+        match pipeline_service::get_pipeline(pool, id).await {
+            Ok(pipeline) => Ok(Some(PipelineObject(pipeline))),
+            Err(pipeline_service::PipelineError::NotFound(_)) => Ok(None),
+            Err(e) => Err(pipeline_error(e)),
+        }
+    }
+
+    /// Jobs newest first, optionally filtered by status, pipeline, runner,
+    /// project and labels (`label: "team=payments,env=prod"`)
+    #[allow(clippy::too_many_arguments)]
+    async fn jobs(
+        &self,
+        ctx: &Context<'_>,
+        status: Option<GqlJobStatus>,
+        pipeline_id: Option<Uuid>,
+        runner_id: Option<String>,
+        project_id: Option<Uuid>,
+        label: Option<String>,
+        limit: Option<u32>,
+        offset: Option<u32>,
+    ) -> GqlResult<Vec<JobObject>> {
+        let page = Page::new(limit, offset)?;
+        let labels = JobListQuery { label }
+            .labels()
+            .map_err(|message| gql_error("BAD_REQUEST", message))?;
+        let filter = JobFilter {
+            status: status.map(Into::into),
+            pipeline_id,
+            runner_id,
+            project_id,
+            labels,
+        };
+
+        list_jobs(ctx, &filter, page).await
+    }
+
+    /// A single job, or null if it does not exist
+    async fn job(&self, ctx: &Context<'_>, id: Uuid) -> GqlResult<Option<JobObject>> {
+        let pool = ctx.data::<PgPool>()?;
+        match job_service::get_job(pool, id).await {
+            Ok(job) => Ok(Some(JobObject(job))),
+            Err(job_service::JobError::NotFound(_)) => Ok(None),
+            Err(e) => Err(job_error(e)),
+        }
+    }
+
+    /// Registered runners, optionally filtered by status
+    async fn runners(
+        &self,
+        ctx: &Context<'_>,
+        status: Option<GqlRunnerStatus>,
+        limit: Option<u32>,
+        offset: Option<u32>,
+    ) -> GqlResult<Vec<RunnerObject>> {
+        let page = Page::new(limit, offset)?;
+        let pool = ctx.data::<PgPool>()?;
+        let status = status.map(rivet_core::domain::runner::RunnerStatus::from);
+
+        let runners = runner_service::list_runners(pool)
+            .await
+            .map_err(runner_error)?
+            .into_iter()
+            .filter(|r| status.is_none_or(|s| r.status == s))
+            .map(RunnerObject);
+
+        Ok(page.slice(runners))
+    }
+
+    /// A single runner, or null if it does not exist
+    async fn runner(&self, ctx: &Context<'_>, id: String) -> GqlResult<Option<RunnerObject>> {
+        let pool = ctx.data::<PgPool>()?;
+        match runner_service::get_runner(pool, &id).await {
+            Ok(runner) => Ok(Some(RunnerObject(runner))),
+            Err(runner_service::RunnerError::NotFound(_)) => Ok(None),
+            Err(e) => Err(runner_error(e)),
+        }
+    }
+}
+
+async fn list_jobs(ctx: &Context<'_>, filter: &JobFilter, page: Page) -> GqlResult<Vec<JobObject>> {
+    let pool = ctx.data::<PgPool>()?;
+    let jobs = job_service::list_jobs_page(pool, filter, page.limit, page.offset)
+        .await
+        .map_err(job_error)?;
+
+    Ok(jobs.into_iter().map(JobObject).collect())
+}
+
+// =============================================================================
+// Objects
+// =============================================================================
+
+/// A pipeline definition
+pub struct PipelineObject(Pipeline);
+
+#[Object(name = "Pipeline")]
+impl PipelineObject {
+    async fn id(&self) -> Uuid {
+        self.0.id
+    }
+
+    async fn project_id(&self) -> Uuid {
+        self.0.project_id
+    }
+
+    async fn name(&self) -> &str {
+        &self.0.name
+    }
+
+    async fn description(&self) -> Option<&str> {
+        self.0.description.as_deref()
+    }
+
+    async fn script(&self) -> &str {
+        &self.0.script
+    }
+
+    async fn created_at(&self) -> DateTime<Utc> {
+        self.0.created_at
+    }
+
+    async fn updated_at(&self) -> DateTime<Utc> {
+        self.0.updated_at
+    }
+
+    /// When the pipeline was archived, null while it is active
+    async fn archived_at(&self) -> Option<DateTime<Utc>> {
+        self.0.archived_at
+    }
+
+    /// Whether launching new jobs of the pipeline is paused
+    async fn disabled(&self) -> bool {
+        self.0.disabled
+    }
+
+    /// Runner tags required by the pipeline
+    async fn tags(&self) -> Vec<TagObject> {
+        self.0.tags.iter().cloned().map(TagObject::from).collect()
+    }
+
+    /// Stages declared by the pipeline script, in execution order
+    async fn stages(&self) -> GqlResult<Vec<StageObject>> {
+        let stages = pipeline_service::list_stages(&self.0).map_err(pipeline_error)?;
+        Ok(stages.into_iter().map(StageObject::from).collect())
+    }
+
+    /// Jobs of this pipeline, newest first
+    async fn jobs(
+        &self,
+        ctx: &Context<'_>,
+        status: Option<GqlJobStatus>,
+        limit: Option<u32>,
+        offset: Option<u32>,
+    ) -> GqlResult<Vec<JobObject>> {
+        let page = Page::new(limit, offset)?;
+        let filter = JobFilter {
+            status: status.map(Into::into),
+            pipeline_id: Some(self.0.id),
+            runner_id: None,
+            project_id: None,
+            labels: Default::default(),
+        };
+
+        list_jobs(ctx, &filter, page).await
+    }
+}
+
+/// A job execution
+pub struct JobObject(Job);
+
+#[Object(name = "Job")]
+impl JobObject {
+    async fn id(&self) -> Uuid {
+        self.0.id
+    }
+
+    /// Number of the job within its pipeline, starting at 1
+    async fn number(&self) -> u64 {
+        self.0.number
+    }
+
+    async fn pipeline_id(&self) -> Uuid {
+        self.0.pipeline_id
+    }
+
+    async fn status(&self) -> GqlJobStatus {
+        self.0.status.into()
+    }
+
+    async fn requested_at(&self) -> DateTime<Utc> {
+        self.0.requested_at
+    }
+
+    async fn started_at(&self) -> Option<DateTime<Utc>> {
+        self.0.started_at
+    }
+
+    async fn completed_at(&self) -> Option<DateTime<Utc>> {
+        self.0.completed_at
+    }
+
+    async fn runner_id(&self) -> Option<&str> {
+        self.0.runner_id.as_deref()
+    }
+
+    /// Launch parameters as a JSON object
+    async fn parameters(&self) -> Json<&std::collections::HashMap<String, serde_json::Value>> {
+        Json(&self.0.parameters)
+    }
+
+    async fn result(&self) -> Option<JobResultObject> {
+        self.0.result.clone().map(JobResultObject::from)
+    }
+
+    /// Current attempt, starting at 1
+    async fn attempt(&self) -> u32 {
+        self.0.attempt
+    }
+
+    /// Attempts allowed before the job is given up on
+    async fn max_attempts(&self) -> u32 {
+        self.0.max_attempts
+    }
+
+    /// Why the job was dead-lettered, if it was
+    async fn dead_letter_reason(&self) -> Option<&str> {
+        self.0.dead_letter_reason.as_deref()
+    }
+
+    /// Job this one resumes, if it was created by a resume
+    async fn resumed_from(&self) -> Option<Uuid> {
+        self.0.resumed_from
+    }
+
+    /// Labels as a JSON object
+    async fn labels(&self) -> Json<&std::collections::BTreeMap<String, String>> {
+        Json(&self.0.labels)
+    }
+
+    /// The pipeline this job was launched from
+    async fn pipeline(&self, ctx: &Context<'_>) -> GqlResult<PipelineObject> {
+        let pool = ctx.data::<PgPool>()?;
+        let pipeline = pipeline_service::get_pipeline(pool, self.0.pipeline_id)
+            .await
+            .map_err(pipeline_error)?;
+
+        Ok(PipelineObject(pipeline))
+    }
+
+    /// Log entries of this job, oldest first
+    async fn logs(
+        &self,
+        ctx: &Context<'_>,
+        limit: Option<u32>,
+        offset: Option<u32>,
+    ) -> GqlResult<Vec<LogEntryObject>> {
+        let page = Page::new(limit, offset)?;
+        let pool = ctx.data::<PgPool>()?;
+        let archive = ctx.data::<LogArchive>()?;
+        let logs =
+            log_service::get_job_logs_page(pool, archive, self.0.id, page.limit, page.offset)
+                .await
+                .map_err(log_error)?;
+
+        Ok(logs.into_iter().map(LogEntryObject::from).collect())
+    }
+
+    /// Total number of log entries of this job
+    async fn log_count(&self, ctx: &Context<'_>) -> GqlResult<i64> {
+        let pool = ctx.data::<PgPool>()?;
+        log_service::get_log_count(pool, self.0.id)
+            .await
+            .map_err(log_error)
+    }
+}
+
+/// A registered runner
+pub struct RunnerObject(Runner);
+
+#[Object(name = "Runner")]
+impl RunnerObject {
+    async fn id(&self) -> &str {
+        &self.0.id
+    }
+
+    async fn status(&self) -> GqlRunnerStatus {
+        self.0.status.into()
+    }
+
+    async fn registered_at(&self) -> DateTime<Utc> {
+        self.0.registered_at
+    }
+
+    async fn last_heartbeat_at(&self) -> DateTime<Utc> {
+        self.0.last_heartbeat_at
+    }
+
+    /// rivet-runner version, null for runners that did not report one
+    async fn version(&self) -> Option<&str> {
+        self.0.info.as_ref().map(|info| info.version.as_str())
+    }
+
+    async fn os(&self) -> Option<&str> {
+        self.0.info.as_ref().map(|info| info.os.as_str())
+    }
+
+    async fn arch(&self) -> Option<&str> {
+        self.0.info.as_ref().map(|info| info.arch.as_str())
+    }
+
+    async fn hostname(&self) -> Option<&str> {
+        self.0.info.as_ref().map(|info| info.hostname.as_str())
+    }
+
+    /// Runner protocol version negotiated at registration
+    async fn protocol_version(&self) -> Option<u32> {
+        self.0.info.as_ref().map(|info| info.protocol_version)
+    }
+
+    /// Jobs claimed by this runner, newest first
+    async fn jobs(
+        &self,
+        ctx: &Context<'_>,
+        status: Option<GqlJobStatus>,
+        limit: Option<u32>,
+        offset: Option<u32>,
+    ) -> GqlResult<Vec<JobObject>> {
+        let page = Page::new(limit, offset)?;
+        let filter = JobFilter {
+            status: status.map(Into::into),
+            pipeline_id: None,
+            runner_id: Some(self.0.id.clone()),
+            project_id: None,
+            labels: Default::default(),
+        };
+
+        list_jobs(ctx, &filter, page).await
+    }
+}
+
+#[derive(SimpleObject)]
+#[graphql(name = "Project")]
+pub struct ProjectObject {
+    id: Uuid,
+    name: String,
+    description: Option<String>,
+    created_at: DateTime<Utc>,
+}
+
+impl From<Project> for ProjectObject {
+    fn from(project: Project) -> Self {
+        Self {
+            id: project.id,
+            name: project.name,
+            description: project.description,
+            created_at: project.created_at,
+        }
+    }
+}
+
+#[derive(SimpleObject)]
+#[graphql(name = "Tag")]
+pub struct TagObject {
+    key: String,
+    value: String,
+}
+
+impl From<Tag> for TagObject {
+    fn from(tag: Tag) -> Self {
+        Self {
+            key: tag.key,
+            value: tag.value,
+        }
+    }
+}
+
+#[derive(SimpleObject)]
+#[graphql(name = "Stage")]
+pub struct StageObject {
+    name: String,
+    /// Container image the stage runs in, when it overrides the default
+    container: Option<String>,
+    /// Stages this one needs; null when it waits for the previous stage
+    needs: Option<Vec<String>>,
+    /// Whether a failure of the stage leaves the job successful
+    allow_failure: bool,
+    /// Environment the stage deploys to
+    deploys_to: Option<String>,
+    /// When the stage runs: on_success, on_failure or always
+    when: String,
+    /// Whether the stage runs only if its condition function passes
+    has_condition: bool,
+}
+
+impl From<StageInfo> for StageObject {
+    fn from(stage: StageInfo) -> Self {
+        Self {
+            name: stage.name,
+            container: stage.container,
+            needs: stage.needs,
+            allow_failure: stage.allow_failure,
+            deploys_to: stage.deploys_to,
+            when: stage.when,
+            has_condition: stage.has_condition,
+        }
+    }
+}
+
+#[derive(SimpleObject)]
+#[graphql(name = "JobResult")]
+pub struct JobResultObject {
+    success: bool,
+    exit_code: i32,
+    output: Option<Json<serde_json::Value>>,
+    error_message: Option<String>,
+}
+
+impl From<JobResult> for JobResultObject {
+    fn from(result: JobResult) -> Self {
+        Self {
+            success: result.success,
+            exit_code: result.exit_code,
+            output: result.output.map(Json),
+            error_message: result.error_message,
+        }
+    }
+}
+
+#[derive(SimpleObject)]
+#[graphql(name = "LogEntry")]
+pub struct LogEntryObject {
+    timestamp: DateTime<Utc>,
+    level: GqlLogLevel,
+    message: String,
+    stage: Option<String>,
+    stream: Option<GqlOutputStream>,
+}
+
+impl From<LogEntry> for LogEntryObject {
+    fn from(entry: LogEntry) -> Self {
+        Self {
+            timestamp: entry.timestamp,
+            level: entry.level.into(),
+            message: entry.message,
+            stage: entry.stage,
+            stream: entry.stream.map(Into::into),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_schema_exposes_query_fields() {
+        let sdl = Schema::build(QueryRoot, EmptyMutation, EmptySubscription)
+            .finish()
+            .sdl();
+
+        assert!(sdl.contains("pipelines("));
+        assert!(sdl.contains("logs(limit: Int, offset: Int): [LogEntry!]!"));
+        assert!(sdl.contains("enum JobStatus"));
+    }
+
+    #[test]
+    fn test_page_validation() {
+        assert!(Page::new(Some(0), None).is_err());
+        assert!(Page::new(Some(rivet_core::dto::pagination::MAX_PAGE_SIZE + 1), None).is_err());
+
+        let page = Page::new(Some(2), Some(1)).unwrap();
+        assert_eq!(page.slice(1..=5), vec![2, 3]);
+    }
+}
@@ -0,0 +1,390 @@
+//! gRPC Runner Service
+//!
+//! tonic implementation of `rivet.v1.RunnerService`, the gRPC counterpart of
+//! the runner-facing HTTP endpoints. Handlers call the same services as the
+//! HTTP API and publish the same events; only the transport differs.
+//!
+//! Log upload is client-streaming: a runner keeps one stream open per job
+//! and pushes batches as they are produced instead of POSTing each batch.
+//!
+//! Runners identify themselves with the `x-runner-credential` metadata,
+//! checked exactly like the HTTP header.
+
+use rivet_core::domain::job::{JobResult, JobStatus};
+use rivet_core::domain::log::LogEntry;
+use rivet_core::domain::runner::RunnerLoad;
+use rivet_core::dto::event::Event;
+use rivet_core::dto::job::{MAX_NEXT_JOB_WAIT, RenewLeaseRequest};
+use rivet_core::dto::runner::{RUNNER_CREDENTIAL_HEADER, RegisterRunner};
+use rivet_core::dto::validation::describe_field_errors;
+use rivet_proto::convert::parse_uuid;
+use rivet_proto::v1;
+use rivet_proto::{RunnerService, RunnerServiceServer};
+use sqlx::PgPool;
+use std::time::Duration;
+use tonic::{Request, Response, Status, Streaming};
+
+use crate::api::auth::{AuthConfig, RunnerIdentity, resolve_runner};
+use crate::api::error::ApiError;
+use crate::events::EventBus;
+use crate::scheduling::SchedulingPolicy;
+use crate::service::{job_service, log_service, runner_service};
+
+/// `RunnerService` backed by the orchestrator database
+#[derive(Debug, Clone)]
+pub struct GrpcRunnerService {
+    pool: PgPool,
+    events: EventBus,
+    auth: AuthConfig,
+    scheduling: SchedulingPolicy,
+}
+
+impl GrpcRunnerService {
+    /// Creates the service over the given pool and event bus
+    pub fn new(
+        pool: PgPool,
+        events: EventBus,
+        auth: AuthConfig,
+        scheduling: SchedulingPolicy,
+    ) -> Self {
+        Self {
+            pool,
+            events,
+            auth,
+            scheduling,
+        }
+    }
+
+    /// Resolves the runner behind the request's credential metadata
+    async fn identity<T>(&self, request: &Request<T>) -> Result<RunnerIdentity, Status> {
+        let credential = request
+            .metadata()
+            .get(RUNNER_CREDENTIAL_HEADER)
+            .and_then(|v| v.to_str().ok());
+
+        resolve_runner(&self.pool, &self.auth, credential)
+            .await
+            .map(RunnerIdentity)
+            .map_err(auth_error)
+    }
+
+    /// Wraps the service into a tonic server, ready to be added to a `tonic::transport::Server`
+    pub fn into_server(self) -> RunnerServiceServer<Self> {
+        RunnerServiceServer::new(self)
+    }
+}
+
+// =============================================================================
+// Error Mapping
+// =============================================================================
+
+fn job_error(e: job_service::JobError) -> Status {
+    match e {
+        job_service::JobError::NotFound(id) => Status::not_found(format!("Job {} not found", id)),
+        job_service::JobError::PipelineNotFound(id) => {
+            Status::not_found(format!("Pipeline {} not found", id))
+        }
+        job_service::JobError::InvalidState(msg) => Status::failed_precondition(msg),
+        job_service::JobError::StaleClaim(id) => {
+            Status::aborted(format!("Claim on job {} is no longer current", id))
+        }
+        job_service::JobError::ValidationError(msg) => Status::invalid_argument(msg),
+        job_service::JobError::InvalidFields(fields) => {
+            Status::invalid_argument(describe_field_errors(&fields))
+        }
+        job_service::JobError::DatabaseError(err) => database_error(err),
+    }
+}
+
+fn log_error(e: log_service::LogError) -> Status {
+    match e {
+        log_service::LogError::JobNotFound(id) => {
+            Status::not_found(format!("Job {} not found", id))
+        }
+        log_service::LogError::StaleClaim(id) => {
+            Status::aborted(format!("Claim on job {} is no longer current", id))
+        }
+        log_service::LogError::ValidationError(msg) => Status::invalid_argument(msg),
+        log_service::LogError::ArchiveError(err) => Status::internal(err.to_string()),
+        log_service::LogError::DatabaseError(err) => database_error(err),
+    }
+}
+
+fn runner_error(e: runner_service::RunnerError) -> Status {
+    match e {
+        runner_service::RunnerError::NotFound(id) => {
+            Status::not_found(format!("Runner {} not found", id))
+        }
+        runner_service::RunnerError::TokenNotFound(id) => {
+            Status::not_found(format!("Registration token {} not found", id))
+        }
+        runner_service::RunnerError::Unauthorized(msg) => Status::unauthenticated(msg),
+        runner_service::RunnerError::ValidationError(msg) => Status::invalid_argument(msg),
+        runner_service::RunnerError::IncompatibleVersion(msg) => Status::failed_precondition(msg),
+        runner_service::RunnerError::DatabaseError(err) => database_error(err),
+    }
+}
+
+fn auth_error(e: ApiError) -> Status {
+    match e {
+        ApiError::Unauthorized(msg) => Status::unauthenticated(msg),
+        ApiError::NotFound(msg) => Status::not_found(msg),
+        ApiError::BadRequest(msg) => Status::invalid_argument(msg),
+        ApiError::InvalidFields(fields) => Status::invalid_argument(describe_field_errors(&fields)),
+        ApiError::Conflict(msg) => Status::failed_precondition(msg),
+        ApiError::DatabaseError(err) => database_error(err),
+        ApiError::InternalError(msg) => Status::internal(msg),
+    }
+}
+
+/// Database details are logged, never sent to the caller
+fn database_error(err: sqlx::Error) -> Status {
+    tracing::error!("Database error: {:?}", err);
+    Status::internal("Database error")
+}
+
+// =============================================================================
+// Service
+// =============================================================================
+
+#[tonic::async_trait]
+impl RunnerService for GrpcRunnerService {
+    async fn register_runner(
+        &self,
+        request: Request<v1::RegisterRunnerRequest>,
+    ) -> Result<Response<v1::RegisterRunnerResponse>, Status> {
+        let req = request.into_inner();
+        tracing::info!("Registering runner over gRPC: {}", req.runner_id);
+
+        let registration = runner_service::register_runner(
+            &self.pool,
+            RegisterRunner {
+                runner_id: req.runner_id,
+                registration_token: req.registration_token,
+                tags: req.tags.into_iter().map(Into::into).collect(),
+                plugins: req.plugins,
+                info: req.info.map(Into::into),
+            },
+            self.auth.require_runner_auth,
+        )
+        .await
+        .map_err(runner_error)?;
+
+        self.events.publish(Event::RunnerRegistered {
+            runner_id: registration.runner.id.clone(),
+        });
+
+        Ok(Response::new(v1::RegisterRunnerResponse {
+            runner: Some(registration.runner.into()),
+            credential: registration.credential,
+        }))
+    }
+
+    async fn heartbeat(
+        &self,
+        request: Request<v1::HeartbeatRequest>,
+    ) -> Result<Response<v1::HeartbeatResponse>, Status> {
+        let identity = self.identity(&request).await?;
+        let request = request.into_inner();
+        let runner_id = request.runner_id;
+        tracing::debug!("Heartbeat from runner over gRPC: {}", runner_id);
+
+        identity.ensure_runner(&runner_id).map_err(auth_error)?;
+
+        let load = request.load.map(RunnerLoad::from);
+        runner_service::update_heartbeat(&self.pool, &runner_id, load.as_ref())
+            .await
+            .map_err(runner_error)?;
+
+        Ok(Response::new(v1::HeartbeatResponse {}))
+    }
+
+    async fn list_scheduled_jobs(
+        &self,
+        _request: Request<v1::ListScheduledJobsRequest>,
+    ) -> Result<Response<v1::ListScheduledJobsResponse>, Status> {
+        let jobs = job_service::list_queued_jobs(&self.pool, self.scheduling)
+            .await
+            .map_err(job_error)?;
+
+        Ok(Response::new(v1::ListScheduledJobsResponse {
+            jobs: jobs.into_iter().map(Into::into).collect(),
+        }))
+    }
+
+    async fn claim_job(
+        &self,
+        request: Request<v1::ClaimJobRequest>,
+    ) -> Result<Response<v1::JobExecutionInfo>, Status> {
+        let identity = self.identity(&request).await?;
+        let req = request.into_inner();
+        identity.ensure_runner(&req.runner_id).map_err(auth_error)?;
+        let job_id = parse_uuid("job_id", &req.job_id)?;
+        tracing::info!(
+            "Runner {} executing job over gRPC: {}",
+            req.runner_id,
+            job_id
+        );
+
+        let (job, pipeline) =
+            job_service::reserve_job_for_execution(&self.pool, job_id, req.runner_id)
+                .await
+                .map_err(job_error)?;
+
+        self.events.publish(Event::JobStarted {
+            job_id: job.id,
+            pipeline_id: job.pipeline_id,
+            runner_id: job.runner_id.clone().unwrap_or_default(),
+        });
+
+        let info = job_service::execution_info(&self.pool, job, pipeline)
+            .await
+            .map_err(job_error)?;
+
+        Ok(Response::new(info.into()))
+    }
+
+    async fn next_job(
+        &self,
+        request: Request<v1::NextJobRequest>,
+    ) -> Result<Response<v1::NextJobResponse>, Status> {
+        let identity = self.identity(&request).await?;
+        let req = request.into_inner();
+        identity.ensure_runner(&req.runner_id).map_err(auth_error)?;
+        let wait = Duration::from_millis(req.wait_millis).min(MAX_NEXT_JOB_WAIT);
+        tracing::debug!(
+            "Runner {} waiting up to {:?} for a job over gRPC",
+            req.runner_id,
+            wait
+        );
+
+        let assigned = job_service::wait_for_next_job(
+            &self.pool,
+            &self.events,
+            &req.runner_id,
+            wait,
+            self.scheduling,
+        )
+        .await
+        .map_err(job_error)?;
+
+        let Some((job, pipeline)) = assigned else {
+            return Ok(Response::new(v1::NextJobResponse { job: None }));
+        };
+
+        self.events.publish(Event::JobStarted {
+            job_id: job.id,
+            pipeline_id: job.pipeline_id,
+            runner_id: job.runner_id.clone().unwrap_or_default(),
+        });
+
+        let info = job_service::execution_info(&self.pool, job, pipeline)
+            .await
+            .map_err(job_error)?;
+
+        Ok(Response::new(v1::NextJobResponse {
+            job: Some(info.into()),
+        }))
+    }
+
+    async fn renew_lease(
+        &self,
+        request: Request<v1::RenewLeaseRequest>,
+    ) -> Result<Response<v1::JobLease>, Status> {
+        let identity = self.identity(&request).await?;
+        let req = request.into_inner();
+        identity.ensure_runner(&req.runner_id).map_err(auth_error)?;
+        let job_id = parse_uuid("job_id", &req.job_id)?;
+        tracing::debug!(
+            "Runner {} renewing lease on job over gRPC: {}",
+            req.runner_id,
+            job_id
+        );
+
+        let lease = job_service::renew_job_lease(
+            &self.pool,
+            job_id,
+            RenewLeaseRequest {
+                runner_id: req.runner_id,
+                ttl_seconds: (req.ttl_seconds > 0).then_some(req.ttl_seconds),
+            },
+        )
+        .await
+        .map_err(job_error)?;
+
+        Ok(Response::new(lease.into()))
+    }
+
+    async fn stream_logs(
+        &self,
+        request: Request<Streaming<v1::LogBatch>>,
+    ) -> Result<Response<v1::StreamLogsResponse>, Status> {
+        let identity = self.identity(&request).await?;
+        let mut stream = request.into_inner();
+        let mut entries_received = 0u64;
+        let mut verified_job = None;
+
+        while let Some(batch) = stream.message().await? {
+            let job_id = parse_uuid("job_id", &batch.job_id)?;
+            if verified_job != Some(job_id) {
+                identity
+                    .ensure_job(&self.pool, job_id)
+                    .await
+                    .map_err(auth_error)?;
+                verified_job = Some(job_id);
+            }
+            let entries = batch
+                .entries
+                .into_iter()
+                .map(LogEntry::try_from)
+                .collect::<Result<Vec<_>, _>>()?;
+
+            tracing::debug!("Adding {} log entries for job: {}", entries.len(), job_id);
+            entries_received += entries.len() as u64;
+
+            log_service::add_log_entries(&self.pool, job_id, entries, batch.claim_token)
+                .await
+                .map_err(log_error)?;
+        }
+
+        Ok(Response::new(v1::StreamLogsResponse { entries_received }))
+    }
+
+    async fn complete_job(
+        &self,
+        request: Request<v1::CompleteJobRequest>,
+    ) -> Result<Response<v1::CompleteJobResponse>, Status> {
+        let identity = self.identity(&request).await?;
+        let req = request.into_inner();
+        let job_id = parse_uuid("job_id", &req.job_id)?;
+        identity
+            .ensure_job(&self.pool, job_id)
+            .await
+            .map_err(auth_error)?;
+        let result = req
+            .result
+            .map(JobResult::try_from)
+            .transpose()?
+            .ok_or_else(|| Status::invalid_argument("result is required"))?;
+
+        let status = if result.success {
+            JobStatus::Succeeded
+        } else {
+            JobStatus::Failed
+        };
+        tracing::info!(
+            "Completing job over gRPC: {} with status {:?}",
+            job_id,
+            status
+        );
+
+        let job =
+            job_service::complete_job(&self.pool, job_id, status, Some(result), req.claim_token)
+                .await
+                .map_err(job_error)?;
+
+        self.events.publish(job_service::completion_event(&job));
+
+        Ok(Response::new(v1::CompleteJobResponse {}))
+    }
+}
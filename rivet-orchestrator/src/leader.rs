@@ -0,0 +1,114 @@
+//! Leader Election
+//!
+//! Background duties (stale runner sweeps, lease expiry, artifact garbage
+//! collection, cron schedules) must run on a single orchestrator when several
+//! replicas share a database. The replica holding a PostgreSQL session
+//! advisory lock is the leader. The lock lives as long as the connection that
+//! took it, so when the leader stops or loses the database, a standby replica
+//! takes the lock and the duties over within `ELECTION_INTERVAL`.
+
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+use sqlx::{PgConnection, PgPool};
+
+/// Advisory lock key held by the leader
+pub const LEADER_LOCK_KEY: i64 = 0x7269_7665_745f_6c64;
+
+/// How often standby replicas try to take the lock and the leader checks
+/// that it still holds it
+pub const ELECTION_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Cloneable handle telling whether this replica is the leader
+#[derive(Debug, Clone, Default)]
+pub struct Leadership {
+    leader: Arc<AtomicBool>,
+}
+
+impl Leadership {
+    /// Whether this replica currently runs the background duties
+    pub fn is_leader(&self) -> bool {
+        self.leader.load(Ordering::Acquire)
+    }
+
+    fn set_leader(&self, leader: bool) -> bool {
+        self.leader.swap(leader, Ordering::AcqRel)
+    }
+}
+
+/// Takes part in the election on behalf of a `Leadership`
+///
+/// Holds a connection of its own, outside the pool, for the lock to live on.
+pub struct Candidate {
+    pool: PgPool,
+    leadership: Leadership,
+    connection: Option<PgConnection>,
+}
+
+impl Candidate {
+    pub fn new(pool: PgPool, leadership: Leadership) -> Self {
+        Self {
+            pool,
+            leadership,
+            connection: None,
+        }
+    }
+
+    /// Tries to take the lock, or checks that it is still held
+    ///
+    /// Errors, including a check outlasting `ELECTION_INTERVAL`, drop the
+    /// connection and with it the lock, so a replica never believes it leads
+    /// while another one does for longer than an election interval.
+    pub async fn elect(&mut self) {
+        let result = tokio::time::timeout(ELECTION_INTERVAL, self.try_elect()).await;
+        let error = match result {
+            Ok(Ok(())) => return,
+            Ok(Err(e)) => e.to_string(),
+            Err(_) => "timed out".to_string(),
+        };
+
+        self.connection = None;
+        if self.leadership.set_leader(false) {
+            tracing::warn!("Lost leadership, background duties paused: {}", error);
+        } else {
+            tracing::warn!("Leader election failed: {}", error);
+        }
+    }
+
+    async fn try_elect(&mut self) -> Result<(), sqlx::Error> {
+        let connection = match &mut self.connection {
+            Some(connection) => connection,
+            None => self.connection.insert(self.pool.acquire().await?.detach()),
+        };
+
+        if self.leadership.is_leader() {
+            // The connection is alive, so the lock is still ours
+            sqlx::query("SELECT 1").execute(&mut *connection).await?;
+            return Ok(());
+        }
+
+        let acquired: bool = sqlx::query_scalar("SELECT pg_try_advisory_lock($1)")
+            .bind(LEADER_LOCK_KEY)
+            .fetch_one(&mut *connection)
+            .await?;
+        if acquired {
+            self.leadership.set_leader(true);
+            tracing::info!("Elected leader, running background duties");
+        }
+        Ok(())
+    }
+
+    /// Runs the election forever
+    ///
+    /// Dropping the future closes the connection, releasing the lock for
+    /// another replica.
+    pub async fn campaign(mut self) {
+        let mut interval = tokio::time::interval(ELECTION_INTERVAL);
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        loop {
+            interval.tick().await;
+            self.elect().await;
+        }
+    }
+}
@@ -0,0 +1,230 @@
+//! Log Archive
+//!
+//! Object storage for the logs of completed jobs. Recent logs live in the
+//! `job_logs` table; once a job completes, its entries are compacted into a
+//! single gzipped JSON Lines object and their rows deleted, keeping the table
+//! small however chatty jobs are. The log service stitches archived entries
+//! and entries stored since back together, so readers cannot tell them apart.
+//!
+//! Archives live in S3 or S3-compatible storage (`s3://bucket/prefix`), a
+//! local directory (`file:///path`) or, for tests, memory (`memory://`).
+
+use std::fmt;
+use std::io::{BufRead, BufReader, Write};
+use std::sync::Arc;
+
+use flate2::Compression;
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use object_store::aws::AmazonS3Builder;
+use object_store::local::LocalFileSystem;
+use object_store::memory::InMemory;
+use object_store::path::Path;
+use object_store::{ObjectStore, ObjectStoreScheme, PutPayload};
+use rivet_core::domain::log::LogEntry;
+use url::Url;
+use uuid::Uuid;
+
+/// Error reading or writing archived logs
+#[derive(Debug)]
+pub enum ArchiveError {
+    /// Logs were archived but no archive is configured to read them from
+    Disabled,
+    Store(object_store::Error),
+    /// An archived object could not be decoded
+    Corrupt(String),
+}
+
+impl fmt::Display for ArchiveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ArchiveError::Disabled => {
+                write!(f, "Logs are archived but no log archive is configured")
+            }
+            ArchiveError::Store(e) => write!(f, "Log archive error: {}", e),
+            ArchiveError::Corrupt(msg) => write!(f, "Corrupt log archive: {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for ArchiveError {}
+
+impl From<object_store::Error> for ArchiveError {
+    fn from(e: object_store::Error) -> Self {
+        ArchiveError::Store(e)
+    }
+}
+
+/// Cloneable handle to the object store holding archived logs
+///
+/// The default archive is disabled: logs stay in the database.
+#[derive(Debug, Clone, Default)]
+pub struct LogArchive {
+    store: Option<Arc<dyn ObjectStore>>,
+    prefix: Path,
+}
+
+impl LogArchive {
+    /// Archives logs in `store`, under `prefix`
+    pub fn new(store: Arc<dyn ObjectStore>, prefix: impl Into<Path>) -> Self {
+        Self {
+            store: Some(store),
+            prefix: prefix.into(),
+        }
+    }
+
+    /// Opens the archive at `url`
+    ///
+    /// S3 credentials, region and endpoint come from the standard `AWS_*`
+    /// environment variables (e.g. `AWS_ENDPOINT` for S3-compatible storage).
+    ///
+    /// # Arguments
+    /// * `url` - `s3://bucket/prefix`, `file:///path` or `memory://`
+    pub fn from_url(url: &str) -> Result<Self, String> {
+        let parsed = Url::parse(url).map_err(|e| format!("'{}': {}", url, e))?;
+        let (scheme, prefix) = ObjectStoreScheme::parse(&parsed)
+            .map_err(|_| format!("'{}': unsupported log archive URL", url))?;
+
+        let store: Arc<dyn ObjectStore> = match scheme {
+            ObjectStoreScheme::AmazonS3 => Arc::new(
+                AmazonS3Builder::from_env()
+                    .with_url(url)
+                    .build()
+                    .map_err(|e| format!("'{}': {}", url, e))?,
+            ),
+            ObjectStoreScheme::Local => Arc::new(LocalFileSystem::new()),
+            ObjectStoreScheme::Memory => Arc::new(InMemory::new()),
+            _ => {
+                return Err(format!(
+                    "'{}': log archives must be s3://, file:// or memory:// URLs",
+                    url
+                ));
+            }
+        };
+
+        Ok(Self::new(store, prefix))
+    }
+
+    /// Whether completed jobs' logs are moved to object storage
+    pub fn is_enabled(&self) -> bool {
+        self.store.is_some()
+    }
+
+    /// Key of the object holding the archived logs of a job
+    pub fn object_key(&self, job_id: Uuid) -> String {
+        self.prefix
+            .child("logs")
+            .child(format!("{}.jsonl.gz", job_id))
+            .to_string()
+    }
+
+    fn store(&self) -> Result<&Arc<dyn ObjectStore>, ArchiveError> {
+        self.store.as_ref().ok_or(ArchiveError::Disabled)
+    }
+
+    /// Writes `entries` to the object at `key`, replacing it
+    ///
+    /// # Returns
+    /// The size of the object in bytes
+    pub async fn put(&self, key: &str, entries: &[LogEntry]) -> Result<u64, ArchiveError> {
+        let data = encode(entries)?;
+        let size = data.len() as u64;
+        self.store()?
+            .put(&Path::from(key), PutPayload::from(data))
+            .await?;
+        Ok(size)
+    }
+
+    /// Reads the entries of the object at `key`
+    pub async fn get(&self, key: &str) -> Result<Vec<LogEntry>, ArchiveError> {
+        let data = self.store()?.get(&Path::from(key)).await?.bytes().await?;
+        decode(&data)
+    }
+
+    /// Deletes the object at `key`, if it exists
+    pub async fn delete(&self, key: &str) -> Result<(), ArchiveError> {
+        match self.store()?.delete(&Path::from(key)).await {
+            Ok(()) | Err(object_store::Error::NotFound { .. }) => Ok(()),
+            Err(e) => Err(e.into()),
+        }
+    }
+}
+
+// =============================================================================
+// Encoding
+// =============================================================================
+
+/// Gzipped JSON Lines, one entry per line
+fn encode(entries: &[LogEntry]) -> Result<Vec<u8>, ArchiveError> {
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    for entry in entries {
+        serde_json::to_writer(&mut encoder, entry)
+            .map_err(|e| ArchiveError::Corrupt(e.to_string()))?;
+        encoder
+            .write_all(b"\n")
+            .map_err(|e| ArchiveError::Corrupt(e.to_string()))?;
+    }
+    encoder
+        .finish()
+        .map_err(|e| ArchiveError::Corrupt(e.to_string()))
+}
+
+fn decode(data: &[u8]) -> Result<Vec<LogEntry>, ArchiveError> {
+    BufReader::new(GzDecoder::new(data))
+        .lines()
+        .filter(|line| !line.as_ref().is_ok_and(|line| line.is_empty()))
+        .map(|line| {
+            let line = line.map_err(|e| ArchiveError::Corrupt(e.to_string()))?;
+            serde_json::from_str(&line).map_err(|e| ArchiveError::Corrupt(e.to_string()))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rivet_core::domain::log::LogLevel;
+
+    fn entry(sequence: u64, message: &str) -> LogEntry {
+        LogEntry {
+            timestamp: chrono::Utc::now(),
+            level: LogLevel::Info,
+            message: message.to_string(),
+            stage: Some("build".to_string()),
+            stream: None,
+            sequence: Some(sequence),
+            fields: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_put_get_round_trip() {
+        let archive = LogArchive::from_url("memory://").unwrap();
+        let key = archive.object_key(Uuid::nil());
+        assert_eq!(key, "logs/00000000-0000-0000-0000-000000000000.jsonl.gz");
+
+        let entries = vec![entry(1, "first"), entry(2, "line\nbreak")];
+        archive.put(&key, &entries).await.unwrap();
+
+        let read = archive.get(&key).await.unwrap();
+        assert_eq!(read.len(), 2);
+        assert_eq!(read[1].message, "line\nbreak");
+        assert_eq!(read[1].sequence, Some(2));
+
+        archive.delete(&key).await.unwrap();
+        archive.delete(&key).await.unwrap();
+        assert!(archive.get(&key).await.is_err());
+    }
+
+    #[test]
+    fn test_from_url() {
+        let archive = LogArchive::from_url("file:///var/lib/rivet/archive").unwrap();
+        assert!(archive.is_enabled());
+        assert_eq!(
+            archive.object_key(Uuid::nil()),
+            "var/lib/rivet/archive/logs/00000000-0000-0000-0000-000000000000.jsonl.gz"
+        );
+        assert!(LogArchive::from_url("ftp://example.com/logs").is_err());
+        assert!(!LogArchive::default().is_enabled());
+    }
+}
@@ -0,0 +1,240 @@
+//! Artifact Repository
+//!
+//! Handles all database operations related to job artifacts.
+
+use rivet_core::domain::artifact::Artifact;
+use rivet_core::dto::artifact::{ArtifactRetention, ArtifactUsage, PipelineArtifactUsage};
+use sqlx::{PgConnection, PgPool};
+use uuid::Uuid;
+
+/// Store an artifact, replacing any previous artifact with the same name for the job
+#[tracing::instrument(name = "artifact_repository::upsert", skip_all)]
+pub async fn upsert(
+    pool: &PgPool,
+    job_id: Uuid,
+    name: &str,
+    sha256: &str,
+    content: &[u8],
+) -> Result<Artifact, sqlx::Error> {
+    let row = sqlx::query_as::<_, ArtifactRow>(
+        r#"
+        INSERT INTO job_artifacts (id, job_id, name, size_bytes, sha256, content, created_at)
+        VALUES ($1, $2, $3, $4, $5, $6, $7)
+        ON CONFLICT (job_id, name) DO UPDATE
+        SET size_bytes = EXCLUDED.size_bytes,
+            sha256 = EXCLUDED.sha256,
+            content = EXCLUDED.content,
+            created_at = EXCLUDED.created_at
+        RETURNING id, job_id, name, size_bytes, sha256, created_at
+        "#,
+    )
+    .bind(Uuid::new_v4())
+    .bind(job_id)
+    .bind(name)
+    .bind(content.len() as i64)
+    .bind(sha256)
+    .bind(content)
+    .bind(chrono::Utc::now())
+    .fetch_one(pool)
+    .await?;
+
+    Ok(row.into())
+}
+
+/// Copy every artifact of a job to another job
+///
+/// # Returns
+/// The number of artifacts copied
+#[tracing::instrument(name = "artifact_repository::copy_to_job", skip_all)]
+pub async fn copy_to_job(
+    conn: &mut PgConnection,
+    from_job_id: Uuid,
+    to_job_id: Uuid,
+) -> Result<u64, sqlx::Error> {
+    let result = sqlx::query(
+        r#"
+        INSERT INTO job_artifacts (id, job_id, name, size_bytes, sha256, content, created_at)
+        SELECT gen_random_uuid(), $2, name, size_bytes, sha256, content, created_at
+        FROM job_artifacts
+        WHERE job_id = $1
+        ON CONFLICT (job_id, name) DO NOTHING
+        "#,
+    )
+    .bind(from_job_id)
+    .bind(to_job_id)
+    .execute(conn)
+    .await?;
+
+    Ok(result.rows_affected())
+}
+
+/// List artifact metadata for a job
+#[tracing::instrument(name = "artifact_repository::find_by_job", skip_all)]
+pub async fn find_by_job(pool: &PgPool, job_id: Uuid) -> Result<Vec<Artifact>, sqlx::Error> {
+    let rows = sqlx::query_as::<_, ArtifactRow>(
+        r#"
+        SELECT id, job_id, name, size_bytes, sha256, created_at
+        FROM job_artifacts
+        WHERE job_id = $1
+        ORDER BY name ASC
+        "#,
+    )
+    .bind(job_id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows.into_iter().map(|r| r.into()).collect())
+}
+
+/// Find an artifact and its content by job and name
+#[tracing::instrument(name = "artifact_repository::find_with_content", skip_all)]
+pub async fn find_with_content(
+    pool: &PgPool,
+    job_id: Uuid,
+    name: &str,
+) -> Result<Option<(Artifact, Vec<u8>)>, sqlx::Error> {
+    let row = sqlx::query_as::<_, ArtifactContentRow>(
+        r#"
+        SELECT id, job_id, name, size_bytes, sha256, created_at, content
+        FROM job_artifacts
+        WHERE job_id = $1 AND name = $2
+        "#,
+    )
+    .bind(job_id)
+    .bind(name)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(row.map(|r| {
+        let content = r.content;
+        (r.artifact.into(), content)
+    }))
+}
+
+/// Artifacts of finished jobs of pipelines with retention limits
+///
+/// # Returns
+/// For each such pipeline, its limits and its artifacts, newest job first
+/// and each job's newest artifact first
+#[tracing::instrument(name = "artifact_repository::find_retained", skip_all)]
+pub async fn find_retained(
+    pool: &PgPool,
+) -> Result<Vec<(Uuid, ArtifactRetention, Vec<Artifact>)>, sqlx::Error> {
+    let rows = sqlx::query_as::<_, RetainedArtifactRow>(
+        r#"
+        SELECT a.id, a.job_id, a.name, a.size_bytes, a.sha256, a.created_at,
+               j.pipeline_id, p.artifact_retention::text AS retention
+        FROM job_artifacts a
+        JOIN jobs j ON j.id = a.job_id
+        JOIN pipelines p ON p.id = j.pipeline_id
+        WHERE p.artifact_retention <> '{}'::jsonb
+          AND j.status NOT IN ('Queued', 'Blocked', 'Running')
+        ORDER BY j.pipeline_id, j.requested_at DESC, j.id, a.created_at DESC
+        "#,
+    )
+    .fetch_all(pool)
+    .await?;
+
+    let mut pipelines: Vec<(Uuid, ArtifactRetention, Vec<Artifact>)> = Vec::new();
+    for row in rows {
+        match pipelines.last_mut() {
+            Some((pipeline_id, _, artifacts)) if *pipeline_id == row.pipeline_id => {
+                artifacts.push(row.artifact.into())
+            }
+            _ => pipelines.push((
+                row.pipeline_id,
+                serde_json::from_str(&row.retention).unwrap_or_default(),
+                vec![row.artifact.into()],
+            )),
+        }
+    }
+
+    Ok(pipelines)
+}
+
+/// Count the stored artifacts and their bytes, overall and per pipeline
+#[tracing::instrument(name = "artifact_repository::usage", skip_all)]
+pub async fn usage(pool: &PgPool) -> Result<ArtifactUsage, sqlx::Error> {
+    let rows: Vec<(Uuid, i64, i64)> = sqlx::query_as(
+        r#"
+        SELECT j.pipeline_id, COUNT(*), COALESCE(SUM(a.size_bytes), 0)::BIGINT
+        FROM job_artifacts a
+        JOIN jobs j ON j.id = a.job_id
+        GROUP BY j.pipeline_id
+        ORDER BY 3 DESC, 1
+        "#,
+    )
+    .fetch_all(pool)
+    .await?;
+
+    let pipelines: Vec<PipelineArtifactUsage> = rows
+        .into_iter()
+        .map(|(pipeline_id, artifacts, bytes)| PipelineArtifactUsage {
+            pipeline_id,
+            artifacts: artifacts as u64,
+            bytes: bytes as u64,
+        })
+        .collect();
+
+    Ok(ArtifactUsage {
+        artifacts: pipelines.iter().map(|p| p.artifacts).sum(),
+        bytes: pipelines.iter().map(|p| p.bytes).sum(),
+        pipelines,
+    })
+}
+
+/// Delete artifacts by ID
+///
+/// # Returns
+/// The number of artifacts deleted
+#[tracing::instrument(name = "artifact_repository::delete_many", skip_all)]
+pub async fn delete_many(pool: &PgPool, ids: &[Uuid]) -> Result<u64, sqlx::Error> {
+    let result = sqlx::query("DELETE FROM job_artifacts WHERE id = ANY($1)")
+        .bind(ids)
+        .execute(pool)
+        .await?;
+
+    Ok(result.rows_affected())
+}
+
+// =============================================================================
+// Database Row Types
+// =============================================================================
+
+#[derive(sqlx::FromRow)]
+struct ArtifactRow {
+    id: Uuid,
+    job_id: Uuid,
+    name: String,
+    size_bytes: i64,
+    sha256: String,
+    created_at: chrono::DateTime<chrono::Utc>,
+}
+
+#[derive(sqlx::FromRow)]
+struct ArtifactContentRow {
+    #[sqlx(flatten)]
+    artifact: ArtifactRow,
+    content: Vec<u8>,
+}
+
+#[derive(sqlx::FromRow)]
+struct RetainedArtifactRow {
+    #[sqlx(flatten)]
+    artifact: ArtifactRow,
+    pipeline_id: Uuid,
+    retention: String,
+}
+
+impl From<ArtifactRow> for Artifact {
+    fn from(row: ArtifactRow) -> Self {
+        Artifact {
+            id: row.id,
+            job_id: row.job_id,
+            name: row.name,
+            size_bytes: row.size_bytes,
+            sha256: row.sha256,
+            created_at: row.created_at,
+        }
+    }
+}
@@ -0,0 +1,140 @@
+//! Blackout Repository
+//!
+//! Handles all database operations related to blackout windows.
+
+use rivet_core::domain::blackout::BlackoutWindow;
+use rivet_core::dto::blackout::CreateBlackoutWindow;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+/// Add a blackout window to a project
+#[tracing::instrument(name = "blackout_repository::create", skip_all)]
+pub async fn create(
+    pool: &PgPool,
+    project_id: Uuid,
+    req: CreateBlackoutWindow,
+) -> Result<BlackoutWindow, sqlx::Error> {
+    let now = chrono::Utc::now();
+    let window = BlackoutWindow {
+        id: Uuid::new_v4(),
+        project_id,
+        pipeline_id: req.pipeline_id,
+        environment: req.environment,
+        starts_at: req.starts_at.unwrap_or(now),
+        ends_at: req.ends_at,
+        reason: req.reason,
+        created_at: now,
+    };
+
+    sqlx::query(
+        r#"
+        INSERT INTO blackout_windows (id, project_id, pipeline_id, environment, starts_at, ends_at, reason, created_at)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+        "#,
+    )
+    .bind(window.id)
+    .bind(window.project_id)
+    .bind(window.pipeline_id)
+    .bind(&window.environment)
+    .bind(window.starts_at)
+    .bind(window.ends_at)
+    .bind(&window.reason)
+    .bind(window.created_at)
+    .execute(pool)
+    .await?;
+
+    Ok(window)
+}
+
+/// List the blackout windows of a project that have not ended, soonest first
+#[tracing::instrument(name = "blackout_repository::list_by_project", skip_all)]
+pub async fn list_by_project(
+    pool: &PgPool,
+    project_id: Uuid,
+) -> Result<Vec<BlackoutWindow>, sqlx::Error> {
+    let rows = sqlx::query_as::<_, BlackoutWindowRow>(
+        r#"
+        SELECT id, project_id, pipeline_id, environment, starts_at, ends_at, reason, created_at
+        FROM blackout_windows
+        WHERE project_id = $1 AND ends_at > NOW()
+        ORDER BY starts_at ASC, created_at ASC
+        "#,
+    )
+    .bind(project_id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows.into_iter().map(|r| r.into()).collect())
+}
+
+/// Open blackout windows deferring a job, none if it overrides them
+///
+/// Mirrors the blackout condition of `scheduling`: windows of the job's
+/// pipeline, or of an environment one of its stages deploys to.
+#[tracing::instrument(name = "blackout_repository::find_active_for_job", skip_all)]
+pub async fn find_active_for_job(
+    pool: &PgPool,
+    job_id: Uuid,
+) -> Result<Vec<BlackoutWindow>, sqlx::Error> {
+    let rows = sqlx::query_as::<_, BlackoutWindowRow>(
+        r#"
+        SELECT b.id, b.project_id, b.pipeline_id, b.environment, b.starts_at, b.ends_at, b.reason, b.created_at
+        FROM jobs j
+        JOIN pipelines bp ON bp.id = j.pipeline_id
+        JOIN blackout_windows b ON b.project_id = bp.project_id
+        WHERE j.id = $1 AND NOT j.override_blackout
+          AND b.starts_at <= NOW() AND b.ends_at > NOW()
+          AND (b.pipeline_id = bp.id
+               OR b.environment IN (SELECT value FROM jsonb_each_text(bp.deploy_stages)))
+        ORDER BY b.ends_at DESC
+        "#,
+    )
+    .bind(job_id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows.into_iter().map(|r| r.into()).collect())
+}
+
+/// Delete a blackout window of a project
+#[tracing::instrument(name = "blackout_repository::delete", skip_all)]
+pub async fn delete(pool: &PgPool, project_id: Uuid, id: Uuid) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query("DELETE FROM blackout_windows WHERE id = $1 AND project_id = $2")
+        .bind(id)
+        .bind(project_id)
+        .execute(pool)
+        .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+// =============================================================================
+// Database Row Types
+// =============================================================================
+
+#[derive(sqlx::FromRow)]
+struct BlackoutWindowRow {
+    id: Uuid,
+    project_id: Uuid,
+    pipeline_id: Option<Uuid>,
+    environment: Option<String>,
+    starts_at: chrono::DateTime<chrono::Utc>,
+    ends_at: chrono::DateTime<chrono::Utc>,
+    reason: Option<String>,
+    created_at: chrono::DateTime<chrono::Utc>,
+}
+
+impl From<BlackoutWindowRow> for BlackoutWindow {
+    fn from(row: BlackoutWindowRow) -> Self {
+        BlackoutWindow {
+            id: row.id,
+            project_id: row.project_id,
+            pipeline_id: row.pipeline_id,
+            environment: row.environment,
+            starts_at: row.starts_at,
+            ends_at: row.ends_at,
+            reason: row.reason,
+            created_at: row.created_at,
+        }
+    }
+}
@@ -0,0 +1,632 @@
+//! Environment Repository
+//!
+//! Handles all database operations related to environments, the deployments
+//! made to them and the approvals jobs deploying to them get.
+//!
+//! Deployments, locks and approvals refer to environments by project and
+//! name, so a stage may deploy to an environment nobody created: it is
+//! created, with no approvals required, by the first job locking it or its
+//! first deployment.
+
+use std::collections::HashMap;
+
+use rivet_core::domain::environment::{
+    Approval, Deployment, DeploymentStatus, Environment, EnvironmentLock,
+};
+use rivet_core::dto::environment::SetEnvironment;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::scheduling::LOCKED_OUT;
+
+/// Create or replace an environment of a project
+#[tracing::instrument(name = "environment_repository::upsert", skip_all)]
+pub async fn upsert(
+    pool: &PgPool,
+    project_id: Uuid,
+    name: &str,
+    req: &SetEnvironment,
+) -> Result<Environment, sqlx::Error> {
+    let row = sqlx::query_as::<_, EnvironmentRow>(
+        r#"
+        INSERT INTO environments (project_id, name, description, required_approvals, created_at)
+        VALUES ($1, $2, $3, $4, $5)
+        ON CONFLICT (project_id, name)
+        DO UPDATE SET description = EXCLUDED.description,
+                      required_approvals = EXCLUDED.required_approvals
+        RETURNING project_id, name, description, required_approvals, created_at
+        "#,
+    )
+    .bind(project_id)
+    .bind(name)
+    .bind(&req.description)
+    .bind(req.required_approvals as i32)
+    .bind(chrono::Utc::now())
+    .fetch_one(pool)
+    .await?;
+
+    Ok(row.into())
+}
+
+/// Find an environment of a project by name
+#[tracing::instrument(name = "environment_repository::find", skip_all)]
+pub async fn find(
+    pool: &PgPool,
+    project_id: Uuid,
+    name: &str,
+) -> Result<Option<Environment>, sqlx::Error> {
+    let row = sqlx::query_as::<_, EnvironmentRow>(
+        r#"
+        SELECT project_id, name, description, required_approvals, created_at
+        FROM environments
+        WHERE project_id = $1 AND name = $2
+        "#,
+    )
+    .bind(project_id)
+    .bind(name)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(row.map(|r| r.into()))
+}
+
+/// List the environments of a project, by name
+#[tracing::instrument(name = "environment_repository::list_by_project", skip_all)]
+pub async fn list_by_project(
+    pool: &PgPool,
+    project_id: Uuid,
+) -> Result<Vec<Environment>, sqlx::Error> {
+    let rows = sqlx::query_as::<_, EnvironmentRow>(
+        r#"
+        SELECT project_id, name, description, required_approvals, created_at
+        FROM environments
+        WHERE project_id = $1
+        ORDER BY name ASC
+        "#,
+    )
+    .bind(project_id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows.into_iter().map(|r| r.into()).collect())
+}
+
+/// Delete an environment of a project with its deployment history and lock
+#[tracing::instrument(name = "environment_repository::delete", skip_all)]
+pub async fn delete(pool: &PgPool, project_id: Uuid, name: &str) -> Result<bool, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+
+    sqlx::query("DELETE FROM deployments WHERE project_id = $1 AND environment = $2")
+        .bind(project_id)
+        .bind(name)
+        .execute(&mut *tx)
+        .await?;
+
+    sqlx::query("DELETE FROM environment_locks WHERE project_id = $1 AND environment = $2")
+        .bind(project_id)
+        .bind(name)
+        .execute(&mut *tx)
+        .await?;
+
+    let result = sqlx::query("DELETE FROM environments WHERE project_id = $1 AND name = $2")
+        .bind(project_id)
+        .bind(name)
+        .execute(&mut *tx)
+        .await?;
+
+    tx.commit().await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+// =============================================================================
+// Deployments
+// =============================================================================
+
+/// Record the progress of a stage of the current attempt of a job as a
+/// deployment, if the stage deploys to an environment
+///
+/// # Returns
+/// Whether the stage deploys to an environment
+#[tracing::instrument(name = "environment_repository::record_deployment", skip_all)]
+pub async fn record_deployment(
+    pool: &PgPool,
+    job_id: Uuid,
+    stage: &str,
+    status: DeploymentStatus,
+    timestamp: chrono::DateTime<chrono::Utc>,
+) -> Result<bool, sqlx::Error> {
+    let completed_at = (status != DeploymentStatus::Running).then_some(timestamp);
+
+    let result = sqlx::query(
+        r#"
+        WITH target AS (
+            SELECT p.project_id, p.deploy_stages ->> $2 AS environment, j.pipeline_id, j.attempt
+            FROM jobs j
+            JOIN pipelines p ON p.id = j.pipeline_id
+            WHERE j.id = $1 AND p.deploy_stages ? $2
+        ),
+        created AS (
+            INSERT INTO environments (project_id, name, required_approvals, created_at)
+            SELECT project_id, environment, 0, $4 FROM target
+            ON CONFLICT (project_id, name) DO NOTHING
+        )
+        INSERT INTO deployments (id, project_id, environment, pipeline_id, job_id, attempt,
+                                 stage, status, started_at, completed_at)
+        SELECT $5, project_id, environment, pipeline_id, $1, attempt, $2, $3, $4, $6
+        FROM target
+        ON CONFLICT (job_id, attempt, stage) DO UPDATE
+        SET status = EXCLUDED.status,
+            completed_at = EXCLUDED.completed_at
+        "#,
+    )
+    .bind(job_id)
+    .bind(stage)
+    .bind(deployment_status_to_string(status))
+    .bind(timestamp)
+    .bind(Uuid::new_v4())
+    .bind(completed_at)
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+/// List the deployments to an environment of a project, newest first
+#[tracing::instrument(name = "environment_repository::find_deployments", skip_all)]
+pub async fn find_deployments(
+    pool: &PgPool,
+    project_id: Uuid,
+    environment: &str,
+    limit: u32,
+) -> Result<Vec<Deployment>, sqlx::Error> {
+    let rows = sqlx::query_as::<_, DeploymentRow>(
+        r#"
+        SELECT d.id, d.environment, d.pipeline_id, d.job_id, d.attempt, d.stage, d.status,
+               j.commit_ref, d.started_at, d.completed_at
+        FROM deployments d
+        JOIN jobs j ON j.id = d.job_id
+        WHERE d.project_id = $1 AND d.environment = $2
+        ORDER BY d.started_at DESC
+        LIMIT $3
+        "#,
+    )
+    .bind(project_id)
+    .bind(environment)
+    .bind(limit as i64)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows.into_iter().map(|r| r.into()).collect())
+}
+
+/// Latest deployment to each environment of a project, by environment name
+///
+/// # Arguments
+/// * `succeeded_only` - Only consider successful deployments, to find what
+///   each environment currently runs
+#[tracing::instrument(name = "environment_repository::find_latest_deployments", skip_all)]
+pub async fn find_latest_deployments(
+    pool: &PgPool,
+    project_id: Uuid,
+    succeeded_only: bool,
+) -> Result<HashMap<String, Deployment>, sqlx::Error> {
+    let rows = sqlx::query_as::<_, DeploymentRow>(
+        r#"
+        SELECT DISTINCT ON (d.environment)
+               d.id, d.environment, d.pipeline_id, d.job_id, d.attempt, d.stage, d.status,
+               j.commit_ref, d.started_at, d.completed_at
+        FROM deployments d
+        JOIN jobs j ON j.id = d.job_id
+        WHERE d.project_id = $1 AND (NOT $2 OR d.status = 'Succeeded')
+        ORDER BY d.environment, d.started_at DESC
+        "#,
+    )
+    .bind(project_id)
+    .bind(succeeded_only)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows
+        .into_iter()
+        .map(|r| (r.environment.clone(), r.into()))
+        .collect())
+}
+
+// =============================================================================
+// Approvals
+// =============================================================================
+
+/// Environments the stages of a job's pipeline deploy to, with the approvals
+/// each requires (0 for environments not created yet), by name
+#[tracing::instrument(name = "environment_repository::find_required_approvals", skip_all)]
+pub async fn find_required_approvals(
+    pool: &PgPool,
+    job_id: Uuid,
+) -> Result<Vec<(String, u32)>, sqlx::Error> {
+    let rows: Vec<(String, i32)> = sqlx::query_as(
+        r#"
+        SELECT DISTINCT d.value, COALESCE(e.required_approvals, 0)
+        FROM jobs j
+        JOIN pipelines p ON p.id = j.pipeline_id
+        CROSS JOIN jsonb_each_text(p.deploy_stages) d
+        LEFT JOIN environments e ON e.project_id = p.project_id AND e.name = d.value
+        WHERE j.id = $1
+        ORDER BY d.value
+        "#,
+    )
+    .bind(job_id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows
+        .into_iter()
+        .map(|(name, required)| (name, required.max(0) as u32))
+        .collect())
+}
+
+/// Record the approval of a job's deployments to an environment
+///
+/// Approving again replaces the approver's comment.
+#[tracing::instrument(name = "environment_repository::approve", skip_all)]
+pub async fn approve(
+    pool: &PgPool,
+    job_id: Uuid,
+    environment: &str,
+    approver: &str,
+    comment: Option<&str>,
+) -> Result<Approval, sqlx::Error> {
+    let row = sqlx::query_as::<_, ApprovalRow>(
+        r#"
+        INSERT INTO job_approvals (job_id, environment, approver, comment, approved_at)
+        VALUES ($1, $2, $3, $4, $5)
+        ON CONFLICT (job_id, environment, approver)
+        DO UPDATE SET comment = EXCLUDED.comment, approved_at = EXCLUDED.approved_at
+        RETURNING job_id, environment, approver, comment, approved_at
+        "#,
+    )
+    .bind(job_id)
+    .bind(environment)
+    .bind(approver)
+    .bind(comment)
+    .bind(chrono::Utc::now())
+    .fetch_one(pool)
+    .await?;
+
+    Ok(row.into())
+}
+
+/// List the approvals of a job, oldest first
+#[tracing::instrument(name = "environment_repository::find_approvals", skip_all)]
+pub async fn find_approvals(pool: &PgPool, job_id: Uuid) -> Result<Vec<Approval>, sqlx::Error> {
+    let rows = sqlx::query_as::<_, ApprovalRow>(
+        r#"
+        SELECT job_id, environment, approver, comment, approved_at
+        FROM job_approvals
+        WHERE job_id = $1
+        ORDER BY approved_at ASC
+        "#,
+    )
+    .bind(job_id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows.into_iter().map(|r| r.into()).collect())
+}
+
+// =============================================================================
+// Locks
+// =============================================================================
+
+/// Take the locks of the environments a job deploys to, all or none
+///
+/// Environments are created as for deployments. A job re-taking a lock it holds (on a retry) keeps it; locks of finished
+/// jobs are taken over.
+///
+/// # Returns
+/// `None` if the job deploys to no environment, else whether it holds every
+/// lock it needs. A job not holding every lock must release those it took.
+#[tracing::instrument(name = "environment_repository::acquire_locks", skip_all)]
+pub async fn acquire_locks(pool: &PgPool, job_id: Uuid) -> Result<Option<bool>, sqlx::Error> {
+    let (wanted, taken): (i64, i64) = sqlx::query_as(
+        r#"
+        WITH wanted AS (
+            SELECT DISTINCT p.project_id, d.value AS environment
+            FROM jobs j
+            JOIN pipelines p ON p.id = j.pipeline_id
+            CROSS JOIN jsonb_each_text(p.deploy_stages) d
+            WHERE j.id = $1
+        ),
+        created AS (
+            INSERT INTO environments (project_id, name, required_approvals, created_at)
+            SELECT project_id, environment, 0, $2 FROM wanted
+            ON CONFLICT (project_id, name) DO NOTHING
+        ),
+        taken AS (
+            INSERT INTO environment_locks (project_id, environment, job_id, acquired_at)
+            SELECT project_id, environment, $1, $2 FROM wanted
+            ON CONFLICT (project_id, environment) DO UPDATE
+            SET job_id = EXCLUDED.job_id,
+                acquired_at = CASE WHEN environment_locks.job_id = EXCLUDED.job_id
+                                   THEN environment_locks.acquired_at
+                                   ELSE EXCLUDED.acquired_at END
+            WHERE environment_locks.job_id = EXCLUDED.job_id
+               OR NOT EXISTS (
+                   SELECT 1 FROM jobs h
+                   WHERE h.id = environment_locks.job_id AND h.status IN ('Queued', 'Running')
+               )
+            RETURNING environment
+        )
+        SELECT (SELECT COUNT(*) FROM wanted), (SELECT COUNT(*) FROM taken)
+        "#,
+    )
+    .bind(job_id)
+    .bind(chrono::Utc::now())
+    .fetch_one(pool)
+    .await?;
+
+    Ok((wanted > 0).then_some(wanted == taken))
+}
+
+/// Release the environment locks a job holds
+#[tracing::instrument(name = "environment_repository::release_locks", skip_all)]
+pub async fn release_locks(pool: &PgPool, job_id: Uuid) -> Result<u64, sqlx::Error> {
+    let result = sqlx::query("DELETE FROM environment_locks WHERE job_id = $1")
+        .bind(job_id)
+        .execute(pool)
+        .await?;
+
+    Ok(result.rows_affected())
+}
+
+/// Release the lock of an environment, whoever holds it
+///
+/// # Returns
+/// The job that held the lock, `None` if no job did
+#[tracing::instrument(name = "environment_repository::force_release_lock", skip_all)]
+pub async fn force_release_lock(
+    pool: &PgPool,
+    project_id: Uuid,
+    environment: &str,
+) -> Result<Option<Uuid>, sqlx::Error> {
+    let holder = sqlx::query_scalar::<_, Uuid>(
+        r#"
+        DELETE FROM environment_locks l
+        USING jobs h
+        WHERE l.project_id = $1 AND l.environment = $2
+          AND h.id = l.job_id AND h.status IN ('Queued', 'Running')
+        RETURNING l.job_id
+        "#,
+    )
+    .bind(project_id)
+    .bind(environment)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(holder)
+}
+
+/// Locks held on the environments of a project, by environment name
+///
+/// Stale locks, of jobs that finished, are left out.
+#[tracing::instrument(name = "environment_repository::find_locks", skip_all)]
+pub async fn find_locks(
+    pool: &PgPool,
+    project_id: Uuid,
+) -> Result<HashMap<String, EnvironmentLock>, sqlx::Error> {
+    let rows = sqlx::query_as::<_, LockRow>(
+        r#"
+        SELECT l.environment, l.job_id, h.pipeline_id, l.acquired_at
+        FROM environment_locks l
+        JOIN jobs h ON h.id = l.job_id
+        WHERE l.project_id = $1 AND h.status IN ('Queued', 'Running')
+        "#,
+    )
+    .bind(project_id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows
+        .into_iter()
+        .map(|r| (r.environment.clone(), r.into()))
+        .collect())
+}
+
+/// Locks held by other jobs on the environments a job deploys to
+#[tracing::instrument(name = "environment_repository::find_blocking_locks", skip_all)]
+pub async fn find_blocking_locks(
+    pool: &PgPool,
+    job_id: Uuid,
+) -> Result<Vec<EnvironmentLock>, sqlx::Error> {
+    let rows = sqlx::query_as::<_, LockRow>(
+        r#"
+        SELECT l.environment, l.job_id, h.pipeline_id, l.acquired_at
+        FROM jobs j
+        JOIN pipelines p ON p.id = j.pipeline_id
+        CROSS JOIN jsonb_each_text(p.deploy_stages) d
+        JOIN environment_locks l ON l.project_id = p.project_id AND l.environment = d.value
+        JOIN jobs h ON h.id = l.job_id
+        WHERE j.id = $1 AND l.job_id <> j.id AND h.status IN ('Queued', 'Running')
+        ORDER BY l.environment
+        "#,
+    )
+    .bind(job_id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows.into_iter().map(|r| r.into()).collect())
+}
+
+/// Blocked jobs deploying to an environment of a project, oldest first
+#[tracing::instrument(name = "environment_repository::find_waiting_jobs", skip_all)]
+pub async fn find_waiting_jobs(
+    pool: &PgPool,
+    project_id: Uuid,
+    environment: &str,
+) -> Result<Vec<Uuid>, sqlx::Error> {
+    let ids = sqlx::query_scalar::<_, Uuid>(
+        r#"
+        SELECT j.id
+        FROM jobs j
+        JOIN pipelines p ON p.id = j.pipeline_id
+        WHERE j.status = 'Blocked' AND p.project_id = $1
+          AND EXISTS (SELECT 1 FROM jsonb_each_text(p.deploy_stages) d WHERE d.value = $2)
+        ORDER BY j.requested_at ASC, j.id
+        "#,
+    )
+    .bind(project_id)
+    .bind(environment)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(ids)
+}
+
+/// Mark queued jobs locked out of an environment as blocked
+#[tracing::instrument(name = "environment_repository::block_locked_out_jobs", skip_all)]
+pub async fn block_locked_out_jobs(pool: &PgPool) -> Result<u64, sqlx::Error> {
+    let result = sqlx::query(&format!(
+        "UPDATE jobs j SET status = 'Blocked' WHERE j.status = 'Queued' AND {LOCKED_OUT}"
+    ))
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected())
+}
+
+/// Put blocked jobs no longer locked out of any environment back in the queue
+///
+/// # Returns
+/// `(job_id, pipeline_id)` of the jobs put back in the queue
+#[tracing::instrument(name = "environment_repository::unblock_jobs", skip_all)]
+pub async fn unblock_jobs(pool: &PgPool) -> Result<Vec<(Uuid, Uuid)>, sqlx::Error> {
+    let rows = sqlx::query_as::<_, (Uuid, Uuid)>(&format!(
+        r#"
+        UPDATE jobs j SET status = 'Queued'
+        WHERE j.status = 'Blocked' AND NOT {LOCKED_OUT}
+        RETURNING j.id, j.pipeline_id
+        "#
+    ))
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows)
+}
+
+// =============================================================================
+// Helper Functions
+// =============================================================================
+
+fn deployment_status_to_string(status: DeploymentStatus) -> &'static str {
+    match status {
+        DeploymentStatus::Running => "Running",
+        DeploymentStatus::Succeeded => "Succeeded",
+        DeploymentStatus::Failed => "Failed",
+    }
+}
+
+fn string_to_deployment_status(s: &str) -> DeploymentStatus {
+    match s {
+        "Succeeded" => DeploymentStatus::Succeeded,
+        "Failed" => DeploymentStatus::Failed,
+        _ => DeploymentStatus::Running,
+    }
+}
+
+// =============================================================================
+// Database Row Types
+// =============================================================================
+
+#[derive(sqlx::FromRow)]
+struct EnvironmentRow {
+    project_id: Uuid,
+    name: String,
+    description: Option<String>,
+    required_approvals: i32,
+    created_at: chrono::DateTime<chrono::Utc>,
+}
+
+impl From<EnvironmentRow> for Environment {
+    fn from(row: EnvironmentRow) -> Self {
+        Environment {
+            name: row.name,
+            project_id: row.project_id,
+            description: row.description,
+            required_approvals: row.required_approvals.max(0) as u32,
+            created_at: row.created_at,
+        }
+    }
+}
+
+#[derive(sqlx::FromRow)]
+struct DeploymentRow {
+    id: Uuid,
+    environment: String,
+    pipeline_id: Uuid,
+    job_id: Uuid,
+    attempt: i32,
+    stage: String,
+    status: String,
+    commit_ref: Option<serde_json::Value>,
+    started_at: chrono::DateTime<chrono::Utc>,
+    completed_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+impl From<DeploymentRow> for Deployment {
+    fn from(row: DeploymentRow) -> Self {
+        Deployment {
+            id: row.id,
+            environment: row.environment,
+            pipeline_id: row.pipeline_id,
+            job_id: row.job_id,
+            attempt: row.attempt.max(1) as u32,
+            stage: row.stage,
+            status: string_to_deployment_status(&row.status),
+            commit: row
+                .commit_ref
+                .and_then(|value| serde_json::from_value(value).ok()),
+            started_at: row.started_at,
+            completed_at: row.completed_at,
+        }
+    }
+}
+
+#[derive(sqlx::FromRow)]
+struct ApprovalRow {
+    job_id: Uuid,
+    environment: String,
+    approver: String,
+    comment: Option<String>,
+    approved_at: chrono::DateTime<chrono::Utc>,
+}
+
+impl From<ApprovalRow> for Approval {
+    fn from(row: ApprovalRow) -> Self {
+        Approval {
+            job_id: row.job_id,
+            environment: row.environment,
+            approver: row.approver,
+            comment: row.comment,
+            approved_at: row.approved_at,
+        }
+    }
+}
+
+#[derive(sqlx::FromRow)]
+struct LockRow {
+    environment: String,
+    job_id: Uuid,
+    pipeline_id: Uuid,
+    acquired_at: chrono::DateTime<chrono::Utc>,
+}
+
+impl From<LockRow> for EnvironmentLock {
+    fn from(row: LockRow) -> Self {
+        EnvironmentLock {
+            environment: row.environment,
+            job_id: row.job_id,
+            pipeline_id: row.pipeline_id,
+            acquired_at: row.acquired_at,
+        }
+    }
+}
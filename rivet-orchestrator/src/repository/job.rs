@@ -2,18 +2,40 @@
 //!
 //! Handles all database operations related to jobs.
 
-use rivet_core::domain::job::{Job, JobResult, JobStatus};
-use rivet_core::dto::job::CreateJob;
-use sqlx::PgPool;
+use rivet_core::domain::job::{CommitRef, Job, JobResult, JobStatus};
+use rivet_core::dto::job::{CreateJob, JobFilter, ScheduleRef};
+use sqlx::{PgConnection, PgPool};
+use std::collections::{BTreeMap, HashMap};
 use uuid::Uuid;
 
+use crate::repository::artifact_repository;
+use crate::scheduling::{SchedulingPolicy, least_loaded_sql, queue_turns_sql};
+
 /// Create a new job in the database
-pub async fn create(pool: &PgPool, req: CreateJob) -> Result<Job, sqlx::Error> {
+///
+/// The job may run up to `max_attempts` times before it is given up on.
+/// `secret_parameters` holds the values of its secret inputs, which
+/// `req.parameters` only shows masked. `traceparent` identifies the span that
+/// launched the job, whose trace the runner continues. `schedule_id` is the
+/// schedule that launched it, if any. The job carries `req.labels` and the
+/// next number of its pipeline.
+#[tracing::instrument(name = "job_repository::create", skip_all)]
+pub async fn create(
+    pool: &PgPool,
+    req: CreateJob,
+    secret_parameters: &HashMap<String, serde_json::Value>,
+    max_attempts: u32,
+    traceparent: Option<&str>,
+    schedule_id: Option<Uuid>,
+) -> Result<Job, sqlx::Error> {
     let id = Uuid::new_v4();
     let now = chrono::Utc::now();
+    let mut tx = pool.begin().await?;
+    let number = next_number(&mut tx, req.pipeline_id).await?;
 
     let job = Job {
         id,
+        number,
         pipeline_id: req.pipeline_id,
         status: JobStatus::Queued,
         requested_at: now,
@@ -22,12 +44,20 @@ pub async fn create(pool: &PgPool, req: CreateJob) -> Result<Job, sqlx::Error> {
         runner_id: None,
         parameters: req.parameters.clone(),
         result: None,
+        attempt: 1,
+        max_attempts,
+        dead_letter_reason: None,
+        resumed_from: None,
+        commit: req.commit.clone(),
+        labels: req.labels.clone(),
     };
 
     sqlx::query(
         r#"
-        INSERT INTO jobs (id, pipeline_id, status, requested_at, parameters)
-        VALUES ($1, $2, $3, $4, $5)
+        INSERT INTO jobs (id, pipeline_id, status, requested_at, parameters, max_attempts,
+                          secret_parameters, traceparent, commit_ref, override_blackout,
+                          schedule_id, labels, number)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13)
         "#,
     )
     .bind(id)
@@ -35,19 +65,235 @@ pub async fn create(pool: &PgPool, req: CreateJob) -> Result<Job, sqlx::Error> {
     .bind("Queued")
     .bind(now)
     .bind(serde_json::to_value(&req.parameters).unwrap())
-    .execute(pool)
+    .bind(max_attempts as i32)
+    .bind(serde_json::to_value(secret_parameters).unwrap())
+    .bind(traceparent)
+    .bind(commit_to_json(req.commit.as_ref()))
+    .bind(req.override_blackout)
+    .bind(schedule_id)
+    .bind(serde_json::to_value(&req.labels).unwrap())
+    .bind(number as i64)
+    .execute(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+
+    Ok(job)
+}
+
+/// Create several jobs of one pipeline in a single transaction
+///
+/// Either every job is created or none is. Jobs are queued in the order of
+/// `parameter_sets`, each the parameters of a job and the values of its
+/// secret inputs. Every job continues the trace of `traceparent`.
+#[tracing::instrument(name = "job_repository::create_many", skip_all)]
+pub async fn create_many(
+    pool: &PgPool,
+    pipeline_id: Uuid,
+    parameter_sets: Vec<(
+        HashMap<String, serde_json::Value>,
+        HashMap<String, serde_json::Value>,
+    )>,
+    max_attempts: u32,
+    traceparent: Option<&str>,
+) -> Result<Vec<Job>, sqlx::Error> {
+    let now = chrono::Utc::now();
+    let mut tx = pool.begin().await?;
+    let mut jobs = Vec::with_capacity(parameter_sets.len());
+
+    for (index, (parameters, secret_parameters)) in parameter_sets.into_iter().enumerate() {
+        // Microsecond steps keep the batch order on `requested_at`
+        let requested_at = now + chrono::Duration::microseconds(index as i64);
+        let job = Job {
+            id: Uuid::new_v4(),
+            number: next_number(&mut tx, pipeline_id).await?,
+            pipeline_id,
+            status: JobStatus::Queued,
+            requested_at,
+            started_at: None,
+            completed_at: None,
+            runner_id: None,
+            parameters,
+            result: None,
+            attempt: 1,
+            max_attempts,
+            dead_letter_reason: None,
+            resumed_from: None,
+            commit: None,
+            labels: BTreeMap::new(),
+        };
+
+        sqlx::query(
+            r#"
+            INSERT INTO jobs (id, pipeline_id, status, requested_at, parameters, max_attempts,
+                              secret_parameters, traceparent, number)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+            "#,
+        )
+        .bind(job.id)
+        .bind(pipeline_id)
+        .bind("Queued")
+        .bind(requested_at)
+        .bind(serde_json::to_value(&job.parameters).unwrap())
+        .bind(max_attempts as i32)
+        .bind(serde_json::to_value(&secret_parameters).unwrap())
+        .bind(traceparent)
+        .bind(job.number as i64)
+        .execute(&mut *tx)
+        .await?;
+
+        jobs.push(job);
+    }
+
+    tx.commit().await?;
+
+    Ok(jobs)
+}
+
+/// Create a job resuming `original`, with its parameters (secret ones
+/// included), commit, labels and artifacts
+///
+/// The artifacts are copied in the same transaction, so the new job starts
+/// with everything the stages it skips produced. The new job continues the
+/// trace of `traceparent`.
+#[tracing::instrument(name = "job_repository::create_resumed", skip_all)]
+pub async fn create_resumed(
+    pool: &PgPool,
+    original: &Job,
+    traceparent: Option<&str>,
+) -> Result<Job, sqlx::Error> {
+    let now = chrono::Utc::now();
+    let mut tx = pool.begin().await?;
+
+    let job = Job {
+        id: Uuid::new_v4(),
+        number: next_number(&mut tx, original.pipeline_id).await?,
+        pipeline_id: original.pipeline_id,
+        status: JobStatus::Queued,
+        requested_at: now,
+        started_at: None,
+        completed_at: None,
+        runner_id: None,
+        parameters: original.parameters.clone(),
+        result: None,
+        attempt: 1,
+        max_attempts: original.max_attempts,
+        dead_letter_reason: None,
+        resumed_from: Some(original.id),
+        commit: original.commit.clone(),
+        labels: original.labels.clone(),
+    };
+
+    sqlx::query(
+        r#"
+        INSERT INTO jobs (id, pipeline_id, status, requested_at, parameters, max_attempts,
+                          resumed_from, secret_parameters, traceparent, commit_ref, labels,
+                          number)
+        VALUES ($1, $2, $3, $4, $5, $6, $7,
+                (SELECT secret_parameters FROM jobs WHERE id = $7), $8, $9, $10, $11)
+        "#,
+    )
+    .bind(job.id)
+    .bind(job.pipeline_id)
+    .bind("Queued")
+    .bind(now)
+    .bind(serde_json::to_value(&job.parameters).unwrap())
+    .bind(job.max_attempts as i32)
+    .bind(original.id)
+    .bind(traceparent)
+    .bind(commit_to_json(job.commit.as_ref()))
+    .bind(serde_json::to_value(&job.labels).unwrap())
+    .bind(job.number as i64)
+    .execute(&mut *tx)
     .await?;
 
+    artifact_repository::copy_to_job(&mut tx, original.id, job.id).await?;
+
+    tx.commit().await?;
+
     Ok(job)
 }
 
+/// Takes the next number of a job of a pipeline
+///
+/// The pipeline's counter stays locked until the transaction of `conn` ends,
+/// so that jobs launched concurrently get distinct numbers.
+async fn next_number(conn: &mut PgConnection, pipeline_id: Uuid) -> Result<u64, sqlx::Error> {
+    let (number,): (i64,) = sqlx::query_as(
+        "UPDATE pipelines SET last_job_number = last_job_number + 1 WHERE id = $1 RETURNING last_job_number",
+    )
+    .bind(pipeline_id)
+    .fetch_one(&mut *conn)
+    .await?;
+
+    Ok(number as u64)
+}
+
+/// Values of the secret inputs of a job
+#[tracing::instrument(name = "job_repository::find_secret_parameters", skip_all)]
+pub async fn find_secret_parameters(
+    pool: &PgPool,
+    id: Uuid,
+) -> Result<HashMap<String, serde_json::Value>, sqlx::Error> {
+    let row: Option<(String,)> =
+        sqlx::query_as("SELECT secret_parameters::text FROM jobs WHERE id = $1")
+            .bind(id)
+            .fetch_optional(pool)
+            .await?;
+
+    Ok(row
+        .and_then(|(json,)| serde_json::from_str(&json).ok())
+        .unwrap_or_default())
+}
+
+/// `traceparent` of the span that launched a job, if it was traced
+#[tracing::instrument(name = "job_repository::find_traceparent", skip_all)]
+pub async fn find_traceparent(pool: &PgPool, id: Uuid) -> Result<Option<String>, sqlx::Error> {
+    let row: Option<(Option<String>,)> =
+        sqlx::query_as("SELECT traceparent FROM jobs WHERE id = $1")
+            .bind(id)
+            .fetch_optional(pool)
+            .await?;
+
+    Ok(row.and_then(|(traceparent,)| traceparent))
+}
+
+/// Schedule that launched a job, if any and it still exists
+#[tracing::instrument(name = "job_repository::find_schedule", skip_all)]
+pub async fn find_schedule(pool: &PgPool, id: Uuid) -> Result<Option<ScheduleRef>, sqlx::Error> {
+    let row: Option<(Uuid, String)> = sqlx::query_as(
+        r#"
+        SELECT s.id, s.cron
+        FROM jobs j
+        JOIN pipeline_schedules s ON s.id = j.schedule_id
+        WHERE j.id = $1
+        "#,
+    )
+    .bind(id)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(row.map(|(id, cron)| ScheduleRef { id, cron }))
+}
+
+/// Fencing token of the current claim of a job, 0 if it was never claimed
+#[tracing::instrument(name = "job_repository::find_claim_token", skip_all)]
+pub async fn find_claim_token(pool: &PgPool, id: Uuid) -> Result<Option<i64>, sqlx::Error> {
+    sqlx::query_scalar("SELECT claim_token FROM jobs WHERE id = $1")
+        .bind(id)
+        .fetch_optional(pool)
+        .await
+}
+
 /// Find a job by ID
+#[tracing::instrument(name = "job_repository::find_by_id", skip_all)]
 pub async fn find_by_id(pool: &PgPool, id: Uuid) -> Result<Option<Job>, sqlx::Error> {
     let row = sqlx::query_as::<_, JobRow>(
         r#"
         SELECT id, pipeline_id, status, requested_at, started_at, completed_at,
                runner_id, parameters, result_success, result_exit_code,
-               result_output, result_error_message
+               result_output, result_error_message, attempt, max_attempts,
+               dead_letter_reason, resumed_from, commit_ref, labels, number
         FROM jobs
         WHERE id = $1
         "#,
@@ -60,6 +306,7 @@ pub async fn find_by_id(pool: &PgPool, id: Uuid) -> Result<Option<Job>, sqlx::Er
 }
 
 /// Find jobs by status
+#[tracing::instrument(name = "job_repository::find_by_status", skip_all)]
 pub async fn find_by_status(pool: &PgPool, status: JobStatus) -> Result<Vec<Job>, sqlx::Error> {
     let status_str = status_to_string(status);
 
@@ -67,7 +314,8 @@ pub async fn find_by_status(pool: &PgPool, status: JobStatus) -> Result<Vec<Job>
         r#"
         SELECT id, pipeline_id, status, requested_at, started_at, completed_at,
                runner_id, parameters, result_success, result_exit_code,
-               result_output, result_error_message
+               result_output, result_error_message, attempt, max_attempts,
+               dead_letter_reason, resumed_from, commit_ref, labels, number
         FROM jobs
         WHERE status = $1
         ORDER BY requested_at ASC
@@ -80,13 +328,36 @@ pub async fn find_by_status(pool: &PgPool, status: JobStatus) -> Result<Vec<Job>
     Ok(rows.into_iter().map(|r| r.into()).collect())
 }
 
+/// Find jobs of every pipeline in a project, newest first
+#[tracing::instrument(name = "job_repository::find_by_project", skip_all)]
+pub async fn find_by_project(pool: &PgPool, project_id: Uuid) -> Result<Vec<Job>, sqlx::Error> {
+    let rows = sqlx::query_as::<_, JobRow>(
+        r#"
+        SELECT id, pipeline_id, status, requested_at, started_at, completed_at,
+               runner_id, parameters, result_success, result_exit_code,
+               result_output, result_error_message, attempt, max_attempts,
+               dead_letter_reason, resumed_from, commit_ref, labels, number
+        FROM jobs
+        WHERE pipeline_id IN (SELECT id FROM pipelines WHERE project_id = $1)
+        ORDER BY requested_at DESC
+        "#,
+    )
+    .bind(project_id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows.into_iter().map(|r| r.into()).collect())
+}
+
 /// Find jobs by pipeline ID
+#[tracing::instrument(name = "job_repository::find_by_pipeline", skip_all)]
 pub async fn find_by_pipeline(pool: &PgPool, pipeline_id: Uuid) -> Result<Vec<Job>, sqlx::Error> {
     let rows = sqlx::query_as::<_, JobRow>(
         r#"
         SELECT id, pipeline_id, status, requested_at, started_at, completed_at,
                runner_id, parameters, result_success, result_exit_code,
-               result_output, result_error_message
+               result_output, result_error_message, attempt, max_attempts,
+               dead_letter_reason, resumed_from, commit_ref, labels, number
         FROM jobs
         WHERE pipeline_id = $1
         ORDER BY requested_at DESC
@@ -99,14 +370,46 @@ pub async fn find_by_pipeline(pool: &PgPool, pipeline_id: Uuid) -> Result<Vec<Jo
     Ok(rows.into_iter().map(|r| r.into()).collect())
 }
 
+/// Find the most recently requested job of a pipeline
+///
+/// When `branch` is given, only jobs launched with a matching `branch` parameter are considered.
+#[tracing::instrument(name = "job_repository::find_latest_by_pipeline", skip_all)]
+pub async fn find_latest_by_pipeline(
+    pool: &PgPool,
+    pipeline_id: Uuid,
+    branch: Option<&str>,
+) -> Result<Option<Job>, sqlx::Error> {
+    let row = sqlx::query_as::<_, JobRow>(
+        r#"
+        SELECT id, pipeline_id, status, requested_at, started_at, completed_at,
+               runner_id, parameters, result_success, result_exit_code,
+               result_output, result_error_message, attempt, max_attempts,
+               dead_letter_reason, resumed_from, commit_ref, labels, number
+        FROM jobs
+        WHERE pipeline_id = $1
+          AND ($2::TEXT IS NULL OR parameters->>'branch' = $2)
+        ORDER BY requested_at DESC
+        LIMIT 1
+        "#,
+    )
+    .bind(pipeline_id)
+    .bind(branch)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(row.map(|r| r.into()))
+}
+
 /// Update job status and runner assignment (for starting execution)
 /// List all jobs
+#[tracing::instrument(name = "job_repository::list_all", skip_all)]
 pub async fn list_all(pool: &PgPool) -> Result<Vec<Job>, sqlx::Error> {
     let rows = sqlx::query_as::<_, JobRow>(
         r#"
         SELECT id, pipeline_id, status, requested_at, started_at, completed_at,
                runner_id, parameters, result_success, result_exit_code,
-               result_output, result_error_message
+               result_output, result_error_message, attempt, max_attempts,
+               dead_letter_reason, resumed_from, commit_ref, labels, number
         FROM jobs
         ORDER BY requested_at DESC
         "#,
@@ -117,6 +420,47 @@ pub async fn list_all(pool: &PgPool) -> Result<Vec<Job>, sqlx::Error> {
     Ok(rows.into_iter().map(|r| r.into()).collect())
 }
 
+/// List one page of jobs matching `filter`, newest first
+///
+/// Ties on `requested_at` are broken by id so pages never overlap.
+#[tracing::instrument(name = "job_repository::list_page", skip_all)]
+pub async fn list_page(
+    pool: &PgPool,
+    filter: &JobFilter,
+    limit: i64,
+    offset: i64,
+) -> Result<Vec<Job>, sqlx::Error> {
+    let rows = sqlx::query_as::<_, JobRow>(
+        r#"
+        SELECT id, pipeline_id, status, requested_at, started_at, completed_at,
+               runner_id, parameters, result_success, result_exit_code,
+               result_output, result_error_message, attempt, max_attempts,
+               dead_letter_reason, resumed_from, commit_ref, labels, number
+        FROM jobs
+        WHERE ($1::TEXT IS NULL OR status = $1)
+          AND ($2::UUID IS NULL OR pipeline_id = $2)
+          AND ($3::TEXT IS NULL OR runner_id = $3)
+          AND ($4::UUID IS NULL
+               OR pipeline_id IN (SELECT id FROM pipelines WHERE project_id = $4))
+          AND labels @> $7
+        ORDER BY requested_at DESC, id DESC
+        LIMIT $5 OFFSET $6
+        "#,
+    )
+    .bind(filter.status.map(status_to_string))
+    .bind(filter.pipeline_id)
+    .bind(filter.runner_id.as_deref())
+    .bind(filter.project_id)
+    .bind(limit)
+    .bind(offset)
+    .bind(serde_json::to_value(&filter.labels).unwrap())
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows.into_iter().map(|r| r.into()).collect())
+}
+
+#[tracing::instrument(name = "job_repository::update_status_to_running", skip_all)]
 pub async fn update_status_to_running(
     pool: &PgPool,
     job_id: Uuid,
@@ -127,7 +471,8 @@ pub async fn update_status_to_running(
     sqlx::query(
         r#"
         UPDATE jobs
-        SET status = $1, started_at = $2, runner_id = $3
+        SET status = $1, started_at = $2, runner_id = $3, claim_token = claim_token + 1,
+            script_hash = (SELECT md5(script) FROM pipelines WHERE id = jobs.pipeline_id)
         WHERE id = $4
         "#,
     )
@@ -141,7 +486,203 @@ pub async fn update_status_to_running(
     Ok(())
 }
 
+/// Find queued jobs in the order the scheduling policy serves them
+#[tracing::instrument(name = "job_repository::find_queued", skip_all)]
+pub async fn find_queued(pool: &PgPool, policy: SchedulingPolicy) -> Result<Vec<Job>, sqlx::Error> {
+    let rows = sqlx::query_as::<_, JobRow>(&format!(
+        r#"
+        WITH turns AS ({turns})
+        SELECT id, pipeline_id, status, requested_at, started_at, completed_at,
+               runner_id, parameters, result_success, result_exit_code,
+               result_output, result_error_message, attempt, max_attempts,
+               dead_letter_reason, resumed_from, commit_ref, labels, number
+        FROM jobs
+        JOIN turns USING (id)
+        ORDER BY turns.turn ASC, requested_at ASC
+        "#,
+        turns = queue_turns_sql(policy),
+    ))
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows.into_iter().map(|r| r.into()).collect())
+}
+
+/// Atomically assign the next queued job to a runner
+///
+/// The job is picked according to `policy`, among the jobs whose pipeline
+/// requires only tags and plugins the runner provides and that no less
+/// loaded runner should take instead (see `least_loaded_sql`). Concurrent callers
+/// never receive the same job: rows locked by another claim are skipped. The job starts
+/// with a lease expiring at `lease_expires_at`, so it is requeued if the
+/// runner never picks it up, records the version of the pipeline script
+/// the runner is about to receive and bumps the job's claim token.
+///
+/// # Returns
+/// The id of the claimed job, `None` when no job is queued
+#[tracing::instrument(name = "job_repository::claim_next_queued", skip_all)]
+pub async fn claim_next_queued(
+    pool: &PgPool,
+    runner_id: &str,
+    lease_expires_at: chrono::DateTime<chrono::Utc>,
+    policy: SchedulingPolicy,
+) -> Result<Option<Uuid>, sqlx::Error> {
+    let now = chrono::Utc::now();
+
+    let id = sqlx::query_scalar::<_, Uuid>(&format!(
+        r#"
+        WITH turns AS ({turns})
+        UPDATE jobs
+        SET status = $1, started_at = $2, runner_id = $3, lease_expires_at = $5,
+            claim_token = claim_token + 1,
+            script_hash = (SELECT md5(script) FROM pipelines WHERE id = jobs.pipeline_id)
+        WHERE id = (
+            SELECT jobs.id FROM jobs
+            JOIN turns USING (id)
+            JOIN pipelines p ON p.id = jobs.pipeline_id
+            LEFT JOIN runners r ON r.id = $3
+            WHERE jobs.status = $4
+              AND COALESCE(r.tags, '[]') @> p.tags
+              AND COALESCE(r.plugins, '[]') @> p.plugins
+              AND {least_loaded}
+            ORDER BY turns.turn ASC, jobs.requested_at ASC
+            LIMIT 1
+            FOR UPDATE OF jobs SKIP LOCKED
+        )
+        RETURNING id
+        "#,
+        turns = queue_turns_sql(policy),
+        least_loaded = least_loaded_sql(),
+    ))
+    .bind("Running")
+    .bind(now)
+    .bind(runner_id)
+    .bind("Queued")
+    .bind(lease_expires_at)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(id)
+}
+
+/// Extend the lease of a job running on the given runner
+///
+/// # Returns
+/// `false` when the job is not running on that runner
+#[tracing::instrument(name = "job_repository::renew_lease", skip_all)]
+pub async fn renew_lease(
+    pool: &PgPool,
+    job_id: Uuid,
+    runner_id: &str,
+    expires_at: chrono::DateTime<chrono::Utc>,
+) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query(
+        r#"
+        UPDATE jobs
+        SET lease_expires_at = $1
+        WHERE id = $2 AND runner_id = $3 AND status = $4
+        "#,
+    )
+    .bind(expires_at)
+    .bind(job_id)
+    .bind(runner_id)
+    .bind("Running")
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+/// Put running jobs whose lease has expired back in the queue
+///
+/// Each requeue starts a new attempt. Jobs already on their last attempt
+/// (the larger of the job's own `max_attempts` and `max_attempts`) are left
+/// alone, see `dead_letter_expired_leases`.
+///
+/// # Returns
+/// The requeued jobs as `(job_id, pipeline_id)` pairs
+#[tracing::instrument(name = "job_repository::requeue_expired_leases", skip_all)]
+pub async fn requeue_expired_leases(
+    pool: &PgPool,
+    max_attempts: i32,
+) -> Result<Vec<(Uuid, Uuid)>, sqlx::Error> {
+    let rows = sqlx::query_as::<_, (Uuid, Uuid)>(
+        r#"
+        UPDATE jobs
+        SET status = $1, started_at = NULL, runner_id = NULL, lease_expires_at = NULL,
+            attempt = attempt + 1
+        WHERE status = $2 AND lease_expires_at < NOW() AND attempt < GREATEST(max_attempts, $3)
+        RETURNING id, pipeline_id
+        "#,
+    )
+    .bind("Queued")
+    .bind("Running")
+    .bind(max_attempts)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows)
+}
+
+/// Move running jobs whose lease expired on their last attempt to the
+/// dead-letter queue
+///
+/// # Returns
+/// The dead-lettered jobs as `(job_id, pipeline_id)` pairs
+#[tracing::instrument(name = "job_repository::dead_letter_expired_leases", skip_all)]
+pub async fn dead_letter_expired_leases(
+    pool: &PgPool,
+    max_attempts: i32,
+) -> Result<Vec<(Uuid, Uuid)>, sqlx::Error> {
+    let rows = sqlx::query_as::<_, (Uuid, Uuid)>(
+        r#"
+        UPDATE jobs
+        SET status = $1, completed_at = NOW(), lease_expires_at = NULL,
+            dead_letter_reason = 'Lease expired on ' || attempt || ' attempt(s), last on runner '
+                || COALESCE(runner_id, 'unknown')
+        WHERE status = $2 AND lease_expires_at < NOW() AND attempt >= GREATEST(max_attempts, $3)
+        RETURNING id, pipeline_id
+        "#,
+    )
+    .bind("DeadLettered")
+    .bind("Running")
+    .bind(max_attempts)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows)
+}
+
+/// Put a dead-lettered job back in the queue, starting over from attempt 1
+///
+/// # Returns
+/// `false` when the job is not dead-lettered
+#[tracing::instrument(name = "job_repository::requeue_dead_lettered", skip_all)]
+pub async fn requeue_dead_lettered(pool: &PgPool, job_id: Uuid) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query(
+        r#"
+        UPDATE jobs
+        SET status = $1, started_at = NULL, completed_at = NULL, runner_id = NULL,
+            lease_expires_at = NULL, attempt = 1, not_before = NULL, dead_letter_reason = NULL,
+            result_success = NULL, result_exit_code = NULL, result_output = NULL,
+            result_error_message = NULL
+        WHERE id = $2 AND status = $3
+        "#,
+    )
+    .bind("Queued")
+    .bind(job_id)
+    .bind("DeadLettered")
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
 /// Update job status to completed state
+///
+/// For completions on the orchestrator's own authority (e.g. cancelling);
+/// runners go through `complete_claimed`.
+#[tracing::instrument(name = "job_repository::update_status_to_completed", skip_all)]
 pub async fn update_status_to_completed(
     pool: &PgPool,
     job_id: Uuid,
@@ -166,7 +707,120 @@ pub async fn update_status_to_completed(
     Ok(())
 }
 
+/// Complete a job on behalf of the runner holding `claim_token`
+///
+/// Only a running job still holding that claim is completed, checked by the
+/// update itself so racing claimants cannot both get through.
+///
+/// # Returns
+/// Whether the job was updated
+#[tracing::instrument(name = "job_repository::complete_claimed", skip_all)]
+pub async fn complete_claimed(
+    pool: &PgPool,
+    job_id: Uuid,
+    status: JobStatus,
+    claim_token: i64,
+) -> Result<bool, sqlx::Error> {
+    let now = chrono::Utc::now();
+    let status_str = status_to_string(status);
+
+    let result = sqlx::query(
+        r#"
+        UPDATE jobs
+        SET status = $1, completed_at = $2
+        WHERE id = $3 AND claim_token = $4 AND status = $5
+        "#,
+    )
+    .bind(status_str)
+    .bind(now)
+    .bind(job_id)
+    .bind(claim_token)
+    .bind("Running")
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+/// Put a failed job back in the queue for its next attempt
+///
+/// The job is not handed to a runner before `not_before`. It is only
+/// requeued while it is running under `claim_token`, as for
+/// `complete_claimed`.
+///
+/// # Returns
+/// Whether the job was requeued
+#[tracing::instrument(name = "job_repository::requeue_for_retry", skip_all)]
+pub async fn requeue_for_retry(
+    pool: &PgPool,
+    job_id: Uuid,
+    not_before: chrono::DateTime<chrono::Utc>,
+    claim_token: i64,
+) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query(
+        r#"
+        UPDATE jobs
+        SET status = $1, started_at = NULL, completed_at = NULL, runner_id = NULL,
+            lease_expires_at = NULL, attempt = attempt + 1, not_before = $2,
+            result_success = NULL, result_exit_code = NULL, result_output = NULL,
+            result_error_message = NULL
+        WHERE id = $3 AND claim_token = $4 AND status = $5
+        "#,
+    )
+    .bind("Queued")
+    .bind(not_before)
+    .bind(job_id)
+    .bind(claim_token)
+    .bind("Running")
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+/// Take a job just claimed back from its runner, blocking it
+///
+/// For jobs that lost the lock of an environment they deploy to to a job
+/// claimed at the same time.
+#[tracing::instrument(name = "job_repository::unclaim_blocked", skip_all)]
+pub async fn unclaim_blocked(pool: &PgPool, job_id: Uuid) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        r#"
+        UPDATE jobs
+        SET status = $1, started_at = NULL, runner_id = NULL, lease_expires_at = NULL
+        WHERE id = $2
+        "#,
+    )
+    .bind("Blocked")
+    .bind(job_id)
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+/// Move a job to the dead-letter queue
+#[tracing::instrument(name = "job_repository::dead_letter", skip_all)]
+pub async fn dead_letter(pool: &PgPool, job_id: Uuid, reason: &str) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        r#"
+        UPDATE jobs
+        SET status = $1, completed_at = $2, lease_expires_at = NULL, dead_letter_reason = $3
+        WHERE id = $4
+        "#,
+    )
+    .bind("DeadLettered")
+    .bind(chrono::Utc::now())
+    .bind(reason)
+    .bind(job_id)
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
 /// Update job result
+#[tracing::instrument(name = "job_repository::update_result", skip_all)]
 pub async fn update_result(
     pool: &PgPool,
     job_id: Uuid,
@@ -190,7 +844,24 @@ pub async fn update_result(
     Ok(())
 }
 
+/// Add labels to a job, replacing those with the same keys
+#[tracing::instrument(name = "job_repository::add_labels", skip_all)]
+pub async fn add_labels(
+    pool: &PgPool,
+    job_id: Uuid,
+    labels: &BTreeMap<String, String>,
+) -> Result<(), sqlx::Error> {
+    sqlx::query("UPDATE jobs SET labels = labels || $1 WHERE id = $2")
+        .bind(serde_json::to_value(labels).unwrap())
+        .bind(job_id)
+        .execute(pool)
+        .await?;
+
+    Ok(())
+}
+
 /// Delete a job by ID
+#[tracing::instrument(name = "job_repository::delete", skip_all)]
 pub async fn delete(pool: &PgPool, id: Uuid) -> Result<bool, sqlx::Error> {
     let result = sqlx::query("DELETE FROM jobs WHERE id = $1")
         .bind(id)
@@ -212,6 +883,8 @@ fn status_to_string(status: JobStatus) -> &'static str {
         JobStatus::Failed => "Failed",
         JobStatus::Cancelled => "Cancelled",
         JobStatus::TimedOut => "TimedOut",
+        JobStatus::DeadLettered => "DeadLettered",
+        JobStatus::Blocked => "Blocked",
     }
 }
 
@@ -223,10 +896,16 @@ fn string_to_status(s: &str) -> JobStatus {
         "Failed" => JobStatus::Failed,
         "Cancelled" => JobStatus::Cancelled,
         "TimedOut" => JobStatus::TimedOut,
+        "DeadLettered" => JobStatus::DeadLettered,
+        "Blocked" => JobStatus::Blocked,
         _ => JobStatus::Queued,
     }
 }
 
+fn commit_to_json(commit: Option<&CommitRef>) -> Option<serde_json::Value> {
+    commit.map(|c| serde_json::to_value(c).unwrap())
+}
+
 // =============================================================================
 // Database Row Types
 // =============================================================================
@@ -234,6 +913,7 @@ fn string_to_status(s: &str) -> JobStatus {
 #[derive(sqlx::FromRow)]
 struct JobRow {
     id: Uuid,
+    number: i64,
     pipeline_id: Uuid,
     status: String,
     requested_at: chrono::DateTime<chrono::Utc>,
@@ -245,6 +925,12 @@ struct JobRow {
     result_exit_code: Option<i32>,
     result_output: Option<serde_json::Value>,
     result_error_message: Option<String>,
+    attempt: i32,
+    max_attempts: i32,
+    dead_letter_reason: Option<String>,
+    resumed_from: Option<Uuid>,
+    commit_ref: Option<serde_json::Value>,
+    labels: serde_json::Value,
 }
 
 impl From<JobRow> for Job {
@@ -266,6 +952,7 @@ impl From<JobRow> for Job {
 
         Job {
             id: row.id,
+            number: row.number as u64,
             pipeline_id: row.pipeline_id,
             status,
             requested_at: row.requested_at,
@@ -274,6 +961,12 @@ impl From<JobRow> for Job {
             runner_id: row.runner_id,
             parameters,
             result,
+            attempt: row.attempt.max(1) as u32,
+            max_attempts: row.max_attempts.max(1) as u32,
+            dead_letter_reason: row.dead_letter_reason,
+            resumed_from: row.resumed_from,
+            commit: row.commit_ref.and_then(|c| serde_json::from_value(c).ok()),
+            labels: serde_json::from_value(row.labels).unwrap_or_default(),
         }
     }
 }
@@ -2,54 +2,281 @@
 //!
 //! Handles all database operations related to job logs.
 
-use rivet_core::domain::log::{LogEntry, LogLevel};
+use chrono::{DateTime, Utc};
+use rivet_core::domain::log::{LogEntry, LogLevel, OutputStream};
 use sqlx::PgPool;
 use uuid::Uuid;
 
+/// Where the archived logs of a job are, see `log_archive`
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct LogArchiveRecord {
+    pub job_id: Uuid,
+    pub object_key: String,
+    /// Entries in the object
+    pub entries: i64,
+    /// Size of the object
+    pub bytes: i64,
+    pub archived_at: DateTime<Utc>,
+}
+
 /// Add log entries for a job
+///
+/// The whole batch is written by a single statement: entries are bound as
+/// column arrays and expanded with `UNNEST`, keeping one round trip per
+/// batch however many entries it holds. Entries get their sequence numbers
+/// in batch order.
+///
+/// The batch is only written while the job still holds `claim_token`,
+/// checked in the same statement.
+///
+/// # Returns
+/// Whether the entries were written
+#[tracing::instrument(name = "log_repository::add_entries", skip_all)]
 pub async fn add_entries(
     pool: &PgPool,
     job_id: Uuid,
     entries: Vec<LogEntry>,
-) -> Result<(), sqlx::Error> {
+    claim_token: i64,
+) -> Result<bool, sqlx::Error> {
+    let mut timestamps = Vec::with_capacity(entries.len());
+    let mut levels = Vec::with_capacity(entries.len());
+    let mut messages = Vec::with_capacity(entries.len());
+    let mut stages = Vec::with_capacity(entries.len());
+    let mut streams = Vec::with_capacity(entries.len());
+    let mut fields = Vec::with_capacity(entries.len());
     for entry in entries {
-        let level_str = level_to_string(entry.level);
-
-        sqlx::query(
-            r#"
-            INSERT INTO job_logs (job_id, timestamp, level, message)
-            VALUES ($1, $2, $3, $4)
-            "#,
-        )
-        .bind(job_id)
-        .bind(entry.timestamp)
-        .bind(level_str)
-        .bind(&entry.message)
-        .execute(pool)
-        .await?;
+        timestamps.push(entry.timestamp);
+        levels.push(level_to_string(entry.level));
+        messages.push(entry.message);
+        stages.push(entry.stage);
+        streams.push(entry.stream.map(stream_to_string));
+        fields.push(
+            entry
+                .fields
+                .map(|fields| serde_json::to_string(&fields))
+                .transpose()
+                .map_err(|e| sqlx::Error::Protocol(format!("Failed to serialize fields: {}", e)))?,
+        );
     }
 
-    Ok(())
+    let result = sqlx::query(
+        r#"
+        INSERT INTO job_logs (job_id, timestamp, level, message, stage, stream, fields)
+        SELECT $1, t.timestamp, t.level, t.message, t.stage, t.stream, t.fields::jsonb
+        FROM UNNEST($2::timestamptz[], $3::text[], $4::text[], $5::text[], $6::text[], $7::text[])
+            WITH ORDINALITY AS t(timestamp, level, message, stage, stream, fields, n)
+        WHERE EXISTS (SELECT 1 FROM jobs WHERE id = $1 AND claim_token = $8)
+        ORDER BY t.n
+        "#,
+    )
+    .bind(job_id)
+    .bind(timestamps)
+    .bind(levels)
+    .bind(messages)
+    .bind(stages)
+    .bind(streams)
+    .bind(fields)
+    .bind(claim_token)
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected() > 0)
 }
 
 /// Get all log entries for a job
+#[tracing::instrument(name = "log_repository::find_by_job", skip_all)]
 pub async fn find_by_job(pool: &PgPool, job_id: Uuid) -> Result<Vec<LogEntry>, sqlx::Error> {
     let rows = sqlx::query_as::<_, LogRow>(
         r#"
-        SELECT timestamp, level, message
+        SELECT timestamp, level, message, stage, stream, sequence, fields::text AS fields
+        FROM job_logs
+        WHERE job_id = $1
+        ORDER BY sequence ASC
+        "#,
+    )
+    .bind(job_id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows.into_iter().map(|r| r.into()).collect())
+}
+
+/// Get one page of log entries for a job, oldest first
+#[tracing::instrument(name = "log_repository::find_page_by_job", skip_all)]
+pub async fn find_page_by_job(
+    pool: &PgPool,
+    job_id: Uuid,
+    limit: i64,
+    offset: i64,
+) -> Result<Vec<LogEntry>, sqlx::Error> {
+    let rows = sqlx::query_as::<_, LogRow>(
+        r#"
+        SELECT timestamp, level, message, stage, stream, sequence, fields::text AS fields
+        FROM job_logs
+        WHERE job_id = $1
+        ORDER BY sequence ASC
+        LIMIT $2 OFFSET $3
+        "#,
+    )
+    .bind(job_id)
+    .bind(limit)
+    .bind(offset)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows.into_iter().map(|r| r.into()).collect())
+}
+
+/// Get the log entries of a job stored after a sequence number, oldest first
+///
+/// Only entries of `min_level` and above are returned. With `tail`, only the
+/// last `tail` of the matching entries are returned.
+#[tracing::instrument(name = "log_repository::find_after_by_job", skip_all)]
+pub async fn find_after_by_job(
+    pool: &PgPool,
+    job_id: Uuid,
+    after: i64,
+    tail: Option<i64>,
+    min_level: LogLevel,
+) -> Result<Vec<LogEntry>, sqlx::Error> {
+    let rows = sqlx::query_as::<_, LogRow>(
+        r#"
+        SELECT timestamp, level, message, stage, stream, sequence, fields
+        FROM (
+            SELECT timestamp, level, message, stage, stream, sequence, fields::text AS fields
+            FROM job_logs
+            WHERE job_id = $1 AND sequence > $2 AND level = ANY($4)
+            ORDER BY sequence DESC
+            LIMIT $3
+        ) latest
+        ORDER BY sequence ASC
+        "#,
+    )
+    .bind(job_id)
+    .bind(after)
+    .bind(tail)
+    .bind(levels_from(min_level))
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows.into_iter().map(|r| r.into()).collect())
+}
+
+/// Search the log entries of a job, oldest first
+///
+/// `query` is a web search style query (`websearch_to_tsquery`) matched
+/// against the `simple` full-text vector of each message.
+///
+/// # Arguments
+/// * `min_level` - Only entries of this level and above
+/// * `limit` - Maximum number of entries returned
+#[tracing::instrument(name = "log_repository::search_by_job", skip_all)]
+pub async fn search_by_job(
+    pool: &PgPool,
+    job_id: Uuid,
+    query: &str,
+    min_level: LogLevel,
+    limit: i64,
+) -> Result<Vec<LogEntry>, sqlx::Error> {
+    let rows = sqlx::query_as::<_, LogRow>(
+        r#"
+        SELECT timestamp, level, message, stage, stream, sequence, fields::text AS fields
         FROM job_logs
         WHERE job_id = $1
-        ORDER BY timestamp ASC
+          AND to_tsvector('simple', message) @@ websearch_to_tsquery('simple', $2)
+          AND level = ANY($3)
+        ORDER BY sequence ASC
+        LIMIT $4
         "#,
     )
     .bind(job_id)
+    .bind(query)
+    .bind(levels_from(min_level))
+    .bind(limit)
     .fetch_all(pool)
     .await?;
 
     Ok(rows.into_iter().map(|r| r.into()).collect())
 }
 
+/// Search the log entries of all jobs, most recent first
+///
+/// # Arguments
+/// * `query` - Web search style query, see `search_by_job`
+/// * `project_id` - Only jobs of pipelines in this project
+/// * `pipeline_id` - Only jobs of this pipeline
+/// * `min_level` - Only entries of this level and above
+/// * `limit` - Maximum number of entries returned
+#[tracing::instrument(name = "log_repository::search", skip_all)]
+pub async fn search(
+    pool: &PgPool,
+    query: &str,
+    project_id: Option<Uuid>,
+    pipeline_id: Option<Uuid>,
+    min_level: LogLevel,
+    limit: i64,
+) -> Result<Vec<(Uuid, Uuid, LogEntry)>, sqlx::Error> {
+    let rows = sqlx::query_as::<_, LogSearchRow>(
+        r#"
+        SELECT l.job_id, j.pipeline_id,
+               l.timestamp, l.level, l.message, l.stage, l.stream, l.sequence,
+               l.fields::text AS fields
+        FROM job_logs l
+        JOIN jobs j ON j.id = l.job_id
+        JOIN pipelines p ON p.id = j.pipeline_id
+        WHERE to_tsvector('simple', l.message) @@ websearch_to_tsquery('simple', $1)
+          AND l.level = ANY($2)
+          AND ($3::uuid IS NULL OR p.project_id = $3)
+          AND ($4::uuid IS NULL OR j.pipeline_id = $4)
+        ORDER BY l.timestamp DESC, l.sequence DESC
+        LIMIT $5
+        "#,
+    )
+    .bind(query)
+    .bind(levels_from(min_level))
+    .bind(project_id)
+    .bind(pipeline_id)
+    .bind(limit)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows
+        .into_iter()
+        .map(|r| (r.job_id, r.pipeline_id, r.entry.into()))
+        .collect())
+}
+
+/// Match messages against a search query the way `search_by_job` does
+///
+/// Lets the log service search entries that are no longer in the database
+/// (archived logs) with the same semantics.
+///
+/// # Returns
+/// The indexes of the matching messages, in ascending order
+#[tracing::instrument(name = "log_repository::match_messages", skip_all)]
+pub async fn match_messages(
+    pool: &PgPool,
+    query: &str,
+    messages: &[&str],
+) -> Result<Vec<usize>, sqlx::Error> {
+    let matches: Vec<i64> = sqlx::query_scalar(
+        r#"
+        SELECT m.ordinality - 1
+        FROM UNNEST($2::text[]) WITH ORDINALITY AS m(message, ordinality)
+        WHERE to_tsvector('simple', m.message) @@ websearch_to_tsquery('simple', $1)
+        ORDER BY m.ordinality
+        "#,
+    )
+    .bind(query)
+    .bind(messages)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(matches.into_iter().map(|i| i as usize).collect())
+}
+
 /// Delete all logs for a job
+#[tracing::instrument(name = "log_repository::delete_by_job", skip_all)]
 pub async fn delete_by_job(pool: &PgPool, job_id: Uuid) -> Result<u64, sqlx::Error> {
     let result = sqlx::query("DELETE FROM job_logs WHERE job_id = $1")
         .bind(job_id)
@@ -59,7 +286,130 @@ pub async fn delete_by_job(pool: &PgPool, job_id: Uuid) -> Result<u64, sqlx::Err
     Ok(result.rows_affected())
 }
 
+/// Find completed jobs with logs still in the database
+///
+/// # Arguments
+/// * `completed_before` - Only jobs completed before this time, leaving
+///   runners time to upload their last entries
+/// * `limit` - Maximum number of jobs returned, least recently completed first
+#[tracing::instrument(name = "log_repository::find_compactable_jobs", skip_all)]
+pub async fn find_compactable_jobs(
+    pool: &PgPool,
+    completed_before: DateTime<Utc>,
+    limit: i64,
+) -> Result<Vec<Uuid>, sqlx::Error> {
+    sqlx::query_scalar(
+        r#"
+        SELECT j.id
+        FROM jobs j
+        WHERE j.status IN ('Succeeded', 'Failed', 'Cancelled', 'TimedOut', 'DeadLettered')
+          AND COALESCE(j.completed_at, j.requested_at) < $1
+          AND EXISTS (SELECT 1 FROM job_logs l WHERE l.job_id = j.id)
+        ORDER BY COALESCE(j.completed_at, j.requested_at) ASC
+        LIMIT $2
+        "#,
+    )
+    .bind(completed_before)
+    .bind(limit)
+    .fetch_all(pool)
+    .await
+}
+
+/// Get the archive record of a job, if its logs were archived
+#[tracing::instrument(name = "log_repository::find_archive", skip_all)]
+pub async fn find_archive(
+    pool: &PgPool,
+    job_id: Uuid,
+) -> Result<Option<LogArchiveRecord>, sqlx::Error> {
+    sqlx::query_as::<_, LogArchiveRecord>(
+        r#"
+        SELECT job_id, object_key, entries, bytes, archived_at
+        FROM job_log_archives
+        WHERE job_id = $1
+        "#,
+    )
+    .bind(job_id)
+    .fetch_optional(pool)
+    .await
+}
+
+/// Record that the logs of a job up to a sequence number were archived
+///
+/// The archive record is saved and the archived rows deleted in a single
+/// transaction. Entries stored after the object was written stay in the
+/// database.
+///
+/// # Returns
+/// The number of rows deleted
+#[tracing::instrument(name = "log_repository::save_archive", skip_all)]
+pub async fn save_archive(
+    pool: &PgPool,
+    record: &LogArchiveRecord,
+    last_sequence: i64,
+) -> Result<u64, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+
+    sqlx::query(
+        r#"
+        INSERT INTO job_log_archives (job_id, object_key, entries, bytes, archived_at)
+        VALUES ($1, $2, $3, $4, $5)
+        ON CONFLICT (job_id) DO UPDATE
+        SET object_key = EXCLUDED.object_key,
+            entries = EXCLUDED.entries,
+            bytes = EXCLUDED.bytes,
+            archived_at = EXCLUDED.archived_at
+        "#,
+    )
+    .bind(record.job_id)
+    .bind(&record.object_key)
+    .bind(record.entries)
+    .bind(record.bytes)
+    .bind(record.archived_at)
+    .execute(&mut *tx)
+    .await?;
+
+    let deleted = sqlx::query("DELETE FROM job_logs WHERE job_id = $1 AND sequence <= $2")
+        .bind(record.job_id)
+        .bind(last_sequence)
+        .execute(&mut *tx)
+        .await?;
+
+    tx.commit().await?;
+    Ok(deleted.rows_affected())
+}
+
+/// Find archive records of jobs that were deleted since
+#[tracing::instrument(name = "log_repository::find_orphaned_archives", skip_all)]
+pub async fn find_orphaned_archives(
+    pool: &PgPool,
+    limit: i64,
+) -> Result<Vec<LogArchiveRecord>, sqlx::Error> {
+    sqlx::query_as::<_, LogArchiveRecord>(
+        r#"
+        SELECT a.job_id, a.object_key, a.entries, a.bytes, a.archived_at
+        FROM job_log_archives a
+        WHERE NOT EXISTS (SELECT 1 FROM jobs j WHERE j.id = a.job_id)
+        LIMIT $1
+        "#,
+    )
+    .bind(limit)
+    .fetch_all(pool)
+    .await
+}
+
+/// Delete the archive record of a job
+#[tracing::instrument(name = "log_repository::delete_archive", skip_all)]
+pub async fn delete_archive(pool: &PgPool, job_id: Uuid) -> Result<(), sqlx::Error> {
+    sqlx::query("DELETE FROM job_log_archives WHERE job_id = $1")
+        .bind(job_id)
+        .execute(pool)
+        .await?;
+
+    Ok(())
+}
+
 /// Get log count for a job
+#[tracing::instrument(name = "log_repository::count_by_job", skip_all)]
 pub async fn count_by_job(pool: &PgPool, job_id: Uuid) -> Result<i64, sqlx::Error> {
     let row: (i64,) = sqlx::query_as(
         r#"
@@ -77,6 +427,15 @@ pub async fn count_by_job(pool: &PgPool, job_id: Uuid) -> Result<i64, sqlx::Erro
 // Helper Functions
 // =============================================================================
 
+/// Names of `min_level` and the levels above it, as stored
+fn levels_from(min_level: LogLevel) -> Vec<&'static str> {
+    LogLevel::ALL
+        .into_iter()
+        .filter(|level| *level >= min_level)
+        .map(level_to_string)
+        .collect()
+}
+
 fn level_to_string(level: LogLevel) -> &'static str {
     match level {
         LogLevel::Debug => "Debug",
@@ -86,6 +445,21 @@ fn level_to_string(level: LogLevel) -> &'static str {
     }
 }
 
+fn stream_to_string(stream: OutputStream) -> &'static str {
+    match stream {
+        OutputStream::Stdout => "Stdout",
+        OutputStream::Stderr => "Stderr",
+    }
+}
+
+fn string_to_stream(s: &str) -> Option<OutputStream> {
+    match s {
+        "Stdout" => Some(OutputStream::Stdout),
+        "Stderr" => Some(OutputStream::Stderr),
+        _ => None,
+    }
+}
+
 fn string_to_level(s: &str) -> LogLevel {
     match s {
         "Debug" => LogLevel::Debug,
@@ -105,6 +479,10 @@ struct LogRow {
     timestamp: chrono::DateTime<chrono::Utc>,
     level: String,
     message: String,
+    stage: Option<String>,
+    stream: Option<String>,
+    sequence: i64,
+    fields: Option<String>,
 }
 
 impl From<LogRow> for LogEntry {
@@ -115,6 +493,20 @@ impl From<LogRow> for LogEntry {
             timestamp: row.timestamp,
             level,
             message: row.message,
+            stage: row.stage,
+            stream: row.stream.as_deref().and_then(string_to_stream),
+            sequence: Some(row.sequence as u64),
+            fields: row
+                .fields
+                .and_then(|fields| serde_json::from_str(&fields).ok()),
         }
     }
 }
+
+#[derive(sqlx::FromRow)]
+struct LogSearchRow {
+    job_id: Uuid,
+    pipeline_id: Uuid,
+    #[sqlx(flatten)]
+    entry: LogRow,
+}
@@ -2,14 +2,36 @@
 //!
 //! Data access layer for the orchestrator.
 //! Each repository handles database operations for a specific domain entity.
+//! Every operation runs in a span named after it (e.g. `job_repository::create`),
+//! so traces show the time spent in the database.
 
+pub mod artifact;
+pub mod blackout;
+pub mod environment;
 pub mod job;
 pub mod log;
 pub mod pipeline;
+pub mod preset;
+pub mod project;
 pub mod runner;
+pub mod schedule;
+pub mod secret;
+pub mod stage;
+pub mod stats;
+pub mod webhook;
 
 // Re-export for convenience
+pub use artifact as artifact_repository;
+pub use blackout as blackout_repository;
+pub use environment as environment_repository;
 pub use job as job_repository;
 pub use log as log_repository;
 pub use pipeline as pipeline_repository;
+pub use preset as preset_repository;
+pub use project as project_repository;
 pub use runner as runner_repository;
+pub use schedule as schedule_repository;
+pub use secret as secret_repository;
+pub use stage as stage_repository;
+pub use stats as stats_repository;
+pub use webhook as webhook_repository;
@@ -3,13 +3,20 @@
 //! Handles all database operations related to pipelines.
 
 use rivet_core::domain::pipeline::Pipeline;
-use rivet_core::dto::pipeline::CreatePipeline;
-use rivet_lua::{create_sandbox, parse_pipeline_definition};
+use rivet_core::dto::artifact::ArtifactRetention;
+use rivet_core::dto::commit_status::CommitStatusConfig;
+use rivet_core::dto::pipeline::{CreatePipeline, PipelineDefaults};
+use rivet_lua::{PipelineDefinition, create_sandbox, parse_pipeline_definition};
 use sqlx::PgPool;
 use uuid::Uuid;
 
-/// Create a new pipeline in the database
-pub async fn create(pool: &PgPool, req: CreatePipeline) -> Result<Pipeline, sqlx::Error> {
+/// Create a new pipeline in the database, owned by `project_id`
+#[tracing::instrument(name = "pipeline_repository::create", skip_all)]
+pub async fn create(
+    pool: &PgPool,
+    project_id: Uuid,
+    req: CreatePipeline,
+) -> Result<Pipeline, sqlx::Error> {
     let id = Uuid::new_v4();
     let now = chrono::Utc::now();
 
@@ -32,12 +39,16 @@ pub async fn create(pool: &PgPool, req: CreatePipeline) -> Result<Pipeline, sqlx
 
     let pipeline = Pipeline {
         id,
+        project_id,
         name: definition.name.clone(),
         description: definition.description.clone(),
         script: req.script.clone(),
         created_at: now,
         updated_at: now,
         tags: tags.clone(),
+        plugins: definition.plugins.clone(),
+        archived_at: None,
+        disabled: false,
     };
 
     let tags_json = serde_json::to_value(&tags)
@@ -45,17 +56,21 @@ pub async fn create(pool: &PgPool, req: CreatePipeline) -> Result<Pipeline, sqlx
 
     sqlx::query(
         r#"
-        INSERT INTO pipelines (id, name, description, script, created_at, updated_at, tags)
-        VALUES ($1, $2, $3, $4, $5, $6, $7)
+        INSERT INTO pipelines (id, project_id, name, description, script, created_at, updated_at, tags,
+                               plugins, deploy_stages)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
         "#,
     )
     .bind(id)
+    .bind(project_id)
     .bind(&definition.name)
     .bind(&definition.description)
     .bind(&req.script)
     .bind(now)
     .bind(now)
     .bind(tags_json)
+    .bind(serde_json::json!(definition.plugins))
+    .bind(deploy_stages_json(&definition))
     .execute(pool)
     .await?;
 
@@ -63,10 +78,12 @@ pub async fn create(pool: &PgPool, req: CreatePipeline) -> Result<Pipeline, sqlx
 }
 
 /// Find a pipeline by ID
+#[tracing::instrument(name = "pipeline_repository::find_by_id", skip_all)]
 pub async fn find_by_id(pool: &PgPool, id: Uuid) -> Result<Option<Pipeline>, sqlx::Error> {
     let row = sqlx::query_as::<_, PipelineRow>(
         r#"
-        SELECT id, name, description, script, created_at, updated_at, tags::text as tags
+        SELECT id, project_id, name, description, script, created_at, updated_at, tags::text as tags,
+               plugins::text as plugins, archived_at, disabled
         FROM pipelines
         WHERE id = $1
         "#,
@@ -78,15 +95,45 @@ pub async fn find_by_id(pool: &PgPool, id: Uuid) -> Result<Option<Pipeline>, sql
     Ok(row.map(|r| r.into()))
 }
 
-/// List all pipelines
-pub async fn list_all(pool: &PgPool) -> Result<Vec<Pipeline>, sqlx::Error> {
+/// List all pipelines, the archived ones if `archived` and the active ones
+/// otherwise
+#[tracing::instrument(name = "pipeline_repository::list_all", skip_all)]
+pub async fn list_all(pool: &PgPool, archived: bool) -> Result<Vec<Pipeline>, sqlx::Error> {
     let rows = sqlx::query_as::<_, PipelineRow>(
         r#"
-        SELECT id, name, description, script, created_at, updated_at, tags::text as tags
+        SELECT id, project_id, name, description, script, created_at, updated_at, tags::text as tags,
+               plugins::text as plugins, archived_at, disabled
         FROM pipelines
+        WHERE (archived_at IS NOT NULL) = $1
         ORDER BY created_at DESC
         "#,
     )
+    .bind(archived)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows.into_iter().map(|r| r.into()).collect())
+}
+
+/// List the pipelines of a project, the archived ones if `archived` and the
+/// active ones otherwise
+#[tracing::instrument(name = "pipeline_repository::find_by_project", skip_all)]
+pub async fn find_by_project(
+    pool: &PgPool,
+    project_id: Uuid,
+    archived: bool,
+) -> Result<Vec<Pipeline>, sqlx::Error> {
+    let rows = sqlx::query_as::<_, PipelineRow>(
+        r#"
+        SELECT id, project_id, name, description, script, created_at, updated_at, tags::text as tags,
+               plugins::text as plugins, archived_at, disabled
+        FROM pipelines
+        WHERE project_id = $1 AND (archived_at IS NOT NULL) = $2
+        ORDER BY created_at DESC
+        "#,
+    )
+    .bind(project_id)
+    .bind(archived)
     .fetch_all(pool)
     .await?;
 
@@ -94,6 +141,7 @@ pub async fn list_all(pool: &PgPool) -> Result<Vec<Pipeline>, sqlx::Error> {
 }
 
 /// Update a pipeline
+#[tracing::instrument(name = "pipeline_repository::update", skip_all)]
 pub async fn update(pool: &PgPool, id: Uuid, req: CreatePipeline) -> Result<bool, sqlx::Error> {
     let now = chrono::Utc::now();
 
@@ -120,8 +168,9 @@ pub async fn update(pool: &PgPool, id: Uuid, req: CreatePipeline) -> Result<bool
     let result = sqlx::query(
         r#"
         UPDATE pipelines
-        SET name = $1, description = $2, script = $3, updated_at = $4, tags = $5
-        WHERE id = $6
+        SET name = $1, description = $2, script = $3, updated_at = $4, tags = $5, plugins = $6,
+            deploy_stages = $7
+        WHERE id = $8
         "#,
     )
     .bind(&definition.name)
@@ -129,6 +178,8 @@ pub async fn update(pool: &PgPool, id: Uuid, req: CreatePipeline) -> Result<bool
     .bind(&req.script)
     .bind(now)
     .bind(tags_json)
+    .bind(serde_json::json!(definition.plugins))
+    .bind(deploy_stages_json(&definition))
     .bind(id)
     .execute(pool)
     .await?;
@@ -137,6 +188,7 @@ pub async fn update(pool: &PgPool, id: Uuid, req: CreatePipeline) -> Result<bool
 }
 
 /// Delete a pipeline by ID
+#[tracing::instrument(name = "pipeline_repository::delete", skip_all)]
 pub async fn delete(pool: &PgPool, id: Uuid) -> Result<bool, sqlx::Error> {
     let result = sqlx::query("DELETE FROM pipelines WHERE id = $1")
         .bind(id)
@@ -146,6 +198,209 @@ pub async fn delete(pool: &PgPool, id: Uuid) -> Result<bool, sqlx::Error> {
     Ok(result.rows_affected() > 0)
 }
 
+/// Archive a pipeline, keeping when it was first archived
+///
+/// # Returns
+/// Whether the pipeline exists
+#[tracing::instrument(name = "pipeline_repository::archive", skip_all)]
+pub async fn archive(pool: &PgPool, id: Uuid) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query(
+        "UPDATE pipelines SET archived_at = COALESCE(archived_at, NOW()) WHERE id = $1",
+    )
+    .bind(id)
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+/// Make an archived pipeline active again
+///
+/// # Returns
+/// Whether the pipeline exists
+#[tracing::instrument(name = "pipeline_repository::unarchive", skip_all)]
+pub async fn unarchive(pool: &PgPool, id: Uuid) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query("UPDATE pipelines SET archived_at = NULL WHERE id = $1")
+        .bind(id)
+        .execute(pool)
+        .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+/// Pause or resume the launches of a pipeline
+///
+/// # Returns
+/// Whether the pipeline exists
+#[tracing::instrument(name = "pipeline_repository::set_disabled", skip_all)]
+pub async fn set_disabled(pool: &PgPool, id: Uuid, disabled: bool) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query("UPDATE pipelines SET disabled = $1 WHERE id = $2")
+        .bind(disabled)
+        .bind(id)
+        .execute(pool)
+        .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+/// Whether any job references a pipeline
+#[tracing::instrument(name = "pipeline_repository::has_jobs", skip_all)]
+pub async fn has_jobs(pool: &PgPool, id: Uuid) -> Result<bool, sqlx::Error> {
+    let (exists,): (bool,) =
+        sqlx::query_as("SELECT EXISTS (SELECT 1 FROM jobs WHERE pipeline_id = $1)")
+            .bind(id)
+            .fetch_one(pool)
+            .await?;
+
+    Ok(exists)
+}
+
+/// Find the input defaults and overrides of a pipeline
+///
+/// # Returns
+/// `None` if the pipeline does not exist
+#[tracing::instrument(name = "pipeline_repository::find_defaults", skip_all)]
+pub async fn find_defaults(
+    pool: &PgPool,
+    id: Uuid,
+) -> Result<Option<PipelineDefaults>, sqlx::Error> {
+    let row: Option<(String,)> =
+        sqlx::query_as("SELECT input_defaults::text FROM pipelines WHERE id = $1")
+            .bind(id)
+            .fetch_optional(pool)
+            .await?;
+
+    Ok(row.map(|(json,)| serde_json::from_str(&json).unwrap_or_default()))
+}
+
+/// Replace the input defaults and overrides of a pipeline
+///
+/// # Returns
+/// Whether the pipeline exists
+#[tracing::instrument(name = "pipeline_repository::set_defaults", skip_all)]
+pub async fn set_defaults(
+    pool: &PgPool,
+    id: Uuid,
+    defaults: &PipelineDefaults,
+) -> Result<bool, sqlx::Error> {
+    let defaults_json = serde_json::to_value(defaults)
+        .map_err(|e| sqlx::Error::Protocol(format!("Failed to serialize defaults: {}", e)))?;
+
+    let result =
+        sqlx::query("UPDATE pipelines SET input_defaults = $1, updated_at = $2 WHERE id = $3")
+            .bind(defaults_json)
+            .bind(chrono::Utc::now())
+            .bind(id)
+            .execute(pool)
+            .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+/// Find the artifact retention limits of a pipeline
+///
+/// # Returns
+/// `None` if the pipeline does not exist
+#[tracing::instrument(name = "pipeline_repository::find_artifact_retention", skip_all)]
+pub async fn find_artifact_retention(
+    pool: &PgPool,
+    id: Uuid,
+) -> Result<Option<ArtifactRetention>, sqlx::Error> {
+    let row: Option<(String,)> =
+        sqlx::query_as("SELECT artifact_retention::text FROM pipelines WHERE id = $1")
+            .bind(id)
+            .fetch_optional(pool)
+            .await?;
+
+    Ok(row.map(|(json,)| serde_json::from_str(&json).unwrap_or_default()))
+}
+
+/// Replace the artifact retention limits of a pipeline
+///
+/// # Returns
+/// Whether the pipeline exists
+#[tracing::instrument(name = "pipeline_repository::set_artifact_retention", skip_all)]
+pub async fn set_artifact_retention(
+    pool: &PgPool,
+    id: Uuid,
+    retention: &ArtifactRetention,
+) -> Result<bool, sqlx::Error> {
+    let retention_json = serde_json::to_value(retention)
+        .map_err(|e| sqlx::Error::Protocol(format!("Failed to serialize retention: {}", e)))?;
+
+    let result =
+        sqlx::query("UPDATE pipelines SET artifact_retention = $1, updated_at = $2 WHERE id = $3")
+            .bind(retention_json)
+            .bind(chrono::Utc::now())
+            .bind(id)
+            .execute(pool)
+            .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+/// Find the commit status reporting of a pipeline
+///
+/// # Returns
+/// `None` if the pipeline does not exist, `Some(None)` if it reports no
+/// commit status
+#[tracing::instrument(name = "pipeline_repository::find_commit_status", skip_all)]
+pub async fn find_commit_status(
+    pool: &PgPool,
+    id: Uuid,
+) -> Result<Option<Option<CommitStatusConfig>>, sqlx::Error> {
+    let row: Option<(Option<String>,)> =
+        sqlx::query_as("SELECT commit_status::text FROM pipelines WHERE id = $1")
+            .bind(id)
+            .fetch_optional(pool)
+            .await?;
+
+    Ok(row.map(|(json,)| json.and_then(|json| serde_json::from_str(&json).ok())))
+}
+
+/// Replace the commit status reporting of a pipeline, `None` to stop reporting
+///
+/// # Returns
+/// Whether the pipeline exists
+#[tracing::instrument(name = "pipeline_repository::set_commit_status", skip_all)]
+pub async fn set_commit_status(
+    pool: &PgPool,
+    id: Uuid,
+    config: Option<&CommitStatusConfig>,
+) -> Result<bool, sqlx::Error> {
+    let config_json = config
+        .map(serde_json::to_value)
+        .transpose()
+        .map_err(|e| sqlx::Error::Protocol(format!("Failed to serialize config: {}", e)))?;
+
+    let result =
+        sqlx::query("UPDATE pipelines SET commit_status = $1, updated_at = $2 WHERE id = $3")
+            .bind(config_json)
+            .bind(chrono::Utc::now())
+            .bind(id)
+            .execute(pool)
+            .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+// =============================================================================
+// Helper Functions
+// =============================================================================
+
+/// Environment each deploying stage of a pipeline deploys to, by stage name
+fn deploy_stages_json(definition: &PipelineDefinition) -> serde_json::Value {
+    definition
+        .stages
+        .iter()
+        .filter_map(|stage| {
+            let environment = stage.deploys_to.clone()?;
+            Some((stage.name.clone(), environment.into()))
+        })
+        .collect::<serde_json::Map<_, _>>()
+        .into()
+}
+
 // =============================================================================
 // Database Row Types
 // =============================================================================
@@ -153,27 +408,36 @@ pub async fn delete(pool: &PgPool, id: Uuid) -> Result<bool, sqlx::Error> {
 #[derive(sqlx::FromRow)]
 struct PipelineRow {
     id: Uuid,
+    project_id: Uuid,
     name: String,
     description: Option<String>,
     script: String,
     created_at: chrono::DateTime<chrono::Utc>,
     updated_at: chrono::DateTime<chrono::Utc>,
     tags: String,
+    plugins: String,
+    archived_at: Option<chrono::DateTime<chrono::Utc>>,
+    disabled: bool,
 }
 
 impl From<PipelineRow> for Pipeline {
     fn from(row: PipelineRow) -> Self {
         let tags: Vec<rivet_core::domain::pipeline::Tag> =
             serde_json::from_str(&row.tags).unwrap_or_else(|_| vec![]);
+        let plugins: Vec<String> = serde_json::from_str(&row.plugins).unwrap_or_else(|_| vec![]);
 
         Pipeline {
             id: row.id,
+            project_id: row.project_id,
             name: row.name,
             description: row.description,
             script: row.script,
             created_at: row.created_at,
             updated_at: row.updated_at,
             tags,
+            plugins,
+            archived_at: row.archived_at,
+            disabled: row.disabled,
         }
     }
 }
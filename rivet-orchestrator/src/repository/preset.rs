@@ -0,0 +1,121 @@
+//! Preset Repository
+//!
+//! Handles all database operations related to the parameter presets of
+//! pipelines.
+
+use rivet_core::domain::preset::ParameterPreset;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+/// Save a preset of a pipeline, replacing the parameters of one with the
+/// same name
+///
+/// # Returns
+/// The preset as stored; it keeps its creation time when replaced
+#[tracing::instrument(name = "preset_repository::upsert", skip_all)]
+pub async fn upsert(
+    pool: &PgPool,
+    preset: &ParameterPreset,
+) -> Result<ParameterPreset, sqlx::Error> {
+    let parameters_json = serde_json::to_value(&preset.parameters)
+        .map_err(|e| sqlx::Error::Protocol(format!("Failed to serialize parameters: {}", e)))?;
+
+    let row = sqlx::query_as::<_, PresetRow>(
+        r#"
+        INSERT INTO pipeline_presets (pipeline_id, name, parameters, created_at, updated_at)
+        VALUES ($1, $2, $3, $4, $5)
+        ON CONFLICT (pipeline_id, name)
+        DO UPDATE SET parameters = EXCLUDED.parameters, updated_at = EXCLUDED.updated_at
+        RETURNING pipeline_id, name, parameters, created_at, updated_at
+        "#,
+    )
+    .bind(preset.pipeline_id)
+    .bind(&preset.name)
+    .bind(parameters_json)
+    .bind(preset.created_at)
+    .bind(preset.updated_at)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(row.into())
+}
+
+/// Find a preset of a pipeline by name
+#[tracing::instrument(name = "preset_repository::find", skip_all)]
+pub async fn find(
+    pool: &PgPool,
+    pipeline_id: Uuid,
+    name: &str,
+) -> Result<Option<ParameterPreset>, sqlx::Error> {
+    let row = sqlx::query_as::<_, PresetRow>(
+        r#"
+        SELECT pipeline_id, name, parameters, created_at, updated_at
+        FROM pipeline_presets
+        WHERE pipeline_id = $1 AND name = $2
+        "#,
+    )
+    .bind(pipeline_id)
+    .bind(name)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(row.map(|r| r.into()))
+}
+
+/// List the presets of a pipeline by name
+#[tracing::instrument(name = "preset_repository::list_by_pipeline", skip_all)]
+pub async fn list_by_pipeline(
+    pool: &PgPool,
+    pipeline_id: Uuid,
+) -> Result<Vec<ParameterPreset>, sqlx::Error> {
+    let rows = sqlx::query_as::<_, PresetRow>(
+        r#"
+        SELECT pipeline_id, name, parameters, created_at, updated_at
+        FROM pipeline_presets
+        WHERE pipeline_id = $1
+        ORDER BY name ASC
+        "#,
+    )
+    .bind(pipeline_id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows.into_iter().map(|r| r.into()).collect())
+}
+
+/// Delete a preset of a pipeline
+#[tracing::instrument(name = "preset_repository::delete", skip_all)]
+pub async fn delete(pool: &PgPool, pipeline_id: Uuid, name: &str) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query("DELETE FROM pipeline_presets WHERE pipeline_id = $1 AND name = $2")
+        .bind(pipeline_id)
+        .bind(name)
+        .execute(pool)
+        .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+// =============================================================================
+// Database Row Types
+// =============================================================================
+
+#[derive(sqlx::FromRow)]
+struct PresetRow {
+    pipeline_id: Uuid,
+    name: String,
+    parameters: serde_json::Value,
+    created_at: chrono::DateTime<chrono::Utc>,
+    updated_at: chrono::DateTime<chrono::Utc>,
+}
+
+impl From<PresetRow> for ParameterPreset {
+    fn from(row: PresetRow) -> Self {
+        ParameterPreset {
+            pipeline_id: row.pipeline_id,
+            name: row.name,
+            parameters: serde_json::from_value(row.parameters).unwrap_or_default(),
+            created_at: row.created_at,
+            updated_at: row.updated_at,
+        }
+    }
+}
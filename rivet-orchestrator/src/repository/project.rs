@@ -0,0 +1,100 @@
+//! Project Repository
+//!
+//! Handles all database operations related to projects.
+
+use rivet_core::domain::project::Project;
+use rivet_core::dto::project::CreateProject;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+/// Create a new project in the database
+#[tracing::instrument(name = "project_repository::create", skip_all)]
+pub async fn create(pool: &PgPool, req: CreateProject) -> Result<Project, sqlx::Error> {
+    let row = sqlx::query_as::<_, ProjectRow>(
+        r#"
+        INSERT INTO projects (id, name, description, created_at)
+        VALUES ($1, $2, $3, $4)
+        RETURNING id, name, description, created_at
+        "#,
+    )
+    .bind(Uuid::new_v4())
+    .bind(&req.name)
+    .bind(&req.description)
+    .bind(chrono::Utc::now())
+    .fetch_one(pool)
+    .await?;
+
+    Ok(row.into())
+}
+
+/// Find a project by ID
+#[tracing::instrument(name = "project_repository::find_by_id", skip_all)]
+pub async fn find_by_id(pool: &PgPool, id: Uuid) -> Result<Option<Project>, sqlx::Error> {
+    let row = sqlx::query_as::<_, ProjectRow>(
+        "SELECT id, name, description, created_at FROM projects WHERE id = $1",
+    )
+    .bind(id)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(row.map(|r| r.into()))
+}
+
+/// Find a project by name
+#[tracing::instrument(name = "project_repository::find_by_name", skip_all)]
+pub async fn find_by_name(pool: &PgPool, name: &str) -> Result<Option<Project>, sqlx::Error> {
+    let row = sqlx::query_as::<_, ProjectRow>(
+        "SELECT id, name, description, created_at FROM projects WHERE name = $1",
+    )
+    .bind(name)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(row.map(|r| r.into()))
+}
+
+/// List all projects
+#[tracing::instrument(name = "project_repository::list_all", skip_all)]
+pub async fn list_all(pool: &PgPool) -> Result<Vec<Project>, sqlx::Error> {
+    let rows = sqlx::query_as::<_, ProjectRow>(
+        "SELECT id, name, description, created_at FROM projects ORDER BY name ASC",
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows.into_iter().map(|r| r.into()).collect())
+}
+
+/// Delete a project by ID (its pipelines, jobs and secrets cascade)
+#[tracing::instrument(name = "project_repository::delete", skip_all)]
+pub async fn delete(pool: &PgPool, id: Uuid) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query("DELETE FROM projects WHERE id = $1")
+        .bind(id)
+        .execute(pool)
+        .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+// =============================================================================
+// Database Row Types
+// =============================================================================
+
+#[derive(sqlx::FromRow)]
+struct ProjectRow {
+    id: Uuid,
+    name: String,
+    description: Option<String>,
+    created_at: chrono::DateTime<chrono::Utc>,
+}
+
+impl From<ProjectRow> for Project {
+    fn from(row: ProjectRow) -> Self {
+        Project {
+            id: row.id,
+            name: row.name,
+            description: row.description,
+            created_at: row.created_at,
+        }
+    }
+}
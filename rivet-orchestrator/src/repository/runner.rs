@@ -2,12 +2,23 @@
 //!
 //! Handles all database operations related to runners.
 
-use rivet_core::domain::runner::{Runner, RunnerStatus};
+use rivet_core::domain::pipeline::Tag;
+use rivet_core::domain::runner::{RegistrationToken, Runner, RunnerLoad, RunnerStatus};
 use rivet_core::dto::runner::RegisterRunner;
 use sqlx::PgPool;
+use uuid::Uuid;
 
 /// Create or update a runner registration in the database
-pub async fn register(pool: &PgPool, req: RegisterRunner) -> Result<Runner, sqlx::Error> {
+///
+/// The runner's credential is replaced by `credential_hash`, and its tags,
+/// plugins and info by those in `req`. Its load is cleared until the next
+/// heartbeat.
+#[tracing::instrument(name = "runner_repository::register", skip_all)]
+pub async fn register(
+    pool: &PgPool,
+    req: RegisterRunner,
+    credential_hash: &str,
+) -> Result<Runner, sqlx::Error> {
     let now = chrono::Utc::now();
 
     let runner = Runner {
@@ -15,21 +26,44 @@ pub async fn register(pool: &PgPool, req: RegisterRunner) -> Result<Runner, sqlx
         registered_at: now,
         last_heartbeat_at: now,
         status: RunnerStatus::Online,
+        tags: req.tags.clone(),
+        plugins: req.plugins.clone(),
+        load: None,
+        info: req.info.clone(),
     };
 
+    let tags_json = serde_json::to_value(&req.tags)
+        .map_err(|e| sqlx::Error::Protocol(format!("Failed to serialize tags: {}", e)))?;
+    let info_json = req
+        .info
+        .as_ref()
+        .map(serde_json::to_value)
+        .transpose()
+        .map_err(|e| sqlx::Error::Protocol(format!("Failed to serialize info: {}", e)))?;
+
     sqlx::query(
         r#"
-        INSERT INTO runners (id, registered_at, last_heartbeat_at, status)
-        VALUES ($1, $2, $3, $4)
+        INSERT INTO runners (id, registered_at, last_heartbeat_at, status, credential_hash, tags,
+                             plugins, info)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
         ON CONFLICT (id) DO UPDATE SET
             last_heartbeat_at = EXCLUDED.last_heartbeat_at,
-            status = EXCLUDED.status
+            status = EXCLUDED.status,
+            credential_hash = EXCLUDED.credential_hash,
+            tags = EXCLUDED.tags,
+            plugins = EXCLUDED.plugins,
+            info = EXCLUDED.info,
+            load = NULL
         "#,
     )
     .bind(&req.runner_id)
     .bind(now)
     .bind(now)
     .bind("Online")
+    .bind(credential_hash)
+    .bind(tags_json)
+    .bind(serde_json::json!(req.plugins))
+    .bind(info_json)
     .execute(pool)
     .await?;
 
@@ -37,30 +71,59 @@ pub async fn register(pool: &PgPool, req: RegisterRunner) -> Result<Runner, sqlx
 }
 
 /// Update the last heartbeat time for a runner
-pub async fn update_heartbeat(pool: &PgPool, runner_id: &str) -> Result<bool, sqlx::Error> {
+///
+/// The stored load is replaced by `load`, or kept when the runner did not
+/// report one.
+#[tracing::instrument(name = "runner_repository::update_heartbeat", skip_all)]
+pub async fn update_heartbeat(
+    pool: &PgPool,
+    runner_id: &str,
+    load: Option<&RunnerLoad>,
+) -> Result<bool, sqlx::Error> {
     let now = chrono::Utc::now();
 
+    let load_json = load
+        .map(serde_json::to_value)
+        .transpose()
+        .map_err(|e| sqlx::Error::Protocol(format!("Failed to serialize load: {}", e)))?;
+
     let result = sqlx::query(
         r#"
         UPDATE runners
-        SET last_heartbeat_at = $1, status = $2
+        SET last_heartbeat_at = $1, status = $2, load = COALESCE($4, load)
         WHERE id = $3
         "#,
     )
     .bind(now)
     .bind("Online")
     .bind(runner_id)
+    .bind(load_json)
     .execute(pool)
     .await?;
 
     Ok(result.rows_affected() > 0)
 }
 
+/// Find the ID of the runner holding a credential
+#[tracing::instrument(name = "runner_repository::find_id_by_credential_hash", skip_all)]
+pub async fn find_id_by_credential_hash(
+    pool: &PgPool,
+    credential_hash: &str,
+) -> Result<Option<String>, sqlx::Error> {
+    sqlx::query_scalar::<_, String>("SELECT id FROM runners WHERE credential_hash = $1")
+        .bind(credential_hash)
+        .fetch_optional(pool)
+        .await
+}
+
 /// Find a runner by ID
+#[tracing::instrument(name = "runner_repository::find_by_id", skip_all)]
 pub async fn find_by_id(pool: &PgPool, id: &str) -> Result<Option<Runner>, sqlx::Error> {
     let row = sqlx::query_as::<_, RunnerRow>(
         r#"
-        SELECT id, registered_at, last_heartbeat_at, status
+        SELECT id, registered_at, last_heartbeat_at, status, tags::text as tags,
+               plugins::text as plugins, load::text as load,
+               info::text as info
         FROM runners
         WHERE id = $1
         "#,
@@ -73,10 +136,13 @@ pub async fn find_by_id(pool: &PgPool, id: &str) -> Result<Option<Runner>, sqlx:
 }
 
 /// List all runners
+#[tracing::instrument(name = "runner_repository::list_all", skip_all)]
 pub async fn list_all(pool: &PgPool) -> Result<Vec<Runner>, sqlx::Error> {
     let rows = sqlx::query_as::<_, RunnerRow>(
         r#"
-        SELECT id, registered_at, last_heartbeat_at, status
+        SELECT id, registered_at, last_heartbeat_at, status, tags::text as tags,
+               plugins::text as plugins, load::text as load,
+               info::text as info
         FROM runners
         ORDER BY registered_at DESC
         "#,
@@ -87,7 +153,29 @@ pub async fn list_all(pool: &PgPool) -> Result<Vec<Runner>, sqlx::Error> {
     Ok(rows.into_iter().map(|r| r.into()).collect())
 }
 
+/// Check whether any registered runner provides the given tags and plugins
+///
+/// Offline runners count: they may come back and pick the job up.
+#[tracing::instrument(name = "runner_repository::exists_providing", skip_all)]
+pub async fn exists_providing(
+    pool: &PgPool,
+    tags: &[Tag],
+    plugins: &[String],
+) -> Result<bool, sqlx::Error> {
+    let tags_json = serde_json::to_value(tags)
+        .map_err(|e| sqlx::Error::Protocol(format!("Failed to serialize tags: {}", e)))?;
+
+    sqlx::query_scalar::<_, bool>(
+        "SELECT EXISTS (SELECT 1 FROM runners WHERE tags @> $1 AND plugins @> $2)",
+    )
+    .bind(tags_json)
+    .bind(serde_json::json!(plugins))
+    .fetch_one(pool)
+    .await
+}
+
 /// Delete a runner by ID
+#[tracing::instrument(name = "runner_repository::delete", skip_all)]
 pub async fn delete(pool: &PgPool, id: &str) -> Result<bool, sqlx::Error> {
     let result = sqlx::query("DELETE FROM runners WHERE id = $1")
         .bind(id)
@@ -98,27 +186,105 @@ pub async fn delete(pool: &PgPool, id: &str) -> Result<bool, sqlx::Error> {
 }
 
 /// Mark runners as offline if they haven't sent a heartbeat recently
-/// Returns the number of runners marked as offline
+/// Returns the IDs of the runners marked as offline
+#[tracing::instrument(name = "runner_repository::mark_stale_runners_offline", skip_all)]
 pub async fn mark_stale_runners_offline(
     pool: &PgPool,
     timeout_seconds: i64,
-) -> Result<u64, sqlx::Error> {
+) -> Result<Vec<String>, sqlx::Error> {
     let cutoff_time = chrono::Utc::now() - chrono::Duration::seconds(timeout_seconds);
 
-    let result = sqlx::query(
+    let runner_ids = sqlx::query_scalar::<_, String>(
         r#"
         UPDATE runners
         SET status = $1
         WHERE last_heartbeat_at < $2 AND status != $3
+        RETURNING id
         "#,
     )
     .bind("Offline")
     .bind(cutoff_time)
     .bind("Offline")
+    .fetch_all(pool)
+    .await?;
+
+    Ok(runner_ids)
+}
+
+// =============================================================================
+// Registration Tokens
+// =============================================================================
+
+/// Store a new registration token
+#[tracing::instrument(name = "runner_repository::create_registration_token", skip_all)]
+pub async fn create_registration_token(
+    pool: &PgPool,
+    description: Option<String>,
+    token_hash: &str,
+) -> Result<RegistrationToken, sqlx::Error> {
+    let token = RegistrationToken {
+        id: Uuid::new_v4(),
+        description,
+        created_at: chrono::Utc::now(),
+    };
+
+    sqlx::query(
+        r#"
+        INSERT INTO runner_registration_tokens (id, description, token_hash, created_at)
+        VALUES ($1, $2, $3, $4)
+        "#,
+    )
+    .bind(token.id)
+    .bind(&token.description)
+    .bind(token_hash)
+    .bind(token.created_at)
     .execute(pool)
     .await?;
 
-    Ok(result.rows_affected())
+    Ok(token)
+}
+
+/// Check whether a registration token exists
+#[tracing::instrument(name = "runner_repository::registration_token_exists", skip_all)]
+pub async fn registration_token_exists(
+    pool: &PgPool,
+    token_hash: &str,
+) -> Result<bool, sqlx::Error> {
+    sqlx::query_scalar::<_, bool>(
+        "SELECT EXISTS (SELECT 1 FROM runner_registration_tokens WHERE token_hash = $1)",
+    )
+    .bind(token_hash)
+    .fetch_one(pool)
+    .await
+}
+
+/// List all registration tokens
+#[tracing::instrument(name = "runner_repository::list_registration_tokens", skip_all)]
+pub async fn list_registration_tokens(
+    pool: &PgPool,
+) -> Result<Vec<RegistrationToken>, sqlx::Error> {
+    let rows = sqlx::query_as::<_, RegistrationTokenRow>(
+        r#"
+        SELECT id, description, created_at
+        FROM runner_registration_tokens
+        ORDER BY created_at DESC
+        "#,
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows.into_iter().map(|r| r.into()).collect())
+}
+
+/// Delete a registration token by ID
+#[tracing::instrument(name = "runner_repository::delete_registration_token", skip_all)]
+pub async fn delete_registration_token(pool: &PgPool, id: Uuid) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query("DELETE FROM runner_registration_tokens WHERE id = $1")
+        .bind(id)
+        .execute(pool)
+        .await?;
+
+    Ok(result.rows_affected() > 0)
 }
 
 // =============================================================================
@@ -131,6 +297,10 @@ struct RunnerRow {
     registered_at: chrono::DateTime<chrono::Utc>,
     last_heartbeat_at: chrono::DateTime<chrono::Utc>,
     status: String,
+    tags: String,
+    plugins: String,
+    load: Option<String>,
+    info: Option<String>,
 }
 
 impl From<RunnerRow> for Runner {
@@ -147,6 +317,27 @@ impl From<RunnerRow> for Runner {
             registered_at: row.registered_at,
             last_heartbeat_at: row.last_heartbeat_at,
             status,
+            tags: serde_json::from_str(&row.tags).unwrap_or_default(),
+            plugins: serde_json::from_str(&row.plugins).unwrap_or_default(),
+            load: row.load.and_then(|load| serde_json::from_str(&load).ok()),
+            info: row.info.and_then(|info| serde_json::from_str(&info).ok()),
+        }
+    }
+}
+
+#[derive(sqlx::FromRow)]
+struct RegistrationTokenRow {
+    id: Uuid,
+    description: Option<String>,
+    created_at: chrono::DateTime<chrono::Utc>,
+}
+
+impl From<RegistrationTokenRow> for RegistrationToken {
+    fn from(row: RegistrationTokenRow) -> Self {
+        RegistrationToken {
+            id: row.id,
+            description: row.description,
+            created_at: row.created_at,
         }
     }
 }
@@ -0,0 +1,156 @@
+//! Schedule Repository
+//!
+//! Handles all database operations related to pipeline schedules.
+
+use rivet_core::domain::schedule::Schedule;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+/// Add a schedule to a pipeline
+#[tracing::instrument(name = "schedule_repository::create", skip_all)]
+pub async fn create(pool: &PgPool, schedule: &Schedule) -> Result<(), sqlx::Error> {
+    let parameters_json = serde_json::to_value(&schedule.parameters)
+        .map_err(|e| sqlx::Error::Protocol(format!("Failed to serialize parameters: {}", e)))?;
+
+    sqlx::query(
+        r#"
+        INSERT INTO pipeline_schedules (id, pipeline_id, cron, timezone, catch_up, parameters, next_run_at, last_run_at, created_at)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+        "#,
+    )
+    .bind(schedule.id)
+    .bind(schedule.pipeline_id)
+    .bind(&schedule.cron)
+    .bind(&schedule.timezone)
+    .bind(schedule.catch_up.as_str())
+    .bind(parameters_json)
+    .bind(schedule.next_run_at)
+    .bind(schedule.last_run_at)
+    .bind(schedule.created_at)
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+/// List the schedules of a pipeline, oldest first
+#[tracing::instrument(name = "schedule_repository::list_by_pipeline", skip_all)]
+pub async fn list_by_pipeline(
+    pool: &PgPool,
+    pipeline_id: Uuid,
+) -> Result<Vec<Schedule>, sqlx::Error> {
+    let rows = sqlx::query_as::<_, ScheduleRow>(
+        r#"
+        SELECT id, pipeline_id, cron, timezone, catch_up, parameters, next_run_at, last_run_at, created_at
+        FROM pipeline_schedules
+        WHERE pipeline_id = $1
+        ORDER BY created_at ASC
+        "#,
+    )
+    .bind(pipeline_id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows.into_iter().map(|r| r.into()).collect())
+}
+
+/// Schedules whose next run is due, most overdue first
+///
+/// Schedules of archived pipelines are paused.
+#[tracing::instrument(name = "schedule_repository::find_due", skip_all)]
+pub async fn find_due(pool: &PgPool) -> Result<Vec<Schedule>, sqlx::Error> {
+    let rows = sqlx::query_as::<_, ScheduleRow>(
+        r#"
+        SELECT id, pipeline_id, cron, timezone, catch_up, parameters, next_run_at, last_run_at, created_at
+        FROM pipeline_schedules
+        WHERE next_run_at <= NOW()
+          AND pipeline_id IN (SELECT id FROM pipelines WHERE archived_at IS NULL)
+        ORDER BY next_run_at ASC
+        "#,
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows.into_iter().map(|r| r.into()).collect())
+}
+
+/// Move a schedule's next run forward, unless it moved since it was read
+///
+/// # Arguments
+/// * `due_at` - `next_run_at` of the schedule as read
+/// * `next_run_at` - New next run
+/// * `ran_at` - Time the due runs were launched at, if any
+///
+/// # Returns
+/// Whether the schedule was advanced; `false` when another caller already
+/// handled the due runs
+#[tracing::instrument(name = "schedule_repository::advance", skip_all)]
+pub async fn advance(
+    pool: &PgPool,
+    id: Uuid,
+    due_at: chrono::DateTime<chrono::Utc>,
+    next_run_at: chrono::DateTime<chrono::Utc>,
+    ran_at: Option<chrono::DateTime<chrono::Utc>>,
+) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query(
+        r#"
+        UPDATE pipeline_schedules
+        SET next_run_at = $3, last_run_at = COALESCE($4, last_run_at)
+        WHERE id = $1 AND next_run_at = $2
+        "#,
+    )
+    .bind(id)
+    .bind(due_at)
+    .bind(next_run_at)
+    .bind(ran_at)
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+/// Delete a schedule of a pipeline
+#[tracing::instrument(name = "schedule_repository::delete", skip_all)]
+pub async fn delete(pool: &PgPool, pipeline_id: Uuid, id: Uuid) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query("DELETE FROM pipeline_schedules WHERE id = $1 AND pipeline_id = $2")
+        .bind(id)
+        .bind(pipeline_id)
+        .execute(pool)
+        .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+// =============================================================================
+// Database Row Types
+// =============================================================================
+
+#[derive(sqlx::FromRow)]
+struct ScheduleRow {
+    id: Uuid,
+    pipeline_id: Uuid,
+    cron: String,
+    timezone: String,
+    catch_up: String,
+    parameters: serde_json::Value,
+    next_run_at: chrono::DateTime<chrono::Utc>,
+    last_run_at: Option<chrono::DateTime<chrono::Utc>>,
+    created_at: chrono::DateTime<chrono::Utc>,
+}
+
+impl From<ScheduleRow> for Schedule {
+    fn from(row: ScheduleRow) -> Self {
+        Schedule {
+            id: row.id,
+            pipeline_id: row.pipeline_id,
+            cron: row.cron,
+            timezone: row.timezone,
+            // Only ever written from a `CatchUpPolicy`
+            catch_up: row.catch_up.parse().unwrap_or_default(),
+            parameters: serde_json::from_value(row.parameters).unwrap_or_default(),
+            next_run_at: row.next_run_at,
+            last_run_at: row.last_run_at,
+            created_at: row.created_at,
+        }
+    }
+}
@@ -0,0 +1,139 @@
+//! Secret Repository
+//!
+//! Handles all database operations related to secrets.
+
+use rivet_core::domain::secret::Secret;
+use sqlx::PgPool;
+use std::collections::HashMap;
+use uuid::Uuid;
+
+/// Create or replace a secret in the given scope
+#[tracing::instrument(name = "secret_repository::upsert", skip_all)]
+pub async fn upsert(
+    pool: &PgPool,
+    project_id: Uuid,
+    name: &str,
+    value: &str,
+    pipeline_id: Option<Uuid>,
+) -> Result<Secret, sqlx::Error> {
+    let now = chrono::Utc::now();
+
+    let row = sqlx::query_as::<_, SecretRow>(
+        r#"
+        INSERT INTO secrets (id, project_id, name, pipeline_id, value, created_at, updated_at)
+        VALUES ($1, $2, $3, $4, $5, $6, $6)
+        ON CONFLICT (project_id, name, COALESCE(pipeline_id, '00000000-0000-0000-0000-000000000000'::uuid))
+        DO UPDATE SET value = EXCLUDED.value, updated_at = EXCLUDED.updated_at
+        RETURNING id, project_id, name, pipeline_id, created_at, updated_at
+        "#,
+    )
+    .bind(Uuid::new_v4())
+    .bind(project_id)
+    .bind(name)
+    .bind(pipeline_id)
+    .bind(value)
+    .bind(now)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(row.into())
+}
+
+/// List secrets in a scope (metadata only)
+#[tracing::instrument(name = "secret_repository::list_by_scope", skip_all)]
+pub async fn list_by_scope(
+    pool: &PgPool,
+    project_id: Uuid,
+    pipeline_id: Option<Uuid>,
+) -> Result<Vec<Secret>, sqlx::Error> {
+    let rows = sqlx::query_as::<_, SecretRow>(
+        r#"
+        SELECT id, project_id, name, pipeline_id, created_at, updated_at
+        FROM secrets
+        WHERE project_id = $1 AND pipeline_id IS NOT DISTINCT FROM $2
+        ORDER BY name ASC
+        "#,
+    )
+    .bind(project_id)
+    .bind(pipeline_id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows.into_iter().map(|r| r.into()).collect())
+}
+
+/// Resolve the secret values visible to a pipeline's jobs
+///
+/// Pipeline-scoped secrets shadow project-wide secrets of the same name.
+#[tracing::instrument(name = "secret_repository::find_values_for_pipeline", skip_all)]
+pub async fn find_values_for_pipeline(
+    pool: &PgPool,
+    project_id: Uuid,
+    pipeline_id: Uuid,
+) -> Result<HashMap<String, String>, sqlx::Error> {
+    let rows = sqlx::query_as::<_, (String, String)>(
+        r#"
+        SELECT name, value
+        FROM secrets
+        WHERE project_id = $1 AND (pipeline_id IS NULL OR pipeline_id = $2)
+        ORDER BY pipeline_id NULLS FIRST
+        "#,
+    )
+    .bind(project_id)
+    .bind(pipeline_id)
+    .fetch_all(pool)
+    .await?;
+
+    // Later rows (pipeline-scoped) overwrite earlier ones (project-wide)
+    Ok(rows.into_iter().collect())
+}
+
+/// Delete a secret from a scope
+#[tracing::instrument(name = "secret_repository::delete", skip_all)]
+pub async fn delete(
+    pool: &PgPool,
+    project_id: Uuid,
+    name: &str,
+    pipeline_id: Option<Uuid>,
+) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query(
+        r#"
+        DELETE FROM secrets
+        WHERE project_id = $1 AND name = $2 AND pipeline_id IS NOT DISTINCT FROM $3
+        "#,
+    )
+    .bind(project_id)
+    .bind(name)
+    .bind(pipeline_id)
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+// =============================================================================
+// Database Row Types
+// =============================================================================
+
+#[derive(sqlx::FromRow)]
+struct SecretRow {
+    id: Uuid,
+    project_id: Uuid,
+    name: String,
+    pipeline_id: Option<Uuid>,
+    created_at: chrono::DateTime<chrono::Utc>,
+    updated_at: chrono::DateTime<chrono::Utc>,
+}
+
+impl From<SecretRow> for Secret {
+    fn from(row: SecretRow) -> Self {
+        Secret {
+            id: row.id,
+            name: row.name,
+            project_id: row.project_id,
+            pipeline_id: row.pipeline_id,
+            created_at: row.created_at,
+            updated_at: row.updated_at,
+        }
+    }
+}
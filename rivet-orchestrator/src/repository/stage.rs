@@ -0,0 +1,131 @@
+//! Stage Repository
+//!
+//! Handles all database operations related to job stages.
+//!
+//! Stages are recorded per attempt of a job; reads only see the job's
+//! current attempt, so a retried job starts with a clean slate.
+
+use rivet_core::domain::job::{StageRun, StageStatus};
+use rivet_core::dto::job::StageUpdate;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+/// Record the progress of a stage in the current attempt of a job
+///
+/// The first update of a stage sets its start time and needs, and the
+/// pipeline version the attempt runs; updates to a final status set its
+/// completion time and the result the stage returned.
+#[tracing::instrument(name = "stage_repository::record", skip_all)]
+pub async fn record(pool: &PgPool, job_id: Uuid, update: &StageUpdate) -> Result<(), sqlx::Error> {
+    let completed_at = (update.status != StageStatus::Running).then_some(update.timestamp);
+    let result_json = update
+        .result
+        .as_ref()
+        .map(serde_json::to_value)
+        .transpose()
+        .map_err(|e| sqlx::Error::Protocol(format!("Failed to serialize result: {}", e)))?;
+
+    sqlx::query(
+        r#"
+        INSERT INTO job_stages (job_id, attempt, name, status, started_at, completed_at,
+                                error_message, needs, script_hash, result)
+        SELECT id, attempt, $2, $3, $4, $5, $6, $7, script_hash, $8
+        FROM jobs
+        WHERE id = $1
+        ON CONFLICT (job_id, attempt, name) DO UPDATE
+        SET status = EXCLUDED.status,
+            completed_at = EXCLUDED.completed_at,
+            error_message = EXCLUDED.error_message,
+            result = EXCLUDED.result
+        "#,
+    )
+    .bind(job_id)
+    .bind(&update.name)
+    .bind(status_to_string(update.status))
+    .bind(update.timestamp)
+    .bind(completed_at)
+    .bind(&update.error_message)
+    .bind(&update.needs)
+    .bind(result_json)
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+/// List the stages of the current attempt of a job, in the order they started
+#[tracing::instrument(name = "stage_repository::find_by_job", skip_all)]
+pub async fn find_by_job(pool: &PgPool, job_id: Uuid) -> Result<Vec<StageRun>, sqlx::Error> {
+    let rows = sqlx::query_as::<_, StageRow>(
+        r#"
+        SELECT s.name, s.status, s.started_at, s.completed_at, s.error_message, s.needs,
+               s.result::text as result
+        FROM job_stages s
+        JOIN jobs j ON j.id = s.job_id AND j.attempt = s.attempt
+        WHERE s.job_id = $1
+        ORDER BY s.started_at ASC, s.name ASC
+        "#,
+    )
+    .bind(job_id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows.into_iter().map(|r| r.into()).collect())
+}
+
+// =============================================================================
+// Helper Functions
+// =============================================================================
+
+fn status_to_string(status: StageStatus) -> &'static str {
+    match status {
+        StageStatus::Running => "Running",
+        StageStatus::Succeeded => "Succeeded",
+        StageStatus::Failed => "Failed",
+        StageStatus::Skipped => "Skipped",
+        StageStatus::Reused => "Reused",
+        StageStatus::AllowedFailure => "AllowedFailure",
+    }
+}
+
+fn string_to_status(s: &str) -> StageStatus {
+    match s {
+        "Succeeded" => StageStatus::Succeeded,
+        "Failed" => StageStatus::Failed,
+        "Skipped" => StageStatus::Skipped,
+        "Reused" => StageStatus::Reused,
+        "AllowedFailure" => StageStatus::AllowedFailure,
+        _ => StageStatus::Running,
+    }
+}
+
+// =============================================================================
+// Database Row Types
+// =============================================================================
+
+#[derive(sqlx::FromRow)]
+struct StageRow {
+    name: String,
+    status: String,
+    started_at: chrono::DateTime<chrono::Utc>,
+    completed_at: Option<chrono::DateTime<chrono::Utc>>,
+    error_message: Option<String>,
+    needs: Vec<String>,
+    result: Option<String>,
+}
+
+impl From<StageRow> for StageRun {
+    fn from(row: StageRow) -> Self {
+        StageRun {
+            name: row.name,
+            status: string_to_status(&row.status),
+            started_at: row.started_at,
+            completed_at: row.completed_at,
+            error_message: row.error_message,
+            needs: row.needs,
+            result: row
+                .result
+                .and_then(|result| serde_json::from_str(&result).ok()),
+        }
+    }
+}
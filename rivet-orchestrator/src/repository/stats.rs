@@ -0,0 +1,289 @@
+//! Stats Repository
+//!
+//! Aggregates job and stage history into pipeline analytics.
+//!
+//! Jobs are attributed to a period by `requested_at`. Failed, timed out and
+//! dead-lettered jobs all count as failures.
+
+use rivet_core::dto::pipeline::{
+    DurationStats, FlakyStage, StageFailures, StatsBucket, TrendBucket,
+};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+/// Job counts of a pipeline since `since`
+pub struct JobCounts {
+    pub jobs: u64,
+    pub succeeded: u64,
+    pub failed: u64,
+    pub cancelled: u64,
+}
+
+#[derive(sqlx::FromRow)]
+struct DurationRow {
+    average: Option<f64>,
+    p50: Option<f64>,
+    p90: Option<f64>,
+    p95: Option<f64>,
+    max: Option<f64>,
+}
+
+#[derive(sqlx::FromRow)]
+struct TrendRow {
+    start: chrono::DateTime<chrono::Utc>,
+    jobs: i64,
+    succeeded: i64,
+    failed: i64,
+    average_duration: Option<f64>,
+}
+
+#[derive(sqlx::FromRow)]
+struct FlakyStageRow {
+    name: String,
+    script_hash: String,
+    runs: i64,
+    failures: i64,
+    flips: i64,
+    retry_flips: i64,
+    last_flip_at: chrono::DateTime<chrono::Utc>,
+}
+
+/// Count the jobs of a pipeline requested since `since`, by outcome
+#[tracing::instrument(name = "stats_repository::count_jobs", skip_all)]
+pub async fn count_jobs(
+    pool: &PgPool,
+    pipeline_id: Uuid,
+    since: chrono::DateTime<chrono::Utc>,
+) -> Result<JobCounts, sqlx::Error> {
+    let (jobs, succeeded, failed, cancelled): (i64, i64, i64, i64) = sqlx::query_as(
+        r#"
+        SELECT COUNT(*),
+               COUNT(*) FILTER (WHERE status = 'Succeeded'),
+               COUNT(*) FILTER (WHERE status IN ('Failed', 'TimedOut', 'DeadLettered')),
+               COUNT(*) FILTER (WHERE status = 'Cancelled')
+        FROM jobs
+        WHERE pipeline_id = $1 AND requested_at >= $2
+        "#,
+    )
+    .bind(pipeline_id)
+    .bind(since)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(JobCounts {
+        jobs: jobs as u64,
+        succeeded: succeeded as u64,
+        failed: failed as u64,
+        cancelled: cancelled as u64,
+    })
+}
+
+/// Durations of the succeeded and failed jobs of a pipeline requested since `since`
+///
+/// # Returns
+/// `None` if no such job finished
+#[tracing::instrument(name = "stats_repository::durations", skip_all)]
+pub async fn durations(
+    pool: &PgPool,
+    pipeline_id: Uuid,
+    since: chrono::DateTime<chrono::Utc>,
+) -> Result<Option<DurationStats>, sqlx::Error> {
+    let row: DurationRow = sqlx::query_as(
+        r#"
+        SELECT AVG(d) AS average,
+               percentile_cont(0.5) WITHIN GROUP (ORDER BY d) AS p50,
+               percentile_cont(0.9) WITHIN GROUP (ORDER BY d) AS p90,
+               percentile_cont(0.95) WITHIN GROUP (ORDER BY d) AS p95,
+               MAX(d) AS max
+        FROM (
+            SELECT EXTRACT(EPOCH FROM completed_at - started_at)::FLOAT8 AS d
+            FROM jobs
+            WHERE pipeline_id = $1 AND requested_at >= $2
+              AND status IN ('Succeeded', 'Failed', 'TimedOut', 'DeadLettered')
+              AND started_at IS NOT NULL AND completed_at IS NOT NULL
+        ) finished
+        "#,
+    )
+    .bind(pipeline_id)
+    .bind(since)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(match row {
+        DurationRow {
+            average: Some(average),
+            p50: Some(p50),
+            p90: Some(p90),
+            p95: Some(p95),
+            max: Some(max),
+        } => Some(DurationStats {
+            average,
+            p50,
+            p90,
+            p95,
+            max,
+        }),
+        _ => None,
+    })
+}
+
+/// Count the failures of each stage of a pipeline's jobs requested since `since`
+///
+/// Every attempt of a job counts, so a stage failing before a retry
+/// succeeds still shows. Stages allowed to fail count when they fail.
+///
+/// # Returns
+/// Stages that failed at least once, most failures first
+#[tracing::instrument(name = "stats_repository::stage_failures", skip_all)]
+pub async fn stage_failures(
+    pool: &PgPool,
+    pipeline_id: Uuid,
+    since: chrono::DateTime<chrono::Utc>,
+) -> Result<Vec<StageFailures>, sqlx::Error> {
+    let rows: Vec<(String, i64, i64)> = sqlx::query_as(
+        r#"
+        SELECT s.name,
+               COUNT(*) FILTER (WHERE s.status IN ('Failed', 'AllowedFailure')) AS failures,
+               COUNT(*) FILTER (WHERE s.status IN ('Succeeded', 'Failed', 'AllowedFailure'))
+        FROM job_stages s
+        JOIN jobs j ON j.id = s.job_id
+        WHERE j.pipeline_id = $1 AND j.requested_at >= $2
+        GROUP BY s.name
+        HAVING COUNT(*) FILTER (WHERE s.status IN ('Failed', 'AllowedFailure')) > 0
+        ORDER BY failures DESC, s.name
+        "#,
+    )
+    .bind(pipeline_id)
+    .bind(since)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows
+        .into_iter()
+        .map(|(stage, failures, runs)| StageFailures {
+            stage,
+            failures: failures as u64,
+            runs: runs as u64,
+        })
+        .collect())
+}
+
+/// Bucket the jobs of a pipeline requested since `since` by day or week (UTC)
+///
+/// # Returns
+/// Every bucket from the one holding `since` to the current one, oldest
+/// first, empty ones included
+#[tracing::instrument(name = "stats_repository::trend", skip_all)]
+pub async fn trend(
+    pool: &PgPool,
+    pipeline_id: Uuid,
+    since: chrono::DateTime<chrono::Utc>,
+    bucket: StatsBucket,
+) -> Result<Vec<TrendBucket>, sqlx::Error> {
+    let rows: Vec<TrendRow> = sqlx::query_as(
+        r#"
+        SELECT b.start,
+               COUNT(j.id) AS jobs,
+               COUNT(j.id) FILTER (WHERE j.status = 'Succeeded') AS succeeded,
+               COUNT(j.id) FILTER (WHERE j.status IN ('Failed', 'TimedOut', 'DeadLettered')) AS failed,
+               (AVG(EXTRACT(EPOCH FROM j.completed_at - j.started_at))
+                    FILTER (WHERE j.status IN ('Succeeded', 'Failed', 'TimedOut', 'DeadLettered')
+                              AND j.started_at IS NOT NULL))::FLOAT8 AS average_duration
+        FROM generate_series(
+                 date_trunc($3, $2::timestamptz, 'UTC'),
+                 date_trunc($3, now(), 'UTC'),
+                 ('1 ' || $3)::interval
+             ) AS b(start)
+        LEFT JOIN jobs j
+               ON j.pipeline_id = $1
+              AND j.requested_at >= $2
+              AND date_trunc($3, j.requested_at, 'UTC') = b.start
+        GROUP BY b.start
+        ORDER BY b.start
+        "#,
+    )
+    .bind(pipeline_id)
+    .bind(since)
+    .bind(bucket.as_str())
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows
+        .into_iter()
+        .map(|row| TrendBucket {
+            start: row.start,
+            jobs: row.jobs as u64,
+            succeeded: row.succeeded as u64,
+            failed: row.failed as u64,
+            average_duration: row.average_duration,
+        })
+        .collect())
+}
+
+/// Find the stages whose outcome flipped at least `min_flips` times on one
+/// version of a pipeline, or that passed on a retry after failing, among the
+/// stage runs started since `since`
+///
+/// Stage runs recorded before versions were tracked are ignored.
+///
+/// # Returns
+/// One entry per stage and version, most retry flips first, then by flip rate
+#[tracing::instrument(name = "stats_repository::flaky_stages", skip_all)]
+pub async fn flaky_stages(
+    pool: &PgPool,
+    pipeline_id: Uuid,
+    since: chrono::DateTime<chrono::Utc>,
+    min_flips: u32,
+) -> Result<Vec<FlakyStage>, sqlx::Error> {
+    let rows: Vec<FlakyStageRow> = sqlx::query_as(
+        r#"
+        WITH outcomes AS (
+            SELECT s.name, s.script_hash, s.job_id, s.started_at,
+                   s.status = 'Succeeded' AS passed,
+                   LAG(s.status = 'Succeeded') OVER (
+                       PARTITION BY s.name, s.script_hash ORDER BY s.started_at, s.attempt
+                   ) AS previous_passed,
+                   LAG(s.status = 'Succeeded') OVER (
+                       PARTITION BY s.name, s.job_id ORDER BY s.attempt
+                   ) AS previous_attempt_passed
+            FROM job_stages s
+            JOIN jobs j ON j.id = s.job_id
+            WHERE j.pipeline_id = $1 AND s.started_at >= $2
+              AND s.script_hash IS NOT NULL
+              AND s.status IN ('Succeeded', 'Failed', 'AllowedFailure')
+        )
+        SELECT name, script_hash,
+               COUNT(*) AS runs,
+               COUNT(*) FILTER (WHERE NOT passed) AS failures,
+               COUNT(*) FILTER (WHERE passed <> previous_passed) AS flips,
+               COUNT(*) FILTER (WHERE passed AND NOT previous_attempt_passed) AS retry_flips,
+               MAX(started_at) FILTER (WHERE passed <> previous_passed) AS last_flip_at
+        FROM outcomes
+        GROUP BY name, script_hash
+        HAVING COUNT(*) FILTER (WHERE passed <> previous_passed) >= $3
+            OR COUNT(*) FILTER (WHERE passed AND NOT previous_attempt_passed) > 0
+        ORDER BY retry_flips DESC,
+                 COUNT(*) FILTER (WHERE passed <> previous_passed)::FLOAT8 / (COUNT(*) - 1) DESC,
+                 name
+        "#,
+    )
+    .bind(pipeline_id)
+    .bind(since)
+    .bind(min_flips as i64)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows
+        .into_iter()
+        .map(|row| FlakyStage {
+            flip_rate: row.flips as f64 / (row.runs - 1) as f64,
+            stage: row.name,
+            version: row.script_hash,
+            runs: row.runs as u64,
+            failures: row.failures as u64,
+            flips: row.flips as u64,
+            retry_flips: row.retry_flips as u64,
+            last_flip_at: row.last_flip_at,
+        })
+        .collect())
+}
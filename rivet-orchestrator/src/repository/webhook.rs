@@ -0,0 +1,130 @@
+//! Webhook Repository
+//!
+//! Handles all database operations related to webhook triggers.
+
+use rivet_core::domain::webhook::WebhookTrigger;
+use rivet_core::dto::commit_status::GitProvider;
+use rivet_core::dto::webhook::CreateWebhookTrigger;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+/// Add a webhook trigger to a pipeline
+#[tracing::instrument(name = "webhook_repository::create", skip_all)]
+pub async fn create(
+    pool: &PgPool,
+    pipeline_id: Uuid,
+    req: CreateWebhookTrigger,
+) -> Result<WebhookTrigger, sqlx::Error> {
+    let trigger = WebhookTrigger {
+        id: Uuid::new_v4(),
+        pipeline_id,
+        provider: req.provider,
+        secret: req.secret,
+        filters: req.filters,
+        parameters: req.parameters,
+        created_at: chrono::Utc::now(),
+    };
+
+    let filters_json = serde_json::to_value(&trigger.filters)
+        .map_err(|e| sqlx::Error::Protocol(format!("Failed to serialize filters: {}", e)))?;
+    let parameters_json = serde_json::to_value(&trigger.parameters)
+        .map_err(|e| sqlx::Error::Protocol(format!("Failed to serialize parameters: {}", e)))?;
+
+    sqlx::query(
+        r#"
+        INSERT INTO webhook_triggers (id, pipeline_id, provider, secret_name, filters, parameters, created_at)
+        VALUES ($1, $2, $3, $4, $5, $6, $7)
+        "#,
+    )
+    .bind(trigger.id)
+    .bind(trigger.pipeline_id)
+    .bind(trigger.provider.as_str())
+    .bind(&trigger.secret)
+    .bind(filters_json)
+    .bind(parameters_json)
+    .bind(trigger.created_at)
+    .execute(pool)
+    .await?;
+
+    Ok(trigger)
+}
+
+/// Find a webhook trigger by ID
+#[tracing::instrument(name = "webhook_repository::find_by_id", skip_all)]
+pub async fn find_by_id(pool: &PgPool, id: Uuid) -> Result<Option<WebhookTrigger>, sqlx::Error> {
+    let row = sqlx::query_as::<_, WebhookTriggerRow>(
+        r#"
+        SELECT id, pipeline_id, provider, secret_name, filters, parameters, created_at
+        FROM webhook_triggers
+        WHERE id = $1
+        "#,
+    )
+    .bind(id)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(row.map(|r| r.into()))
+}
+
+/// List the webhook triggers of a pipeline, oldest first
+#[tracing::instrument(name = "webhook_repository::list_by_pipeline", skip_all)]
+pub async fn list_by_pipeline(
+    pool: &PgPool,
+    pipeline_id: Uuid,
+) -> Result<Vec<WebhookTrigger>, sqlx::Error> {
+    let rows = sqlx::query_as::<_, WebhookTriggerRow>(
+        r#"
+        SELECT id, pipeline_id, provider, secret_name, filters, parameters, created_at
+        FROM webhook_triggers
+        WHERE pipeline_id = $1
+        ORDER BY created_at ASC
+        "#,
+    )
+    .bind(pipeline_id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows.into_iter().map(|r| r.into()).collect())
+}
+
+/// Delete a webhook trigger of a pipeline
+#[tracing::instrument(name = "webhook_repository::delete", skip_all)]
+pub async fn delete(pool: &PgPool, pipeline_id: Uuid, id: Uuid) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query("DELETE FROM webhook_triggers WHERE id = $1 AND pipeline_id = $2")
+        .bind(id)
+        .bind(pipeline_id)
+        .execute(pool)
+        .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+// =============================================================================
+// Database Row Types
+// =============================================================================
+
+#[derive(sqlx::FromRow)]
+struct WebhookTriggerRow {
+    id: Uuid,
+    pipeline_id: Uuid,
+    provider: String,
+    secret_name: String,
+    filters: serde_json::Value,
+    parameters: serde_json::Value,
+    created_at: chrono::DateTime<chrono::Utc>,
+}
+
+impl From<WebhookTriggerRow> for WebhookTrigger {
+    fn from(row: WebhookTriggerRow) -> Self {
+        WebhookTrigger {
+            id: row.id,
+            pipeline_id: row.pipeline_id,
+            // Only ever written from a `GitProvider`
+            provider: row.provider.parse().unwrap_or(GitProvider::Github),
+            secret: row.secret_name,
+            filters: serde_json::from_value(row.filters).unwrap_or_default(),
+            parameters: serde_json::from_value(row.parameters).unwrap_or_default(),
+            created_at: row.created_at,
+        }
+    }
+}
@@ -0,0 +1,208 @@
+//! Job Scheduling Policy
+//!
+//! Decides which queued job a runner receives next. Strict FIFO lets one
+//! pipeline that enqueues hundreds of jobs hold every runner until its
+//! backlog drains; the fair policies interleave pipelines (or projects)
+//! instead. Whatever the policy, a job goes to the least loaded runner able
+//! to run it, going by the load runners report with their heartbeats.
+
+use std::fmt;
+use std::str::FromStr;
+
+/// Order in which queued jobs are handed to runners
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SchedulingPolicy {
+    /// Oldest queued job first, regardless of where it comes from
+    Fifo,
+    /// Round-robin across pipelines, weighted by the jobs each one already
+    /// has running
+    #[default]
+    FairPipelines,
+    /// Same as `FairPipelines`, grouping jobs by project instead
+    FairProjects,
+}
+
+impl SchedulingPolicy {
+    /// SQL expression grouping jobs for fair ordering
+    ///
+    /// Evaluated over `jobs j JOIN pipelines p`, `None` for FIFO.
+    pub(crate) fn group_key(self) -> Option<&'static str> {
+        match self {
+            SchedulingPolicy::Fifo => None,
+            SchedulingPolicy::FairPipelines => Some("j.pipeline_id"),
+            SchedulingPolicy::FairProjects => Some("p.project_id"),
+        }
+    }
+}
+
+impl fmt::Display for SchedulingPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            SchedulingPolicy::Fifo => "fifo",
+            SchedulingPolicy::FairPipelines => "fair-pipelines",
+            SchedulingPolicy::FairProjects => "fair-projects",
+        })
+    }
+}
+
+impl FromStr for SchedulingPolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "fifo" => Ok(SchedulingPolicy::Fifo),
+            "fair-pipelines" | "fair" => Ok(SchedulingPolicy::FairPipelines),
+            "fair-projects" => Ok(SchedulingPolicy::FairProjects),
+            other => Err(format!(
+                "Unknown scheduling policy '{}' (expected fifo, fair-pipelines or fair-projects)",
+                other
+            )),
+        }
+    }
+}
+
+/// Condition on `jobs j` selecting jobs a runner may pick up now
+///
+/// Jobs waiting for a retry backoff, for the approvals an environment one of
+/// their stages deploys to requires, or for an open blackout window of their
+/// pipeline or of such an environment to close (unless they override it),
+/// stay queued but are not ready yet.
+/// Jobs locked out of an environment (`LOCKED_OUT`) are not ready either,
+/// even before they are marked `Blocked`.
+const READY: &str = r#"j.status = 'Queued' AND (j.not_before IS NULL OR j.not_before <= NOW())
+        AND NOT EXISTS (
+            SELECT 1
+            FROM pipelines dp
+            CROSS JOIN jsonb_each_text(dp.deploy_stages) d
+            JOIN environments e ON e.project_id = dp.project_id AND e.name = d.value
+            WHERE dp.id = j.pipeline_id
+              AND e.required_approvals > (
+                  SELECT COUNT(*) FROM job_approvals a
+                  WHERE a.job_id = j.id AND a.environment = e.name
+              )
+        )
+        AND (j.override_blackout OR NOT EXISTS (
+            SELECT 1
+            FROM pipelines bp
+            JOIN blackout_windows b ON b.project_id = bp.project_id
+            WHERE bp.id = j.pipeline_id
+              AND b.starts_at <= NOW() AND b.ends_at > NOW()
+              AND (b.pipeline_id = bp.id
+                   OR b.environment IN (SELECT value FROM jsonb_each_text(bp.deploy_stages)))
+        ))"#;
+
+/// Condition on `jobs j` holding for jobs deploying to an environment whose
+/// lock another job holds
+///
+/// A lock counts while its holder is queued (between retries) or running;
+/// locks of finished jobs are stale, whether or not they were released.
+pub(crate) const LOCKED_OUT: &str = r#"EXISTS (
+            SELECT 1
+            FROM pipelines lp
+            CROSS JOIN jsonb_each_text(lp.deploy_stages) ld
+            JOIN environment_locks l ON l.project_id = lp.project_id AND l.environment = ld.value
+            JOIN jobs h ON h.id = l.job_id
+            WHERE lp.id = j.pipeline_id
+              AND l.job_id <> j.id
+              AND h.status IN ('Queued', 'Running')
+        )"#;
+
+/// SQL selecting the queued jobs in the order the policy serves them
+///
+/// Produces `(id, turn)` rows for the jobs that are ready to run, lower
+/// turns first; ties are broken by `requested_at`. A job's turn is its
+/// position in its group's queue plus the number of jobs the group already
+/// has running, so a group hogging runners yields to the others until they
+/// catch up.
+pub(crate) fn queue_turns_sql(policy: SchedulingPolicy) -> String {
+    let Some(group) = policy.group_key() else {
+        return format!(
+            "SELECT j.id, 0::BIGINT AS turn FROM jobs j WHERE {READY} AND NOT {LOCKED_OUT}"
+        );
+    };
+
+    format!(
+        r#"
+        WITH running AS (
+            SELECT {group} AS grp, COUNT(*) AS n
+            FROM jobs j JOIN pipelines p ON p.id = j.pipeline_id
+            WHERE j.status = 'Running'
+            GROUP BY {group}
+        )
+        SELECT j.id,
+               ROW_NUMBER() OVER (PARTITION BY {group} ORDER BY j.requested_at, j.id)
+                   + COALESCE(running.n, 0) AS turn
+        FROM jobs j
+        JOIN pipelines p ON p.id = j.pipeline_id
+        LEFT JOIN running ON running.grp = {group}
+        WHERE {READY} AND NOT {LOCKED_OUT}
+        "#
+    )
+}
+
+/// How long a ready job waits for a less loaded runner before any runner
+/// able to run it may take it
+pub const LOAD_BALANCE_GRACE_SECS: i64 = 10;
+
+/// Share of its parallel job slots runner `alias` is using
+///
+/// Running jobs are counted from the jobs table rather than taken from the
+/// last heartbeat, so a claim counts right away.
+fn busy_share_sql(alias: &str) -> String {
+    format!(
+        "((SELECT COUNT(*) FROM jobs rj WHERE rj.runner_id = {alias}.id AND rj.status = 'Running')::FLOAT8 \
+          / GREATEST(({alias}.load->>'max_parallel_jobs')::FLOAT8, 1))"
+    )
+}
+
+/// CPU load runner `alias` last reported
+fn cpu_load_sql(alias: &str) -> String {
+    format!("COALESCE(({alias}.load->>'cpu_load')::FLOAT8, 0)")
+}
+
+/// Condition letting the claiming runner `r` take job `jobs` of pipeline `p`
+///
+/// Holds unless another online runner providing what the pipeline requires
+/// has a free slot and a lower load (busy share first, CPU load to break
+/// ties), so jobs go to the least loaded runner instead of whichever polls
+/// first. Runners that never reported a load neither wait nor make others
+/// wait, and a job ready for `LOAD_BALANCE_GRACE_SECS` goes to any runner in
+/// case the less loaded ones are not polling.
+pub(crate) fn least_loaded_sql() -> String {
+    let (other_share, own_share) = (busy_share_sql("o"), busy_share_sql("r"));
+    let (other_cpu, own_cpu) = (cpu_load_sql("o"), cpu_load_sql("r"));
+    format!(
+        r#"(
+            r.load IS NULL
+            OR COALESCE(jobs.not_before, jobs.requested_at)
+                <= NOW() - INTERVAL '{LOAD_BALANCE_GRACE_SECS} seconds'
+            OR NOT EXISTS (
+                SELECT 1 FROM runners o
+                WHERE o.id <> r.id
+                  AND o.status = 'Online'
+                  AND o.load IS NOT NULL
+                  AND o.tags @> p.tags
+                  AND o.plugins @> p.plugins
+                  AND {other_share} < 1
+                  AND ({other_share}, {other_cpu}) < ({own_share}, {own_cpu})
+            )
+        )"#
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_policy_round_trips_through_strings() {
+        for policy in [
+            SchedulingPolicy::Fifo,
+            SchedulingPolicy::FairPipelines,
+            SchedulingPolicy::FairProjects,
+        ] {
+            assert_eq!(policy.to_string().parse::<SchedulingPolicy>(), Ok(policy));
+        }
+        assert!("round-robin".parse::<SchedulingPolicy>().is_err());
+    }
+}
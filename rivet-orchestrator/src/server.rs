@@ -0,0 +1,487 @@
+//! Embeddable Server
+//!
+//! Entry point for running the orchestrator inside another application.
+//! A `Server` bundles the HTTP router and the background duties that keep
+//! orchestrator state healthy (e.g. marking silent runners offline,
+//! requeueing jobs whose lease expired, deleting expired artifacts and
+//! archiving the logs of completed jobs) or
+//! report to the outside world (commit statuses).
+//!
+//! The caller owns the runtime: mount the router wherever it fits and spawn
+//! `background_tasks()` under whatever supervision the application uses.
+//! Replicas sharing a database elect a leader (see `leader`); only the
+//! leader runs the periodic duties.
+
+use axum::Router;
+use sqlx::PgPool;
+use std::net::SocketAddr;
+use std::time::Duration;
+use tower_http::compression::predicate::{NotForContentType, Predicate};
+use tower_http::compression::{CompressionLayer, DefaultPredicate};
+
+use crate::api::auth::AuthConfig;
+use crate::api::cors::CorsConfig;
+use crate::api::rate_limit::{RateLimitConfig, RateLimiter};
+use crate::api::{self, AppState};
+use crate::commit_status;
+use crate::config::ConfigError;
+use crate::debug::DebugRelay;
+use crate::events::EventBus;
+use crate::graphql;
+#[cfg(feature = "grpc")]
+use crate::grpc::GrpcRunnerService;
+use crate::leader::{Candidate, Leadership};
+use crate::log_archive::LogArchive;
+use crate::scheduling::SchedulingPolicy;
+use crate::service::{
+    artifact as artifact_service, environment as environment_service, job as job_service,
+    log as log_service, runner as runner_service, schedule as schedule_service,
+};
+use crate::workers::WorkerMonitor;
+use rivet_core::domain::job::JobStatus;
+use rivet_core::dto::event::Event;
+#[cfg(feature = "grpc")]
+use rivet_proto::RunnerServiceServer;
+
+/// Default time without a heartbeat before a runner is marked offline
+pub const DEFAULT_RUNNER_TIMEOUT: Duration = Duration::from_secs(90);
+
+/// Default interval between stale-runner and expired-lease sweeps
+pub const DEFAULT_SWEEP_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Interval between checks for due cron schedules
+pub const SCHEDULE_INTERVAL: Duration = Duration::from_secs(15);
+
+/// Default interval between artifact garbage collections
+pub const DEFAULT_ARTIFACT_GC_INTERVAL: Duration = Duration::from_secs(3600);
+
+/// Interval between log compactions, when a log archive is configured
+pub const LOG_COMPACTION_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Default time after completion before the logs of a job are archived
+pub const DEFAULT_LOG_COMPACT_AFTER: Duration = Duration::from_secs(600);
+
+/// Builder for `Server`
+#[derive(Debug, Default)]
+pub struct ServerBuilder {
+    pool: Option<PgPool>,
+    runner_timeout: Option<Duration>,
+    sweep_interval: Option<Duration>,
+    artifact_gc_interval: Option<Duration>,
+    log_archive: LogArchive,
+    log_compact_after: Option<Duration>,
+    auth: AuthConfig,
+    scheduling: SchedulingPolicy,
+    rate_limits: RateLimitConfig,
+    cors: CorsConfig,
+    disable_compression: bool,
+}
+
+impl ServerBuilder {
+    /// Sets the database connection pool (required)
+    pub fn pool(mut self, pool: PgPool) -> Self {
+        self.pool = Some(pool);
+        self
+    }
+
+    /// Sets how long a runner may go without a heartbeat before it is marked offline
+    pub fn runner_timeout(mut self, timeout: Duration) -> Self {
+        self.runner_timeout = Some(timeout);
+        self
+    }
+
+    /// Sets how often background tasks check for stale runners and expired job leases
+    pub fn sweep_interval(mut self, interval: Duration) -> Self {
+        self.sweep_interval = Some(interval);
+        self
+    }
+
+    /// Sets how often artifacts past their pipeline's retention limits are deleted
+    pub fn artifact_gc_interval(mut self, interval: Duration) -> Self {
+        self.artifact_gc_interval = Some(interval);
+        self
+    }
+
+    /// Moves the logs of completed jobs to object storage (default: logs stay
+    /// in the database)
+    pub fn log_archive(mut self, archive: LogArchive) -> Self {
+        self.log_archive = archive;
+        self
+    }
+
+    /// Sets how long after a job completes its logs are archived
+    pub fn log_compact_after(mut self, delay: Duration) -> Self {
+        self.log_compact_after = Some(delay);
+        self
+    }
+
+    /// Sets the order in which queued jobs are handed to runners
+    /// (default: fair across pipelines)
+    pub fn scheduling_policy(mut self, policy: SchedulingPolicy) -> Self {
+        self.scheduling = policy;
+        self
+    }
+
+    /// Requires `Authorization: Bearer <token>` to manage runner registration tokens
+    pub fn admin_token(mut self, token: impl Into<String>) -> Self {
+        self.auth.admin_token = Some(token.into());
+        self
+    }
+
+    /// Requires runners to register with a registration token and to send
+    /// their credential on job lifecycle calls (default: off)
+    pub fn require_runner_auth(mut self, required: bool) -> Self {
+        self.auth.require_runner_auth = required;
+        self
+    }
+
+    /// Limits the request rate of each client per route class (default: unlimited)
+    ///
+    /// Clients without a token are identified by IP address, which requires
+    /// serving the router with `ConnectInfo<SocketAddr>` (as `serve` does).
+    pub fn rate_limits(mut self, limits: RateLimitConfig) -> Self {
+        self.rate_limits = limits;
+        self
+    }
+
+    /// Allows browsers on other origins to call the API (default: disabled)
+    pub fn cors(mut self, cors: CorsConfig) -> Self {
+        self.cors = cors;
+        self
+    }
+
+    /// Compresses responses with gzip or brotli for clients that accept it
+    /// (default: enabled)
+    pub fn compression(mut self, enabled: bool) -> Self {
+        self.disable_compression = !enabled;
+        self
+    }
+
+    /// Builds the server
+    ///
+    /// # Errors
+    /// `ConfigError::Invalid`, listing every problem, if no database pool was
+    /// provided or an interval or timeout is zero.
+    pub fn build(self) -> Result<Server, ConfigError> {
+        let mut errors = Vec::new();
+        if self.pool.is_none() {
+            errors.push("a database pool is required, call .pool() first".to_string());
+        }
+        let durations = [
+            ("runner_timeout", self.runner_timeout),
+            ("sweep_interval", self.sweep_interval),
+            ("artifact_gc_interval", self.artifact_gc_interval),
+        ];
+        for (name, duration) in durations {
+            if duration.is_some_and(|d| d.is_zero()) {
+                errors.push(format!("{} must be greater than zero", name));
+            }
+        }
+        let Some(pool) = self.pool.filter(|_| errors.is_empty()) else {
+            return Err(ConfigError::Invalid(errors));
+        };
+
+        Ok(Server {
+            pool,
+            runner_timeout: self.runner_timeout.unwrap_or(DEFAULT_RUNNER_TIMEOUT),
+            sweep_interval: self.sweep_interval.unwrap_or(DEFAULT_SWEEP_INTERVAL),
+            artifact_gc_interval: self
+                .artifact_gc_interval
+                .unwrap_or(DEFAULT_ARTIFACT_GC_INTERVAL),
+            log_archive: self.log_archive,
+            log_compact_after: self.log_compact_after.unwrap_or(DEFAULT_LOG_COMPACT_AFTER),
+            events: EventBus::default(),
+            workers: WorkerMonitor::default(),
+            leadership: Leadership::default(),
+            auth: self.auth,
+            scheduling: self.scheduling,
+            rate_limiter: RateLimiter::new(self.rate_limits),
+            cors: self.cors,
+            compression: !self.disable_compression,
+        })
+    }
+}
+
+/// A configured orchestrator instance
+#[derive(Debug, Clone)]
+pub struct Server {
+    pool: PgPool,
+    runner_timeout: Duration,
+    sweep_interval: Duration,
+    artifact_gc_interval: Duration,
+    log_archive: LogArchive,
+    log_compact_after: Duration,
+    events: EventBus,
+    workers: WorkerMonitor,
+    leadership: Leadership,
+    auth: AuthConfig,
+    scheduling: SchedulingPolicy,
+    rate_limiter: RateLimiter,
+    cors: CorsConfig,
+    compression: bool,
+}
+
+impl Server {
+    /// Creates a new builder
+    pub fn builder() -> ServerBuilder {
+        ServerBuilder::default()
+    }
+
+    /// Returns the database pool used by this server
+    pub fn pool(&self) -> &PgPool {
+        &self.pool
+    }
+
+    /// Returns the event bus, to publish or observe orchestrator events in-process
+    pub fn events(&self) -> &EventBus {
+        &self.events
+    }
+
+    /// Returns whether this replica won the election to run the background duties
+    pub fn leadership(&self) -> &Leadership {
+        &self.leadership
+    }
+
+    /// Returns the API router, ready to be merged or nested into another axum app
+    ///
+    /// All routes live under the `/api` prefix. CORS, when enabled, wraps
+    /// every other layer so preflight requests are answered before
+    /// authentication and rate limiting. Responses are compressed for clients
+    /// that accept it, except artifacts, which are usually compressed already.
+    pub fn into_router(self) -> Router {
+        let cors = self.cors.layer();
+        let router = api::create_router(AppState {
+            schema: graphql::build_schema(self.pool.clone(), self.log_archive.clone()),
+            pool: self.pool,
+            events: self.events,
+            debug: DebugRelay::default(),
+            auth: self.auth,
+            scheduling: self.scheduling,
+            rate_limiter: self.rate_limiter,
+            workers: self.workers,
+            leadership: self.leadership,
+            log_archive: self.log_archive,
+        });
+
+        let router = if self.compression {
+            // Artifacts are usually archives already
+            let predicate = DefaultPredicate::new()
+                .and(NotForContentType::const_new("application/octet-stream"));
+            router.layer(CompressionLayer::new().compress_when(predicate))
+        } else {
+            router
+        };
+
+        match cors {
+            Some(cors) => router.layer(cors),
+            None => router,
+        }
+    }
+
+    /// Returns the gRPC runner service, sharing this server's pool and event bus
+    ///
+    /// Serve it with `tonic::transport::Server` next to the HTTP router.
+    #[cfg(feature = "grpc")]
+    pub fn grpc_service(&self) -> RunnerServiceServer<GrpcRunnerService> {
+        GrpcRunnerService::new(
+            self.pool.clone(),
+            self.events.clone(),
+            self.auth.clone(),
+            self.scheduling,
+        )
+        .into_server()
+    }
+
+    /// Returns a future that runs the orchestrator background duties forever
+    ///
+    /// The future never completes on its own; drop or abort it to stop.
+    /// Periodic duties report their passes to the readiness probe (`/readyz`),
+    /// and skip them while another replica is the leader. Commit statuses are
+    /// reported by every replica for the events it publishes.
+    pub fn background_tasks(&self) -> impl Future<Output = ()> + Send + 'static {
+        let pool = self.pool.clone();
+        let events = self.events.clone();
+        let workers = self.workers.clone();
+        let leadership = self.leadership.clone();
+        let mut candidate = Candidate::new(pool.clone(), leadership.clone());
+        let timeout_seconds = self.runner_timeout.as_secs() as i64;
+        let sweep_interval = self.sweep_interval;
+        let artifact_gc_interval = self.artifact_gc_interval;
+        let log_archive = self.log_archive.clone();
+        let log_compact_after = self.log_compact_after;
+
+        workers.register("sweep", sweep_interval);
+        workers.register("artifact_gc", artifact_gc_interval);
+        workers.register("schedules", SCHEDULE_INTERVAL);
+        if log_archive.is_enabled() {
+            workers.register("log_compaction", LOG_COMPACTION_INTERVAL);
+        }
+
+        let artifact_gc = {
+            let pool = pool.clone();
+            let workers = workers.clone();
+            let leadership = leadership.clone();
+            async move {
+                let mut interval = tokio::time::interval(artifact_gc_interval);
+                loop {
+                    interval.tick().await;
+                    if !leadership.is_leader() {
+                        workers.record_pass("artifact_gc");
+                        continue;
+                    }
+                    if let Err(e) = artifact_service::collect_garbage(&pool, false).await {
+                        tracing::warn!("Failed to collect artifact garbage: {:?}", e);
+                    }
+                    workers.record_pass("artifact_gc");
+                }
+            }
+        };
+
+        let commit_statuses = commit_status::report_commit_statuses(pool.clone(), events.clone());
+
+        let log_compaction = {
+            let pool = pool.clone();
+            let workers = workers.clone();
+            let leadership = leadership.clone();
+            async move {
+                if !log_archive.is_enabled() {
+                    return;
+                }
+                let mut interval = tokio::time::interval(LOG_COMPACTION_INTERVAL);
+                loop {
+                    interval.tick().await;
+                    if leadership.is_leader() {
+                        match log_service::compact_logs(&pool, &log_archive, log_compact_after)
+                            .await
+                        {
+                            Ok(0) => {}
+                            Ok(jobs) => tracing::info!("Archived the logs of {} jobs", jobs),
+                            Err(e) => tracing::warn!("Failed to compact logs: {:?}", e),
+                        }
+                    }
+                    workers.record_pass("log_compaction");
+                }
+            }
+        };
+
+        let schedules = {
+            let pool = pool.clone();
+            let events = events.clone();
+            let workers = workers.clone();
+            let leadership = leadership.clone();
+            async move {
+                let mut interval = tokio::time::interval(SCHEDULE_INTERVAL);
+                loop {
+                    interval.tick().await;
+                    if !leadership.is_leader() {
+                        workers.record_pass("schedules");
+                        continue;
+                    }
+                    match schedule_service::run_due_schedules(&pool).await {
+                        Ok(jobs) => {
+                            for job in jobs {
+                                events.publish(Event::JobQueued {
+                                    job_id: job.id,
+                                    pipeline_id: job.pipeline_id,
+                                });
+                            }
+                        }
+                        Err(e) => tracing::warn!("Failed to run due schedules: {:?}", e),
+                    }
+                    workers.record_pass("schedules");
+                }
+            }
+        };
+
+        let sweep = async move {
+            let mut interval = tokio::time::interval(sweep_interval);
+            loop {
+                interval.tick().await;
+                if !leadership.is_leader() {
+                    workers.record_pass("sweep");
+                    continue;
+                }
+                match runner_service::mark_stale_runners_offline(&pool, timeout_seconds).await {
+                    Ok(runner_ids) => {
+                        for runner_id in runner_ids {
+                            events.publish(Event::RunnerOffline { runner_id });
+                        }
+                    }
+                    Err(e) => tracing::warn!("Failed to mark stale runners offline: {:?}", e),
+                }
+                match job_service::requeue_expired_jobs(&pool).await {
+                    Ok(expired) => {
+                        for (job_id, pipeline_id) in expired.requeued {
+                            events.publish(Event::JobQueued {
+                                job_id,
+                                pipeline_id,
+                            });
+                        }
+                        for (job_id, pipeline_id) in expired.dead_lettered {
+                            events.publish(Event::JobCompleted {
+                                job_id,
+                                pipeline_id,
+                                status: JobStatus::DeadLettered,
+                            });
+                        }
+                    }
+                    Err(e) => tracing::warn!("Failed to requeue expired jobs: {:?}", e),
+                }
+                match environment_service::sync_blocked_jobs(&pool).await {
+                    Ok(unblocked) => {
+                        for (job_id, pipeline_id) in unblocked {
+                            events.publish(Event::JobQueued {
+                                job_id,
+                                pipeline_id,
+                            });
+                        }
+                    }
+                    Err(e) => tracing::warn!("Failed to sync blocked jobs: {:?}", e),
+                }
+                workers.record_pass("sweep");
+            }
+        };
+
+        async move {
+            // Stand for election before the duties' first pass
+            candidate.elect().await;
+            tokio::join!(
+                candidate.campaign(),
+                sweep,
+                artifact_gc,
+                commit_statuses,
+                schedules,
+                log_compaction
+            );
+        }
+    }
+
+    /// Serves the API on the given listener until the server stops
+    ///
+    /// Background tasks are not started; spawn `background_tasks()` separately.
+    pub async fn serve(self, listener: tokio::net::TcpListener) -> std::io::Result<()> {
+        axum::serve(
+            listener,
+            self.into_router()
+                .into_make_service_with_connect_info::<SocketAddr>(),
+        )
+        .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_build_reports_invalid_settings() {
+        let Err(ConfigError::Invalid(errors)) =
+            Server::builder().sweep_interval(Duration::ZERO).build()
+        else {
+            panic!("expected invalid settings");
+        };
+        assert_eq!(errors.len(), 2);
+        assert!(errors[0].contains("database pool"));
+        assert!(errors[1].contains("sweep_interval"));
+    }
+}
@@ -0,0 +1,201 @@
+//! Artifact Service
+//!
+//! Business logic for storing and retrieving job artifacts.
+
+use rivet_core::domain::artifact::Artifact;
+use rivet_core::dto::artifact::{ArtifactGcReport, ArtifactUsage, ExpiredArtifact};
+use sha2::{Digest, Sha256};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::repository::{artifact_repository, job_repository};
+
+/// Maximum size of a single artifact (100 MiB)
+pub const MAX_ARTIFACT_SIZE: usize = 100 * 1024 * 1024;
+
+/// Service error type
+#[derive(Debug)]
+pub enum ArtifactError {
+    NotFound(String),
+    JobNotFound(Uuid),
+    ValidationError(String),
+    DatabaseError(sqlx::Error),
+}
+
+impl From<sqlx::Error> for ArtifactError {
+    fn from(err: sqlx::Error) -> Self {
+        ArtifactError::DatabaseError(err)
+    }
+}
+
+pub type Result<T> = std::result::Result<T, ArtifactError>;
+
+/// Store an artifact for a job
+///
+/// Uploading an artifact with an existing name replaces it.
+pub async fn upload_artifact(
+    pool: &PgPool,
+    job_id: Uuid,
+    name: &str,
+    content: &[u8],
+) -> Result<Artifact> {
+    validate_artifact_name(name)?;
+
+    if content.len() > MAX_ARTIFACT_SIZE {
+        return Err(ArtifactError::ValidationError(format!(
+            "Artifact too large (max: {} bytes)",
+            MAX_ARTIFACT_SIZE
+        )));
+    }
+
+    ensure_job_exists(pool, job_id).await?;
+
+    let sha256 = hex::encode(Sha256::digest(content));
+    let artifact = artifact_repository::upsert(pool, job_id, name, &sha256, content).await?;
+
+    tracing::info!(
+        "Stored artifact '{}' for job {} ({} bytes)",
+        name,
+        job_id,
+        artifact.size_bytes
+    );
+
+    Ok(artifact)
+}
+
+/// List artifacts of a job
+pub async fn list_artifacts(pool: &PgPool, job_id: Uuid) -> Result<Vec<Artifact>> {
+    ensure_job_exists(pool, job_id).await?;
+
+    let artifacts = artifact_repository::find_by_job(pool, job_id).await?;
+    Ok(artifacts)
+}
+
+/// Get an artifact and its content
+pub async fn get_artifact(pool: &PgPool, job_id: Uuid, name: &str) -> Result<(Artifact, Vec<u8>)> {
+    ensure_job_exists(pool, job_id).await?;
+
+    artifact_repository::find_with_content(pool, job_id, name)
+        .await?
+        .ok_or_else(|| ArtifactError::NotFound(name.to_string()))
+}
+
+/// Count the stored artifacts and their bytes, overall and per pipeline
+pub async fn get_usage(pool: &PgPool) -> Result<ArtifactUsage> {
+    let usage = artifact_repository::usage(pool).await?;
+    Ok(usage)
+}
+
+/// Find the artifacts pipelines' retention limits expire, and delete them
+/// unless `dry_run` is set
+pub async fn collect_garbage(pool: &PgPool, dry_run: bool) -> Result<ArtifactGcReport> {
+    let usage = artifact_repository::usage(pool).await?;
+    let now = chrono::Utc::now();
+
+    let mut expired = Vec::new();
+    for (pipeline_id, retention, artifacts) in artifact_repository::find_retained(pool).await? {
+        expired.extend(
+            retention
+                .expire(&artifacts, now)
+                .into_iter()
+                .map(|(artifact, reason)| ExpiredArtifact {
+                    pipeline_id,
+                    artifact: artifact.clone(),
+                    reason,
+                }),
+        );
+    }
+    let expired_bytes = expired
+        .iter()
+        .map(|e| e.artifact.size_bytes.max(0) as u64)
+        .sum();
+
+    if !dry_run && !expired.is_empty() {
+        let ids: Vec<Uuid> = expired.iter().map(|e| e.artifact.id).collect();
+        let deleted = artifact_repository::delete_many(pool, &ids).await?;
+        tracing::info!(
+            "Artifact garbage collection deleted {} artifacts ({} bytes)",
+            deleted,
+            expired_bytes
+        );
+    }
+
+    Ok(ArtifactGcReport {
+        dry_run,
+        expired,
+        expired_bytes,
+        usage,
+    })
+}
+
+async fn ensure_job_exists(pool: &PgPool, job_id: Uuid) -> Result<()> {
+    job_repository::find_by_id(pool, job_id)
+        .await?
+        .ok_or(ArtifactError::JobNotFound(job_id))?;
+    Ok(())
+}
+
+// =============================================================================
+// Validation
+// =============================================================================
+
+fn validate_artifact_name(name: &str) -> Result<()> {
+    if name.trim().is_empty() {
+        return Err(ArtifactError::ValidationError(
+            "Artifact name cannot be empty".to_string(),
+        ));
+    }
+
+    if name.len() > 255 {
+        return Err(ArtifactError::ValidationError(
+            "Artifact name is too long (max 255 characters)".to_string(),
+        ));
+    }
+
+    // Names end up as file names on download, keep them to a single path component
+    if name.contains(['/', '\\'])
+        || name == "."
+        || name == ".."
+        || name.chars().any(char::is_control)
+    {
+        return Err(ArtifactError::ValidationError(format!(
+            "Invalid artifact name '{}'",
+            name
+        )));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_artifact_name_valid() {
+        assert!(validate_artifact_name("build.tar.gz").is_ok());
+        assert!(validate_artifact_name("report-2024_01.html").is_ok());
+    }
+
+    #[test]
+    fn test_validate_artifact_name_rejects_paths() {
+        for name in [
+            "",
+            "  ",
+            "../secret",
+            "dir/file",
+            "dir\\file",
+            "..",
+            "bad\nname",
+        ] {
+            assert!(
+                matches!(
+                    validate_artifact_name(name),
+                    Err(ArtifactError::ValidationError(_))
+                ),
+                "expected '{}' to be rejected",
+                name
+            );
+        }
+    }
+}
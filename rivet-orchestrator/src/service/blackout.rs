@@ -0,0 +1,235 @@
+//! Blackout Service
+//!
+//! Business logic for blackout windows: periods during which the jobs of a
+//! pipeline, or the jobs deploying to an environment, are deferred. Deferred
+//! jobs stay queued and are not scheduled until the window closes (see
+//! `scheduling`), unless they were launched to override blackouts.
+
+use rivet_core::domain::blackout::BlackoutWindow;
+use rivet_core::dto::blackout::CreateBlackoutWindow;
+use rivet_core::dto::validation::FieldError;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::repository::{blackout_repository, pipeline_repository};
+
+/// Service error type
+#[derive(Debug)]
+pub enum BlackoutError {
+    NotFound(Uuid),
+    PipelineNotFound(Uuid),
+    InvalidFields(Vec<FieldError>),
+    DatabaseError(sqlx::Error),
+}
+
+impl From<sqlx::Error> for BlackoutError {
+    fn from(err: sqlx::Error) -> Self {
+        BlackoutError::DatabaseError(err)
+    }
+}
+
+pub type Result<T> = std::result::Result<T, BlackoutError>;
+
+/// Add a blackout window to a project
+pub async fn create_window(
+    pool: &PgPool,
+    project_id: Uuid,
+    mut req: CreateBlackoutWindow,
+) -> Result<BlackoutWindow> {
+    req.environment = req.environment.map(|name| name.trim().to_string());
+    validate_create_request(&req, chrono::Utc::now())?;
+
+    if let Some(pipeline_id) = req.pipeline_id {
+        pipeline_repository::find_by_id(pool, pipeline_id)
+            .await?
+            .filter(|pipeline| pipeline.project_id == project_id)
+            .ok_or(BlackoutError::PipelineNotFound(pipeline_id))?;
+    }
+
+    let window = blackout_repository::create(pool, project_id, req).await?;
+
+    tracing::info!(
+        "Blackout window {} added for {} from {} until {}",
+        window.id,
+        describe_target(&window),
+        window.starts_at,
+        window.ends_at
+    );
+
+    Ok(window)
+}
+
+/// List the blackout windows of a project that have not ended
+pub async fn list_windows(pool: &PgPool, project_id: Uuid) -> Result<Vec<BlackoutWindow>> {
+    Ok(blackout_repository::list_by_project(pool, project_id).await?)
+}
+
+/// Remove a blackout window from a project
+///
+/// Jobs it deferred become ready as soon as no other window defers them.
+pub async fn delete_window(pool: &PgPool, project_id: Uuid, id: Uuid) -> Result<()> {
+    if !blackout_repository::delete(pool, project_id, id).await? {
+        return Err(BlackoutError::NotFound(id));
+    }
+
+    tracing::info!("Blackout window {} removed", id);
+    Ok(())
+}
+
+/// What a blackout window applies to, for messages
+pub fn describe_target(window: &BlackoutWindow) -> String {
+    match (&window.pipeline_id, &window.environment) {
+        (Some(pipeline_id), _) => format!("pipeline {}", pipeline_id),
+        (None, Some(environment)) => format!("environment '{}'", environment),
+        (None, None) => "nothing".to_string(),
+    }
+}
+
+// =============================================================================
+// Validation
+// =============================================================================
+
+fn validate_create_request(
+    req: &CreateBlackoutWindow,
+    now: chrono::DateTime<chrono::Utc>,
+) -> Result<()> {
+    let mut errors = Vec::new();
+    match (&req.pipeline_id, &req.environment) {
+        (Some(_), Some(_)) | (None, None) => errors.push(FieldError::new(
+            "pipeline_id",
+            "Exactly one of pipeline_id and environment must be set",
+        )),
+        (None, Some(environment)) if environment.is_empty() || environment.len() > 255 => errors
+            .push(FieldError::new(
+                "environment",
+                "Must be between 1 and 255 characters",
+            )),
+        _ => {}
+    }
+    if req.ends_at <= now {
+        errors.push(FieldError::new("ends_at", "Must be in the future"));
+    }
+    if let Some(starts_at) = req.starts_at
+        && starts_at >= req.ends_at
+    {
+        errors.push(FieldError::new("starts_at", "Must be before ends_at"));
+    }
+
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(BlackoutError::InvalidFields(errors))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{DateTime, Utc};
+
+    fn utc(time: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(time)
+            .unwrap()
+            .with_timezone(&Utc)
+    }
+
+    fn window(starts_at: Option<&str>, ends_at: &str) -> CreateBlackoutWindow {
+        CreateBlackoutWindow {
+            pipeline_id: None,
+            environment: Some("prod".to_string()),
+            starts_at: starts_at.map(utc),
+            ends_at: utc(ends_at),
+            reason: None,
+        }
+    }
+
+    fn invalid_fields(req: &CreateBlackoutWindow, now: DateTime<Utc>) -> Vec<String> {
+        match validate_create_request(req, now) {
+            Err(BlackoutError::InvalidFields(errors)) => {
+                errors.into_iter().map(|e| e.field).collect()
+            }
+            other => panic!("expected invalid fields, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_rejects_inverted_and_past_windows() {
+        let now = utc("2026-10-16T12:00:00Z");
+        assert!(validate_create_request(&window(None, "2026-10-16T13:00:00Z"), now).is_ok());
+        assert!(
+            validate_create_request(
+                &window(Some("2026-10-16T08:00:00Z"), "2026-10-16T13:00:00Z"),
+                now
+            )
+            .is_ok()
+        );
+
+        assert_eq!(
+            invalid_fields(
+                &window(Some("2026-10-16T14:00:00Z"), "2026-10-16T13:00:00Z"),
+                now
+            ),
+            ["starts_at"]
+        );
+        assert_eq!(
+            invalid_fields(
+                &window(Some("2026-10-16T13:00:00Z"), "2026-10-16T13:00:00Z"),
+                now
+            ),
+            ["starts_at"]
+        );
+        assert_eq!(
+            invalid_fields(&window(None, "2026-10-16T12:00:00Z"), now),
+            ["ends_at"]
+        );
+    }
+
+    #[test]
+    fn test_compares_instants_across_time_zones() {
+        let now = utc("2026-10-16T12:00:00Z");
+
+        // 14:00 in UTC+2 is 12:00 UTC, before 13:00 UTC despite its later clock time
+        assert!(
+            validate_create_request(
+                &window(Some("2026-10-16T14:00:00+02:00"), "2026-10-16T13:00:00Z"),
+                now
+            )
+            .is_ok()
+        );
+        // 13:00 in UTC+2 has already passed
+        assert_eq!(
+            invalid_fields(&window(None, "2026-10-16T13:00:00+02:00"), now),
+            ["ends_at"]
+        );
+        // 09:00 in UTC-5 is 14:00 UTC, after 13:00 UTC
+        assert_eq!(
+            invalid_fields(
+                &window(Some("2026-10-16T09:00:00-05:00"), "2026-10-16T13:00:00Z"),
+                now
+            ),
+            ["starts_at"]
+        );
+    }
+
+    #[test]
+    fn test_requires_exactly_one_target() {
+        let now = utc("2026-10-16T12:00:00Z");
+        let both = CreateBlackoutWindow {
+            pipeline_id: Some(Uuid::new_v4()),
+            ..window(None, "2026-10-16T13:00:00Z")
+        };
+        assert_eq!(invalid_fields(&both, now), ["pipeline_id"]);
+
+        let neither = CreateBlackoutWindow {
+            environment: None,
+            ..window(None, "2026-10-16T13:00:00Z")
+        };
+        assert_eq!(invalid_fields(&neither, now), ["pipeline_id"]);
+
+        let unnamed = CreateBlackoutWindow {
+            environment: Some(String::new()),
+            ..window(None, "2026-10-16T13:00:00Z")
+        };
+        assert_eq!(invalid_fields(&unnamed, now), ["environment"]);
+    }
+}
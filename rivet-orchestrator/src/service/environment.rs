@@ -0,0 +1,321 @@
+//! Environment Service
+//!
+//! Business logic for environments: what is deployed to them, the approvals
+//! a job needs before its stages may deploy to them, and the lock letting
+//! one job deploy to an environment at a time. Queued jobs lacking approvals,
+//! or locked out of an environment, are not scheduled (see `scheduling`).
+
+use rivet_core::domain::environment::Deployment;
+use rivet_core::domain::job::JobStatus;
+use rivet_core::dto::environment::{
+    ApproveJob, DEFAULT_DEPLOYMENT_LIMIT, DeploymentQuery, EnvironmentLockStatus,
+    EnvironmentSummary, JobApprovals, MAX_REQUIRED_APPROVALS, SetEnvironment,
+};
+use rivet_core::dto::pagination::MAX_PAGE_SIZE;
+use rivet_core::dto::validation::FieldError;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::repository::{environment_repository, job_repository};
+
+/// Service error type
+#[derive(Debug)]
+pub enum EnvironmentError {
+    NotFound(String),
+    JobNotFound(Uuid),
+    ValidationError(String),
+    InvalidFields(Vec<FieldError>),
+    /// The job can no longer be approved
+    InvalidState(String),
+    DatabaseError(sqlx::Error),
+}
+
+impl From<sqlx::Error> for EnvironmentError {
+    fn from(err: sqlx::Error) -> Self {
+        EnvironmentError::DatabaseError(err)
+    }
+}
+
+pub type Result<T> = std::result::Result<T, EnvironmentError>;
+
+/// List the environments of a project with what is deployed to them
+pub async fn list_environments(pool: &PgPool, project_id: Uuid) -> Result<Vec<EnvironmentSummary>> {
+    let environments = environment_repository::list_by_project(pool, project_id).await?;
+    let mut current =
+        environment_repository::find_latest_deployments(pool, project_id, true).await?;
+    let mut latest =
+        environment_repository::find_latest_deployments(pool, project_id, false).await?;
+    let mut locks = environment_repository::find_locks(pool, project_id).await?;
+
+    Ok(environments
+        .into_iter()
+        .map(|environment| EnvironmentSummary {
+            current: current.remove(&environment.name),
+            latest: latest.remove(&environment.name),
+            lock: locks.remove(&environment.name),
+            environment,
+        })
+        .collect())
+}
+
+/// Get an environment of a project with what is deployed to it
+pub async fn get_environment(
+    pool: &PgPool,
+    project_id: Uuid,
+    name: &str,
+) -> Result<EnvironmentSummary> {
+    let environment = environment_repository::find(pool, project_id, name)
+        .await?
+        .ok_or_else(|| EnvironmentError::NotFound(name.to_string()))?;
+    let current = environment_repository::find_latest_deployments(pool, project_id, true)
+        .await?
+        .remove(name);
+    let latest = environment_repository::find_latest_deployments(pool, project_id, false)
+        .await?
+        .remove(name);
+    let lock = environment_repository::find_locks(pool, project_id)
+        .await?
+        .remove(name);
+
+    Ok(EnvironmentSummary {
+        environment,
+        current,
+        latest,
+        lock,
+    })
+}
+
+/// Create or change an environment of a project
+pub async fn set_environment(
+    pool: &PgPool,
+    project_id: Uuid,
+    name: &str,
+    req: SetEnvironment,
+) -> Result<EnvironmentSummary> {
+    validate_set_request(name, &req)?;
+
+    environment_repository::upsert(pool, project_id, name, &req).await?;
+
+    tracing::info!(
+        "Environment set: {} ({} approvals required)",
+        name,
+        req.required_approvals
+    );
+
+    get_environment(pool, project_id, name).await
+}
+
+/// Delete an environment of a project with its deployment history
+pub async fn delete_environment(pool: &PgPool, project_id: Uuid, name: &str) -> Result<()> {
+    if !environment_repository::delete(pool, project_id, name).await? {
+        return Err(EnvironmentError::NotFound(name.to_string()));
+    }
+
+    tracing::info!("Environment deleted: {}", name);
+    Ok(())
+}
+
+/// List the deployments to an environment of a project, newest first
+pub async fn list_deployments(
+    pool: &PgPool,
+    project_id: Uuid,
+    name: &str,
+    query: DeploymentQuery,
+) -> Result<Vec<Deployment>> {
+    let limit = query.limit.unwrap_or(DEFAULT_DEPLOYMENT_LIMIT);
+    if limit == 0 || limit > MAX_PAGE_SIZE {
+        return Err(EnvironmentError::ValidationError(format!(
+            "limit must be between 1 and {}",
+            MAX_PAGE_SIZE
+        )));
+    }
+
+    environment_repository::find(pool, project_id, name)
+        .await?
+        .ok_or_else(|| EnvironmentError::NotFound(name.to_string()))?;
+
+    Ok(environment_repository::find_deployments(pool, project_id, name, limit).await?)
+}
+
+/// Lock of an environment of a project, with the jobs waiting for it
+pub async fn get_lock(
+    pool: &PgPool,
+    project_id: Uuid,
+    name: &str,
+) -> Result<EnvironmentLockStatus> {
+    let lock = environment_repository::find_locks(pool, project_id)
+        .await?
+        .remove(name);
+    let waiting = environment_repository::find_waiting_jobs(pool, project_id, name).await?;
+
+    Ok(EnvironmentLockStatus {
+        environment: name.to_string(),
+        lock,
+        waiting,
+    })
+}
+
+/// Release the lock of an environment of a project, whoever holds it
+///
+/// The holder keeps running; the jobs it blocked are put back in the queue.
+///
+/// # Returns
+/// `(job_id, pipeline_id)` of the jobs put back in the queue
+pub async fn force_release_lock(
+    pool: &PgPool,
+    project_id: Uuid,
+    name: &str,
+) -> Result<Vec<(Uuid, Uuid)>> {
+    let Some(holder) = environment_repository::force_release_lock(pool, project_id, name).await?
+    else {
+        return Err(EnvironmentError::InvalidState(format!(
+            "Environment '{}' is not locked",
+            name
+        )));
+    };
+
+    let unblocked = environment_repository::unblock_jobs(pool).await?;
+
+    tracing::warn!(
+        "Lock of environment {} force-released from job {} ({} jobs unblocked)",
+        name,
+        holder,
+        unblocked.len()
+    );
+
+    Ok(unblocked)
+}
+
+/// Bring the `Blocked` state of jobs in line with the environment locks
+///
+/// Locks are released as their holders finish, and taken over once stale
+/// (holders that were cancelled, dead-lettered or otherwise finished without
+/// releasing them). Run periodically to catch up with both.
+///
+/// # Returns
+/// `(job_id, pipeline_id)` of the jobs put back in the queue
+pub async fn sync_blocked_jobs(pool: &PgPool) -> Result<Vec<(Uuid, Uuid)>> {
+    environment_repository::block_locked_out_jobs(pool).await?;
+    Ok(environment_repository::unblock_jobs(pool).await?)
+}
+
+/// Approvals of a job, for each environment it deploys to
+pub async fn job_approvals(pool: &PgPool, job_id: Uuid) -> Result<Vec<JobApprovals>> {
+    job_repository::find_by_id(pool, job_id)
+        .await?
+        .ok_or(EnvironmentError::JobNotFound(job_id))?;
+
+    let required = environment_repository::find_required_approvals(pool, job_id).await?;
+    let mut approvals = environment_repository::find_approvals(pool, job_id).await?;
+
+    Ok(required
+        .into_iter()
+        .map(|(environment, required)| {
+            let (given, rest) = approvals
+                .drain(..)
+                .partition(|approval| approval.environment == environment);
+            approvals = rest;
+            JobApprovals {
+                environment,
+                required,
+                approvals: given,
+            }
+        })
+        .collect())
+}
+
+/// Approve a queued or blocked job's deployments to an environment
+///
+/// # Returns
+/// The approvals of the job, for each environment it deploys to
+pub async fn approve_job(
+    pool: &PgPool,
+    job_id: Uuid,
+    req: ApproveJob,
+) -> Result<Vec<JobApprovals>> {
+    validate_approve_request(&req)?;
+
+    let job = job_repository::find_by_id(pool, job_id)
+        .await?
+        .ok_or(EnvironmentError::JobNotFound(job_id))?;
+    if !matches!(job.status, JobStatus::Queued | JobStatus::Blocked) {
+        return Err(EnvironmentError::InvalidState(format!(
+            "Job {} is {:?}, only queued or blocked jobs can be approved",
+            job_id, job.status
+        )));
+    }
+
+    let required = environment_repository::find_required_approvals(pool, job_id).await?;
+    if !required
+        .iter()
+        .any(|(environment, _)| environment == &req.environment)
+    {
+        return Err(EnvironmentError::ValidationError(format!(
+            "Job {} does not deploy to environment '{}'",
+            job_id, req.environment
+        )));
+    }
+
+    environment_repository::approve(
+        pool,
+        job_id,
+        &req.environment,
+        req.approver.trim(),
+        req.comment.as_deref(),
+    )
+    .await?;
+
+    tracing::info!(
+        "Job {} approved for {} by {}",
+        job_id,
+        req.environment,
+        req.approver.trim()
+    );
+
+    job_approvals(pool, job_id).await
+}
+
+// =============================================================================
+// Validation
+// =============================================================================
+
+fn validate_set_request(name: &str, req: &SetEnvironment) -> Result<()> {
+    let mut errors = Vec::new();
+    if name.trim().is_empty() || name.len() > 255 {
+        errors.push(FieldError::new(
+            "name",
+            "Must be between 1 and 255 characters",
+        ));
+    }
+    if req.required_approvals > MAX_REQUIRED_APPROVALS {
+        errors.push(FieldError::new(
+            "required_approvals",
+            format!("Must be at most {}", MAX_REQUIRED_APPROVALS),
+        ));
+    }
+
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(EnvironmentError::InvalidFields(errors))
+    }
+}
+
+fn validate_approve_request(req: &ApproveJob) -> Result<()> {
+    let mut errors = Vec::new();
+    if req.environment.trim().is_empty() {
+        errors.push(FieldError::new("environment", "Must not be empty"));
+    }
+    if req.approver.trim().is_empty() || req.approver.len() > 255 {
+        errors.push(FieldError::new(
+            "approver",
+            "Must be between 1 and 255 characters",
+        ));
+    }
+
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(EnvironmentError::InvalidFields(errors))
+    }
+}
@@ -2,14 +2,51 @@
 //!
 //! Business logic for job management and lifecycle.
 
-use rivet_core::domain::job::{Job, JobResult, JobStatus};
-use rivet_core::domain::pipeline::Pipeline;
-use rivet_core::dto::job::CreateJob;
-use rivet_lua::{create_sandbox, parse_pipeline_definition};
+use rivet_core::domain::environment::{DeploymentStatus, EnvironmentLock};
+use rivet_core::domain::job::{
+    CommitRef, Job, JobResult, JobStatus, MAX_JOB_LABELS, StageRun, validate_label,
+};
+use rivet_core::domain::pipeline::{InputType, Pipeline, Tag};
+use rivet_core::dto::event::Event;
+use rivet_core::dto::job::{
+    CreateJob, DEFAULT_JOB_LEASE_TTL, InputFile, JobExecutionInfo, JobFilter, JobLease, JobPlan,
+    MASKED_INPUT, MAX_JOB_LEASE_TTL, MAX_LAUNCH_BATCH, RenewLeaseRequest, StageUpdate,
+};
+use rivet_core::dto::pagination::MAX_PAGE_SIZE;
+use rivet_core::dto::pipeline::PipelineDefaults;
+use rivet_core::dto::validation::FieldError;
+use rivet_core::telemetry;
+use rivet_lua::definition::InputDefinition;
+use rivet_lua::input::register_input_module;
+use rivet_lua::{create_sandbox, parse_pipeline_definition, plan_stages};
 use sqlx::PgPool;
+use std::time::Duration;
+use tokio::sync::broadcast;
+use tokio::time::Instant;
 use uuid::Uuid;
 
-use crate::repository::{job_repository, pipeline_repository};
+use crate::events::EventBus;
+use crate::repository::{
+    blackout_repository, environment_repository, job_repository, pipeline_repository,
+    runner_repository, secret_repository, stage_repository,
+};
+use crate::scheduling::SchedulingPolicy;
+use crate::service::blackout_service;
+
+/// Highest `max_retries` a pipeline may ask for
+pub const MAX_JOB_RETRIES: u32 = 10;
+
+/// Delay before the first retry of a failed job, doubled on every attempt
+pub const RETRY_BACKOFF_BASE: Duration = Duration::from_secs(10);
+
+/// Longest delay between two attempts of a failed job
+pub const RETRY_BACKOFF_MAX: Duration = Duration::from_secs(600);
+
+/// Attempts a job gets before it is dead-lettered when its lease keeps expiring
+pub const MAX_LEASE_ATTEMPTS: u32 = 3;
+
+/// How often a long-polling runner re-checks for jobs queued by other orchestrators
+pub const NEXT_JOB_RECHECK_INTERVAL: Duration = Duration::from_secs(5);
 
 /// Service error type
 #[derive(Debug)]
@@ -17,7 +54,12 @@ pub enum JobError {
     NotFound(Uuid),
     PipelineNotFound(Uuid),
     InvalidState(String),
+    /// The caller's claim on the job is no longer current: the job was
+    /// requeued, claimed again or already completed
+    StaleClaim(Uuid),
     ValidationError(String),
+    /// Request fields that failed validation, each with its reason
+    InvalidFields(Vec<FieldError>),
     DatabaseError(sqlx::Error),
 }
 
@@ -29,10 +71,27 @@ impl From<sqlx::Error> for JobError {
 
 /// Create and schedule a new job
 pub async fn launch_job(pool: &PgPool, req: CreateJob) -> Result<Job, JobError> {
+    launch(pool, req, None).await
+}
+
+/// Create and schedule a new job on behalf of a cron schedule
+///
+/// As [`launch_job`], the job recording the schedule so that its scripts
+/// can tell what launched it.
+pub async fn launch_scheduled_job(
+    pool: &PgPool,
+    req: CreateJob,
+    schedule_id: Uuid,
+) -> Result<Job, JobError> {
+    launch(pool, req, Some(schedule_id)).await
+}
+
+async fn launch(pool: &PgPool, req: CreateJob, schedule_id: Option<Uuid>) -> Result<Job, JobError> {
     // Verify pipeline exists
     let pipeline = pipeline_repository::find_by_id(pool, req.pipeline_id)
         .await?
         .ok_or(JobError::PipelineNotFound(req.pipeline_id))?;
+    ensure_active(&pipeline)?;
 
     // Parse pipeline definition to validate and enrich parameters
     let lua = create_sandbox()
@@ -41,23 +100,171 @@ pub async fn launch_job(pool: &PgPool, req: CreateJob) -> Result<Job, JobError>
     let definition = parse_pipeline_definition(&lua, &pipeline.script)
         .map_err(|e| JobError::ValidationError(format!("Failed to parse pipeline: {}", e)))?;
 
-    // Validate and enrich parameters with defaults
-    let enriched_params = validate_and_enrich_parameters(&definition, req.parameters)?;
+    let max_attempts = max_attempts(&definition)?;
+
+    if let Some(commit) = &req.commit {
+        let errors = validate_commit(commit);
+        if !errors.is_empty() {
+            return Err(JobError::InvalidFields(prefix_fields("commit", errors)));
+        }
+    }
+
+    let errors = validate_labels(&req.labels);
+    if !errors.is_empty() {
+        return Err(JobError::InvalidFields(errors));
+    }
+
+    ensure_capable_runner(pool, &pipeline).await?;
+
+    // Merge the orchestrator's defaults, then validate and apply the script's
+    let parameters = pipeline_defaults(pool, pipeline.id)
+        .await?
+        .apply(req.parameters);
+    let enriched_params = validate_and_enrich_parameters(&definition.inputs, parameters)
+        .map_err(|errors| JobError::InvalidFields(prefix_fields("parameters", errors)))?;
+    let (enriched_params, secret_params) =
+        split_secret_parameters(&definition.inputs, enriched_params);
 
     // Create enriched request
     let enriched_req = CreateJob {
         pipeline_id: req.pipeline_id,
         parameters: enriched_params,
+        plan: false,
+        commit: req.commit,
+        override_blackout: req.override_blackout,
+        labels: req.labels,
     };
 
     // Create job in database
-    let job = job_repository::create(pool, enriched_req).await?;
+    let traceparent = telemetry::traceparent(&tracing::Span::current());
+    let mut job = job_repository::create(
+        pool,
+        enriched_req,
+        &secret_params,
+        max_attempts,
+        traceparent.as_deref(),
+        schedule_id,
+    )
+    .await?;
 
     tracing::info!("Job created: {} for pipeline: {}", job.id, job.pipeline_id);
 
+    if deploys(&definition) {
+        block_locked_out(pool, std::slice::from_mut(&mut job)).await?;
+    }
+
     Ok(job)
 }
 
+/// Work out which stages a job would run, without queueing it
+///
+/// The request is validated as by [`launch_job`], so a plan is only
+/// answered for a job that could be launched. Stage conditions are then
+/// evaluated against the parameters, input defaults applied.
+pub async fn plan_job(pool: &PgPool, req: CreateJob) -> Result<JobPlan, JobError> {
+    let pipeline = pipeline_repository::find_by_id(pool, req.pipeline_id)
+        .await?
+        .ok_or(JobError::PipelineNotFound(req.pipeline_id))?;
+    ensure_active(&pipeline)?;
+
+    let lua = create_sandbox()
+        .map_err(|e| JobError::ValidationError(format!("Failed to create sandbox: {}", e)))?;
+
+    let definition = parse_pipeline_definition(&lua, &pipeline.script)
+        .map_err(|e| JobError::ValidationError(format!("Failed to parse pipeline: {}", e)))?;
+
+    max_attempts(&definition)?;
+
+    ensure_capable_runner(pool, &pipeline).await?;
+
+    let parameters = pipeline_defaults(pool, pipeline.id)
+        .await?
+        .apply(req.parameters);
+    let parameters = validate_and_enrich_parameters(&definition.inputs, parameters)
+        .map_err(|errors| JobError::InvalidFields(prefix_fields("parameters", errors)))?;
+
+    // Conditions read the parameters through the input module, as on the runner
+    register_input_module(&lua, parameters.clone())
+        .map_err(|e| JobError::ValidationError(format!("Failed to register inputs: {}", e)))?;
+
+    let stages = plan_stages(&definition);
+    let (parameters, _) = split_secret_parameters(&definition.inputs, parameters);
+
+    Ok(JobPlan {
+        pipeline_id: req.pipeline_id,
+        parameters,
+        stages,
+    })
+}
+
+/// Create and schedule one job per parameter set, all or nothing
+///
+/// Every parameter set is validated before any job is created; the jobs are
+/// then inserted in a single transaction.
+pub async fn launch_job_batch(
+    pool: &PgPool,
+    pipeline_id: Uuid,
+    parameter_sets: Vec<std::collections::HashMap<String, serde_json::Value>>,
+) -> Result<Vec<Job>, JobError> {
+    if parameter_sets.is_empty() || parameter_sets.len() > MAX_LAUNCH_BATCH {
+        return Err(JobError::ValidationError(format!(
+            "A batch must contain between 1 and {} parameter sets (got {})",
+            MAX_LAUNCH_BATCH,
+            parameter_sets.len()
+        )));
+    }
+
+    let pipeline = pipeline_repository::find_by_id(pool, pipeline_id)
+        .await?
+        .ok_or(JobError::PipelineNotFound(pipeline_id))?;
+    ensure_active(&pipeline)?;
+
+    let lua = create_sandbox()
+        .map_err(|e| JobError::ValidationError(format!("Failed to create sandbox: {}", e)))?;
+
+    let definition = parse_pipeline_definition(&lua, &pipeline.script)
+        .map_err(|e| JobError::ValidationError(format!("Failed to parse pipeline: {}", e)))?;
+
+    let max_attempts = max_attempts(&definition)?;
+
+    ensure_capable_runner(pool, &pipeline).await?;
+
+    let defaults = pipeline_defaults(pool, pipeline_id).await?;
+
+    // Validate every set first so all invalid fields are reported at once
+    let mut enriched_sets = Vec::with_capacity(parameter_sets.len());
+    let mut errors = Vec::new();
+    for (index, parameters) in parameter_sets.into_iter().enumerate() {
+        match validate_and_enrich_parameters(&definition.inputs, defaults.apply(parameters)) {
+            Ok(parameters) => {
+                enriched_sets.push(split_secret_parameters(&definition.inputs, parameters))
+            }
+            Err(set_errors) => errors.extend(prefix_fields(&format!("[{}]", index), set_errors)),
+        }
+    }
+    if !errors.is_empty() {
+        return Err(JobError::InvalidFields(errors));
+    }
+
+    let traceparent = telemetry::traceparent(&tracing::Span::current());
+    let mut jobs = job_repository::create_many(
+        pool,
+        pipeline_id,
+        enriched_sets,
+        max_attempts,
+        traceparent.as_deref(),
+    )
+    .await?;
+
+    tracing::info!("{} jobs created for pipeline: {}", jobs.len(), pipeline_id);
+
+    if deploys(&definition) {
+        block_locked_out(pool, &mut jobs).await?;
+    }
+
+    Ok(jobs)
+}
+
 /// Get a job by ID
 pub async fn get_job(pool: &PgPool, id: Uuid) -> Result<Job, JobError> {
     let job = job_repository::find_by_id(pool, id)
@@ -73,12 +280,45 @@ pub async fn list_jobs_by_status(pool: &PgPool, status: JobStatus) -> Result<Vec
     Ok(jobs)
 }
 
+/// List queued jobs in the order the scheduling policy serves them
+pub async fn list_queued_jobs(
+    pool: &PgPool,
+    policy: SchedulingPolicy,
+) -> Result<Vec<Job>, JobError> {
+    let jobs = job_repository::find_queued(pool, policy).await?;
+    Ok(jobs)
+}
+
 /// List all jobs
 pub async fn list_all_jobs(pool: &PgPool) -> Result<Vec<Job>, JobError> {
     let jobs = job_repository::list_all(pool).await?;
     Ok(jobs)
 }
 
+/// List all jobs of a project
+pub async fn list_jobs_by_project(pool: &PgPool, project_id: Uuid) -> Result<Vec<Job>, JobError> {
+    let jobs = job_repository::find_by_project(pool, project_id).await?;
+    Ok(jobs)
+}
+
+/// List one page of jobs matching `filter`, newest first
+pub async fn list_jobs_page(
+    pool: &PgPool,
+    filter: &JobFilter,
+    limit: u32,
+    offset: u32,
+) -> Result<Vec<Job>, JobError> {
+    if limit == 0 || limit > MAX_PAGE_SIZE {
+        return Err(JobError::ValidationError(format!(
+            "limit must be between 1 and {}",
+            MAX_PAGE_SIZE
+        )));
+    }
+
+    let jobs = job_repository::list_page(pool, filter, limit as i64, offset as i64).await?;
+    Ok(jobs)
+}
+
 /// List jobs by pipeline
 pub async fn list_jobs_by_pipeline(pool: &PgPool, pipeline_id: Uuid) -> Result<Vec<Job>, JobError> {
     // Verify pipeline exists
@@ -90,6 +330,23 @@ pub async fn list_jobs_by_pipeline(pool: &PgPool, pipeline_id: Uuid) -> Result<V
     Ok(jobs)
 }
 
+/// Get the latest job of a pipeline, optionally restricted to a branch
+///
+/// Returns `None` when the pipeline has never run (on that branch).
+pub async fn get_latest_job_for_pipeline(
+    pool: &PgPool,
+    pipeline_id: Uuid,
+    branch: Option<&str>,
+) -> Result<Option<Job>, JobError> {
+    // Verify pipeline exists
+    let _pipeline = pipeline_repository::find_by_id(pool, pipeline_id)
+        .await?
+        .ok_or(JobError::PipelineNotFound(pipeline_id))?;
+
+    let job = job_repository::find_latest_by_pipeline(pool, pipeline_id, branch).await?;
+    Ok(job)
+}
+
 /// Reserve a job for execution by a runner
 pub async fn reserve_job_for_execution(
     pool: &PgPool,
@@ -114,9 +371,63 @@ pub async fn reserve_job_for_execution(
         .await?
         .ok_or(JobError::PipelineNotFound(job.pipeline_id))?;
 
+    // Only runners providing what the pipeline requires may run it
+    if !pipeline.tags.is_empty() || !pipeline.plugins.is_empty() {
+        let capable = runner_repository::find_by_id(pool, &runner_id)
+            .await?
+            .is_some_and(|runner| runner.provides(&pipeline.tags, &pipeline.plugins));
+        if !capable {
+            return Err(JobError::InvalidState(format!(
+                "Runner {} does not provide {}",
+                runner_id,
+                describe_requirements(&pipeline.tags, &pipeline.plugins)
+            )));
+        }
+    }
+
+    // Jobs deploying to environments that require approvals wait for them
+    let unapproved = unapproved_environments(pool, job_id).await?;
+    if !unapproved.is_empty() {
+        return Err(JobError::InvalidState(format!(
+            "Job {} awaits approval to deploy to {}",
+            job_id,
+            unapproved.join(", ")
+        )));
+    }
+
+    // Jobs wait for the blackout windows applying to them to close
+    if let Some(window) = blackout_repository::find_active_for_job(pool, job_id)
+        .await?
+        .first()
+    {
+        return Err(JobError::InvalidState(format!(
+            "Job {} is deferred by a blackout window of {} until {}",
+            job_id,
+            blackout_service::describe_target(window),
+            window.ends_at
+        )));
+    }
+
+    // Only one job deploying to an environment runs at a time
+    let blocking = environment_repository::find_blocking_locks(pool, job_id).await?;
+    if !blocking.is_empty() {
+        return Err(JobError::InvalidState(format!(
+            "Job {} waits for {}",
+            job_id,
+            describe_locks(&blocking)
+        )));
+    }
+
     // Update job status to Running
     job_repository::update_status_to_running(pool, job_id, runner_id).await?;
 
+    if !lock_environments(pool, job_id).await? {
+        return Err(JobError::InvalidState(format!(
+            "Job {} lost an environment lock to another job",
+            job_id
+        )));
+    }
+
     tracing::info!("Job {} reserved and started", job_id);
 
     // Return updated job
@@ -127,13 +438,380 @@ pub async fn reserve_job_for_execution(
     Ok((updated_job, pipeline))
 }
 
+/// Build what a runner needs to execute a reserved job
+///
+/// Includes the secrets visible to the job's pipeline, resolved so that
+/// pipeline-scoped secrets shadow project-wide ones, the values of its
+/// secret inputs in place of their masks, and the pipeline's default
+/// container image.
+pub async fn execution_info(
+    pool: &PgPool,
+    job: Job,
+    pipeline: Pipeline,
+) -> Result<JobExecutionInfo, JobError> {
+    let secrets =
+        secret_repository::find_values_for_pipeline(pool, pipeline.project_id, pipeline.id).await?;
+
+    let secret_parameters = job_repository::find_secret_parameters(pool, job.id).await?;
+    let mut secret_inputs: Vec<String> = secret_parameters.keys().cloned().collect();
+    secret_inputs.sort();
+    let mut parameters = job.parameters;
+    parameters.extend(secret_parameters);
+
+    let traceparent = job_repository::find_traceparent(pool, job.id).await?;
+    let schedule = job_repository::find_schedule(pool, job.id).await?;
+    let claim_token = job_repository::find_claim_token(pool, job.id)
+        .await?
+        .map(|token| token as u64);
+
+    let skip_stages = match job.resumed_from {
+        Some(original) => stage_repository::find_by_job(pool, original)
+            .await?
+            .into_iter()
+            .filter(|stage| stage.status.is_done())
+            .map(|stage| stage.name)
+            .collect(),
+        None => Vec::new(),
+    };
+
+    let ctx = match job.resumed_from {
+        Some(original) => job_repository::find_by_id(pool, original)
+            .await?
+            .and_then(|original| original.result?.output?.get("ctx").cloned()),
+        None => None,
+    };
+
+    let containers = container_settings(&pipeline);
+
+    Ok(JobExecutionInfo {
+        job_id: job.id,
+        pipeline_id: pipeline.id,
+        pipeline_name: pipeline.name,
+        number: job.number,
+        attempt: job.attempt,
+        commit: job.commit,
+        schedule,
+        pipeline_source: pipeline.script,
+        parameters,
+        secrets,
+        secret_inputs,
+        resumed_from: job.resumed_from,
+        skip_stages,
+        traceparent,
+        claim_token,
+        default_container: containers.default_container,
+        default_container_variants: containers.default_container_variants,
+        env: containers.env,
+        ctx,
+    })
+}
+
+/// Container settings a pipeline declares
+struct ContainerSettings {
+    /// Default container image
+    default_container: Option<String>,
+    /// Images of the default container by architecture
+    default_container_variants: std::collections::HashMap<String, String>,
+    /// Environment variables of the job's containers
+    env: std::collections::HashMap<String, String>,
+}
+
+/// Default container image a pipeline declares, its images by architecture
+/// and the environment variables of its containers
+///
+/// The script was validated when the pipeline was created; should it no
+/// longer parse, the runner's defaults are used and the runner reports
+/// the parse error.
+fn container_settings(pipeline: &Pipeline) -> ContainerSettings {
+    let definition = match create_sandbox() {
+        Ok(lua) => parse_pipeline_definition(&lua, &pipeline.script).map_err(|e| e.to_string()),
+        Err(e) => Err(e.to_string()),
+    };
+
+    match definition {
+        Ok(definition) => ContainerSettings {
+            default_container: definition.default_container,
+            default_container_variants: definition.default_container_variants.into_iter().collect(),
+            env: definition.env.into_iter().collect(),
+        },
+        Err(e) => {
+            tracing::warn!(
+                "Failed to read the container settings of pipeline {}: {}",
+                pipeline.id,
+                e
+            );
+            ContainerSettings {
+                default_container: None,
+                default_container_variants: Default::default(),
+                env: Default::default(),
+            }
+        }
+    }
+}
+
+/// Assign the next queued job to a runner, if any
+///
+/// The job is picked according to the scheduling `policy`.
+pub async fn claim_next_job(
+    pool: &PgPool,
+    runner_id: &str,
+    policy: SchedulingPolicy,
+) -> Result<Option<(Job, Pipeline)>, JobError> {
+    let lease_expires_at = chrono::Utc::now() + DEFAULT_JOB_LEASE_TTL;
+    let Some(job_id) =
+        job_repository::claim_next_queued(pool, runner_id, lease_expires_at, policy).await?
+    else {
+        return Ok(None);
+    };
+
+    if !lock_environments(pool, job_id).await? {
+        return Ok(None);
+    }
+
+    let job = job_repository::find_by_id(pool, job_id)
+        .await?
+        .ok_or(JobError::NotFound(job_id))?;
+
+    let pipeline = pipeline_repository::find_by_id(pool, job.pipeline_id)
+        .await?
+        .ok_or(JobError::PipelineNotFound(job.pipeline_id))?;
+
+    tracing::info!("Job {} assigned to runner {}", job_id, runner_id);
+
+    Ok(Some((job, pipeline)))
+}
+
+/// Wait up to `wait` for a job to assign to a runner (long polling)
+///
+/// Wakes up as soon as a job is queued on this orchestrator, and re-checks
+/// every `NEXT_JOB_RECHECK_INTERVAL` for jobs queued elsewhere.
+///
+/// # Returns
+/// The claimed job and its pipeline, `None` if nothing was queued in time
+pub async fn wait_for_next_job(
+    pool: &PgPool,
+    events: &EventBus,
+    runner_id: &str,
+    wait: Duration,
+    policy: SchedulingPolicy,
+) -> Result<Option<(Job, Pipeline)>, JobError> {
+    if runner_id.trim().is_empty() {
+        return Err(JobError::ValidationError(
+            "runner_id cannot be empty".to_string(),
+        ));
+    }
+
+    let deadline = Instant::now() + wait;
+    // Subscribe before the first attempt so a job queued in between still wakes us
+    let mut receiver = events.subscribe();
+
+    loop {
+        if let Some(assigned) = claim_next_job(pool, runner_id, policy).await? {
+            return Ok(Some(assigned));
+        }
+
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        if remaining.is_zero() {
+            return Ok(None);
+        }
+
+        let nap = remaining.min(NEXT_JOB_RECHECK_INTERVAL);
+        let _ = tokio::time::timeout(nap, job_queued(&mut receiver)).await;
+    }
+}
+
+/// Resolves on the next `JobQueued` event, or when events were missed
+async fn job_queued(receiver: &mut broadcast::Receiver<Event>) {
+    loop {
+        match receiver.recv().await {
+            Ok(Event::JobQueued { .. }) | Err(broadcast::error::RecvError::Lagged(_)) => return,
+            Ok(_) => continue,
+            // The bus lives as long as the server; fall back to periodic re-checks
+            Err(broadcast::error::RecvError::Closed) => std::future::pending().await,
+        }
+    }
+}
+
+/// Renew the lease a runner holds on a running job
+pub async fn renew_job_lease(
+    pool: &PgPool,
+    job_id: Uuid,
+    req: RenewLeaseRequest,
+) -> Result<JobLease, JobError> {
+    if req.runner_id.trim().is_empty() {
+        return Err(JobError::ValidationError(
+            "runner_id cannot be empty".to_string(),
+        ));
+    }
+
+    let ttl = req
+        .ttl_seconds
+        .map(Duration::from_secs)
+        .unwrap_or(DEFAULT_JOB_LEASE_TTL);
+    if ttl.is_zero() || ttl > MAX_JOB_LEASE_TTL {
+        return Err(JobError::ValidationError(format!(
+            "ttl_seconds must be between 1 and {}",
+            MAX_JOB_LEASE_TTL.as_secs()
+        )));
+    }
+
+    let expires_at = chrono::Utc::now() + ttl;
+    if !job_repository::renew_lease(pool, job_id, &req.runner_id, expires_at).await? {
+        let job = job_repository::find_by_id(pool, job_id)
+            .await?
+            .ok_or(JobError::NotFound(job_id))?;
+
+        return Err(JobError::InvalidState(format!(
+            "Job {} is not running on runner {} (current: {:?})",
+            job_id, req.runner_id, job.status
+        )));
+    }
+
+    Ok(JobLease {
+        job_id,
+        runner_id: req.runner_id,
+        expires_at,
+    })
+}
+
+/// Jobs affected by a sweep of expired leases, as `(job_id, pipeline_id)` pairs
+#[derive(Debug, Default)]
+pub struct ExpiredLeases {
+    /// Jobs put back in the queue for another attempt
+    pub requeued: Vec<(Uuid, Uuid)>,
+    /// Jobs whose lease expired on their last attempt
+    pub dead_lettered: Vec<(Uuid, Uuid)>,
+}
+
+/// Requeue running jobs whose runner stopped renewing their lease
+///
+/// A job whose lease expired `MAX_LEASE_ATTEMPTS` times (typically because
+/// it crashes or hangs every runner it lands on) is dead-lettered instead.
+pub async fn requeue_expired_jobs(pool: &PgPool) -> Result<ExpiredLeases, JobError> {
+    let dead_lettered =
+        job_repository::dead_letter_expired_leases(pool, MAX_LEASE_ATTEMPTS as i32).await?;
+    let requeued = job_repository::requeue_expired_leases(pool, MAX_LEASE_ATTEMPTS as i32).await?;
+
+    for (job_id, _) in &dead_lettered {
+        tracing::warn!(
+            "Lease on job {} expired on its last attempt, job dead-lettered",
+            job_id
+        );
+    }
+    for (job_id, _) in &requeued {
+        tracing::warn!("Lease on job {} expired, job requeued", job_id);
+    }
+
+    Ok(ExpiredLeases {
+        requeued,
+        dead_lettered,
+    })
+}
+
+/// Put a dead-lettered job back in the queue for a fresh set of attempts
+pub async fn requeue_dead_lettered_job(pool: &PgPool, job_id: Uuid) -> Result<Job, JobError> {
+    let job = job_repository::find_by_id(pool, job_id)
+        .await?
+        .ok_or(JobError::NotFound(job_id))?;
+
+    if !job_repository::requeue_dead_lettered(pool, job_id).await? {
+        return Err(JobError::InvalidState(format!(
+            "Job {} is not dead-lettered (current: {:?})",
+            job_id, job.status
+        )));
+    }
+
+    tracing::info!("Dead-lettered job {} requeued", job_id);
+
+    job_repository::find_by_id(pool, job_id)
+        .await?
+        .ok_or(JobError::NotFound(job_id))
+}
+
+/// Create a job resuming a job that did not succeed
+///
+/// The new job runs with the same parameters, starts with a copy of the
+/// original's artifacts and skips the stages that succeeded there. A runner
+/// that kept the original's workspace continues from it.
+pub async fn resume_job(pool: &PgPool, job_id: Uuid) -> Result<Job, JobError> {
+    let original = job_repository::find_by_id(pool, job_id)
+        .await?
+        .ok_or(JobError::NotFound(job_id))?;
+
+    if !is_resumable(original.status) {
+        return Err(JobError::InvalidState(format!(
+            "Job {} cannot be resumed (current: {:?})",
+            job_id, original.status
+        )));
+    }
+
+    let pipeline = pipeline_repository::find_by_id(pool, original.pipeline_id)
+        .await?
+        .ok_or(JobError::PipelineNotFound(original.pipeline_id))?;
+    ensure_active(&pipeline)?;
+
+    let traceparent = telemetry::traceparent(&tracing::Span::current());
+    let job = job_repository::create_resumed(pool, &original, traceparent.as_deref()).await?;
+
+    tracing::info!("Job {} created resuming job {}", job.id, job_id);
+
+    Ok(job)
+}
+
+/// Record the progress of a stage reported by the runner
+pub async fn record_stage(
+    pool: &PgPool,
+    job_id: Uuid,
+    update: StageUpdate,
+) -> Result<(), JobError> {
+    if update.name.trim().is_empty() {
+        return Err(JobError::ValidationError(
+            "Stage name cannot be empty".to_string(),
+        ));
+    }
+
+    let _job = job_repository::find_by_id(pool, job_id)
+        .await?
+        .ok_or(JobError::NotFound(job_id))?;
+
+    stage_repository::record(pool, job_id, &update).await?;
+    if let Some(status) = DeploymentStatus::of_stage(update.status) {
+        environment_repository::record_deployment(
+            pool,
+            job_id,
+            &update.name,
+            status,
+            update.timestamp,
+        )
+        .await?;
+    }
+    Ok(())
+}
+
+/// List the stages of the current attempt of a job
+pub async fn list_stages(pool: &PgPool, job_id: Uuid) -> Result<Vec<StageRun>, JobError> {
+    let _job = job_repository::find_by_id(pool, job_id)
+        .await?
+        .ok_or(JobError::NotFound(job_id))?;
+
+    let stages = stage_repository::find_by_job(pool, job_id).await?;
+    Ok(stages)
+}
+
 /// Complete a job with final status and result
+///
+/// Runners must send back the `claim_token` they received with the job: the
+/// job is only completed by the runner holding its current claim, and only
+/// once. The claim and the running status are checked by the same update
+/// that completes or requeues the job, so a runner that lost the job (its
+/// lease expired and it was claimed again) cannot write to it.
 pub async fn complete_job(
     pool: &PgPool,
     job_id: Uuid,
     status: JobStatus,
     result: Option<JobResult>,
-) -> Result<(), JobError> {
+    claim_token: Option<u64>,
+) -> Result<Job, JobError> {
     // Verify job exists
     let job = job_repository::find_by_id(pool, job_id)
         .await?
@@ -141,39 +819,160 @@ pub async fn complete_job(
 
     // Validate status transition
     validate_completion_status(status)?;
+    let claim_token = claim_token
+        .ok_or_else(|| JobError::ValidationError("claim_token is required".to_string()))?;
+    let fence = claim_token as i64;
 
-    // Ensure job is in running state
+    // Only running jobs are completed, checked again by the update below
     if job.status != JobStatus::Running {
+        return Err(stale_claim(job_id, claim_token));
+    }
+
+    // A failed attempt is retried while the pipeline's max_retries allows it
+    let failed_attempt = is_retryable(status);
+    if failed_attempt && job.attempt < job.max_attempts {
+        let backoff = retry_backoff(job.attempt);
+        let requeued =
+            job_repository::requeue_for_retry(pool, job_id, chrono::Utc::now() + backoff, fence)
+                .await?;
+        if !requeued {
+            return Err(stale_claim(job_id, claim_token));
+        }
+
         tracing::warn!(
-            "Completing job {} that is not in Running state (current: {:?})",
+            "Job {} failed on attempt {} of {}, retrying in {:?}",
             job_id,
-            job.status
+            job.attempt,
+            job.max_attempts,
+            backoff
         );
+
+        return job_repository::find_by_id(pool, job_id)
+            .await?
+            .ok_or(JobError::NotFound(job_id));
     }
 
+    let dead_letter_reason = (failed_attempt && job.max_attempts > 1).then(|| {
+        let cause = match result.as_ref().and_then(|r| r.error_message.as_deref()) {
+            Some(message) => message.to_string(),
+            None => format!("{:?}", status),
+        };
+        format!("Failed on all {} attempts: {}", job.max_attempts, cause)
+    });
+
     // Update job status
-    job_repository::update_status_to_completed(pool, job_id, status).await?;
+    if !job_repository::complete_claimed(pool, job_id, status, fence).await? {
+        return Err(stale_claim(job_id, claim_token));
+    }
+    release_environments(pool, job_id).await?;
 
     // If there's a result, update it
     if let Some(result) = result {
+        add_annotations(pool, &job, &result).await?;
         job_repository::update_result(pool, job_id, result).await?;
     }
 
-    tracing::info!("Job {} completed with status: {:?}", job_id, status);
+    // Retries are exhausted: park the job for an operator instead
+    if let Some(reason) = &dead_letter_reason {
+        job_repository::dead_letter(pool, job_id, reason).await?;
+        tracing::warn!("Job {} dead-lettered: {}", job_id, reason);
+    } else {
+        tracing::info!("Job {} completed with status: {:?}", job_id, status);
+    }
+
+    let completed_job = job_repository::find_by_id(pool, job_id)
+        .await?
+        .ok_or(JobError::NotFound(job_id))?;
+
+    Ok(completed_job)
+}
+
+/// Error for a completion rejected because `claim_token` is no longer current
+fn stale_claim(job_id: Uuid, claim_token: u64) -> JobError {
+    tracing::warn!(
+        "Rejecting completion of job {} under stale claim {}",
+        job_id,
+        claim_token
+    );
+    JobError::StaleClaim(job_id)
+}
 
+/// Add the annotations a job's scripts set, found in its result, to its labels
+///
+/// Invalid annotations, and those past `MAX_JOB_LABELS` labels, are dropped
+/// with a warning rather than failing the completion.
+async fn add_annotations(pool: &PgPool, job: &Job, result: &JobResult) -> Result<(), JobError> {
+    let Some(annotations) = result
+        .output
+        .as_ref()
+        .and_then(|output| output.get("annotations"))
+        .and_then(|annotations| annotations.as_object())
+    else {
+        return Ok(());
+    };
+
+    let mut labels = std::collections::BTreeMap::new();
+    for (key, value) in annotations {
+        let Some(value) = value.as_str() else {
+            tracing::warn!(
+                "Dropping annotation {} of job {}: not a string",
+                key,
+                job.id
+            );
+            continue;
+        };
+        if let Err(message) = validate_label(key, value) {
+            tracing::warn!("Dropping annotation of job {}: {}", job.id, message);
+            continue;
+        }
+        let new = !job.labels.contains_key(key) && !labels.contains_key(key);
+        if new && job.labels.len() + labels.len() >= MAX_JOB_LABELS {
+            tracing::warn!(
+                "Dropping annotation {} of job {}: it already has {} labels",
+                key,
+                job.id,
+                MAX_JOB_LABELS
+            );
+            continue;
+        }
+        labels.insert(key.clone(), value.to_string());
+    }
+
+    if !labels.is_empty() {
+        job_repository::add_labels(pool, job.id, &labels).await?;
+    }
     Ok(())
 }
 
+/// Event announcing the outcome of `complete_job`
+///
+/// A failed job that was put back in the queue for another attempt is
+/// announced as queued rather than completed.
+pub fn completion_event(job: &Job) -> Event {
+    match job.status {
+        JobStatus::Queued => Event::JobQueued {
+            job_id: job.id,
+            pipeline_id: job.pipeline_id,
+        },
+        status => Event::JobCompleted {
+            job_id: job.id,
+            pipeline_id: job.pipeline_id,
+            status,
+        },
+    }
+}
+
 /// Cancel a job
 pub async fn cancel_job(pool: &PgPool, job_id: Uuid) -> Result<(), JobError> {
     let job = job_repository::find_by_id(pool, job_id)
         .await?
         .ok_or(JobError::NotFound(job_id))?;
 
-    // Can only cancel queued or running jobs
+    // Can only cancel queued, blocked or running jobs
     match job.status {
-        JobStatus::Queued | JobStatus::Running => {
+        JobStatus::Queued | JobStatus::Blocked | JobStatus::Running => {
             job_repository::update_status_to_completed(pool, job_id, JobStatus::Cancelled).await?;
+            release_environments(pool, job_id).await?;
             tracing::info!("Job {} cancelled", job_id);
             Ok(())
         }
@@ -188,6 +987,39 @@ pub async fn cancel_job(pool: &PgPool, job_id: Uuid) -> Result<(), JobError> {
 // Validation
 // =============================================================================
 
+/// Attempts a job of this pipeline gets, from its `max_retries`
+fn max_attempts(definition: &rivet_lua::PipelineDefinition) -> Result<u32, JobError> {
+    if definition.max_retries > MAX_JOB_RETRIES {
+        return Err(JobError::ValidationError(format!(
+            "max_retries must be at most {} (got {})",
+            MAX_JOB_RETRIES, definition.max_retries
+        )));
+    }
+
+    Ok(definition.max_retries + 1)
+}
+
+/// Whether a job ending with `status` may be retried
+fn is_retryable(status: JobStatus) -> bool {
+    matches!(status, JobStatus::Failed | JobStatus::TimedOut)
+}
+
+/// Whether a job that ended with `status` may be resumed
+fn is_resumable(status: JobStatus) -> bool {
+    matches!(
+        status,
+        JobStatus::Failed | JobStatus::TimedOut | JobStatus::Cancelled | JobStatus::DeadLettered
+    )
+}
+
+/// Delay before the attempt following `attempt` (1-based)
+fn retry_backoff(attempt: u32) -> Duration {
+    let factor = 2u32.saturating_pow(attempt.saturating_sub(1));
+    RETRY_BACKOFF_BASE
+        .saturating_mul(factor)
+        .min(RETRY_BACKOFF_MAX)
+}
+
 fn validate_completion_status(status: JobStatus) -> Result<(), JobError> {
     match status {
         JobStatus::Succeeded | JobStatus::Failed | JobStatus::TimedOut | JobStatus::Cancelled => {
@@ -200,91 +1032,409 @@ fn validate_completion_status(status: JobStatus) -> Result<(), JobError> {
     }
 }
 
+/// Input defaults and overrides the orchestrator holds for a pipeline
+async fn pipeline_defaults(pool: &PgPool, pipeline_id: Uuid) -> Result<PipelineDefaults, JobError> {
+    let defaults = pipeline_repository::find_defaults(pool, pipeline_id)
+        .await?
+        .ok_or(JobError::PipelineNotFound(pipeline_id))?;
+    Ok(defaults)
+}
+
 /// Validate and enrich job parameters with pipeline defaults
+/// Reject jobs of pipelines no registered runner could ever claim
+///
+/// Jobs are only claimed by runners providing every tag and plugin their
+/// pipeline requires; failing at launch beats a job queued forever.
+async fn ensure_capable_runner(pool: &PgPool, pipeline: &Pipeline) -> Result<(), JobError> {
+    if pipeline.tags.is_empty() && pipeline.plugins.is_empty() {
+        return Ok(());
+    }
+
+    if runner_repository::exists_providing(pool, &pipeline.tags, &pipeline.plugins).await? {
+        return Ok(());
+    }
+
+    Err(JobError::ValidationError(format!(
+        "No registered runner provides {}",
+        describe_requirements(&pipeline.tags, &pipeline.plugins)
+    )))
+}
+
+/// Reject new jobs of archived or disabled pipelines
+fn ensure_active(pipeline: &Pipeline) -> Result<(), JobError> {
+    if pipeline.archived_at.is_some() {
+        return Err(JobError::InvalidState(format!(
+            "Pipeline {} is archived",
+            pipeline.id
+        )));
+    }
+    if pipeline.disabled {
+        return Err(JobError::InvalidState(format!(
+            "Pipeline {} ({}) is disabled; enable it to launch jobs",
+            pipeline.name, pipeline.id
+        )));
+    }
+    Ok(())
+}
+
+/// Environments a job deploys to that lack the approvals they require
+async fn unapproved_environments(pool: &PgPool, job_id: Uuid) -> Result<Vec<String>, JobError> {
+    let required = environment_repository::find_required_approvals(pool, job_id).await?;
+    if required.iter().all(|(_, count)| *count == 0) {
+        return Ok(Vec::new());
+    }
+
+    let approvals = environment_repository::find_approvals(pool, job_id).await?;
+    Ok(required
+        .into_iter()
+        .filter(|(environment, count)| {
+            let given = approvals
+                .iter()
+                .filter(|a| &a.environment == environment)
+                .count() as u32;
+            given < *count
+        })
+        .map(|(environment, _)| environment)
+        .collect())
+}
+
+/// Whether any stage of a pipeline deploys to an environment
+fn deploys(definition: &rivet_lua::PipelineDefinition) -> bool {
+    definition
+        .stages
+        .iter()
+        .any(|stage| stage.deploys_to.is_some())
+}
+
+/// Take the locks of the environments a job just claimed deploys to
+///
+/// A job losing a lock to a job claimed at the same time goes back to
+/// waiting, blocked.
+///
+/// # Returns
+/// Whether the job may run
+async fn lock_environments(pool: &PgPool, job_id: Uuid) -> Result<bool, JobError> {
+    match environment_repository::acquire_locks(pool, job_id).await? {
+        None => Ok(true),
+        Some(true) => {
+            environment_repository::block_locked_out_jobs(pool).await?;
+            Ok(true)
+        }
+        Some(false) => {
+            environment_repository::release_locks(pool, job_id).await?;
+            job_repository::unclaim_blocked(pool, job_id).await?;
+            tracing::info!("Job {} lost an environment lock, job blocked", job_id);
+            Ok(false)
+        }
+    }
+}
+
+/// Release the environment locks of a finished job, unblocking the jobs
+/// waiting for them
+///
+/// Jobs unblocked here are picked up by runners as they re-check the queue.
+async fn release_environments(pool: &PgPool, job_id: Uuid) -> Result<(), JobError> {
+    if environment_repository::release_locks(pool, job_id).await? > 0 {
+        environment_repository::unblock_jobs(pool).await?;
+    }
+    Ok(())
+}
+
+/// Mark just created jobs blocked if another job holds the lock of an
+/// environment they deploy to
+async fn block_locked_out(pool: &PgPool, jobs: &mut [Job]) -> Result<(), JobError> {
+    if environment_repository::block_locked_out_jobs(pool).await? == 0 {
+        return Ok(());
+    }
+
+    for job in jobs.iter_mut() {
+        if let Some(updated) = job_repository::find_by_id(pool, job.id).await? {
+            *job = updated;
+        }
+    }
+    Ok(())
+}
+
+/// Describe environment locks, for error messages
+fn describe_locks(locks: &[EnvironmentLock]) -> String {
+    locks
+        .iter()
+        .map(|lock| format!("{} (locked by job {})", lock.environment, lock.job_id))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Describe the tags and plugins a pipeline requires, for error messages
+fn describe_requirements(tags: &[Tag], plugins: &[String]) -> String {
+    let mut parts = Vec::new();
+    if !tags.is_empty() {
+        let tags: Vec<String> = tags
+            .iter()
+            .map(|t| format!("{}={}", t.key, t.value))
+            .collect();
+        parts.push(format!("tags {}", tags.join(", ")));
+    }
+    if !plugins.is_empty() {
+        parts.push(format!("plugins {}", plugins.join(", ")));
+    }
+    parts.join(" and ")
+}
+
+/// Check that a commit names a repository path and a hexadecimal SHA
+fn validate_commit(commit: &CommitRef) -> Vec<FieldError> {
+    let mut errors = Vec::new();
+    let segments: Vec<&str> = commit.repository.split('/').collect();
+    if segments.len() < 2 || segments.iter().any(|s| s.trim().is_empty()) {
+        errors.push(FieldError::new(
+            "repository",
+            "Must be a repository path such as owner/repo",
+        ));
+    }
+    if !(7..=64).contains(&commit.sha.len()) || !commit.sha.chars().all(|c| c.is_ascii_hexdigit()) {
+        errors.push(FieldError::new(
+            "sha",
+            "Must be a commit SHA (7 to 64 hex digits)",
+        ));
+    }
+    errors
+}
+
+/// Check the labels a job is launched with
+fn validate_labels(labels: &std::collections::BTreeMap<String, String>) -> Vec<FieldError> {
+    if labels.len() > MAX_JOB_LABELS {
+        return vec![FieldError::new(
+            "labels",
+            format!("At most {} labels are allowed", MAX_JOB_LABELS),
+        )];
+    }
+    labels
+        .iter()
+        .filter_map(|(key, value)| {
+            let message = validate_label(key, value).err()?;
+            Some(FieldError::new(format!("labels.{}", key), message))
+        })
+        .collect()
+}
+
+/// Validate parameters against the pipeline inputs and apply defaults
+///
+/// # Returns
+/// The parameters with defaults applied, or every invalid input keyed by
+/// its name
 fn validate_and_enrich_parameters(
-    definition: &rivet_lua::PipelineDefinition,
+    inputs: &std::collections::HashMap<String, InputDefinition>,
     mut parameters: std::collections::HashMap<String, serde_json::Value>,
-) -> Result<std::collections::HashMap<String, serde_json::Value>, JobError> {
-    // Check all required inputs are provided
-    for (key, input_def) in &definition.inputs {
+) -> Result<std::collections::HashMap<String, serde_json::Value>, Vec<FieldError>> {
+    let mut errors = Vec::new();
+
+    for (key, input_def) in inputs {
         if !parameters.contains_key(key) {
             if let Some(default) = &input_def.default {
                 // Apply default value
                 parameters.insert(key.clone(), default.clone());
             } else if input_def.required {
-                return Err(JobError::ValidationError(format!(
-                    "Missing required input '{}' (type: {})",
-                    key, input_def.input_type
-                )));
+                errors.push(FieldError::new(
+                    key,
+                    format!("Missing required input (type: {})", input_def.input_type),
+                ));
             }
-        } else {
-            // Validate type
-            let value = &parameters[key];
-            validate_input_type(key, value, &input_def.input_type)?;
-
-            // Validate options if provided
-            if let Some(options) = &input_def.options {
-                let value_matches = options.iter().any(|opt| match (value, opt) {
-                    (serde_json::Value::Number(a), serde_json::Value::Number(b)) => {
-                        a.as_f64() == b.as_f64()
-                    }
-                    (serde_json::Value::String(a), serde_json::Value::String(b)) => a == b,
-                    (serde_json::Value::Bool(a), serde_json::Value::Bool(b)) => a == b,
-                    _ => false,
-                });
-
-                if !value_matches {
-                    let options_str = options
-                        .iter()
-                        .map(|v| match v {
-                            serde_json::Value::String(s) => s.clone(),
-                            serde_json::Value::Number(n) => n.to_string(),
-                            serde_json::Value::Bool(b) => b.to_string(),
-                            _ => format!("{:?}", v),
-                        })
-                        .collect::<Vec<_>>()
-                        .join(", ");
-
-                    return Err(JobError::ValidationError(format!(
-                        "Invalid value for input '{}'. Must be one of: {}",
-                        key, options_str
-                    )));
+            continue;
+        }
+
+        if let Err(messages) = validate_input_value(&parameters[key], input_def) {
+            errors.extend(messages.into_iter().map(|m| FieldError::new(key, m)));
+        }
+    }
+
+    if !errors.is_empty() {
+        // Report inputs in a stable order, each input's errors as found
+        errors.sort_by(|a, b| a.field.cmp(&b.field));
+        return Err(errors);
+    }
+
+    Ok(parameters)
+}
+
+/// Validate values set for a pipeline's inputs
+///
+/// Each value must name a declared input and suit it; inputs left out are
+/// not reported, unlike in a launch. Secret and file inputs cannot be set,
+/// as the values would be stored in the clear.
+pub(crate) fn validate_input_values(
+    inputs: &std::collections::HashMap<String, InputDefinition>,
+    values: &std::collections::HashMap<String, serde_json::Value>,
+) -> Vec<FieldError> {
+    let mut errors: Vec<FieldError> = values
+        .iter()
+        .flat_map(|(key, value)| {
+            let messages = match inputs.get(key) {
+                Some(input_def)
+                    if matches!(input_def.input_type, InputType::Secret | InputType::File) =>
+                {
+                    vec![format!(
+                        "A {} input cannot be set here",
+                        input_def.input_type
+                    )]
+                }
+                Some(input_def) => validate_input_value(value, input_def)
+                    .err()
+                    .unwrap_or_default(),
+                None => vec!["Pipeline has no such input".to_string()],
+            };
+            messages.into_iter().map(move |m| FieldError::new(key, m))
+        })
+        .collect();
+    errors.sort_by(|a, b| a.field.cmp(&b.field));
+    errors
+}
+
+/// Validate that a value suits an input: its type, its options if any and
+/// its constraints
+///
+/// # Returns
+/// Every problem with the value; a value of the wrong type is not checked
+/// further
+fn validate_input_value(
+    value: &serde_json::Value,
+    input_def: &InputDefinition,
+) -> Result<(), Vec<String>> {
+    let options = input_def.options.as_deref();
+    let mut errors = Vec::new();
+
+    match input_def.input_type {
+        InputType::String | InputType::Number | InputType::Bool => {
+            validate_input_type(value, Some(input_def.input_type)).map_err(|e| vec![e])?;
+            errors.extend(validate_input_option(value, options).err());
+        }
+        InputType::Enum => {
+            validate_input_type(value, None).map_err(|e| vec![e])?;
+            errors.extend(validate_input_option(value, options).err());
+        }
+        InputType::Array => {
+            let items = value
+                .as_array()
+                .ok_or_else(|| vec![format!("Expected type 'array', but got: {}", value)])?;
+            for (index, item) in items.iter().enumerate() {
+                if let Err(message) = validate_input_type(item, input_def.items)
+                    .and_then(|()| validate_input_option(item, options))
+                {
+                    errors.push(format!("Item {}: {}", index, message));
                 }
             }
         }
+        // Never echo the value of a secret
+        InputType::Secret if !value.is_string() => {
+            return Err(vec!["Expected type 'secret' (a string)".to_string()]);
+        }
+        InputType::Secret => {}
+        InputType::File => {
+            InputFile::parse(value).map_err(|e| vec![e])?;
+        }
     }
 
-    Ok(parameters)
+    errors.extend(input_def.constraint_violations(value));
+
+    match errors.is_empty() {
+        true => Ok(()),
+        false => Err(errors),
+    }
+}
+
+/// Validate that a value is one of the input's options, when it has some
+fn validate_input_option(
+    value: &serde_json::Value,
+    options: Option<&[serde_json::Value]>,
+) -> Result<(), String> {
+    let Some(options) = options else {
+        return Ok(());
+    };
+
+    let value_matches = options.iter().any(|opt| match (value, opt) {
+        (serde_json::Value::Number(a), serde_json::Value::Number(b)) => a.as_f64() == b.as_f64(),
+        (serde_json::Value::String(a), serde_json::Value::String(b)) => a == b,
+        (serde_json::Value::Bool(a), serde_json::Value::Bool(b)) => a == b,
+        _ => false,
+    });
+
+    if !value_matches {
+        let options_str = options
+            .iter()
+            .map(|v| match v {
+                serde_json::Value::String(s) => s.clone(),
+                serde_json::Value::Number(n) => n.to_string(),
+                serde_json::Value::Bool(b) => b.to_string(),
+                _ => format!("{:?}", v),
+            })
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        return Err(format!("Must be one of: {}", options_str));
+    }
+
+    Ok(())
 }
 
-/// Validate that a parameter value matches the expected type
+/// Validate that a value is a string, number or bool of the expected type
+/// (any of them when `None`)
 fn validate_input_type(
-    name: &str,
     value: &serde_json::Value,
-    expected_type: &str,
-) -> Result<(), JobError> {
+    expected_type: Option<InputType>,
+) -> Result<(), String> {
     let matches = match expected_type {
-        "string" => value.is_string(),
-        "number" => value.is_number(),
-        "bool" => value.is_boolean(),
-        _ => {
-            return Err(JobError::ValidationError(format!(
-                "Unknown input type: {}",
-                expected_type
-            )));
-        }
+        Some(InputType::Number) => value.is_number(),
+        Some(InputType::Bool) => value.is_boolean(),
+        Some(_) => value.is_string(),
+        None => value.is_string() || value.is_number() || value.is_boolean(),
     };
 
     if !matches {
-        return Err(JobError::ValidationError(format!(
-            "Input '{}' expected type '{}', but got: {:?}",
-            name, expected_type, value
-        )));
+        return Err(match expected_type {
+            Some(expected_type) => format!("Expected type '{}', but got: {}", expected_type, value),
+            None => format!("Expected a string, number or bool, but got: {}", value),
+        });
     }
 
     Ok(())
 }
 
+/// Move the values of secret inputs out of a job's parameters
+///
+/// # Returns
+/// The parameters with secret values masked, and the secret values by input
+fn split_secret_parameters(
+    inputs: &std::collections::HashMap<String, InputDefinition>,
+    mut parameters: std::collections::HashMap<String, serde_json::Value>,
+) -> (
+    std::collections::HashMap<String, serde_json::Value>,
+    std::collections::HashMap<String, serde_json::Value>,
+) {
+    let mut secret_parameters = std::collections::HashMap::new();
+
+    for (key, input_def) in inputs {
+        if input_def.input_type != InputType::Secret {
+            continue;
+        }
+        if let Some(value) = parameters.insert(key.clone(), MASKED_INPUT.into()) {
+            secret_parameters.insert(key.clone(), value);
+        } else {
+            parameters.remove(key);
+        }
+    }
+
+    (parameters, secret_parameters)
+}
+
+/// Nest field errors under a parent field, e.g. `parameters.branch`
+pub(crate) fn prefix_fields(parent: &str, errors: Vec<FieldError>) -> Vec<FieldError> {
+    errors
+        .into_iter()
+        .map(|e| FieldError {
+            field: format!("{}.{}", parent, e.field),
+            message: e.message,
+        })
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -301,5 +1451,215 @@ mod tests {
     fn test_validate_completion_status_invalid() {
         assert!(validate_completion_status(JobStatus::Queued).is_err());
         assert!(validate_completion_status(JobStatus::Running).is_err());
+        assert!(validate_completion_status(JobStatus::DeadLettered).is_err());
+    }
+
+    #[test]
+    fn test_only_unsuccessful_jobs_are_resumable() {
+        assert!(is_resumable(JobStatus::Failed));
+        assert!(is_resumable(JobStatus::DeadLettered));
+        assert!(!is_resumable(JobStatus::Succeeded));
+        assert!(!is_resumable(JobStatus::Running));
+        assert!(!is_resumable(JobStatus::Queued));
+    }
+
+    #[test]
+    fn test_parameter_errors_list_every_field() {
+        let input = |input_type: &str, required: bool, options: Option<Vec<serde_json::Value>>| {
+            InputDefinition {
+                input_type: input_type.parse().unwrap(),
+                items: None,
+                description: None,
+                required,
+                default: None,
+                options,
+                pattern: None,
+                min: None,
+                max: None,
+                min_length: None,
+                max_length: None,
+            }
+        };
+        let inputs = std::collections::HashMap::from([
+            ("branch".to_string(), input("string", true, None)),
+            ("count".to_string(), input("number", false, None)),
+            (
+                "env".to_string(),
+                input("string", false, Some(vec![serde_json::json!("prod")])),
+            ),
+        ]);
+        let parameters = std::collections::HashMap::from([
+            ("count".to_string(), serde_json::json!("three")),
+            ("env".to_string(), serde_json::json!("dev")),
+        ]);
+
+        let errors = validate_and_enrich_parameters(&inputs, parameters).unwrap_err();
+        let errors = prefix_fields("parameters", errors);
+        let fields: Vec<&str> = errors.iter().map(|e| e.field.as_str()).collect();
+        assert_eq!(
+            fields,
+            ["parameters.branch", "parameters.count", "parameters.env"]
+        );
+        assert_eq!(errors[2].message, "Must be one of: prod");
+    }
+
+    #[test]
+    fn test_rich_input_types() {
+        let input = |input_type: InputType, items, options: Option<Vec<serde_json::Value>>| {
+            InputDefinition {
+                input_type,
+                items,
+                description: None,
+                required: false,
+                default: None,
+                options,
+                pattern: None,
+                min: None,
+                max: None,
+                min_length: None,
+                max_length: None,
+            }
+        };
+        let env = input(InputType::Enum, None, Some(vec![serde_json::json!("prod")]));
+        let targets = input(InputType::Array, Some(InputType::String), None);
+        let token = input(InputType::Secret, None, None);
+        let config = input(InputType::File, None, None);
+
+        assert!(validate_input_value(&serde_json::json!("prod"), &env).is_ok());
+        assert!(validate_input_value(&serde_json::json!("dev"), &env).is_err());
+        assert!(validate_input_value(&serde_json::json!(["a", "b"]), &targets).is_ok());
+        assert_eq!(
+            validate_input_value(&serde_json::json!(["a", 2]), &targets).unwrap_err(),
+            ["Item 1: Expected type 'string', but got: 2"]
+        );
+        assert!(validate_input_value(&serde_json::json!(42), &token).is_err());
+
+        let file = serde_json::to_value(InputFile::new("app.toml", b"port = 80")).unwrap();
+        assert!(validate_input_value(&file, &config).is_ok());
+        let escaping = serde_json::json!({ "name": "../app.toml", "content": "" });
+        assert!(validate_input_value(&escaping, &config).is_err());
+
+        let inputs = std::collections::HashMap::from([("token".to_string(), token)]);
+        let (parameters, secrets) = split_secret_parameters(
+            &inputs,
+            std::collections::HashMap::from([
+                ("token".to_string(), serde_json::json!("hunter2")),
+                ("branch".to_string(), serde_json::json!("main")),
+            ]),
+        );
+        assert_eq!(parameters["token"], MASKED_INPUT);
+        assert_eq!(parameters["branch"], "main");
+        assert_eq!(secrets["token"], "hunter2");
+    }
+
+    #[test]
+    fn test_constraint_violations_are_all_reported() {
+        let input = |input_type: InputType| InputDefinition {
+            input_type,
+            items: None,
+            description: None,
+            required: true,
+            default: None,
+            options: None,
+            pattern: Some(regex::Regex::new("^[a-z-]+$").unwrap()),
+            min: Some(1.0),
+            max: Some(8.0),
+            min_length: Some(3),
+            max_length: Some(5),
+        };
+        let inputs = std::collections::HashMap::from([
+            ("name".to_string(), input(InputType::String)),
+            ("tags".to_string(), input(InputType::Array)),
+        ]);
+        let parameters = std::collections::HashMap::from([
+            ("name".to_string(), serde_json::json!("Release_1")),
+            ("tags".to_string(), serde_json::json!(["ok", 12])),
+        ]);
+
+        let errors = validate_and_enrich_parameters(&inputs, parameters).unwrap_err();
+        let errors: Vec<(&str, &str)> = errors
+            .iter()
+            .map(|e| (e.field.as_str(), e.message.as_str()))
+            .collect();
+        assert_eq!(
+            errors,
+            [
+                ("name", "Must have at most 5 characters"),
+                ("name", "Must match pattern '^[a-z-]+$'"),
+                ("tags", "Must have at least 3 items"),
+                ("tags", "Item 1: Must be at most 8"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_describe_requirements() {
+        let tags = vec![Tag {
+            key: "os".to_string(),
+            value: "linux".to_string(),
+        }];
+        let plugins = vec!["git".to_string(), "docker".to_string()];
+
+        assert_eq!(
+            describe_requirements(&tags, &plugins),
+            "tags os=linux and plugins git, docker"
+        );
+        assert_eq!(describe_requirements(&[], &plugins), "plugins git, docker");
+    }
+
+    #[test]
+    fn test_retry_backoff_doubles_up_to_max() {
+        assert_eq!(retry_backoff(1), RETRY_BACKOFF_BASE);
+        assert_eq!(retry_backoff(2), RETRY_BACKOFF_BASE * 2);
+        assert_eq!(retry_backoff(3), RETRY_BACKOFF_BASE * 4);
+        assert_eq!(retry_backoff(40), RETRY_BACKOFF_MAX);
+    }
+
+    #[test]
+    fn test_label_errors_name_the_label() {
+        let labels = std::collections::BTreeMap::from([
+            ("team".to_string(), "payments".to_string()),
+            ("cost center".to_string(), "42".to_string()),
+            ("env".to_string(), "prod,staging".to_string()),
+        ]);
+
+        let fields: Vec<String> = validate_labels(&labels)
+            .into_iter()
+            .map(|e| e.field)
+            .collect();
+        assert_eq!(fields, vec!["labels.cost center", "labels.env"]);
+
+        let many = (0..=MAX_JOB_LABELS)
+            .map(|i| (format!("k{}", i), "v".to_string()))
+            .collect();
+        assert_eq!(validate_labels(&many)[0].field, "labels");
+    }
+
+    #[test]
+    fn test_only_active_pipelines_launch_jobs() {
+        let now = chrono::Utc::now();
+        let mut pipeline = Pipeline {
+            id: Uuid::new_v4(),
+            project_id: Uuid::new_v4(),
+            name: "deploy".to_string(),
+            description: None,
+            script: String::new(),
+            created_at: now,
+            updated_at: now,
+            tags: Vec::new(),
+            plugins: Vec::new(),
+            archived_at: None,
+            disabled: false,
+        };
+        assert!(ensure_active(&pipeline).is_ok());
+
+        pipeline.disabled = true;
+        let Err(JobError::InvalidState(message)) = ensure_active(&pipeline) else {
+            panic!("disabled pipelines must refuse launches");
+        };
+        assert!(message.contains("deploy") && message.contains("disabled"));
+
+        pipeline.archived_at = Some(now);
+        assert!(ensure_active(&pipeline).is_err());
     }
 }
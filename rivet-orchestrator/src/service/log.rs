@@ -1,18 +1,40 @@
 //! Log Service
 //!
 //! Business logic for job log management.
+//!
+//! When a log archive is configured, the logs of completed jobs are compacted
+//! into object storage (see `log_archive`). Reads stitch the archived entries
+//! and those stored since together, ordered by sequence number.
+
+use std::time::Duration;
 
-use rivet_core::domain::log::LogEntry;
+use chrono::Utc;
+use rivet_core::domain::log::{LogEntry, LogLevel};
+use rivet_core::dto::log::{
+    DEFAULT_LOG_SEARCH_LIMIT, LogQuery, LogSearchHit, LogSearchQuery, MAX_LOG_BATCH_BYTES,
+    MAX_LOG_BATCH_ENTRIES, MAX_LOG_MESSAGE_LENGTH, MAX_LOG_SEARCH_QUERY_LENGTH,
+};
+use rivet_core::dto::pagination::MAX_PAGE_SIZE;
 use sqlx::PgPool;
 use uuid::Uuid;
 
-use crate::repository::log_repository;
+use crate::log_archive::{ArchiveError, LogArchive};
+use crate::repository::log_repository::{self, LogArchiveRecord};
+
+/// Jobs compacted, and orphaned archives deleted, per compaction pass
+const COMPACTION_BATCH: i64 = 50;
+
+/// Archived messages sent to the database per round trip when searching them
+const SEARCH_MATCH_CHUNK: usize = 5_000;
 
 /// Service error type
 #[derive(Debug)]
 pub enum LogError {
     JobNotFound(Uuid),
+    /// The entries were sent under a claim the job no longer holds
+    StaleClaim(Uuid),
     ValidationError(String),
+    ArchiveError(ArchiveError),
     DatabaseError(sqlx::Error),
 }
 
@@ -22,19 +44,40 @@ impl From<sqlx::Error> for LogError {
     }
 }
 
+impl From<ArchiveError> for LogError {
+    fn from(err: ArchiveError) -> Self {
+        LogError::ArchiveError(err)
+    }
+}
+
 pub type Result<T> = std::result::Result<T, LogError>;
 
 /// Add log entries for a job
-pub async fn add_log_entries(pool: &PgPool, job_id: Uuid, entries: Vec<LogEntry>) -> Result<()> {
+///
+/// Runners must send the `claim_token` they received with the job: the
+/// entries are only added while the job still holds that claim, so a runner
+/// that lost the job cannot write to it (see `job_service::complete_job`).
+pub async fn add_log_entries(
+    pool: &PgPool,
+    job_id: Uuid,
+    entries: Vec<LogEntry>,
+    claim_token: Option<u64>,
+) -> Result<()> {
     // Validate entries
     validate_log_entries(&entries)?;
+    let claim_token = claim_token
+        .ok_or_else(|| LogError::ValidationError("claim_token is required".to_string()))?;
 
     if entries.is_empty() {
         return Ok(());
     }
 
     // Add entries to database
-    log_repository::add_entries(pool, job_id, entries).await?;
+    let added = log_repository::add_entries(pool, job_id, entries, claim_token as i64).await?;
+    if !added {
+        tracing::warn!("Rejecting logs of job {} sent under a stale claim", job_id);
+        return Err(LogError::StaleClaim(job_id));
+    }
 
     tracing::debug!("Added log entries for job: {}", job_id);
 
@@ -42,59 +85,372 @@ pub async fn add_log_entries(pool: &PgPool, job_id: Uuid, entries: Vec<LogEntry>
 }
 
 /// Get all log entries for a job
-pub async fn get_job_logs(pool: &PgPool, job_id: Uuid) -> Result<Vec<LogEntry>> {
+pub async fn get_job_logs(
+    pool: &PgPool,
+    archive: &LogArchive,
+    job_id: Uuid,
+) -> Result<Vec<LogEntry>> {
+    // Rows are read before the archive: rows compacted in between are then
+    // found in both, rather than in neither
     let logs = log_repository::find_by_job(pool, job_id).await?;
 
+    match load_archived(pool, archive, job_id).await? {
+        Some(archived) => Ok(stitch(archived, logs)),
+        None => Ok(logs),
+    }
+}
+
+/// Get one page of log entries for a job
+pub async fn get_job_logs_page(
+    pool: &PgPool,
+    archive: &LogArchive,
+    job_id: Uuid,
+    limit: u32,
+    offset: u32,
+) -> Result<Vec<LogEntry>> {
+    if limit == 0 || limit > MAX_PAGE_SIZE {
+        return Err(LogError::ValidationError(format!(
+            "limit must be between 1 and {}",
+            MAX_PAGE_SIZE
+        )));
+    }
+
+    if log_repository::find_archive(pool, job_id).await?.is_some() {
+        let logs = get_job_logs(pool, archive, job_id).await?;
+        return Ok(logs
+            .into_iter()
+            .skip(offset as usize)
+            .take(limit as usize)
+            .collect());
+    }
+
+    let logs = log_repository::find_page_by_job(pool, job_id, limit as i64, offset as i64).await?;
+
+    Ok(logs)
+}
+
+/// Get the log entries of a job matching a query
+///
+/// `after` is the sequence of the last entry the caller already has, `level`
+/// the least severe level to return. With `tail`, only the last `tail`
+/// matching entries are returned.
+pub async fn query_job_logs(
+    pool: &PgPool,
+    archive: &LogArchive,
+    job_id: Uuid,
+    query: &LogQuery,
+) -> Result<Vec<LogEntry>> {
+    let tail = query.tail;
+    if tail.is_some_and(|tail| tail == 0 || tail > MAX_PAGE_SIZE) {
+        return Err(LogError::ValidationError(format!(
+            "tail must be between 1 and {}",
+            MAX_PAGE_SIZE
+        )));
+    }
+
+    let after = i64::try_from(query.after.unwrap_or(0))
+        .map_err(|_| LogError::ValidationError("after is out of range".to_string()))?;
+    let min_level = query
+        .min_level()
+        .map_err(LogError::ValidationError)?
+        .unwrap_or(LogLevel::Debug);
+
+    if log_repository::find_archive(pool, job_id).await?.is_some() {
+        let logs = get_job_logs(pool, archive, job_id).await?;
+        return Ok(filter_entries(logs, after as u64, tail, min_level));
+    }
+
+    let logs =
+        log_repository::find_after_by_job(pool, job_id, after, tail.map(i64::from), min_level)
+            .await?;
+
     Ok(logs)
 }
 
 /// Get log count for a job
 pub async fn get_log_count(pool: &PgPool, job_id: Uuid) -> Result<i64> {
     let count = log_repository::count_by_job(pool, job_id).await?;
+    let archived = log_repository::find_archive(pool, job_id)
+        .await?
+        .map_or(0, |record| record.entries);
+
+    Ok(archived + count)
+}
+
+/// Search the log entries of a job, oldest first
+///
+/// Archived logs are searched too: their messages are matched by the
+/// database with the same full-text semantics as stored rows.
+pub async fn search_job_logs(
+    pool: &PgPool,
+    archive: &LogArchive,
+    job_id: Uuid,
+    query: &LogSearchQuery,
+) -> Result<Vec<LogEntry>> {
+    let (q, min_level, limit) = validate_search_query(query)?;
+
+    if log_repository::find_archive(pool, job_id).await?.is_none() {
+        let logs = log_repository::search_by_job(pool, job_id, q, min_level, limit as i64).await?;
+        return Ok(logs);
+    }
+
+    let candidates: Vec<LogEntry> = get_job_logs(pool, archive, job_id)
+        .await?
+        .into_iter()
+        .filter(|entry| entry.level >= min_level)
+        .collect();
 
-    Ok(count)
+    let mut hits = Vec::new();
+    for chunk in candidates.chunks(SEARCH_MATCH_CHUNK) {
+        let messages: Vec<&str> = chunk.iter().map(|entry| entry.message.as_str()).collect();
+        let matches = log_repository::match_messages(pool, q, &messages).await?;
+        hits.extend(matches.into_iter().map(|i| chunk[i].clone()));
+        if hits.len() >= limit as usize {
+            hits.truncate(limit as usize);
+            break;
+        }
+    }
+
+    Ok(hits)
 }
 
-/// Delete all logs for a job
-pub async fn delete_job_logs(pool: &PgPool, job_id: Uuid) -> Result<u64> {
-    let deleted = log_repository::delete_by_job(pool, job_id).await?;
+/// Search the log entries of all jobs, most recent first
+///
+/// Only logs still in the database are searched; the logs of jobs compacted
+/// into the archive are found by searching those jobs one at a time.
+///
+/// # Arguments
+/// * `project_id` - Only search the jobs of this project
+pub async fn search_logs(
+    pool: &PgPool,
+    project_id: Option<Uuid>,
+    query: &LogSearchQuery,
+) -> Result<Vec<LogSearchHit>> {
+    let (q, min_level, limit) = validate_search_query(query)?;
+
+    let hits = log_repository::search(
+        pool,
+        q,
+        project_id,
+        query.pipeline_id,
+        min_level,
+        limit as i64,
+    )
+    .await?;
+
+    Ok(hits
+        .into_iter()
+        .map(|(job_id, pipeline_id, entry)| LogSearchHit {
+            job_id,
+            pipeline_id,
+            entry,
+        })
+        .collect())
+}
+
+/// Delete all logs for a job, archived ones included
+pub async fn delete_job_logs(pool: &PgPool, archive: &LogArchive, job_id: Uuid) -> Result<u64> {
+    let mut deleted = log_repository::delete_by_job(pool, job_id).await?;
+    if let Some(record) = log_repository::find_archive(pool, job_id).await? {
+        archive.delete(&record.object_key).await?;
+        log_repository::delete_archive(pool, job_id).await?;
+        deleted += record.entries as u64;
+    }
 
     tracing::info!("Deleted {} log entries for job: {}", deleted, job_id);
 
     Ok(deleted)
 }
 
+// =============================================================================
+// Archiving
+// =============================================================================
+
+/// Move the logs of a job to the archive
+///
+/// The job's archived and stored entries are written to a single object,
+/// replacing the previous one, then their rows are deleted.
+///
+/// # Returns
+/// The number of entries archived
+pub async fn archive_job_logs(pool: &PgPool, archive: &LogArchive, job_id: Uuid) -> Result<usize> {
+    let logs = get_job_logs(pool, archive, job_id).await?;
+    let Some(last_sequence) = logs.iter().filter_map(|entry| entry.sequence).max() else {
+        return Ok(0);
+    };
+
+    let object_key = archive.object_key(job_id);
+    let bytes = archive.put(&object_key, &logs).await?;
+    let record = LogArchiveRecord {
+        job_id,
+        object_key,
+        entries: logs.len() as i64,
+        bytes: bytes as i64,
+        archived_at: Utc::now(),
+    };
+    log_repository::save_archive(pool, &record, last_sequence as i64).await?;
+
+    tracing::debug!(
+        "Archived {} log entries of job {} ({} bytes)",
+        logs.len(),
+        job_id,
+        bytes
+    );
+
+    Ok(logs.len())
+}
+
+/// Archive the logs of jobs completed at least `compact_after` ago, and
+/// delete the archives of deleted jobs
+///
+/// Handles up to `COMPACTION_BATCH` jobs of each; the rest wait for the
+/// next pass. A job failing to archive is reported and skipped.
+///
+/// # Returns
+/// The number of jobs whose logs were archived
+pub async fn compact_logs(
+    pool: &PgPool,
+    archive: &LogArchive,
+    compact_after: Duration,
+) -> Result<usize> {
+    let completed_before =
+        Utc::now() - chrono::Duration::from_std(compact_after).unwrap_or(chrono::Duration::MAX);
+    let jobs =
+        log_repository::find_compactable_jobs(pool, completed_before, COMPACTION_BATCH).await?;
+
+    let mut archived = 0;
+    for job_id in jobs {
+        match archive_job_logs(pool, archive, job_id).await {
+            Ok(_) => archived += 1,
+            Err(e) => tracing::warn!("Failed to archive the logs of job {}: {:?}", job_id, e),
+        }
+    }
+
+    for record in log_repository::find_orphaned_archives(pool, COMPACTION_BATCH).await? {
+        archive.delete(&record.object_key).await?;
+        log_repository::delete_archive(pool, record.job_id).await?;
+    }
+
+    Ok(archived)
+}
+
+/// Archived entries of a job, `None` if its logs were never archived
+async fn load_archived(
+    pool: &PgPool,
+    archive: &LogArchive,
+    job_id: Uuid,
+) -> Result<Option<Vec<LogEntry>>> {
+    match log_repository::find_archive(pool, job_id).await? {
+        Some(record) => Ok(Some(archive.get(&record.object_key).await?)),
+        None => Ok(None),
+    }
+}
+
+/// Archived entries followed by the entries stored after them
+fn stitch(archived: Vec<LogEntry>, stored: Vec<LogEntry>) -> Vec<LogEntry> {
+    let last = archived
+        .iter()
+        .filter_map(|entry| entry.sequence)
+        .max()
+        .unwrap_or(0);
+
+    let mut logs = archived;
+    logs.extend(
+        stored
+            .into_iter()
+            .filter(|entry| entry.sequence.is_some_and(|sequence| sequence > last)),
+    );
+    logs
+}
+
+/// Applies a log query to entries ordered by sequence number
+fn filter_entries(
+    logs: Vec<LogEntry>,
+    after: u64,
+    tail: Option<u32>,
+    min_level: LogLevel,
+) -> Vec<LogEntry> {
+    let matching: Vec<LogEntry> = logs
+        .into_iter()
+        .filter(|entry| entry.sequence.unwrap_or(0) > after && entry.level >= min_level)
+        .collect();
+
+    match tail {
+        Some(tail) => {
+            let skip = matching.len().saturating_sub(tail as usize);
+            matching.into_iter().skip(skip).collect()
+        }
+        None => matching,
+    }
+}
+
 // =============================================================================
 // Validation
 // =============================================================================
 
 fn validate_log_entries(entries: &[LogEntry]) -> Result<()> {
-    const MAX_MESSAGE_LENGTH: usize = 10_000;
-    const MAX_BATCH_SIZE: usize = 1000;
-
-    if entries.len() > MAX_BATCH_SIZE {
+    if entries.len() > MAX_LOG_BATCH_ENTRIES {
         return Err(LogError::ValidationError(format!(
             "Too many log entries in batch (max: {})",
-            MAX_BATCH_SIZE
+            MAX_LOG_BATCH_ENTRIES
         )));
     }
 
     for (i, entry) in entries.iter().enumerate() {
-        if entry.message.len() > MAX_MESSAGE_LENGTH {
+        if entry.message.len() > MAX_LOG_MESSAGE_LENGTH {
             return Err(LogError::ValidationError(format!(
                 "Log entry {} message too long (max: {} chars)",
-                i, MAX_MESSAGE_LENGTH
+                i, MAX_LOG_MESSAGE_LENGTH
             )));
         }
     }
 
+    let total: usize = entries.iter().map(|e| e.message.len()).sum();
+    if total > MAX_LOG_BATCH_BYTES {
+        return Err(LogError::ValidationError(format!(
+            "Log batch too large (max: {} bytes of messages)",
+            MAX_LOG_BATCH_BYTES
+        )));
+    }
+
     Ok(())
 }
 
+/// Checks a search query
+///
+/// # Returns
+/// The trimmed query text, the minimum level and the limit
+fn validate_search_query(query: &LogSearchQuery) -> Result<(&str, LogLevel, u32)> {
+    let q = query.q.trim();
+    if q.is_empty() {
+        return Err(LogError::ValidationError("q must not be empty".to_string()));
+    }
+    if q.len() > MAX_LOG_SEARCH_QUERY_LENGTH {
+        return Err(LogError::ValidationError(format!(
+            "q is too long (max: {} bytes)",
+            MAX_LOG_SEARCH_QUERY_LENGTH
+        )));
+    }
+
+    let limit = query.limit.unwrap_or(DEFAULT_LOG_SEARCH_LIMIT);
+    if limit == 0 || limit > MAX_PAGE_SIZE {
+        return Err(LogError::ValidationError(format!(
+            "limit must be between 1 and {}",
+            MAX_PAGE_SIZE
+        )));
+    }
+
+    let min_level = query
+        .min_level()
+        .map_err(LogError::ValidationError)?
+        .unwrap_or(LogLevel::Debug);
+
+    Ok((q, min_level, limit))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use rivet_core::domain::log::LogLevel;
 
     #[test]
     fn test_validate_log_entries_valid() {
@@ -103,11 +459,19 @@ mod tests {
                 timestamp: chrono::Utc::now(),
                 level: LogLevel::Info,
                 message: "Test message".to_string(),
+                stage: None,
+                stream: None,
+                sequence: None,
+                fields: None,
             },
             LogEntry {
                 timestamp: chrono::Utc::now(),
                 level: LogLevel::Error,
                 message: "Error message".to_string(),
+                stage: None,
+                stream: None,
+                sequence: None,
+                fields: None,
             },
         ];
 
@@ -122,6 +486,10 @@ mod tests {
                 timestamp: chrono::Utc::now(),
                 level: LogLevel::Info,
                 message: format!("Message {}", i),
+                stage: None,
+                stream: None,
+                sequence: None,
+                fields: None,
             })
             .collect();
 
@@ -135,9 +503,81 @@ mod tests {
             timestamp: chrono::Utc::now(),
             level: LogLevel::Info,
             message: "x".repeat(10_001),
+            stage: None,
+            stream: None,
+            sequence: None,
+            fields: None,
         }];
 
         let result = validate_log_entries(&entries);
         assert!(matches!(result, Err(LogError::ValidationError(_))));
     }
+
+    #[test]
+    fn test_validate_log_entries_batch_too_large() {
+        let entries: Vec<LogEntry> = (0..200)
+            .map(|_| LogEntry {
+                timestamp: chrono::Utc::now(),
+                level: LogLevel::Info,
+                message: "x".repeat(MAX_LOG_MESSAGE_LENGTH),
+                stage: None,
+                stream: None,
+                sequence: None,
+                fields: None,
+            })
+            .collect();
+
+        let result = validate_log_entries(&entries);
+        assert!(matches!(result, Err(LogError::ValidationError(_))));
+    }
+
+    #[test]
+    fn test_stitch_and_filter_archived_entries() {
+        let entry = |sequence, level| LogEntry {
+            timestamp: chrono::Utc::now(),
+            level,
+            message: format!("Message {}", sequence),
+            stage: None,
+            stream: None,
+            sequence: Some(sequence),
+            fields: None,
+        };
+
+        // Entry 3 was archived but its row not deleted yet
+        let archived = vec![entry(1, LogLevel::Info), entry(3, LogLevel::Error)];
+        let stored = vec![entry(3, LogLevel::Error), entry(7, LogLevel::Info)];
+        let logs = stitch(archived, stored);
+        let sequences: Vec<_> = logs.iter().filter_map(|entry| entry.sequence).collect();
+        assert_eq!(sequences, vec![1, 3, 7]);
+
+        let errors = filter_entries(logs.clone(), 0, None, LogLevel::Error);
+        assert_eq!(errors.len(), 1);
+        let tail = filter_entries(logs.clone(), 1, Some(1), LogLevel::Debug);
+        assert_eq!(tail[0].sequence, Some(7));
+        assert!(filter_entries(logs, 7, None, LogLevel::Debug).is_empty());
+    }
+
+    #[test]
+    fn test_validate_search_query() {
+        let query = |q: &str, limit: Option<u32>| LogSearchQuery {
+            q: q.to_string(),
+            limit,
+            ..Default::default()
+        };
+
+        let trimmed = query(" error: linker ", None);
+        let (q, min_level, limit) = validate_search_query(&trimmed).unwrap();
+        assert_eq!(q, "error: linker");
+        assert_eq!(min_level, LogLevel::Debug);
+        assert_eq!(limit, DEFAULT_LOG_SEARCH_LIMIT);
+
+        assert!(validate_search_query(&query("  ", None)).is_err());
+        assert!(validate_search_query(&query(&"a".repeat(257), None)).is_err());
+        assert!(validate_search_query(&query("error", Some(0))).is_err());
+        assert!(validate_search_query(&query("error", Some(MAX_PAGE_SIZE + 1))).is_err());
+
+        let mut leveled = query("error", Some(10));
+        leveled.level = Some("loud".to_string());
+        assert!(validate_search_query(&leveled).is_err());
+    }
 }
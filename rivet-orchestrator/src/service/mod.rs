@@ -3,13 +3,29 @@
 //! Business logic layer for the orchestrator.
 //! Services orchestrate between repositories and contain domain logic.
 
+pub mod artifact;
+pub mod blackout;
+pub mod environment;
 pub mod job;
 pub mod log;
 pub mod pipeline;
+pub mod preset;
+pub mod project;
 pub mod runner;
+pub mod schedule;
+pub mod secret;
+pub mod webhook;
 
 // Re-export for convenience
+pub use artifact as artifact_service;
+pub use blackout as blackout_service;
+pub use environment as environment_service;
 pub use job as job_service;
 pub use log as log_service;
 pub use pipeline as pipeline_service;
+pub use preset as preset_service;
+pub use project as project_service;
 pub use runner as runner_service;
+pub use schedule as schedule_service;
+pub use secret as secret_service;
+pub use webhook as webhook_service;
@@ -3,18 +3,29 @@
 //! Business logic for pipeline management.
 
 use rivet_core::domain::pipeline::Pipeline;
-use rivet_core::dto::pipeline::CreatePipeline;
-use rivet_lua::{create_sandbox, parse_pipeline_definition};
+use rivet_core::dto::artifact::ArtifactRetention;
+use rivet_core::dto::commit_status::CommitStatusConfig;
+use rivet_core::dto::pipeline::{
+    CreatePipeline, DEFAULT_MIN_FLIPS, DEFAULT_STATS_DAYS, DeletePipelineQuery, FlakyStage,
+    FlakyStagesQuery, MAX_PIPELINE_SCRIPT_BYTES, MAX_STATS_DAYS, PipelineDefaults,
+    PipelineDefinitionInfo, PipelineGraph, PipelineStats, PipelineStatsQuery, StageInfo,
+    StatsBucket,
+};
+use rivet_core::dto::validation::{FieldError, describe_field_errors};
+use rivet_lua::{PipelineDefinition, create_sandbox, parse_pipeline_definition};
 use sqlx::PgPool;
 use uuid::Uuid;
 
-use crate::repository::pipeline_repository;
+use crate::repository::{pipeline_repository, stats_repository};
+use crate::service::job_service::{prefix_fields, validate_input_values};
 
 /// Service error type
 #[derive(Debug)]
 pub enum PipelineError {
     NotFound(Uuid),
     ValidationError(String),
+    /// Request fields that failed validation, each with its reason
+    InvalidFields(Vec<FieldError>),
     DatabaseError(sqlx::Error),
 }
 
@@ -23,6 +34,9 @@ impl std::fmt::Display for PipelineError {
         match self {
             PipelineError::NotFound(id) => write!(f, "Pipeline not found: {}", id),
             PipelineError::ValidationError(msg) => write!(f, "Validation error: {}", msg),
+            PipelineError::InvalidFields(errors) => {
+                write!(f, "Validation error: {}", describe_field_errors(errors))
+            }
             PipelineError::DatabaseError(err) => write!(f, "Database error: {}", err),
         }
     }
@@ -38,13 +52,17 @@ impl From<sqlx::Error> for PipelineError {
 
 pub type Result<T> = std::result::Result<T, PipelineError>;
 
-/// Create a new pipeline
-pub async fn create_pipeline(pool: &PgPool, req: CreatePipeline) -> Result<Pipeline> {
+/// Create a new pipeline in a project
+pub async fn create_pipeline(
+    pool: &PgPool,
+    project_id: Uuid,
+    req: CreatePipeline,
+) -> Result<Pipeline> {
     // Validate request
     validate_pipeline_request(&req)?;
 
     // Create pipeline in database
-    let pipeline = pipeline_repository::create(pool, req).await?;
+    let pipeline = pipeline_repository::create(pool, project_id, req).await?;
 
     tracing::info!("Pipeline created: {} ({})", pipeline.name, pipeline.id);
 
@@ -60,9 +78,21 @@ pub async fn get_pipeline(pool: &PgPool, id: Uuid) -> Result<Pipeline> {
     Ok(pipeline)
 }
 
-/// List all pipelines
-pub async fn list_pipelines(pool: &PgPool) -> Result<Vec<Pipeline>> {
-    let pipelines = pipeline_repository::list_all(pool).await?;
+/// List all pipelines, the archived ones if `archived` and the active ones
+/// otherwise
+pub async fn list_pipelines(pool: &PgPool, archived: bool) -> Result<Vec<Pipeline>> {
+    let pipelines = pipeline_repository::list_all(pool, archived).await?;
+    Ok(pipelines)
+}
+
+/// List the pipelines of a project, the archived ones if `archived` and the
+/// active ones otherwise
+pub async fn list_pipelines_by_project(
+    pool: &PgPool,
+    project_id: Uuid,
+    archived: bool,
+) -> Result<Vec<Pipeline>> {
+    let pipelines = pipeline_repository::find_by_project(pool, project_id, archived).await?;
     Ok(pipelines)
 }
 
@@ -87,8 +117,37 @@ pub async fn update_pipeline(pool: &PgPool, id: Uuid, req: CreatePipeline) -> Re
     get_pipeline(pool, id).await
 }
 
-/// Delete a pipeline
-pub async fn delete_pipeline(pool: &PgPool, id: Uuid) -> Result<()> {
+/// Delete or archive a pipeline
+///
+/// The pipeline is archived when `query.archive` is set, or when jobs
+/// reference it and `query.force` is not set; otherwise it is deleted, along
+/// with its jobs.
+///
+/// # Returns
+/// The archived pipeline, or `None` if it was deleted
+pub async fn delete_pipeline(
+    pool: &PgPool,
+    id: Uuid,
+    query: DeletePipelineQuery,
+) -> Result<Option<Pipeline>> {
+    if query.archive && query.force {
+        return Err(PipelineError::ValidationError(
+            "archive and force cannot be combined".to_string(),
+        ));
+    }
+
+    let archive =
+        query.archive || (!query.force && pipeline_repository::has_jobs(pool, id).await?);
+    if archive {
+        if !pipeline_repository::archive(pool, id).await? {
+            return Err(PipelineError::NotFound(id));
+        }
+
+        tracing::info!("Pipeline archived: {}", id);
+
+        return get_pipeline(pool, id).await.map(Some);
+    }
+
     let deleted = pipeline_repository::delete(pool, id).await?;
 
     if !deleted {
@@ -97,32 +156,340 @@ pub async fn delete_pipeline(pool: &PgPool, id: Uuid) -> Result<()> {
 
     tracing::info!("Pipeline deleted: {}", id);
 
-    Ok(())
+    Ok(None)
+}
+
+/// Make an archived pipeline active again
+pub async fn unarchive_pipeline(pool: &PgPool, id: Uuid) -> Result<Pipeline> {
+    if !pipeline_repository::unarchive(pool, id).await? {
+        return Err(PipelineError::NotFound(id));
+    }
+
+    tracing::info!("Pipeline unarchived: {}", id);
+
+    get_pipeline(pool, id).await
+}
+
+/// Pause (`disabled`) or resume the launches of a pipeline
+///
+/// A disabled pipeline keeps its jobs, schedules and webhook triggers, but
+/// every launch of a new job is refused until it is enabled again.
+pub async fn set_pipeline_disabled(pool: &PgPool, id: Uuid, disabled: bool) -> Result<Pipeline> {
+    if !pipeline_repository::set_disabled(pool, id, disabled).await? {
+        return Err(PipelineError::NotFound(id));
+    }
+
+    tracing::info!(
+        "Pipeline {}: {}",
+        if disabled { "disabled" } else { "enabled" },
+        id
+    );
+
+    get_pipeline(pool, id).await
+}
+
+/// Get the input defaults and overrides of a pipeline
+pub async fn get_defaults(pool: &PgPool, id: Uuid) -> Result<PipelineDefaults> {
+    let defaults = pipeline_repository::find_defaults(pool, id)
+        .await?
+        .ok_or(PipelineError::NotFound(id))?;
+
+    Ok(defaults)
+}
+
+/// Replace the input defaults and overrides of a pipeline
+///
+/// Every value must name an input the pipeline declares and suit its type
+/// and options, so launches are not broken by a typo on the server.
+pub async fn set_defaults(
+    pool: &PgPool,
+    id: Uuid,
+    defaults: PipelineDefaults,
+) -> Result<PipelineDefaults> {
+    let pipeline = get_pipeline(pool, id).await?;
+    let definition = parse_definition(&pipeline)?;
+
+    let mut errors = prefix_fields(
+        "defaults",
+        validate_input_values(&definition.inputs, &defaults.defaults),
+    );
+    errors.extend(prefix_fields(
+        "overrides",
+        validate_input_values(&definition.inputs, &defaults.overrides),
+    ));
+    if !errors.is_empty() {
+        return Err(PipelineError::InvalidFields(errors));
+    }
+
+    if !pipeline_repository::set_defaults(pool, id, &defaults).await? {
+        return Err(PipelineError::NotFound(id));
+    }
+
+    tracing::info!(
+        "Input defaults of pipeline {} set ({} defaults, {} overrides)",
+        id,
+        defaults.defaults.len(),
+        defaults.overrides.len()
+    );
+
+    Ok(defaults)
+}
+
+/// Get the artifact retention limits of a pipeline
+pub async fn get_artifact_retention(pool: &PgPool, id: Uuid) -> Result<ArtifactRetention> {
+    let retention = pipeline_repository::find_artifact_retention(pool, id)
+        .await?
+        .ok_or(PipelineError::NotFound(id))?;
+
+    Ok(retention)
+}
+
+/// Replace the artifact retention limits of a pipeline
+///
+/// Limits that would delete every artifact as soon as it is stored are
+/// rejected.
+pub async fn set_artifact_retention(
+    pool: &PgPool,
+    id: Uuid,
+    retention: ArtifactRetention,
+) -> Result<ArtifactRetention> {
+    let zero_limits = [
+        ("keep_last_jobs", retention.keep_last_jobs.map(u64::from)),
+        ("max_total_bytes", retention.max_total_bytes),
+        ("ttl_seconds", retention.ttl_seconds),
+    ];
+    let errors: Vec<FieldError> = zero_limits
+        .into_iter()
+        .filter(|(_, limit)| *limit == Some(0))
+        .map(|(field, _)| FieldError::new(field, "Must be at least 1"))
+        .collect();
+    if !errors.is_empty() {
+        return Err(PipelineError::InvalidFields(errors));
+    }
+
+    if !pipeline_repository::set_artifact_retention(pool, id, &retention).await? {
+        return Err(PipelineError::NotFound(id));
+    }
+
+    tracing::info!("Artifact retention of pipeline {} set: {:?}", id, retention);
+
+    Ok(retention)
+}
+
+/// Get the commit status reporting of a pipeline, `None` if it reports none
+pub async fn get_commit_status(pool: &PgPool, id: Uuid) -> Result<Option<CommitStatusConfig>> {
+    let config = pipeline_repository::find_commit_status(pool, id)
+        .await?
+        .ok_or(PipelineError::NotFound(id))?;
+
+    Ok(config)
+}
+
+/// Replace the commit status reporting of a pipeline, `None` to stop reporting
+///
+/// The token secret is looked up when a status is reported, so it may be
+/// created afterwards.
+pub async fn set_commit_status(
+    pool: &PgPool,
+    id: Uuid,
+    config: Option<CommitStatusConfig>,
+) -> Result<Option<CommitStatusConfig>> {
+    if let Some(config) = &config {
+        validate_commit_status(config)?;
+    }
+
+    if !pipeline_repository::set_commit_status(pool, id, config.as_ref()).await? {
+        return Err(PipelineError::NotFound(id));
+    }
+
+    match &config {
+        Some(config) => tracing::info!(
+            "Pipeline {} reports commit statuses to {}",
+            id,
+            config.provider.as_str()
+        ),
+        None => tracing::info!("Pipeline {} stopped reporting commit statuses", id),
+    }
+
+    Ok(config)
+}
+
+/// Compute the analytics of a pipeline over its recent jobs
+///
+/// # Arguments
+/// * `query` - Days of history to cover and width of the trend buckets
+pub async fn get_stats(
+    pool: &PgPool,
+    id: Uuid,
+    query: PipelineStatsQuery,
+) -> Result<PipelineStats> {
+    let since = stats_since(query.days)?;
+    let bucket = query.bucket.unwrap_or(StatsBucket::Day);
+
+    if pipeline_repository::find_by_id(pool, id).await?.is_none() {
+        return Err(PipelineError::NotFound(id));
+    }
+
+    let counts = stats_repository::count_jobs(pool, id, since).await?;
+    let duration = stats_repository::durations(pool, id, since).await?;
+    let stage_failures = stats_repository::stage_failures(pool, id, since).await?;
+    let trend = stats_repository::trend(pool, id, since, bucket).await?;
+
+    let finished = counts.succeeded + counts.failed;
+    let success_rate = (finished > 0).then(|| counts.succeeded as f64 / finished as f64);
+
+    Ok(PipelineStats {
+        pipeline_id: id,
+        since,
+        jobs: counts.jobs,
+        succeeded: counts.succeeded,
+        failed: counts.failed,
+        cancelled: counts.cancelled,
+        success_rate,
+        duration,
+        stage_failures,
+        trend,
+    })
+}
+
+/// Find the stages of a pipeline whose outcome flips on an unchanged script
+///
+/// # Arguments
+/// * `query` - Days of history to cover and flips needed to flag a stage
+pub async fn find_flaky_stages(
+    pool: &PgPool,
+    id: Uuid,
+    query: FlakyStagesQuery,
+) -> Result<Vec<FlakyStage>> {
+    let since = stats_since(query.days)?;
+    let min_flips = query.min_flips.unwrap_or(DEFAULT_MIN_FLIPS);
+    if min_flips == 0 {
+        return Err(PipelineError::InvalidFields(vec![FieldError::new(
+            "min_flips",
+            "Must be at least 1",
+        )]));
+    }
+
+    if pipeline_repository::find_by_id(pool, id).await?.is_none() {
+        return Err(PipelineError::NotFound(id));
+    }
+
+    Ok(stats_repository::flaky_stages(pool, id, since, min_flips).await?)
+}
+
+/// Start of the period stats cover, `days` (default `DEFAULT_STATS_DAYS`) ago
+fn stats_since(days: Option<u32>) -> Result<chrono::DateTime<chrono::Utc>> {
+    let days = days.unwrap_or(DEFAULT_STATS_DAYS);
+    if !(1..=MAX_STATS_DAYS).contains(&days) {
+        return Err(PipelineError::InvalidFields(vec![FieldError::new(
+            "days",
+            format!("Must be between 1 and {}", MAX_STATS_DAYS),
+        )]));
+    }
+
+    Ok(chrono::Utc::now() - chrono::Duration::days(days.into()))
+}
+
+/// List the stages declared by a pipeline script, in execution order
+pub fn list_stages(pipeline: &Pipeline) -> Result<Vec<StageInfo>> {
+    let definition = parse_definition(pipeline)?;
+    Ok(definition
+        .stages
+        .iter()
+        .map(|stage| stage.to_info())
+        .collect())
+}
+
+/// Get the serializable parts of a pipeline script's definition
+///
+/// Lets UIs and tooling show a pipeline's inputs, stages, runner tags and
+/// plugins without evaluating Lua themselves.
+pub async fn get_definition(pool: &PgPool, id: Uuid) -> Result<PipelineDefinitionInfo> {
+    let pipeline = get_pipeline(pool, id).await?;
+    Ok(parse_definition(&pipeline)?.to_info())
+}
+
+/// Get the stage dependency graph of a pipeline
+///
+/// Shows the order stages run in, implicit dependencies included, before a
+/// job is launched.
+pub async fn get_graph(pool: &PgPool, id: Uuid) -> Result<PipelineGraph> {
+    let pipeline = get_pipeline(pool, id).await?;
+    Ok(PipelineGraph::from_stages(&list_stages(&pipeline)?))
+}
+
+/// Parse the definition of a stored pipeline script
+fn parse_definition(pipeline: &Pipeline) -> Result<PipelineDefinition> {
+    let lua = create_sandbox()
+        .map_err(|e| PipelineError::ValidationError(format!("Failed to create sandbox: {}", e)))?;
+
+    parse_pipeline_definition(&lua, &pipeline.script)
+        .map_err(|e| PipelineError::ValidationError(format!("Invalid pipeline definition: {}", e)))
 }
 
 // =============================================================================
 // Validation
 // =============================================================================
 
+fn validate_commit_status(config: &CommitStatusConfig) -> Result<()> {
+    let mut errors = Vec::new();
+    if config.token_secret.trim().is_empty() {
+        errors.push(FieldError::new("token_secret", "Must not be empty"));
+    }
+    if config.context.as_ref().is_some_and(|c| c.trim().is_empty()) {
+        errors.push(FieldError::new("context", "Must not be empty"));
+    }
+    let urls = [
+        ("api_url", config.api_url.as_ref()),
+        ("target_url", config.target_url.as_ref()),
+    ];
+    for (field, url) in urls {
+        if url.is_some_and(|url| !url.starts_with("http://") && !url.starts_with("https://")) {
+            errors.push(FieldError::new(field, "Must be an http(s) URL"));
+        }
+    }
+
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(PipelineError::InvalidFields(errors))
+    }
+}
+
 fn validate_pipeline_request(req: &CreatePipeline) -> Result<()> {
+    let invalid = |field: &str, message: String| {
+        PipelineError::InvalidFields(vec![FieldError::new(field, message)])
+    };
+
     if req.script.trim().is_empty() {
-        return Err(PipelineError::ValidationError(
+        return Err(invalid(
+            "script",
             "Pipeline script cannot be empty".to_string(),
         ));
     }
+    if req.script.len() > MAX_PIPELINE_SCRIPT_BYTES {
+        return Err(invalid(
+            "script",
+            format!(
+                "Pipeline script is {} bytes, the limit is {}",
+                req.script.len(),
+                MAX_PIPELINE_SCRIPT_BYTES
+            ),
+        ));
+    }
 
     // Validate pipeline structure using definition parser
     // This validates Lua syntax, pipeline structure, and required fields
     let lua = create_sandbox()
         .map_err(|e| PipelineError::ValidationError(format!("Failed to create sandbox: {}", e)))?;
 
-    let definition = parse_pipeline_definition(&lua, &req.script).map_err(|e| {
-        PipelineError::ValidationError(format!("Invalid pipeline definition: {}", e))
-    })?;
+    let definition = parse_pipeline_definition(&lua, &req.script)
+        .map_err(|e| invalid("script", format!("Invalid pipeline definition: {}", e)))?;
 
     // Verify at least one stage is defined
     if definition.stages.is_empty() {
-        return Err(PipelineError::ValidationError(
+        return Err(invalid(
+            "script",
             "Pipeline must have at least one stage".to_string(),
         ));
     }
@@ -0,0 +1,119 @@
+//! Preset Service
+//!
+//! Business logic for parameter presets: named parameter sets saved for a
+//! pipeline so that routine launches need not type them again. Their values
+//! are checked against the pipeline's inputs like input defaults, so secret
+//! and file inputs cannot be saved.
+
+use chrono::Utc;
+use rivet_core::domain::preset::{ParameterPreset, validate_preset_name};
+use rivet_core::dto::preset::SavePreset;
+use rivet_core::dto::validation::FieldError;
+use rivet_lua::{create_sandbox, parse_pipeline_definition};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::repository::{pipeline_repository, preset_repository};
+use crate::service::job_service::{prefix_fields, validate_input_values};
+
+/// Service error type
+#[derive(Debug)]
+pub enum PresetError {
+    NotFound(String),
+    PipelineNotFound(Uuid),
+    InvalidFields(Vec<FieldError>),
+    DatabaseError(sqlx::Error),
+}
+
+impl From<sqlx::Error> for PresetError {
+    fn from(err: sqlx::Error) -> Self {
+        PresetError::DatabaseError(err)
+    }
+}
+
+pub type Result<T> = std::result::Result<T, PresetError>;
+
+/// Save a preset of a pipeline, replacing one with the same name
+pub async fn save_preset(
+    pool: &PgPool,
+    pipeline_id: Uuid,
+    name: &str,
+    req: SavePreset,
+) -> Result<ParameterPreset> {
+    if let Err(message) = validate_preset_name(name) {
+        return Err(PresetError::InvalidFields(vec![FieldError::new(
+            "name", message,
+        )]));
+    }
+
+    let pipeline = pipeline_repository::find_by_id(pool, pipeline_id)
+        .await?
+        .ok_or(PresetError::PipelineNotFound(pipeline_id))?;
+
+    let definition = create_sandbox()
+        .map_err(|e| e.to_string())
+        .and_then(|lua| {
+            parse_pipeline_definition(&lua, &pipeline.script).map_err(|e| e.to_string())
+        })
+        .map_err(|e| {
+            PresetError::InvalidFields(vec![FieldError::new(
+                "parameters",
+                format!("Invalid pipeline definition: {}", e),
+            )])
+        })?;
+    let errors = prefix_fields(
+        "parameters",
+        validate_input_values(&definition.inputs, &req.parameters),
+    );
+    if !errors.is_empty() {
+        return Err(PresetError::InvalidFields(errors));
+    }
+
+    let now = Utc::now();
+    let preset = preset_repository::upsert(
+        pool,
+        &ParameterPreset {
+            pipeline_id,
+            name: name.to_string(),
+            parameters: req.parameters,
+            created_at: now,
+            updated_at: now,
+        },
+    )
+    .await?;
+
+    tracing::info!(
+        "Preset {} of pipeline {} saved ({} parameters)",
+        preset.name,
+        pipeline_id,
+        preset.parameters.len()
+    );
+
+    Ok(preset)
+}
+
+/// Get a preset of a pipeline by name
+pub async fn get_preset(pool: &PgPool, pipeline_id: Uuid, name: &str) -> Result<ParameterPreset> {
+    preset_repository::find(pool, pipeline_id, name)
+        .await?
+        .ok_or_else(|| PresetError::NotFound(name.to_string()))
+}
+
+/// List the presets of a pipeline
+pub async fn list_presets(pool: &PgPool, pipeline_id: Uuid) -> Result<Vec<ParameterPreset>> {
+    pipeline_repository::find_by_id(pool, pipeline_id)
+        .await?
+        .ok_or(PresetError::PipelineNotFound(pipeline_id))?;
+
+    Ok(preset_repository::list_by_pipeline(pool, pipeline_id).await?)
+}
+
+/// Remove a preset from a pipeline
+pub async fn delete_preset(pool: &PgPool, pipeline_id: Uuid, name: &str) -> Result<()> {
+    if !preset_repository::delete(pool, pipeline_id, name).await? {
+        return Err(PresetError::NotFound(name.to_string()));
+    }
+
+    tracing::info!("Preset {} removed from pipeline {}", name, pipeline_id);
+    Ok(())
+}
@@ -0,0 +1,131 @@
+//! Project Service
+//!
+//! Business logic for project management. Projects are namespaces: every
+//! pipeline (and through it every job) and every secret belongs to one.
+
+use rivet_core::domain::project::{DEFAULT_PROJECT_ID, Project};
+use rivet_core::dto::project::CreateProject;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::repository::project_repository;
+
+/// Service error type
+#[derive(Debug)]
+pub enum ProjectError {
+    NotFound(String),
+    ValidationError(String),
+    DatabaseError(sqlx::Error),
+}
+
+impl From<sqlx::Error> for ProjectError {
+    fn from(err: sqlx::Error) -> Self {
+        ProjectError::DatabaseError(err)
+    }
+}
+
+pub type Result<T> = std::result::Result<T, ProjectError>;
+
+/// Create a new project
+pub async fn create_project(pool: &PgPool, req: CreateProject) -> Result<Project> {
+    validate_project_name(&req.name)?;
+
+    if project_repository::find_by_name(pool, &req.name)
+        .await?
+        .is_some()
+    {
+        return Err(ProjectError::ValidationError(format!(
+            "Project '{}' already exists",
+            req.name
+        )));
+    }
+
+    let project = project_repository::create(pool, req).await?;
+
+    tracing::info!("Project created: {} ({})", project.name, project.id);
+
+    Ok(project)
+}
+
+/// Resolve a project from its id or its name
+pub async fn resolve_project(pool: &PgPool, project: &str) -> Result<Project> {
+    let found = match Uuid::parse_str(project) {
+        Ok(id) => project_repository::find_by_id(pool, id).await?,
+        Err(_) => project_repository::find_by_name(pool, project).await?,
+    };
+
+    found.ok_or_else(|| ProjectError::NotFound(project.to_string()))
+}
+
+/// List all projects
+pub async fn list_projects(pool: &PgPool) -> Result<Vec<Project>> {
+    let projects = project_repository::list_all(pool).await?;
+    Ok(projects)
+}
+
+/// Delete a project together with its pipelines, jobs and secrets
+///
+/// The default project cannot be deleted.
+pub async fn delete_project(pool: &PgPool, project: &str) -> Result<()> {
+    let project = resolve_project(pool, project).await?;
+
+    if project.id == DEFAULT_PROJECT_ID {
+        return Err(ProjectError::ValidationError(
+            "The default project cannot be deleted".to_string(),
+        ));
+    }
+
+    if !project_repository::delete(pool, project.id).await? {
+        return Err(ProjectError::NotFound(project.name));
+    }
+
+    tracing::info!("Project deleted: {} ({})", project.name, project.id);
+
+    Ok(())
+}
+
+// =============================================================================
+// Validation
+// =============================================================================
+
+/// Project names appear in URLs: lowercase letters, digits and '-', not a UUID
+fn validate_project_name(name: &str) -> Result<()> {
+    if name.is_empty() || name.len() > 64 {
+        return Err(ProjectError::ValidationError(
+            "Project name must be between 1 and 64 characters".to_string(),
+        ));
+    }
+
+    let valid = name
+        .chars()
+        .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-')
+        && !name.starts_with('-')
+        && !name.ends_with('-');
+
+    if !valid || Uuid::parse_str(name).is_ok() {
+        return Err(ProjectError::ValidationError(format!(
+            "Invalid project name '{}' (allowed: lowercase letters, digits and inner '-')",
+            name
+        )));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_project_name() {
+        assert!(validate_project_name("default").is_ok());
+        assert!(validate_project_name("team-a2").is_ok());
+
+        assert!(validate_project_name("").is_err());
+        assert!(validate_project_name("Team").is_err());
+        assert!(validate_project_name("team_a").is_err());
+        assert!(validate_project_name("-team").is_err());
+        assert!(validate_project_name(&"a".repeat(65)).is_err());
+        assert!(validate_project_name(&Uuid::new_v4().to_string()).is_err());
+    }
+}
@@ -1,10 +1,23 @@
 //! Runner Service
 //!
 //! Business logic for runner management.
+//!
+//! Runners register with a registration token minted by an admin and receive
+//! a per-runner credential in return, which identifies them on job calls.
+//! Only SHA-256 hashes of tokens and credentials are stored.
+//!
+//! Registration is also a version handshake: runners speaking a protocol
+//! version the orchestrator does not serve are turned away.
 
-use rivet_core::domain::runner::Runner;
-use rivet_core::dto::runner::RegisterRunner;
+use rivet_core::domain::runner::RunnerInfo;
+use rivet_core::domain::runner::{RegistrationToken, Runner, RunnerLoad};
+use rivet_core::dto::runner::{
+    CreateRegistrationToken, MIN_RUNNER_PROTOCOL_VERSION, NewRegistrationToken,
+    RUNNER_PROTOCOL_VERSION, RegisterRunner, RunnerRegistration,
+};
+use sha2::{Digest, Sha256};
 use sqlx::PgPool;
+use uuid::Uuid;
 
 use crate::repository::runner_repository;
 
@@ -12,7 +25,11 @@ use crate::repository::runner_repository;
 #[derive(Debug)]
 pub enum RunnerError {
     NotFound(String),
+    TokenNotFound(Uuid),
+    Unauthorized(String),
     ValidationError(String),
+    /// The runner speaks a protocol version the orchestrator does not serve
+    IncompatibleVersion(String),
     DatabaseError(sqlx::Error),
 }
 
@@ -27,24 +44,86 @@ pub type Result<T> = std::result::Result<T, RunnerError>;
 /// Register a runner with the orchestrator
 ///
 /// This creates a new runner entry or updates an existing one.
-/// When a runner re-registers, it updates its heartbeat.
-pub async fn register_runner(pool: &PgPool, req: RegisterRunner) -> Result<Runner> {
+/// When a runner re-registers, it updates its heartbeat and receives a new
+/// credential, invalidating the previous one. Runners reporting a protocol
+/// version outside `MIN_RUNNER_PROTOCOL_VERSION..=RUNNER_PROTOCOL_VERSION`
+/// are refused; runners reporting none predate the handshake and are accepted.
+///
+/// # Arguments
+/// * `pool` - Database connection pool
+/// * `req` - Registration request
+/// * `require_token` - Whether registration without a token is refused
+pub async fn register_runner(
+    pool: &PgPool,
+    req: RegisterRunner,
+    require_token: bool,
+) -> Result<RunnerRegistration> {
     // Validate request
     validate_register_request(&req)?;
+    if let Some(info) = &req.info {
+        check_compatibility(info)?;
+    }
+
+    match req.registration_token.as_deref() {
+        Some(token)
+            if !runner_repository::registration_token_exists(pool, &hash_secret(token)).await? =>
+        {
+            return Err(RunnerError::Unauthorized(
+                "Invalid registration token".to_string(),
+            ));
+        }
+        None if require_token => {
+            return Err(RunnerError::Unauthorized(
+                "A registration token is required to register runners".to_string(),
+            ));
+        }
+        _ => {}
+    }
 
     // Register runner in database
-    let runner = runner_repository::register(pool, req).await?;
+    let credential = generate_secret("rrc");
+    let runner = runner_repository::register(pool, req, &hash_secret(&credential)).await?;
 
-    tracing::info!("Runner registered: {}", runner.id);
+    match &runner.info {
+        Some(info) => tracing::info!(
+            "Runner registered: {} (rivet-runner {}, {}/{}, {}, protocol {})",
+            runner.id,
+            info.version,
+            info.os,
+            info.arch,
+            info.hostname,
+            info.protocol_version
+        ),
+        None => tracing::info!("Runner registered: {} (no version reported)", runner.id),
+    }
 
-    Ok(runner)
+    Ok(RunnerRegistration {
+        runner,
+        credential: Some(credential),
+    })
+}
+
+/// Resolve the runner a credential belongs to
+///
+/// # Returns
+/// The runner ID
+pub async fn authenticate_runner(pool: &PgPool, credential: &str) -> Result<String> {
+    runner_repository::find_id_by_credential_hash(pool, &hash_secret(credential))
+        .await?
+        .ok_or_else(|| RunnerError::Unauthorized("Invalid runner credential".to_string()))
 }
 
 /// Update heartbeat for a runner
 ///
-/// Keeps the runner marked as online. Should be called periodically by runners.
-pub async fn update_heartbeat(pool: &PgPool, runner_id: &str) -> Result<()> {
-    let updated = runner_repository::update_heartbeat(pool, runner_id).await?;
+/// Keeps the runner marked as online and records the load it reports, which
+/// the scheduler uses to prefer the least loaded runners. Should be called
+/// periodically by runners.
+pub async fn update_heartbeat(
+    pool: &PgPool,
+    runner_id: &str,
+    load: Option<&RunnerLoad>,
+) -> Result<()> {
+    let updated = runner_repository::update_heartbeat(pool, runner_id, load).await?;
 
     if !updated {
         return Err(RunnerError::NotFound(runner_id.to_string()));
@@ -83,6 +162,53 @@ pub async fn delete_runner(pool: &PgPool, id: &str) -> Result<()> {
     Ok(())
 }
 
+// =============================================================================
+// Registration Tokens
+// =============================================================================
+
+/// Mint a new registration token
+///
+/// The returned secret is not stored and cannot be retrieved again.
+pub async fn create_registration_token(
+    pool: &PgPool,
+    req: CreateRegistrationToken,
+) -> Result<NewRegistrationToken> {
+    let description = req
+        .description
+        .map(|d| d.trim().to_string())
+        .filter(|d| !d.is_empty());
+
+    let token = generate_secret("rrt");
+    let info =
+        runner_repository::create_registration_token(pool, description, &hash_secret(&token))
+            .await?;
+
+    tracing::info!("Registration token created: {}", info.id);
+
+    Ok(NewRegistrationToken { info, token })
+}
+
+/// List registration tokens (without their secrets)
+pub async fn list_registration_tokens(pool: &PgPool) -> Result<Vec<RegistrationToken>> {
+    let tokens = runner_repository::list_registration_tokens(pool).await?;
+    Ok(tokens)
+}
+
+/// Revoke a registration token
+///
+/// Runners that already registered with it keep their credentials.
+pub async fn delete_registration_token(pool: &PgPool, id: Uuid) -> Result<()> {
+    let deleted = runner_repository::delete_registration_token(pool, id).await?;
+
+    if !deleted {
+        return Err(RunnerError::TokenNotFound(id));
+    }
+
+    tracing::info!("Registration token revoked: {}", id);
+
+    Ok(())
+}
+
 /// Mark stale runners as offline
 ///
 /// This should be called periodically to mark runners that haven't
@@ -91,20 +217,67 @@ pub async fn delete_runner(pool: &PgPool, id: &str) -> Result<()> {
 /// # Arguments
 /// * `pool` - Database connection pool
 /// * `timeout_seconds` - How long to wait before marking a runner as offline
-pub async fn mark_stale_runners_offline(pool: &PgPool, timeout_seconds: i64) -> Result<u64> {
-    let count = runner_repository::mark_stale_runners_offline(pool, timeout_seconds).await?;
+///
+/// # Returns
+/// IDs of the runners that were marked offline
+pub async fn mark_stale_runners_offline(
+    pool: &PgPool,
+    timeout_seconds: i64,
+) -> Result<Vec<String>> {
+    let runner_ids = runner_repository::mark_stale_runners_offline(pool, timeout_seconds).await?;
 
-    if count > 0 {
-        tracing::info!("Marked {} runner(s) as offline", count);
+    if !runner_ids.is_empty() {
+        tracing::info!("Marked {} runner(s) as offline", runner_ids.len());
     }
 
-    Ok(count)
+    Ok(runner_ids)
 }
 
 // =============================================================================
 // Validation
 // =============================================================================
 
+// =============================================================================
+// Secrets
+// =============================================================================
+
+/// Generates a random secret such as `rrt_3f2b...` (64 hex characters)
+fn generate_secret(prefix: &str) -> String {
+    format!(
+        "{}_{}{}",
+        prefix,
+        Uuid::new_v4().simple(),
+        Uuid::new_v4().simple()
+    )
+}
+
+/// Hex encoded SHA-256 of a secret, as stored in the database
+fn hash_secret(secret: &str) -> String {
+    hex::encode(Sha256::digest(secret.as_bytes()))
+}
+
+/// Refuses runners speaking a protocol version this orchestrator does not serve
+fn check_compatibility(info: &RunnerInfo) -> Result<()> {
+    let version = info.protocol_version;
+    let advice = if version < MIN_RUNNER_PROTOCOL_VERSION {
+        "upgrade the runner"
+    } else if version > RUNNER_PROTOCOL_VERSION {
+        "upgrade the orchestrator"
+    } else {
+        return Ok(());
+    };
+
+    Err(RunnerError::IncompatibleVersion(format!(
+        "rivet-runner {} speaks runner protocol {}, but orchestrator {} supports protocols {} to {}; {}",
+        info.version,
+        version,
+        env!("CARGO_PKG_VERSION"),
+        MIN_RUNNER_PROTOCOL_VERSION,
+        RUNNER_PROTOCOL_VERSION,
+        advice
+    )))
+}
+
 fn validate_register_request(req: &RegisterRunner) -> Result<()> {
     if req.runner_id.trim().is_empty() {
         return Err(RunnerError::ValidationError(
@@ -120,3 +293,40 @@ fn validate_register_request(req: &RegisterRunner) -> Result<()> {
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_secrets_are_unique_and_hashed() {
+        let a = generate_secret("rrc");
+        let b = generate_secret("rrc");
+
+        assert!(a.starts_with("rrc_"));
+        assert_eq!(a.len(), 4 + 64);
+        assert_ne!(a, b);
+
+        assert_eq!(hash_secret(&a), hash_secret(&a));
+        assert_ne!(hash_secret(&a), hash_secret(&b));
+        assert_eq!(hash_secret(&a).len(), 64);
+    }
+
+    #[test]
+    fn test_protocol_compatibility() {
+        let info = |protocol_version| RunnerInfo {
+            version: "0.1.0".to_string(),
+            protocol_version,
+            ..Default::default()
+        };
+
+        assert!(check_compatibility(&info(RUNNER_PROTOCOL_VERSION)).is_ok());
+        let Err(RunnerError::IncompatibleVersion(msg)) =
+            check_compatibility(&info(RUNNER_PROTOCOL_VERSION + 1))
+        else {
+            panic!("newer protocols must be refused");
+        };
+        assert!(msg.ends_with("upgrade the orchestrator"));
+        assert!(check_compatibility(&info(MIN_RUNNER_PROTOCOL_VERSION - 1)).is_err());
+    }
+}
@@ -0,0 +1,453 @@
+//! Schedule Service
+//!
+//! Business logic for cron schedules: each schedule launches a job of its
+//! pipeline at the times its cron expression matches, evaluated in the
+//! schedule's time zone. Times missed while the orchestrator was down are
+//! handled by the schedule's catch-up policy.
+
+use std::str::FromStr;
+
+use chrono::{DateTime, Utc};
+use chrono_tz::Tz;
+use croner::Cron;
+use rivet_core::domain::job::Job;
+use rivet_core::domain::schedule::{CatchUpPolicy, Schedule};
+use rivet_core::dto::job::CreateJob;
+use rivet_core::dto::schedule::CreateSchedule;
+use rivet_core::dto::validation::FieldError;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::repository::{pipeline_repository, schedule_repository};
+use crate::service::job_service::{self, JobError};
+
+/// Most jobs a schedule launches at once for the times it missed; the most
+/// recent ones are kept
+pub const MAX_CATCH_UP_RUNS: usize = 100;
+
+/// How late a run may be launched and still count as on time rather than
+/// missed (the scheduler checks schedules more often than that)
+pub const MISSED_AFTER: chrono::Duration = chrono::Duration::minutes(2);
+
+/// Service error type
+#[derive(Debug)]
+pub enum ScheduleError {
+    NotFound(Uuid),
+    PipelineNotFound(Uuid),
+    InvalidFields(Vec<FieldError>),
+    DatabaseError(sqlx::Error),
+}
+
+impl From<sqlx::Error> for ScheduleError {
+    fn from(err: sqlx::Error) -> Self {
+        ScheduleError::DatabaseError(err)
+    }
+}
+
+pub type Result<T> = std::result::Result<T, ScheduleError>;
+
+/// Add a schedule to a pipeline
+pub async fn create_schedule(
+    pool: &PgPool,
+    pipeline_id: Uuid,
+    req: CreateSchedule,
+) -> Result<Schedule> {
+    let timezone = req.timezone.as_deref().unwrap_or("UTC").trim().to_string();
+    let compiled =
+        CompiledSchedule::new(&req.cron, &timezone).map_err(ScheduleError::InvalidFields)?;
+
+    pipeline_repository::find_by_id(pool, pipeline_id)
+        .await?
+        .ok_or(ScheduleError::PipelineNotFound(pipeline_id))?;
+
+    let now = Utc::now();
+    let next_run_at = compiled.next_after(now).ok_or_else(|| {
+        ScheduleError::InvalidFields(vec![FieldError::new("cron", "Never matches")])
+    })?;
+    let schedule = Schedule {
+        id: Uuid::new_v4(),
+        pipeline_id,
+        cron: req.cron.trim().to_string(),
+        timezone,
+        catch_up: req.catch_up,
+        parameters: req.parameters,
+        next_run_at,
+        last_run_at: None,
+        created_at: now,
+    };
+    schedule_repository::create(pool, &schedule).await?;
+
+    tracing::info!(
+        "Schedule {} added to pipeline {} ({} {}), next run at {}",
+        schedule.id,
+        pipeline_id,
+        schedule.cron,
+        schedule.timezone,
+        schedule.next_run_at
+    );
+
+    Ok(schedule)
+}
+
+/// List the schedules of a pipeline
+pub async fn list_schedules(pool: &PgPool, pipeline_id: Uuid) -> Result<Vec<Schedule>> {
+    pipeline_repository::find_by_id(pool, pipeline_id)
+        .await?
+        .ok_or(ScheduleError::PipelineNotFound(pipeline_id))?;
+
+    Ok(schedule_repository::list_by_pipeline(pool, pipeline_id).await?)
+}
+
+/// Remove a schedule from a pipeline
+pub async fn delete_schedule(pool: &PgPool, pipeline_id: Uuid, id: Uuid) -> Result<()> {
+    if !schedule_repository::delete(pool, pipeline_id, id).await? {
+        return Err(ScheduleError::NotFound(id));
+    }
+
+    tracing::info!("Schedule {} removed from pipeline {}", id, pipeline_id);
+    Ok(())
+}
+
+/// Launch the jobs of the schedules that are due
+///
+/// Each due schedule launches the runs its catch-up policy keeps, then moves
+/// on to its first time after now. A schedule whose runs another caller
+/// already handled is left alone, so only one orchestrator launches them.
+/// A schedule whose cron expression or time zone no longer compiles (e.g.
+/// after a time zone was dropped from the database) is parked instead, and
+/// does not run again until it is replaced.
+///
+/// # Returns
+/// The launched jobs
+pub async fn run_due_schedules(pool: &PgPool) -> Result<Vec<Job>> {
+    let now = Utc::now();
+    let mut launched = Vec::new();
+
+    for schedule in schedule_repository::find_due(pool).await? {
+        let compiled = match CompiledSchedule::new(&schedule.cron, &schedule.timezone) {
+            Ok(compiled) => compiled,
+            Err(errors) => {
+                // Parked until replaced, rather than warned about every tick
+                let parked = schedule_repository::advance(
+                    pool,
+                    schedule.id,
+                    schedule.next_run_at,
+                    parked_at(),
+                    None,
+                )
+                .await?;
+                if parked {
+                    tracing::warn!(
+                        "Schedule {} no longer compiles, disabling it: {:?}",
+                        schedule.id,
+                        errors
+                    );
+                }
+                continue;
+            }
+        };
+        let plan = compiled.plan_runs(schedule.next_run_at, now, schedule.catch_up);
+        // A schedule that never matches again is parked far in the future
+        let next_run_at = plan.next_run_at.unwrap_or_else(parked_at);
+
+        let ran_at = (!plan.runs.is_empty()).then_some(now);
+        if !schedule_repository::advance(
+            pool,
+            schedule.id,
+            schedule.next_run_at,
+            next_run_at,
+            ran_at,
+        )
+        .await?
+        {
+            continue;
+        }
+
+        if plan.missed > 0 {
+            tracing::warn!(
+                "Schedule {} missed {} run(s) since {}, launching {} ({})",
+                schedule.id,
+                plan.missed,
+                schedule.next_run_at,
+                plan.runs.len(),
+                schedule.catch_up.as_str()
+            );
+        }
+
+        for scheduled_for in plan.runs {
+            match launch(pool, &schedule).await {
+                Ok(job) => {
+                    tracing::info!(
+                        "Schedule {} launched job {} for {}",
+                        schedule.id,
+                        job.id,
+                        scheduled_for
+                    );
+                    launched.push(job);
+                }
+                Err(e) => tracing::warn!(
+                    "Schedule {} failed to launch a job for {}: {:?}",
+                    schedule.id,
+                    scheduled_for,
+                    e
+                ),
+            }
+        }
+    }
+
+    Ok(launched)
+}
+
+/// Next run time of a schedule that will not run again
+fn parked_at() -> DateTime<Utc> {
+    DateTime::<Utc>::MAX_UTC - chrono::Duration::days(1)
+}
+
+async fn launch(pool: &PgPool, schedule: &Schedule) -> std::result::Result<Job, JobError> {
+    job_service::launch_scheduled_job(
+        pool,
+        CreateJob {
+            pipeline_id: schedule.pipeline_id,
+            parameters: schedule.parameters.clone(),
+            plan: false,
+            commit: None,
+            override_blackout: false,
+            labels: Default::default(),
+        },
+        schedule.id,
+    )
+    .await
+}
+
+// =============================================================================
+// Cron Evaluation
+// =============================================================================
+
+/// A schedule's cron expression, ready to be evaluated in its time zone
+struct CompiledSchedule {
+    cron: Cron,
+    timezone: Tz,
+}
+
+/// Runs a due schedule launches
+#[derive(Debug, PartialEq)]
+struct RunPlan {
+    /// Times to launch a job for, oldest first
+    runs: Vec<DateTime<Utc>>,
+    /// Due times that were missed rather than merely reached
+    missed: usize,
+    /// First time after now, `None` if the expression never matches again
+    next_run_at: Option<DateTime<Utc>>,
+}
+
+impl CompiledSchedule {
+    /// Parse a cron expression and an IANA time zone
+    fn new(cron: &str, timezone: &str) -> std::result::Result<Self, Vec<FieldError>> {
+        let mut errors = Vec::new();
+        let parsed = Cron::new(cron.trim())
+            .parse()
+            .map_err(|e| errors.push(FieldError::new("cron", e.to_string())));
+        let zone = Tz::from_str(timezone).map_err(|_| {
+            errors.push(FieldError::new(
+                "timezone",
+                format!("Unknown time zone '{}'", timezone),
+            ))
+        });
+
+        match (parsed, zone) {
+            (Ok(cron), Ok(timezone)) => Ok(CompiledSchedule { cron, timezone }),
+            _ => Err(errors),
+        }
+    }
+
+    /// First time the expression matches strictly after `after`
+    ///
+    /// Wall-clock times skipped by a daylight saving change move to the end
+    /// of the gap; times repeated by one match once, at their first instance.
+    fn next_after(&self, after: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        let mut from = after.with_timezone(&self.timezone);
+        // Within a repeated hour, the search may land on the first instance
+        // of a wall-clock time already passed; step past the fold
+        for _ in 0..=60 {
+            let next = self
+                .cron
+                .find_next_occurrence(&from, false)
+                .ok()?
+                .with_timezone(&Utc);
+            if next > after {
+                return Some(next);
+            }
+            from += chrono::Duration::minutes(1);
+        }
+        None
+    }
+
+    /// Runs to launch for a schedule due at `due_at`, as of `now`
+    fn plan_runs(
+        &self,
+        due_at: DateTime<Utc>,
+        now: DateTime<Utc>,
+        policy: CatchUpPolicy,
+    ) -> RunPlan {
+        let is_missed = |time: &DateTime<Utc>| now - *time > MISSED_AFTER;
+        let mut due = vec![due_at];
+        let mut missed = usize::from(is_missed(&due_at));
+        let mut next_run_at = self.next_after(due_at);
+        while let Some(time) = next_run_at.filter(|time| *time <= now) {
+            missed += usize::from(is_missed(&time));
+            due.push(time);
+            if due.len() > MAX_CATCH_UP_RUNS {
+                due.remove(0);
+            }
+            next_run_at = self.next_after(time);
+        }
+
+        let latest = due.last().copied();
+        let runs = match policy {
+            CatchUpPolicy::Skip => latest.filter(|time| !is_missed(time)).into_iter().collect(),
+            CatchUpPolicy::RunOnce => latest.into_iter().collect(),
+            CatchUpPolicy::RunAll => due,
+        };
+
+        RunPlan {
+            runs,
+            missed,
+            next_run_at,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn utc(time: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(time)
+            .unwrap()
+            .with_timezone(&Utc)
+    }
+
+    #[test]
+    fn test_rejects_invalid_expressions_and_time_zones() {
+        let errors = CompiledSchedule::new("61 * * * *", "Mars/Olympus")
+            .err()
+            .unwrap();
+        let fields: Vec<&str> = errors.iter().map(|e| e.field.as_str()).collect();
+        assert_eq!(fields, ["cron", "timezone"]);
+
+        assert!(CompiledSchedule::new("0 3 * * *", "Europe/Madrid").is_ok());
+        assert!(CompiledSchedule::new("@daily", "UTC").is_ok());
+    }
+
+    #[test]
+    fn test_local_times_follow_daylight_saving() {
+        let schedule = CompiledSchedule::new("0 3 * * *", "Europe/Madrid").unwrap();
+
+        // 03:00 CET (UTC+1) in winter, 03:00 CEST (UTC+2) in summer
+        assert_eq!(
+            schedule.next_after(utc("2026-01-10T12:00:00Z")),
+            Some(utc("2026-01-11T02:00:00Z"))
+        );
+        assert_eq!(
+            schedule.next_after(utc("2026-07-10T12:00:00Z")),
+            Some(utc("2026-07-11T01:00:00Z"))
+        );
+        // Clocks go from 02:00 to 03:00 on 2026-03-29 and from 03:00 back to
+        // 02:00 on 2026-10-25
+        assert_eq!(
+            schedule.next_after(utc("2026-03-28T12:00:00Z")),
+            Some(utc("2026-03-29T01:00:00Z"))
+        );
+        assert_eq!(
+            schedule.next_after(utc("2026-10-24T12:00:00Z")),
+            Some(utc("2026-10-25T02:00:00Z"))
+        );
+    }
+
+    #[test]
+    fn test_skipped_and_repeated_times_run_once() {
+        let schedule = CompiledSchedule::new("30 2 * * *", "Europe/Madrid").unwrap();
+
+        // 02:30 does not exist on 2026-03-29: runs at 03:00 CEST instead
+        assert_eq!(
+            schedule.next_after(utc("2026-03-28T12:00:00Z")),
+            Some(utc("2026-03-29T01:00:00Z"))
+        );
+        // 02:30 happens twice on 2026-10-25: runs at the first one only
+        let first = schedule.next_after(utc("2026-10-24T12:00:00Z")).unwrap();
+        assert_eq!(first, utc("2026-10-25T00:30:00Z"));
+        assert_eq!(
+            schedule.next_after(first),
+            Some(utc("2026-10-26T01:30:00Z"))
+        );
+    }
+
+    #[test]
+    fn test_catch_up_policies() {
+        let schedule = CompiledSchedule::new("0 * * * *", "UTC").unwrap();
+        let due_at = utc("2026-05-01T08:00:00Z");
+        let now = utc("2026-05-01T11:30:00Z");
+        let next_run_at = Some(utc("2026-05-01T12:00:00Z"));
+
+        assert_eq!(
+            schedule.plan_runs(due_at, now, CatchUpPolicy::Skip),
+            RunPlan {
+                runs: vec![],
+                missed: 4,
+                next_run_at,
+            }
+        );
+        assert_eq!(
+            schedule.plan_runs(due_at, now, CatchUpPolicy::RunOnce),
+            RunPlan {
+                runs: vec![utc("2026-05-01T11:00:00Z")],
+                missed: 4,
+                next_run_at,
+            }
+        );
+        assert_eq!(
+            schedule.plan_runs(due_at, now, CatchUpPolicy::RunAll).runs,
+            [
+                utc("2026-05-01T08:00:00Z"),
+                utc("2026-05-01T09:00:00Z"),
+                utc("2026-05-01T10:00:00Z"),
+                utc("2026-05-01T11:00:00Z"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_on_time_runs_are_not_missed() {
+        let schedule = CompiledSchedule::new("*/5 * * * *", "UTC").unwrap();
+        let due_at = utc("2026-05-01T08:05:00Z");
+        let now = utc("2026-05-01T08:05:20Z");
+
+        for policy in [
+            CatchUpPolicy::Skip,
+            CatchUpPolicy::RunOnce,
+            CatchUpPolicy::RunAll,
+        ] {
+            assert_eq!(
+                schedule.plan_runs(due_at, now, policy),
+                RunPlan {
+                    runs: vec![due_at],
+                    missed: 0,
+                    next_run_at: Some(utc("2026-05-01T08:10:00Z")),
+                }
+            );
+        }
+    }
+
+    #[test]
+    fn test_run_all_keeps_the_most_recent_runs() {
+        let schedule = CompiledSchedule::new("* * * * *", "UTC").unwrap();
+        let due_at = utc("2026-05-01T00:00:00Z");
+        let now = utc("2026-05-01T10:00:00Z");
+
+        let plan = schedule.plan_runs(due_at, now, CatchUpPolicy::RunAll);
+        assert_eq!(plan.runs.len(), MAX_CATCH_UP_RUNS);
+        assert_eq!(plan.missed, 598);
+        assert_eq!(plan.runs.last(), Some(&now));
+        assert_eq!(plan.next_run_at, Some(utc("2026-05-01T10:01:00Z")));
+    }
+}
@@ -0,0 +1,141 @@
+//! Secret Service
+//!
+//! Business logic for secret management. Secret values are write-only through
+//! this service: they can be set and deleted, never read back.
+
+use rivet_core::domain::secret::Secret;
+use rivet_core::dto::secret::SetSecret;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::repository::{pipeline_repository, secret_repository};
+
+/// Service error type
+#[derive(Debug)]
+pub enum SecretError {
+    NotFound(String),
+    PipelineNotFound(Uuid),
+    ValidationError(String),
+    DatabaseError(sqlx::Error),
+}
+
+impl From<sqlx::Error> for SecretError {
+    fn from(err: sqlx::Error) -> Self {
+        SecretError::DatabaseError(err)
+    }
+}
+
+pub type Result<T> = std::result::Result<T, SecretError>;
+
+/// Create or replace a secret in a project
+pub async fn set_secret(pool: &PgPool, project_id: Uuid, req: SetSecret) -> Result<Secret> {
+    validate_set_request(&req)?;
+
+    if let Some(pipeline_id) = req.pipeline_id {
+        ensure_pipeline_exists(pool, project_id, pipeline_id).await?;
+    }
+
+    let secret =
+        secret_repository::upsert(pool, project_id, &req.name, &req.value, req.pipeline_id).await?;
+
+    tracing::info!(
+        "Secret set: {} (scope: {})",
+        secret.name,
+        scope_label(req.pipeline_id)
+    );
+
+    Ok(secret)
+}
+
+/// List secrets in a scope of a project
+pub async fn list_secrets(
+    pool: &PgPool,
+    project_id: Uuid,
+    pipeline_id: Option<Uuid>,
+) -> Result<Vec<Secret>> {
+    if let Some(pipeline_id) = pipeline_id {
+        ensure_pipeline_exists(pool, project_id, pipeline_id).await?;
+    }
+
+    let secrets = secret_repository::list_by_scope(pool, project_id, pipeline_id).await?;
+    Ok(secrets)
+}
+
+/// Delete a secret from a scope of a project
+pub async fn delete_secret(
+    pool: &PgPool,
+    project_id: Uuid,
+    name: &str,
+    pipeline_id: Option<Uuid>,
+) -> Result<()> {
+    let deleted = secret_repository::delete(pool, project_id, name, pipeline_id).await?;
+
+    if !deleted {
+        return Err(SecretError::NotFound(name.to_string()));
+    }
+
+    tracing::info!(
+        "Secret deleted: {} (scope: {})",
+        name,
+        scope_label(pipeline_id)
+    );
+
+    Ok(())
+}
+
+/// Pipelines of other projects are reported as not found
+async fn ensure_pipeline_exists(pool: &PgPool, project_id: Uuid, pipeline_id: Uuid) -> Result<()> {
+    pipeline_repository::find_by_id(pool, pipeline_id)
+        .await?
+        .filter(|pipeline| pipeline.project_id == project_id)
+        .ok_or(SecretError::PipelineNotFound(pipeline_id))?;
+    Ok(())
+}
+
+fn scope_label(pipeline_id: Option<Uuid>) -> String {
+    match pipeline_id {
+        Some(id) => format!("pipeline {}", id),
+        None => "project".to_string(),
+    }
+}
+
+// =============================================================================
+// Validation
+// =============================================================================
+
+fn validate_set_request(req: &SetSecret) -> Result<()> {
+    const MAX_VALUE_LENGTH: usize = 64 * 1024;
+
+    if req.name.is_empty() || req.name.len() > 255 {
+        return Err(SecretError::ValidationError(
+            "Secret name must be between 1 and 255 characters".to_string(),
+        ));
+    }
+
+    // Secrets are exposed to pipelines by name, keep names identifier-like
+    if !req
+        .name
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-' || c == '.')
+    {
+        return Err(SecretError::ValidationError(format!(
+            "Invalid secret name '{}' (allowed: letters, digits, '_', '-', '.')",
+            req.name
+        )));
+    }
+
+    if req.value.is_empty() {
+        return Err(SecretError::ValidationError(
+            "Secret value cannot be empty".to_string(),
+        ));
+    }
+
+    if req.value.len() > MAX_VALUE_LENGTH {
+        return Err(SecretError::ValidationError(format!(
+            "Secret value too long (max: {} bytes)",
+            MAX_VALUE_LENGTH
+        )));
+    }
+
+    Ok(())
+}
@@ -0,0 +1,678 @@
+//! Webhook Service
+//!
+//! Business logic for webhook triggers: the push events GitHub and GitLab
+//! deliver are verified against the trigger's secret, parsed, matched against
+//! its filters and, when they match, launch a job of its pipeline for the
+//! pushed commit.
+
+use std::collections::HashMap;
+
+use globset::{GlobBuilder, GlobMatcher};
+use hmac::{Hmac, Mac};
+use rivet_core::domain::job::{CommitRef, Job};
+use rivet_core::domain::webhook::{TriggerFilters, WebhookTrigger};
+use rivet_core::dto::commit_status::GitProvider;
+use rivet_core::dto::job::CreateJob;
+use rivet_core::dto::validation::FieldError;
+use rivet_core::dto::webhook::CreateWebhookTrigger;
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::repository::{pipeline_repository, secret_repository, webhook_repository};
+use crate::service::job_service::{self, JobError};
+
+/// Service error type
+#[derive(Debug)]
+pub enum WebhookError {
+    NotFound(Uuid),
+    PipelineNotFound(Uuid),
+    /// The delivery is not signed with the trigger's secret
+    Unauthorized(String),
+    ValidationError(String),
+    InvalidFields(Vec<FieldError>),
+    /// The matching push could not launch a job
+    LaunchFailed(JobError),
+    DatabaseError(sqlx::Error),
+}
+
+impl From<sqlx::Error> for WebhookError {
+    fn from(err: sqlx::Error) -> Self {
+        WebhookError::DatabaseError(err)
+    }
+}
+
+pub type Result<T> = std::result::Result<T, WebhookError>;
+
+/// A delivery from a Git provider, as received
+#[derive(Debug, Clone, Copy)]
+pub struct Delivery<'a> {
+    /// Event name (`X-GitHub-Event` or `X-Gitlab-Event`)
+    pub event: Option<&'a str>,
+    /// Proof of the secret (`X-Hub-Signature-256` or `X-Gitlab-Token`)
+    pub signature: Option<&'a str>,
+    pub body: &'a [u8],
+}
+
+/// What a delivery led to
+#[derive(Debug)]
+pub enum DeliveryOutcome {
+    Launched(Box<Job>),
+    /// No job was launched, for the given reason
+    Skipped(String),
+}
+
+/// Add a webhook trigger to a pipeline
+pub async fn create_trigger(
+    pool: &PgPool,
+    pipeline_id: Uuid,
+    req: CreateWebhookTrigger,
+) -> Result<WebhookTrigger> {
+    validate_trigger_request(&req)?;
+
+    pipeline_repository::find_by_id(pool, pipeline_id)
+        .await?
+        .ok_or(WebhookError::PipelineNotFound(pipeline_id))?;
+
+    let trigger = webhook_repository::create(pool, pipeline_id, req).await?;
+
+    tracing::info!(
+        "Webhook trigger {} added to pipeline {} ({})",
+        trigger.id,
+        pipeline_id,
+        trigger.provider.as_str()
+    );
+
+    Ok(trigger)
+}
+
+/// List the webhook triggers of a pipeline
+pub async fn list_triggers(pool: &PgPool, pipeline_id: Uuid) -> Result<Vec<WebhookTrigger>> {
+    pipeline_repository::find_by_id(pool, pipeline_id)
+        .await?
+        .ok_or(WebhookError::PipelineNotFound(pipeline_id))?;
+
+    Ok(webhook_repository::list_by_pipeline(pool, pipeline_id).await?)
+}
+
+/// Remove a webhook trigger from a pipeline
+pub async fn delete_trigger(pool: &PgPool, pipeline_id: Uuid, id: Uuid) -> Result<()> {
+    if !webhook_repository::delete(pool, pipeline_id, id).await? {
+        return Err(WebhookError::NotFound(id));
+    }
+
+    tracing::info!(
+        "Webhook trigger {} removed from pipeline {}",
+        id,
+        pipeline_id
+    );
+    Ok(())
+}
+
+/// Handle a delivery to a webhook trigger
+///
+/// The delivery must prove the trigger's secret. Events other than pushes,
+/// pushes deleting their ref and pushes the filters reject are skipped.
+pub async fn handle_delivery(
+    pool: &PgPool,
+    trigger_id: Uuid,
+    delivery: Delivery<'_>,
+) -> Result<DeliveryOutcome> {
+    let trigger = webhook_repository::find_by_id(pool, trigger_id)
+        .await?
+        .ok_or(WebhookError::NotFound(trigger_id))?;
+    let pipeline = pipeline_repository::find_by_id(pool, trigger.pipeline_id)
+        .await?
+        .ok_or(WebhookError::PipelineNotFound(trigger.pipeline_id))?;
+
+    let secrets =
+        secret_repository::find_values_for_pipeline(pool, pipeline.project_id, pipeline.id).await?;
+    let Some(secret) = secrets.get(&trigger.secret) else {
+        tracing::warn!(
+            "Webhook trigger {} refers to missing secret '{}'",
+            trigger.id,
+            trigger.secret
+        );
+        return Err(WebhookError::Unauthorized(
+            "Delivery could not be verified".to_string(),
+        ));
+    };
+    if !verify_signature(trigger.provider, secret, delivery.signature, delivery.body) {
+        return Err(WebhookError::Unauthorized(
+            "Delivery could not be verified".to_string(),
+        ));
+    }
+
+    let push = match parse_push(trigger.provider, delivery.event, delivery.body)? {
+        Ok(push) => push,
+        Err(reason) => return Ok(DeliveryOutcome::Skipped(reason)),
+    };
+    if push.deleted {
+        return Ok(DeliveryOutcome::Skipped(format!(
+            "{} was deleted",
+            push.git_ref
+        )));
+    }
+    // Patterns were validated when the trigger was created
+    let filters = CompiledFilters::new(&trigger.filters).map_err(WebhookError::InvalidFields)?;
+    if let Err(reason) = filters.matches(&push) {
+        tracing::debug!("Webhook trigger {} skipped a push: {}", trigger.id, reason);
+        return Ok(DeliveryOutcome::Skipped(reason));
+    }
+
+    let job = job_service::launch_job(
+        pool,
+        CreateJob {
+            pipeline_id: trigger.pipeline_id,
+            parameters: push.fill_parameters(&trigger.parameters),
+            plan: false,
+            commit: Some(CommitRef {
+                repository: push.repository.clone(),
+                sha: push.sha.clone(),
+            }),
+            override_blackout: false,
+            labels: Default::default(),
+        },
+    )
+    .await
+    .map_err(WebhookError::LaunchFailed)?;
+
+    tracing::info!(
+        "Webhook trigger {} launched job {} for {} at {}",
+        trigger.id,
+        job.id,
+        push.git_ref,
+        push.sha
+    );
+
+    Ok(DeliveryOutcome::Launched(Box::new(job)))
+}
+
+// =============================================================================
+// Verification
+// =============================================================================
+
+/// Whether a delivery proves the trigger's secret
+///
+/// GitHub signs the body with HMAC-SHA256 (`sha256=<hex>`); GitLab sends the
+/// secret itself as a token.
+fn verify_signature(
+    provider: GitProvider,
+    secret: &str,
+    signature: Option<&str>,
+    body: &[u8],
+) -> bool {
+    let Some(signature) = signature else {
+        return false;
+    };
+
+    match provider {
+        GitProvider::Github => {
+            let Some(digest) = signature
+                .strip_prefix("sha256=")
+                .and_then(|hex_digest| hex::decode(hex_digest).ok())
+            else {
+                return false;
+            };
+            let Ok(mut mac) = Hmac::<Sha256>::new_from_slice(secret.as_bytes()) else {
+                return false;
+            };
+            mac.update(body);
+            mac.verify_slice(&digest).is_ok()
+        }
+        // Compare digests so the comparison time does not depend on the secret
+        GitProvider::Gitlab => {
+            Sha256::digest(signature.as_bytes()) == Sha256::digest(secret.as_bytes())
+        }
+    }
+}
+
+// =============================================================================
+// Push Events
+// =============================================================================
+
+/// A push, as far as triggers are concerned
+#[derive(Debug)]
+struct Push {
+    /// Full ref pushed (`refs/heads/main`, `refs/tags/v1.0`)
+    git_ref: String,
+    sha: String,
+    /// Repository path (`owner/repo`)
+    repository: String,
+    /// Files added, modified or removed by the pushed commits, unknown when
+    /// the payload lists no commits or only the first of them
+    changed_files: Option<Vec<String>>,
+    deleted: bool,
+}
+
+impl Push {
+    fn branch(&self) -> Option<&str> {
+        self.git_ref.strip_prefix("refs/heads/")
+    }
+
+    fn tag(&self) -> Option<&str> {
+        self.git_ref.strip_prefix("refs/tags/")
+    }
+
+    /// Trigger parameters with the push's placeholders replaced in string values
+    fn fill_parameters(
+        &self,
+        parameters: &HashMap<String, serde_json::Value>,
+    ) -> HashMap<String, serde_json::Value> {
+        let placeholders = [
+            ("{branch}", self.branch().unwrap_or_default()),
+            ("{tag}", self.tag().unwrap_or_default()),
+            ("{ref}", self.git_ref.as_str()),
+            ("{sha}", self.sha.as_str()),
+            ("{repository}", self.repository.as_str()),
+        ];
+
+        parameters
+            .iter()
+            .map(|(name, value)| {
+                let value = match value {
+                    serde_json::Value::String(s) => serde_json::Value::String(
+                        placeholders
+                            .iter()
+                            .fold(s.clone(), |s, (placeholder, with)| {
+                                s.replace(placeholder, with)
+                            }),
+                    ),
+                    other => other.clone(),
+                };
+                (name.clone(), value)
+            })
+            .collect()
+    }
+}
+
+#[derive(Deserialize)]
+struct PushCommit {
+    #[serde(default)]
+    added: Vec<String>,
+    #[serde(default)]
+    modified: Vec<String>,
+    #[serde(default)]
+    removed: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct GithubRepository {
+    full_name: String,
+}
+
+#[derive(Deserialize)]
+struct GithubPush {
+    #[serde(rename = "ref")]
+    git_ref: String,
+    after: String,
+    #[serde(default)]
+    deleted: bool,
+    repository: GithubRepository,
+    #[serde(default)]
+    commits: Vec<PushCommit>,
+}
+
+#[derive(Deserialize)]
+struct GitlabProject {
+    path_with_namespace: String,
+}
+
+#[derive(Deserialize)]
+struct GitlabPush {
+    #[serde(rename = "ref")]
+    git_ref: String,
+    after: String,
+    checkout_sha: Option<String>,
+    project: GitlabProject,
+    #[serde(default)]
+    commits: Vec<PushCommit>,
+}
+
+/// SHA providers send as the new head of a deleted ref
+const NULL_SHA: &str = "0000000000000000000000000000000000000000";
+
+/// Most commits a push payload lists, both providers leave out the rest
+const MAX_LISTED_COMMITS: usize = 20;
+
+/// Parse the push a delivery carries
+///
+/// # Returns
+/// The push, or why the delivery is skipped when it is another event
+fn parse_push(
+    provider: GitProvider,
+    event: Option<&str>,
+    body: &[u8],
+) -> Result<std::result::Result<Push, String>> {
+    let event = event.unwrap_or_default();
+    let invalid = |e: serde_json::Error| {
+        WebhookError::ValidationError(format!("Invalid {} push payload: {}", provider.as_str(), e))
+    };
+    // Force-pushes can list no commits at all, and long pushes only the
+    // first ones, so the files they change are not known
+    let files = |commits: Vec<PushCommit>| -> Option<Vec<String>> {
+        if commits.is_empty() || commits.len() >= MAX_LISTED_COMMITS {
+            return None;
+        }
+        Some(
+            commits
+                .into_iter()
+                .flat_map(|c| c.added.into_iter().chain(c.modified).chain(c.removed))
+                .collect(),
+        )
+    };
+
+    let push = match provider {
+        GitProvider::Github => {
+            if event != "push" {
+                return Ok(Err(format!("Ignored '{}' event", event)));
+            }
+            let payload: GithubPush = serde_json::from_slice(body).map_err(invalid)?;
+            Push {
+                deleted: payload.deleted || payload.after == NULL_SHA,
+                git_ref: payload.git_ref,
+                sha: payload.after,
+                repository: payload.repository.full_name,
+                changed_files: files(payload.commits),
+            }
+        }
+        GitProvider::Gitlab => {
+            if event != "Push Hook" && event != "Tag Push Hook" {
+                return Ok(Err(format!("Ignored '{}' event", event)));
+            }
+            let payload: GitlabPush = serde_json::from_slice(body).map_err(invalid)?;
+            Push {
+                deleted: payload.after == NULL_SHA,
+                git_ref: payload.git_ref,
+                sha: payload.checkout_sha.unwrap_or(payload.after),
+                repository: payload.project.path_with_namespace,
+                changed_files: files(payload.commits),
+            }
+        }
+    };
+
+    Ok(Ok(push))
+}
+
+// =============================================================================
+// Filters
+// =============================================================================
+
+/// Trigger filters with their patterns compiled
+struct CompiledFilters {
+    branches: Vec<GlobMatcher>,
+    tags: Vec<GlobMatcher>,
+    paths: Vec<GlobMatcher>,
+}
+
+impl CompiledFilters {
+    /// Compile the patterns of trigger filters
+    ///
+    /// # Returns
+    /// The compiled filters, or every invalid pattern keyed by its position
+    fn new(filters: &TriggerFilters) -> std::result::Result<Self, Vec<FieldError>> {
+        let mut errors = Vec::new();
+        let mut compile = |field: &str, patterns: &[String]| -> Vec<GlobMatcher> {
+            patterns
+                .iter()
+                .enumerate()
+                .filter_map(|(index, pattern)| {
+                    match GlobBuilder::new(pattern).literal_separator(true).build() {
+                        Ok(glob) => Some(glob.compile_matcher()),
+                        Err(e) => {
+                            errors.push(FieldError::new(
+                                format!("{}[{}]", field, index),
+                                e.kind().to_string(),
+                            ));
+                            None
+                        }
+                    }
+                })
+                .collect()
+        };
+
+        let compiled = Self {
+            branches: compile("branches", &filters.branches),
+            tags: compile("tags", &filters.tags),
+            paths: compile("paths", &filters.paths),
+        };
+
+        if errors.is_empty() {
+            Ok(compiled)
+        } else {
+            Err(errors)
+        }
+    }
+
+    /// Whether a push matches the filters
+    ///
+    /// # Returns
+    /// Why the push does not match, if it does not
+    fn matches(&self, push: &Push) -> std::result::Result<(), String> {
+        let any_ref_filter = !self.branches.is_empty() || !self.tags.is_empty();
+
+        if let Some(branch) = push.branch() {
+            if any_ref_filter && !self.branches.iter().any(|glob| glob.is_match(branch)) {
+                return Err(format!("Branch '{}' does not match the filters", branch));
+            }
+            // A push whose changed files are unknown may touch any path
+            if !self.paths.is_empty()
+                && let Some(changed_files) = &push.changed_files
+                && !changed_files
+                    .iter()
+                    .any(|file| self.paths.iter().any(|glob| glob.is_match(file)))
+            {
+                return Err("No changed file matches the path filters".to_string());
+            }
+            Ok(())
+        } else if let Some(tag) = push.tag() {
+            if any_ref_filter && !self.tags.iter().any(|glob| glob.is_match(tag)) {
+                return Err(format!("Tag '{}' does not match the filters", tag));
+            }
+            Ok(())
+        } else {
+            Err(format!("Ignored push to '{}'", push.git_ref))
+        }
+    }
+}
+
+// =============================================================================
+// Validation
+// =============================================================================
+
+fn validate_trigger_request(req: &CreateWebhookTrigger) -> Result<()> {
+    let mut errors = Vec::new();
+    if req.secret.trim().is_empty() {
+        errors.push(FieldError::new("secret", "Must not be empty"));
+    }
+    if let Err(filter_errors) = CompiledFilters::new(&req.filters) {
+        errors.extend(job_service::prefix_fields("filters", filter_errors));
+    }
+
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(WebhookError::InvalidFields(errors))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn push(git_ref: &str, changed_files: &[&str]) -> Push {
+        Push {
+            git_ref: git_ref.to_string(),
+            sha: "4f2b8c1d9e".to_string(),
+            repository: "acme/api".to_string(),
+            changed_files: Some(changed_files.iter().map(|f| f.to_string()).collect()),
+            deleted: false,
+        }
+    }
+
+    fn filters(branches: &[&str], tags: &[&str], paths: &[&str]) -> CompiledFilters {
+        let strings = |patterns: &[&str]| patterns.iter().map(|p| p.to_string()).collect();
+        CompiledFilters::new(&TriggerFilters {
+            branches: strings(branches),
+            tags: strings(tags),
+            paths: strings(paths),
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn test_filters_match_pushes() {
+        let unfiltered = filters(&[], &[], &[]);
+        assert!(
+            unfiltered
+                .matches(&push("refs/heads/anything", &[]))
+                .is_ok()
+        );
+        assert!(unfiltered.matches(&push("refs/tags/v1.0", &[])).is_ok());
+
+        let branches = filters(&["main", "release/*"], &[], &[]);
+        assert!(branches.matches(&push("refs/heads/main", &[])).is_ok());
+        assert!(
+            branches
+                .matches(&push("refs/heads/release/1.2", &[]))
+                .is_ok()
+        );
+        assert!(
+            branches
+                .matches(&push("refs/heads/release/1.2/fix", &[]))
+                .is_err()
+        );
+        assert!(branches.matches(&push("refs/tags/v1.0", &[])).is_err());
+
+        let tags = filters(&[], &["v*"], &[]);
+        assert!(tags.matches(&push("refs/tags/v1.0", &[])).is_ok());
+        assert!(tags.matches(&push("refs/tags/nightly", &[])).is_err());
+        assert!(tags.matches(&push("refs/heads/main", &[])).is_err());
+
+        let paths = filters(&["main"], &[], &["services/api/**"]);
+        assert!(
+            paths
+                .matches(&push(
+                    "refs/heads/main",
+                    &["README.md", "services/api/src/lib.rs"]
+                ))
+                .is_ok()
+        );
+        assert!(
+            paths
+                .matches(&push("refs/heads/main", &["services/web/index.ts"]))
+                .is_err()
+        );
+
+        let unknown = Push {
+            changed_files: None,
+            ..push("refs/heads/main", &[])
+        };
+        assert!(paths.matches(&unknown).is_ok());
+    }
+
+    #[test]
+    fn test_invalid_patterns_are_reported() {
+        let errors = CompiledFilters::new(&TriggerFilters {
+            branches: vec!["main".to_string(), "feature/[".to_string()],
+            tags: Vec::new(),
+            paths: vec!["{src".to_string()],
+        })
+        .err()
+        .unwrap();
+
+        let fields: Vec<&str> = errors.iter().map(|e| e.field.as_str()).collect();
+        assert_eq!(fields, ["branches[1]", "paths[0]"]);
+    }
+
+    #[test]
+    fn test_verify_signature() {
+        let body = br#"{"ref":"refs/heads/main"}"#;
+        let mut mac = Hmac::<Sha256>::new_from_slice(b"s3cret").unwrap();
+        mac.update(body);
+        let signature = format!("sha256={}", hex::encode(mac.finalize().into_bytes()));
+
+        assert!(verify_signature(
+            GitProvider::Github,
+            "s3cret",
+            Some(&signature),
+            body
+        ));
+        assert!(!verify_signature(
+            GitProvider::Github,
+            "other",
+            Some(&signature),
+            body
+        ));
+        assert!(!verify_signature(GitProvider::Github, "s3cret", None, body));
+        assert!(verify_signature(
+            GitProvider::Gitlab,
+            "s3cret",
+            Some("s3cret"),
+            body
+        ));
+        assert!(!verify_signature(
+            GitProvider::Gitlab,
+            "s3cret",
+            Some("nope"),
+            body
+        ));
+    }
+
+    #[test]
+    fn test_parse_github_push() {
+        let body = br#"{
+            "ref": "refs/heads/main",
+            "after": "4f2b8c1d9e4f2b8c1d9e4f2b8c1d9e4f2b8c1d9e",
+            "deleted": false,
+            "repository": {"full_name": "acme/api"},
+            "commits": [{"added": ["a.rs"], "modified": ["b.rs"], "removed": []}]
+        }"#;
+
+        let push = parse_push(GitProvider::Github, Some("push"), body)
+            .unwrap()
+            .unwrap();
+        assert_eq!(push.branch(), Some("main"));
+        assert_eq!(push.repository, "acme/api");
+        assert_eq!(
+            push.changed_files.as_deref(),
+            Some(["a.rs".to_string(), "b.rs".to_string()].as_slice())
+        );
+
+        let parameters = HashMap::from([(
+            "target".to_string(),
+            serde_json::json!("{repository}@{branch}"),
+        )]);
+        assert_eq!(
+            push.fill_parameters(&parameters)["target"],
+            serde_json::json!("acme/api@main")
+        );
+
+        let payload = |commits: usize| {
+            serde_json::json!({
+                "ref": "refs/heads/main",
+                "after": "4f2b8c1d9e4f2b8c1d9e4f2b8c1d9e4f2b8c1d9e",
+                "repository": {"full_name": "acme/api"},
+                "commits": vec![serde_json::json!({"modified": ["a.rs"]}); commits],
+            })
+            .to_string()
+        };
+        for commits in [0, MAX_LISTED_COMMITS] {
+            let push = parse_push(
+                GitProvider::Github,
+                Some("push"),
+                payload(commits).as_bytes(),
+            )
+            .unwrap()
+            .unwrap();
+            assert_eq!(push.changed_files, None);
+        }
+
+        assert!(
+            parse_push(GitProvider::Github, Some("ping"), b"{}")
+                .unwrap()
+                .is_err()
+        );
+    }
+}
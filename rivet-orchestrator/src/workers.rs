@@ -0,0 +1,112 @@
+//! Background Worker Monitor
+//!
+//! Tracks when each periodic background duty last completed a pass, so the
+//! readiness probe can tell stalled or never-started workers from healthy
+//! ones.
+
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use rivet_core::dto::health::ComponentStatus;
+
+/// Intervals a worker may go without completing a pass before it counts as
+/// stalled
+pub const STALLED_AFTER_INTERVALS: u32 = 3;
+
+/// Cloneable handle to the pass times of the background workers
+#[derive(Debug, Clone, Default)]
+pub struct WorkerMonitor {
+    workers: Arc<Mutex<BTreeMap<&'static str, Worker>>>,
+}
+
+#[derive(Debug)]
+struct Worker {
+    interval: Duration,
+    started_at: Instant,
+    last_pass: Option<Instant>,
+}
+
+impl WorkerMonitor {
+    /// Starts tracking a worker completing a pass every `interval`
+    pub fn register(&self, name: &'static str, interval: Duration) {
+        self.workers.lock().unwrap().insert(
+            name,
+            Worker {
+                interval,
+                started_at: Instant::now(),
+                last_pass: None,
+            },
+        );
+    }
+
+    /// Records that a worker completed a pass, whether or not it succeeded
+    pub fn record_pass(&self, name: &'static str) {
+        if let Some(worker) = self.workers.lock().unwrap().get_mut(name) {
+            worker.last_pass = Some(Instant::now());
+        }
+    }
+
+    /// Status of every worker as of `now`, one `worker:<name>` component each
+    ///
+    /// Without any registered worker, the background tasks were never
+    /// started, which is reported as a failing `workers` component.
+    pub fn report(&self, now: Instant) -> Vec<ComponentStatus> {
+        let workers = self.workers.lock().unwrap();
+        if workers.is_empty() {
+            return vec![ComponentStatus::failing(
+                "workers",
+                "background tasks are not running",
+            )];
+        }
+
+        workers
+            .iter()
+            .map(|(name, worker)| {
+                let component = format!("worker:{}", name);
+                let since =
+                    now.saturating_duration_since(worker.last_pass.unwrap_or(worker.started_at));
+                let stalled = since > worker.interval * STALLED_AFTER_INTERVALS;
+                match (worker.last_pass, stalled) {
+                    (_, true) => ComponentStatus::failing(
+                        component,
+                        format!("no pass completed for {}s", since.as_secs()),
+                    ),
+                    (None, false) => ComponentStatus::ok(component, "starting"),
+                    (Some(_), false) => ComponentStatus::ok(
+                        component,
+                        format!("last pass {}s ago", since.as_secs()),
+                    ),
+                }
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_report_flags_stalled_workers() {
+        let monitor = WorkerMonitor::default();
+        assert!(!monitor.report(Instant::now())[0].is_ok());
+
+        monitor.register("sweep", Duration::from_secs(10));
+        monitor.register("gc", Duration::from_secs(60));
+        monitor.record_pass("gc");
+        let report = monitor.report(Instant::now());
+        assert_eq!(report[1], ComponentStatus::ok("worker:sweep", "starting"));
+
+        let later = Instant::now() + Duration::from_secs(45);
+        let report = monitor.report(later);
+        assert_eq!(
+            report[0],
+            ComponentStatus::ok("worker:gc", "last pass 45s ago")
+        );
+        assert_eq!(
+            report[1],
+            ComponentStatus::failing("worker:sweep", "no pass completed for 45s")
+        );
+    }
+}
@@ -0,0 +1,41 @@
+---@meta
+
+---Annotations module for labelling the job from its scripts
+---
+---Annotations are added to the job's labels when it ends, replacing labels
+---given at launch with the same key, so jobs can be found by what they did
+---(`rivet job list --label release=1.4.0`). They are saved in the job result
+---(`output.annotations`) too, even when the job fails.
+---
+---Keys are 1 to 63 letters, digits, `-`, `_`, `.` or `/`; values are at most
+---255 characters, without commas or control characters. A job carries at
+---most 32 labels; annotations past that are dropped.
+---
+---@class annotations
+annotations = {}
+
+---Set an annotation, replacing its previous value
+---
+---Numbers and booleans are stored as text. Raises an error if the key or
+---value is not a valid label.
+---
+---@param key string The label key
+---@param value string|number|boolean The label value
+---
+---@usage
+---annotations.set("release", input.get("version"))
+---annotations.set("deployed-to", "prod")
+function annotations.set(key, value) end
+
+---Get an annotation set earlier in the job
+---
+---Labels given at launch are not returned, only annotations.
+---
+---@param key string The label key
+---@return string? value The value, or nil if it was not set
+function annotations.get(key) end
+
+---Get all annotations set so far
+---
+---@return table<string, string> annotations The annotations, by key
+function annotations.all() end
@@ -12,6 +12,13 @@
 ---@class container
 container = {}
 
+---Defaults for commands run inside a container.with() block
+---@class ContainerOptions
+---@field env table<string, string>|nil Environment variables for every command
+---@field user string|nil User to run commands as (default: the image's user)
+---@field cwd string|nil Working directory (relative to /workspace, default: /workspace)
+---@field timeout number|nil Kill each command after this many seconds
+
 ---Execute a function with all process calls running in a specific container
 ---
 ---Creates a container from the specified image, executes the provided function
@@ -20,8 +27,13 @@ container = {}
 ---The container lives for the duration of the function execution. Multiple process.run()
 ---calls within the same container.with() block share the container, amortizing startup costs.
 ---
+---An optional options table sets defaults (`env`, `user`, `cwd`, `timeout`) for
+---every process.run() inside the block; fields given to process.run() win, and
+---`env` tables are merged.
+---
 ---@param image string Container image reference (e.g., "python:3.11", "docker.io/alpine/git:latest")
----@param fn function The function to execute with process calls containerized
+---@param options ContainerOptions|function Command defaults, or the function when omitted
+---@param fn function|nil The function to execute with process calls containerized
 ---
 ---@usage
 ---Run multiple commands in the same Python environment
@@ -65,6 +77,17 @@ container = {}
 ---end)
 ---
 ---@usage
+---Share environment, user and working directory across commands
+---container.with("node:18-alpine", {
+---    env = {NODE_ENV = "production"},
+---    user = "node",
+---    cwd = "frontend"
+---}, function()
+---    process.run({cmd = "npm", args = {"ci"}})
+---    process.run({cmd = "npm", args = {"run", "build"}, env = {CI = "true"}})
+---end)
+---
+---@usage
 ---Nested container execution (each process.run uses innermost container)
 ---container.with("alpine:latest", function()
 ---    -- This runs in alpine
@@ -92,4 +115,56 @@ container = {}
 ---    log.error("Container execution failed: " .. tostring(err))
 ---    log.warning("Falling back to default behavior")
 ---end
-function container.with(image, fn) end
+function container.with(image, options, fn) end
+
+---Options for building an image
+---@class BuildOptions
+---@field tag string Tag of the built image (e.g., "registry.example.com/app:1.0")
+---@field context string|nil Build context directory, relative to /workspace (default: ".")
+---@field dockerfile string|nil Dockerfile, relative to /workspace (default: "Dockerfile")
+---@field build_args table<string, string>|nil Build arguments (--build-arg)
+---@field timeout number|nil Kill the build after this many seconds
+
+---Build an image from a Dockerfile in the workspace
+---
+---The build runs on the runner's podman, not inside a container, so no
+---docker-in-docker setup is needed. Output is streamed to the job log.
+---Raises an error if the build fails or times out.
+---
+---@param options BuildOptions
+---@return string tag The tag of the built image
+---
+---@usage
+---local sha = input.require("commit_sha")
+---container.build({
+---    context = ".",
+---    dockerfile = "docker/Dockerfile",
+---    tag = "registry.example.com/app:" .. sha,
+---    build_args = {VERSION = sha}
+---})
+function container.build(options) end
+
+---Options for pushing an image
+---@class PushOptions
+---@field tag string Image to push
+---@field registry string|nil Registry to log in to (default: taken from the tag, docker.io if it names none)
+---@field username string|nil Registry username
+---@field username_secret string|nil Name of the secret holding the registry username
+---@field password_secret string|nil Name of the secret holding the registry password; without it the push is anonymous
+---@field timeout number|nil Kill the push after this many seconds
+
+---Push an image to its registry
+---
+---Credentials come from the job's secrets and are only visible to podman:
+---they are never passed on a command line and are masked in the job log.
+---Raises an error if the login or the push fails.
+---
+---@param options PushOptions
+---
+---@usage
+---container.push({
+---    tag = "registry.example.com/app:" .. sha,
+---    username = "ci-bot",
+---    password_secret = "REGISTRY_PASSWORD"
+---})
+function container.push(options) end
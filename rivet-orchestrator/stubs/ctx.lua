@@ -0,0 +1,28 @@
+---@meta
+
+---Values shared by the stages of one job
+---
+---A plain table every stage of the job sees: a stage sets fields, the
+---stages after it read them. Use it instead of global variables, e.g. for
+---the image tag a build stage pushed and a deploy stage rolls out.
+---
+---The table is saved in the job result (`output.ctx`, shown by
+---`rivet job get`) when the job ends, so values must be strings, numbers,
+---booleans or tables of them; secret values are masked there. A resumed job
+---starts with the table the job it resumes ended with, since its reused
+---stages do not run again.
+---
+---@usage
+---Hand a value to a later stage
+----- in the build stage
+---ctx.image = "ghcr.io/acme/app:" .. input.get("version")
+---container.push({tag = ctx.image})
+---
+----- in the deploy stage
+---process.run({
+---    cmd = "kubectl",
+---    args = {"set", "image", "deploy/app", "app=" .. ctx.image}
+---})
+---
+---@type table<string, any>
+ctx = {}
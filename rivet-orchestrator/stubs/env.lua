@@ -0,0 +1,44 @@
+---@meta
+
+---Env module for reading the pipeline's environment variables
+---
+---Holds the variables of the pipeline's `env` table, which are also set in
+---every container of the job, below the `env` options of `container.with`
+---and `process.run`. Numbers and booleans in the table are read as text.
+---
+---This module does NOT provide access to the runner's OS environment - only
+---the variables the pipeline declares.
+---
+---@class env
+env = {}
+
+---Get an environment variable with an optional default value
+---
+---@param name string The name of the variable
+---@param default? string The value to return if the pipeline does not set it
+---@return string? value The value of the variable or the default
+---
+---@usage
+---local mode = env.get("BUILD_MODE", "debug")
+function env.get(name, default) end
+
+---Check whether the pipeline sets an environment variable
+---
+---@param name string The name of the variable
+---@return boolean exists True if the pipeline's `env` table sets it
+---
+---@usage
+---if env.has("REGISTRY") then
+---  container.push({tag = env.get("REGISTRY") .. "/app:latest"})
+---end
+function env.has(name) end
+
+---Get all environment variables of the pipeline
+---
+---@return table<string, string> vars The variables, by name
+---
+---@usage
+---for name, value in pairs(env.all()) do
+---  log.debug(name .. "=" .. value)
+---end
+function env.all() end
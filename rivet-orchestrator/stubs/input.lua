@@ -93,3 +93,17 @@ function input.all() end
 ---  log.warning("No input parameters provided")
 ---end
 function input.keys() end
+
+---Get the elements of an array input
+---
+---Returns the elements as strings. A non-array input gives a single-element
+---list, and a missing one an empty list.
+---
+---@param name string The name of the array input
+---@return string[] items The elements of the input
+---
+---@usage
+---for _, target in ipairs(input.list("targets")) do
+---  log.info("Building for " .. target)
+---end
+function input.list(name) end
@@ -0,0 +1,36 @@
+---@meta
+
+---Commit a job builds
+---@class JobCommit
+---@field repository string Repository path, e.g. `owner/repo`
+---@field sha string Full commit SHA
+
+---Cron schedule that launched a job
+---@class JobSchedule
+---@field id string Schedule ID
+---@field cron string Cron expression of the schedule
+
+---The job the script runs in
+---
+---A read-only table: assigning to it raises an error. Use its identifiers
+---to tag images and releases, or in notifications, so they can be traced
+---back to the job that produced them.
+---
+---@usage
+---Tag an image with the commit it was built from
+---local tag = job.commit and job.commit.sha:sub(1, 12) or job.id
+---container.build({tag = "ghcr.io/acme/app:" .. tag})
+---
+---Say where a notification comes from
+---log.info(job.pipeline_name .. " #" .. job.number .. " (attempt " .. job.attempt .. ")")
+---
+---@class Job
+---@field id string Job ID
+---@field pipeline_id string Pipeline ID
+---@field pipeline_name string Pipeline name
+---@field number integer Number of the job within its pipeline, starting at 1
+---@field attempt integer Attempt of the job, starting at 1 (retries and requeues add one)
+---@field runner_id string Runner running the job
+---@field commit JobCommit? Commit the job builds, set for jobs launched by a webhook or with a commit
+---@field schedule JobSchedule? Schedule that launched the job
+job = {}
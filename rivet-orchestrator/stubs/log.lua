@@ -5,12 +5,20 @@
 ---Provides structured logging at different severity levels.
 ---Logs are buffered and sent to the orchestrator for centralized collection.
 ---
----All log functions accept a single string message parameter.
----For formatted output, use Lua's string.format() or concatenation.
+---The level functions accept a single string message parameter; their `f`
+---variants (`log.infof`, ...) format the message with string.format().
+---`log.with` returns a logger attaching structured fields to its entries.
 ---
 ---@class log
 log = {}
 
+---Logger attaching structured fields to its entries, returned by `log.with`
+---
+---Call its functions as methods: `logger:info(msg)`.
+---
+---@class Logger
+local Logger = {}
+
 ---Log a debug message
 ---
 ---Debug messages are used for detailed diagnostic information useful during development.
@@ -58,3 +66,82 @@ function log.warning(msg) end
 ---log.error("Failed to connect to database")
 ---log.error("Invalid configuration: missing required field 'api_key'")
 function log.error(msg) end
+
+---Log a formatted debug message
+---
+---@param format string A string.format() format
+---@param ... any Values for the format
+---
+---@usage
+---log.debugf("Processing item %d of %d", current, total)
+function log.debugf(format, ...) end
+
+---Log a formatted informational message
+---
+---@param format string A string.format() format
+---@param ... any Values for the format
+---
+---@usage
+---log.infof("built %s in %ds", tag, secs)
+function log.infof(format, ...) end
+
+---Log a formatted warning message
+---
+---@param format string A string.format() format
+---@param ... any Values for the format
+function log.warningf(format, ...) end
+
+---Log a formatted error message
+---
+---@param format string A string.format() format
+---@param ... any Values for the format
+function log.errorf(format, ...) end
+
+---Logger attaching structured fields to its entries
+---
+---The fields are stored as JSON with each entry (and shown after the message
+---by `rivet job logs`), so values must be strings, numbers, booleans or
+---tables of them. Secret values in them are masked.
+---
+---@param fields table<string, any> Fields to attach
+---@return Logger
+---
+---@usage
+---local build = log.with({stage = "build", attempt = 2})
+---build:info("cache hit")
+---build:infof("built %s in %ds", tag, secs)
+function log.with(fields) end
+
+---@param msg string The message to log
+function Logger:debug(msg) end
+
+---@param msg string The message to log
+function Logger:info(msg) end
+
+---@param msg string The message to log
+function Logger:warning(msg) end
+
+---@param msg string The message to log
+function Logger:error(msg) end
+
+---@param format string A string.format() format
+---@param ... any Values for the format
+function Logger:debugf(format, ...) end
+
+---@param format string A string.format() format
+---@param ... any Values for the format
+function Logger:infof(format, ...) end
+
+---@param format string A string.format() format
+---@param ... any Values for the format
+function Logger:warningf(format, ...) end
+
+---@param format string A string.format() format
+---@param ... any Values for the format
+function Logger:errorf(format, ...) end
+
+---Logger adding `fields` to this logger's
+---
+---@param fields table<string, any> Fields to attach
+---@return Logger
+function Logger:with(fields) end
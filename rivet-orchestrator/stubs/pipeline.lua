@@ -19,26 +19,52 @@
 pipeline = {}
 
 ---Input type enumeration
----@alias InputType "string" | "number" | "bool"
+---`enum` values are one of `options`, `array` values a list (see `input.list`),
+---`secret` values are masked in logs and job records, and `file` values are
+---uploaded at launch and given to the job as the file's path in the workspace
+---@alias InputType "string" | "number" | "bool" | "enum" | "array" | "secret" | "file"
 
 ---Input definition for pipeline parameters
 ---@class InputDefinition
 ---@field type InputType The type of the input parameter
 ---@field description string? Human-readable description of the input
----@field default string|number|boolean? Default value if not provided
----@field options (string|number|boolean)[]? Valid options for enum-like inputs
+---@field default string|number|boolean|(string|number|boolean)[]? Default value if not provided (not for secret and file inputs)
+---@field options (string|number|boolean)[]? Valid options (required for enum inputs; each element of an array input)
+---@field items "string"|"number"|"bool"? Type of the elements of an array input
+---@field pattern string? Regular expression string values (or array elements) must match; unanchored, as in JSON Schema
+---@field min number? Smallest number accepted (number inputs, number array elements)
+---@field max number? Largest number accepted (number inputs, number array elements)
+---@field min_length integer? Fewest characters of a string, or elements of an array
+---@field max_length integer? Most characters of a string, or elements of an array
 ---@field required boolean? Whether this input is required (default: true)
 
 ---Stage condition function
 ---@alias StageCondition fun(): boolean
 
+---What a stage script may return, stored with the stage and shown by `rivet job get`
+---@class StageResult
+---@field artifacts string[]? Files the stage produced
+---@field metrics table<string, number>? Numbers the stage measured (e.g., `{ tests = 124 }`)
+---@field summary string? One-line account of what the stage did
+
 ---Stage script function
----@alias StageScript fun(): nil
+---@alias StageScript fun(): StageResult?
+
+---Pipeline-level hook, called with the name of the stage it runs around
+---@alias StageHook fun(stage: string): nil
+
+---When a stage runs, depending on the stages it waits for (directly or not)
+---@alias StageWhen "on_success" | "on_failure" | "always"
 
 ---Stage definition
 ---@class StageDefinition
 ---@field name string Unique identifier for this stage
 ---@field container string? Container image to use for this stage (e.g., "rust:latest")
+---@field needs string[]? Stages that must succeed first; stages that do not need each other run concurrently (default: the previous stage, `{}` for none)
+---@field allow_failure boolean? Whether a failure of this stage leaves the job successful (default: false)
+---@field deploys_to string? Environment the stage deploys to (e.g., "prod"); its runs are recorded as deployments, and jobs wait for the approvals the environment requires
+---@field when StageWhen? Run only if no stage it waits for failed, only if one did, or either way (default: "on_success")
+---@field always boolean? Shorthand for `when = "always"`, for teardown and notification stages
 ---@field condition StageCondition? Function that returns true if stage should run
 ---@field script StageScript The stage implementation function
 
@@ -54,7 +80,11 @@ pipeline = {}
 ---@field inputs table<string, InputDefinition>? Input parameter definitions
 ---@field runner Tag[]? Runner requirements as key-value tags
 ---@field plugins string[]? Plugin names required by this pipeline
----@field stages StageDefinition[] Ordered list of stages to execute
+---@field max_retries integer? Times a failed job is retried automatically, with exponential backoff (default: 0, max: 10)
+---@field env table<string, string|number|boolean>? Environment variables set in every container of the job and readable through the `env` module
+---@field before_each StageHook? Called before every stage script; its failure fails the stage
+---@field after_each StageHook? Called after every stage script, even a failed one; its failure fails the stage
+---@field stages StageDefinition[] Stages to execute, in order unless they declare `needs`
 
 ---Define a pipeline with the given configuration
 ---
@@ -167,10 +197,25 @@ function PipelineBuilder:tag(tag) end
 ---builder:plugin("docker")
 function PipelineBuilder:plugin(plugin_name) end
 
+---Set how many times a failed job is retried automatically
+---@param retries integer Number of retries (default: 0, max: 10)
+---@return PipelineBuilder self
+function PipelineBuilder:max_retries(retries) end
+
+---Set the hook called before every stage script
+---@param hook StageHook Called with the stage name
+---@return PipelineBuilder self
+function PipelineBuilder:before_each(hook) end
+
+---Set the hook called after every stage script, even a failed one
+---@param hook StageHook Called with the stage name
+---@return PipelineBuilder self
+function PipelineBuilder:after_each(hook) end
+
 ---Add a stage definition
 ---
 ---Can be called multiple times to add multiple stages.
----Stages execute in the order they are added.
+---Stages execute in the order they are added, unless they declare `needs`.
 ---@param stage StageDefinition Stage configuration
 ---@return PipelineBuilder self
 ---
@@ -17,26 +17,33 @@ process = {}
 ---@field stdout string|nil Captured stdout (if capture_stdout was true)
 ---@field stderr string|nil Captured stderr (if capture_stderr was true)
 ---@field exit_code number The process exit code
+---@field timed_out boolean True if the process was killed for exceeding its timeout
 
 ---Options for process execution
 ---@class ProcessOptions
 ---@field cmd string The command to execute (binary name or path)
 ---@field args string[]|nil Arguments to pass to the command
+---@field shell boolean|"sh"|"bash"|"cmd"|"powershell"|"pwsh"|nil Run `cmd` as a command line in this shell (true: sh in Linux containers, cmd in Windows ones); cannot be combined with `args`
 ---@field capture_stdout boolean|nil Capture stdout and return it (default: false)
 ---@field capture_stderr boolean|nil Capture stderr and return it (default: false)
 ---@field capture_exit_code boolean|nil Capture exit code (default: true, always returned)
 ---@field stdout_level string|nil Log level for stdout ("debug"|"info"|"warning"|"error", default: "info")
 ---@field stderr_level string|nil Log level for stderr ("debug"|"info"|"warning"|"error", default: "error")
 ---@field env table<string, string>|nil Additional environment variables to set
----@field cwd string|nil Working directory (relative to /workspace, default: /workspace)
+---@field user string|nil User to run the command as (default: the image's user)
+---@field cwd string|nil Working directory (relative to the workspace, default: /workspace, or C:\workspace in Windows containers)
+---@field timeout number|nil Kill the command, and in Linux containers the processes it started, after this many seconds (default: the runner's command timeout)
+---@field check boolean|nil Raise an error, ending with the last lines of stderr, if the command exits non-zero or times out (default: false)
 
 ---Execute a process inside the current container context
 ---
----Runs the specified command with the given arguments. Output is automatically
----logged unless explicitly captured. Exit codes are always returned.
+---Runs the specified command with the given arguments. Output is logged line by
+---line as the command produces it, tagged with its stream (stdout/stderr),
+---unless explicitly captured. Exit codes are always returned.
 ---
----If the process exits with a non-zero code, it does NOT automatically error.
----Check the exit_code in the result if you want to handle failures.
+---If the process exits with a non-zero code, it does NOT automatically error
+---unless `check` is set. Check the exit_code in the result if you want to
+---handle failures yourself.
 ---
 ---@param options ProcessOptions Configuration for process execution
 ---@return ProcessResult result The result of the process execution
@@ -47,6 +54,10 @@ process = {}
 ---log.info("Exit code: " .. result.exit_code)
 ---
 ---@usage
+---Fail the stage if the tests fail or take over two minutes
+---process.run({cmd = "cargo", args = {"test"}, timeout = 120, check = true})
+---
+---@usage
 ---Capture output for processing
 ---local result = process.run({
 ---    cmd = "git",
@@ -88,6 +99,15 @@ process = {}
 ---})
 ---
 ---@usage
+---Run as another user with a tighter timeout
+---process.run({
+---    cmd = "make",
+---    args = {"install"},
+---    user = "builder",
+---    timeout = 120
+---})
+---
+---@usage
 ---Run in a specific directory
 ---process.run({
 ---    cmd = "npm",
@@ -96,6 +116,13 @@ process = {}
 ---})
 ---
 ---@usage
+---Run a command line in a Windows container
+---process.run({
+---    cmd = "dir /B && msbuild app.sln",
+---    shell = "cmd"
+---})
+---
+---@usage
 ---Capture both stdout and stderr
 ---local result = process.run({
 ---    cmd = "curl",
@@ -0,0 +1,14 @@
+//! Compiles the runner protocol definitions with the vendored `protoc`.
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let mut config = prost_build::Config::new();
+    config.protoc_executable(protoc_bin_vendored::protoc_bin_path()?);
+
+    tonic_prost_build::configure().compile_with_config(
+        config,
+        &["proto/rivet/v1/runner.proto"],
+        &["proto"],
+    )?;
+
+    Ok(())
+}
@@ -0,0 +1,604 @@
+//! Protocol Conversions
+//!
+//! Maps between generated `rivet.v1` messages and `rivet-core` types.
+//! Conversions into protocol messages are infallible; conversions back into
+//! core types validate ids, enums and JSON payloads and fail with
+//! `ConversionError`, which maps to `INVALID_ARGUMENT` on the server.
+
+use std::collections::HashMap;
+
+use chrono::{DateTime, Utc};
+use rivet_core::domain::job::{CommitRef, Job, JobResult, JobStatus};
+use rivet_core::domain::log::{LogEntry, LogLevel, OutputStream};
+use rivet_core::domain::pipeline::Tag;
+use rivet_core::domain::runner::{Runner, RunnerInfo, RunnerLoad, RunnerStatus};
+use rivet_core::dto::job::{JobExecutionInfo, JobLease, ScheduleRef};
+use uuid::Uuid;
+
+use crate::v1;
+
+/// A protocol message that cannot be represented as a core type
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConversionError(pub String);
+
+impl std::fmt::Display for ConversionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Invalid message: {}", self.0)
+    }
+}
+
+impl std::error::Error for ConversionError {}
+
+impl From<ConversionError> for tonic::Status {
+    fn from(err: ConversionError) -> Self {
+        tonic::Status::invalid_argument(err.0)
+    }
+}
+
+type Result<T> = std::result::Result<T, ConversionError>;
+
+// =============================================================================
+// Scalars
+// =============================================================================
+
+/// Parses a UUID field, naming the field in the error
+pub fn parse_uuid(field: &str, value: &str) -> Result<Uuid> {
+    Uuid::parse_str(value).map_err(|e| ConversionError(format!("{}: {}", field, e)))
+}
+
+fn to_millis(time: DateTime<Utc>) -> i64 {
+    time.timestamp_millis()
+}
+
+fn from_millis(field: &str, millis: i64) -> Result<DateTime<Utc>> {
+    DateTime::from_timestamp_millis(millis)
+        .ok_or_else(|| ConversionError(format!("{}: timestamp out of range", field)))
+}
+
+fn parameters_to_json(parameters: &HashMap<String, serde_json::Value>) -> String {
+    serde_json::to_string(parameters).unwrap_or_else(|_| "{}".to_string())
+}
+
+fn parameters_from_json(json: &str) -> Result<HashMap<String, serde_json::Value>> {
+    if json.is_empty() {
+        return Ok(HashMap::new());
+    }
+    serde_json::from_str(json).map_err(|e| ConversionError(format!("parameters_json: {}", e)))
+}
+
+// =============================================================================
+// Enums
+// =============================================================================
+
+impl From<JobStatus> for v1::JobStatus {
+    fn from(status: JobStatus) -> Self {
+        match status {
+            JobStatus::Queued => v1::JobStatus::Queued,
+            JobStatus::Running => v1::JobStatus::Running,
+            JobStatus::Succeeded => v1::JobStatus::Succeeded,
+            JobStatus::Failed => v1::JobStatus::Failed,
+            JobStatus::Cancelled => v1::JobStatus::Cancelled,
+            JobStatus::TimedOut => v1::JobStatus::TimedOut,
+            JobStatus::DeadLettered => v1::JobStatus::DeadLettered,
+            JobStatus::Blocked => v1::JobStatus::Blocked,
+        }
+    }
+}
+
+fn job_status_from_proto(value: i32) -> Result<JobStatus> {
+    match v1::JobStatus::try_from(value) {
+        Ok(v1::JobStatus::Queued) => Ok(JobStatus::Queued),
+        Ok(v1::JobStatus::Running) => Ok(JobStatus::Running),
+        Ok(v1::JobStatus::Succeeded) => Ok(JobStatus::Succeeded),
+        Ok(v1::JobStatus::Failed) => Ok(JobStatus::Failed),
+        Ok(v1::JobStatus::Cancelled) => Ok(JobStatus::Cancelled),
+        Ok(v1::JobStatus::TimedOut) => Ok(JobStatus::TimedOut),
+        Ok(v1::JobStatus::DeadLettered) => Ok(JobStatus::DeadLettered),
+        Ok(v1::JobStatus::Blocked) => Ok(JobStatus::Blocked),
+        Ok(v1::JobStatus::Unspecified) | Err(_) => {
+            Err(ConversionError(format!("unknown job status {}", value)))
+        }
+    }
+}
+
+impl From<RunnerStatus> for v1::RunnerStatus {
+    fn from(status: RunnerStatus) -> Self {
+        match status {
+            RunnerStatus::Online => v1::RunnerStatus::Online,
+            RunnerStatus::Offline => v1::RunnerStatus::Offline,
+            RunnerStatus::Busy => v1::RunnerStatus::Busy,
+        }
+    }
+}
+
+fn runner_status_from_proto(value: i32) -> Result<RunnerStatus> {
+    match v1::RunnerStatus::try_from(value) {
+        Ok(v1::RunnerStatus::Online) => Ok(RunnerStatus::Online),
+        Ok(v1::RunnerStatus::Offline) => Ok(RunnerStatus::Offline),
+        Ok(v1::RunnerStatus::Busy) => Ok(RunnerStatus::Busy),
+        Ok(v1::RunnerStatus::Unspecified) | Err(_) => {
+            Err(ConversionError(format!("unknown runner status {}", value)))
+        }
+    }
+}
+
+impl From<LogLevel> for v1::LogLevel {
+    fn from(level: LogLevel) -> Self {
+        match level {
+            LogLevel::Debug => v1::LogLevel::Debug,
+            LogLevel::Info => v1::LogLevel::Info,
+            LogLevel::Warning => v1::LogLevel::Warning,
+            LogLevel::Error => v1::LogLevel::Error,
+        }
+    }
+}
+
+fn log_level_from_proto(value: i32) -> Result<LogLevel> {
+    match v1::LogLevel::try_from(value) {
+        Ok(v1::LogLevel::Debug) => Ok(LogLevel::Debug),
+        Ok(v1::LogLevel::Info) => Ok(LogLevel::Info),
+        Ok(v1::LogLevel::Warning) => Ok(LogLevel::Warning),
+        Ok(v1::LogLevel::Error) => Ok(LogLevel::Error),
+        Ok(v1::LogLevel::Unspecified) | Err(_) => {
+            Err(ConversionError(format!("unknown log level {}", value)))
+        }
+    }
+}
+
+impl From<OutputStream> for v1::OutputStream {
+    fn from(stream: OutputStream) -> Self {
+        match stream {
+            OutputStream::Stdout => v1::OutputStream::Stdout,
+            OutputStream::Stderr => v1::OutputStream::Stderr,
+        }
+    }
+}
+
+/// Unknown streams are dropped rather than rejected: the line itself is still worth keeping
+fn output_stream_from_proto(value: i32) -> Option<OutputStream> {
+    match v1::OutputStream::try_from(value) {
+        Ok(v1::OutputStream::Stdout) => Some(OutputStream::Stdout),
+        Ok(v1::OutputStream::Stderr) => Some(OutputStream::Stderr),
+        Ok(v1::OutputStream::Unspecified) | Err(_) => None,
+    }
+}
+
+// =============================================================================
+// Runners
+// =============================================================================
+
+impl From<Tag> for v1::Tag {
+    fn from(tag: Tag) -> Self {
+        Self {
+            key: tag.key,
+            value: tag.value,
+        }
+    }
+}
+
+impl From<v1::Tag> for Tag {
+    fn from(tag: v1::Tag) -> Self {
+        Self {
+            key: tag.key,
+            value: tag.value,
+        }
+    }
+}
+
+impl From<Runner> for v1::Runner {
+    fn from(runner: Runner) -> Self {
+        Self {
+            id: runner.id,
+            registered_at: to_millis(runner.registered_at),
+            last_heartbeat_at: to_millis(runner.last_heartbeat_at),
+            status: v1::RunnerStatus::from(runner.status).into(),
+            tags: runner.tags.into_iter().map(Into::into).collect(),
+            plugins: runner.plugins,
+            load: runner.load.map(Into::into),
+            info: runner.info.map(Into::into),
+        }
+    }
+}
+
+impl TryFrom<v1::Runner> for Runner {
+    type Error = ConversionError;
+
+    fn try_from(runner: v1::Runner) -> Result<Self> {
+        Ok(Self {
+            registered_at: from_millis("registered_at", runner.registered_at)?,
+            last_heartbeat_at: from_millis("last_heartbeat_at", runner.last_heartbeat_at)?,
+            status: runner_status_from_proto(runner.status)?,
+            id: runner.id,
+            tags: runner.tags.into_iter().map(Into::into).collect(),
+            plugins: runner.plugins,
+            load: runner.load.map(Into::into),
+            info: runner.info.map(Into::into),
+        })
+    }
+}
+
+impl From<RunnerLoad> for v1::RunnerLoad {
+    fn from(load: RunnerLoad) -> Self {
+        Self {
+            running_jobs: load.running_jobs,
+            max_parallel_jobs: load.max_parallel_jobs,
+            cpu_load: load.cpu_load,
+            memory_load: load.memory_load,
+        }
+    }
+}
+
+impl From<v1::RunnerLoad> for RunnerLoad {
+    fn from(load: v1::RunnerLoad) -> Self {
+        Self {
+            running_jobs: load.running_jobs,
+            max_parallel_jobs: load.max_parallel_jobs,
+            cpu_load: load.cpu_load,
+            memory_load: load.memory_load,
+        }
+    }
+}
+
+impl From<RunnerInfo> for v1::RunnerInfo {
+    fn from(info: RunnerInfo) -> Self {
+        Self {
+            version: info.version,
+            os: info.os,
+            arch: info.arch,
+            hostname: info.hostname,
+            protocol_version: info.protocol_version,
+        }
+    }
+}
+
+impl From<v1::RunnerInfo> for RunnerInfo {
+    fn from(info: v1::RunnerInfo) -> Self {
+        Self {
+            version: info.version,
+            os: info.os,
+            arch: info.arch,
+            hostname: info.hostname,
+            protocol_version: info.protocol_version,
+        }
+    }
+}
+
+// =============================================================================
+// Jobs
+// =============================================================================
+
+impl From<JobResult> for v1::JobResult {
+    fn from(result: JobResult) -> Self {
+        Self {
+            success: result.success,
+            exit_code: result.exit_code,
+            output_json: result.output.map(|output| output.to_string()),
+            error_message: result.error_message,
+        }
+    }
+}
+
+impl TryFrom<v1::JobResult> for JobResult {
+    type Error = ConversionError;
+
+    fn try_from(result: v1::JobResult) -> Result<Self> {
+        let output = result
+            .output_json
+            .map(|json| serde_json::from_str(&json))
+            .transpose()
+            .map_err(|e| ConversionError(format!("output_json: {}", e)))?;
+
+        Ok(Self {
+            success: result.success,
+            exit_code: result.exit_code,
+            output,
+            error_message: result.error_message,
+        })
+    }
+}
+
+impl From<Job> for v1::Job {
+    fn from(job: Job) -> Self {
+        Self {
+            id: job.id.to_string(),
+            pipeline_id: job.pipeline_id.to_string(),
+            status: v1::JobStatus::from(job.status).into(),
+            requested_at: to_millis(job.requested_at),
+            started_at: job.started_at.map(to_millis),
+            completed_at: job.completed_at.map(to_millis),
+            runner_id: job.runner_id,
+            parameters_json: parameters_to_json(&job.parameters),
+            result: job.result.map(Into::into),
+            dead_letter_reason: job.dead_letter_reason,
+            attempt: job.attempt,
+            max_attempts: job.max_attempts,
+            resumed_from: job.resumed_from.map(|id| id.to_string()),
+            commit: job.commit.map(|commit| v1::CommitRef {
+                repository: commit.repository,
+                sha: commit.sha,
+            }),
+            labels: job.labels.into_iter().collect(),
+            number: job.number,
+        }
+    }
+}
+
+impl TryFrom<v1::Job> for Job {
+    type Error = ConversionError;
+
+    fn try_from(job: v1::Job) -> Result<Self> {
+        Ok(Self {
+            id: parse_uuid("id", &job.id)?,
+            number: job.number,
+            pipeline_id: parse_uuid("pipeline_id", &job.pipeline_id)?,
+            status: job_status_from_proto(job.status)?,
+            requested_at: from_millis("requested_at", job.requested_at)?,
+            started_at: job
+                .started_at
+                .map(|ms| from_millis("started_at", ms))
+                .transpose()?,
+            completed_at: job
+                .completed_at
+                .map(|ms| from_millis("completed_at", ms))
+                .transpose()?,
+            runner_id: job.runner_id,
+            parameters: parameters_from_json(&job.parameters_json)?,
+            result: job.result.map(TryInto::try_into).transpose()?,
+            attempt: job.attempt.max(1),
+            max_attempts: job.max_attempts.max(1),
+            dead_letter_reason: job.dead_letter_reason,
+            resumed_from: job
+                .resumed_from
+                .map(|id| parse_uuid("resumed_from", &id))
+                .transpose()?,
+            commit: job.commit.map(|commit| CommitRef {
+                repository: commit.repository,
+                sha: commit.sha,
+            }),
+            labels: job.labels.into_iter().collect(),
+        })
+    }
+}
+
+impl From<JobExecutionInfo> for v1::JobExecutionInfo {
+    fn from(info: JobExecutionInfo) -> Self {
+        Self {
+            job_id: info.job_id.to_string(),
+            pipeline_id: info.pipeline_id.to_string(),
+            pipeline_name: info.pipeline_name,
+            number: info.number,
+            attempt: info.attempt,
+            commit: info.commit.map(|commit| v1::CommitRef {
+                repository: commit.repository,
+                sha: commit.sha,
+            }),
+            schedule: info.schedule.map(|schedule| v1::ScheduleRef {
+                id: schedule.id.to_string(),
+                cron: schedule.cron,
+            }),
+            pipeline_source: info.pipeline_source,
+            parameters_json: parameters_to_json(&info.parameters),
+            secrets: info.secrets,
+            resumed_from: info.resumed_from.map(|id| id.to_string()),
+            skip_stages: info.skip_stages,
+            secret_inputs: info.secret_inputs,
+            traceparent: info.traceparent,
+            claim_token: info.claim_token,
+            default_container: info.default_container,
+            default_container_variants: info.default_container_variants,
+            env: info.env,
+            ctx_json: info.ctx.map(|ctx| ctx.to_string()),
+        }
+    }
+}
+
+impl TryFrom<v1::JobExecutionInfo> for JobExecutionInfo {
+    type Error = ConversionError;
+
+    fn try_from(info: v1::JobExecutionInfo) -> Result<Self> {
+        Ok(Self {
+            job_id: parse_uuid("job_id", &info.job_id)?,
+            pipeline_id: parse_uuid("pipeline_id", &info.pipeline_id)?,
+            pipeline_name: info.pipeline_name,
+            number: info.number,
+            attempt: info.attempt,
+            commit: info.commit.map(|commit| CommitRef {
+                repository: commit.repository,
+                sha: commit.sha,
+            }),
+            schedule: info
+                .schedule
+                .map(|schedule| {
+                    Ok::<_, ConversionError>(ScheduleRef {
+                        id: parse_uuid("schedule.id", &schedule.id)?,
+                        cron: schedule.cron,
+                    })
+                })
+                .transpose()?,
+            pipeline_source: info.pipeline_source,
+            parameters: parameters_from_json(&info.parameters_json)?,
+            secrets: info.secrets,
+            secret_inputs: info.secret_inputs,
+            resumed_from: info
+                .resumed_from
+                .map(|id| parse_uuid("resumed_from", &id))
+                .transpose()?,
+            skip_stages: info.skip_stages,
+            traceparent: info.traceparent,
+            claim_token: info.claim_token,
+            default_container: info.default_container,
+            default_container_variants: info.default_container_variants,
+            env: info.env,
+            ctx: info
+                .ctx_json
+                .map(|json| serde_json::from_str(&json))
+                .transpose()
+                .map_err(|e| ConversionError(format!("ctx_json: {}", e)))?,
+        })
+    }
+}
+
+impl From<JobLease> for v1::JobLease {
+    fn from(lease: JobLease) -> Self {
+        Self {
+            job_id: lease.job_id.to_string(),
+            runner_id: lease.runner_id,
+            expires_at: to_millis(lease.expires_at),
+        }
+    }
+}
+
+impl TryFrom<v1::JobLease> for JobLease {
+    type Error = ConversionError;
+
+    fn try_from(lease: v1::JobLease) -> Result<Self> {
+        Ok(Self {
+            job_id: parse_uuid("job_id", &lease.job_id)?,
+            runner_id: lease.runner_id,
+            expires_at: from_millis("expires_at", lease.expires_at)?,
+        })
+    }
+}
+
+// =============================================================================
+// Logs
+// =============================================================================
+
+impl From<LogEntry> for v1::LogEntry {
+    fn from(entry: LogEntry) -> Self {
+        Self {
+            timestamp: to_millis(entry.timestamp),
+            level: v1::LogLevel::from(entry.level).into(),
+            message: entry.message,
+            stage: entry.stage,
+            stream: entry
+                .stream
+                .map_or(v1::OutputStream::Unspecified, Into::into)
+                .into(),
+            fields_json: entry
+                .fields
+                .map(|fields| serde_json::to_string(&fields).unwrap_or_else(|_| "{}".to_string())),
+        }
+    }
+}
+
+impl TryFrom<v1::LogEntry> for LogEntry {
+    type Error = ConversionError;
+
+    fn try_from(entry: v1::LogEntry) -> Result<Self> {
+        Ok(Self {
+            timestamp: from_millis("timestamp", entry.timestamp)?,
+            level: log_level_from_proto(entry.level)?,
+            message: entry.message,
+            stage: entry.stage,
+            stream: output_stream_from_proto(entry.stream),
+            sequence: None,
+            fields: entry
+                .fields_json
+                .map(|json| serde_json::from_str(&json))
+                .transpose()
+                .map_err(|e| ConversionError(format!("fields_json: {}", e)))?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn test_job_round_trip() {
+        let job = Job {
+            id: Uuid::new_v4(),
+            number: 42,
+            pipeline_id: Uuid::new_v4(),
+            status: JobStatus::Running,
+            requested_at: DateTime::from_timestamp_millis(1_700_000_000_123).unwrap(),
+            started_at: DateTime::from_timestamp_millis(1_700_000_001_000),
+            completed_at: None,
+            runner_id: Some("runner-1".to_string()),
+            parameters: HashMap::from([("branch".to_string(), serde_json::json!("main"))]),
+            result: Some(JobResult::success_with_output(
+                serde_json::json!({"ok": true}),
+            )),
+            attempt: 2,
+            max_attempts: 3,
+            dead_letter_reason: None,
+            resumed_from: Some(Uuid::new_v4()),
+            commit: Some(CommitRef {
+                repository: "acme/api".to_string(),
+                sha: "4f2b8c1d".to_string(),
+            }),
+            labels: BTreeMap::from([("team".to_string(), "payments".to_string())]),
+        };
+
+        let decoded = Job::try_from(v1::Job::from(job.clone())).unwrap();
+
+        assert_eq!(decoded.id, job.id);
+        assert_eq!(decoded.number, 42);
+        assert_eq!(decoded.status, JobStatus::Running);
+        assert_eq!(decoded.requested_at, job.requested_at);
+        assert_eq!(decoded.started_at, job.started_at);
+        assert_eq!(decoded.parameters, job.parameters);
+        assert_eq!((decoded.attempt, decoded.max_attempts), (2, 3));
+        assert_eq!(decoded.resumed_from, job.resumed_from);
+        assert_eq!(decoded.commit, job.commit);
+        assert_eq!(decoded.labels, job.labels);
+        assert_eq!(
+            decoded.result.unwrap().output,
+            Some(serde_json::json!({"ok": true}))
+        );
+    }
+
+    #[test]
+    fn test_invalid_messages_are_rejected() {
+        let entry = v1::LogEntry {
+            timestamp: 0,
+            level: v1::LogLevel::Unspecified.into(),
+            message: "hello".to_string(),
+            stage: None,
+            stream: 0,
+            fields_json: None,
+        };
+        assert!(LogEntry::try_from(entry).is_err());
+
+        let entry = v1::LogEntry {
+            timestamp: 0,
+            level: v1::LogLevel::Info.into(),
+            message: "hello".to_string(),
+            fields_json: Some("[1, 2]".to_string()),
+            ..Default::default()
+        };
+        let err = LogEntry::try_from(entry).unwrap_err();
+        assert!(err.0.starts_with("fields_json"));
+
+        let info = v1::JobExecutionInfo {
+            job_id: "not-a-uuid".to_string(),
+            ..Default::default()
+        };
+        let err = JobExecutionInfo::try_from(info).unwrap_err();
+        assert!(err.0.starts_with("job_id"));
+
+        let info = v1::JobExecutionInfo {
+            job_id: Uuid::new_v4().to_string(),
+            pipeline_id: Uuid::new_v4().to_string(),
+            ctx_json: Some("{\"image\":".to_string()),
+            ..Default::default()
+        };
+        let err = JobExecutionInfo::try_from(info).unwrap_err();
+        assert!(err.0.starts_with("ctx_json"));
+
+        let info = v1::JobExecutionInfo {
+            job_id: Uuid::new_v4().to_string(),
+            pipeline_id: Uuid::new_v4().to_string(),
+            schedule: Some(v1::ScheduleRef {
+                id: "nightly".to_string(),
+                cron: "@daily".to_string(),
+            }),
+            ..Default::default()
+        };
+        let err = JobExecutionInfo::try_from(info).unwrap_err();
+        assert!(err.0.starts_with("schedule.id"));
+    }
+}
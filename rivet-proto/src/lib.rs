@@ -0,0 +1,21 @@
+//! Rivet Proto
+//!
+//! gRPC definitions for runner ↔ orchestrator communication.
+//!
+//! This crate contains:
+//! - Generated messages and the `RunnerService` client/server (package `rivet.v1`)
+//! - Conversions between protocol messages and `rivet-core` types
+//!
+//! The HTTP API remains the reference interface; the gRPC transport exists for
+//! lower per-call overhead and streaming log upload from runners.
+
+pub mod convert;
+
+/// Generated code for the `rivet.v1` package
+pub mod v1 {
+    tonic::include_proto!("rivet.v1");
+}
+
+pub use convert::ConversionError;
+pub use v1::runner_service_client::RunnerServiceClient;
+pub use v1::runner_service_server::{RunnerService, RunnerServiceServer};
@@ -2,15 +2,29 @@
 //!
 //! Defines all configurable parameters for the runner including
 //! polling intervals, logging configuration, and orchestrator connection settings.
-
-use std::path::PathBuf;
+//!
+//! Settings come from an optional TOML file (`--config <path>` or
+//! `RUNNER_CONFIG`), overridden by environment variables. The file's keys
+//! are the environment variable names in lowercase; see `reload` for the
+//! settings that take effect when the file changes.
+
+use anyhow::Context;
+use rivet_core::domain::pipeline::Tag;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
 use std::time::Duration;
 
+use crate::podman::{ContainerOs, ContainerRuntime, ImagePolicy};
+
+/// Environment variable naming the configuration file
+pub const CONFIG_PATH_ENV: &str = "RUNNER_CONFIG";
+
 /// Runner configuration
 ///
 /// All timeouts and intervals are configurable to allow tuning
 /// for different deployment scenarios (dev vs prod, fast vs slow networks).
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct Config {
     /// Unique identifier for this runner instance
     pub runner_id: String,
@@ -18,28 +32,108 @@ pub struct Config {
     /// Orchestrator base URL (e.g., "http://localhost:8080")
     pub orchestrator_url: String,
 
+    /// Orchestrator gRPC endpoint (e.g., "http://localhost:9090"); when set,
+    /// the job lifecycle uses gRPC instead of HTTP
+    pub orchestrator_grpc_url: Option<String>,
+
+    /// Bearer token for secured orchestrators
+    pub orchestrator_token: Option<String>,
+
+    /// Registration token minted by an orchestrator admin
+    pub registration_token: Option<String>,
+
+    /// Extra headers sent with every orchestrator request (e.g., tenant headers)
+    pub orchestrator_headers: Vec<(String, String)>,
+
+    /// Client certificate (PEM) presented to the orchestrator for mutual TLS
+    pub tls_cert_path: Option<PathBuf>,
+
+    /// Private key (PEM, PKCS#8) matching `tls_cert_path`
+    pub tls_key_path: Option<PathBuf>,
+
+    /// Extra CA certificate (PEM) used to verify the orchestrator
+    pub tls_ca_path: Option<PathBuf>,
+
     /// Base directory for job workspaces (default: /tmp)
     pub workspace_base: PathBuf,
 
     /// Default container image for job execution (default: docker.io/alpine:latest)
     pub default_container_image: String,
 
+    /// Container engine driving job containers (default: podman, docker on
+    /// Windows hosts)
+    pub container_runtime: ContainerRuntime,
+
+    /// Operating system of job containers, registered as the `os` tag
+    /// (default: the host's)
+    pub container_os: ContainerOs,
+
     /// How often to poll the orchestrator for new jobs
     pub poll_interval: Duration,
 
+    /// How long a long-poll request waits for a job; zero disables long
+    /// polling in favour of interval polling
+    pub long_poll_wait: Duration,
+
     /// How often to send buffered logs to the orchestrator
     pub log_send_interval: Duration,
 
+    /// How often to renew the lease on each running job; leases are taken
+    /// for three intervals so one missed renewal does not requeue the job
+    pub lease_renew_interval: Duration,
+
     /// Maximum time a job can run before timing out
     #[allow(dead_code)]
     pub job_timeout: Duration,
 
-    /// Labels for capability matching (e.g., env=prod, region=us-west)
-    #[allow(dead_code)]
+    /// Maximum time a single command may run, unless it sets its own timeout
+    pub command_timeout: Duration,
+
+    /// Labels for capability matching (e.g., env=prod, region=us-west),
+    /// registered as the runner's tags
     pub labels: std::collections::HashMap<String, String>,
 
+    /// Plugins the runner provides to pipelines requiring them
+    pub plugins: Vec<String>,
+
     /// Max parallel jobs the runner can handle
     pub max_parallel_jobs: usize,
+
+    /// Images pulled at startup so jobs do not wait for them
+    pub prepull_images: Vec<String>,
+
+    /// Images paused warm containers are kept for (pulled at startup too)
+    pub warm_images: Vec<String>,
+
+    /// Warm containers kept per warm image
+    pub warm_pool_size: usize,
+
+    /// Evict images jobs have not used for this long (None = never)
+    pub image_cache_max_age: Option<Duration>,
+
+    /// Evict least recently used images while all images take more bytes
+    /// than this (None = no limit)
+    pub image_cache_max_size: Option<u64>,
+
+    /// Failed-job workspaces kept for debugging (0 = delete every workspace
+    /// once its job is done)
+    pub keep_failed_workspaces: usize,
+
+    /// Delete kept workspaces older than this (None = only trim by count)
+    pub workspace_max_age: Option<Duration>,
+
+    /// How often workspaces are swept and their disk usage reported
+    pub workspace_sweep_interval: Duration,
+
+    /// Fail jobs whose workspace grows beyond this many bytes (None = no quota)
+    pub workspace_quota: Option<u64>,
+
+    /// Registries the containers of jobs may come from (empty = any)
+    pub allowed_registries: Vec<String>,
+
+    /// Patterns the images of job containers must match, `*` matching any
+    /// characters (empty = any)
+    pub allowed_images: Vec<String>,
 }
 
 impl Config {
@@ -48,77 +142,272 @@ impl Config {
         Self {
             runner_id,
             orchestrator_url,
+            orchestrator_grpc_url: None,
+            orchestrator_token: None,
+            registration_token: None,
+            orchestrator_headers: Vec::new(),
+            tls_cert_path: None,
+            tls_key_path: None,
+            tls_ca_path: None,
             workspace_base: PathBuf::from("/tmp"),
             default_container_image: "docker.io/alpine:latest".to_string(),
+            container_runtime: ContainerRuntime::for_host(),
+            container_os: ContainerOs::for_host(),
             poll_interval: Duration::from_secs(5),
+            long_poll_wait: Duration::from_secs(30),
             log_send_interval: Duration::from_secs(30),
+            lease_renew_interval: Duration::from_secs(20),
             job_timeout: Duration::from_secs(300), // 5 minutes
+            command_timeout: Duration::from_secs(3600),
             labels: std::collections::HashMap::new(),
+            plugins: Vec::new(),
             max_parallel_jobs: 2,
+            prepull_images: Vec::new(),
+            warm_images: Vec::new(),
+            warm_pool_size: 1,
+            image_cache_max_age: None,
+            image_cache_max_size: None,
+            keep_failed_workspaces: 0,
+            workspace_max_age: None,
+            workspace_sweep_interval: Duration::from_secs(300),
+            workspace_quota: None,
+            allowed_registries: Vec::new(),
+            allowed_images: Vec::new(),
+        }
+    }
+
+    /// Loads the configuration file, if any, then applies the environment
+    ///
+    /// Without a runner ID or orchestrator URL from either, the defaults
+    /// (a random ID and `http://localhost:8080`) are used.
+    ///
+    /// # Arguments
+    /// * `path` - Configuration file; `None` uses defaults and the environment only
+    pub fn load(path: Option<&Path>) -> anyhow::Result<Self> {
+        let mut config = Self::default();
+        if let Some(path) = path {
+            let content = std::fs::read_to_string(path)
+                .with_context(|| format!("Failed to read {}", path.display()))?;
+            let file: ConfigFile = toml::from_str(&content)
+                .with_context(|| format!("Invalid configuration file {}", path.display()))?;
+            file.apply(&mut config);
         }
+        config.apply_env(|name| std::env::var(name).ok())?;
+        Ok(config)
     }
 
-    /// Creates configuration from environment variables
+    /// Overrides settings with the environment variables that are set
     ///
-    /// Expected environment variables:
-    /// - RUNNER_ID (required)
-    /// - ORCHESTRATOR_URL (required)
+    /// Environment variables:
+    /// - RUNNER_ID
+    /// - ORCHESTRATOR_URL
+    /// - ORCHESTRATOR_GRPC_URL (optional, gRPC endpoint for the job lifecycle)
+    /// - ORCHESTRATOR_TOKEN (optional, bearer token)
+    /// - RUNNER_REGISTRATION_TOKEN (optional, required by orchestrators enforcing runner auth)
+    /// - ORCHESTRATOR_HEADERS (optional, comma-separated Name=value pairs)
+    /// - ORCHESTRATOR_TLS_CERT (optional, client certificate path for mTLS)
+    /// - ORCHESTRATOR_TLS_KEY (optional, client key path, required with ORCHESTRATOR_TLS_CERT)
+    /// - ORCHESTRATOR_TLS_CA (optional, CA certificate path to verify the orchestrator)
     /// - WORKSPACE_BASE (optional, default: /tmp)
     /// - DEFAULT_CONTAINER_IMAGE (optional, default: docker.io/alpine:latest)
+    /// - CONTAINER_RUNTIME (optional, podman or docker, default: docker on Windows, podman elsewhere)
+    /// - CONTAINER_OS (optional, linux or windows, default: the host's)
     /// - POLL_INTERVAL (optional, seconds, default: 5)
+    /// - LONG_POLL_WAIT (optional, seconds, default: 30, 0 disables long polling)
     /// - LOG_SEND_INTERVAL (optional, seconds, default: 30)
+    /// - LEASE_RENEW_INTERVAL (optional, seconds, default: 20)
     /// - JOB_TIMEOUT (optional, seconds, default: 300)
+    /// - COMMAND_TIMEOUT (optional, seconds, default: 3600)
+    /// - RUNNER_LABELS (optional, comma-separated key=value tags matched against pipelines)
+    /// - RUNNER_PLUGINS (optional, comma-separated plugins matched against pipelines)
     /// - MAX_PARALLEL_JOBS (optional, default: 2)
-    pub fn from_env() -> anyhow::Result<Self> {
-        let runner_id = std::env::var("RUNNER_ID")
-            .map_err(|_| anyhow::anyhow!("RUNNER_ID environment variable not set"))?;
-
-        let orchestrator_url = std::env::var("ORCHESTRATOR_URL")
-            .map_err(|_| anyhow::anyhow!("ORCHESTRATOR_URL environment variable not set"))?;
-
-        let workspace_base = std::env::var("WORKSPACE_BASE")
-            .ok()
-            .map(PathBuf::from)
-            .unwrap_or_else(|| PathBuf::from("/tmp"));
-
-        let default_container_image = std::env::var("DEFAULT_CONTAINER_IMAGE")
-            .ok()
-            .unwrap_or_else(|| "docker.io/alpine:latest".to_string());
-
-        let poll_interval = std::env::var("POLL_INTERVAL")
-            .ok()
-            .and_then(|s| s.parse::<u64>().ok())
-            .map(Duration::from_secs)
-            .unwrap_or(Duration::from_secs(5));
-
-        let log_send_interval = std::env::var("LOG_SEND_INTERVAL")
-            .ok()
-            .and_then(|s| s.parse::<u64>().ok())
-            .map(Duration::from_secs)
-            .unwrap_or(Duration::from_secs(30));
-
-        let job_timeout = std::env::var("JOB_TIMEOUT")
-            .ok()
-            .and_then(|s| s.parse::<u64>().ok())
-            .map(Duration::from_secs)
-            .unwrap_or(Duration::from_secs(300));
-
-        let max_parallel_jobs = std::env::var("MAX_PARALLEL_JOBS")
-            .ok()
-            .and_then(|s| s.parse::<usize>().ok())
-            .unwrap_or(2);
-
-        Ok(Self {
+    /// - PREPULL_IMAGES (optional, comma-separated images pulled at startup)
+    /// - WARM_IMAGES (optional, comma-separated images kept warm)
+    /// - WARM_POOL_SIZE (optional, warm containers per warm image, default: 1)
+    /// - IMAGE_CACHE_MAX_AGE (optional, seconds, default: 0 = never evict by age)
+    /// - IMAGE_CACHE_MAX_SIZE_MB (optional, default: 0 = no size limit)
+    /// - KEEP_FAILED_WORKSPACES (optional, default: 0 = delete all workspaces after the job)
+    /// - WORKSPACE_MAX_AGE (optional, seconds, default: 0 = keep until trimmed by count)
+    /// - WORKSPACE_SWEEP_INTERVAL (optional, seconds, default: 300)
+    /// - WORKSPACE_QUOTA_MB (optional, default: 0 = no quota)
+    /// - ALLOWED_REGISTRIES (optional, comma-separated registries job containers may come from)
+    /// - ALLOWED_IMAGES (optional, comma-separated patterns job container images must match)
+    pub fn apply_env(&mut self, var: impl Fn(&str) -> Option<String>) -> anyhow::Result<()> {
+        let var = |name: &str| var(name).filter(|s| !s.is_empty());
+        let secs = |name: &str| {
+            var(name)
+                .and_then(|s| s.parse::<u64>().ok())
+                .map(Duration::from_secs)
+        };
+        let count = |name: &str| var(name).and_then(|s| s.parse::<usize>().ok());
+        // Zero turns these limits off
+        let limit = |name: &str| {
+            var(name)
+                .and_then(|s| s.parse::<u64>().ok())
+                .map(|n| (n > 0).then_some(n))
+        };
+        let list = |name: &str| var(name).map(|s| parse_list(&s));
+
+        if let Some(runner_id) = var("RUNNER_ID") {
+            self.runner_id = runner_id;
+        }
+        if let Some(url) = var("ORCHESTRATOR_URL") {
+            self.orchestrator_url = url;
+        }
+        if let Some(url) = var("ORCHESTRATOR_GRPC_URL") {
+            self.orchestrator_grpc_url = Some(url);
+        }
+        if let Some(token) = var("ORCHESTRATOR_TOKEN") {
+            self.orchestrator_token = Some(token);
+        }
+        if let Some(token) = var("RUNNER_REGISTRATION_TOKEN") {
+            self.registration_token = Some(token);
+        }
+        if let Some(headers) = var("ORCHESTRATOR_HEADERS") {
+            self.orchestrator_headers = parse_headers(&headers)?;
+        }
+        if let Some(path) = var("ORCHESTRATOR_TLS_CERT") {
+            self.tls_cert_path = Some(PathBuf::from(path));
+        }
+        if let Some(path) = var("ORCHESTRATOR_TLS_KEY") {
+            self.tls_key_path = Some(PathBuf::from(path));
+        }
+        if let Some(path) = var("ORCHESTRATOR_TLS_CA") {
+            self.tls_ca_path = Some(PathBuf::from(path));
+        }
+        if let Some(base) = var("WORKSPACE_BASE") {
+            self.workspace_base = PathBuf::from(base);
+        }
+        if let Some(image) = var("DEFAULT_CONTAINER_IMAGE") {
+            self.default_container_image = image;
+        }
+        if let Some(runtime) = var("CONTAINER_RUNTIME") {
+            self.container_runtime = runtime.parse().context("Invalid CONTAINER_RUNTIME")?;
+        }
+        if let Some(os) = var("CONTAINER_OS") {
+            self.container_os = os.parse().context("Invalid CONTAINER_OS")?;
+        }
+        if let Some(interval) = secs("POLL_INTERVAL") {
+            self.poll_interval = interval;
+        }
+        if let Some(wait) = secs("LONG_POLL_WAIT") {
+            self.long_poll_wait = wait;
+        }
+        if let Some(interval) = secs("LOG_SEND_INTERVAL") {
+            self.log_send_interval = interval;
+        }
+        if let Some(interval) = secs("LEASE_RENEW_INTERVAL") {
+            self.lease_renew_interval = interval;
+        }
+        if let Some(timeout) = secs("JOB_TIMEOUT") {
+            self.job_timeout = timeout;
+        }
+        if let Some(timeout) = secs("COMMAND_TIMEOUT") {
+            self.command_timeout = timeout;
+        }
+        if let Some(labels) = var("RUNNER_LABELS") {
+            self.labels = parse_pairs("RUNNER_LABELS", &labels)?.into_iter().collect();
+        }
+        if let Some(plugins) = list("RUNNER_PLUGINS") {
+            self.plugins = plugins;
+        }
+        if let Some(max) = count("MAX_PARALLEL_JOBS") {
+            self.max_parallel_jobs = max;
+        }
+        if let Some(images) = list("PREPULL_IMAGES") {
+            self.prepull_images = images;
+        }
+        if let Some(images) = list("WARM_IMAGES") {
+            self.warm_images = images;
+        }
+        if let Some(size) = count("WARM_POOL_SIZE") {
+            self.warm_pool_size = size;
+        }
+        if let Some(age) = limit("IMAGE_CACHE_MAX_AGE") {
+            self.image_cache_max_age = age.map(Duration::from_secs);
+        }
+        if let Some(mb) = limit("IMAGE_CACHE_MAX_SIZE_MB") {
+            self.image_cache_max_size = mb.map(|mb| mb * 1024 * 1024);
+        }
+        if let Some(keep) = count("KEEP_FAILED_WORKSPACES") {
+            self.keep_failed_workspaces = keep;
+        }
+        if let Some(age) = limit("WORKSPACE_MAX_AGE") {
+            self.workspace_max_age = age.map(Duration::from_secs);
+        }
+        if let Some(interval) = secs("WORKSPACE_SWEEP_INTERVAL") {
+            self.workspace_sweep_interval = interval;
+        }
+        if let Some(mb) = limit("WORKSPACE_QUOTA_MB") {
+            self.workspace_quota = mb.map(|mb| mb * 1024 * 1024);
+        }
+        if let Some(registries) = list("ALLOWED_REGISTRIES") {
+            self.allowed_registries = registries;
+        }
+        if let Some(images) = list("ALLOWED_IMAGES") {
+            self.allowed_images = images;
+        }
+
+        Ok(())
+    }
+
+    /// Images the containers of jobs may run
+    pub fn image_policy(&self) -> ImagePolicy {
+        ImagePolicy {
+            registries: self.allowed_registries.clone(),
+            images: self.allowed_images.clone(),
+        }
+    }
+
+    /// Applies the settings of `new` that take effect without a restart
+    ///
+    /// Jobs started from then on use the new settings, and the runner's
+    /// capacity follows `max_parallel_jobs` (running jobs are not stopped
+    /// when it shrinks).
+    ///
+    /// # Returns
+    /// The configuration now in effect, and the settings that changed but
+    /// need a restart to take effect
+    pub fn reload(&self, new: &Config) -> (Config, Vec<&'static str>) {
+        let mut reloaded = new.clone();
+        let mut ignored = Vec::new();
+
+        macro_rules! keep {
+            ($($field:ident),+) => {$(
+                if reloaded.$field != self.$field {
+                    ignored.push(stringify!($field));
+                    reloaded.$field = self.$field.clone();
+                }
+            )+};
+        }
+        // Used at registration, or by the long-lived parts of the runner
+        keep!(
             runner_id,
             orchestrator_url,
+            orchestrator_grpc_url,
+            orchestrator_token,
+            registration_token,
+            orchestrator_headers,
+            tls_cert_path,
+            tls_key_path,
+            tls_ca_path,
             workspace_base,
-            default_container_image,
-            poll_interval,
-            log_send_interval,
-            job_timeout,
-            labels: std::collections::HashMap::new(),
-            max_parallel_jobs,
-        })
+            container_runtime,
+            container_os,
+            long_poll_wait,
+            labels,
+            plugins,
+            prepull_images,
+            warm_images,
+            warm_pool_size,
+            image_cache_max_age,
+            image_cache_max_size,
+            keep_failed_workspaces,
+            workspace_max_age,
+            workspace_sweep_interval
+        );
+
+        (reloaded, ignored)
     }
 
     /// Adds a label for capability matching
@@ -128,6 +417,44 @@ impl Config {
         self
     }
 
+    /// Labels as the tags registered with the orchestrator, sorted by key
+    pub fn tags(&self) -> Vec<Tag> {
+        let mut tags: Vec<Tag> = self
+            .labels
+            .iter()
+            .map(|(key, value)| Tag {
+                key: key.clone(),
+                value: value.clone(),
+            })
+            .collect();
+        tags.sort_by(|a, b| a.key.cmp(&b.key));
+        tags
+    }
+
+    /// Tags registered with the orchestrator: the labels, plus the container
+    /// `os` and host `arch` unless labels set them, sorted by key
+    pub fn registered_tags(&self) -> Vec<Tag> {
+        let mut tags = self.tags();
+        for (key, value) in [("os", self.container_os.as_str()), ("arch", host_arch())] {
+            if !self.labels.contains_key(key) {
+                tags.push(Tag {
+                    key: key.to_string(),
+                    value: value.to_string(),
+                });
+            }
+        }
+        tags.sort_by(|a, b| a.key.cmp(&b.key));
+        tags
+    }
+
+    /// Architecture images are picked for: the `arch` label, or the host's
+    pub fn arch(&self) -> &str {
+        self.labels
+            .get("arch")
+            .map(String::as_str)
+            .unwrap_or_else(|| host_arch())
+    }
+
     /// Validates the configuration
     pub fn validate(&self) -> anyhow::Result<()> {
         if self.runner_id.is_empty() {
@@ -144,6 +471,18 @@ impl Config {
             anyhow::bail!("orchestrator_url must start with http:// or https://");
         }
 
+        if self
+            .orchestrator_grpc_url
+            .as_deref()
+            .is_some_and(|url| !url.starts_with("http://") && !url.starts_with("https://"))
+        {
+            anyhow::bail!("orchestrator_grpc_url must start with http:// or https://");
+        }
+
+        if self.tls_cert_path.is_some() != self.tls_key_path.is_some() {
+            anyhow::bail!("tls_cert_path and tls_key_path must be set together");
+        }
+
         if self.poll_interval.as_secs() == 0 {
             anyhow::bail!("poll_interval must be greater than 0");
         }
@@ -152,10 +491,209 @@ impl Config {
             anyhow::bail!("log_send_interval must be greater than 0");
         }
 
+        if self.lease_renew_interval.as_secs() == 0 {
+            anyhow::bail!("lease_renew_interval must be greater than 0");
+        }
+
+        if self.command_timeout.is_zero() {
+            anyhow::bail!("command_timeout must be greater than 0");
+        }
+
+        if self.workspace_sweep_interval.is_zero() {
+            anyhow::bail!("workspace_sweep_interval must be greater than 0");
+        }
+
+        if self.max_parallel_jobs == 0 {
+            anyhow::bail!("max_parallel_jobs must be greater than 0");
+        }
+
+        self.image_policy()
+            .check(&self.default_container_image)
+            .context("default_container_image is not allowed")?;
+
         Ok(())
     }
 }
 
+/// TLS material read from the configured certificate paths (PEM encoded)
+#[derive(Default, Clone)]
+pub struct TlsMaterial {
+    /// Client certificate and key for mutual TLS
+    pub identity: Option<(Vec<u8>, Vec<u8>)>,
+    /// Extra CA certificate used to verify the orchestrator
+    pub ca_certificate: Option<Vec<u8>>,
+}
+
+impl Config {
+    /// Reads the configured certificate and key files
+    ///
+    /// Called once at startup; both the HTTP and gRPC transports use the result.
+    pub fn load_tls(&self) -> anyhow::Result<TlsMaterial> {
+        let read = |path: &PathBuf, what: &str| {
+            std::fs::read(path)
+                .with_context(|| format!("Failed to read {} {}", what, path.display()))
+        };
+
+        let identity = match (&self.tls_cert_path, &self.tls_key_path) {
+            (Some(cert_path), Some(key_path)) => Some((
+                read(cert_path, "TLS certificate")?,
+                read(key_path, "TLS key")?,
+            )),
+            _ => None,
+        };
+        let ca_certificate = self
+            .tls_ca_path
+            .as_ref()
+            .map(|path| read(path, "CA certificate"))
+            .transpose()?;
+
+        Ok(TlsMaterial {
+            identity,
+            ca_certificate,
+        })
+    }
+}
+
+/// Configuration file contents; keys are the environment variables in
+/// lowercase, with lists and `key = value` tables instead of comma-separated
+/// strings
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct ConfigFile {
+    runner_id: Option<String>,
+    orchestrator_url: Option<String>,
+    orchestrator_grpc_url: Option<String>,
+    orchestrator_token: Option<String>,
+    runner_registration_token: Option<String>,
+    orchestrator_headers: Option<BTreeMap<String, String>>,
+    orchestrator_tls_cert: Option<PathBuf>,
+    orchestrator_tls_key: Option<PathBuf>,
+    orchestrator_tls_ca: Option<PathBuf>,
+    workspace_base: Option<PathBuf>,
+    default_container_image: Option<String>,
+    container_runtime: Option<ContainerRuntime>,
+    container_os: Option<ContainerOs>,
+    poll_interval: Option<u64>,
+    long_poll_wait: Option<u64>,
+    log_send_interval: Option<u64>,
+    lease_renew_interval: Option<u64>,
+    job_timeout: Option<u64>,
+    command_timeout: Option<u64>,
+    runner_labels: Option<BTreeMap<String, String>>,
+    runner_plugins: Option<Vec<String>>,
+    max_parallel_jobs: Option<usize>,
+    prepull_images: Option<Vec<String>>,
+    warm_images: Option<Vec<String>>,
+    warm_pool_size: Option<usize>,
+    image_cache_max_age: Option<u64>,
+    image_cache_max_size_mb: Option<u64>,
+    keep_failed_workspaces: Option<usize>,
+    workspace_max_age: Option<u64>,
+    workspace_sweep_interval: Option<u64>,
+    workspace_quota_mb: Option<u64>,
+    allowed_registries: Option<Vec<String>>,
+    allowed_images: Option<Vec<String>>,
+}
+
+impl ConfigFile {
+    /// Overrides the settings present in the file
+    fn apply(self, config: &mut Config) {
+        let secs = |s: Option<u64>| s.map(Duration::from_secs);
+        // Zero turns these limits off
+        let limit = |n: Option<u64>| n.map(|n| (n > 0).then_some(n));
+        let mb = |n: u64| n * 1024 * 1024;
+
+        macro_rules! set {
+            ($($field:ident = $value:expr),+ $(,)?) => {$(
+                if let Some(value) = $value {
+                    config.$field = value;
+                }
+            )+};
+        }
+        set!(
+            runner_id = self.runner_id,
+            orchestrator_url = self.orchestrator_url,
+            orchestrator_grpc_url = self.orchestrator_grpc_url.map(Some),
+            orchestrator_token = self.orchestrator_token.map(Some),
+            registration_token = self.runner_registration_token.map(Some),
+            orchestrator_headers = self
+                .orchestrator_headers
+                .map(|headers| headers.into_iter().collect()),
+            tls_cert_path = self.orchestrator_tls_cert.map(Some),
+            tls_key_path = self.orchestrator_tls_key.map(Some),
+            tls_ca_path = self.orchestrator_tls_ca.map(Some),
+            workspace_base = self.workspace_base,
+            default_container_image = self.default_container_image,
+            container_runtime = self.container_runtime,
+            container_os = self.container_os,
+            poll_interval = secs(self.poll_interval),
+            long_poll_wait = secs(self.long_poll_wait),
+            log_send_interval = secs(self.log_send_interval),
+            lease_renew_interval = secs(self.lease_renew_interval),
+            job_timeout = secs(self.job_timeout),
+            command_timeout = secs(self.command_timeout),
+            labels = self
+                .runner_labels
+                .map(|labels| labels.into_iter().collect()),
+            plugins = self.runner_plugins,
+            max_parallel_jobs = self.max_parallel_jobs,
+            prepull_images = self.prepull_images,
+            warm_images = self.warm_images,
+            warm_pool_size = self.warm_pool_size,
+            image_cache_max_age =
+                limit(self.image_cache_max_age).map(|age| age.map(Duration::from_secs)),
+            image_cache_max_size = limit(self.image_cache_max_size_mb).map(|n| n.map(mb)),
+            keep_failed_workspaces = self.keep_failed_workspaces,
+            workspace_max_age =
+                limit(self.workspace_max_age).map(|age| age.map(Duration::from_secs)),
+            workspace_sweep_interval = secs(self.workspace_sweep_interval),
+            workspace_quota = limit(self.workspace_quota_mb).map(|n| n.map(mb)),
+            allowed_registries = self.allowed_registries,
+            allowed_images = self.allowed_images,
+        );
+    }
+}
+
+/// Architecture of the runner host, as container images name it
+/// (`amd64`, `arm64`, ...)
+pub fn host_arch() -> &'static str {
+    match std::env::consts::ARCH {
+        "x86_64" => "amd64",
+        "aarch64" => "arm64",
+        "x86" => "386",
+        "powerpc64" => "ppc64le",
+        arch => arch,
+    }
+}
+
+/// Parses a comma-separated list, skipping empty entries
+fn parse_list(s: &str) -> Vec<String> {
+    s.split(',')
+        .map(str::trim)
+        .filter(|item| !item.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// Parses a comma-separated list of `Name=value` header pairs
+fn parse_headers(s: &str) -> anyhow::Result<Vec<(String, String)>> {
+    parse_pairs("ORCHESTRATOR_HEADERS", s)
+}
+
+/// Parses a comma-separated list of `Name=value` pairs read from `var`
+fn parse_pairs(var: &str, s: &str) -> anyhow::Result<Vec<(String, String)>> {
+    s.split(',')
+        .map(str::trim)
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| {
+            let (name, value) = pair.split_once('=').ok_or_else(|| {
+                anyhow::anyhow!("invalid {} entry '{}', expected Name=value", var, pair)
+            })?;
+            Ok((name.trim().to_string(), value.trim().to_string()))
+        })
+        .collect()
+}
+
 impl Default for Config {
     fn default() -> Self {
         Self::new(
@@ -196,6 +734,20 @@ mod tests {
 
         config.orchestrator_url = "http://localhost:8080".to_string();
         assert!(config.validate().is_ok());
+
+        // A client certificate without its key should fail
+        config.tls_cert_path = Some(PathBuf::from("/etc/rivet/runner.crt"));
+        assert!(config.validate().is_err());
+
+        config.tls_key_path = Some(PathBuf::from("/etc/rivet/runner.key"));
+        assert!(config.validate().is_ok());
+
+        // gRPC endpoints need an explicit scheme
+        config.orchestrator_grpc_url = Some("localhost:9090".to_string());
+        assert!(config.validate().is_err());
+
+        config.orchestrator_grpc_url = Some("http://localhost:9090".to_string());
+        assert!(config.validate().is_ok());
     }
 
     #[test]
@@ -207,4 +759,122 @@ mod tests {
         assert_eq!(config.labels.get("env"), Some(&"prod".to_string()));
         assert_eq!(config.labels.get("region"), Some(&"us-west".to_string()));
     }
+
+    #[test]
+    fn test_tags_sorted_by_key() {
+        let config = Config::default()
+            .with_label("region".to_string(), "us-west".to_string())
+            .with_label("env".to_string(), "prod".to_string());
+
+        let keys: Vec<String> = config.tags().into_iter().map(|t| t.key).collect();
+        assert_eq!(keys, vec!["env", "region"]);
+    }
+
+    #[test]
+    fn test_registered_tags_include_platform() {
+        let config = Config::default().with_label("arch".to_string(), "arm64".to_string());
+
+        let tags: Vec<(String, String)> = config
+            .registered_tags()
+            .into_iter()
+            .map(|t| (t.key, t.value))
+            .collect();
+        assert_eq!(
+            tags,
+            vec![
+                ("arch".to_string(), "arm64".to_string()),
+                ("os".to_string(), config.container_os.as_str().to_string()),
+            ]
+        );
+        assert_eq!(config.arch(), "arm64");
+        assert_eq!(Config::default().arch(), host_arch());
+    }
+
+    #[test]
+    fn test_parse_headers() {
+        let headers = parse_headers("X-Tenant=acme, X-Env=prod,").unwrap();
+        assert_eq!(
+            headers,
+            vec![
+                ("X-Tenant".to_string(), "acme".to_string()),
+                ("X-Env".to_string(), "prod".to_string()),
+            ]
+        );
+
+        assert!(parse_headers("X-Tenant").is_err());
+    }
+
+    #[test]
+    fn test_file_with_env_overrides() {
+        let file: ConfigFile = toml::from_str(
+            r#"
+            runner_id = "runner-1"
+            orchestrator_url = "https://rivet.internal"
+            max_parallel_jobs = 4
+            poll_interval = 10
+            workspace_quota_mb = 0
+            runner_labels = { os = "linux" }
+            allowed_registries = ["docker.io"]
+            container_runtime = "docker"
+            "#,
+        )
+        .unwrap();
+        let mut config = Config::default();
+        file.apply(&mut config);
+        config
+            .apply_env(|name| match name {
+                "MAX_PARALLEL_JOBS" => Some("8".to_string()),
+                "POLL_INTERVAL" => Some(String::new()),
+                "CONTAINER_OS" => Some("windows".to_string()),
+                _ => None,
+            })
+            .unwrap();
+
+        assert_eq!(config.runner_id, "runner-1");
+        assert_eq!(config.max_parallel_jobs, 8);
+        assert_eq!(config.poll_interval, Duration::from_secs(10));
+        assert_eq!(config.workspace_quota, None);
+        assert_eq!(config.labels.get("os"), Some(&"linux".to_string()));
+        assert_eq!(config.container_runtime, ContainerRuntime::Docker);
+        assert_eq!(config.container_os, ContainerOs::Windows);
+        assert!(config.validate().is_ok());
+        assert!(
+            config
+                .clone()
+                .apply_env(|name| (name == "CONTAINER_RUNTIME").then(|| "lxc".to_string()))
+                .is_err()
+        );
+
+        // A default image from a registry that is not allowed
+        config.default_container_image = "quay.io/acme/builder".to_string();
+        assert!(config.validate().is_err());
+
+        assert!(toml::from_str::<ConfigFile>("max_jobs = 4").is_err());
+    }
+
+    #[test]
+    fn test_reload_keeps_restart_only_settings() {
+        let config = Config::default();
+        let mut new = config.clone();
+        new.max_parallel_jobs = 6;
+        new.command_timeout = Duration::from_secs(60);
+        new.runner_id = "renamed".to_string();
+        new.labels.insert("os".to_string(), "linux".to_string());
+
+        let (reloaded, ignored) = config.reload(&new);
+        assert_eq!(reloaded.max_parallel_jobs, 6);
+        assert_eq!(reloaded.command_timeout, Duration::from_secs(60));
+        assert_eq!(reloaded.runner_id, config.runner_id);
+        assert!(reloaded.labels.is_empty());
+        assert_eq!(ignored, vec!["runner_id", "labels"]);
+    }
+
+    #[test]
+    fn test_parse_list() {
+        assert_eq!(
+            parse_list(" alpine:latest,,rust:1 ,"),
+            vec!["alpine:latest".to_string(), "rust:1".to_string()]
+        );
+        assert!(parse_list("").is_empty());
+    }
 }
@@ -1,29 +1,75 @@
 //! Execution context for pipeline jobs
 //!
 //! Contains all state needed during pipeline execution:
-//! - Log buffer for collecting logs
+//! - Log buffer for collecting logs, tagged with the running stage
+//! - Stage progress not yet reported to the orchestrator
 //! - Workspace path for job files
-//! - Job input parameters
+//! - Job input parameters and the pipeline's environment variables
+//! - Annotations the job's scripts set
+//! - Secrets visible to the job and secret inputs, masked in its logs
 //! - Container stack for tracking current execution context
 //! - Container manager for executing commands
 
-use rivet_core::domain::log::{LogEntry, LogLevel};
+use rivet_core::domain::job::{StageResult, StageStatus};
+use rivet_core::domain::log::{LogEntry, LogLevel, OutputStream};
+use rivet_core::dto::job::StageUpdate;
 use serde_json::Value as JsonValue;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
+use std::future::Future;
 use std::path::PathBuf;
 use std::sync::{Arc, Mutex};
+use std::time::Duration;
 use uuid::Uuid;
 
-use crate::podman::ContainerManager;
+use crate::lua::modules::Annotations;
+use crate::podman::{ContainerManager, ImagePolicy};
+use crate::pool::WarmPool;
+use crate::state::RunnerState;
+
+/// Replaces secret values in log messages
+const SECRET_MASK: &str = "***";
+
+tokio::task_local! {
+    /// Stage the current future runs as part of
+    static STAGE: String;
+}
+
+/// Runs a future as part of a stage
+///
+/// Log entries added and containers pushed while the future runs belong to
+/// the stage, which lets independent stages run concurrently.
+pub async fn in_stage<F: Future>(stage: String, future: F) -> F::Output {
+    STAGE.scope(stage, future).await
+}
+
+/// Stage the current future runs as part of, `None` outside of stages
+pub fn current_stage() -> Option<String> {
+    STAGE.try_with(Clone::clone).ok()
+}
 
 /// Execution context shared across pipeline execution
 pub struct Context {
     /// Log buffer with entries
     log_buffer: Mutex<Vec<LogEntry>>,
 
+    /// Stage progress not yet reported, oldest first
+    stage_updates: Mutex<Vec<StageUpdate>>,
+
     /// Job input parameters
     pub inputs: HashMap<String, JsonValue>,
 
+    /// Environment variables the pipeline sets in the job's containers
+    pub env: BTreeMap<String, String>,
+
+    /// Annotations the job's scripts set, added to its labels when it ends
+    pub annotations: Annotations,
+
+    /// Secret values visible to the job, by name
+    secrets: HashMap<String, String>,
+
+    /// Values of the job's secret inputs, masked in logs like secrets
+    secret_inputs: Vec<String>,
+
     /// Container manager for this job
     /// Manages multiple containers and tracks the execution stack
     pub container_manager: ContainerManager,
@@ -36,35 +82,181 @@ impl Context {
     /// * `job_id` - The job ID
     /// * `workspace_base` - Base directory for workspaces (e.g., /tmp)
     /// * `inputs` - Job input parameters
+    /// * `env` - Environment variables the pipeline sets in the job's containers
+    /// * `secrets` - Secret values visible to the job, by name
+    /// * `secret_inputs` - Parameters of secret inputs
+    /// * `command_timeout` - Timeout of commands that do not set their own
+    /// * `images` - Images the job's containers may run
+    /// * `pool` - Runner-wide pool of warm containers
+    /// * `state` - Runner state the job's containers are recorded in
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         job_id: Uuid,
         workspace_base: PathBuf,
         inputs: HashMap<String, JsonValue>,
+        env: BTreeMap<String, String>,
+        secrets: HashMap<String, String>,
+        secret_inputs: &[String],
+        command_timeout: Duration,
+        images: ImagePolicy,
+        pool: Arc<WarmPool>,
+        state: Arc<RunnerState>,
     ) -> Arc<Self> {
+        let secret_inputs = secret_inputs
+            .iter()
+            .filter_map(|name| inputs.get(name)?.as_str().map(str::to_string))
+            .collect();
+
         let workspace = workspace_base.join(job_id.to_string());
         let workspace_str = workspace.to_string_lossy().to_string();
 
-        let container_manager = ContainerManager::new(job_id, workspace_str);
+        let container_manager = ContainerManager::new(
+            job_id,
+            workspace_str,
+            command_timeout,
+            env.clone(),
+            images,
+            pool,
+            state,
+        );
 
         Arc::new(Self {
             log_buffer: Mutex::new(Vec::new()),
+            stage_updates: Mutex::new(Vec::new()),
             inputs,
+            env,
+            annotations: Annotations::default(),
+            secrets,
+            secret_inputs,
             container_manager,
         })
     }
 
+    /// Records the progress of a stage, to be reported to the orchestrator
+    ///
+    /// # Arguments
+    /// * `name` - Name of the stage
+    /// * `status` - Status the stage reached
+    /// * `needs` - Stages the stage declared it needs
+    /// * `error_message` - Why the stage failed, secret values masked
+    pub fn record_stage(
+        &self,
+        name: &str,
+        status: StageStatus,
+        needs: &[String],
+        error_message: Option<String>,
+    ) {
+        let update = StageUpdate {
+            name: name.to_string(),
+            status,
+            timestamp: chrono::Utc::now(),
+            error_message: error_message.map(|m| self.mask_secrets(m)),
+            needs: needs.to_vec(),
+            result: None,
+        };
+        self.stage_updates.lock().unwrap().push(update);
+    }
+
+    /// Records the success of a stage with the result its script returned
+    ///
+    /// # Arguments
+    /// * `name` - Name of the stage
+    /// * `needs` - Stages the stage declared it needs
+    /// * `result` - What the script returned, secret values masked
+    pub fn record_stage_success(&self, name: &str, needs: &[String], result: Option<StageResult>) {
+        let result = result.map(|mut result| {
+            result.summary = result.summary.map(|s| self.mask_secrets(s));
+            result.artifacts = result
+                .artifacts
+                .into_iter()
+                .map(|a| self.mask_secrets(a))
+                .collect();
+            result
+        });
+
+        let update = StageUpdate {
+            name: name.to_string(),
+            status: StageStatus::Succeeded,
+            timestamp: chrono::Utc::now(),
+            error_message: None,
+            needs: needs.to_vec(),
+            result,
+        };
+        self.stage_updates.lock().unwrap().push(update);
+    }
+
+    /// Drains all stage updates not yet reported
+    pub fn drain_stage_updates(&self) -> Vec<StageUpdate> {
+        let mut updates = self.stage_updates.lock().unwrap();
+        updates.drain(..).collect()
+    }
+
+    /// Gets the value of a secret
+    ///
+    /// # Arguments
+    /// * `name` - Name of the secret
+    ///
+    /// # Returns
+    /// The secret value, or None if no such secret is visible to the job
+    pub fn secret(&self, name: &str) -> Option<&str> {
+        self.secrets.get(name).map(String::as_str)
+    }
+
     /// Adds a log entry to the buffer
-    pub fn add_log(&self, entry: LogEntry) {
+    ///
+    /// Entries without a stage are tagged with the running one, and secret
+    /// values are masked in the message.
+    pub fn add_log(&self, mut entry: LogEntry) {
+        entry.message = self.mask_secrets(entry.message);
+        for value in entry
+            .fields
+            .iter_mut()
+            .flat_map(|fields| fields.values_mut())
+        {
+            self.mask_json(value);
+        }
+
+        if entry.stage.is_none() {
+            entry.stage = current_stage();
+        }
+
         let mut buffer = self.log_buffer.lock().unwrap();
         buffer.push(entry);
     }
 
+    /// Replaces the secret values in the strings of a JSON value
+    pub fn mask_json(&self, value: &mut JsonValue) {
+        match value {
+            JsonValue::String(s) => *s = self.mask_secrets(std::mem::take(s)),
+            JsonValue::Array(items) => items.iter_mut().for_each(|item| self.mask_json(item)),
+            JsonValue::Object(fields) => {
+                fields.values_mut().for_each(|field| self.mask_json(field))
+            }
+            _ => {}
+        }
+    }
+
+    /// Replaces the secret values in a message
+    fn mask_secrets(&self, mut message: String) -> String {
+        let values = self.secrets.values().chain(&self.secret_inputs);
+        for value in values.filter(|v| !v.is_empty()) {
+            if message.contains(value.as_str()) {
+                message = message.replace(value.as_str(), SECRET_MASK);
+            }
+        }
+        message
+    }
+
     /// Logs a debug message
     pub fn log_debug(&self, message: String) {
         self.add_log(LogEntry {
             timestamp: chrono::Utc::now(),
             level: LogLevel::Debug,
             message,
+            stage: None,
+            stream: None,
+            sequence: None,
+            fields: None,
         });
     }
 
@@ -74,6 +266,10 @@ impl Context {
             timestamp: chrono::Utc::now(),
             level: LogLevel::Info,
             message,
+            stage: None,
+            stream: None,
+            sequence: None,
+            fields: None,
         });
     }
 
@@ -83,6 +279,10 @@ impl Context {
             timestamp: chrono::Utc::now(),
             level: LogLevel::Warning,
             message,
+            stage: None,
+            stream: None,
+            sequence: None,
+            fields: None,
         });
     }
 
@@ -92,6 +292,28 @@ impl Context {
             timestamp: chrono::Utc::now(),
             level: LogLevel::Error,
             message,
+            stage: None,
+            stream: None,
+            sequence: None,
+            fields: None,
+        });
+    }
+
+    /// Logs a line of process output
+    ///
+    /// # Arguments
+    /// * `level` - Level the process output is logged at
+    /// * `stream` - Stream the line was read from
+    /// * `message` - The line, without terminator
+    pub fn log_output(&self, level: LogLevel, stream: OutputStream, message: String) {
+        self.add_log(LogEntry {
+            timestamp: chrono::Utc::now(),
+            level,
+            message,
+            stage: None,
+            stream: Some(stream),
+            sequence: None,
+            fields: None,
         });
     }
 
@@ -0,0 +1,379 @@
+//! Debug sessions of failed jobs
+//!
+//! When a failed job's workspace is kept, each of its containers is also
+//! committed to an image tagged under `snapshot_repository`. A user asking
+//! to debug the job reaches this runner through the orchestrator: the
+//! runner starts a container from the snapshot with the kept workspace
+//! mounted and relays an interactive shell over the session's WebSocket.
+//!
+//! Snapshots are deleted along with the kept workspace.
+
+use anyhow::{Context, Result};
+use rivet_client::{DebugFrame, DebugReceiver, DebugSender, OrchestratorClient};
+use rivet_core::dto::debug::DebugRequest;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::process::Stdio;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWriteExt};
+use tokio::sync::mpsc;
+use tracing::{debug, info, warn};
+use uuid::Uuid;
+
+use crate::config::Config;
+use crate::podman::{SNAPSHOT_LABEL, command, container_os, inspect_images, run_container};
+use crate::workspace::FAILED_DIR;
+
+/// How long each long-poll for debug requests waits
+const DEBUG_POLL_WAIT: Duration = Duration::from_secs(30);
+
+/// Pause after a failed poll before trying again
+const DEBUG_POLL_BACKOFF: Duration = Duration::from_secs(5);
+
+/// Image repository the container snapshots of a failed job are tagged in
+pub fn snapshot_repository(job_id: Uuid) -> String {
+    format!("localhost/rivet-debug-{}", job_id)
+}
+
+/// A snapshot as reported by `image inspect`
+///
+/// Podman reports labels at the top level, docker only under `Config`.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct SnapshotInfo {
+    id: String,
+    #[serde(default)]
+    labels: Option<HashMap<String, String>>,
+    #[serde(default)]
+    config: Option<SnapshotConfig>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct SnapshotConfig {
+    #[serde(default)]
+    labels: Option<HashMap<String, String>>,
+}
+
+impl SnapshotInfo {
+    /// Image the snapshotted container ran
+    fn image(&self) -> Option<&str> {
+        self.labels
+            .as_ref()
+            .or_else(|| self.config.as_ref()?.labels.as_ref())?
+            .get(SNAPSHOT_LABEL)
+            .map(String::as_str)
+    }
+}
+
+/// Serves the debug sessions the orchestrator relays to this runner
+pub struct DebugListener {
+    client: OrchestratorClient,
+    runner_id: String,
+    workspace_base: PathBuf,
+    default_image: String,
+}
+
+impl DebugListener {
+    /// Creates the listener described by the runner configuration
+    pub fn new(config: &Config, client: OrchestratorClient) -> Arc<Self> {
+        Arc::new(Self {
+            client,
+            runner_id: config.runner_id.clone(),
+            workspace_base: config.workspace_base.clone(),
+            default_image: config.default_container_image.clone(),
+        })
+    }
+
+    /// Waits for debug requests and serves each in its own task
+    ///
+    /// Runs for the lifetime of the runner, or until the orchestrator turns
+    /// out not to support debug sessions.
+    pub async fn run(self: Arc<Self>) {
+        loop {
+            match self
+                .client
+                .next_debug_request(&self.runner_id, DEBUG_POLL_WAIT)
+                .await
+            {
+                Ok(Some(request)) => {
+                    let listener = Arc::clone(&self);
+                    tokio::spawn(async move {
+                        let session_id = request.session_id;
+                        if let Err(e) = listener.serve(request).await {
+                            warn!("Debug session {} failed: {:#}", session_id, e);
+                        }
+                    });
+                }
+                Ok(None) => {}
+                Err(e) if matches!(e.status(), Some(404 | 405 | 501)) => {
+                    info!("Orchestrator does not support debug sessions ({})", e);
+                    return;
+                }
+                Err(e) => {
+                    debug!("Failed to poll for debug sessions: {}", e);
+                    tokio::time::sleep(DEBUG_POLL_BACKOFF).await;
+                }
+            }
+        }
+    }
+
+    /// Restores a failed job's environment and relays a shell in it
+    async fn serve(&self, request: DebugRequest) -> Result<()> {
+        let job_id = request.job_id;
+        info!(
+            "Serving debug session {} for job {}",
+            request.session_id, job_id
+        );
+
+        let (mut sender, receiver) = self
+            .client
+            .attach_debug_session(request.session_id)
+            .await
+            .context("Failed to attach to the debug session")?;
+
+        let workspace = self
+            .workspace_base
+            .join(FAILED_DIR)
+            .join(job_id.to_string());
+        if !tokio::fs::try_exists(&workspace).await.unwrap_or(false) {
+            let notice = format!(
+                "The workspace of job {} was not kept on runner {} \
+                 (KEEP_FAILED_WORKSPACES is 0 or it was deleted since)",
+                job_id, self.runner_id
+            );
+            return end_session(&mut sender, notice).await;
+        }
+
+        let wanted = request.image.as_deref().unwrap_or(&self.default_image);
+        let image = match restore_image(job_id, wanted, request.image.is_some()).await {
+            Ok(image) => image,
+            Err(e) => return end_session(&mut sender, format!("{:#}", e)).await,
+        };
+
+        let container_name = format!("rivet-debug-{}", request.session_id);
+        let workspace = workspace.to_string_lossy().into_owned();
+        if let Err(e) = run_container(&container_name, &image, &workspace).await {
+            return end_session(&mut sender, format!("{:#}", e)).await;
+        }
+
+        let notice = format!(
+            "Restored job {} ({}) on runner {}, workspace at {}",
+            job_id,
+            wanted,
+            self.runner_id,
+            container_os().workspace_dir()
+        );
+        let result = match sender.send(DebugFrame::Notice(notice)).await {
+            Ok(()) => relay_shell(&container_name, sender, receiver).await,
+            Err(e) => Err(e.into()),
+        };
+
+        let _ = command()
+            .arg("rm")
+            .arg("-f")
+            .arg(&container_name)
+            .output()
+            .await;
+        info!("Debug session {} ended", request.session_id);
+
+        result
+    }
+}
+
+/// Picks the image a debug container is started from
+///
+/// # Arguments
+/// * `job_id` - The failed job
+/// * `wanted` - Image of the job container to restore
+/// * `explicit` - Whether the user asked for `wanted`
+///
+/// # Returns
+/// The snapshot of the container that ran `wanted`, or `wanted` itself when
+/// the job left no snapshot and the user did not ask for a specific image
+async fn restore_image(job_id: Uuid, wanted: &str, explicit: bool) -> Result<String> {
+    let snapshots = list_snapshots(job_id).await?;
+    if let Some(snapshot) = snapshots.iter().find(|s| s.image() == Some(wanted)) {
+        return Ok(snapshot.id.clone());
+    }
+
+    if snapshots.is_empty() && !explicit {
+        warn!(
+            "Job {} left no container snapshot, debugging it in a fresh {} container",
+            job_id, wanted
+        );
+        return Ok(wanted.to_string());
+    }
+
+    let available: Vec<&str> = snapshots.iter().filter_map(SnapshotInfo::image).collect();
+    anyhow::bail!(
+        "Job {} has no container for image {} (available: {})",
+        job_id,
+        wanted,
+        if available.is_empty() {
+            "none".to_string()
+        } else {
+            available.join(", ")
+        }
+    )
+}
+
+/// Lists the container snapshots of a job
+async fn list_snapshots(job_id: Uuid) -> Result<Vec<SnapshotInfo>> {
+    let filter = format!("reference={}", snapshot_repository(job_id));
+    inspect_images(Some(&filter))
+        .await
+        .with_context(|| format!("Failed to list snapshots of job {}", job_id))
+}
+
+/// Deletes the container snapshots of a job
+pub async fn remove_snapshots(job_id: Uuid) {
+    let snapshots = match list_snapshots(job_id).await {
+        Ok(snapshots) => snapshots,
+        Err(e) => {
+            warn!("{:#}", e);
+            return;
+        }
+    };
+
+    for snapshot in snapshots {
+        debug!("Removing snapshot {} of job {}", snapshot.id, job_id);
+        let output = command()
+            .arg("rmi")
+            .arg("-f")
+            .arg(&snapshot.id)
+            .output()
+            .await;
+        match output {
+            Ok(output) if output.status.success() => {}
+            Ok(output) => warn!(
+                "Failed to remove snapshot {}: {}",
+                snapshot.id,
+                String::from_utf8_lossy(&output.stderr).trim()
+            ),
+            Err(e) => warn!("Failed to remove snapshot {}: {}", snapshot.id, e),
+        }
+    }
+}
+
+/// Sends a last notice and ends the session
+async fn end_session(sender: &mut DebugSender, notice: String) -> Result<()> {
+    warn!("Ending debug session: {}", notice);
+    sender.send(DebugFrame::Notice(notice)).await?;
+    sender.close().await?;
+    Ok(())
+}
+
+/// Runs an interactive shell in a container, relaying it over the session
+///
+/// Ends when the shell exits or the user leaves.
+async fn relay_shell(
+    container_name: &str,
+    mut sender: DebugSender,
+    mut receiver: DebugReceiver,
+) -> Result<()> {
+    let (shell, shell_args) = container_os().shell();
+    let mut child = command()
+        .arg("exec")
+        .arg("-i")
+        .arg(container_name)
+        .arg(shell)
+        .args(shell_args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .context("Failed to start the debug shell")?;
+
+    let mut stdin = child.stdin.take().expect("stdin is piped");
+    let (output_tx, mut output_rx) = mpsc::channel(16);
+    tokio::spawn(forward_output(
+        child.stdout.take().expect("stdout is piped"),
+        output_tx.clone(),
+    ));
+    tokio::spawn(forward_output(
+        child.stderr.take().expect("stderr is piped"),
+        output_tx,
+    ));
+
+    let input = async {
+        while let Some(frame) = receiver.next().await {
+            match frame {
+                Ok(DebugFrame::Data(data)) => {
+                    if stdin.write_all(&data).await.is_err() {
+                        break;
+                    }
+                }
+                Ok(DebugFrame::Notice(_)) => {}
+                Err(e) => {
+                    debug!("Debug session closed: {}", e);
+                    break;
+                }
+            }
+        }
+    };
+    let output = async {
+        while let Some(chunk) = output_rx.recv().await {
+            if sender.send(DebugFrame::Data(chunk)).await.is_err() {
+                break;
+            }
+        }
+    };
+
+    // The shell exited (its output ended) or the user left (its input ended)
+    tokio::select! {
+        _ = input => {}
+        _ = output => {}
+    }
+
+    let _ = child.kill().await;
+    let status = child
+        .wait()
+        .await
+        .context("Failed to wait for the debug shell")?;
+    let notice = format!("Shell exited ({})", status);
+    let _ = sender.send(DebugFrame::Notice(notice)).await;
+    let _ = sender.close().await;
+
+    Ok(())
+}
+
+/// Forwards a shell output pipe chunk by chunk until it closes
+async fn forward_output(mut pipe: impl AsyncRead + Unpin, output: mpsc::Sender<Vec<u8>>) {
+    let mut buffer = [0u8; 4096];
+    loop {
+        match pipe.read(&mut buffer).await {
+            Ok(0) | Err(_) => break,
+            Ok(n) => {
+                if output.send(buffer[..n].to_vec()).await.is_err() {
+                    break;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_snapshots() {
+        let json = r#"[
+            {"Id": "abc", "Labels": {"rivet.image": "docker.io/alpine:latest"}},
+            {"Id": "def", "Labels": null},
+            {"Id": "ghi", "Config": {"Labels": {"rivet.image": "mcr.microsoft.com/windows/nanoserver:ltsc2022"}}}
+        ]"#;
+        let snapshots: Vec<SnapshotInfo> = serde_json::from_str(json).unwrap();
+
+        assert_eq!(snapshots[0].image(), Some("docker.io/alpine:latest"));
+        assert_eq!(snapshots[1].image(), None);
+        assert_eq!(
+            snapshots[2].image(),
+            Some("mcr.microsoft.com/windows/nanoserver:ltsc2022")
+        );
+    }
+}
@@ -0,0 +1,454 @@
+//! gRPC orchestrator transport
+//!
+//! `GrpcOrchestrator` implements `OrchestratorApi` over the `rivet.v1.RunnerService`
+//! for the job lifecycle (register, heartbeat, poll, claim, logs, complete).
+//! Operations runners do not use are delegated to the HTTP client.
+//!
+//! Logs are uploaded over one client-streaming call per job: the first
+//! `send_logs` opens the stream, later batches are pushed onto it, and
+//! `complete_job` closes it and waits for the upload to finish so that every
+//! log line is stored before the job is marked complete.
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use rivet_client::{
+    ClientError, OrchestratorApi, OrchestratorClient, REQUEST_ID_HEADER, Result, TRACEPARENT_HEADER,
+};
+use rivet_core::domain::job::{Job, JobResult, JobStatus};
+use rivet_core::domain::log::LogEntry;
+use rivet_core::domain::pipeline::Pipeline;
+use rivet_core::domain::runner::{Runner, RunnerLoad};
+use rivet_core::dto::job::{CreateJob, JobExecutionInfo, JobLease, StageUpdate};
+use rivet_core::dto::pipeline::{CreatePipeline, DeletePipelineQuery};
+use rivet_core::telemetry;
+use rivet_proto::{RunnerServiceClient, v1};
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+use tokio_stream::wrappers::ReceiverStream;
+use tonic::Status;
+use tonic::metadata::{AsciiMetadataKey, AsciiMetadataValue};
+use tonic::service::{Interceptor, interceptor::InterceptedService};
+use tonic::transport::{Certificate, Channel, ClientTlsConfig, Identity};
+use tracing::warn;
+use uuid::Uuid;
+
+use crate::config::TlsMaterial;
+
+/// Batches buffered per job before `send_logs` waits for the stream to catch up
+const LOG_STREAM_BUFFER: usize = 16;
+
+type Client = RunnerServiceClient<InterceptedService<Channel, MetadataInterceptor>>;
+
+/// Orchestrator client speaking gRPC for the job lifecycle
+pub struct GrpcOrchestrator {
+    client: Client,
+    http: OrchestratorClient,
+    log_streams: Mutex<HashMap<Uuid, LogStream>>,
+}
+
+/// An open log upload for a single job
+struct LogStream {
+    sender: mpsc::Sender<v1::LogBatch>,
+    upload: JoinHandle<std::result::Result<(), Status>>,
+}
+
+impl GrpcOrchestrator {
+    /// Creates a client for the gRPC endpoint at `url`
+    ///
+    /// The connection is established lazily on first use, so the orchestrator
+    /// does not need to be reachable yet.
+    ///
+    /// # Arguments
+    /// * `url` - gRPC endpoint (e.g., "http://localhost:9090"), `https` enables TLS
+    /// * `http` - HTTP client used for operations outside the runner protocol
+    /// * `token` - Bearer token sent with every call
+    /// * `headers` - Extra metadata sent with every call
+    /// * `tls` - Certificates for `https` endpoints
+    pub fn connect(
+        url: &str,
+        http: OrchestratorClient,
+        token: Option<&str>,
+        headers: &[(String, String)],
+        tls: TlsMaterial,
+    ) -> anyhow::Result<Self> {
+        let mut endpoint = Channel::from_shared(url.to_string())?;
+        if url.starts_with("https://") {
+            let mut tls_config = ClientTlsConfig::new().with_enabled_roots();
+            if let Some((cert, key)) = tls.identity {
+                tls_config = tls_config.identity(Identity::from_pem(cert, key));
+            }
+            if let Some(ca) = tls.ca_certificate {
+                tls_config = tls_config.ca_certificate(Certificate::from_pem(ca));
+            }
+            endpoint = endpoint.tls_config(tls_config)?;
+        }
+
+        let interceptor = MetadataInterceptor::new(token, headers)?;
+        let client = RunnerServiceClient::with_interceptor(endpoint.connect_lazy(), interceptor);
+
+        Ok(Self {
+            client,
+            http,
+            log_streams: Mutex::new(HashMap::new()),
+        })
+    }
+
+    /// Opens the log stream for a job
+    fn open_log_stream(&self) -> LogStream {
+        let (sender, receiver) = mpsc::channel(LOG_STREAM_BUFFER);
+        let mut client = self.client.clone();
+
+        let upload = tokio::spawn(async move {
+            client
+                .stream_logs(ReceiverStream::new(receiver))
+                .await
+                .map(|_| ())
+        });
+
+        LogStream { sender, upload }
+    }
+
+    /// Closes a job's log stream, waiting for buffered batches to be stored
+    async fn close_log_stream(&self, job_id: Uuid) -> Result<()> {
+        let stream = self.log_streams.lock().unwrap().remove(&job_id);
+        let Some(LogStream { sender, upload }) = stream else {
+            return Ok(());
+        };
+
+        drop(sender);
+        match upload.await {
+            Ok(result) => result.map_err(status_to_error),
+            Err(e) => Err(ClientError::InternalError(format!(
+                "Log upload task failed: {}",
+                e
+            ))),
+        }
+    }
+}
+
+// =============================================================================
+// Metadata & Errors
+// =============================================================================
+
+/// Adds credentials, extra headers and a request id to every call
+#[derive(Clone)]
+struct MetadataInterceptor {
+    metadata: Vec<(AsciiMetadataKey, AsciiMetadataValue)>,
+}
+
+impl MetadataInterceptor {
+    fn new(token: Option<&str>, headers: &[(String, String)]) -> anyhow::Result<Self> {
+        let mut metadata = Vec::new();
+        if let Some(token) = token {
+            metadata.push((
+                AsciiMetadataKey::from_static("authorization"),
+                format!("Bearer {}", token).parse()?,
+            ));
+        }
+        for (name, value) in headers {
+            metadata.push((name.to_ascii_lowercase().parse()?, value.parse()?));
+        }
+        Ok(Self { metadata })
+    }
+}
+
+impl Interceptor for MetadataInterceptor {
+    fn call(
+        &mut self,
+        mut request: tonic::Request<()>,
+    ) -> std::result::Result<tonic::Request<()>, Status> {
+        let metadata = request.metadata_mut();
+        for (key, value) in &self.metadata {
+            metadata.insert(key.clone(), value.clone());
+        }
+        if let Ok(request_id) = Uuid::new_v4().to_string().parse() {
+            metadata.insert(REQUEST_ID_HEADER, request_id);
+        }
+        if let Some(traceparent) = telemetry::traceparent(&tracing::Span::current())
+            .and_then(|traceparent| traceparent.parse().ok())
+        {
+            metadata.insert(TRACEPARENT_HEADER, traceparent);
+        }
+        Ok(request)
+    }
+}
+
+/// Maps gRPC status codes onto the errors the HTTP client reports
+fn status_to_error(status: Status) -> ClientError {
+    let message = status.message().to_string();
+    match status.code() {
+        tonic::Code::NotFound => ClientError::NotFound(message),
+        tonic::Code::InvalidArgument | tonic::Code::FailedPrecondition => ClientError::Validation {
+            field: None,
+            message,
+            fields: Vec::new(),
+        },
+        tonic::Code::AlreadyExists | tonic::Code::Aborted => ClientError::Conflict(message),
+        tonic::Code::Unauthenticated | tonic::Code::PermissionDenied => {
+            ClientError::Unauthorized(message)
+        }
+        tonic::Code::ResourceExhausted => ClientError::RateLimited { retry_after: None },
+        tonic::Code::Unavailable => ClientError::api_error(503, message),
+        tonic::Code::DeadlineExceeded => ClientError::api_error(504, message),
+        tonic::Code::Unimplemented => ClientError::api_error(501, message),
+        _ => ClientError::api_error(500, message),
+    }
+}
+
+fn conversion_error(err: rivet_proto::ConversionError) -> ClientError {
+    ClientError::ParseError(err.to_string())
+}
+
+#[async_trait]
+impl OrchestratorApi for GrpcOrchestrator {
+    // =============================================================================
+    // Pipelines (HTTP)
+    // =============================================================================
+
+    async fn create_pipeline(&self, req: CreatePipeline) -> Result<Pipeline> {
+        self.http.create_pipeline(req).await
+    }
+
+    async fn list_pipelines(&self) -> Result<Vec<Pipeline>> {
+        self.http.list_pipelines().await
+    }
+
+    async fn get_pipeline(&self, pipeline_id: Uuid) -> Result<Pipeline> {
+        self.http.get_pipeline(pipeline_id).await
+    }
+
+    async fn delete_pipeline(
+        &self,
+        pipeline_id: Uuid,
+        query: &DeletePipelineQuery,
+    ) -> Result<Option<Pipeline>> {
+        self.http.delete_pipeline(pipeline_id, query).await
+    }
+
+    // =============================================================================
+    // Jobs
+    // =============================================================================
+
+    async fn launch_job(&self, req: CreateJob) -> Result<Job> {
+        self.http.launch_job(req).await
+    }
+
+    async fn get_job(&self, job_id: Uuid) -> Result<Job> {
+        self.http.get_job(job_id).await
+    }
+
+    async fn list_all_jobs(&self) -> Result<Vec<Job>> {
+        self.http.list_all_jobs().await
+    }
+
+    async fn list_jobs_page(&self, limit: u32, offset: u32) -> Result<Vec<Job>> {
+        self.http.list_jobs_page(limit, offset).await
+    }
+
+    async fn list_scheduled_jobs(&self) -> Result<Vec<Job>> {
+        let response = self
+            .client
+            .clone()
+            .list_scheduled_jobs(v1::ListScheduledJobsRequest {})
+            .await
+            .map_err(status_to_error)?;
+
+        response
+            .into_inner()
+            .jobs
+            .into_iter()
+            .map(|job| Job::try_from(job).map_err(conversion_error))
+            .collect()
+    }
+
+    async fn list_jobs_by_pipeline(&self, pipeline_id: Uuid) -> Result<Vec<Job>> {
+        self.http.list_jobs_by_pipeline(pipeline_id).await
+    }
+
+    async fn claim_job(&self, job_id: Uuid, runner_id: &str) -> Result<JobExecutionInfo> {
+        let response = self
+            .client
+            .clone()
+            .claim_job(v1::ClaimJobRequest {
+                job_id: job_id.to_string(),
+                runner_id: runner_id.to_string(),
+            })
+            .await
+            .map_err(status_to_error)?;
+
+        JobExecutionInfo::try_from(response.into_inner()).map_err(conversion_error)
+    }
+
+    async fn next_job(&self, runner_id: &str, wait: Duration) -> Result<Option<JobExecutionInfo>> {
+        let response = self
+            .client
+            .clone()
+            .next_job(v1::NextJobRequest {
+                runner_id: runner_id.to_string(),
+                wait_millis: wait.as_millis() as u64,
+            })
+            .await
+            .map_err(status_to_error)?;
+
+        response
+            .into_inner()
+            .job
+            .map(|info| JobExecutionInfo::try_from(info).map_err(conversion_error))
+            .transpose()
+    }
+
+    async fn update_job_status(&self, job_id: Uuid, status: JobStatus) -> Result<()> {
+        self.http.update_job_status(job_id, status).await
+    }
+
+    async fn renew_job_lease(
+        &self,
+        job_id: Uuid,
+        runner_id: &str,
+        ttl: Duration,
+    ) -> Result<JobLease> {
+        let response = self
+            .client
+            .clone()
+            .renew_lease(v1::RenewLeaseRequest {
+                job_id: job_id.to_string(),
+                runner_id: runner_id.to_string(),
+                ttl_seconds: ttl.as_secs().max(1),
+            })
+            .await
+            .map_err(status_to_error)?;
+
+        JobLease::try_from(response.into_inner()).map_err(conversion_error)
+    }
+
+    async fn complete_job(
+        &self,
+        job_id: Uuid,
+        claim_token: Option<u64>,
+        result: JobResult,
+    ) -> Result<()> {
+        if let Err(e) = self.close_log_stream(job_id).await {
+            warn!("Log stream for job {} ended with an error: {}", job_id, e);
+        }
+
+        self.client
+            .clone()
+            .complete_job(v1::CompleteJobRequest {
+                job_id: job_id.to_string(),
+                result: Some(result.into()),
+                claim_token,
+            })
+            .await
+            .map_err(status_to_error)?;
+
+        Ok(())
+    }
+
+    // =============================================================================
+    // Logs
+    // =============================================================================
+
+    async fn send_stage_updates(&self, job_id: Uuid, updates: Vec<StageUpdate>) -> Result<()> {
+        self.http.send_stage_updates(job_id, updates).await
+    }
+
+    async fn get_job_logs(&self, job_id: Uuid) -> Result<Vec<LogEntry>> {
+        self.http.get_job_logs(job_id).await
+    }
+
+    async fn send_logs(
+        &self,
+        job_id: Uuid,
+        claim_token: Option<u64>,
+        entries: Vec<LogEntry>,
+    ) -> Result<()> {
+        let batch = v1::LogBatch {
+            job_id: job_id.to_string(),
+            entries: entries.into_iter().map(Into::into).collect(),
+            claim_token,
+        };
+
+        let sender = self
+            .log_streams
+            .lock()
+            .unwrap()
+            .entry(job_id)
+            .or_insert_with(|| self.open_log_stream())
+            .sender
+            .clone();
+
+        if let Err(mpsc::error::SendError(batch)) = sender.send(batch).await {
+            // The upload ended early: report why, then retry the batch on a fresh stream
+            if let Err(e) = self.close_log_stream(job_id).await {
+                warn!("Log stream for job {} failed: {}", job_id, e);
+            }
+
+            let stream = self.open_log_stream();
+            let sender = stream.sender.clone();
+            self.log_streams.lock().unwrap().insert(job_id, stream);
+            sender
+                .send(batch)
+                .await
+                .map_err(|_| ClientError::InternalError("Log stream closed".to_string()))?;
+        }
+
+        Ok(())
+    }
+
+    async fn flush_logs(&self, job_id: Uuid) -> Result<()> {
+        self.close_log_stream(job_id).await
+    }
+
+    // =============================================================================
+    // Runners
+    // =============================================================================
+
+    async fn register_runner(&self, runner_id: &str) -> Result<Runner> {
+        let response = self
+            .client
+            .clone()
+            .register_runner(v1::RegisterRunnerRequest {
+                runner_id: runner_id.to_string(),
+                registration_token: None,
+                tags: Vec::new(),
+                plugins: Vec::new(),
+                info: None,
+            })
+            .await
+            .map_err(status_to_error)?;
+
+        let runner = response
+            .into_inner()
+            .runner
+            .ok_or_else(|| ClientError::ParseError("Missing runner in response".to_string()))?;
+        Runner::try_from(runner).map_err(conversion_error)
+    }
+
+    async fn send_heartbeat(&self, runner_id: &str, load: Option<RunnerLoad>) -> Result<()> {
+        self.client
+            .clone()
+            .heartbeat(v1::HeartbeatRequest {
+                runner_id: runner_id.to_string(),
+                load: load.map(Into::into),
+            })
+            .await
+            .map_err(status_to_error)?;
+
+        Ok(())
+    }
+
+    async fn list_runners(&self) -> Result<Vec<Runner>> {
+        self.http.list_runners().await
+    }
+
+    async fn get_runner(&self, runner_id: &str) -> Result<Runner> {
+        self.http.get_runner(runner_id).await
+    }
+
+    async fn delete_runner(&self, runner_id: &str) -> Result<()> {
+        self.http.delete_runner(runner_id).await
+    }
+}
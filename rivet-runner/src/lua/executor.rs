@@ -4,42 +4,78 @@
 //! - Creating execution sandboxes
 //! - Registering core modules
 //! - Parsing and executing pipelines with PipelineDefinition
-//! - Running individual stages
+//! - Running stages in dependency order, independent ones concurrently
+//! - Switching to the container a stage declares while it runs
+//! - Saving the `ctx` table stages share, and the annotations scripts set,
+//!   in the job result
+//! - Exposing the job's metadata as the read-only `job` table
 
 use anyhow::{Context as AnyhowContext, Result};
-use rivet_core::domain::job::JobResult;
-use rivet_lua::{create_sandbox, parse_pipeline_definition};
+use futures_util::StreamExt;
+use futures_util::stream::FuturesUnordered;
+use rivet_core::domain::job::{JobResult, StageResult, StageStatus};
+use rivet_core::domain::log::{LogLevel, OutputStream};
+use rivet_lua::{
+    PipelineDefinition, StageDefinition, StageWhen, create_sandbox, parse_pipeline_definition,
+    parse_stage_result,
+};
 use std::sync::Arc;
+use std::time::Instant;
 use tracing::{debug, error, info};
-use uuid::Uuid;
 
-use crate::context::Context;
+use crate::context::{self, Context};
 use crate::lua::modules::{
-    register_container_module, register_input_module, register_log_module, register_process_module,
+    JobMetadata, ctx_to_json, register_annotations_module, register_container_module,
+    register_ctx_module, register_env_module, register_input_module, register_job_module,
+    register_log_module, register_process_module,
 };
+use crate::podman::ExecOptions;
+
+/// How a stage ended, as seen by the stages that need it
+enum StageOutcome {
+    /// Ran without failing the job, or was skipped
+    Done,
+    /// Failed, with the job's failure message
+    Failed(String),
+}
 
 /// Lua executor service
 pub struct LuaExecutor {
     context: Arc<Context>,
+    /// Architecture stage images are picked for
+    arch: String,
 }
 
 impl LuaExecutor {
     /// Creates a new Lua executor with the given context
-    pub fn new(context: Arc<Context>) -> Self {
-        Self { context }
+    ///
+    /// # Arguments
+    /// * `context` - The job's execution context
+    /// * `arch` - Architecture stage images are picked for (`amd64`, ...)
+    pub fn new(context: Arc<Context>, arch: String) -> Self {
+        Self { context, arch }
     }
 
     /// Executes a pipeline from source code
     ///
     /// # Arguments
-    /// * `job_id` - The job ID for logging
+    /// * `job` - Metadata of the job, shown to scripts as the `job` table
     /// * `pipeline_source` - The Lua source code
+    /// * `skip_stages` - Stages that succeeded in the job this one resumes
+    /// * `resumed_ctx` - `ctx` table the job this one resumes ended with
     ///
     /// # Returns
-    /// The job result (success or error)
-    pub async fn execute_pipeline(&self, job_id: Uuid, pipeline_source: &str) -> JobResult {
+    /// The job result (success or error), with the `ctx` table stages left
+    /// as its output
+    pub async fn execute_pipeline(
+        &self,
+        job: &JobMetadata,
+        pipeline_source: &str,
+        skip_stages: &[String],
+        resumed_ctx: Option<&serde_json::Value>,
+    ) -> JobResult {
         // Create Lua sandbox with modules registered
-        let lua = match self.create_sandbox() {
+        let lua = match self.create_sandbox(job, resumed_ctx) {
             Ok(lua) => lua,
             Err(e) => {
                 return self.log_and_fail("Failed to create execution sandbox", e);
@@ -63,67 +99,348 @@ impl LuaExecutor {
             definition.stages.len()
         );
 
-        // Execute stages
-        for (idx, stage) in definition.stages.iter().enumerate() {
-            info!(
-                "Executing stage {}/{}: {}",
-                idx + 1,
-                definition.stages.len(),
-                stage.name
-            );
+        // Execute stages once everything they need is done, independent
+        // ones concurrently
+        let definition = &definition;
+        let stages = &definition.stages;
+        let mut outcomes: Vec<Option<StageOutcome>> = stages.iter().map(|_| None).collect();
+        let mut upstream_failures: Vec<Option<&str>> = vec![None; stages.len()];
+        let mut started = vec![false; stages.len()];
+        let mut running = FuturesUnordered::new();
+        let mut failure = None;
 
-            self.context
-                .log_info(format!("Starting stage: {}", stage.name));
-
-            // Check condition if present
-            if let Some(ref condition) = stage.condition {
-                match self.evaluate_condition(condition, &stage.name) {
-                    Ok(true) => {
-                        debug!("Stage '{}' condition passed", stage.name);
-                    }
-                    Ok(false) => {
-                        info!("Stage '{}' skipped (condition returned false)", stage.name);
-                        self.context.log_info(format!(
-                            "Stage '{}' skipped (condition not met)",
-                            stage.name
-                        ));
-                        continue;
-                    }
-                    Err(e) => {
-                        error!("Stage '{}' condition evaluation failed: {}", stage.name, e);
-                        self.context.log_error(format!(
-                            "Stage '{}' condition evaluation failed: {}",
-                            stage.name, e
-                        ));
-                        return JobResult::error(
-                            format!("Stage '{}' condition failed: {}", stage.name, e),
-                            1,
-                        );
-                    }
+        loop {
+            for (idx, stage) in stages.iter().enumerate() {
+                if started[idx] || stage.dependencies.iter().any(|&d| outcomes[d].is_none()) {
+                    continue;
                 }
+                started[idx] = true;
+
+                // A failed stage this one waits for, directly or not
+                let upstream_failure = stage.dependencies.iter().find_map(|&d| match outcomes[d] {
+                    Some(StageOutcome::Failed(_)) => Some(stages[d].name.as_str()),
+                    _ => upstream_failures[d],
+                });
+                upstream_failures[idx] = upstream_failure;
+
+                running.push(async move {
+                    let outcome = context::in_stage(
+                        stage.name.clone(),
+                        self.run_stage(definition, stage, skip_stages, upstream_failure),
+                    )
+                    .await;
+                    (idx, outcome)
+                });
             }
 
-            // Execute stage script
-            if let Err(e) = self.execute_stage(&stage.script, &stage.name) {
-                error!("Stage '{}' failed: {}", stage.name, e);
+            let Some((idx, outcome)) = running.next().await else {
+                break;
+            };
+            if let StageOutcome::Failed(message) = &outcome {
+                failure.get_or_insert_with(|| message.clone());
+            }
+            outcomes[idx] = Some(outcome);
+        }
+
+        let mut result = match failure {
+            Some(message) => JobResult::error(message, 1),
+            None => {
+                info!("Job {} completed successfully", job.id);
                 self.context
-                    .log_error(format!("Stage '{}' failed: {}", stage.name, e));
-                return JobResult::error(format!("Stage '{}' failed: {}", stage.name, e), 1);
+                    .log_info("Pipeline completed successfully".to_string());
+                JobResult::success()
             }
+        };
+
+        let mut output = serde_json::Map::new();
+
+        // Kept on failures too, for the job that resumes this one
+        match ctx_to_json(&lua) {
+            Ok(Some(mut ctx)) => {
+                self.context.mask_json(&mut ctx);
+                output.insert("ctx".to_string(), ctx);
+            }
+            Ok(None) => {}
+            Err(e) => self
+                .context
+                .log_warning(format!("The ctx table could not be saved: {}", e)),
+        }
+
+        // Added to the job's labels by the orchestrator
+        let annotations = self.context.annotations.lock().unwrap().clone();
+        if !annotations.is_empty() {
+            let mut annotations = serde_json::json!(annotations);
+            self.context.mask_json(&mut annotations);
+            output.insert("annotations".to_string(), annotations);
+        }
+
+        if !output.is_empty() {
+            result.output = Some(serde_json::Value::Object(output));
+        }
 
+        result
+    }
+
+    /// Runs a single stage, reporting its progress
+    ///
+    /// # Arguments
+    /// * `definition` - The pipeline, for its stage hooks
+    /// * `stage` - The stage to run
+    /// * `skip_stages` - Stages that succeeded in the job this one resumes
+    /// * `upstream_failure` - A failed stage this one waits for, directly or not
+    ///
+    /// # Returns
+    /// How the stage ended
+    #[tracing::instrument(name = "stage", skip_all, fields(stage = %stage.name))]
+    async fn run_stage(
+        &self,
+        definition: &PipelineDefinition,
+        stage: &StageDefinition,
+        skip_stages: &[String],
+        upstream_failure: Option<&str>,
+    ) -> StageOutcome {
+        let needs = stage.needs.as_deref().unwrap_or_default();
+
+        match (stage.when, upstream_failure) {
+            (StageWhen::OnSuccess, Some(failed)) => {
+                info!("Stage '{}' skipped (stage '{}' failed)", stage.name, failed);
+                self.context.log_warning(format!(
+                    "Stage '{}' skipped (stage '{}' failed)",
+                    stage.name, failed
+                ));
+                self.context.record_stage(
+                    &stage.name,
+                    StageStatus::Skipped,
+                    needs,
+                    Some(format!("Stage '{}' failed", failed)),
+                );
+                return StageOutcome::Done;
+            }
+            (StageWhen::OnFailure, None) => {
+                info!("Stage '{}' skipped (no failure to handle)", stage.name);
+                self.context.log_info(format!(
+                    "Stage '{}' skipped (runs only after a failure)",
+                    stage.name
+                ));
+                self.context
+                    .record_stage(&stage.name, StageStatus::Skipped, needs, None);
+                return StageOutcome::Done;
+            }
+            _ => {}
+        }
+
+        if skip_stages.contains(&stage.name) {
+            info!("Stage '{}' reused from the resumed job", stage.name);
+            self.context.log_info(format!(
+                "Stage '{}' skipped (succeeded in the resumed job)",
+                stage.name
+            ));
             self.context
-                .log_info(format!("Stage '{}' completed", stage.name));
+                .record_stage(&stage.name, StageStatus::Reused, needs, None);
+            return StageOutcome::Done;
         }
 
-        info!("Job {} completed successfully", job_id);
+        info!("Executing stage: {}", stage.name);
+        self.context
+            .log_info(format!("Starting stage: {}", stage.name));
         self.context
-            .log_info("Pipeline completed successfully".to_string());
+            .record_stage(&stage.name, StageStatus::Running, needs, None);
+
+        // Check condition if present
+        if let Some(ref condition) = stage.condition {
+            match self.evaluate_condition(condition, &stage.name).await {
+                Ok(true) => {
+                    debug!("Stage '{}' condition passed", stage.name);
+                }
+                Ok(false) => {
+                    info!("Stage '{}' skipped (condition returned false)", stage.name);
+                    self.context.log_info(format!(
+                        "Stage '{}' skipped (condition not met)",
+                        stage.name
+                    ));
+                    self.context
+                        .record_stage(&stage.name, StageStatus::Skipped, needs, None);
+                    return StageOutcome::Done;
+                }
+                Err(e) => {
+                    error!("Stage '{}' condition evaluation failed: {}", stage.name, e);
+                    self.context.log_error(format!(
+                        "Stage '{}' condition evaluation failed: {}",
+                        stage.name, e
+                    ));
+                    return self.fail_stage(
+                        stage,
+                        format!("Condition failed: {}", e),
+                        format!("Stage '{}' condition failed: {}", stage.name, e),
+                    );
+                }
+            }
+        }
+
+        // Switch to the stage's container for its hooks and script
+        let image = stage.container_for(&self.arch);
+        if let Some(image) = image
+            && let Err(e) = self.enter_container(image, &stage.name).await
+        {
+            error!("Stage '{}' container failed: {}", stage.name, e);
+            self.context
+                .log_error(format!("Stage '{}' failed: {}", stage.name, e));
+            return self.fail_stage(
+                stage,
+                e.to_string(),
+                format!("Stage '{}' failed: {}", stage.name, e),
+            );
+        }
 
-        JobResult::success()
+        // Execute stage script between the pipeline's hooks; after_each
+        // runs even when the stage failed
+        let mut stage_result = None;
+        let mut result = match &definition.before_each {
+            Some(hook) => self.run_hook(hook, "before_each", &stage.name).await,
+            None => Ok(()),
+        };
+        if result.is_ok() {
+            result = self
+                .execute_stage(&stage.script, &stage.name)
+                .await
+                .map(|returned| stage_result = returned);
+        }
+        if let Some(hook) = &definition.after_each {
+            let after = self.run_hook(hook, "after_each", &stage.name).await;
+            result = result.and(after);
+        }
+
+        // Switch back, even when the stage failed
+        if let Some(image) = image
+            && let Some(container_name) = self.context.container_manager.pop_container()
+        {
+            self.context.log_debug(format!(
+                "Container {} popped from stack for image {}",
+                container_name, image
+            ));
+        }
+
+        if let Err(e) = result {
+            error!("Stage '{}' failed: {}", stage.name, e);
+            self.context
+                .log_error(format!("Stage '{}' failed: {}", stage.name, e));
+            return self.fail_stage(
+                stage,
+                e.to_string(),
+                format!("Stage '{}' failed: {}", stage.name, e),
+            );
+        }
+
+        match stage_result.as_ref().and_then(|r| r.summary.as_deref()) {
+            Some(summary) => self
+                .context
+                .log_info(format!("Stage '{}' completed: {}", stage.name, summary)),
+            None => self
+                .context
+                .log_info(format!("Stage '{}' completed", stage.name)),
+        }
+        self.context
+            .record_stage_success(&stage.name, needs, stage_result);
+        StageOutcome::Done
+    }
+
+    /// Records a stage failure
+    ///
+    /// # Arguments
+    /// * `stage` - The failed stage
+    /// * `error_message` - Why the stage failed
+    /// * `job_error` - The job's failure message, unless the stage allows failure
+    fn fail_stage(
+        &self,
+        stage: &StageDefinition,
+        error_message: String,
+        job_error: String,
+    ) -> StageOutcome {
+        let needs = stage.needs.as_deref().unwrap_or_default();
+
+        if stage.allow_failure {
+            info!("Stage '{}' is allowed to fail, continuing", stage.name);
+            self.context.log_warning(format!(
+                "Stage '{}' is allowed to fail, continuing",
+                stage.name
+            ));
+            self.context.record_stage(
+                &stage.name,
+                StageStatus::AllowedFailure,
+                needs,
+                Some(error_message),
+            );
+            return StageOutcome::Done;
+        }
+
+        self.context
+            .record_stage(&stage.name, StageStatus::Failed, needs, Some(error_message));
+        StageOutcome::Failed(job_error)
+    }
+
+    /// Starts the container a stage declares and makes it current
+    ///
+    /// The image is pulled first unless the runner already has it, with the
+    /// pull's output and duration in the job log.
+    ///
+    /// # Arguments
+    /// * `image` - The stage's container image
+    /// * `stage_name` - The stage, for logs
+    async fn enter_container(&self, image: &str, stage_name: &str) -> Result<()> {
+        let manager = &self.context.container_manager;
+        let started = Instant::now();
+
+        let on_line = |stream: OutputStream, line: &str| {
+            self.context
+                .log_output(LogLevel::Info, stream, line.to_string());
+        };
+        self.context.log_info(format!(
+            "Stage '{}' runs in container {}",
+            stage_name, image
+        ));
+        if let Some(output) = manager
+            .pull(image, &on_line)
+            .await
+            .with_context(|| format!("Failed to pull image {}", image))?
+        {
+            if output.timed_out {
+                anyhow::bail!("Pull of image {} timed out", image);
+            }
+            if output.exit_code != 0 {
+                anyhow::bail!(
+                    "Pull of image {} failed with exit code {}",
+                    image,
+                    output.exit_code
+                );
+            }
+            self.context.log_info(format!(
+                "Pulled image {} in {:.1}s",
+                image,
+                started.elapsed().as_secs_f64()
+            ));
+        }
+
+        let container_name = manager
+            .push_container(image, ExecOptions::default())
+            .await
+            .with_context(|| format!("Failed to start container for image {}", image))?;
+        self.context.log_debug(format!(
+            "Container {} pushed to stack for image {}",
+            container_name, image
+        ));
+
+        Ok(())
     }
 
     /// Creates and configures a Lua execution sandbox
-    fn create_sandbox(&self) -> Result<mlua::Lua> {
+    ///
+    /// # Arguments
+    /// * `resumed_ctx` - Values the `ctx` table starts with
+    fn create_sandbox(
+        &self,
+        job: &JobMetadata,
+        resumed_ctx: Option<&serde_json::Value>,
+    ) -> Result<mlua::Lua> {
         let lua = create_sandbox().context("Failed to create base sandbox")?;
 
         // Register log module
@@ -134,6 +451,10 @@ impl LuaExecutor {
         register_input_module(&lua, self.context.inputs.clone())
             .context("Failed to register input module")?;
 
+        // Register env module with the pipeline's environment variables
+        register_env_module(&lua, self.context.env.clone())
+            .context("Failed to register env module")?;
+
         // Register process module
         register_process_module(&lua, Arc::clone(&self.context))
             .context("Failed to register process module")?;
@@ -142,32 +463,75 @@ impl LuaExecutor {
         register_container_module(&lua, Arc::clone(&self.context))
             .context("Failed to register container module")?;
 
+        // Register ctx module, shared by the job's stages
+        register_ctx_module(&lua, resumed_ctx).context("Failed to register ctx module")?;
+
+        // Register annotations module, labelling the job when it ends
+        register_annotations_module(&lua, Arc::clone(&self.context.annotations))
+            .context("Failed to register annotations module")?;
+
+        // Register job module, describing the job read-only
+        register_job_module(&lua, job).context("Failed to register job module")?;
+
         // TODO: Register output module
 
         Ok(lua)
     }
 
     /// Evaluates a stage condition function
-    fn evaluate_condition(&self, condition: &mlua::Function, stage_name: &str) -> Result<bool> {
+    ///
+    /// Called asynchronously, like stages, since conditions may run processes.
+    async fn evaluate_condition(
+        &self,
+        condition: &mlua::Function,
+        stage_name: &str,
+    ) -> Result<bool> {
         debug!("Evaluating condition for stage: {}", stage_name);
 
         let result: bool = condition
-            .call(())
+            .call_async(())
+            .await
             .map_err(|e| anyhow::anyhow!("Condition evaluation failed: {}", e))?;
 
         Ok(result)
     }
 
     /// Executes a single stage script function
-    fn execute_stage(&self, script: &mlua::Function, stage_name: &str) -> Result<()> {
+    ///
+    /// The stage runs as a coroutine so the async `process` and `container`
+    /// functions can suspend it instead of blocking the runtime.
+    ///
+    /// # Returns
+    /// The result table the script returned, if any
+    async fn execute_stage(
+        &self,
+        script: &mlua::Function,
+        stage_name: &str,
+    ) -> Result<Option<StageResult>> {
         debug!("Executing stage: {}", stage_name);
 
-        script
-            .call::<()>(())
+        let returned = script
+            .call_async::<mlua::Value>(())
+            .await
             .map_err(|e| anyhow::anyhow!("Stage execution failed: {}", e))?;
+        let result = parse_stage_result(returned)?;
 
         debug!("Stage '{}' completed successfully", stage_name);
-        Ok(())
+        Ok(result)
+    }
+
+    /// Calls a pipeline-level stage hook with the stage name
+    async fn run_hook(
+        &self,
+        hook: &mlua::Function,
+        hook_name: &str,
+        stage_name: &str,
+    ) -> Result<()> {
+        debug!("Running {} hook for stage: {}", hook_name, stage_name);
+
+        hook.call_async::<()>(stage_name.to_string())
+            .await
+            .map_err(|e| anyhow::anyhow!("{} hook failed: {}", hook_name, e))
     }
 
     /// Logs an error and returns a failed JobResult
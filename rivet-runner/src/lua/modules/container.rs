@@ -1,14 +1,24 @@
 //! Container module implementation for the runner
 //!
 //! Provides container context management for Lua scripts.
-//! Implements container.with(image, fn) which pushes a container onto the stack,
-//! executes the function, then pops the container.
+//! Implements container.with(image, [options,] fn) which pushes a container
+//! onto the stack, executes the function, then pops the container. The
+//! optional options table (`env`, `user`, `cwd`, `timeout`) sets defaults for
+//! every command run inside the block.
+//!
+//! container.build{...} and container.push{...} build images from the
+//! workspace and push them, logging in with credentials taken from the
+//! job's secrets.
 
 use mlua::prelude::*;
+use rivet_core::domain::log::{LogLevel, OutputStream};
+use std::collections::BTreeMap;
 use std::sync::Arc;
 use tracing::{debug, error};
 
 use crate::context::Context;
+use crate::lua::modules::process::parse_exec_options;
+use crate::podman::{BuildOptions, ExecOptions, ExecOutput, RegistryCredentials, registry_of};
 
 /// Register the container module into a Lua context
 ///
@@ -20,47 +30,148 @@ use crate::context::Context;
 pub fn register_container_module(lua: &Lua, context: Arc<Context>) -> LuaResult<()> {
     let container_table = lua.create_table()?;
 
-    // container.with(image, fn)
+    // container.with(image, [options,] fn)
     {
         let context = context.clone();
         container_table.set(
             "with",
-            lua.create_function(move |_lua_ctx, (image, func): (String, LuaFunction)| {
-                debug!("Entering container.with with image: {}", image);
+            lua.create_async_function(
+                move |_lua_ctx, (image, second, third): (String, LuaValue, Option<LuaFunction>)| {
+                    let context = context.clone();
+                    async move {
+                        debug!("Entering container.with with image: {}", image);
+
+                        let (defaults, func) = match (second, third) {
+                            (LuaValue::Function(func), None) => (ExecOptions::default(), func),
+                            (LuaValue::Table(options), Some(func)) => {
+                                (parse_exec_options(&options)?, func)
+                            }
+                            _ => {
+                                return Err(LuaError::RuntimeError(
+                                    "container.with expects (image, fn) or (image, options, fn)"
+                                        .to_string(),
+                                ));
+                            }
+                        };
+
+                        // Push container onto stack
+                        let container_name = context
+                            .container_manager
+                            .push_container(&image, defaults)
+                            .await
+                            .map_err(|e| {
+                                error!("Failed to push container for image {}: {}", image, e);
+                                context.log_error(format!(
+                                    "Failed to start container for image {}: {}",
+                                    image, e
+                                ));
+                                LuaError::RuntimeError(format!("Failed to start container: {}", e))
+                            })?;
+
+                        context.log_debug(format!(
+                            "Container {} pushed to stack for image {}",
+                            container_name, image
+                        ));
+
+                        // Execute the function
+                        let result = func.call_async::<()>(()).await;
+
+                        // Always pop the container, even if function failed
+                        context.container_manager.pop_container();
+                        context.log_debug(format!(
+                            "Container {} popped from stack for image {}",
+                            container_name, image
+                        ));
+
+                        // Propagate any error from the function
+                        result?;
+
+                        Ok(())
+                    }
+                },
+            )?,
+        )?;
+    }
 
-                // Push container onto stack
-                let container_name =
-                    context
+    // container.build{ context, dockerfile, tag, build_args, timeout }
+    {
+        let context = context.clone();
+        container_table.set(
+            "build",
+            lua.create_async_function(move |_lua_ctx, options: LuaTable| {
+                let context = context.clone();
+                async move {
+                    let tag: String = options.get("tag").map_err(|_| {
+                        LuaError::RuntimeError("container.build requires 'tag' field".to_string())
+                    })?;
+                    let build_options = BuildOptions {
+                        context: options
+                            .get::<Option<String>>("context")?
+                            .unwrap_or_else(|| ".".to_string()),
+                        dockerfile: options
+                            .get::<Option<String>>("dockerfile")?
+                            .unwrap_or_else(|| "Dockerfile".to_string()),
+                        build_args: options
+                            .get::<Option<BTreeMap<String, String>>>("build_args")?
+                            .unwrap_or_default(),
+                        timeout: parse_exec_options(&options)?.timeout,
+                        tag,
+                    };
+
+                    context.log_info(format!("Building image {}", build_options.tag));
+
+                    let on_line = |stream: OutputStream, line: &str| {
+                        context.log_output(LogLevel::Info, stream, line.to_string());
+                    };
+                    let output = context
                         .container_manager
-                        .push_container(&image)
+                        .build(&build_options, &on_line)
+                        .await
                         .map_err(|e| {
-                            error!("Failed to push container for image {}: {}", image, e);
-                            context.log_error(format!(
-                                "Failed to start container for image {}: {}",
-                                image, e
-                            ));
-                            LuaError::RuntimeError(format!("Failed to start container: {}", e))
+                            LuaError::RuntimeError(format!("Failed to build image: {}", e))
                         })?;
 
-                context.log_debug(format!(
-                    "Container {} pushed to stack for image {}",
-                    container_name, image
-                ));
+                    check_output(&output, &format!("Build of image {}", build_options.tag))?;
+                    context.log_info(format!("Built image {}", build_options.tag));
+
+                    Ok(build_options.tag)
+                }
+            })?,
+        )?;
+    }
+
+    // container.push{ tag, registry, username, username_secret, password_secret, timeout }
+    {
+        let context = context.clone();
+        container_table.set(
+            "push",
+            lua.create_async_function(move |_lua_ctx, options: LuaTable| {
+                let context = context.clone();
+                async move {
+                    let tag: String = options.get("tag").map_err(|_| {
+                        LuaError::RuntimeError("container.push requires 'tag' field".to_string())
+                    })?;
+                    let timeout = parse_exec_options(&options)?.timeout;
+                    let credentials = parse_credentials(&context, &options, &tag)?;
 
-                // Execute the function
-                let result = func.call::<()>(());
+                    context.log_info(format!("Pushing image {}", tag));
 
-                // Always pop the container, even if function failed
-                context.container_manager.pop_container();
-                context.log_debug(format!(
-                    "Container {} popped from stack for image {}",
-                    container_name, image
-                ));
+                    let on_line = |stream: OutputStream, line: &str| {
+                        context.log_output(LogLevel::Info, stream, line.to_string());
+                    };
+                    let output = context
+                        .container_manager
+                        .push(&tag, credentials.as_ref(), timeout, &on_line)
+                        .await
+                        .map_err(|e| {
+                            LuaError::RuntimeError(format!("Failed to push image: {}", e))
+                        })?;
 
-                // Propagate any error from the function
-                result?;
+                    check_output(&output, &format!("Push of image {}", tag))?;
+                    context.log_info(format!("Pushed image {}", tag));
 
-                Ok(())
+                    Ok(())
+                }
             })?,
         )?;
     }
@@ -68,3 +179,58 @@ pub fn register_container_module(lua: &Lua, context: Arc<Context>) -> LuaResult<
     lua.globals().set("container", container_table)?;
     Ok(())
 }
+
+/// Reads the registry credentials of a `container.push` options table
+///
+/// The password always comes from a secret; the username may be given
+/// directly or as a secret. Without a password the push is anonymous (or
+/// uses credentials from an earlier push of the job).
+fn parse_credentials(
+    context: &Context,
+    options: &LuaTable,
+    tag: &str,
+) -> LuaResult<Option<RegistryCredentials>> {
+    let Some(password_secret) = options.get::<Option<String>>("password_secret")? else {
+        return Ok(None);
+    };
+
+    let secret = |name: &str| {
+        context.secret(name).map(str::to_string).ok_or_else(|| {
+            LuaError::RuntimeError(format!("Secret '{}' is not available to this job", name))
+        })
+    };
+
+    let username = match options.get::<Option<String>>("username_secret")? {
+        Some(name) => secret(&name)?,
+        None => options.get::<Option<String>>("username")?.ok_or_else(|| {
+            LuaError::RuntimeError(
+                "container.push requires 'username' or 'username_secret' with 'password_secret'"
+                    .to_string(),
+            )
+        })?,
+    };
+
+    let registry = options
+        .get::<Option<String>>("registry")?
+        .unwrap_or_else(|| registry_of(tag).to_string());
+
+    Ok(Some(RegistryCredentials {
+        registry,
+        username,
+        password: secret(&password_secret)?,
+    }))
+}
+
+/// Turns a failed or timed out build/push into a Lua error
+fn check_output(output: &ExecOutput, what: &str) -> LuaResult<()> {
+    if output.timed_out {
+        return Err(LuaError::RuntimeError(format!("{} timed out", what)));
+    }
+    if output.exit_code != 0 {
+        return Err(LuaError::RuntimeError(format!(
+            "{} failed with exit code {}",
+            what, output.exit_code
+        )));
+    }
+    Ok(())
+}
@@ -2,91 +2,144 @@
 //!
 //! Provides logging functionality to Lua scripts with buffered output
 //! that is sent to the orchestrator.
+//!
+//! Besides plain messages, scripts may format them printf-style and attach
+//! structured fields, stored as JSON with the entry:
+//!
+//! ```lua
+//! log.infof("built %s in %ds", tag, secs)
+//! local build = log.with({ stage = "build", attempt = 2 })
+//! build:info("cache hit")
+//! ```
 
 use mlua::prelude::*;
 use rivet_core::domain::log::{LogEntry, LogLevel};
+use serde_json::Value as JsonValue;
+use std::collections::BTreeMap;
 use std::sync::Arc;
 
 use crate::context::Context;
 
+/// Structured fields attached to the entries of a logger
+type Fields = BTreeMap<String, JsonValue>;
+
+/// Log functions, by the name of their level
+const LEVELS: [(&str, LogLevel); 4] = [
+    ("debug", LogLevel::Debug),
+    ("info", LogLevel::Info),
+    ("warning", LogLevel::Warning),
+    ("error", LogLevel::Error),
+];
+
 /// Register the log module into a Lua context
 ///
-/// Creates a `log` global table with functions: debug, info, warning, error
+/// Creates a `log` global table with functions: debug, info, warning, error,
+/// their printf-style variants debugf, infof, warningf, errorf, and with,
+/// returning a logger that attaches fields to its entries
 ///
 /// # Arguments
 /// * `lua` - The Lua context to register into
 /// * `context` - The execution context to write logs to
 pub fn register_log_module(lua: &Lua, context: Arc<Context>) -> LuaResult<()> {
-    let log_table = lua.create_table()?;
+    let string: LuaTable = lua.globals().get("string")?;
+    let format: LuaFunction = string.get("format")?;
 
-    // log.debug(msg)
-    {
-        let context = context.clone();
-        log_table.set(
-            "debug",
-            lua.create_function(move |_, msg: String| {
-                let entry = LogEntry {
-                    timestamp: chrono::Utc::now(),
-                    level: LogLevel::Debug,
-                    message: msg,
-                };
-                context.add_log(entry);
-                Ok(())
-            })?,
-        )?;
-    }
+    let log_table = create_logger(lua, context, format, None)?;
+    lua.globals().set("log", log_table)?;
+    Ok(())
+}
 
-    // log.info(msg)
-    {
-        let context = context.clone();
-        log_table.set(
-            "info",
-            lua.create_function(move |_, msg: String| {
-                let entry = LogEntry {
-                    timestamp: chrono::Utc::now(),
-                    level: LogLevel::Info,
-                    message: msg,
-                };
-                context.add_log(entry);
-                Ok(())
-            })?,
-        )?;
+/// A table of log functions whose entries carry `fields`
+///
+/// The functions may be called with `.` or as methods with `:`: a leading
+/// table argument is the logger itself and is ignored.
+///
+/// # Arguments
+/// * `lua` - The Lua context
+/// * `context` - The execution context to write logs to
+/// * `format` - Lua's `string.format`, as it was when the module was registered
+/// * `fields` - Fields attached to every entry, `None` for the `log` table
+fn create_logger(
+    lua: &Lua,
+    context: Arc<Context>,
+    format: LuaFunction,
+    fields: Option<Arc<Fields>>,
+) -> LuaResult<LuaTable> {
+    let logger = lua.create_table()?;
+
+    for (name, level) in LEVELS {
+        // log.info(msg)
+        {
+            let context = context.clone();
+            let fields = fields.clone();
+            logger.set(
+                name,
+                lua.create_function(move |lua, args: LuaMultiValue| {
+                    let message = match arguments(args).into_iter().next() {
+                        Some(message) => String::from_lua(message, lua)?,
+                        None => String::new(),
+                    };
+                    add_log(&context, level, message, fields.as_deref());
+                    Ok(())
+                })?,
+            )?;
+        }
+
+        // log.infof(format, ...)
+        {
+            let context = context.clone();
+            let format = format.clone();
+            let fields = fields.clone();
+            logger.set(
+                format!("{}f", name),
+                lua.create_function(move |_, args: LuaMultiValue| {
+                    let message: String = format.call(LuaMultiValue::from_iter(arguments(args)))?;
+                    add_log(&context, level, message, fields.as_deref());
+                    Ok(())
+                })?,
+            )?;
+        }
     }
 
-    // log.warning(msg)
+    // log.with(fields): a logger adding `fields` to those of this one
     {
         let context = context.clone();
-        log_table.set(
-            "warning",
-            lua.create_function(move |_, msg: String| {
-                let entry = LogEntry {
-                    timestamp: chrono::Utc::now(),
-                    level: LogLevel::Warning,
-                    message: msg,
-                };
-                context.add_log(entry);
-                Ok(())
+        logger.set(
+            "with",
+            lua.create_function(move |lua, args: LuaMultiValue| {
+                let mut merged = fields.as_deref().cloned().unwrap_or_default();
+                if let Some(extra) = args.into_iter().last() {
+                    let extra: Fields = lua.from_value(extra).map_err(|e| {
+                        LuaError::RuntimeError(format!("log.with expects a table of fields: {}", e))
+                    })?;
+                    merged.extend(extra);
+                }
+
+                create_logger(lua, context.clone(), format.clone(), Some(Arc::new(merged)))
             })?,
         )?;
     }
 
-    // log.error(msg)
-    {
-        let context = context.clone();
-        log_table.set(
-            "error",
-            lua.create_function(move |_, msg: String| {
-                let entry = LogEntry {
-                    timestamp: chrono::Utc::now(),
-                    level: LogLevel::Error,
-                    message: msg,
-                };
-                context.add_log(entry);
-                Ok(())
-            })?,
-        )?;
+    Ok(logger)
+}
+
+/// Arguments of a log function, without the logger a method call passes first
+fn arguments(args: LuaMultiValue) -> Vec<LuaValue> {
+    let mut args: Vec<LuaValue> = args.into_iter().collect();
+    if matches!(args.first(), Some(LuaValue::Table(_))) {
+        args.remove(0);
     }
+    args
+}
 
-    lua.globals().set("log", log_table)?;
-    Ok(())
+fn add_log(context: &Context, level: LogLevel, message: String, fields: Option<&Fields>) {
+    context.add_log(LogEntry {
+        timestamp: chrono::Utc::now(),
+        level,
+        message,
+        stage: None,
+        stream: None,
+        sequence: None,
+        fields: fields.filter(|fields| !fields.is_empty()).cloned(),
+    });
 }
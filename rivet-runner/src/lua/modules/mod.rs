@@ -8,13 +8,20 @@
 //! - Container runtime (podman/kubectl)
 //! - Orchestrator connection (for logging)
 //! - Job parameters and state
+//!
+//! The input, annotations, ctx, env and job modules have no such needs and live in rivet-lua, where
+//! the orchestrator and pipeline unit tests use them too; they are
+//! re-exported here.
 
 pub mod container;
-pub mod input;
 pub mod log;
 pub mod process;
 
 pub use container::register_container_module;
-pub use input::register_input_module;
 pub use log::register_log_module;
 pub use process::register_process_module;
+pub use rivet_lua::annotations::{Annotations, register_annotations_module};
+pub use rivet_lua::ctx::{ctx_to_json, register_ctx_module};
+pub use rivet_lua::env::register_env_module;
+pub use rivet_lua::input::register_input_module;
+pub use rivet_lua::job::{JobMetadata, register_job_module};
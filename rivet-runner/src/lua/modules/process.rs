@@ -1,13 +1,23 @@
 //! Process module implementation for the runner
 //!
 //! Provides process execution functionality to Lua scripts.
-//! Commands are executed inside the container managed by the context.
+//! Commands are executed inside the container managed by the context; output
+//! that is not captured is streamed into the job log line by line. With the
+//! `shell` option, the command line runs in `sh`, `bash`, `cmd` or
+//! PowerShell instead, so scripts work in Windows containers too. With
+//! `check`, a command that fails or times out raises an error instead of
+//! leaving its exit code to be inspected.
 
 use mlua::prelude::*;
+use rivet_core::domain::log::{LogLevel, OutputStream};
+use rivet_lua::process::check_exit;
+use std::collections::BTreeMap;
 use std::sync::Arc;
+use std::time::Duration;
 use tracing::{debug, warn};
 
 use crate::context::Context;
+use crate::podman::{ContainerOs, ExecOptions, MAX_CAPTURED_OUTPUT, container_os};
 
 /// Register the process module into a Lua context
 ///
@@ -24,70 +34,122 @@ pub fn register_process_module(lua: &Lua, context: Arc<Context>) -> LuaResult<()
         let context = context.clone();
         process_table.set(
             "run",
-            lua.create_function(move |lua_ctx, options: LuaTable| {
-                // Parse options
-                let cmd: String = options.get("cmd").map_err(|_| {
-                    LuaError::RuntimeError("process.run requires 'cmd' field".to_string())
-                })?;
-
-                let args: Vec<String> = options
-                    .get::<Option<LuaTable>>("args")
-                    .ok()
-                    .flatten()
-                    .map(|tbl| {
-                        let mut args = Vec::new();
-                        for pair in tbl.pairs::<i32, String>() {
-                            if let Ok((_, arg)) = pair {
+            lua.create_async_function(move |lua_ctx, options: LuaTable| {
+                let context = context.clone();
+                async move {
+                    // Parse options
+                    let cmd: String = options.get("cmd").map_err(|_| {
+                        LuaError::RuntimeError("process.run requires 'cmd' field".to_string())
+                    })?;
+
+                    let args: Vec<String> = options
+                        .get::<Option<LuaTable>>("args")
+                        .ok()
+                        .flatten()
+                        .map(|tbl| {
+                            let mut args = Vec::new();
+                            for (_, arg) in tbl.pairs::<i32, String>().flatten() {
                                 args.push(arg);
                             }
+                            args
+                        })
+                        .unwrap_or_default();
+
+                    let shell = match options.get::<LuaValue>("shell")? {
+                        LuaValue::Nil | LuaValue::Boolean(false) => None,
+                        LuaValue::Boolean(true) => Some(default_shell(container_os()).to_string()),
+                        LuaValue::String(shell) => Some(shell.to_str()?.to_string()),
+                        _ => {
+                            return Err(LuaError::RuntimeError(
+                                "'shell' must be a boolean or a shell name".to_string(),
+                            ));
                         }
-                        args
-                    })
-                    .unwrap_or_default();
-
-                let capture_stdout: bool = options.get("capture_stdout").unwrap_or(false);
-                let capture_stderr: bool = options.get("capture_stderr").unwrap_or(false);
-                let stdout_level: String = options
-                    .get("stdout_level")
-                    .unwrap_or_else(|_| "info".to_string());
-                let stderr_level: String = options
-                    .get("stderr_level")
-                    .unwrap_or_else(|_| "error".to_string());
-                let cwd: Option<String> = options.get("cwd").ok();
-
-                debug!("Executing process: {} {:?}", cmd, args);
-
-                // Execute command in container
-                let (stdout, stderr, exit_code) = context
-                    .container_manager
-                    .exec(&cmd, &args, cwd.as_deref())
-                    .map_err(|e| {
-                        LuaError::RuntimeError(format!("Failed to execute command: {}", e))
-                    })?;
-
-                // Log stdout if not captured
-                if !capture_stdout && !stdout.is_empty() {
-                    log_output(&context, &stdout, &stdout_level);
-                }
-
-                // Log stderr if not captured
-                if !capture_stderr && !stderr.is_empty() {
-                    log_output(&context, &stderr, &stderr_level);
-                }
-
-                // Create result table
-                let result = lua_ctx.create_table()?;
-                result.set("exit_code", exit_code)?;
-
-                if capture_stdout {
-                    result.set("stdout", stdout)?;
-                }
-
-                if capture_stderr {
-                    result.set("stderr", stderr)?;
+                    };
+                    let (program, args) = match shell {
+                        Some(_) if !args.is_empty() => {
+                            return Err(LuaError::RuntimeError(
+                                "'args' cannot be used with 'shell', put them in 'cmd'".to_string(),
+                            ));
+                        }
+                        Some(shell) => shell_command(&shell, &cmd).ok_or_else(|| {
+                            LuaError::RuntimeError(format!(
+                                "Unknown shell '{}' (sh, bash, cmd, powershell or pwsh)",
+                                shell
+                            ))
+                        })?,
+                        None => (cmd.clone(), args),
+                    };
+
+                    let capture_stdout: bool = options.get("capture_stdout").unwrap_or(false);
+                    let capture_stderr: bool = options.get("capture_stderr").unwrap_or(false);
+                    let stdout_level = parse_level(
+                        &options
+                            .get::<String>("stdout_level")
+                            .unwrap_or_else(|_| "info".to_string()),
+                    );
+                    let stderr_level = parse_level(
+                        &options
+                            .get::<String>("stderr_level")
+                            .unwrap_or_else(|_| "error".to_string()),
+                    );
+                    let check: bool = options.get::<Option<bool>>("check")?.unwrap_or(false);
+                    let exec_options = parse_exec_options(&options)?;
+
+                    debug!("Executing process: {} {:?}", cmd, args);
+
+                    // Log output that is not captured as it is produced
+                    let on_line = |stream: OutputStream, line: &str| {
+                        let (captured, level) = match stream {
+                            OutputStream::Stdout => (capture_stdout, stdout_level),
+                            OutputStream::Stderr => (capture_stderr, stderr_level),
+                        };
+                        if !captured && !line.trim().is_empty() {
+                            context.log_output(level, stream, line.to_string());
+                        }
+                    };
+
+                    // Execute command in container
+                    let output = context
+                        .container_manager
+                        .exec(&program, &args, &exec_options, &on_line)
+                        .await
+                        .map_err(|e| {
+                            LuaError::RuntimeError(format!("Failed to execute command: {}", e))
+                        })?;
+
+                    if output.timed_out {
+                        context.log_error(format!("Command '{}' timed out and was killed", cmd));
+                    }
+                    if output.truncated && (capture_stdout || capture_stderr) {
+                        context.log_warning(format!(
+                            "Captured output of '{}' was truncated to {} bytes",
+                            cmd, MAX_CAPTURED_OUTPUT
+                        ));
+                    }
+                    if check {
+                        check_exit(
+                            &cmd,
+                            output.exit_code.into(),
+                            output.timed_out,
+                            &output.stderr,
+                        )?;
+                    }
+
+                    // Create result table
+                    let result = lua_ctx.create_table()?;
+                    result.set("exit_code", output.exit_code)?;
+                    result.set("timed_out", output.timed_out)?;
+
+                    if capture_stdout {
+                        result.set("stdout", output.stdout)?;
+                    }
+
+                    if capture_stderr {
+                        result.set("stderr", output.stderr)?;
+                    }
+
+                    Ok(result)
                 }
-
-                Ok(result)
             })?,
         )?;
     }
@@ -96,21 +158,79 @@ pub fn register_process_module(lua: &Lua, context: Arc<Context>) -> LuaResult<()
     Ok(())
 }
 
-/// Logs output with the specified level
-fn log_output(context: &Context, output: &str, level: &str) {
-    let trimmed = output.trim();
-    if trimmed.is_empty() {
-        return;
+/// Shell of `shell = true`: `sh` in Linux containers, `cmd` in Windows ones
+fn default_shell(os: ContainerOs) -> &'static str {
+    match os {
+        ContainerOs::Linux => "sh",
+        ContainerOs::Windows => "cmd",
     }
+}
+
+/// Program and arguments running a command line in a shell
+///
+/// # Arguments
+/// * `shell` - `sh`, `bash`, `cmd`, `powershell` or `pwsh`
+/// * `command_line` - The command line, as the shell parses it
+///
+/// # Returns
+/// The program and its arguments, or `None` for unknown shells
+fn shell_command(shell: &str, command_line: &str) -> Option<(String, Vec<String>)> {
+    let (program, flags): (&str, &[&str]) = match shell {
+        "sh" | "bash" => (shell, &["-c"]),
+        "cmd" => ("cmd.exe", &["/S", "/C"]),
+        "powershell" | "pwsh" => (shell, &["-NoProfile", "-NonInteractive", "-Command"]),
+        _ => return None,
+    };
+
+    let mut args: Vec<String> = flags.iter().map(|flag| flag.to_string()).collect();
+    args.push(command_line.to_string());
+    Some((program.to_string(), args))
+}
+
+/// Parses a `stdout_level`/`stderr_level` option, defaulting to info
+fn parse_level(level: &str) -> LogLevel {
+    level.parse().unwrap_or_else(|e| {
+        warn!("{}, defaulting to info", e);
+        LogLevel::Info
+    })
+}
 
-    match level.to_lowercase().as_str() {
-        "debug" => context.log_debug(trimmed.to_string()),
-        "info" => context.log_info(trimmed.to_string()),
-        "warning" | "warn" => context.log_warning(trimmed.to_string()),
-        "error" => context.log_error(trimmed.to_string()),
-        _ => {
-            warn!("Unknown log level '{}', defaulting to info", level);
-            context.log_info(trimmed.to_string());
+/// Parses the `env`, `user`, `cwd` and `timeout` fields of an options table
+///
+/// Shared by `process.run` and `container.with`. Missing fields are left
+/// unset so they fall back to the enclosing container's defaults.
+///
+/// # Arguments
+/// * `options` - The Lua options table
+///
+/// # Returns
+/// The parsed exec options, or an error for malformed fields
+pub(crate) fn parse_exec_options(options: &LuaTable) -> LuaResult<ExecOptions> {
+    let env = match options.get::<Option<LuaTable>>("env")? {
+        Some(tbl) => tbl
+            .pairs::<String, String>()
+            .collect::<LuaResult<BTreeMap<_, _>>>()
+            .map_err(|_| {
+                LuaError::RuntimeError("'env' must map variable names to strings".to_string())
+            })?,
+        None => BTreeMap::new(),
+    };
+
+    let timeout = match options.get::<Option<f64>>("timeout")? {
+        Some(secs) if secs > 0.0 && secs.is_finite() => Some(Duration::from_secs_f64(secs)),
+        Some(secs) => {
+            return Err(LuaError::RuntimeError(format!(
+                "'timeout' must be a positive number of seconds, got {}",
+                secs
+            )));
         }
-    }
+        None => None,
+    };
+
+    Ok(ExecOptions {
+        env,
+        user: options.get("user")?,
+        cwd: options.get("cwd")?,
+        timeout,
+    })
 }
@@ -3,32 +3,55 @@
 //! A stateless worker that executes pipeline jobs in sandboxed Lua environments.
 //!
 //! Architecture:
-//! - Configuration: Load settings from environment or defaults
+//! - Configuration: Load settings from a file and the environment, reloading
+//!   the file as it changes
 //! - Repositories: HTTP communication with orchestrator (jobs, logs, runners)
 //! - Services: Business logic (execution, capabilities, log buffering)
 //! - Scheduler: Job polling and lifecycle management
 //!
 //! The runner polls the orchestrator for scheduled jobs, executes them in
-//! secure Lua sandboxes, and streams logs back periodically.
+//! secure Lua sandboxes, and streams logs back periodically through an
+//! on-disk spool.
 
 mod config;
 mod context;
+mod debug;
+#[cfg(feature = "grpc")]
+mod grpc;
 mod lua;
 mod podman;
+mod pool;
+mod reload;
 mod scheduler;
+mod spool;
+mod state;
+mod workspace;
 
 use anyhow::Result;
+use std::path::PathBuf;
 use std::sync::Arc;
 use std::time::Duration;
-use tracing::{error, info, warn};
+use tokio::sync::watch;
+#[cfg(not(feature = "grpc"))]
+use tracing::warn;
+use tracing::{error, info};
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 
-use crate::config::Config;
+use crate::config::{CONFIG_PATH_ENV, Config, TlsMaterial};
+#[cfg(feature = "grpc")]
+use crate::grpc::GrpcOrchestrator;
 use crate::scheduler::JobPoller;
-use rivet_client::OrchestratorClient;
+use rivet_client::{OrchestratorApi, OrchestratorClient, RetryPolicy};
+#[cfg(feature = "grpc")]
+use rivet_core::domain::runner::RunnerInfo;
+use rivet_core::dto::runner::{RUNNER_CREDENTIAL_HEADER, RUNNER_PROTOCOL_VERSION, RegisterRunner};
+use rivet_core::telemetry::Telemetry;
 
 #[tokio::main]
 async fn main() -> Result<()> {
+    // Span export, when OTEL_EXPORTER_OTLP_ENDPOINT is set
+    let telemetry = Telemetry::from_env("rivet-runner").map_err(anyhow::Error::msg)?;
+
     // Initialize logging
     tracing_subscriber::registry()
         .with(
@@ -36,44 +59,70 @@ async fn main() -> Result<()> {
                 .unwrap_or_else(|_| "rivet_runner=info,tower_http=debug".into()),
         )
         .with(tracing_subscriber::fmt::layer())
+        .with(telemetry.as_ref().map(Telemetry::layer))
         .init();
 
     info!("Starting Rivet Runner");
 
-    // Check podman availability
-    info!("Checking podman availability...");
-    podman::check_podman_available()?;
-    info!("Podman check passed");
-
     // Load configuration
-    let config = load_config()?;
+    let config_path = config_path()?;
+    let config = load_config(config_path.as_deref())?;
     info!(
         "Loaded configuration: runner_id={}, orchestrator_url={}",
         config.runner_id, config.orchestrator_url
     );
 
+    // Check container runtime availability
+    podman::init(config.container_runtime, config.container_os);
+    info!(
+        "Checking {} availability...",
+        config.container_runtime.program()
+    );
+    podman::check_runtime_available()?;
+    info!("Container runtime check passed");
+
     // Initialize orchestrator client
-    let client = Arc::new(OrchestratorClient::new(config.orchestrator_url.clone()));
+    let tls = config.load_tls()?;
+    let client = build_client(&config, &tls, None)?;
 
     info!("Orchestrator client initialized");
 
     // Register runner
     info!("Registering runner with orchestrator");
-    register_with_retry(&client, &config.runner_id).await?;
+    let credential = register_with_retry(&client, &config).await?;
     info!("Runner registered successfully");
 
+    // Job calls identify the runner with the credential issued on registration
+    let client = build_client(&config, &tls, credential.as_deref())?;
+
+    // Serve debug sessions of failed jobs kept on this runner
+    tokio::spawn(debug::DebugListener::new(&config, client.clone()).run());
+
+    // Follow changes to the configuration file
+    let (config_tx, config_rx) = watch::channel(config.clone());
+    if let Some(path) = config_path {
+        info!("Watching {} for configuration changes", path.display());
+        tokio::spawn(reload::watch_config(path, config_tx));
+    }
+
     // Create job poller
-    let poller = JobPoller::new(config.clone(), client);
+    let api = build_api(&config, client, credential.as_deref(), tls)?;
+    let poller = JobPoller::new(config_rx, api);
 
     info!("Runner initialized successfully");
     info!(
-        "Poll interval: {:?}, Log send interval: {:?}",
-        config.poll_interval, config.log_send_interval
+        "Poll interval: {:?}, Long poll wait: {:?}, Log send interval: {:?}",
+        config.poll_interval, config.long_poll_wait, config.log_send_interval
     );
 
     // Start polling loop
     info!("Starting job polling loop");
-    if let Err(e) = poller.run().await {
+    let polled = poller.run().await;
+
+    if let Some(telemetry) = &telemetry {
+        telemetry.shutdown();
+    }
+    if let Err(e) = polled {
         error!("Poller error: {}", e);
         return Err(e);
     }
@@ -81,70 +130,161 @@ async fn main() -> Result<()> {
     Ok(())
 }
 
-/// Loads configuration from environment variables with fallback to defaults
-fn load_config() -> Result<Config> {
-    match Config::from_env() {
-        Ok(config) => {
-            config.validate()?;
-            Ok(config)
+/// Configuration file given with `--config <path>` or `RUNNER_CONFIG`
+fn config_path() -> Result<Option<PathBuf>> {
+    let mut args = std::env::args().skip(1);
+    let mut path = None;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--config" => {
+                let value = args
+                    .next()
+                    .ok_or_else(|| anyhow::anyhow!("--config requires a path"))?;
+                path = Some(PathBuf::from(value));
+            }
+            _ => match arg.strip_prefix("--config=") {
+                Some(value) => path = Some(PathBuf::from(value)),
+                None => anyhow::bail!(
+                    "Unknown argument '{}' (usage: rivet-runner [--config <path>])",
+                    arg
+                ),
+            },
+        }
+    }
+
+    Ok(path.or_else(|| {
+        std::env::var(CONFIG_PATH_ENV)
+            .ok()
+            .filter(|path| !path.is_empty())
+            .map(PathBuf::from)
+    }))
+}
+
+/// Loads the configuration file, if any, overridden by environment variables
+fn load_config(path: Option<&std::path::Path>) -> Result<Config> {
+    let config = Config::load(path)?;
+    config.validate()?;
+    if let Some(path) = path {
+        info!("Configuration loaded from {}", path.display());
+    }
+    Ok(config)
+}
+
+/// Builds the orchestrator client with the configured credentials
+fn build_client(
+    config: &Config,
+    tls: &TlsMaterial,
+    runner_credential: Option<&str>,
+) -> Result<OrchestratorClient> {
+    let mut builder = OrchestratorClient::builder().base_url(&config.orchestrator_url);
+    if let Some(token) = &config.orchestrator_token {
+        builder = builder.token(token);
+    }
+    if let Some(credential) = runner_credential {
+        builder = builder.runner_credential(credential);
+    }
+    for (name, value) in &config.orchestrator_headers {
+        builder = builder.header(name, value);
+    }
+    if let Some((cert, key)) = &tls.identity {
+        builder = builder.client_identity_pem(cert.clone(), key.clone());
+        if let Some(cert_path) = &config.tls_cert_path {
+            info!("Using client certificate {} for mTLS", cert_path.display());
         }
-        Err(_) => {
-            info!("Failed to load config from environment, using defaults");
-            let config = Config::default();
-            config.validate()?;
-            Ok(config)
+    }
+    if let Some(ca) = &tls.ca_certificate {
+        builder = builder.ca_certificate_pem(ca.clone());
+    }
+
+    Ok(builder.build()?)
+}
+
+/// Selects the transport used for the job lifecycle
+///
+/// gRPC is used when `ORCHESTRATOR_GRPC_URL` is set, HTTP otherwise.
+fn build_api(
+    config: &Config,
+    http: OrchestratorClient,
+    runner_credential: Option<&str>,
+    tls: TlsMaterial,
+) -> Result<Arc<dyn OrchestratorApi>> {
+    #[cfg(feature = "grpc")]
+    if let Some(grpc_url) = &config.orchestrator_grpc_url {
+        info!("Using gRPC transport at {}", grpc_url);
+        let mut headers = config.orchestrator_headers.clone();
+        if let Some(credential) = runner_credential {
+            headers.push((RUNNER_CREDENTIAL_HEADER.to_string(), credential.to_string()));
         }
+        let grpc = GrpcOrchestrator::connect(
+            grpc_url,
+            http,
+            config.orchestrator_token.as_deref(),
+            &headers,
+            tls,
+        )?;
+        return Ok(Arc::new(grpc));
     }
+
+    #[cfg(not(feature = "grpc"))]
+    if config.orchestrator_grpc_url.is_some() {
+        warn!("ORCHESTRATOR_GRPC_URL is set but the runner was built without gRPC support");
+    }
+    let _ = (runner_credential, tls);
+
+    Ok(Arc::new(http))
 }
 
 /// Register with orchestrator with retry logic and exponential backoff
 ///
 /// This handles the case where the orchestrator may not be ready yet when
-/// the runner starts (common in container environments).
-async fn register_with_retry(client: &Arc<OrchestratorClient>, runner_id: &str) -> Result<()> {
-    const MAX_RETRIES: u32 = 10;
-    const INITIAL_DELAY_MS: u64 = 500;
-    const MAX_DELAY_MS: u64 = 30_000;
-
-    let mut attempt = 0;
-    let mut delay_ms = INITIAL_DELAY_MS;
-
-    loop {
-        attempt += 1;
-
-        match client.register_runner(runner_id).await {
-            Ok(_) => {
-                if attempt > 1 {
-                    info!(
-                        "Successfully registered with orchestrator after {} attempt(s)",
-                        attempt
-                    );
-                }
-                return Ok(());
-            }
-            Err(e) => {
-                if attempt >= MAX_RETRIES {
-                    error!(
-                        "Failed to register with orchestrator after {} attempts",
-                        MAX_RETRIES
-                    );
-                    return Err(anyhow::anyhow!(
-                        "Failed to register capabilities with orchestrator: {}",
-                        e
-                    ));
-                }
-
-                warn!(
-                    "Failed to register with orchestrator (attempt {}/{}): {}",
-                    attempt, MAX_RETRIES, e
-                );
-                warn!("Retrying in {} ms...", delay_ms);
-
-                tokio::time::sleep(Duration::from_millis(delay_ms)).await;
-
-                // Exponential backoff with cap
-                delay_ms = (delay_ms * 2).min(MAX_DELAY_MS);
-            }
-        }
+/// the runner starts (common in container environments), so it uses a much
+/// more patient retry policy than regular calls.
+///
+/// # Returns
+/// The credential issued by the orchestrator, `None` for orchestrators that
+/// predate runner credentials
+async fn register_with_retry(
+    client: &OrchestratorClient,
+    config: &Config,
+) -> Result<Option<String>> {
+    let startup_client = client.clone().with_retry_policy(RetryPolicy {
+        max_retries: 10,
+        initial_backoff: Duration::from_millis(500),
+        max_backoff: Duration::from_secs(30),
+        jitter: true,
+    });
+
+    let registration = startup_client
+        .register_runner_with(&RegisterRunner {
+            runner_id: config.runner_id.clone(),
+            registration_token: config.registration_token.clone(),
+            tags: config.registered_tags(),
+            plugins: config.plugins.clone(),
+            info: Some(runner_info()),
+        })
+        .await
+        .map_err(|e| anyhow::anyhow!("Failed to register with orchestrator: {}", e))?;
+
+    Ok(registration.credential)
+}
+
+/// Describes this runner for the registration handshake
+///
+/// # Returns
+/// The runner version, platform, hostname and protocol version
+fn runner_info() -> RunnerInfo {
+    let hostname = std::fs::read_to_string("/proc/sys/kernel/hostname")
+        .ok()
+        .or_else(|| std::env::var("HOSTNAME").ok())
+        .map(|name| name.trim().to_string())
+        .filter(|name| !name.is_empty())
+        .unwrap_or_else(|| "unknown".to_string());
+
+    RunnerInfo {
+        version: env!("CARGO_PKG_VERSION").to_string(),
+        os: std::env::consts::OS.to_string(),
+        arch: std::env::consts::ARCH.to_string(),
+        hostname,
+        protocol_version: RUNNER_PROTOCOL_VERSION,
     }
 }
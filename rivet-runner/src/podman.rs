@@ -1,49 +1,543 @@
 //! Podman container management
 //!
 //! Handles container lifecycle for job execution:
-//! - Checking podman availability
+//! - Driving podman, or docker (e.g. Docker for Windows), for Linux or
+//!   Windows containers
+//! - Checking the container runtime's availability
 //! - Managing multiple containers per job
 //! - Tracking container stack for nested container.with() calls
-//! - Executing commands in containers
+//! - Executing commands in containers, streaming their output line by line
+//! - Building images from the workspace and pushing them to registries
+//! - Snapshotting the containers of failed jobs for debug sessions
 //! - Cleaning up all containers after job completion
+//!
+//! The container runtime is driven through `tokio::process`, so a long
+//! command never blocks the runtime that sends logs and renews leases for
+//! the job.
 
 use anyhow::{Context, Result};
-use std::collections::HashMap;
-use std::process::Command;
-use std::sync::Mutex;
+use rivet_core::domain::log::OutputStream;
+use rivet_core::dto::log::MAX_LOG_MESSAGE_LENGTH;
+use serde::Deserialize;
+use serde::de::DeserializeOwned;
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Component, Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::str::FromStr;
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncWriteExt, BufReader};
+use tokio::sync::watch;
 use tracing::{debug, error, info, warn};
 use uuid::Uuid;
 
-/// Checks if podman is installed and available
-pub fn check_podman_available() -> Result<()> {
-    let output = Command::new("podman")
+use crate::context::current_stage;
+use crate::pool::WarmPool;
+use crate::state::RunnerState;
+
+/// Most output kept in memory per stream of a command
+///
+/// Longer output is still streamed to the log, only the copy returned to the
+/// pipeline is cut.
+pub const MAX_CAPTURED_OUTPUT: usize = 1024 * 1024;
+
+/// Exit code reported for commands killed by their timeout, as `timeout(1)` does
+pub const TIMEOUT_EXIT_CODE: i32 = 124;
+
+/// Environment variable marking the processes of one exec, so that a timed
+/// out command is killed with every process it started
+const EXEC_ID_VAR: &str = "RIVET_EXEC_ID";
+
+/// Kills, in a Linux container, every process whose environment holds
+/// `RIVET_EXEC_ID=$1`
+const KILL_EXEC_SCRIPT: &str = r#"for p in /proc/[0-9]*; do
+  { tr '\0' '\n' < "$p/environ"; } 2>/dev/null | grep -qx "RIVET_EXEC_ID=$1" && kill -9 "${p#/proc/}" 2>/dev/null
+done
+true"#;
+
+/// Label recording the image a job container snapshot was taken from
+pub const SNAPSHOT_LABEL: &str = "rivet.image";
+
+// =============================================================================
+// Container Runtime
+// =============================================================================
+
+/// Container engine the runner drives
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ContainerRuntime {
+    Podman,
+    /// Docker Engine or Docker for Windows, whose CLI takes the same
+    /// commands as podman's for what the runner does
+    Docker,
+}
+
+impl ContainerRuntime {
+    /// Docker on Windows hosts, podman elsewhere
+    pub fn for_host() -> Self {
+        if cfg!(windows) {
+            ContainerRuntime::Docker
+        } else {
+            ContainerRuntime::Podman
+        }
+    }
+
+    /// Executable of the engine's CLI
+    pub fn program(self) -> &'static str {
+        match self {
+            ContainerRuntime::Podman => "podman",
+            ContainerRuntime::Docker => "docker",
+        }
+    }
+}
+
+impl FromStr for ContainerRuntime {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "podman" => Ok(ContainerRuntime::Podman),
+            "docker" => Ok(ContainerRuntime::Docker),
+            _ => anyhow::bail!("Unknown container runtime '{}' (podman or docker)", s),
+        }
+    }
+}
+
+/// Operating system of the containers jobs run in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ContainerOs {
+    Linux,
+    Windows,
+}
+
+impl ContainerOs {
+    /// Windows containers on Windows hosts, Linux containers elsewhere
+    pub fn for_host() -> Self {
+        if cfg!(windows) {
+            ContainerOs::Windows
+        } else {
+            ContainerOs::Linux
+        }
+    }
+
+    /// Name registered as the runner's `os` tag
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ContainerOs::Linux => "linux",
+            ContainerOs::Windows => "windows",
+        }
+    }
+
+    /// Where containers mount the job's workspace
+    pub fn workspace_dir(self) -> &'static str {
+        match self {
+            ContainerOs::Linux => "/workspace",
+            ContainerOs::Windows => "C:\\workspace",
+        }
+    }
+
+    /// Path inside containers of a path relative to the workspace
+    pub fn workspace_path(self, relative: &Path) -> String {
+        let separator = match self {
+            ContainerOs::Linux => "/",
+            ContainerOs::Windows => "\\",
+        };
+        let mut path = self.workspace_dir().to_string();
+        for component in relative.components() {
+            let part = match component {
+                Component::Normal(part) => part.to_string_lossy(),
+                Component::ParentDir => "..".into(),
+                _ => continue,
+            };
+            path.push_str(separator);
+            path.push_str(&part);
+        }
+        path
+    }
+
+    /// Working directory of a command: absolute directories are kept, others
+    /// are relative to the workspace
+    pub fn working_dir(self, dir: Option<&str>) -> String {
+        match dir {
+            Some(dir) if self.is_absolute(dir) => dir.to_string(),
+            Some(dir) => self.workspace_path(Path::new(dir)),
+            None => self.workspace_dir().to_string(),
+        }
+    }
+
+    /// Whether a path inside containers is absolute
+    fn is_absolute(self, path: &str) -> bool {
+        match self {
+            ContainerOs::Linux => path.starts_with('/'),
+            ContainerOs::Windows => {
+                path.starts_with(['\\', '/']) || path.as_bytes().get(1) == Some(&b':')
+            }
+        }
+    }
+
+    /// Interactive shell of debug sessions, with its arguments
+    pub fn shell(self) -> (&'static str, &'static [&'static str]) {
+        match self {
+            ContainerOs::Linux => ("/bin/sh", &["-i"]),
+            ContainerOs::Windows => ("cmd.exe", &[]),
+        }
+    }
+}
+
+impl FromStr for ContainerOs {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "linux" => Ok(ContainerOs::Linux),
+            "windows" => Ok(ContainerOs::Windows),
+            _ => anyhow::bail!("Unknown container OS '{}' (linux or windows)", s),
+        }
+    }
+}
+
+/// Runtime and container OS of the process, set once at startup
+static PLATFORM: OnceLock<(ContainerRuntime, ContainerOs)> = OnceLock::new();
+
+/// Sets the container runtime and container OS of the process
+pub fn init(runtime: ContainerRuntime, os: ContainerOs) {
+    let _ = PLATFORM.set((runtime, os));
+}
+
+/// The container runtime of the process
+pub fn runtime() -> ContainerRuntime {
+    PLATFORM
+        .get()
+        .map(|(runtime, _)| *runtime)
+        .unwrap_or_else(ContainerRuntime::for_host)
+}
+
+/// The operating system of job containers
+pub fn container_os() -> ContainerOs {
+    PLATFORM
+        .get()
+        .map(|(_, os)| *os)
+        .unwrap_or_else(ContainerOs::for_host)
+}
+
+/// A command of the container runtime's CLI
+pub fn command() -> tokio::process::Command {
+    tokio::process::Command::new(runtime().program())
+}
+
+/// Passes environment variables to an exec'd command
+///
+/// Only the names go on the runtime's command line, which any local user
+/// can read from the process list. The runtime takes each value from its
+/// own environment, so secrets never show up in `ps`.
+fn pass_env(command: &mut tokio::process::Command, env: &BTreeMap<String, String>) {
+    for (key, value) in env {
+        command.arg("-e").arg(key).env(key, value);
+    }
+}
+
+/// Checks if the container runtime is installed and available
+pub fn check_runtime_available() -> Result<()> {
+    let program = runtime().program();
+    let output = Command::new(program)
         .arg("--version")
         .output()
-        .context("Failed to execute 'podman --version'. Is podman installed?")?;
+        .with_context(|| {
+            format!(
+                "Failed to execute '{} --version'. Is {} installed?",
+                program, program
+            )
+        })?;
 
     if !output.status.success() {
-        anyhow::bail!("Podman is not working correctly");
+        anyhow::bail!("{} is not working correctly", program);
     }
 
     let version = String::from_utf8_lossy(&output.stdout);
-    info!("Podman is available: {}", version.trim());
+    info!("{} is available: {}", program, version.trim());
 
     Ok(())
 }
 
+/// Inspects local images
+///
+/// `image inspect` reports the same fields (`Id`, `Size`, `Config.Labels`)
+/// with podman and docker, unlike `images --format json`.
+///
+/// # Arguments
+/// * `filter` - `images --filter` value selecting the images, if any
+pub async fn inspect_images<T: DeserializeOwned>(filter: Option<&str>) -> Result<Vec<T>> {
+    let mut list = command();
+    list.args(["images", "-q", "--no-trunc"]);
+    if let Some(filter) = filter {
+        list.arg("--filter").arg(filter);
+    }
+    let output = list.output().await.context("Failed to list images")?;
+    if !output.status.success() {
+        anyhow::bail!("{}", String::from_utf8_lossy(&output.stderr).trim());
+    }
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let mut ids: Vec<&str> = stdout.split_whitespace().collect();
+    ids.sort_unstable();
+    ids.dedup();
+    if ids.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let output = command()
+        .args(["image", "inspect"])
+        .args(&ids)
+        .output()
+        .await
+        .context("Failed to inspect images")?;
+    if !output.status.success() {
+        anyhow::bail!("{}", String::from_utf8_lossy(&output.stderr).trim());
+    }
+
+    serde_json::from_slice(&output.stdout).context("Failed to parse image inspect output")
+}
+
+/// Options of a command run with `ContainerManager::exec`
+///
+/// Also used as defaults for every command run inside a `container.with()`
+/// block; see `ExecOptions::merged`.
+#[derive(Debug, Clone, Default)]
+pub struct ExecOptions {
+    /// Extra environment variables (`podman exec -e`)
+    pub env: BTreeMap<String, String>,
+    /// User to run as (`podman exec -u`, None = the image's user)
+    pub user: Option<String>,
+    /// Working directory (relative to /workspace, None = /workspace)
+    pub cwd: Option<String>,
+    /// Kill the command after this long (None = the manager's default)
+    pub timeout: Option<Duration>,
+}
+
+impl ExecOptions {
+    /// Applies `overrides` on top of these options
+    ///
+    /// Set fields of `overrides` win; environment variables are merged, with
+    /// `overrides` winning on conflicts.
+    pub fn merged(&self, overrides: &ExecOptions) -> ExecOptions {
+        let mut env = self.env.clone();
+        env.extend(overrides.env.clone());
+
+        ExecOptions {
+            env,
+            user: overrides.user.clone().or_else(|| self.user.clone()),
+            cwd: overrides.cwd.clone().or_else(|| self.cwd.clone()),
+            timeout: overrides.timeout.or(self.timeout),
+        }
+    }
+}
+
+/// Options of an image built with `ContainerManager::build`
+#[derive(Debug, Clone)]
+pub struct BuildOptions {
+    /// Build context directory (relative to the workspace)
+    pub context: String,
+    /// Dockerfile (relative to the workspace)
+    pub dockerfile: String,
+    /// Tag of the built image
+    pub tag: String,
+    /// Build arguments (`podman build --build-arg`)
+    pub build_args: BTreeMap<String, String>,
+    /// Kill the build after this long (None = the manager's default)
+    pub timeout: Option<Duration>,
+}
+
+/// Credentials to log in to a registry with
+pub struct RegistryCredentials {
+    pub registry: String,
+    pub username: String,
+    pub password: String,
+}
+
+/// Images the containers of jobs may run, by registry and by name
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ImagePolicy {
+    /// Registries images may come from (empty = any)
+    pub registries: Vec<String>,
+    /// Patterns images must match as written by the job, `*` matching any
+    /// characters (empty = any)
+    pub images: Vec<String>,
+}
+
+impl ImagePolicy {
+    /// Fails unless `image` comes from an allowed registry and matches an
+    /// allowed pattern
+    pub fn check(&self, image: &str) -> Result<()> {
+        let registry = registry_of(image);
+        if !self.registries.is_empty() && !self.registries.iter().any(|r| r == registry) {
+            anyhow::bail!(
+                "Image {} comes from registry {}, which this runner does not allow (allowed: {})",
+                image,
+                registry,
+                self.registries.join(", ")
+            );
+        }
+        if !self.images.is_empty()
+            && !self
+                .images
+                .iter()
+                .any(|pattern| wildcard_match(pattern, image))
+        {
+            anyhow::bail!(
+                "Image {} is not allowed on this runner (allowed: {})",
+                image,
+                self.images.join(", ")
+            );
+        }
+        Ok(())
+    }
+}
+
+/// Whether `text` matches `pattern`, `*` matching any characters
+fn wildcard_match(pattern: &str, text: &str) -> bool {
+    match pattern.split_once('*') {
+        None => pattern == text,
+        Some((prefix, rest)) => {
+            let Some(text) = text.strip_prefix(prefix) else {
+                return false;
+            };
+            (0..=text.len())
+                .filter(|i| text.is_char_boundary(*i))
+                .any(|i| wildcard_match(rest, &text[i..]))
+        }
+    }
+}
+
+/// Result of a podman command (`exec`, `build` or `push`)
+#[derive(Debug, Clone, Default)]
+pub struct ExecOutput {
+    /// Standard output, cut at `MAX_CAPTURED_OUTPUT`
+    pub stdout: String,
+    /// Standard error, cut at `MAX_CAPTURED_OUTPUT`
+    pub stderr: String,
+    /// Exit code, `TIMEOUT_EXIT_CODE` when the command timed out
+    pub exit_code: i32,
+    /// Whether the command was killed by its timeout
+    pub timed_out: bool,
+    /// Whether `stdout` or `stderr` was cut
+    pub truncated: bool,
+}
+
+/// Captured output of one stream
+#[derive(Default)]
+struct Captured {
+    output: String,
+    truncated: bool,
+}
+
+impl Captured {
+    fn push(&mut self, text: &str) {
+        if self.output.len() + text.len() <= MAX_CAPTURED_OUTPUT {
+            self.output.push_str(text);
+        } else {
+            self.truncated = true;
+        }
+    }
+}
+
+/// Reads a child pipe line by line until it closes
+///
+/// Each line is passed to `on_line` without its terminator and kept in
+/// `captured`. Lines longer than `MAX_LOG_MESSAGE_LENGTH` are split so a
+/// command printing without newlines cannot grow the buffer unbounded.
+/// Invalid UTF-8 is replaced rather than ending the read.
+async fn read_lines(
+    pipe: impl AsyncRead + Unpin,
+    stream: OutputStream,
+    on_line: &(dyn Fn(OutputStream, &str) + Sync),
+    captured: &mut Captured,
+) {
+    let mut reader = BufReader::new(pipe);
+    let mut line = Vec::new();
+
+    loop {
+        let chunk = match reader.fill_buf().await {
+            Ok(chunk) => chunk,
+            Err(e) => {
+                warn!("Failed to read command {}: {}", stream, e);
+                break;
+            }
+        };
+
+        if chunk.is_empty() {
+            if !line.is_empty() {
+                emit_line(&line, stream, on_line, captured);
+            }
+            break;
+        }
+
+        let room = MAX_LOG_MESSAGE_LENGTH - line.len();
+        let (consumed, complete) = match chunk.iter().take(room).position(|b| *b == b'\n') {
+            Some(i) => (i + 1, true),
+            None => (chunk.len().min(room), false),
+        };
+        line.extend_from_slice(&chunk[..consumed]);
+        reader.consume(consumed);
+
+        if complete || line.len() >= MAX_LOG_MESSAGE_LENGTH {
+            emit_line(&line, stream, on_line, captured);
+            line.clear();
+        }
+    }
+}
+
+fn emit_line(
+    line: &[u8],
+    stream: OutputStream,
+    on_line: &(dyn Fn(OutputStream, &str) + Sync),
+    captured: &mut Captured,
+) {
+    let text = String::from_utf8_lossy(line);
+    on_line(stream, text.trim_end_matches(['\n', '\r']));
+    captured.push(&text);
+}
+
+/// Active containers with their command defaults, the last one current
+type ContainerStack = Vec<(String, ExecOptions)>;
+
 /// Container manager for a job
 ///
 /// Manages multiple containers that can be created via container.with().
 /// Tracks a stack of active containers, with the top being the current execution context.
+/// Each stage pushes onto its own stack above the default container, so stages
+/// running concurrently never run commands in each other's containers.
 pub struct ContainerManager {
     job_id: Uuid,
     workspace_path: String,
 
+    /// Timeout of commands that do not set their own
+    command_timeout: Duration,
+
+    /// Environment variables of every command, below those of the
+    /// container stack and of the command
+    env: BTreeMap<String, String>,
+
+    /// Images the job's containers may run
+    images: ImagePolicy,
+
     /// Registry of all containers: image -> container_name
-    containers: Mutex<HashMap<String, String>>,
+    ///
+    /// Held while a container starts so two stages never create the same one.
+    containers: tokio::sync::Mutex<HashMap<String, String>>,
+
+    /// Stacks of active containers with their command defaults (top = current
+    /// context), by stage; the `None` stack holds the default container
+    stacks: Mutex<HashMap<Option<String>, ContainerStack>>,
+
+    /// Runner-wide pool of warm containers and image cache
+    pool: Arc<WarmPool>,
 
-    /// Stack of active container names (top = current context)
-    stack: Mutex<Vec<String>>,
+    /// Records the job's containers, to remove them after a crash
+    state: Arc<RunnerState>,
+
+    /// Why the job was aborted; set once, kills the running command
+    aborted: watch::Sender<Option<String>>,
 }
 
 impl ContainerManager {
@@ -52,12 +546,31 @@ impl ContainerManager {
     /// # Arguments
     /// * `job_id` - The job ID
     /// * `workspace_path` - Path to workspace directory to mount in all containers
-    pub fn new(job_id: Uuid, workspace_path: String) -> Self {
+    /// * `command_timeout` - Timeout of commands that do not set their own
+    /// * `env` - Environment variables of every command (the pipeline's `env`)
+    /// * `images` - Images the job's containers may run
+    /// * `pool` - Warm containers the job's first container may be taken from
+    /// * `state` - Runner state the job's containers are recorded in
+    pub fn new(
+        job_id: Uuid,
+        workspace_path: String,
+        command_timeout: Duration,
+        env: BTreeMap<String, String>,
+        images: ImagePolicy,
+        pool: Arc<WarmPool>,
+        state: Arc<RunnerState>,
+    ) -> Self {
         Self {
             job_id,
             workspace_path,
-            containers: Mutex::new(HashMap::new()),
-            stack: Mutex::new(Vec::new()),
+            command_timeout,
+            env,
+            images,
+            containers: tokio::sync::Mutex::new(HashMap::new()),
+            stacks: Mutex::new(HashMap::new()),
+            pool,
+            state,
+            aborted: watch::Sender::new(None),
         }
     }
 
@@ -68,17 +581,21 @@ impl ContainerManager {
     ///
     /// # Returns
     /// Container name
-    pub fn start_default(&self, image: &str) -> Result<String> {
+    #[tracing::instrument(name = "podman::start_default", skip_all, fields(image = %image))]
+    pub async fn start_default(&self, image: &str) -> Result<String> {
         info!(
             "Starting default container with image {} for job {}",
             image, self.job_id
         );
 
-        let container_name = self.ensure_container_running(image)?;
+        let container_name = self.ensure_container_running(image).await?;
 
-        // Push to stack
-        let mut stack = self.stack.lock().unwrap();
-        stack.push(container_name.clone());
+        // Push to the stack shared by all stages
+        let mut stacks = self.stacks.lock().unwrap();
+        stacks
+            .entry(None)
+            .or_default()
+            .push((container_name.clone(), ExecOptions::default()));
 
         info!(
             "Default container {} started and pushed to stack",
@@ -89,15 +606,21 @@ impl ContainerManager {
 
     /// Ensures a container for the given image is running
     ///
-    /// If container already exists, returns its name. Otherwise creates it.
+    /// If container already exists, returns its name. Otherwise the job's
+    /// first container is taken from the warm pool when one is ready, and
+    /// any other container is created. Images the runner does not allow are
+    /// refused.
     ///
     /// # Arguments
     /// * `image` - Container image to run
     ///
     /// # Returns
     /// Container name
-    pub fn ensure_container_running(&self, image: &str) -> Result<String> {
-        let mut containers = self.containers.lock().unwrap();
+    #[tracing::instrument(name = "podman::ensure_container_running", skip_all, fields(image = %image))]
+    pub async fn ensure_container_running(&self, image: &str) -> Result<String> {
+        self.images.check(image)?;
+
+        let mut containers = self.containers.lock().await;
 
         // Check if container already exists for this image
         if let Some(container_name) = containers.get(image) {
@@ -108,71 +631,89 @@ impl ContainerManager {
             return Ok(container_name.clone());
         }
 
+        // The first container of a job may adopt a warm one, workspace included
+        let adopted = if containers.is_empty() {
+            self.pool.take(image, &self.workspace_path).await
+        } else {
+            None
+        };
+        if let Some(container_name) = adopted {
+            self.state.add_container(self.job_id, &container_name).await;
+            containers.insert(image.to_string(), container_name.clone());
+            return Ok(container_name);
+        }
+
         // Generate container name from image hash
         let container_name = self.generate_container_name(image);
 
         // Ensure workspace directory exists
-        std::fs::create_dir_all(&self.workspace_path)
+        tokio::fs::create_dir_all(&self.workspace_path)
+            .await
             .context("Failed to create workspace directory")?;
 
         info!("Creating container {} for image {}", container_name, image);
+        self.state.add_container(self.job_id, &container_name).await;
+        run_container(&container_name, image, &self.workspace_path).await?;
+        self.pool.touch(image).await;
 
-        // Start container with workspace mounted, sleeping indefinitely
-        // podman run blocks until container is running, so no need to wait
-        // Override entrypoint to /bin/sh to handle images with custom entrypoints (like alpine/git)
-        let output = Command::new("podman")
-            .arg("run")
-            .arg("-d") // Detached
-            .arg("--name")
-            .arg(&container_name)
-            .arg("--entrypoint")
-            .arg("/bin/sh") // Override any image entrypoint
-            .arg("-v")
-            .arg(format!("{}:/workspace", self.workspace_path))
-            .arg("-w")
-            .arg("/workspace") // Set working directory
-            .arg(image)
-            .arg("-c")
-            .arg("sleep infinity")
-            .output()
-            .context("Failed to execute podman run command")?;
-
-        let stdout = String::from_utf8_lossy(&output.stdout);
-        let stderr = String::from_utf8_lossy(&output.stderr);
+        // Register container
+        containers.insert(image.to_string(), container_name.clone());
 
-        // Always log stdout/stderr as debug
-        if !stdout.trim().is_empty() {
-            debug!("podman run stdout: {}", stdout.trim());
-        }
-        if !stderr.trim().is_empty() {
-            debug!("podman run stderr: {}", stderr.trim());
-        }
+        Ok(container_name)
+    }
 
-        if !output.status.success() {
-            let exit_code = output.status.code().unwrap_or(-1);
+    /// Pulls an image a container is about to be started from
+    ///
+    /// Nothing is pulled when the job already runs a container for the
+    /// image or the image is already on the runner. Images the runner does
+    /// not allow are refused. Output is streamed like `exec`.
+    ///
+    /// # Arguments
+    /// * `image` - Container image to pull
+    /// * `on_line` - Called for every stdout and stderr line
+    ///
+    /// # Returns
+    /// The pull's output and exit code, or None if nothing was pulled
+    #[tracing::instrument(name = "podman::pull", skip_all, fields(image = %image))]
+    pub async fn pull(
+        &self,
+        image: &str,
+        on_line: &(dyn Fn(OutputStream, &str) + Sync),
+    ) -> Result<Option<ExecOutput>> {
+        self.images.check(image)?;
 
-            let error_msg = format!(
-                "Failed to start container for image {}: exit_code={}, stdout='{}', stderr='{}'",
-                image,
-                exit_code,
-                stdout.trim(),
-                stderr.trim()
-            );
+        if self.containers.lock().await.contains_key(image) {
+            return Ok(None);
+        }
 
-            error!("{}", error_msg);
-            anyhow::bail!("{}", error_msg);
+        let exists = command()
+            .args(["image", "inspect", image])
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status()
+            .await
+            .context("Failed to execute image inspect")?;
+        if exists.success() {
+            debug!("Image {} is already on the runner", image);
+            return Ok(None);
         }
 
-        let container_id = stdout.trim().to_string();
-        info!(
-            "Container {} started successfully with ID: {}",
-            container_name, container_id
-        );
+        info!("Pulling image {} for job {}", image, self.job_id);
 
-        // Register container
-        containers.insert(image.to_string(), container_name.clone());
+        let mut command = command();
+        command.arg("pull").arg(image);
 
-        Ok(container_name)
+        let description = format!("pull of {}", image);
+        let abort = self.abort_signal()?;
+        run_streamed(
+            &mut command,
+            self.command_timeout,
+            abort,
+            on_line,
+            &description,
+        )
+        .await
+        .map(Some)
     }
 
     /// Pushes a container onto the stack
@@ -182,14 +723,17 @@ impl ContainerManager {
     ///
     /// # Arguments
     /// * `image` - Container image to push
+    /// * `defaults` - Options applied to every command run in this context
     ///
     /// # Returns
     /// Container name
-    pub fn push_container(&self, image: &str) -> Result<String> {
-        let container_name = self.ensure_container_running(image)?;
+    #[tracing::instrument(name = "podman::push_container", skip_all, fields(image = %image))]
+    pub async fn push_container(&self, image: &str, defaults: ExecOptions) -> Result<String> {
+        let container_name = self.ensure_container_running(image).await?;
 
-        let mut stack = self.stack.lock().unwrap();
-        stack.push(container_name.clone());
+        let mut stacks = self.stacks.lock().unwrap();
+        let stack = stacks.entry(current_stage()).or_default();
+        stack.push((container_name.clone(), defaults));
 
         debug!(
             "Pushed container {} onto stack (depth: {})",
@@ -206,8 +750,9 @@ impl ContainerManager {
     /// # Returns
     /// The popped container name, or None if stack is empty
     pub fn pop_container(&self) -> Option<String> {
-        let mut stack = self.stack.lock().unwrap();
-        let popped = stack.pop();
+        let mut stacks = self.stacks.lock().unwrap();
+        let stack = stacks.entry(current_stage()).or_default();
+        let popped = stack.pop().map(|(name, _)| name);
 
         if let Some(ref name) = popped {
             debug!(
@@ -220,94 +765,360 @@ impl ContainerManager {
         popped
     }
 
-    /// Gets the current container name from the top of the stack
+    /// Gets the current container from the top of the running stage's stack
     ///
     /// # Returns
-    /// Current container name, or None if stack is empty
-    pub fn current_container(&self) -> Option<String> {
-        let stack = self.stack.lock().unwrap();
-        stack.last().cloned()
+    /// Current container name and its command defaults, falling back to the
+    /// default container, or None if no container was started
+    pub fn current_container(&self) -> Option<(String, ExecOptions)> {
+        let stacks = self.stacks.lock().unwrap();
+        stacks
+            .get(&current_stage())
+            .and_then(|stack| stack.last())
+            .or_else(|| stacks.get(&None).and_then(|stack| stack.last()))
+            .cloned()
+    }
+
+    /// Aborts the job's commands
+    ///
+    /// The running command is killed and later ones fail right away. Only
+    /// the first reason is kept.
+    ///
+    /// # Arguments
+    /// * `reason` - Why the job was aborted, reported as its failure
+    pub fn abort(&self, reason: String) {
+        self.aborted.send_if_modified(|aborted| {
+            if aborted.is_some() {
+                return false;
+            }
+            *aborted = Some(reason);
+            true
+        });
+    }
+
+    /// Why the job was aborted, None if it was not
+    pub fn abort_reason(&self) -> Option<String> {
+        self.aborted.borrow().clone()
+    }
+
+    /// Fails if the job was aborted, otherwise subscribes to its abortion
+    fn abort_signal(&self) -> Result<watch::Receiver<Option<String>>> {
+        match self.abort_reason() {
+            Some(reason) => anyhow::bail!("Job aborted: {}", reason),
+            None => Ok(self.aborted.subscribe()),
+        }
     }
 
     /// Executes a command in the current container
     ///
+    /// Output is handed to `on_line` as it is produced, one line at a time
+    /// (without the line terminator), and returned up to
+    /// `MAX_CAPTURED_OUTPUT` per stream. A command still running when its
+    /// timeout expires is killed and reported with `TIMEOUT_EXIT_CODE`; in
+    /// Linux containers, so are the processes it started.
+    ///
     /// # Arguments
     /// * `cmd` - Command to execute
     /// * `args` - Arguments for the command
-    /// * `cwd` - Working directory (relative to /workspace, None = /workspace)
+    /// * `options` - Environment, user, working directory and timeout, on
+    ///   top of the current container's defaults
+    /// * `on_line` - Called for every stdout and stderr line
     ///
     /// # Returns
-    /// (stdout, stderr, exit_code)
-    pub fn exec(
+    /// The command's output and exit code
+    #[tracing::instrument(name = "podman::exec", skip_all, fields(cmd = %cmd))]
+    pub async fn exec(
         &self,
         cmd: &str,
         args: &[String],
-        cwd: Option<&str>,
-    ) -> Result<(String, String, i32)> {
-        let container_name = self
+        options: &ExecOptions,
+        on_line: &(dyn Fn(OutputStream, &str) + Sync),
+    ) -> Result<ExecOutput> {
+        let (container_name, defaults) = self
             .current_container()
             .ok_or_else(|| anyhow::anyhow!("No active container in stack"))?;
+        let base = ExecOptions {
+            env: self.env.clone(),
+            ..ExecOptions::default()
+        };
+        let options = base.merged(&defaults).merged(options);
 
         debug!(
             "Executing in container {}: {} {:?}",
             container_name, cmd, args
         );
 
-        let working_dir = match cwd {
-            Some(dir) => {
-                if dir.starts_with('/') {
-                    dir.to_string()
-                } else {
-                    format!("/workspace/{}", dir)
-                }
-            }
-            None => "/workspace".to_string(),
-        };
+        let working_dir = container_os().working_dir(options.cwd.as_deref());
+
+        let mut command = command();
+        command.arg("exec").arg("-w").arg(&working_dir);
+
+        if let Some(user) = &options.user {
+            command.arg("-u").arg(user);
+        }
+
+        pass_env(&mut command, &options.env);
 
-        let mut command = Command::new("podman");
+        // Inherited by the processes the command starts
+        let exec_id = Uuid::new_v4().to_string();
         command
-            .arg("exec")
-            .arg("-w")
-            .arg(&working_dir)
-            .arg(&container_name)
-            .arg(cmd);
+            .arg("-e")
+            .arg(format!("{}={}", EXEC_ID_VAR, exec_id));
+
+        command.arg(&container_name).arg(cmd);
 
         for arg in args {
             command.arg(arg);
         }
 
-        let output = command
-            .output()
-            .context("Failed to execute podman exec command")?;
+        let timeout = options.timeout.unwrap_or(self.command_timeout);
+        let description = format!("{} in container {}", cmd, container_name);
+        let abort = self.abort_signal()?;
+        let output = run_streamed(&mut command, timeout, abort, on_line, &description).await?;
+
+        // Killing the runtime's client leaves the command running inside
+        // the container
+        if output.timed_out {
+            kill_exec(&container_name, &exec_id).await;
+        }
+
+        Ok(output)
+    }
 
-        let stdout = String::from_utf8_lossy(&output.stdout).to_string();
-        let stderr = String::from_utf8_lossy(&output.stderr).to_string();
-        let exit_code = output.status.code().unwrap_or(1);
+    /// Builds an image from a Dockerfile in the workspace
+    ///
+    /// Runs `podman build` on the runner host; output is streamed like `exec`.
+    ///
+    /// # Arguments
+    /// * `options` - Build context, Dockerfile, tag, build arguments and timeout
+    /// * `on_line` - Called for every stdout and stderr line
+    ///
+    /// # Returns
+    /// The build's output and exit code
+    #[tracing::instrument(name = "podman::build", skip_all, fields(tag = %options.tag))]
+    pub async fn build(
+        &self,
+        options: &BuildOptions,
+        on_line: &(dyn Fn(OutputStream, &str) + Sync),
+    ) -> Result<ExecOutput> {
+        let context_dir = self.workspace_file(&options.context)?;
+        let dockerfile = self.workspace_file(&options.dockerfile)?;
+
+        info!(
+            "Building image {} from {} for job {}",
+            options.tag, options.context, self.job_id
+        );
+
+        let mut command = command();
+        command
+            .arg("build")
+            .arg("-f")
+            .arg(&dockerfile)
+            .arg("-t")
+            .arg(&options.tag);
+
+        for (key, value) in &options.build_args {
+            command.arg("--build-arg").arg(format!("{}={}", key, value));
+        }
+
+        command.arg(&context_dir);
+
+        let timeout = options.timeout.unwrap_or(self.command_timeout);
+        let description = format!("build of {}", options.tag);
+        let abort = self.abort_signal()?;
+        run_streamed(&mut command, timeout, abort, on_line, &description).await
+    }
+
+    /// Pushes an image to its registry
+    ///
+    /// With credentials, logs in first; the credentials are stored in an
+    /// auth file private to this job (removed by `cleanup`) and the password
+    /// is handed to podman on stdin, never on its command line.
+    ///
+    /// # Arguments
+    /// * `tag` - Image to push (e.g., registry.example.com/app:1.0)
+    /// * `credentials` - Registry and username/password to log in with
+    /// * `timeout` - Timeout of the push (None = the manager's default)
+    /// * `on_line` - Called for every stdout and stderr line
+    ///
+    /// # Returns
+    /// The push's output and exit code
+    #[tracing::instrument(name = "podman::push", skip_all, fields(tag = %tag))]
+    pub async fn push(
+        &self,
+        tag: &str,
+        credentials: Option<&RegistryCredentials>,
+        timeout: Option<Duration>,
+        on_line: &(dyn Fn(OutputStream, &str) + Sync),
+    ) -> Result<ExecOutput> {
+        let auth_file = self.auth_file();
+
+        if let Some(credentials) = credentials {
+            self.login(credentials).await?;
+        }
+
+        info!("Pushing image {} for job {}", tag, self.job_id);
+
+        let mut command = if auth_file.exists() {
+            self.authenticated("push")
+        } else {
+            let mut command = command();
+            command.arg("push");
+            command
+        };
+        command.arg(tag);
+
+        let timeout = timeout.unwrap_or(self.command_timeout);
+        let description = format!("push of {}", tag);
+        let abort = self.abort_signal()?;
+        run_streamed(&mut command, timeout, abort, on_line, &description).await
+    }
+
+    /// Logs in to a registry, storing the credentials in the job's auth file
+    async fn login(&self, credentials: &RegistryCredentials) -> Result<()> {
+        debug!(
+            "Logging in to registry {} as {}",
+            credentials.registry, credentials.username
+        );
+
+        let mut child = self
+            .authenticated("login")
+            .arg("--username")
+            .arg(&credentials.username)
+            .arg("--password-stdin")
+            .arg(&credentials.registry)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()
+            .context("Failed to execute podman login command")?;
+
+        let mut stdin = child.stdin.take().expect("stdin is piped");
+        stdin
+            .write_all(credentials.password.as_bytes())
+            .await
+            .context("Failed to pass password to podman login")?;
+        drop(stdin);
+
+        let output = child
+            .wait_with_output()
+            .await
+            .context("Failed to wait for podman login command")?;
 
         if !output.status.success() {
-            debug!(
-                "Command failed in container {}: cmd={} exit_code={} stdout='{}' stderr='{}'",
-                container_name,
-                cmd,
-                exit_code,
-                stdout.trim(),
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            anyhow::bail!(
+                "Failed to log in to registry {}: {}",
+                credentials.registry,
                 stderr.trim()
             );
-        } else {
-            debug!(
-                "Command completed successfully: exit_code={}, stdout_len={}, stderr_len={}",
-                exit_code,
-                stdout.len(),
-                stderr.len()
+        }
+
+        Ok(())
+    }
+
+    /// Path of the registry credentials of this job: podman's auth file, or
+    /// docker's configuration directory
+    ///
+    /// Kept next to (not inside) the workspace so containers never see it.
+    fn auth_file(&self) -> PathBuf {
+        match runtime() {
+            ContainerRuntime::Podman => PathBuf::from(format!("{}.auth.json", self.workspace_path)),
+            ContainerRuntime::Docker => PathBuf::from(format!("{}.docker", self.workspace_path)),
+        }
+    }
+
+    /// A `login` or `push` command using the job's registry credentials
+    ///
+    /// Podman takes the auth file after the subcommand, docker its
+    /// configuration directory before it.
+    fn authenticated(&self, subcommand: &str) -> tokio::process::Command {
+        let mut command = command();
+        match runtime() {
+            ContainerRuntime::Podman => {
+                command
+                    .arg(subcommand)
+                    .arg("--authfile")
+                    .arg(self.auth_file());
+            }
+            ContainerRuntime::Docker => {
+                command
+                    .arg("--config")
+                    .arg(self.auth_file())
+                    .arg(subcommand);
+            }
+        }
+        command
+    }
+
+    /// Resolves a path relative to the workspace, rejecting escapes
+    ///
+    /// # Arguments
+    /// * `relative` - Path relative to the workspace root
+    ///
+    /// # Returns
+    /// The absolute path on the runner host
+    fn workspace_file(&self, relative: &str) -> Result<PathBuf> {
+        let path = Path::new(relative);
+        let escapes = path
+            .components()
+            .any(|c| !matches!(c, Component::Normal(_) | Component::CurDir));
+        if escapes {
+            anyhow::bail!(
+                "Path '{}' must be relative to the workspace and stay inside it",
+                relative
             );
         }
 
-        Ok((stdout, stderr, exit_code))
+        Ok(Path::new(&self.workspace_path).join(path))
+    }
+
+    /// Commits every container of the job to an image
+    ///
+    /// The snapshots are tagged under `repository` and labelled with the
+    /// image each container ran (`SNAPSHOT_LABEL`), so a debug session can
+    /// restore the environment the job failed in. Failures are only logged.
+    ///
+    /// # Arguments
+    /// * `repository` - Image repository the snapshots are tagged in
+    #[tracing::instrument(name = "podman::snapshot", skip_all)]
+    pub async fn snapshot(&self, repository: &str) {
+        let containers = self.containers.lock().await;
+
+        for (i, (image, container_name)) in containers.iter().enumerate() {
+            let tag = format!("{}:{}", repository, i);
+            debug!("Committing container {} to {}", container_name, tag);
+
+            let mut commit = command();
+            commit.arg("commit");
+            if runtime() == ContainerRuntime::Podman {
+                commit.arg("--quiet");
+            }
+            let output = commit
+                .arg("--change")
+                .arg(format!("LABEL {}=\"{}\"", SNAPSHOT_LABEL, image))
+                .arg(container_name)
+                .arg(&tag)
+                .output()
+                .await;
+
+            match output {
+                Ok(output) if output.status.success() => {}
+                Ok(output) => warn!(
+                    "Failed to snapshot container {}: {}",
+                    container_name,
+                    String::from_utf8_lossy(&output.stderr).trim()
+                ),
+                Err(e) => warn!("Failed to snapshot container {}: {}", container_name, e),
+            }
+        }
     }
 
     /// Stops and removes all containers created by this manager
-    pub fn cleanup(&self) -> Result<()> {
-        let containers = self.containers.lock().unwrap();
+    #[tracing::instrument(name = "podman::cleanup", skip_all)]
+    pub async fn cleanup(&self) -> Result<()> {
+        let containers = std::mem::take(&mut *self.containers.lock().await);
 
         info!(
             "Cleaning up {} container(s) for job {}",
@@ -319,17 +1130,15 @@ impl ContainerManager {
             debug!("Stopping container {} (image: {})", container_name, image);
 
             // Stop container (ignore errors if already stopped)
-            let _ = Command::new("podman")
-                .arg("stop")
-                .arg(container_name)
-                .output();
+            let _ = command().arg("stop").arg(container_name).output().await;
 
             // Remove container
-            let rm_output = Command::new("podman")
+            let rm_output = command()
                 .arg("rm")
                 .arg("-f") // Force remove
                 .arg(container_name)
-                .output();
+                .output()
+                .await;
 
             match rm_output {
                 Ok(output) if output.status.success() => {
@@ -345,6 +1154,17 @@ impl ContainerManager {
             }
         }
 
+        let auth_file = self.auth_file();
+        let removed = match runtime() {
+            ContainerRuntime::Podman => tokio::fs::remove_file(&auth_file).await,
+            ContainerRuntime::Docker => tokio::fs::remove_dir_all(&auth_file).await,
+        };
+        match removed {
+            Ok(()) => debug!("Registry auth file removed"),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => warn!("Failed to remove registry auth file: {}", e),
+        }
+
         info!("Cleanup complete for job {}", self.job_id);
         Ok(())
     }
@@ -365,9 +1185,382 @@ impl ContainerManager {
 }
 
 impl Drop for ContainerManager {
+    /// Removes containers `cleanup` was not called for (e.g. the job task
+    /// panicked); there is no runtime to await on here, so this blocks.
     fn drop(&mut self) {
-        if let Err(e) = self.cleanup() {
-            warn!("Failed to cleanup containers on drop: {}", e);
+        let _ = match runtime() {
+            ContainerRuntime::Podman => std::fs::remove_file(self.auth_file()),
+            ContainerRuntime::Docker => std::fs::remove_dir_all(self.auth_file()),
+        };
+
+        for container_name in self.containers.get_mut().values() {
+            warn!("Removing leftover container {}", container_name);
+            if let Err(e) = Command::new(runtime().program())
+                .arg("rm")
+                .arg("-f")
+                .arg(container_name)
+                .output()
+            {
+                warn!("Failed to remove container {}: {}", container_name, e);
+            }
         }
     }
 }
+
+/// Starts a container for an image with the workspace mounted
+///
+/// The container only idles; commands are run in it with `exec`. The
+/// workspace is mounted at `/workspace` in Linux containers and at
+/// `C:\workspace` in Windows containers.
+///
+/// # Arguments
+/// * `container_name` - Name of the new container
+/// * `image` - Container image to run
+/// * `workspace_path` - Host directory mounted as the workspace
+#[tracing::instrument(name = "podman::run_container", skip_all, fields(image = %image))]
+pub async fn run_container(container_name: &str, image: &str, workspace_path: &str) -> Result<()> {
+    let os = container_os();
+    let workspace_dir = os.workspace_dir();
+
+    // run blocks until the container is running, so no need to wait. The
+    // entrypoint is overridden to handle images with custom entrypoints
+    // (like alpine/git)
+    let mut command = command();
+    command
+        .arg("run")
+        .arg("-d") // Detached
+        .arg("--name")
+        .arg(container_name);
+    match os {
+        ContainerOs::Linux => command
+            .arg("--entrypoint")
+            .arg("/bin/sh")
+            .arg("-v")
+            .arg(format!("{}:{}", workspace_path, workspace_dir))
+            .arg("-w")
+            .arg(workspace_dir)
+            .arg(image)
+            .arg("-c")
+            .arg("sleep infinity"),
+        // Host paths of Windows have a drive colon, which `-v` splits on
+        ContainerOs::Windows => command
+            .arg("--entrypoint")
+            .arg("cmd.exe")
+            .arg("--mount")
+            .arg(format!(
+                "type=bind,source={},target={}",
+                workspace_path, workspace_dir
+            ))
+            .arg("-w")
+            .arg(workspace_dir)
+            .arg(image)
+            .arg("/S")
+            .arg("/C")
+            .arg("ping -t localhost > NUL"),
+    };
+    let output = command
+        .output()
+        .await
+        .context("Failed to execute run command")?;
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    // Always log stdout/stderr as debug
+    if !stdout.trim().is_empty() {
+        debug!("podman run stdout: {}", stdout.trim());
+    }
+    if !stderr.trim().is_empty() {
+        debug!("podman run stderr: {}", stderr.trim());
+    }
+
+    if !output.status.success() {
+        let exit_code = output.status.code().unwrap_or(-1);
+
+        let error_msg = format!(
+            "Failed to start container for image {}: exit_code={}, stdout='{}', stderr='{}'",
+            image,
+            exit_code,
+            stdout.trim(),
+            stderr.trim()
+        );
+
+        error!("{}", error_msg);
+        anyhow::bail!("{}", error_msg);
+    }
+
+    let container_id = stdout.trim().to_string();
+    info!(
+        "Container {} started successfully with ID: {}",
+        container_name, container_id
+    );
+
+    Ok(())
+}
+
+/// Kills the processes of an exec that timed out, see `EXEC_ID_VAR`
+///
+/// Only Linux containers are handled; failures are logged, the command
+/// having already been reported as timed out.
+async fn kill_exec(container_name: &str, exec_id: &str) {
+    if container_os() != ContainerOs::Linux {
+        debug!(
+            "Processes of a timed out command are not killed in {} containers",
+            container_os().as_str()
+        );
+        return;
+    }
+
+    let result = command()
+        .args(["exec", "-u", "0", container_name, "sh", "-c"])
+        .arg(KILL_EXEC_SCRIPT)
+        .args(["sh", exec_id])
+        .stdin(Stdio::null())
+        .output()
+        .await;
+    match result {
+        Ok(output) if output.status.success() => {
+            debug!("Killed the processes of exec {}", exec_id)
+        }
+        Ok(output) => warn!(
+            "Failed to kill the processes of a timed out command in {}: {}",
+            container_name,
+            String::from_utf8_lossy(&output.stderr).trim()
+        ),
+        Err(e) => warn!(
+            "Failed to kill the processes of a timed out command in {}: {}",
+            container_name, e
+        ),
+    }
+}
+
+/// Runs a podman command to completion, streaming its output
+///
+/// Output is handed to `on_line` as it is produced and returned up to
+/// `MAX_CAPTURED_OUTPUT` per stream. A command still running when `timeout`
+/// expires is killed and reported with `TIMEOUT_EXIT_CODE`; one running
+/// when the job is aborted is killed and reported as an error.
+///
+/// # Arguments
+/// * `command` - The podman command, with all its arguments
+/// * `timeout` - How long the command may run
+/// * `abort` - Signals the job's abortion
+/// * `on_line` - Called for every stdout and stderr line
+/// * `description` - What is run, for diagnostics
+async fn run_streamed(
+    command: &mut tokio::process::Command,
+    timeout: Duration,
+    mut abort: watch::Receiver<Option<String>>,
+    on_line: &(dyn Fn(OutputStream, &str) + Sync),
+    description: &str,
+) -> Result<ExecOutput> {
+    let mut child = command
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .with_context(|| format!("Failed to execute podman for {}", description))?;
+
+    let child_stdout = child.stdout.take().expect("stdout is piped");
+    let child_stderr = child.stderr.take().expect("stderr is piped");
+
+    let mut stdout = Captured::default();
+    let mut stderr = Captured::default();
+
+    // Both pipes are drained concurrently or the command blocks once
+    // one of them fills up
+    let run = async {
+        tokio::join!(
+            read_lines(child_stdout, OutputStream::Stdout, on_line, &mut stdout),
+            read_lines(child_stderr, OutputStream::Stderr, on_line, &mut stderr),
+        );
+        child.wait().await
+    };
+
+    let result = tokio::select! {
+        result = tokio::time::timeout(timeout, run) => Some(result),
+        _ = abort.wait_for(Option::is_some) => None,
+    };
+    let Some(result) = result else {
+        if let Err(e) = child.kill().await {
+            warn!("Failed to kill aborted command: {}", e);
+        }
+        let reason = abort.borrow().clone().unwrap_or_default();
+        anyhow::bail!("Job aborted: {}", reason);
+    };
+
+    let (exit_code, timed_out) = match result {
+        Ok(status) => {
+            let status =
+                status.with_context(|| format!("Failed to wait for podman for {}", description))?;
+            (status.code().unwrap_or(1), false)
+        }
+        Err(_) => {
+            warn!("Timed out after {:?}: {}", timeout, description);
+            if let Err(e) = child.kill().await {
+                warn!("Failed to kill timed out command: {}", e);
+            }
+            (TIMEOUT_EXIT_CODE, true)
+        }
+    };
+
+    if exit_code != 0 {
+        debug!(
+            "Command failed: {} exit_code={} stdout_len={} stderr_len={}",
+            description,
+            exit_code,
+            stdout.output.len(),
+            stderr.output.len()
+        );
+    } else {
+        debug!(
+            "Command completed successfully: {} stdout_len={} stderr_len={}",
+            description,
+            stdout.output.len(),
+            stderr.output.len()
+        );
+    }
+
+    Ok(ExecOutput {
+        truncated: stdout.truncated || stderr.truncated,
+        stdout: stdout.output,
+        stderr: stderr.output,
+        exit_code,
+        timed_out,
+    })
+}
+
+/// Registry an image reference belongs to
+///
+/// Follows podman's rule: the first path component names a registry when it
+/// looks like a host (contains `.` or `:`, or is `localhost`); otherwise the
+/// image lives on Docker Hub.
+pub fn registry_of(image: &str) -> &str {
+    match image.split_once('/') {
+        Some((host, _)) if host.contains('.') || host.contains(':') || host == "localhost" => host,
+        _ => "docker.io",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_registry_of() {
+        assert_eq!(registry_of("alpine:latest"), "docker.io");
+        assert_eq!(registry_of("library/alpine"), "docker.io");
+        assert_eq!(registry_of("ghcr.io/org/app:1.0"), "ghcr.io");
+        assert_eq!(registry_of("localhost/app"), "localhost");
+        assert_eq!(registry_of("registry:5000/app"), "registry:5000");
+    }
+
+    #[test]
+    fn test_image_policy() {
+        let any = ImagePolicy::default();
+        assert!(any.check("alpine:latest").is_ok());
+
+        let policy = ImagePolicy {
+            registries: vec!["docker.io".to_string(), "ghcr.io".to_string()],
+            images: vec!["alpine:*".to_string(), "ghcr.io/acme/*".to_string()],
+        };
+        assert!(policy.check("alpine:3.20").is_ok());
+        assert!(policy.check("ghcr.io/acme/builder:1").is_ok());
+        assert!(policy.check("rust:1").is_err());
+        assert!(policy.check("quay.io/acme/builder:1").is_err());
+        assert!(policy.check("ghcr.io/other/app").is_err());
+    }
+
+    #[test]
+    fn test_exec_options_merged_overrides_defaults() {
+        let defaults = ExecOptions {
+            env: BTreeMap::from([
+                ("CI".to_string(), "true".to_string()),
+                ("MODE".to_string(), "debug".to_string()),
+            ]),
+            user: Some("builder".to_string()),
+            cwd: Some("app".to_string()),
+            timeout: None,
+        };
+        let overrides = ExecOptions {
+            env: BTreeMap::from([("MODE".to_string(), "release".to_string())]),
+            cwd: Some("app/sub".to_string()),
+            timeout: Some(Duration::from_secs(5)),
+            ..Default::default()
+        };
+
+        let merged = defaults.merged(&overrides);
+        assert_eq!(merged.env["CI"], "true");
+        assert_eq!(merged.env["MODE"], "release");
+        assert_eq!(merged.user.as_deref(), Some("builder"));
+        assert_eq!(merged.cwd.as_deref(), Some("app/sub"));
+        assert_eq!(merged.timeout, Some(Duration::from_secs(5)));
+    }
+
+    #[test]
+    fn test_pass_env_keeps_values_off_the_command_line() {
+        let env = BTreeMap::from([
+            ("API_TOKEN".to_string(), "s3cr3t".to_string()),
+            ("CI".to_string(), "true".to_string()),
+        ]);
+        let mut command = tokio::process::Command::new("podman");
+        pass_env(&mut command, &env);
+
+        let command = command.as_std();
+        let args: Vec<_> = command.get_args().collect();
+        assert_eq!(args, ["-e", "API_TOKEN", "-e", "CI"]);
+
+        let envs: HashMap<_, _> = command.get_envs().collect();
+        assert_eq!(
+            envs[std::ffi::OsStr::new("API_TOKEN")],
+            Some("s3cr3t".as_ref())
+        );
+        assert_eq!(envs[std::ffi::OsStr::new("CI")], Some("true".as_ref()));
+    }
+
+    #[tokio::test]
+    async fn test_read_lines_streams_each_line() {
+        let lines = Mutex::new(Vec::new());
+        let mut captured = Captured::default();
+        read_lines(
+            &b"first\r\nsecond\n\xffthird"[..],
+            OutputStream::Stderr,
+            &|stream, line| lines.lock().unwrap().push((stream, line.to_string())),
+            &mut captured,
+        )
+        .await;
+
+        assert_eq!(captured.output, "first\r\nsecond\n\u{fffd}third");
+        assert!(!captured.truncated);
+        assert_eq!(
+            lines.into_inner().unwrap(),
+            vec![
+                (OutputStream::Stderr, "first".to_string()),
+                (OutputStream::Stderr, "second".to_string()),
+                (OutputStream::Stderr, "\u{fffd}third".to_string()),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_read_lines_bounds_memory() {
+        let line_count = Mutex::new(0);
+        let mut captured = Captured::default();
+        let output = vec![b'x'; MAX_CAPTURED_OUTPUT + MAX_LOG_MESSAGE_LENGTH];
+        read_lines(
+            &output[..],
+            OutputStream::Stdout,
+            &|_, line| {
+                assert!(line.len() <= MAX_LOG_MESSAGE_LENGTH);
+                *line_count.lock().unwrap() += 1;
+            },
+            &mut captured,
+        )
+        .await;
+
+        assert!(*line_count.lock().unwrap() > 1);
+        assert!(captured.output.len() <= MAX_CAPTURED_OUTPUT);
+        assert!(captured.truncated);
+    }
+}
@@ -0,0 +1,403 @@
+//! Warm container pool and image cache
+//!
+//! Keeps job start latency away from image pulls and container creation:
+//! - Pre-pulls configured images at startup
+//! - Keeps paused containers ready for frequently used images; the first
+//!   container of a job adopts one, workspace included
+//! - Evicts images the runner used once they are too old or the image
+//!   store grows too large
+//!
+//! A warm container mounts its own directory under `WARM_DIR` at
+//! /workspace. Adopting it renames that directory to the job's workspace:
+//! the bind mount follows the directory, so the container sees the job's
+//! files without being recreated.
+
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::Notify;
+use tracing::{debug, info, warn};
+use uuid::Uuid;
+
+use crate::config::Config;
+use crate::podman::{command, inspect_images, run_container};
+
+/// Directory (under the workspace base) holding warm containers' workspaces
+pub const WARM_DIR: &str = ".rivet-warm";
+
+/// Name prefix of warm containers, used to remove leftovers at startup
+const WARM_CONTAINER_PREFIX: &str = "rivet-warm-";
+
+/// How often the pool is refilled and the image cache checked
+const MAINTENANCE_INTERVAL: Duration = Duration::from_secs(60);
+
+/// A paused container waiting for a job
+struct WarmContainer {
+    name: String,
+    workspace: PathBuf,
+    paused: bool,
+}
+
+/// An image as reported by `image inspect`
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct ImageInfo {
+    id: String,
+    #[serde(default)]
+    size: u64,
+}
+
+/// Runner-wide pool of warm containers and image cache
+pub struct WarmPool {
+    /// Images pulled at startup and never evicted
+    prepull_images: Vec<String>,
+
+    /// Images warm containers are kept for
+    warm_images: Vec<String>,
+
+    /// Warm containers kept per image
+    warm_pool_size: usize,
+
+    /// Evict images not used for this long
+    image_max_age: Option<Duration>,
+
+    /// Evict least recently used images while the image store is larger
+    image_max_size: Option<u64>,
+
+    /// Directory warm containers' workspaces are created in
+    warm_dir: PathBuf,
+
+    /// Idle warm containers by image
+    idle: tokio::sync::Mutex<HashMap<String, Vec<WarmContainer>>>,
+
+    /// Last use of the images this runner ran, by image ID
+    last_used: Mutex<HashMap<String, Instant>>,
+
+    /// IDs of configured images, never evicted
+    pinned: Mutex<HashSet<String>>,
+
+    /// Wakes the maintenance loop when a warm container was taken
+    refill: Notify,
+}
+
+impl WarmPool {
+    /// Creates the pool described by the runner configuration
+    pub fn new(config: &Config) -> Arc<Self> {
+        Arc::new(Self {
+            prepull_images: config.prepull_images.clone(),
+            warm_images: config.warm_images.clone(),
+            warm_pool_size: config.warm_pool_size,
+            image_max_age: config.image_cache_max_age,
+            image_max_size: config.image_cache_max_size,
+            warm_dir: config.workspace_base.join(WARM_DIR),
+            idle: tokio::sync::Mutex::new(HashMap::new()),
+            last_used: Mutex::new(HashMap::new()),
+            pinned: Mutex::new(HashSet::new()),
+            refill: Notify::new(),
+        })
+    }
+
+    /// Pre-pulls images, then keeps the pool filled and the cache bounded
+    ///
+    /// Runs for the lifetime of the runner.
+    pub async fn run_maintenance(self: Arc<Self>) {
+        self.remove_leftovers().await;
+        self.prepull().await;
+
+        loop {
+            self.fill().await;
+            self.evict().await;
+
+            tokio::select! {
+                _ = tokio::time::sleep(MAINTENANCE_INTERVAL) => {}
+                _ = self.refill.notified() => {}
+            }
+        }
+    }
+
+    /// Takes a warm container for a job
+    ///
+    /// # Arguments
+    /// * `image` - Image the job needs a container for
+    /// * `job_workspace` - The job's workspace; none is taken if it already exists
+    ///
+    /// # Returns
+    /// Name of the adopted container, or None if none was available
+    pub async fn take(&self, image: &str, job_workspace: &str) -> Option<String> {
+        // A workspace taken over from a resumed job cannot be swapped for a warm one
+        if tokio::fs::try_exists(job_workspace).await.unwrap_or(true) {
+            return None;
+        }
+
+        let warm = self.idle.lock().await.get_mut(image)?.pop()?;
+        self.refill.notify_one();
+
+        match adopt(&warm, Path::new(job_workspace)).await {
+            Ok(()) => {
+                info!("Adopted warm container {} for image {}", warm.name, image);
+                self.touch(image).await;
+                Some(warm.name)
+            }
+            Err(e) => {
+                warn!("Failed to adopt warm container {}: {:#}", warm.name, e);
+                discard(&warm).await;
+                None
+            }
+        }
+    }
+
+    /// Records that a job used an image, for eviction
+    pub async fn touch(&self, image: &str) {
+        if self.image_max_age.is_none() && self.image_max_size.is_none() {
+            return;
+        }
+
+        match image_id(image).await {
+            Ok(id) => {
+                self.last_used.lock().unwrap().insert(id, Instant::now());
+            }
+            Err(e) => debug!("Failed to resolve image {}: {:#}", image, e),
+        }
+    }
+
+    /// Pulls the configured images and pins them in the cache
+    async fn prepull(&self) {
+        let images: HashSet<&String> = self
+            .prepull_images
+            .iter()
+            .chain(&self.warm_images)
+            .collect();
+
+        for image in images {
+            info!("Pre-pulling image {}", image);
+            let output = command()
+                .arg("pull")
+                .arg("--quiet")
+                .arg(image)
+                .output()
+                .await;
+
+            match output {
+                // Docker prints the reference rather than the ID of the image
+                Ok(output) if output.status.success() => match image_id(image).await {
+                    Ok(id) => {
+                        self.pinned.lock().unwrap().insert(id);
+                    }
+                    Err(e) => warn!("Failed to resolve image {}: {:#}", image, e),
+                },
+                Ok(output) => {
+                    let stderr = String::from_utf8_lossy(&output.stderr);
+                    warn!("Failed to pre-pull image {}: {}", image, stderr.trim());
+                }
+                Err(e) => warn!("Failed to pre-pull image {}: {}", image, e),
+            }
+        }
+    }
+
+    /// Starts warm containers until every warm image has `warm_pool_size`
+    async fn fill(&self) {
+        for image in &self.warm_images {
+            loop {
+                let idle = self.idle.lock().await.get(image).map_or(0, Vec::len);
+                if idle >= self.warm_pool_size {
+                    break;
+                }
+
+                match self.start_warm(image).await {
+                    Ok(warm) => {
+                        debug!("Warm container {} ready for image {}", warm.name, image);
+                        self.idle
+                            .lock()
+                            .await
+                            .entry(image.clone())
+                            .or_default()
+                            .push(warm);
+                    }
+                    Err(e) => {
+                        warn!("Failed to start warm container for {}: {:#}", image, e);
+                        break;
+                    }
+                }
+            }
+        }
+    }
+
+    /// Starts and pauses a container with its own workspace
+    async fn start_warm(&self, image: &str) -> Result<WarmContainer> {
+        let id = Uuid::new_v4();
+        let name = format!("{}{}", WARM_CONTAINER_PREFIX, id);
+        let workspace = self.warm_dir.join(id.to_string());
+
+        tokio::fs::create_dir_all(&workspace)
+            .await
+            .context("Failed to create warm workspace")?;
+        run_container(&name, image, &workspace.to_string_lossy()).await?;
+
+        // Pausing needs cgroup support; a running container works as well
+        let paused = podman(&["pause", &name]).await.is_ok();
+
+        Ok(WarmContainer {
+            name,
+            workspace,
+            paused,
+        })
+    }
+
+    /// Removes images the runner used that are too old or over the size limit
+    ///
+    /// Only images jobs of this runner used are removed, least recently used
+    /// first; configured images and images still in use are kept.
+    async fn evict(&self) {
+        if self.image_max_age.is_none() && self.image_max_size.is_none() {
+            return;
+        }
+
+        let images = match list_images().await {
+            Ok(images) => images,
+            Err(e) => {
+                warn!("Failed to list images: {:#}", e);
+                return;
+            }
+        };
+
+        let mut total: u64 = images.iter().map(|image| image.size).sum();
+        let candidates = {
+            let last_used = self.last_used.lock().unwrap();
+            let pinned = self.pinned.lock().unwrap();
+            let mut candidates: Vec<(Instant, &ImageInfo)> = images
+                .iter()
+                .filter(|image| !pinned.contains(&image.id))
+                .filter_map(|image| last_used.get(&image.id).map(|used| (*used, image)))
+                .collect();
+            candidates.sort_by_key(|(used, _)| *used);
+            candidates
+        };
+
+        for (used, image) in candidates {
+            let too_old = self
+                .image_max_age
+                .is_some_and(|max_age| used.elapsed() > max_age);
+            let too_big = self.image_max_size.is_some_and(|max_size| total > max_size);
+            if !too_old && !too_big {
+                continue;
+            }
+
+            match podman(&["rmi", &image.id]).await {
+                Ok(()) => {
+                    info!("Evicted image {} ({} bytes)", image.id, image.size);
+                    total = total.saturating_sub(image.size);
+                    self.last_used.lock().unwrap().remove(&image.id);
+                }
+                Err(e) => debug!("Kept image {}: {:#}", image.id, e),
+            }
+        }
+    }
+
+    /// Removes warm containers and workspaces left by a previous run
+    async fn remove_leftovers(&self) {
+        let filter = format!("name=^{}", WARM_CONTAINER_PREFIX);
+        let output = command()
+            .args(["ps", "-a", "-q", "--filter", &filter])
+            .output()
+            .await;
+
+        if let Ok(output) = output {
+            for id in String::from_utf8_lossy(&output.stdout).split_whitespace() {
+                debug!("Removing leftover warm container {}", id);
+                let _ = podman(&["rm", "-f", id]).await;
+            }
+        }
+
+        match tokio::fs::remove_dir_all(&self.warm_dir).await {
+            Ok(()) => {}
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => warn!("Failed to remove leftover warm workspaces: {}", e),
+        }
+    }
+}
+
+/// Hands a warm container over to a job
+async fn adopt(warm: &WarmContainer, job_workspace: &Path) -> Result<()> {
+    if tokio::fs::try_exists(job_workspace).await.unwrap_or(true) {
+        anyhow::bail!("Workspace {} already exists", job_workspace.display());
+    }
+
+    tokio::fs::rename(&warm.workspace, job_workspace)
+        .await
+        .context("Failed to move warm workspace")?;
+
+    if warm.paused {
+        podman(&["unpause", &warm.name]).await?;
+    }
+
+    Ok(())
+}
+
+/// Removes a warm container and whatever is left of its workspace
+async fn discard(warm: &WarmContainer) {
+    if let Err(e) = podman(&["rm", "-f", &warm.name]).await {
+        warn!("Failed to remove warm container {}: {:#}", warm.name, e);
+    }
+    let _ = tokio::fs::remove_dir_all(&warm.workspace).await;
+}
+
+/// Resolves an image reference to its ID
+async fn image_id(image: &str) -> Result<String> {
+    let output = command()
+        .args(["image", "inspect", "--format", "{{.Id}}", image])
+        .output()
+        .await
+        .context("Failed to execute image inspect")?;
+
+    if !output.status.success() {
+        anyhow::bail!("{}", String::from_utf8_lossy(&output.stderr).trim());
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// Lists local images with their sizes
+async fn list_images() -> Result<Vec<ImageInfo>> {
+    inspect_images(None).await
+}
+
+/// Runs a container runtime command, failing with its stderr
+async fn podman(args: &[&str]) -> Result<()> {
+    let output = command()
+        .args(args)
+        .output()
+        .await
+        .with_context(|| format!("Failed to execute {}", args[0]))?;
+
+    if !output.status.success() {
+        anyhow::bail!(
+            "{} failed: {}",
+            args[0],
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_podman_images() {
+        let output = r#"[
+            {"Id": "abc123", "Names": ["docker.io/library/alpine:latest"], "Size": 8123456, "Created": 1700000000},
+            {"Id": "def456", "Names": null, "Size": 42}
+        ]"#;
+
+        let images: Vec<ImageInfo> = serde_json::from_str(output).unwrap();
+        assert_eq!(images.len(), 2);
+        assert_eq!(images[0].id, "abc123");
+        assert_eq!(images[0].size, 8123456);
+        assert_eq!(images[1].size, 42);
+    }
+}
@@ -0,0 +1,70 @@
+//! Configuration hot reload
+//!
+//! Watches the configuration file and publishes the settings that can
+//! change without a restart (see `Config::reload`): poll and log intervals,
+//! timeouts, the default image, the image allowlists and `max_parallel_jobs`.
+//! Jobs pick up the settings in effect when they start. A file that fails
+//! to load or validate is reported and the running settings are kept.
+
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime};
+
+use tokio::sync::watch;
+use tracing::{info, warn};
+
+use crate::config::Config;
+
+/// How often the configuration file is checked for changes
+pub const RELOAD_CHECK_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Reloads the configuration whenever the file at `path` changes
+///
+/// Environment variables still override the file on reload. Runs until the
+/// receivers of `config` are gone.
+pub async fn watch_config(path: PathBuf, config: watch::Sender<Config>) {
+    let modified = |path: &PathBuf| -> Option<SystemTime> {
+        std::fs::metadata(path).and_then(|m| m.modified()).ok()
+    };
+    let mut last_modified = modified(&path);
+    let mut ticker = tokio::time::interval(RELOAD_CHECK_INTERVAL);
+
+    while !config.is_closed() {
+        ticker.tick().await;
+
+        let current_modified = modified(&path);
+        if current_modified == last_modified {
+            continue;
+        }
+        last_modified = current_modified;
+
+        let new = match Config::load(Some(&path)).and_then(|new| new.validate().map(|_| new)) {
+            Ok(new) => new,
+            Err(e) => {
+                warn!(
+                    "Not reloading {}, keeping the running configuration: {:#}",
+                    path.display(),
+                    e
+                );
+                continue;
+            }
+        };
+
+        let (reloaded, ignored) = config.borrow().reload(&new);
+        if !ignored.is_empty() {
+            warn!(
+                "Changes to {} take effect after a restart",
+                ignored.join(", ")
+            );
+        }
+        let changed = config.send_if_modified(|current| {
+            if *current == reloaded {
+                return false;
+            }
+            *current = reloaded;
+            true
+        });
+        if changed {
+            info!("Configuration reloaded from {}", path.display());
+        }
+    }
+}
@@ -0,0 +1,69 @@
+//! Runner load
+//!
+//! Measures the load reported to the orchestrator with each heartbeat: the
+//! jobs running against the configured parallelism, and the host's CPU and
+//! memory load as read from `/proc`. CPU and memory are left out on hosts
+//! without `/proc`.
+
+use rivet_core::domain::runner::RunnerLoad;
+
+/// Measures the current load of the runner
+///
+/// # Arguments
+/// * `running_jobs` - Jobs the runner is executing
+/// * `max_parallel_jobs` - Jobs the runner executes at most at once
+pub fn current_load(running_jobs: usize, max_parallel_jobs: usize) -> RunnerLoad {
+    let cpus = std::thread::available_parallelism()
+        .map(|n| n.get())
+        .unwrap_or(1);
+
+    RunnerLoad {
+        running_jobs: running_jobs as u32,
+        max_parallel_jobs: max_parallel_jobs as u32,
+        cpu_load: std::fs::read_to_string("/proc/loadavg")
+            .ok()
+            .and_then(|s| parse_loadavg(&s))
+            .map(|load| load / cpus as f64),
+        memory_load: std::fs::read_to_string("/proc/meminfo")
+            .ok()
+            .and_then(|s| parse_meminfo(&s)),
+    }
+}
+
+/// One-minute load average from the contents of `/proc/loadavg`
+fn parse_loadavg(s: &str) -> Option<f64> {
+    s.split_whitespace().next()?.parse().ok()
+}
+
+/// Fraction of memory in use from the contents of `/proc/meminfo`
+fn parse_meminfo(s: &str) -> Option<f64> {
+    let field = |name: &str| -> Option<f64> {
+        s.lines()
+            .find_map(|line| line.strip_prefix(name)?.strip_prefix(':'))?
+            .split_whitespace()
+            .next()?
+            .parse()
+            .ok()
+    };
+
+    let total = field("MemTotal")?;
+    let available = field("MemAvailable")?;
+    (total > 0.0).then(|| (1.0 - available / total).clamp(0.0, 1.0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_proc_files() {
+        assert_eq!(parse_loadavg("0.52 0.58 0.59 1/467 12345\n"), Some(0.52));
+        assert_eq!(parse_loadavg(""), None);
+
+        let meminfo = "MemTotal:       16000000 kB\n\
+                       MemFree:         2000000 kB\n\
+                       MemAvailable:    4000000 kB\n";
+        assert_eq!(parse_meminfo(meminfo), Some(0.75));
+        assert_eq!(parse_meminfo("MemTotal: 100 kB\n"), None);
+    }
+}
@@ -4,6 +4,7 @@
 //! coordinating job execution. It manages the lifecycle of jobs
 //! from claiming to completion.
 
+pub mod load;
 pub mod poller;
 
 pub use poller::JobPoller;
@@ -1,59 +1,230 @@
 //! Job poller
 //!
 //! Polls the orchestrator for scheduled jobs and executes them.
-//! Each job runs in its own task with a context containing logs, workspace, and container stack.
+//! Each job runs in its own detached task with a context containing logs, workspace, and
+//! container stack, so polling continues while jobs run as long as there is capacity left.
+//!
+//! By default jobs are received through long polling (`next_job`), which
+//! returns as soon as a job is queued. Orchestrators without the long-poll
+//! endpoint are detected on the first request and polled on an interval.
+//!
+//! The configuration is followed as it is reloaded: each job runs with the
+//! settings in effect when it starts, and capacity follows `max_parallel_jobs`.
+//!
+//! Logs go through the job's spool (see `spool`): what a job logs is written
+//! to disk every second and sent from there, and what could not be sent by
+//! the time the job finishes is sent in the background.
 
 use anyhow::{Context as AnyhowContext, Result};
 use rivet_core::domain::job::JobResult;
+use rivet_core::dto::job::JobExecutionInfo;
+use rivet_core::dto::log::split_log_batches;
+use rivet_core::telemetry;
+use std::path::PathBuf;
 use std::sync::Arc;
-use tokio::sync::Semaphore;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore, oneshot, watch};
+use tokio::task::JoinSet;
 use tokio::time::{self, Duration};
-use tracing::{debug, error, info, warn};
+use tracing::{Instrument, debug, error, info, info_span, warn};
 use uuid::Uuid;
 
 use crate::config::Config;
 use crate::context::Context;
+use crate::debug;
 use crate::lua::executor::LuaExecutor;
-use rivet_client::OrchestratorClient;
+use crate::lua::modules::JobMetadata;
+use crate::pool::WarmPool;
+use crate::scheduler::load::current_load;
+use crate::spool::{LogSpool, SPOOL_FLUSH_INTERVAL};
+use crate::state::RunnerState;
+use crate::workspace::{self, WorkspaceManager, dir_size};
+use rivet_client::{ClientError, OrchestratorApi};
+
+/// How often a running job's workspace is measured against its quota
+const QUOTA_CHECK_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Attempts at sending a finished job's last logs before leaving them to
+/// the background drain of the spool
+const FINAL_LOG_SEND_ATTEMPTS: u32 = 3;
+
+/// Delay between attempts at sending a finished job's last logs
+const FINAL_LOG_SEND_RETRY: Duration = Duration::from_secs(2);
 
 /// Job poller that continuously polls for and executes jobs
 pub struct JobPoller {
-    config: Config,
-    client: Arc<OrchestratorClient>,
+    config: watch::Receiver<Config>,
+    client: Arc<dyn OrchestratorApi>,
     semaphore: Arc<Semaphore>,
+    running: Arc<AtomicUsize>,
+    pool: Arc<WarmPool>,
+    workspaces: Arc<WorkspaceManager>,
+    spool: Arc<LogSpool>,
+    state: Arc<RunnerState>,
+}
+
+/// Log sender of a running job, see `JobPoller::spawn_log_sender`
+struct LogSender {
+    stop: oneshot::Sender<()>,
+    /// Resolves to whether all of the job's logs were sent
+    handle: tokio::task::JoinHandle<bool>,
+}
+
+/// Slot a job holds while it runs: its semaphore permit and its place in
+/// the running count reported with heartbeats
+struct JobSlot {
+    _permit: OwnedSemaphorePermit,
+    running: Arc<AtomicUsize>,
+}
+
+impl JobSlot {
+    fn new(permit: OwnedSemaphorePermit, running: &Arc<AtomicUsize>) -> Self {
+        running.fetch_add(1, Ordering::Relaxed);
+        Self {
+            _permit: permit,
+            running: Arc::clone(running),
+        }
+    }
+}
+
+impl Drop for JobSlot {
+    fn drop(&mut self) {
+        self.running.fetch_sub(1, Ordering::Relaxed);
+    }
 }
 
 impl JobPoller {
     /// Creates a new job poller
-    pub fn new(config: Config, client: Arc<OrchestratorClient>) -> Self {
-        let semaphore = Arc::new(Semaphore::new(config.max_parallel_jobs));
+    ///
+    /// # Arguments
+    /// * `config` - Configuration, updated as it is reloaded
+    /// * `client` - Orchestrator transport for the job lifecycle
+    pub fn new(config: watch::Receiver<Config>, client: Arc<dyn OrchestratorApi>) -> Self {
+        let current = config.borrow().clone();
+        let semaphore = Arc::new(Semaphore::new(current.max_parallel_jobs));
+        let pool = WarmPool::new(&current);
+        let workspaces = WorkspaceManager::new(&current);
+        let spool = LogSpool::new(&current);
+        let state = RunnerState::open(&current);
         Self {
             config,
             client,
             semaphore,
+            running: Arc::new(AtomicUsize::new(0)),
+            pool,
+            workspaces,
+            spool,
+            state,
         }
     }
 
+    /// Settings in effect now
+    fn config(&self) -> Config {
+        self.config.borrow().clone()
+    }
+
     /// Starts the polling loop
+    ///
+    /// Jobs left behind by an earlier run of the runner are recovered first.
     pub async fn run(&self) -> Result<()> {
+        self.state.recover(self.client.as_ref()).await;
+
+        let _heartbeat_handle = self.start_heartbeat_loop();
+        let _capacity_handle = tokio::spawn(follow_capacity(
+            self.config.clone(),
+            Arc::clone(&self.semaphore),
+        ));
+        let _pool_handle = tokio::spawn(Arc::clone(&self.pool).run_maintenance());
+        let _sweeper_handle = tokio::spawn(Arc::clone(&self.workspaces).run_sweeper());
+        let _spool_handle = tokio::spawn(
+            Arc::clone(&self.spool)
+                .run_recovery(Arc::clone(&self.client), self.config().log_send_interval),
+        );
+        let mut tasks = JoinSet::new();
+
+        if !self.config().long_poll_wait.is_zero() {
+            self.run_long_poll(&mut tasks).await;
+        }
+
+        self.run_interval_poll(&mut tasks).await
+    }
+
+    /// Receives jobs through long polling
+    ///
+    /// Returns only when the orchestrator does not support long polling.
+    async fn run_long_poll(&self, tasks: &mut JoinSet<()>) {
+        let Config {
+            runner_id,
+            long_poll_wait,
+            ..
+        } = self.config();
         info!(
-            "Starting job poller (interval: {:?})",
-            self.config.poll_interval
+            "Starting job poller (long poll, wait: {:?})",
+            long_poll_wait
         );
 
-        let _heartbeat_handle = self.start_heartbeat_loop();
+        loop {
+            // Only ask for a job when there is capacity to run it
+            let permit = match Arc::clone(&self.semaphore).acquire_owned().await {
+                Ok(permit) => permit,
+                Err(_) => return,
+            };
+            reap_finished(tasks);
+
+            debug!("Waiting for the next job");
+
+            match self.client.next_job(&runner_id, long_poll_wait).await {
+                Ok(Some(exec_info)) => {
+                    self.spawn_claimed_job_task(tasks, exec_info, permit);
+                }
+                Ok(None) => {
+                    debug!("No jobs available");
+                }
+                Err(e) if long_poll_unsupported(&e) => {
+                    warn!(
+                        "Orchestrator does not support long polling ({}), falling back to interval polling",
+                        e
+                    );
+                    return;
+                }
+                Err(e) => {
+                    error!("Error while waiting for a job: {:#}", e);
+                    drop(permit);
+                    time::sleep(self.config().poll_interval).await;
+                }
+            }
+        }
+    }
 
-        let mut interval = time::interval(self.config.poll_interval);
+    /// Polls for scheduled jobs on a fixed interval
+    async fn run_interval_poll(&self, tasks: &mut JoinSet<()>) -> Result<()> {
+        let mut poll_interval = self.config().poll_interval;
+        info!("Starting job poller (interval: {:?})", poll_interval);
+
+        let mut interval = time::interval(poll_interval);
 
         loop {
             interval.tick().await;
 
+            let reloaded = self.config().poll_interval;
+            if reloaded != poll_interval {
+                info!("Poll interval changed to {:?}", reloaded);
+                poll_interval = reloaded;
+                interval = time::interval_at(time::Instant::now() + poll_interval, poll_interval);
+            }
+            reap_finished(tasks);
+
+            if self.semaphore.available_permits() == 0 {
+                debug!("Max parallel jobs reached, skipping poll");
+                continue;
+            }
+
             debug!("Polling for scheduled jobs");
 
-            match self.poll_and_execute_once().await {
-                Ok(executed) => {
-                    if executed > 0 {
-                        info!("Executed {} job(s) this cycle", executed);
+            match self.poll_and_execute_once(tasks).await {
+                Ok(started) => {
+                    if started > 0 {
+                        info!("Started {} job(s) this cycle", started);
                     }
                 }
                 Err(e) => {
@@ -64,7 +235,10 @@ impl JobPoller {
     }
 
     /// Performs a single poll cycle
-    async fn poll_and_execute_once(&self) -> Result<usize> {
+    ///
+    /// Starts as many scheduled jobs as there is capacity for and returns
+    /// without waiting for them to finish.
+    async fn poll_and_execute_once(&self, tasks: &mut JoinSet<()>) -> Result<usize> {
         let jobs = self
             .client
             .list_scheduled_jobs()
@@ -78,53 +252,86 @@ impl JobPoller {
 
         info!("Found {} job(s) to execute", jobs.len());
 
-        let mut handles = Vec::new();
+        let mut started = 0;
 
         for job in jobs {
             let job_id = job.id;
 
             // Try to acquire semaphore permit, skip if at max capacity
             if let Ok(permit) = self.semaphore.clone().try_acquire_owned() {
-                let handle = self.spawn_job_task(job_id, permit);
-                handles.push(handle);
+                self.spawn_job_task(tasks, job_id, permit);
+                started += 1;
             } else {
                 debug!("Max parallel jobs reached, skipping job {} for now", job_id);
             }
         }
 
-        let num_jobs = handles.len();
+        Ok(started)
+    }
 
-        for handle in handles {
-            if let Err(e) = handle.await {
-                warn!("Job task panicked: {}", e);
+    /// Spawns a task to execute a single job
+    fn spawn_job_task(&self, tasks: &mut JoinSet<()>, job_id: Uuid, permit: OwnedSemaphorePermit) {
+        let client = Arc::clone(&self.client);
+        let config = self.config();
+        let pool = Arc::clone(&self.pool);
+        let workspaces = Arc::clone(&self.workspaces);
+        let spool = Arc::clone(&self.spool);
+        let state = Arc::clone(&self.state);
+        let slot = JobSlot::new(permit, &self.running);
+
+        tasks.spawn(async move {
+            if let Err(e) =
+                Self::execute_job(job_id, config, client, pool, workspaces, spool, state).await
+            {
+                error!("Failed to execute job {}: {:#}", job_id, e);
             }
-        }
-
-        Ok(num_jobs)
+            // Release the slot once the job is done
+            drop(slot);
+        });
     }
 
-    /// Spawns a task to execute a single job
-    fn spawn_job_task(
+    /// Spawns a task to run a job already claimed through long polling
+    fn spawn_claimed_job_task(
         &self,
-        job_id: Uuid,
-        _permit: tokio::sync::OwnedSemaphorePermit,
-    ) -> tokio::task::JoinHandle<()> {
+        tasks: &mut JoinSet<()>,
+        exec_info: JobExecutionInfo,
+        permit: OwnedSemaphorePermit,
+    ) {
         let client = Arc::clone(&self.client);
-        let config = self.config.clone();
+        let config = self.config();
+        let pool = Arc::clone(&self.pool);
+        let workspaces = Arc::clone(&self.workspaces);
+        let spool = Arc::clone(&self.spool);
+        let state = Arc::clone(&self.state);
+        let job_id = exec_info.job_id;
+        let slot = JobSlot::new(permit, &self.running);
 
-        tokio::spawn(async move {
-            if let Err(e) = Self::execute_job(job_id, config, client).await {
+        info!(
+            "Assigned job {} (pipeline {})",
+            exec_info.job_id, exec_info.pipeline_id
+        );
+
+        tasks.spawn(async move {
+            if let Err(e) =
+                Self::run_claimed_job(exec_info, config, client, pool, workspaces, spool, state)
+                    .await
+            {
                 error!("Failed to execute job {}: {:#}", job_id, e);
             }
-            // Permit is automatically released when dropped
-        })
+            // Release the slot once the job is done
+            drop(slot);
+        });
     }
 
-    /// Executes a single job with log streaming
+    /// Claims and executes a single job with log streaming
     async fn execute_job(
         job_id: Uuid,
         config: Config,
-        client: Arc<OrchestratorClient>,
+        client: Arc<dyn OrchestratorApi>,
+        pool: Arc<WarmPool>,
+        workspaces: Arc<WorkspaceManager>,
+        spool: Arc<LogSpool>,
+        state: Arc<RunnerState>,
     ) -> Result<()> {
         info!("Starting execution of job {}", job_id);
 
@@ -139,52 +346,182 @@ impl JobPoller {
             exec_info.job_id, exec_info.pipeline_id
         );
 
+        Self::run_claimed_job(exec_info, config, client, pool, workspaces, spool, state).await
+    }
+
+    /// Executes a job this runner has claimed
+    ///
+    /// The job runs in a span continuing the trace of the request that
+    /// launched it. Periodic log and lease calls are left out of the trace.
+    async fn run_claimed_job(
+        exec_info: JobExecutionInfo,
+        config: Config,
+        client: Arc<dyn OrchestratorApi>,
+        pool: Arc<WarmPool>,
+        workspaces: Arc<WorkspaceManager>,
+        spool: Arc<LogSpool>,
+        state: Arc<RunnerState>,
+    ) -> Result<()> {
+        let span = info_span!(
+            "job",
+            otel.kind = "consumer",
+            job_id = %exec_info.job_id,
+            pipeline_id = %exec_info.pipeline_id,
+        );
+        if let Some(traceparent) = &exec_info.traceparent {
+            telemetry::set_parent(&span, traceparent);
+        }
+
+        Self::run_job(exec_info, config, client, pool, workspaces, spool, state)
+            .instrument(span)
+            .await
+    }
+
+    /// Runs a claimed job, inside the span `run_claimed_job` opens
+    async fn run_job(
+        exec_info: JobExecutionInfo,
+        config: Config,
+        client: Arc<dyn OrchestratorApi>,
+        pool: Arc<WarmPool>,
+        workspaces: Arc<WorkspaceManager>,
+        spool: Arc<LogSpool>,
+        state: Arc<RunnerState>,
+    ) -> Result<()> {
+        let job_id = exec_info.job_id;
+        let claim_token = exec_info.claim_token;
+        state.claim(job_id, claim_token).await;
+        workspaces.start(job_id).await;
+
+        // File inputs are written once the workspace exists, see below
+        let mut parameters = exec_info.parameters;
+        let input_files = workspace::take_input_files(&mut parameters);
+
         // Create execution context
-        let context = Context::new(job_id, config.workspace_base.clone(), exec_info.parameters);
-
-        // Start the default container
-        context.log_info("Starting default container...".to_string());
-        if let Err(e) = context
-            .container_manager
-            .start_default(&config.default_container_image)
-        {
+        let context = Context::new(
+            job_id,
+            config.workspace_base.clone(),
+            parameters,
+            exec_info.env.clone().into_iter().collect(),
+            exec_info.secrets,
+            &exec_info.secret_inputs,
+            config.command_timeout,
+            config.image_policy(),
+            pool,
+            Arc::clone(&state),
+        );
+
+        if let Some(original) = exec_info.resumed_from {
+            if workspaces.resume(original, job_id).await {
+                context.log_info(format!("Continuing from the workspace of job {}", original));
+            } else {
+                context.log_warning(format!(
+                    "Workspace of job {} is not kept on this runner, starting from an empty workspace",
+                    original
+                ));
+            }
+        }
+
+        // Start the default container, the pipeline's or else the runner's
+        let default_image = exec_info
+            .default_container_variants
+            .get(config.arch())
+            .or(exec_info.default_container.as_ref())
+            .unwrap_or(&config.default_container_image);
+        context.log_info(format!("Starting default container ({})...", default_image));
+        if let Err(e) = context.container_manager.start_default(default_image).await {
             error!("Failed to start default container: {:#}", e);
             context.log_error(format!("Failed to start default container: {}", e));
             let result = JobResult::failed(format!("Failed to start default container: {}", e));
-            let _ = client.complete_job(job_id, result).await;
+            let _ = client.complete_job(job_id, claim_token, result).await;
+            workspaces.finish(job_id, false).await;
+            state.release(job_id).await;
             return Err(e);
         }
         context.log_info("Default container started successfully".to_string());
 
+        let workspace_path = config.workspace_base.join(job_id.to_string());
+        if let Err(e) = workspace::write_input_files(&workspace_path, &input_files).await {
+            error!("Failed to write input files of job {}: {}", job_id, e);
+            context.log_error(format!("Failed to write input files: {}", e));
+            let result = JobResult::failed(format!("Failed to write input files: {}", e));
+            let _ = client.complete_job(job_id, claim_token, result).await;
+            workspaces.finish(job_id, false).await;
+            state.release(job_id).await;
+            return Err(e.into());
+        }
+
         // Spawn log sender task
+        spool.open(job_id, claim_token).await;
         let log_sender = Self::spawn_log_sender(
             job_id,
+            claim_token,
             Arc::clone(&context),
             Arc::clone(&client),
+            Arc::clone(&spool),
             config.log_send_interval,
         );
 
+        // Keep the job leased while it runs
+        let lease_renewer = Self::spawn_lease_renewer(
+            job_id,
+            config.runner_id.clone(),
+            Arc::clone(&client),
+            config.lease_renew_interval,
+        );
+
+        // Abort the job if its workspace outgrows the quota
+        let quota_watcher = config.workspace_quota.map(|quota| {
+            Self::spawn_quota_watcher(
+                Arc::clone(&context),
+                config.workspace_base.join(job_id.to_string()),
+                quota,
+            )
+        });
+
         // Create executor and execute pipeline
-        let executor = LuaExecutor::new(Arc::clone(&context));
-        let result = executor
-            .execute_pipeline(job_id, &exec_info.pipeline_source)
+        let executor = LuaExecutor::new(Arc::clone(&context), config.arch().to_string());
+        let job = JobMetadata {
+            id: job_id,
+            pipeline_id: exec_info.pipeline_id,
+            pipeline_name: exec_info.pipeline_name.clone(),
+            number: exec_info.number,
+            attempt: exec_info.attempt,
+            runner_id: Some(config.runner_id.clone()),
+            commit: exec_info.commit.clone(),
+            schedule: exec_info.schedule.clone(),
+        };
+        let mut result = executor
+            .execute_pipeline(
+                &job,
+                &exec_info.pipeline_source,
+                &exec_info.skip_stages,
+                exec_info.ctx.as_ref(),
+            )
             .await;
 
-        // Always abort log sender
-        log_sender.abort();
+        // Always abort lease renewer and quota watcher
+        lease_renewer.abort();
+        if let Some(quota_watcher) = quota_watcher {
+            quota_watcher.abort();
+        }
 
-        // Send remaining logs
-        let remaining_logs = context.drain_logs();
-        if !remaining_logs.is_empty() {
-            info!(
-                "Sending {} remaining logs for job {}",
-                remaining_logs.len(),
+        if let Some(reason) = context.container_manager.abort_reason() {
+            result = JobResult {
+                output: result.output,
+                ..JobResult::failed(reason)
+            };
+        }
+
+        // Let the log sender send remaining stage updates and logs
+        let _ = log_sender.stop.send(());
+        let drained = log_sender.handle.await.unwrap_or(false);
+        if !drained {
+            warn!(
+                "Could not send all logs of job {}, sending them in the background",
                 job_id
             );
-            if let Err(e) = client.send_logs(job_id, remaining_logs).await {
-                warn!("Failed to send final logs: {:#}", e);
-            }
         }
+        spool.close(job_id, drained).await;
 
         info!(
             "Job {} completed with status: {}",
@@ -192,57 +529,201 @@ impl JobPoller {
             if result.success { "success" } else { "failure" }
         );
 
+        // Keep the environment of a failed job for debug sessions
+        if !result.success && config.keep_failed_workspaces > 0 {
+            context
+                .container_manager
+                .snapshot(&debug::snapshot_repository(job_id))
+                .await;
+        }
+
         // Cleanup container
         context.log_info("Cleaning up container...".to_string());
-        if let Err(e) = context.container_manager.cleanup() {
+        if let Err(e) = context.container_manager.cleanup().await {
             warn!("Failed to cleanup container: {:#}", e);
             context.log_warning(format!("Failed to cleanup container: {}", e));
         } else {
             context.log_info("Container cleaned up successfully".to_string());
         }
 
-        // Report completion
-        client
-            .complete_job(job_id, result)
-            .await
-            .context("Failed to complete job")?;
+        if let Some(kept) = workspaces.finish(job_id, result.success).await {
+            info!(
+                "Workspace of failed job {} kept at {}",
+                job_id,
+                kept.display()
+            );
+        }
+
+        // Report completion; the job is done with either way
+        let completed = client.complete_job(job_id, claim_token, result).await;
+        state.release(job_id).await;
+        completed.context("Failed to complete job")?;
 
         Ok(())
     }
 
-    /// Spawns a background task to send logs periodically
+    /// Spawns a background task moving the job's logs to its spool every
+    /// `SPOOL_FLUSH_INTERVAL` and sending them every `interval`
+    ///
+    /// Once stopped, the task sends what is left, retrying a few times.
     fn spawn_log_sender(
         job_id: Uuid,
+        claim_token: Option<u64>,
         context: Arc<Context>,
-        client: Arc<OrchestratorClient>,
+        client: Arc<dyn OrchestratorApi>,
+        spool: Arc<LogSpool>,
+        interval: Duration,
+    ) -> LogSender {
+        let (stop, mut stopped) = oneshot::channel();
+
+        let handle = tokio::spawn(async move {
+            let mut flush_ticker = time::interval(SPOOL_FLUSH_INTERVAL);
+            let mut send_ticker = time::interval(interval);
+
+            loop {
+                tokio::select! {
+                    _ = &mut stopped => break,
+                    _ = flush_ticker.tick() => {
+                        Self::spool_logs(job_id, claim_token, &context, &spool, client.as_ref()).await;
+                    }
+                    _ = send_ticker.tick() => {
+                        Self::send_stage_updates(job_id, &context, client.as_ref()).await;
+                        Self::spool_logs(job_id, claim_token, &context, &spool, client.as_ref()).await;
+                        match spool.drain(job_id, client.as_ref()).await {
+                            Ok(0) => debug!("No logs to send for job {}", job_id),
+                            Ok(sent) => debug!("Sent {} logs for job {}", sent, job_id),
+                            Err(e) => error!("{:#}", e),
+                        }
+                    }
+                }
+            }
+
+            Self::send_stage_updates(job_id, &context, client.as_ref()).await;
+            Self::spool_logs(job_id, claim_token, &context, &spool, client.as_ref()).await;
+            for attempt in 1..=FINAL_LOG_SEND_ATTEMPTS {
+                match spool.drain(job_id, client.as_ref()).await {
+                    Ok(sent) => {
+                        if sent > 0 {
+                            info!("Sent {} remaining logs for job {}", sent, job_id);
+                        }
+                        return true;
+                    }
+                    Err(e) => {
+                        warn!("Failed to send final logs: {:#}", e);
+                        if attempt < FINAL_LOG_SEND_ATTEMPTS {
+                            time::sleep(FINAL_LOG_SEND_RETRY).await;
+                        }
+                    }
+                }
+            }
+            false
+        });
+
+        LogSender { stop, handle }
+    }
+
+    /// Moves the entries the job logged since the last call to its spool
+    ///
+    /// Entries that cannot be written, e.g. on a full disk, are sent
+    /// straight away instead, and dropped if that fails too.
+    async fn spool_logs(
+        job_id: Uuid,
+        claim_token: Option<u64>,
+        context: &Context,
+        spool: &LogSpool,
+        client: &dyn OrchestratorApi,
+    ) {
+        let logs = context.drain_logs();
+        if let Err(e) = spool.append(job_id, &logs).await {
+            error!("Failed to spool logs of job {}: {:#}", job_id, e);
+            for batch in split_log_batches(logs) {
+                if let Err(e) = client.send_logs(job_id, claim_token, batch).await {
+                    error!("Failed to send logs for job {}: {:#}", job_id, e);
+                }
+            }
+        }
+    }
+
+    /// Reports the stage progress recorded since the last call
+    async fn send_stage_updates(job_id: Uuid, context: &Context, client: &dyn OrchestratorApi) {
+        let updates = context.drain_stage_updates();
+        if updates.is_empty() {
+            return;
+        }
+
+        debug!("Sending {} stage updates for job {}", updates.len(), job_id);
+
+        if let Err(e) = client.send_stage_updates(job_id, updates).await {
+            warn!("Failed to send stage updates for job {}: {:#}", job_id, e);
+        }
+    }
+
+    /// Spawns a background task that renews the job lease periodically
+    ///
+    /// Stops renewing when the orchestrator does not know about leases or
+    /// the job is no longer running on this runner.
+    fn spawn_lease_renewer(
+        job_id: Uuid,
+        runner_id: String,
+        client: Arc<dyn OrchestratorApi>,
         interval: Duration,
     ) -> tokio::task::JoinHandle<()> {
+        let ttl = interval * 3;
+
         tokio::spawn(async move {
             let mut ticker = time::interval(interval);
 
             loop {
                 ticker.tick().await;
 
-                let logs = context.drain_logs();
+                debug!("Renewing lease on job {}", job_id);
 
-                if logs.is_empty() {
-                    debug!("No logs to send for job {}", job_id);
-                    continue;
+                match client.renew_job_lease(job_id, &runner_id, ttl).await {
+                    Ok(_) => {}
+                    Err(e) if matches!(e.status(), Some(400 | 404 | 405 | 501)) => {
+                        warn!("Stopped renewing lease on job {}: {}", job_id, e);
+                        return;
+                    }
+                    Err(e) => {
+                        warn!("Failed to renew lease on job {}: {:#}", job_id, e);
+                    }
                 }
+            }
+        })
+    }
 
-                debug!("Sending {} logs for job {}", logs.len(), job_id);
+    /// Spawns a background task aborting the job once its workspace exceeds `quota` bytes
+    fn spawn_quota_watcher(
+        context: Arc<Context>,
+        workspace: PathBuf,
+        quota: u64,
+    ) -> tokio::task::JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut ticker = time::interval(QUOTA_CHECK_INTERVAL);
 
-                if let Err(e) = client.send_logs(job_id, logs).await {
-                    error!("Failed to send logs for job {}: {:#}", job_id, e);
+            loop {
+                ticker.tick().await;
+
+                let used = dir_size(workspace.clone()).await;
+                if used > quota {
+                    let reason = format!(
+                        "Workspace uses {} bytes, over its quota of {} bytes",
+                        used, quota
+                    );
+                    context.log_error(reason.clone());
+                    context.container_manager.abort(reason);
+                    return;
                 }
             }
         })
     }
 
-    /// Starts a background task to send heartbeats
+    /// Starts a background task to send heartbeats, reporting the runner's load
     fn start_heartbeat_loop(&self) -> tokio::task::JoinHandle<()> {
         let client = Arc::clone(&self.client);
-        let runner_id = self.config.runner_id.clone();
+        let config = self.config.clone();
+        let runner_id = self.config().runner_id;
+        let running = Arc::clone(&self.running);
         let heartbeat_interval = Duration::from_secs(30);
 
         tokio::spawn(async move {
@@ -253,10 +734,59 @@ impl JobPoller {
 
                 debug!("Sending heartbeat");
 
-                if let Err(e) = client.send_heartbeat(&runner_id).await {
+                let max_parallel_jobs = config.borrow().max_parallel_jobs;
+                let load = current_load(running.load(Ordering::Relaxed), max_parallel_jobs);
+                if let Err(e) = client.send_heartbeat(&runner_id, Some(load)).await {
                     warn!("Failed to send heartbeat: {:#}", e);
                 }
             }
         })
     }
 }
+
+/// Resizes the job slots as `max_parallel_jobs` is reloaded
+///
+/// Slots are added right away. Running jobs keep theirs when capacity
+/// shrinks: the excess is retired as they finish, and no job starts until
+/// the runner is back under the new limit.
+async fn follow_capacity(mut config: watch::Receiver<Config>, semaphore: Arc<Semaphore>) {
+    let mut capacity = config.borrow().max_parallel_jobs;
+
+    while config.changed().await.is_ok() {
+        let wanted = config.borrow_and_update().max_parallel_jobs;
+        if wanted == capacity {
+            continue;
+        }
+
+        info!("Max parallel jobs changed from {} to {}", capacity, wanted);
+        if wanted > capacity {
+            semaphore.add_permits(wanted - capacity);
+        } else {
+            let excess = (capacity - wanted) as u32;
+            let semaphore = Arc::clone(&semaphore);
+            tokio::spawn(async move {
+                if let Ok(permits) = semaphore.acquire_many_owned(excess).await {
+                    permits.forget();
+                }
+            });
+        }
+        capacity = wanted;
+    }
+}
+
+/// Collects finished job tasks, logging the ones that panicked
+fn reap_finished(tasks: &mut JoinSet<()>) {
+    while let Some(result) = tasks.try_join_next() {
+        if let Err(e) = result {
+            warn!("Job task panicked: {}", e);
+        }
+    }
+}
+
+/// Whether a long-poll error means the orchestrator predates the endpoint
+///
+/// Older orchestrators route `/api/v1/jobs/next` to `/api/v1/jobs/{id}` and reject
+/// it as a bad or unknown id; gRPC servers answer `UNIMPLEMENTED`.
+fn long_poll_unsupported(err: &ClientError) -> bool {
+    matches!(err.status(), Some(400 | 404 | 405 | 501))
+}
@@ -0,0 +1,413 @@
+//! Log spool
+//!
+//! Log entries go through disk on their way to the orchestrator. Every
+//! `SPOOL_FLUSH_INTERVAL` the log sender moves the entries a job logged from
+//! memory to an append-only JSON Lines file named after the job under
+//! `SPOOL_DIR`, and every `log_send_interval` it sends the entries the
+//! orchestrator has not acknowledged yet. A cursor file next to the spool
+//! records how far that is, so:
+//!
+//! - an unreachable orchestrator costs disk rather than memory, and sending
+//!   resumes where it stopped once it is back;
+//! - spools left by a crashed or restarted runner are drained in the
+//!   background when it starts again, then deleted.
+//!
+//! Delivery is at least once: a batch sent right before a crash, whose
+//! cursor was not moved yet, is sent again. Batches the orchestrator rejects
+//! for good (a 4xx other than 408 or 429) are dropped rather than retried
+//! forever; among them, batches sent under a claim the job no longer has.
+//! The claim token is kept in a third file, so that recovered spools are
+//! sent under the claim their entries were logged under.
+
+use anyhow::Context as AnyhowContext;
+use rivet_client::{ClientError, OrchestratorApi};
+use rivet_core::domain::log::LogEntry;
+use rivet_core::dto::log::split_log_batches;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncSeekExt, AsyncWriteExt};
+use tracing::{debug, info, warn};
+use uuid::Uuid;
+
+use crate::config::Config;
+
+/// Directory (under the workspace base) holding the log spools of jobs
+pub const SPOOL_DIR: &str = ".rivet-logs";
+
+/// How often the entries a job logged are moved from memory to its spool
+pub const SPOOL_FLUSH_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Most bytes of a spool read and sent at once
+const SPOOL_READ_CHUNK: u64 = 4 * 1024 * 1024;
+
+/// Extension of spool files
+const SPOOL_EXTENSION: &str = "jsonl";
+
+/// Extension of the files recording how much of a spool was sent
+const CURSOR_EXTENSION: &str = "cursor";
+
+/// Extension of the files recording the claim token of a spool's job
+const CLAIM_EXTENSION: &str = "claim";
+
+/// On-disk log buffers of the jobs this runner executes
+pub struct LogSpool {
+    /// Directory holding the spools
+    dir: PathBuf,
+
+    /// Jobs running on this runner, whose spools their own log sender drains
+    active: Mutex<HashSet<Uuid>>,
+}
+
+impl LogSpool {
+    /// Creates the spool described by the runner configuration
+    pub fn new(config: &Config) -> Arc<Self> {
+        Arc::new(Self {
+            dir: config.workspace_base.join(SPOOL_DIR),
+            active: Mutex::new(HashSet::new()),
+        })
+    }
+
+    /// Marks a job's spool as drained by the job's log sender
+    ///
+    /// # Arguments
+    /// * `job_id` - The job starting to run
+    /// * `claim_token` - Token the job was claimed with, sent with its entries
+    pub async fn open(&self, job_id: Uuid, claim_token: Option<u64>) {
+        self.active.lock().unwrap().insert(job_id);
+
+        if let Some(claim_token) = claim_token
+            && let Err(e) = self.write_claim_token(job_id, claim_token).await
+        {
+            warn!("Failed to record claim of job {}: {:#}", job_id, e);
+        }
+    }
+
+    async fn write_claim_token(&self, job_id: Uuid, claim_token: u64) -> anyhow::Result<()> {
+        tokio::fs::create_dir_all(&self.dir)
+            .await
+            .with_context(|| format!("Failed to create {}", self.dir.display()))?;
+        let path = self.claim_path(job_id);
+        tokio::fs::write(&path, claim_token.to_string())
+            .await
+            .with_context(|| format!("Failed to write {}", path.display()))
+    }
+
+    /// Hands a finished job's spool over to the background drain
+    ///
+    /// A spool that was fully sent is deleted.
+    ///
+    /// # Arguments
+    /// * `job_id` - The finished job
+    /// * `drained` - Whether everything in the spool was sent
+    pub async fn close(&self, job_id: Uuid, drained: bool) {
+        if drained {
+            self.remove(job_id).await;
+        }
+        self.active.lock().unwrap().remove(&job_id);
+    }
+
+    fn spool_path(&self, job_id: Uuid) -> PathBuf {
+        self.dir.join(format!("{}.{}", job_id, SPOOL_EXTENSION))
+    }
+
+    fn cursor_path(&self, job_id: Uuid) -> PathBuf {
+        self.dir.join(format!("{}.{}", job_id, CURSOR_EXTENSION))
+    }
+
+    fn claim_path(&self, job_id: Uuid) -> PathBuf {
+        self.dir.join(format!("{}.{}", job_id, CLAIM_EXTENSION))
+    }
+
+    /// Appends entries to a job's spool
+    pub async fn append(&self, job_id: Uuid, entries: &[LogEntry]) -> anyhow::Result<()> {
+        if entries.is_empty() {
+            return Ok(());
+        }
+
+        let mut data = Vec::new();
+        for entry in entries {
+            serde_json::to_writer(&mut data, entry)?;
+            data.push(b'\n');
+        }
+
+        tokio::fs::create_dir_all(&self.dir)
+            .await
+            .with_context(|| format!("Failed to create {}", self.dir.display()))?;
+        let path = self.spool_path(job_id);
+        let mut file = tokio::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .await
+            .with_context(|| format!("Failed to open {}", path.display()))?;
+        file.write_all(&data)
+            .await
+            .with_context(|| format!("Failed to write {}", path.display()))?;
+        file.flush().await?;
+
+        Ok(())
+    }
+
+    /// Sends the entries of a job's spool the orchestrator has not
+    /// acknowledged yet
+    ///
+    /// Stops at the first batch that fails to send; the next call resumes
+    /// with it.
+    ///
+    /// # Returns
+    /// The number of entries sent
+    pub async fn drain(&self, job_id: Uuid, client: &dyn OrchestratorApi) -> anyhow::Result<usize> {
+        let spool = self.spool_path(job_id);
+        let cursor = self.cursor_path(job_id);
+        let mut offset = read_cursor(&cursor).await;
+        // Spools written before claim tokens existed are sent without one
+        let claim_token = tokio::fs::read_to_string(self.claim_path(job_id))
+            .await
+            .ok()
+            .and_then(|s| s.trim().parse().ok());
+        let mut sent = 0;
+
+        loop {
+            let (pending, end) = read_pending(&spool, offset).await?;
+            if end == offset {
+                return Ok(sent);
+            }
+
+            let (entries, ends): (Vec<LogEntry>, Vec<u64>) = pending.into_iter().unzip();
+            let mut batched = 0;
+            for batch in split_log_batches(entries) {
+                let count = batch.len();
+                match client.send_logs(job_id, claim_token, batch).await {
+                    Ok(()) => sent += count,
+                    Err(e) if rejected(&e) => {
+                        warn!(
+                            "Orchestrator rejected {} log entries of job {}, dropping them: {}",
+                            count, job_id, e
+                        );
+                    }
+                    Err(e) => {
+                        return Err(anyhow::Error::new(e)
+                            .context(format!("Failed to send logs of job {}", job_id)));
+                    }
+                }
+
+                batched += count;
+                write_cursor(&cursor, ends[batched - 1]).await?;
+            }
+
+            // Past the unreadable lines after the last entry too
+            offset = end;
+            write_cursor(&cursor, offset).await?;
+        }
+    }
+
+    /// Deletes a job's spool, cursor and claim token
+    pub async fn remove(&self, job_id: Uuid) {
+        for path in [
+            self.spool_path(job_id),
+            self.cursor_path(job_id),
+            self.claim_path(job_id),
+        ] {
+            if let Err(e) = tokio::fs::remove_file(&path).await
+                && e.kind() != std::io::ErrorKind::NotFound
+            {
+                warn!("Failed to remove {}: {}", path.display(), e);
+            }
+        }
+    }
+
+    /// Drains the spools of jobs not running on this runner
+    ///
+    /// Picks up spools left by an earlier run of the runner and those of
+    /// jobs that finished while the orchestrator was unreachable. Runs for
+    /// the lifetime of the runner.
+    pub async fn run_recovery(
+        self: Arc<Self>,
+        client: Arc<dyn OrchestratorApi>,
+        interval: Duration,
+    ) {
+        let mut ticker = tokio::time::interval(interval);
+
+        loop {
+            ticker.tick().await;
+
+            for job_id in self.spooled_jobs().await {
+                if self.active.lock().unwrap().contains(&job_id) {
+                    continue;
+                }
+
+                match self.drain(job_id, client.as_ref()).await {
+                    Ok(sent) => {
+                        if let Err(e) = client.flush_logs(job_id).await {
+                            warn!("Failed to flush recovered logs of job {}: {}", job_id, e);
+                        }
+                        info!("Sent {} spooled log entries of job {}", sent, job_id);
+                        self.remove(job_id).await;
+                    }
+                    Err(e) => debug!("Spool of job {} not drained yet: {:#}", job_id, e),
+                }
+            }
+        }
+    }
+
+    /// Jobs with a spool on disk
+    async fn spooled_jobs(&self) -> Vec<Uuid> {
+        let mut jobs = Vec::new();
+        let Ok(mut entries) = tokio::fs::read_dir(&self.dir).await else {
+            return jobs;
+        };
+
+        while let Ok(Some(entry)) = entries.next_entry().await {
+            let path = entry.path();
+            if path.extension().and_then(|e| e.to_str()) != Some(SPOOL_EXTENSION) {
+                continue;
+            }
+            if let Some(job_id) = path
+                .file_stem()
+                .and_then(|stem| stem.to_str())
+                .and_then(|stem| Uuid::parse_str(stem).ok())
+            {
+                jobs.push(job_id);
+            }
+        }
+
+        jobs
+    }
+}
+
+/// Whether the orchestrator refused a batch for good, so sending it again
+/// cannot succeed
+fn rejected(err: &ClientError) -> bool {
+    matches!(err.status(), Some(status) if (400..500).contains(&status) && status != 408 && status != 429)
+}
+
+/// Offset of the first entry of a spool not sent yet
+async fn read_cursor(path: &Path) -> u64 {
+    tokio::fs::read_to_string(path)
+        .await
+        .ok()
+        .and_then(|s| s.trim().parse().ok())
+        .unwrap_or(0)
+}
+
+/// Records the offset of the first entry not sent yet
+///
+/// The cursor is replaced atomically, so a crash leaves the old or the new
+/// offset, never a torn one.
+async fn write_cursor(path: &Path, offset: u64) -> anyhow::Result<()> {
+    let partial = path.with_extension("cursor.tmp");
+    tokio::fs::write(&partial, offset.to_string())
+        .await
+        .with_context(|| format!("Failed to write {}", partial.display()))?;
+    tokio::fs::rename(&partial, path)
+        .await
+        .with_context(|| format!("Failed to write {}", path.display()))?;
+    Ok(())
+}
+
+/// Reads the complete entries of a spool after `offset`, at most
+/// `SPOOL_READ_CHUNK` bytes of them
+///
+/// A last line without terminator is being written, or was cut short by a
+/// crash, and is left out. Lines that do not decode are skipped.
+///
+/// # Returns
+/// Each entry with the offset right after it, and the offset right after
+/// the last complete line read
+async fn read_pending(path: &Path, offset: u64) -> anyhow::Result<(Vec<(LogEntry, u64)>, u64)> {
+    let mut file = match tokio::fs::File::open(path).await {
+        Ok(file) => file,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok((Vec::new(), offset)),
+        Err(e) => {
+            return Err(anyhow::Error::new(e).context(format!("Failed to open {}", path.display())));
+        }
+    };
+    file.seek(std::io::SeekFrom::Start(offset)).await?;
+
+    let mut data = Vec::new();
+    file.take(SPOOL_READ_CHUNK).read_to_end(&mut data).await?;
+
+    let mut pending = Vec::new();
+    let mut start = 0;
+    while let Some(len) = data[start..].iter().position(|&b| b == b'\n') {
+        let line = &data[start..start + len];
+        start += len + 1;
+        match serde_json::from_slice(line) {
+            Ok(entry) => pending.push((entry, offset + start as u64)),
+            Err(e) => warn!("Skipping unreadable entry in {}: {}", path.display(), e),
+        }
+    }
+
+    Ok((pending, offset + start as u64))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rivet_core::domain::log::LogLevel;
+
+    fn entry(message: &str) -> LogEntry {
+        LogEntry {
+            timestamp: chrono::Utc::now(),
+            level: LogLevel::Info,
+            message: message.to_string(),
+            stage: None,
+            stream: None,
+            sequence: None,
+            fields: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_append_and_read_pending() {
+        let base = std::env::temp_dir().join(format!("rivet-spool-test-{}", Uuid::new_v4()));
+        let config = Config {
+            workspace_base: base.clone(),
+            ..Config::default()
+        };
+        let spool = LogSpool::new(&config);
+        let job_id = Uuid::new_v4();
+
+        spool.open(job_id, Some(7)).await;
+        spool
+            .append(job_id, &[entry("first"), entry("second")])
+            .await
+            .unwrap();
+        let path = spool.spool_path(job_id);
+
+        // A line cut short by a crash is left out
+        let mut file = tokio::fs::OpenOptions::new()
+            .append(true)
+            .open(&path)
+            .await
+            .unwrap();
+        file.write_all(b"{\"timest").await.unwrap();
+
+        let (pending, _) = read_pending(&path, 0).await.unwrap();
+        let messages: Vec<&str> = pending.iter().map(|(e, _)| e.message.as_str()).collect();
+        assert_eq!(messages, vec!["first", "second"]);
+
+        // Resuming after the first entry
+        let cursor = spool.cursor_path(job_id);
+        write_cursor(&cursor, pending[0].1).await.unwrap();
+        let offset = read_cursor(&cursor).await;
+        let (pending, end) = read_pending(&path, offset).await.unwrap();
+        assert_eq!(pending.len(), 1);
+        assert_eq!(pending[0].0.message, "second");
+        assert_eq!(end, pending[0].1);
+        assert!(read_pending(&path, end).await.unwrap().0.is_empty());
+
+        assert_eq!(spool.spooled_jobs().await, vec![job_id]);
+        let claim = tokio::fs::read_to_string(spool.claim_path(job_id))
+            .await
+            .unwrap();
+        assert_eq!(claim, "7");
+        spool.remove(job_id).await;
+        assert!(spool.spooled_jobs().await.is_empty());
+        assert!(!spool.claim_path(job_id).exists());
+
+        let _ = tokio::fs::remove_dir_all(&base).await;
+    }
+}
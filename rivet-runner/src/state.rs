@@ -0,0 +1,338 @@
+//! Runner state
+//!
+//! The jobs a runner executes and the containers it started for them are
+//! recorded in `STATE_FILE` under the workspace base, replaced atomically on
+//! every change. When the runner process dies mid-job, the next run finds
+//! them there: `recover` fails the jobs the orchestrator still has running on
+//! this runner and removes their containers, along with job containers
+//! (`rivet-<job id>-*`) whose job no runner is executing any more.
+//!
+//! The rest of what a job leaves behind is recovered elsewhere: its
+//! workspace by the workspace sweep, its unsent logs by the log spool.
+
+use anyhow::Context as AnyhowContext;
+use chrono::{DateTime, Utc};
+use rivet_client::OrchestratorApi;
+use rivet_core::domain::job::{JobResult, JobStatus};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::PathBuf;
+use std::sync::Arc;
+use tokio::sync::Mutex;
+use tracing::{debug, info, warn};
+use uuid::Uuid;
+
+use crate::config::Config;
+
+/// File (under the workspace base) recording the jobs this runner executes
+pub const STATE_FILE: &str = ".rivet-state.json";
+
+/// Name prefix of job containers, followed by the job ID
+const JOB_CONTAINER_PREFIX: &str = "rivet-";
+
+/// A job this runner claimed and has not finished
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JobState {
+    pub claimed_at: DateTime<Utc>,
+    /// Token the job was claimed with, needed to fail it after a restart
+    #[serde(default)]
+    pub claim_token: Option<u64>,
+    /// Containers started for the job
+    #[serde(default)]
+    pub containers: BTreeSet<String>,
+}
+
+/// Contents of `STATE_FILE`
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct StateFile {
+    /// Runner the jobs were claimed as
+    runner_id: String,
+    jobs: BTreeMap<Uuid, JobState>,
+}
+
+/// Jobs this runner executes, persisted across restarts
+pub struct RunnerState {
+    path: PathBuf,
+    runner_id: String,
+    /// Jobs in flight; held while the file is written so writes stay ordered
+    jobs: Mutex<BTreeMap<Uuid, JobState>>,
+    /// Jobs found in the file at startup, left by an earlier run
+    leftover: Mutex<Option<StateFile>>,
+}
+
+impl RunnerState {
+    /// Opens the state described by the runner configuration
+    ///
+    /// The jobs recorded by an earlier run are kept aside for `recover`. A
+    /// file that cannot be read is reported and ignored.
+    pub fn open(config: &Config) -> Arc<Self> {
+        let path = config.workspace_base.join(STATE_FILE);
+        let leftover = match std::fs::read(&path) {
+            Ok(data) => match serde_json::from_slice::<StateFile>(&data) {
+                Ok(file) => Some(file),
+                Err(e) => {
+                    warn!("Ignoring unreadable runner state {}: {}", path.display(), e);
+                    None
+                }
+            },
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => None,
+            Err(e) => {
+                warn!("Failed to read runner state {}: {}", path.display(), e);
+                None
+            }
+        };
+
+        Arc::new(Self {
+            path,
+            runner_id: config.runner_id.clone(),
+            jobs: Mutex::new(BTreeMap::new()),
+            leftover: Mutex::new(leftover),
+        })
+    }
+
+    /// Records that the runner claimed a job
+    pub async fn claim(&self, job_id: Uuid, claim_token: Option<u64>) {
+        let mut jobs = self.jobs.lock().await;
+        jobs.insert(
+            job_id,
+            JobState {
+                claimed_at: Utc::now(),
+                claim_token,
+                containers: BTreeSet::new(),
+            },
+        );
+        self.save(&jobs).await;
+    }
+
+    /// Records a container started for a job
+    pub async fn add_container(&self, job_id: Uuid, container_name: &str) {
+        let mut jobs = self.jobs.lock().await;
+        if let Some(job) = jobs.get_mut(&job_id)
+            && job.containers.insert(container_name.to_string())
+        {
+            self.save(&jobs).await;
+        }
+    }
+
+    /// Forgets a job once it is finished and its containers removed
+    pub async fn release(&self, job_id: Uuid) {
+        let mut jobs = self.jobs.lock().await;
+        if jobs.remove(&job_id).is_some() {
+            self.save(&jobs).await;
+        }
+    }
+
+    /// Writes the jobs in flight to `STATE_FILE`
+    ///
+    /// Failures are reported: the state only matters after a crash, and the
+    /// job must not fail for it.
+    async fn save(&self, jobs: &BTreeMap<Uuid, JobState>) {
+        if let Err(e) = self.write(jobs).await {
+            warn!("Failed to save runner state: {:#}", e);
+        }
+    }
+
+    async fn write(&self, jobs: &BTreeMap<Uuid, JobState>) -> anyhow::Result<()> {
+        let file = StateFile {
+            runner_id: self.runner_id.clone(),
+            jobs: jobs.clone(),
+        };
+        let data = serde_json::to_vec_pretty(&file)?;
+
+        if let Some(dir) = self.path.parent() {
+            tokio::fs::create_dir_all(dir).await?;
+        }
+        let partial = self.path.with_extension("json.tmp");
+        tokio::fs::write(&partial, data)
+            .await
+            .with_context(|| format!("Failed to write {}", partial.display()))?;
+        tokio::fs::rename(&partial, &self.path)
+            .await
+            .with_context(|| format!("Failed to write {}", self.path.display()))?;
+        Ok(())
+    }
+
+    /// Cleans up after the jobs an earlier run of the runner left behind
+    ///
+    /// Must run before the runner starts jobs. Jobs the orchestrator still
+    /// has running on this runner are failed; a job claimed under another
+    /// runner ID (e.g. a random one) cannot be, and is requeued once its
+    /// lease expires. Job containers are removed unless their job is running
+    /// on another runner sharing this podman.
+    pub async fn recover(&self, client: &dyn OrchestratorApi) {
+        let leftover = self.leftover.lock().await.take().unwrap_or_default();
+
+        let mut containers: BTreeMap<Uuid, BTreeSet<String>> = leftover
+            .jobs
+            .iter()
+            .map(|(job_id, job)| (*job_id, job.containers.clone()))
+            .collect();
+        for name in list_job_containers().await {
+            if let Some(job_id) = container_job(&name) {
+                containers.entry(job_id).or_default().insert(name);
+            }
+        }
+
+        for (job_id, names) in containers {
+            let ours = leftover.jobs.contains_key(&job_id);
+            let remove = match client.get_job(job_id).await {
+                Ok(job) if job.status == JobStatus::Running => {
+                    let runner = job.runner_id.as_deref();
+                    let interrupted = runner == Some(self.runner_id.as_str())
+                        || (ours && runner == Some(leftover.runner_id.as_str()));
+                    if interrupted {
+                        let claim_token = leftover.jobs.get(&job_id).and_then(|j| j.claim_token);
+                        fail_job(client, job_id, claim_token).await;
+                    }
+                    // Running elsewhere: only remove what this runner started
+                    interrupted || ours
+                }
+                Ok(_) => true,
+                Err(e) if e.status() == Some(404) => true,
+                Err(e) => {
+                    warn!("Failed to look up job {}: {}", job_id, e);
+                    ours
+                }
+            };
+
+            if remove {
+                for name in &names {
+                    remove_container(name).await;
+                }
+            } else {
+                debug!(
+                    "Keeping containers of job {}, running on another runner",
+                    job_id
+                );
+            }
+        }
+
+        // Nothing is in flight yet; drop the file of the earlier run
+        self.save(&*self.jobs.lock().await).await;
+    }
+}
+
+/// Fails a job the runner was executing when it stopped
+///
+/// With the token of the claim the job ran under, a job that was claimed
+/// again in the meantime is left to its new runner.
+async fn fail_job(client: &dyn OrchestratorApi, job_id: Uuid, claim_token: Option<u64>) {
+    info!("Failing job {}, interrupted by a runner restart", job_id);
+    let result = JobResult::failed("The runner restarted while the job was running".to_string());
+    if let Err(e) = client.complete_job(job_id, claim_token, result).await {
+        warn!(
+            "Failed to fail interrupted job {}, it is requeued once its lease expires: {}",
+            job_id, e
+        );
+    }
+}
+
+/// Job the container named `name` was started for, if it is a job container
+fn container_job(name: &str) -> Option<Uuid> {
+    let rest = name.strip_prefix(JOB_CONTAINER_PREFIX)?;
+    Uuid::parse_str(rest.get(..36)?).ok()
+}
+
+/// Names of the containers that may be job containers
+async fn list_job_containers() -> Vec<String> {
+    let filter = format!("name=^{}", JOB_CONTAINER_PREFIX);
+    let output = crate::podman::command()
+        .args(["ps", "-a", "--filter", &filter, "--format", "{{.Names}}"])
+        .output()
+        .await;
+
+    match output {
+        Ok(output) if output.status.success() => String::from_utf8_lossy(&output.stdout)
+            .split_whitespace()
+            .map(str::to_string)
+            .collect(),
+        Ok(output) => {
+            warn!(
+                "Failed to list containers: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            );
+            Vec::new()
+        }
+        Err(e) => {
+            warn!("Failed to list containers: {}", e);
+            Vec::new()
+        }
+    }
+}
+
+async fn remove_container(name: &str) {
+    info!("Removing leftover container {}", name);
+    let output = crate::podman::command()
+        .args(["rm", "-f", name])
+        .output()
+        .await;
+
+    match output {
+        Ok(output) if output.status.success() => {}
+        Ok(output) => warn!(
+            "Failed to remove container {}: {}",
+            name,
+            String::from_utf8_lossy(&output.stderr).trim()
+        ),
+        Err(e) => warn!("Failed to remove container {}: {}", name, e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_container_job() {
+        let job_id = Uuid::new_v4();
+        assert_eq!(
+            container_job(&format!("rivet-{}-1f2e3d", job_id)),
+            Some(job_id)
+        );
+        assert_eq!(container_job("rivet-warm-0a1b2c"), None);
+        assert_eq!(container_job("rivet-debug-session"), None);
+        assert_eq!(container_job("postgres"), None);
+    }
+
+    #[tokio::test]
+    async fn test_state_survives_restart() {
+        let base = std::env::temp_dir().join(format!("rivet-state-test-{}", Uuid::new_v4()));
+        let config = Config {
+            workspace_base: base.clone(),
+            ..Config::default()
+        };
+        let job_id = Uuid::new_v4();
+
+        let state = RunnerState::open(&config);
+        state.claim(job_id, Some(3)).await;
+        state.add_container(job_id, "rivet-job-container").await;
+        state.add_container(Uuid::new_v4(), "unclaimed").await;
+
+        // A restarted runner finds the job and its container
+        let restarted = RunnerState::open(&config);
+        let leftover = restarted.leftover.lock().await.take().unwrap();
+        assert_eq!(leftover.runner_id, config.runner_id);
+        assert_eq!(leftover.jobs.len(), 1);
+        assert_eq!(leftover.jobs[&job_id].claim_token, Some(3));
+        assert!(
+            leftover.jobs[&job_id]
+                .containers
+                .contains("rivet-job-container")
+        );
+
+        state.release(job_id).await;
+        let released = RunnerState::open(&config);
+        assert!(
+            released
+                .leftover
+                .lock()
+                .await
+                .take()
+                .unwrap()
+                .jobs
+                .is_empty()
+        );
+
+        let _ = tokio::fs::remove_dir_all(&base).await;
+    }
+}
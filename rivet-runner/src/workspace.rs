@@ -0,0 +1,510 @@
+//! Workspace retention and disk usage
+//!
+//! Every job gets a workspace directory named after it under the workspace
+//! base. Once the job is done its workspace is deleted, or for failed jobs
+//! moved under `FAILED_DIR` and kept for debugging (the newest
+//! `keep_failed_workspaces` only), along with the job's container snapshots. A periodic sweep removes workspaces left
+//! by crashed runs, expires kept ones and reports disk usage.
+//!
+//! Workspaces of running jobs are marked by an empty file under `JOBS_DIR`,
+//! so workspaces left by an earlier run of the runner are found without
+//! touching anything else in the (possibly shared) workspace base.
+//!
+//! A job resuming a failed one takes over the failed job's kept workspace
+//! when this runner has it, so the stages it skips need not produce their
+//! files again.
+//!
+//! Files sent for `file` inputs are written under `INPUTS_DIR` in the
+//! workspace, and the inputs' values replaced with the files' paths.
+
+use rivet_core::dto::job::InputFile;
+use serde_json::Value as JsonValue;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
+use tracing::{debug, info, warn};
+use uuid::Uuid;
+
+use crate::config::Config;
+use crate::debug::remove_snapshots;
+use crate::podman::{ContainerRuntime, container_os, runtime};
+
+/// Directory (under the workspace base) holding kept failed-job workspaces
+pub const FAILED_DIR: &str = ".rivet-failed";
+
+/// Directory (under the workspace base) marking workspaces of running jobs
+const JOBS_DIR: &str = ".rivet-jobs";
+
+/// Directory (under a job's workspace) holding the files of `file` inputs
+const INPUTS_DIR: &str = ".rivet-inputs";
+
+/// Manages the workspaces of the jobs this runner executes
+pub struct WorkspaceManager {
+    /// Base directory of all workspaces
+    base: PathBuf,
+
+    /// Failed-job workspaces kept for debugging (0 = delete them too)
+    keep_failed: usize,
+
+    /// Delete kept workspaces older than this (None = keep until trimmed)
+    max_age: Option<Duration>,
+
+    /// How often the sweep runs
+    sweep_interval: Duration,
+
+    /// Jobs currently running, whose workspaces must not be swept
+    active: Mutex<HashSet<Uuid>>,
+}
+
+/// Disk usage reported by a sweep
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct DiskUsage {
+    /// Workspaces of running jobs
+    pub active: usize,
+    /// Failed-job workspaces kept for debugging
+    pub kept: usize,
+    /// Bytes used by all of them
+    pub bytes: u64,
+}
+
+impl WorkspaceManager {
+    /// Creates the manager described by the runner configuration
+    pub fn new(config: &Config) -> Arc<Self> {
+        Arc::new(Self {
+            base: config.workspace_base.clone(),
+            keep_failed: config.keep_failed_workspaces,
+            max_age: config.workspace_max_age,
+            sweep_interval: config.workspace_sweep_interval,
+            active: Mutex::new(HashSet::new()),
+        })
+    }
+
+    /// Marks a job's workspace as in use
+    pub async fn start(&self, job_id: Uuid) {
+        self.active.lock().unwrap().insert(job_id);
+
+        let jobs_dir = self.base.join(JOBS_DIR);
+        let marked = match tokio::fs::create_dir_all(&jobs_dir).await {
+            Ok(()) => tokio::fs::write(jobs_dir.join(job_id.to_string()), b"").await,
+            Err(e) => Err(e),
+        };
+        if let Err(e) = marked {
+            warn!("Failed to mark workspace of job {}: {}", job_id, e);
+        }
+    }
+
+    /// Hands the kept workspace of a failed job over to the job resuming it
+    ///
+    /// The failed job's snapshots go with it: its environment is no longer
+    /// available for debugging.
+    ///
+    /// # Arguments
+    /// * `original` - The failed job being resumed
+    /// * `job_id` - The resuming job, already started
+    ///
+    /// # Returns
+    /// Whether the workspace was kept on this runner and taken over
+    pub async fn resume(&self, original: Uuid, job_id: Uuid) -> bool {
+        let kept = self.base.join(FAILED_DIR).join(original.to_string());
+        let workspace = self.base.join(job_id.to_string());
+
+        match tokio::fs::rename(&kept, &workspace).await {
+            Ok(()) => {
+                remove_snapshots(original).await;
+                true
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => false,
+            Err(e) => {
+                warn!(
+                    "Failed to take over workspace of job {} for job {}: {}",
+                    original, job_id, e
+                );
+                false
+            }
+        }
+    }
+
+    /// Applies the retention policy to a finished job's workspace
+    ///
+    /// # Arguments
+    /// * `job_id` - The finished job
+    /// * `success` - Whether the job succeeded
+    ///
+    /// # Returns
+    /// Where the workspace was kept, None if it was deleted
+    pub async fn finish(&self, job_id: Uuid, success: bool) -> Option<PathBuf> {
+        self.active.lock().unwrap().remove(&job_id);
+        let workspace = self.base.join(job_id.to_string());
+        let _ = tokio::fs::remove_file(self.base.join(JOBS_DIR).join(job_id.to_string())).await;
+
+        if success || self.keep_failed == 0 {
+            remove_workspace(&workspace).await;
+            return None;
+        }
+
+        let failed_dir = self.base.join(FAILED_DIR);
+        let kept = failed_dir.join(job_id.to_string());
+        let moved = match tokio::fs::create_dir_all(&failed_dir).await {
+            Ok(()) => tokio::fs::rename(&workspace, &kept).await,
+            Err(e) => Err(e),
+        };
+
+        match moved {
+            Ok(()) => {
+                self.trim_failed().await;
+                Some(kept)
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                remove_snapshots(job_id).await;
+                None
+            }
+            Err(e) => {
+                warn!("Failed to keep workspace of job {}: {}", job_id, e);
+                remove_workspace(&workspace).await;
+                remove_snapshots(job_id).await;
+                None
+            }
+        }
+    }
+
+    /// Sweeps workspaces periodically
+    ///
+    /// Runs for the lifetime of the runner.
+    pub async fn run_sweeper(self: Arc<Self>) {
+        let mut ticker = tokio::time::interval(self.sweep_interval);
+
+        loop {
+            ticker.tick().await;
+            let usage = self.sweep().await;
+            info!(
+                "Workspace disk usage: {} active, {} kept, {} bytes",
+                usage.active, usage.kept, usage.bytes
+            );
+        }
+    }
+
+    /// Removes orphaned and expired workspaces and measures the rest
+    pub async fn sweep(&self) -> DiskUsage {
+        let mut usage = DiskUsage::default();
+
+        // Marked workspaces of jobs that are not running were left by a crash
+        for job_id in marked_jobs(&self.base.join(JOBS_DIR)).await {
+            let path = self.base.join(job_id.to_string());
+            if self.active.lock().unwrap().contains(&job_id) {
+                usage.active += 1;
+                usage.bytes += dir_size(path).await;
+            } else {
+                info!("Removing orphaned workspace of job {}", job_id);
+                remove_workspace(&path).await;
+                let _ =
+                    tokio::fs::remove_file(self.base.join(JOBS_DIR).join(job_id.to_string())).await;
+            }
+        }
+
+        self.trim_failed().await;
+        for (path, _) in job_dirs(&self.base.join(FAILED_DIR)).await {
+            usage.kept += 1;
+            usage.bytes += dir_size(path).await;
+        }
+
+        usage
+    }
+
+    /// Deletes kept workspaces beyond `keep_failed` (oldest first) or `max_age`
+    async fn trim_failed(&self) {
+        let mut kept: Vec<(SystemTime, PathBuf, Uuid)> = Vec::new();
+        for (path, job_id) in job_dirs(&self.base.join(FAILED_DIR)).await {
+            let modified = tokio::fs::metadata(&path)
+                .await
+                .and_then(|m| m.modified())
+                .unwrap_or(SystemTime::UNIX_EPOCH);
+            kept.push((modified, path, job_id));
+        }
+
+        // Newest first
+        kept.sort_by_key(|(modified, _, _)| std::cmp::Reverse(*modified));
+
+        for (i, (modified, path, job_id)) in kept.into_iter().enumerate() {
+            let expired = self
+                .max_age
+                .is_some_and(|max_age| modified.elapsed().is_ok_and(|age| age > max_age));
+            if i >= self.keep_failed || expired {
+                debug!("Deleting kept workspace {}", path.display());
+                remove_workspace(&path).await;
+                remove_snapshots(job_id).await;
+            }
+        }
+    }
+}
+
+/// Lists the job workspaces (directories named after a job ID) in a directory
+async fn job_dirs(dir: &Path) -> Vec<(PathBuf, Uuid)> {
+    let mut dirs = Vec::new();
+    let Ok(mut entries) = tokio::fs::read_dir(dir).await else {
+        return dirs;
+    };
+
+    while let Ok(Some(entry)) = entries.next_entry().await {
+        let is_dir = entry.file_type().await.is_ok_and(|t| t.is_dir());
+        let job_id = entry
+            .file_name()
+            .to_str()
+            .and_then(|name| Uuid::parse_str(name).ok());
+        if let (true, Some(job_id)) = (is_dir, job_id) {
+            dirs.push((entry.path(), job_id));
+        }
+    }
+
+    dirs
+}
+
+/// Lists the jobs marked as running in `JOBS_DIR`
+async fn marked_jobs(dir: &Path) -> Vec<Uuid> {
+    let mut jobs = Vec::new();
+    let Ok(mut entries) = tokio::fs::read_dir(dir).await else {
+        return jobs;
+    };
+
+    while let Ok(Some(entry)) = entries.next_entry().await {
+        if let Some(job_id) = entry
+            .file_name()
+            .to_str()
+            .and_then(|n| Uuid::parse_str(n).ok())
+        {
+            jobs.push(job_id);
+        }
+    }
+
+    jobs
+}
+
+/// Takes the files of `file` inputs out of a job's parameters
+///
+/// Each file input's value becomes the path its file has in containers,
+/// `/workspace/.rivet-inputs/<input>/<file name>`.
+///
+/// # Returns
+/// The files to write with [`write_input_files`], by path relative to the
+/// workspace
+pub fn take_input_files(parameters: &mut HashMap<String, JsonValue>) -> Vec<(PathBuf, Vec<u8>)> {
+    let mut files = Vec::new();
+
+    for (name, value) in parameters.iter_mut() {
+        // Only file inputs take objects; their names become a directory
+        if !value.is_object()
+            || matches!(name.as_str(), "" | "." | "..")
+            || name.contains(['/', '\\'])
+        {
+            continue;
+        }
+        let Ok((file, content)) = InputFile::parse(value) else {
+            continue;
+        };
+
+        let path = Path::new(INPUTS_DIR).join(name).join(&file.name);
+        *value = JsonValue::String(container_os().workspace_path(&path));
+        files.push((path, content));
+    }
+
+    files
+}
+
+/// Writes the files of `file` inputs into a job's workspace
+///
+/// # Arguments
+/// * `workspace` - The job's workspace
+/// * `files` - Files taken by [`take_input_files`]
+pub async fn write_input_files(
+    workspace: &Path,
+    files: &[(PathBuf, Vec<u8>)],
+) -> std::io::Result<()> {
+    for (path, content) in files {
+        let path = workspace.join(path);
+        if let Some(dir) = path.parent() {
+            tokio::fs::create_dir_all(dir).await?;
+        }
+        tokio::fs::write(&path, content).await?;
+    }
+    Ok(())
+}
+
+/// Deletes a workspace
+///
+/// Files created by non-root users inside rootless containers belong to
+/// sub-UIDs the runner cannot delete; those are removed from podman's user
+/// namespace instead.
+pub async fn remove_workspace(path: &Path) {
+    match tokio::fs::remove_dir_all(path).await {
+        Ok(()) => return,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return,
+        Err(e) if runtime() != ContainerRuntime::Podman => {
+            warn!("Failed to remove workspace {}: {}", path.display(), e);
+            return;
+        }
+        Err(e) => debug!(
+            "Failed to remove {}: {}, retrying with podman unshare",
+            path.display(),
+            e
+        ),
+    }
+
+    let output = tokio::process::Command::new("podman")
+        .arg("unshare")
+        .arg("rm")
+        .arg("-rf")
+        .arg(path)
+        .output()
+        .await;
+
+    match output {
+        Ok(output) if output.status.success() => {}
+        Ok(output) => warn!(
+            "Failed to remove workspace {}: {}",
+            path.display(),
+            String::from_utf8_lossy(&output.stderr).trim()
+        ),
+        Err(e) => warn!("Failed to remove workspace {}: {}", path.display(), e),
+    }
+}
+
+/// Total size of the files in a directory tree, without following symlinks
+pub async fn dir_size(path: PathBuf) -> u64 {
+    tokio::task::spawn_blocking(move || dir_size_blocking(&path))
+        .await
+        .unwrap_or(0)
+}
+
+fn dir_size_blocking(path: &Path) -> u64 {
+    let Ok(entries) = std::fs::read_dir(path) else {
+        return 0;
+    };
+
+    entries
+        .flatten()
+        .map(|entry| match entry.metadata() {
+            Ok(meta) if meta.is_dir() => dir_size_blocking(&entry.path()),
+            Ok(meta) => meta.len(),
+            Err(_) => 0,
+        })
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn manager(base: &Path, keep_failed: usize) -> WorkspaceManager {
+        let config = Config {
+            workspace_base: base.to_path_buf(),
+            keep_failed_workspaces: keep_failed,
+            ..Default::default()
+        };
+        Arc::into_inner(WorkspaceManager::new(&config)).unwrap()
+    }
+
+    async fn workspace(base: &Path, job_id: Uuid, bytes: usize) {
+        let path = base.join(job_id.to_string());
+        tokio::fs::create_dir_all(path.join("sub")).await.unwrap();
+        tokio::fs::write(path.join("sub/file"), vec![0u8; bytes])
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_retention_keeps_newest_failed_workspaces() {
+        let base = std::env::temp_dir().join(format!("rivet-ws-test-{}", Uuid::new_v4()));
+        let workspaces = manager(&base, 1);
+
+        let (ok, failed, newer_failed) = (Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4());
+        for job_id in [ok, failed] {
+            workspaces.start(job_id).await;
+            workspace(&base, job_id, 10).await;
+        }
+
+        assert_eq!(workspaces.finish(ok, true).await, None);
+        assert!(workspaces.finish(failed, false).await.is_some());
+
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        workspaces.start(newer_failed).await;
+        workspace(&base, newer_failed, 10).await;
+        let kept = workspaces.finish(newer_failed, false).await.unwrap();
+
+        assert!(!base.join(ok.to_string()).exists());
+        assert!(!base.join(FAILED_DIR).join(failed.to_string()).exists());
+        assert!(kept.join("sub/file").exists());
+
+        // A marked workspace no running job owns was left by a crash, an
+        // unmarked one is not the runner's
+        let orphan = Uuid::new_v4();
+        workspaces.start(orphan).await;
+        workspace(&base, orphan, 10).await;
+        workspaces.active.lock().unwrap().remove(&orphan);
+        let foreign = Uuid::new_v4();
+        workspace(&base, foreign, 10).await;
+        let running = Uuid::new_v4();
+        workspaces.start(running).await;
+        workspace(&base, running, 100).await;
+
+        let usage = workspaces.sweep().await;
+        assert!(!base.join(orphan.to_string()).exists());
+        assert!(base.join(foreign.to_string()).exists());
+        assert_eq!(
+            usage,
+            DiskUsage {
+                active: 1,
+                kept: 1,
+                bytes: 110
+            }
+        );
+
+        tokio::fs::remove_dir_all(&base).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_resume_takes_over_kept_workspace() {
+        let base = std::env::temp_dir().join(format!("rivet-ws-test-{}", Uuid::new_v4()));
+        let workspaces = manager(&base, 1);
+
+        let (failed, resumed, elsewhere) = (Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4());
+        workspaces.start(failed).await;
+        workspace(&base, failed, 10).await;
+        workspaces.finish(failed, false).await.unwrap();
+
+        workspaces.start(resumed).await;
+        assert!(workspaces.resume(failed, resumed).await);
+        assert!(base.join(resumed.to_string()).join("sub/file").exists());
+        assert!(!base.join(FAILED_DIR).join(failed.to_string()).exists());
+
+        // Not kept on this runner
+        assert!(!workspaces.resume(elsewhere, Uuid::new_v4()).await);
+
+        tokio::fs::remove_dir_all(&base).await.unwrap();
+    }
+
+    #[test]
+    fn test_file_inputs_become_workspace_paths() {
+        let mut parameters = HashMap::from([
+            (
+                "config".to_string(),
+                serde_json::to_value(InputFile::new("app.toml", b"port = 80")).unwrap(),
+            ),
+            ("branch".to_string(), serde_json::json!("main")),
+        ]);
+
+        let files = take_input_files(&mut parameters);
+
+        assert_eq!(
+            parameters["config"],
+            "/workspace/.rivet-inputs/config/app.toml"
+        );
+        assert_eq!(parameters["branch"], "main");
+        assert_eq!(
+            files,
+            [(
+                PathBuf::from(".rivet-inputs/config/app.toml"),
+                b"port = 80".to_vec()
+            )]
+        );
+    }
+}
@@ -0,0 +1,132 @@
+//! Ephemeral Databases
+//!
+//! Every test orchestrator gets a database of its own, named
+//! `rivet_test_<uuid>`, so that tests run in parallel without seeing each
+//! other's pipelines and jobs.
+
+use std::str::FromStr;
+
+use rivet_orchestrator::db;
+use sqlx::postgres::{PgConnectOptions, PgPoolOptions};
+use sqlx::{Connection, PgConnection, PgPool};
+use uuid::Uuid;
+
+use crate::error::TestkitError;
+
+/// Environment variable holding the URL of the server test databases are created on
+pub const TEST_DATABASE_URL_ENV: &str = "RIVET_TEST_DATABASE_URL";
+
+/// Name prefix of test databases, followed by a UUID
+const TEST_DATABASE_PREFIX: &str = "rivet_test_";
+
+/// Connections a test orchestrator opens to its database
+const TEST_POOL_SIZE: u32 = 5;
+
+/// Migrated database dropped when the value is dropped
+pub struct EphemeralDatabase {
+    /// Connection options of the server's own database, to drop this one from
+    admin: PgConnectOptions,
+    name: String,
+    pool: PgPool,
+    dropped: bool,
+}
+
+impl EphemeralDatabase {
+    /// Creates a database on the server `TEST_DATABASE_URL_ENV` points to
+    pub async fn create() -> Result<Self, TestkitError> {
+        let url = std::env::var(TEST_DATABASE_URL_ENV)
+            .or_else(|_| std::env::var("DATABASE_URL"))
+            .map_err(|_| TestkitError::NoDatabase)?;
+        Self::create_on(&url).await
+    }
+
+    /// Creates a database on the server at `url` and migrates it
+    ///
+    /// # Arguments
+    /// * `url` - Any database of the server, e.g. `postgres://postgres@localhost/postgres`
+    pub async fn create_on(url: &str) -> Result<Self, TestkitError> {
+        let admin = PgConnectOptions::from_str(url)?;
+        let name = format!("{}{}", TEST_DATABASE_PREFIX, Uuid::new_v4().simple());
+
+        let mut connection = PgConnection::connect_with(&admin).await?;
+        sqlx::query(&format!("CREATE DATABASE \"{}\"", name))
+            .execute(&mut connection)
+            .await?;
+        connection.close().await?;
+
+        let pool = PgPoolOptions::new()
+            .max_connections(TEST_POOL_SIZE)
+            .connect_with(admin.clone().database(&name))
+            .await;
+        let pool = match pool {
+            Ok(pool) => pool,
+            Err(e) => {
+                drop_database(&admin, &name).await?;
+                return Err(e.into());
+            }
+        };
+
+        // From here on, dropping the value drops the database
+        let database = Self {
+            admin,
+            name,
+            pool,
+            dropped: false,
+        };
+        db::run_migrations(&database.pool).await?;
+        Ok(database)
+    }
+
+    /// Name of the database
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Pool of connections to the database
+    pub fn pool(&self) -> &PgPool {
+        &self.pool
+    }
+
+    /// Closes the pool and drops the database
+    pub async fn drop_database(mut self) -> Result<(), TestkitError> {
+        self.dropped = true;
+        self.pool.close().await;
+        drop_database(&self.admin, &self.name).await
+    }
+}
+
+impl Drop for EphemeralDatabase {
+    /// Drops the database on a thread of its own, as `drop` cannot await
+    ///
+    /// Connections still open, e.g. those of the pool, are terminated.
+    fn drop(&mut self) {
+        if self.dropped {
+            return;
+        }
+
+        let (admin, name) = (self.admin.clone(), self.name.clone());
+        let dropped = std::thread::spawn(move || {
+            tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()?
+                .block_on(drop_database(&admin, &name))
+        })
+        .join();
+
+        if let Ok(Err(e)) = dropped {
+            eprintln!("Failed to drop test database {}: {}", self.name, e);
+        }
+    }
+}
+
+async fn drop_database(admin: &PgConnectOptions, name: &str) -> Result<(), TestkitError> {
+    let mut connection = PgConnection::connect_with(admin).await?;
+    sqlx::query(&format!(
+        "DROP DATABASE IF EXISTS \"{}\" WITH (FORCE)",
+        name
+    ))
+    .execute(&mut connection)
+    .await?;
+    connection.close().await?;
+    Ok(())
+}
@@ -0,0 +1,50 @@
+//! Test Kit Errors
+
+use std::fmt;
+
+use rivet_orchestrator::config::ConfigError;
+
+/// Error setting up or tearing down a test orchestrator
+#[derive(Debug)]
+pub enum TestkitError {
+    /// Neither `RIVET_TEST_DATABASE_URL` nor `DATABASE_URL` is set
+    NoDatabase,
+    Database(sqlx::Error),
+    Io(std::io::Error),
+    /// The server was configured with invalid settings
+    Config(ConfigError),
+}
+
+impl fmt::Display for TestkitError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TestkitError::NoDatabase => write!(
+                f,
+                "No test database server, set RIVET_TEST_DATABASE_URL (or DATABASE_URL)"
+            ),
+            TestkitError::Database(e) => write!(f, "Database error: {}", e),
+            TestkitError::Io(e) => write!(f, "I/O error: {}", e),
+            TestkitError::Config(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for TestkitError {}
+
+impl From<sqlx::Error> for TestkitError {
+    fn from(e: sqlx::Error) -> Self {
+        TestkitError::Database(e)
+    }
+}
+
+impl From<std::io::Error> for TestkitError {
+    fn from(e: std::io::Error) -> Self {
+        TestkitError::Io(e)
+    }
+}
+
+impl From<ConfigError> for TestkitError {
+    fn from(e: ConfigError) -> Self {
+        TestkitError::Config(e)
+    }
+}
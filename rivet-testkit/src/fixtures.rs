@@ -0,0 +1,327 @@
+//! Fixtures
+//!
+//! Builders for the pipelines and jobs tests start from. They go through
+//! `OrchestratorApi`, so they work against a `TestOrchestrator` as well as a
+//! `MockOrchestrator`.
+
+use std::collections::{BTreeMap, HashMap};
+
+use rivet_client::OrchestratorApi;
+use rivet_core::domain::job::{CommitRef, Job};
+use rivet_core::domain::pipeline::Pipeline;
+use rivet_core::dto::job::CreateJob;
+use rivet_core::dto::pipeline::CreatePipeline;
+use serde_json::Value;
+use uuid::Uuid;
+
+/// Builds the Lua script of a pipeline and creates it
+///
+/// # Example
+/// ```
+/// use rivet_testkit::PipelineBuilder;
+///
+/// let script = PipelineBuilder::new("build")
+///     .input("target", "string", Some("release".into()))
+///     .stage("compile", r#"log.info("compiling " .. input.get("target"))"#)
+///     .to_script();
+/// assert!(script.starts_with("return pipeline.define({"));
+/// ```
+#[derive(Debug, Clone)]
+pub struct PipelineBuilder {
+    name: String,
+    description: Option<String>,
+    /// `(name, type, default)` of each input
+    inputs: Vec<(String, String, Option<Value>)>,
+    /// Runner tags as `(key, value)` pairs
+    tags: Vec<(String, String)>,
+    max_retries: Option<u32>,
+    /// `(name, environment deployed to, body of the script function)` of
+    /// each stage
+    stages: Vec<(String, Option<String>, String)>,
+}
+
+impl PipelineBuilder {
+    /// Starts a pipeline named `name`, without inputs or stages
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            description: None,
+            inputs: Vec::new(),
+            tags: Vec::new(),
+            max_retries: None,
+            stages: Vec::new(),
+        }
+    }
+
+    pub fn description(mut self, description: impl Into<String>) -> Self {
+        self.description = Some(description.into());
+        self
+    }
+
+    /// Adds an input of type `kind` (`string`, `number`, `bool`, ...),
+    /// required unless it has a default
+    pub fn input(
+        mut self,
+        name: impl Into<String>,
+        kind: impl Into<String>,
+        default: Option<Value>,
+    ) -> Self {
+        self.inputs.push((name.into(), kind.into(), default));
+        self
+    }
+
+    /// Requires runners providing the tag `key=value`
+    pub fn tag(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.tags.push((key.into(), value.into()));
+        self
+    }
+
+    pub fn max_retries(mut self, max_retries: u32) -> Self {
+        self.max_retries = Some(max_retries);
+        self
+    }
+
+    /// Adds a stage running the Lua statements `body`
+    pub fn stage(mut self, name: impl Into<String>, body: impl Into<String>) -> Self {
+        self.stages.push((name.into(), None, body.into()));
+        self
+    }
+
+    /// Adds a stage deploying to `environment`, running the Lua statements
+    /// `body`
+    pub fn deploy_stage(
+        mut self,
+        name: impl Into<String>,
+        environment: impl Into<String>,
+        body: impl Into<String>,
+    ) -> Self {
+        self.stages
+            .push((name.into(), Some(environment.into()), body.into()));
+        self
+    }
+
+    /// The pipeline's Lua script
+    pub fn to_script(&self) -> String {
+        let mut script = String::from("return pipeline.define({\n");
+        script.push_str(&format!("    name = {},\n", lua_string(&self.name)));
+        if let Some(description) = &self.description {
+            script.push_str(&format!("    description = {},\n", lua_string(description)));
+        }
+
+        if !self.inputs.is_empty() {
+            script.push_str("    inputs = {\n");
+            for (name, kind, default) in &self.inputs {
+                script.push_str(&format!(
+                    "        [{}] = {{ type = {}",
+                    lua_string(name),
+                    lua_string(kind)
+                ));
+                if let Some(default) = default {
+                    script.push_str(&format!(", default = {}", lua_value(default)));
+                }
+                script.push_str(" },\n");
+            }
+            script.push_str("    },\n");
+        }
+
+        if !self.tags.is_empty() {
+            script.push_str("    runner = {\n");
+            for (key, value) in &self.tags {
+                script.push_str(&format!(
+                    "        {{ key = {}, value = {} }},\n",
+                    lua_string(key),
+                    lua_string(value)
+                ));
+            }
+            script.push_str("    },\n");
+        }
+
+        if let Some(max_retries) = self.max_retries {
+            script.push_str(&format!("    max_retries = {},\n", max_retries));
+        }
+
+        script.push_str("    stages = {\n");
+        for (name, environment, body) in &self.stages {
+            script.push_str(&format!(
+                "        {{\n            name = {},\n",
+                lua_string(name)
+            ));
+            if let Some(environment) = environment {
+                script.push_str(&format!(
+                    "            deploys_to = {},\n",
+                    lua_string(environment)
+                ));
+            }
+            script.push_str(&format!(
+                "            script = function()\n{}\n            end\n        }},\n",
+                body
+            ));
+        }
+        script.push_str("    }\n})\n");
+        script
+    }
+
+    /// Creates the pipeline
+    pub async fn create(&self, api: &dyn OrchestratorApi) -> rivet_client::Result<Pipeline> {
+        api.create_pipeline(CreatePipeline {
+            script: self.to_script(),
+        })
+        .await
+    }
+}
+
+/// Builds a job of a pipeline and launches it
+#[derive(Debug, Clone)]
+pub struct JobBuilder {
+    request: CreateJob,
+}
+
+impl JobBuilder {
+    /// Starts a job of the pipeline `pipeline_id`, without parameters
+    pub fn new(pipeline_id: Uuid) -> Self {
+        Self {
+            request: CreateJob {
+                pipeline_id,
+                parameters: HashMap::new(),
+                plan: false,
+                commit: None,
+                override_blackout: false,
+                labels: BTreeMap::new(),
+            },
+        }
+    }
+
+    /// Sets the value of an input
+    pub fn parameter(mut self, name: impl Into<String>, value: impl Into<Value>) -> Self {
+        self.request.parameters.insert(name.into(), value.into());
+        self
+    }
+
+    /// Sets the commit the job builds
+    pub fn commit(mut self, repository: impl Into<String>, sha: impl Into<String>) -> Self {
+        self.request.commit = Some(CommitRef {
+            repository: repository.into(),
+            sha: sha.into(),
+        });
+        self
+    }
+
+    /// Sets a label of the job
+    pub fn label(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.request.labels.insert(key.into(), value.into());
+        self
+    }
+
+    /// Runs the job even during a blackout window
+    pub fn override_blackout(mut self) -> Self {
+        self.request.override_blackout = true;
+        self
+    }
+
+    /// The launch request
+    pub fn into_request(self) -> CreateJob {
+        self.request
+    }
+
+    /// Launches the job
+    pub async fn launch(self, api: &dyn OrchestratorApi) -> rivet_client::Result<Job> {
+        api.launch_job(self.request).await
+    }
+}
+
+// =============================================================================
+// Lua Literals
+// =============================================================================
+
+/// Quoted Lua string literal of `s`
+fn lua_string(s: &str) -> String {
+    let mut quoted = String::with_capacity(s.len() + 2);
+    quoted.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            c if c.is_control() => quoted.push_str(&format!("\\u{{{:x}}}", c as u32)),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+/// Lua literal of a JSON value
+fn lua_value(value: &Value) -> String {
+    match value {
+        Value::Null => "nil".to_string(),
+        Value::Bool(b) => b.to_string(),
+        Value::Number(n) => n.to_string(),
+        Value::String(s) => lua_string(s),
+        Value::Array(items) => {
+            let items: Vec<String> = items.iter().map(lua_value).collect();
+            format!("{{ {} }}", items.join(", "))
+        }
+        Value::Object(fields) => {
+            let fields: Vec<String> = fields
+                .iter()
+                .map(|(key, value)| format!("[{}] = {}", lua_string(key), lua_value(value)))
+                .collect();
+            format!("{{ {} }}", fields.join(", "))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rivet_client::MockOrchestrator;
+    use rivet_core::domain::job::JobStatus;
+
+    #[test]
+    fn test_lua_literals() {
+        assert_eq!(lua_string(r#"say "hi"\n"#), r#""say \"hi\"\\n""#);
+        assert_eq!(lua_string("a\nb\u{1}"), r#""a\nb\u{1}""#);
+        assert_eq!(
+            lua_value(&serde_json::json!({ "k": [1, true, null, "x"] })),
+            r#"{ ["k"] = { 1, true, nil, "x" } }"#
+        );
+    }
+
+    #[test]
+    fn test_pipeline_script() {
+        let script = PipelineBuilder::new("build \"nightly\"")
+            .input("target", "string", Some("release".into()))
+            .tag("os", "linux")
+            .max_retries(2)
+            .stage("compile", r#"log.info("compiling")"#)
+            .deploy_stage("release", "prod", r#"log.info("releasing")"#)
+            .to_script();
+
+        assert!(script.contains(r#"name = "build \"nightly\"","#));
+        assert!(script.contains(r#"["target"] = { type = "string", default = "release" },"#));
+        assert!(script.contains(r#"{ key = "os", value = "linux" },"#));
+        assert!(script.contains("max_retries = 2,"));
+        assert!(script.contains("script = function()\nlog.info(\"compiling\")\n"));
+        assert!(script.contains("name = \"release\",\n            deploys_to = \"prod\",\n"));
+        assert!(script.ends_with("    }\n})\n"));
+    }
+
+    #[tokio::test]
+    async fn test_launch_job() {
+        let mock = MockOrchestrator::new();
+        let pipeline = mock.add_pipeline("build", "return {}");
+
+        let job = JobBuilder::new(pipeline.id)
+            .parameter("target", "release")
+            .commit("owner/repo", "0123abc")
+            .launch(&mock)
+            .await
+            .unwrap();
+
+        assert_eq!(job.status, JobStatus::Queued);
+        assert_eq!(job.parameters["target"], "release");
+        assert_eq!(job.commit.unwrap().sha, "0123abc");
+    }
+}
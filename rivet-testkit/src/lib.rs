@@ -0,0 +1,46 @@
+//! Rivet Test Kit
+//!
+//! Runs a real orchestrator in-process, so that the client, the runner and
+//! the CLI can be integration tested against the actual API rather than
+//! `MockOrchestrator`. `TestOrchestrator::start` creates an ephemeral
+//! PostgreSQL database, migrates it and serves the orchestrator API on a
+//! random local port; the database is dropped along with the orchestrator.
+//! `PipelineBuilder` and `JobBuilder` create the pipelines and jobs a test
+//! needs through any `OrchestratorApi`.
+//!
+//! The databases are created on the server `RIVET_TEST_DATABASE_URL` points
+//! to (`DATABASE_URL` when unset), as a user allowed to create databases.
+//! PostgreSQL 13 or later is required.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use rivet_client::OrchestratorApi;
+//! use rivet_testkit::{JobBuilder, PipelineBuilder, TestOrchestrator};
+//!
+//! # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+//! let orchestrator = TestOrchestrator::start().await?;
+//! let client = orchestrator.client();
+//!
+//! let pipeline = PipelineBuilder::new("build")
+//!     .stage("compile", r#"log.info("compiling")"#)
+//!     .create(&client)
+//!     .await?;
+//! let job = JobBuilder::new(pipeline.id).launch(&client).await?;
+//!
+//! assert_eq!(client.list_scheduled_jobs().await?.len(), 1);
+//! # let _ = job;
+//! orchestrator.shutdown().await?;
+//! # Ok(())
+//! # }
+//! ```
+
+mod database;
+mod error;
+mod fixtures;
+mod orchestrator;
+
+pub use database::{EphemeralDatabase, TEST_DATABASE_URL_ENV};
+pub use error::TestkitError;
+pub use fixtures::{JobBuilder, PipelineBuilder};
+pub use orchestrator::{TestOrchestrator, TestOrchestratorBuilder};
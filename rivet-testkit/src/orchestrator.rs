@@ -0,0 +1,192 @@
+//! Test Orchestrator
+//!
+//! An orchestrator served in-process on `127.0.0.1` and a random port, on an
+//! `EphemeralDatabase`. Background duties (lease expiry, sweeps, schedules)
+//! only run when asked for, so that tests drive the job lifecycle themselves.
+
+use rivet_client::OrchestratorClient;
+use rivet_orchestrator::events::EventBus;
+use rivet_orchestrator::{Server, ServerBuilder};
+use sqlx::PgPool;
+use tokio::task::JoinHandle;
+
+use crate::database::EphemeralDatabase;
+use crate::error::TestkitError;
+
+type Configure = Box<dyn FnOnce(ServerBuilder) -> ServerBuilder + Send>;
+
+/// Builder for `TestOrchestrator`
+#[derive(Default)]
+pub struct TestOrchestratorBuilder {
+    database_url: Option<String>,
+    background_tasks: bool,
+    configure: Option<Configure>,
+}
+
+impl TestOrchestratorBuilder {
+    /// Creates the database on the server at `url` instead of the one
+    /// `RIVET_TEST_DATABASE_URL` points to
+    pub fn database_url(mut self, url: impl Into<String>) -> Self {
+        self.database_url = Some(url.into());
+        self
+    }
+
+    /// Runs the background duties, off by default
+    pub fn background_tasks(mut self, enabled: bool) -> Self {
+        self.background_tasks = enabled;
+        self
+    }
+
+    /// Configures the server further, e.g. with an admin token
+    ///
+    /// The database pool is set by the test kit.
+    pub fn configure(
+        mut self,
+        configure: impl FnOnce(ServerBuilder) -> ServerBuilder + Send + 'static,
+    ) -> Self {
+        self.configure = Some(Box::new(configure));
+        self
+    }
+
+    /// Creates the database and starts serving
+    pub async fn start(self) -> Result<TestOrchestrator, TestkitError> {
+        let database = match &self.database_url {
+            Some(url) => EphemeralDatabase::create_on(url).await?,
+            None => EphemeralDatabase::create().await?,
+        };
+
+        let mut builder = Server::builder().pool(database.pool().clone());
+        if let Some(configure) = self.configure {
+            builder = configure(builder);
+        }
+        let server = builder.build()?;
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await?;
+        let url = format!("http://{}", listener.local_addr()?);
+        let events = server.events().clone();
+        let background = self
+            .background_tasks
+            .then(|| tokio::spawn(server.background_tasks()));
+        let serving = tokio::spawn(async move {
+            if let Err(e) = server.serve(listener).await {
+                eprintln!("Test orchestrator stopped: {}", e);
+            }
+        });
+
+        Ok(TestOrchestrator {
+            url,
+            events,
+            tasks: std::iter::once(serving).chain(background).collect(),
+            database: Some(database),
+        })
+    }
+}
+
+/// Orchestrator running in-process for the duration of a test
+///
+/// Stops serving and drops its database when dropped; `shutdown` does the
+/// same and reports failures.
+pub struct TestOrchestrator {
+    url: String,
+    events: EventBus,
+    /// The server, and the background duties when enabled
+    tasks: Vec<JoinHandle<()>>,
+    database: Option<EphemeralDatabase>,
+}
+
+impl TestOrchestrator {
+    /// Starts an orchestrator with the default settings
+    pub async fn start() -> Result<Self, TestkitError> {
+        Self::builder().start().await
+    }
+
+    /// Creates a builder to configure the orchestrator before it starts
+    pub fn builder() -> TestOrchestratorBuilder {
+        TestOrchestratorBuilder::default()
+    }
+
+    /// Base URL of the orchestrator, e.g. `http://127.0.0.1:41234`
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    /// Client of the orchestrator's HTTP API
+    pub fn client(&self) -> OrchestratorClient {
+        OrchestratorClient::new(&self.url)
+    }
+
+    /// Pool of connections to the orchestrator's database, to set up or
+    /// check state the API does not expose
+    pub fn pool(&self) -> &PgPool {
+        self.database().pool()
+    }
+
+    /// Event bus of the orchestrator, to wait for events in-process
+    pub fn events(&self) -> &EventBus {
+        &self.events
+    }
+
+    /// Ephemeral database of the orchestrator
+    pub fn database(&self) -> &EphemeralDatabase {
+        self.database
+            .as_ref()
+            .expect("the database lives as long as the orchestrator")
+    }
+
+    /// Stops serving and drops the database
+    pub async fn shutdown(mut self) -> Result<(), TestkitError> {
+        self.stop();
+        match self.database.take() {
+            Some(database) => database.drop_database().await,
+            None => Ok(()),
+        }
+    }
+
+    fn stop(&mut self) {
+        for task in self.tasks.drain(..) {
+            task.abort();
+        }
+    }
+}
+
+impl Drop for TestOrchestrator {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    #[ignore = "needs a PostgreSQL server, see RIVET_TEST_DATABASE_URL"]
+    async fn test_orchestrator_lifecycle() {
+        let orchestrator = TestOrchestrator::start().await.unwrap();
+        let client = orchestrator.client();
+        let name = orchestrator.database().name().to_string();
+
+        assert!(client.list_pipelines().await.unwrap().is_empty());
+        client.register_runner("testkit-runner").await.unwrap();
+        assert_eq!(client.list_runners().await.unwrap().len(), 1);
+
+        // Every orchestrator has a database of its own
+        let other = TestOrchestrator::start().await.unwrap();
+        assert!(other.client().list_runners().await.unwrap().is_empty());
+        drop(other);
+
+        let pool = orchestrator.pool().clone();
+        orchestrator.shutdown().await.unwrap();
+        assert!(pool.is_closed());
+        assert!(client.list_runners().await.is_err());
+
+        let admin = EphemeralDatabase::create().await.unwrap();
+        let exists: bool =
+            sqlx::query_scalar("SELECT EXISTS (SELECT 1 FROM pg_database WHERE datname = $1)")
+                .bind(&name)
+                .fetch_one(admin.pool())
+                .await
+                .unwrap();
+        assert!(!exists);
+    }
+}
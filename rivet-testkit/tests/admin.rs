@@ -0,0 +1,105 @@
+//! Endpoints reserved to the admin token
+
+use rivet_client::OrchestratorClient;
+use rivet_core::dto::commit_status::GitProvider;
+use rivet_core::dto::secret::SetSecret;
+use rivet_core::dto::webhook::CreateWebhookTrigger;
+use rivet_testkit::{PipelineBuilder, TestOrchestrator};
+
+async fn start() -> (TestOrchestrator, OrchestratorClient) {
+    let orchestrator = TestOrchestrator::builder()
+        .configure(|server| server.admin_token("admin"))
+        .start()
+        .await
+        .unwrap();
+    let admin = OrchestratorClient::builder()
+        .base_url(orchestrator.url())
+        .token("admin")
+        .build()
+        .unwrap();
+    (orchestrator, admin)
+}
+
+fn secret(value: &str) -> SetSecret {
+    SetSecret {
+        name: "DEPLOY_KEY".to_string(),
+        value: value.to_string(),
+        pipeline_id: None,
+    }
+}
+
+#[tokio::test]
+#[ignore = "needs a PostgreSQL server, see RIVET_TEST_DATABASE_URL"]
+async fn test_secret_writes_require_admin() {
+    let (orchestrator, admin) = start().await;
+    let client = orchestrator.client();
+
+    let err = client.set_secret(secret("anyone")).await.unwrap_err();
+    assert_eq!(err.status(), Some(401));
+    admin.set_secret(secret("admin")).await.unwrap();
+
+    // Reading the metadata stays open
+    assert_eq!(client.list_secrets(None).await.unwrap().len(), 1);
+
+    let err = client.delete_secret("DEPLOY_KEY", None).await.unwrap_err();
+    assert_eq!(err.status(), Some(401));
+    admin.delete_secret("DEPLOY_KEY", None).await.unwrap();
+    assert!(client.list_secrets(None).await.unwrap().is_empty());
+}
+
+#[tokio::test]
+#[ignore = "needs a PostgreSQL server, see RIVET_TEST_DATABASE_URL"]
+async fn test_runner_deletion_requires_admin() {
+    let (orchestrator, admin) = start().await;
+    let client = orchestrator.client();
+    client.register_runner("runner-1").await.unwrap();
+
+    let err = client.delete_runner("runner-1").await.unwrap_err();
+    assert_eq!(err.status(), Some(401));
+    assert_eq!(client.list_runners().await.unwrap().len(), 1);
+
+    admin.delete_runner("runner-1").await.unwrap();
+    assert!(client.list_runners().await.unwrap().is_empty());
+}
+
+#[tokio::test]
+#[ignore = "needs a PostgreSQL server, see RIVET_TEST_DATABASE_URL"]
+async fn test_webhook_secret_cannot_be_shadowed() {
+    let (orchestrator, admin) = start().await;
+    let client = orchestrator.client();
+    let pipeline = PipelineBuilder::new("build")
+        .stage("compile", r#"log.info("compiling")"#)
+        .create(&admin)
+        .await
+        .unwrap();
+    admin.set_secret(secret("admin")).await.unwrap();
+    admin
+        .create_webhook_trigger(
+            pipeline.id,
+            &CreateWebhookTrigger {
+                provider: GitProvider::Gitlab,
+                secret: "DEPLOY_KEY".to_string(),
+                filters: Default::default(),
+                parameters: Default::default(),
+            },
+        )
+        .await
+        .unwrap();
+
+    // A pipeline-scoped secret would take precedence over the project's
+    let err = client
+        .set_secret(SetSecret {
+            pipeline_id: Some(pipeline.id),
+            ..secret("anyone")
+        })
+        .await
+        .unwrap_err();
+    assert_eq!(err.status(), Some(401));
+    assert!(
+        client
+            .list_secrets(Some(pipeline.id))
+            .await
+            .unwrap()
+            .is_empty()
+    );
+}
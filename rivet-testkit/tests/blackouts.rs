@@ -0,0 +1,141 @@
+//! Jobs deferred by blackout windows
+
+use std::time::Duration;
+
+use chrono::Utc;
+use rivet_client::OrchestratorClient;
+use rivet_core::domain::job::JobStatus;
+use rivet_core::dto::blackout::CreateBlackoutWindow;
+use rivet_core::dto::environment::SetEnvironment;
+use rivet_testkit::{JobBuilder, PipelineBuilder, TestOrchestrator};
+use uuid::Uuid;
+
+const NO_WAIT: Duration = Duration::from_millis(100);
+
+/// Creates the environment `prod` and two pipelines: one deploying to it,
+/// one that does not
+async fn setup(client: &OrchestratorClient) -> (Uuid, Uuid) {
+    client
+        .set_environment(
+            "prod",
+            &SetEnvironment {
+                description: None,
+                required_approvals: 0,
+            },
+        )
+        .await
+        .unwrap();
+    let release = PipelineBuilder::new("release")
+        .deploy_stage("deploy", "prod", r#"log.info("deploying")"#)
+        .create(client)
+        .await
+        .unwrap();
+    let build = PipelineBuilder::new("build")
+        .stage("compile", r#"log.info("compiling")"#)
+        .create(client)
+        .await
+        .unwrap();
+    (release.id, build.id)
+}
+
+fn window(
+    pipeline_id: Option<Uuid>,
+    environment: Option<&str>,
+    hours: i64,
+) -> CreateBlackoutWindow {
+    CreateBlackoutWindow {
+        pipeline_id,
+        environment: environment.map(str::to_string),
+        starts_at: None,
+        ends_at: Utc::now() + chrono::Duration::hours(hours),
+        reason: Some("freeze".to_string()),
+    }
+}
+
+#[tokio::test]
+#[ignore = "needs a PostgreSQL server, see RIVET_TEST_DATABASE_URL"]
+async fn test_overlapping_windows_defer_until_the_last_closes() {
+    let orchestrator = TestOrchestrator::start().await.unwrap();
+    let client = orchestrator.client();
+    let (release, _) = setup(&client).await;
+    client.register_runner("runner-1").await.unwrap();
+
+    let short = client
+        .create_blackout(&window(None, Some("prod"), 1))
+        .await
+        .unwrap();
+    let long = client
+        .create_blackout(&window(Some(release), None, 2))
+        .await
+        .unwrap();
+    assert_eq!(client.list_blackouts().await.unwrap().len(), 2);
+
+    let job = JobBuilder::new(release).launch(&client).await.unwrap();
+    assert!(
+        client
+            .next_job("runner-1", NO_WAIT)
+            .await
+            .unwrap()
+            .is_none()
+    );
+    let err = client.claim_job(job.id, "runner-1").await.unwrap_err();
+    let until = long.ends_at.format("until %Y-%m-%d %H:%M:%S").to_string();
+    assert!(err.to_string().contains(&until));
+
+    // Still deferred by the other window
+    client.delete_blackout(long.id).await.unwrap();
+    assert!(
+        client
+            .next_job("runner-1", NO_WAIT)
+            .await
+            .unwrap()
+            .is_none()
+    );
+
+    client.delete_blackout(short.id).await.unwrap();
+    let info = client.next_job("runner-1", NO_WAIT).await.unwrap().unwrap();
+    assert_eq!(info.job_id, job.id);
+}
+
+#[tokio::test]
+#[ignore = "needs a PostgreSQL server, see RIVET_TEST_DATABASE_URL"]
+async fn test_windows_defer_only_the_jobs_they_target() {
+    let orchestrator = TestOrchestrator::start().await.unwrap();
+    let client = orchestrator.client();
+    let (release, build) = setup(&client).await;
+    client.register_runner("runner-1").await.unwrap();
+    client
+        .create_blackout(&window(None, Some("prod"), 1))
+        .await
+        .unwrap();
+
+    // A window that has not started yet defers nothing
+    client
+        .create_blackout(&CreateBlackoutWindow {
+            starts_at: Some(Utc::now() + chrono::Duration::hours(1)),
+            ..window(Some(build), None, 2)
+        })
+        .await
+        .unwrap();
+
+    let deferred = JobBuilder::new(release).launch(&client).await.unwrap();
+    let other = JobBuilder::new(build).launch(&client).await.unwrap();
+    let urgent = JobBuilder::new(release)
+        .override_blackout()
+        .launch(&client)
+        .await
+        .unwrap();
+
+    let err = client.claim_job(deferred.id, "runner-1").await.unwrap_err();
+    assert!(
+        err.to_string()
+            .contains("blackout window of environment 'prod'")
+    );
+    // Deferred jobs stay queued, unlike those locked out of an environment
+    assert_eq!(
+        client.get_job(deferred.id).await.unwrap().status,
+        JobStatus::Queued
+    );
+    client.claim_job(other.id, "runner-1").await.unwrap();
+    client.claim_job(urgent.id, "runner-1").await.unwrap();
+}
@@ -0,0 +1,122 @@
+//! Claim fencing of job completions and log uploads
+
+use rivet_core::domain::job::{JobResult, JobStatus};
+use rivet_core::domain::log::{LogEntry, LogLevel};
+use rivet_testkit::{JobBuilder, PipelineBuilder, TestOrchestrator};
+
+fn entry(message: &str) -> LogEntry {
+    LogEntry {
+        timestamp: chrono::Utc::now(),
+        level: LogLevel::Info,
+        message: message.to_string(),
+        stage: None,
+        stream: None,
+        sequence: None,
+        fields: None,
+    }
+}
+
+#[tokio::test]
+#[ignore = "needs a PostgreSQL server, see RIVET_TEST_DATABASE_URL"]
+async fn test_claim_token_is_required() {
+    let orchestrator = TestOrchestrator::start().await.unwrap();
+    let client = orchestrator.client();
+    let pipeline = PipelineBuilder::new("build")
+        .stage("compile", r#"log.info("compiling")"#)
+        .create(&client)
+        .await
+        .unwrap();
+    let job = JobBuilder::new(pipeline.id).launch(&client).await.unwrap();
+    client.register_runner("runner-1").await.unwrap();
+    client.claim_job(job.id, "runner-1").await.unwrap();
+
+    let err = client
+        .send_logs(job.id, None, vec![entry("hello")])
+        .await
+        .unwrap_err();
+    assert_eq!(err.status(), Some(400));
+    let err = client
+        .complete_job(job.id, None, JobResult::success())
+        .await
+        .unwrap_err();
+    assert_eq!(err.status(), Some(400));
+
+    let job = client.get_job(job.id).await.unwrap();
+    assert_eq!(job.status, JobStatus::Running);
+    assert!(client.get_job_logs(job.id).await.unwrap().is_empty());
+}
+
+#[tokio::test]
+#[ignore = "needs a PostgreSQL server, see RIVET_TEST_DATABASE_URL"]
+async fn test_stale_claim_is_rejected() {
+    let orchestrator = TestOrchestrator::start().await.unwrap();
+    let client = orchestrator.client();
+    let pipeline = PipelineBuilder::new("build")
+        .stage("compile", r#"log.info("compiling")"#)
+        .create(&client)
+        .await
+        .unwrap();
+    let job = JobBuilder::new(pipeline.id).launch(&client).await.unwrap();
+    client.register_runner("runner-1").await.unwrap();
+    client.register_runner("runner-2").await.unwrap();
+
+    // The job is requeued (e.g. its lease expired) and claimed again
+    let first = client.claim_job(job.id, "runner-1").await.unwrap();
+    sqlx::query("UPDATE jobs SET status = 'Queued', runner_id = NULL WHERE id = $1")
+        .bind(job.id)
+        .execute(orchestrator.pool())
+        .await
+        .unwrap();
+    let second = client.claim_job(job.id, "runner-2").await.unwrap();
+    assert_ne!(first.claim_token, second.claim_token);
+
+    let err = client
+        .send_logs(job.id, first.claim_token, vec![entry("stale")])
+        .await
+        .unwrap_err();
+    assert_eq!(err.status(), Some(409));
+    let err = client
+        .complete_job(job.id, first.claim_token, JobResult::success())
+        .await
+        .unwrap_err();
+    assert_eq!(err.status(), Some(409));
+
+    let job = client.get_job(job.id).await.unwrap();
+    assert_eq!(job.status, JobStatus::Running);
+    assert!(client.get_job_logs(job.id).await.unwrap().is_empty());
+}
+
+#[tokio::test]
+#[ignore = "needs a PostgreSQL server, see RIVET_TEST_DATABASE_URL"]
+async fn test_current_claim_is_accepted() {
+    let orchestrator = TestOrchestrator::start().await.unwrap();
+    let client = orchestrator.client();
+    let pipeline = PipelineBuilder::new("build")
+        .stage("compile", r#"log.info("compiling")"#)
+        .create(&client)
+        .await
+        .unwrap();
+    let job = JobBuilder::new(pipeline.id).launch(&client).await.unwrap();
+    client.register_runner("runner-1").await.unwrap();
+    let info = client.claim_job(job.id, "runner-1").await.unwrap();
+
+    client
+        .send_logs(job.id, info.claim_token, vec![entry("hello")])
+        .await
+        .unwrap();
+    client
+        .complete_job(job.id, info.claim_token, JobResult::success())
+        .await
+        .unwrap();
+
+    let job = client.get_job(job.id).await.unwrap();
+    assert_eq!(job.status, JobStatus::Succeeded);
+    assert_eq!(client.get_job_logs(job.id).await.unwrap().len(), 1);
+
+    // A job is completed only once
+    let err = client
+        .complete_job(job.id, info.claim_token, JobResult::success())
+        .await
+        .unwrap_err();
+    assert_eq!(err.status(), Some(409));
+}
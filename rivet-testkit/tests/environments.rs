@@ -0,0 +1,310 @@
+//! Environments: approvals, deployments and locks
+
+use rivet_client::OrchestratorClient;
+use rivet_core::domain::environment::DeploymentStatus;
+use rivet_core::domain::job::{Job, JobResult, JobStatus, StageStatus};
+use rivet_core::dto::environment::{ApproveJob, SetEnvironment};
+use rivet_core::dto::job::StageUpdate;
+use rivet_orchestrator::service::job_service;
+use rivet_testkit::{JobBuilder, PipelineBuilder, TestOrchestrator};
+use uuid::Uuid;
+
+/// Creates the environment `prod` and a pipeline deploying to it
+async fn setup(client: &OrchestratorClient, required_approvals: u32) -> Uuid {
+    client
+        .set_environment(
+            "prod",
+            &SetEnvironment {
+                description: None,
+                required_approvals,
+            },
+        )
+        .await
+        .unwrap();
+    PipelineBuilder::new("release")
+        .stage("build", r#"log.info("building")"#)
+        .deploy_stage("deploy", "prod", r#"log.info("deploying")"#)
+        .create(client)
+        .await
+        .unwrap()
+        .id
+}
+
+fn approval(approver: &str) -> ApproveJob {
+    ApproveJob {
+        environment: "prod".to_string(),
+        approver: approver.to_string(),
+        comment: None,
+    }
+}
+
+fn stage(name: &str, status: StageStatus) -> StageUpdate {
+    StageUpdate {
+        name: name.to_string(),
+        status,
+        timestamp: chrono::Utc::now(),
+        error_message: None,
+        needs: Vec::new(),
+        result: None,
+    }
+}
+
+/// Runs a claimed job's deploy stage to `status`
+async fn deploy(client: &OrchestratorClient, job: &Job, status: StageStatus) {
+    client
+        .send_stage_updates(job.id, vec![stage("build", StageStatus::Succeeded)])
+        .await
+        .unwrap();
+    client
+        .send_stage_updates(job.id, vec![stage("deploy", StageStatus::Running)])
+        .await
+        .unwrap();
+    client
+        .send_stage_updates(job.id, vec![stage("deploy", status)])
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+#[ignore = "needs a PostgreSQL server, see RIVET_TEST_DATABASE_URL"]
+async fn test_jobs_wait_for_the_required_approvals() {
+    let orchestrator = TestOrchestrator::start().await.unwrap();
+    let client = orchestrator.client();
+    let pipeline_id = setup(&client, 2).await;
+    let job = JobBuilder::new(pipeline_id).launch(&client).await.unwrap();
+    client.register_runner("runner-1").await.unwrap();
+
+    let approvals = client.get_job_approvals(job.id).await.unwrap();
+    assert_eq!(approvals.len(), 1);
+    assert_eq!(approvals[0].required, 2);
+    assert!(!approvals[0].is_satisfied());
+    assert!(client.claim_job(job.id, "runner-1").await.is_err());
+
+    // Each approver counts once
+    client
+        .approve_job(job.id, &approval("alice"))
+        .await
+        .unwrap();
+    let approvals = client
+        .approve_job(job.id, &approval("alice"))
+        .await
+        .unwrap();
+    assert_eq!(approvals[0].approvals.len(), 1);
+    assert!(!approvals[0].is_satisfied());
+    assert!(client.claim_job(job.id, "runner-1").await.is_err());
+
+    // Once the count is reached the job can run, and is approved no more
+    let approvals = client.approve_job(job.id, &approval("bob")).await.unwrap();
+    assert!(approvals[0].is_satisfied());
+    client.claim_job(job.id, "runner-1").await.unwrap();
+    let err = client
+        .approve_job(job.id, &approval("carol"))
+        .await
+        .unwrap_err();
+    assert!(err.is_client_error());
+    let approvals = client.get_job_approvals(job.id).await.unwrap();
+    assert_eq!(approvals[0].approvals.len(), 2);
+}
+
+#[tokio::test]
+#[ignore = "needs a PostgreSQL server, see RIVET_TEST_DATABASE_URL"]
+async fn test_approvals_are_refused_after_cancel() {
+    let orchestrator = TestOrchestrator::start().await.unwrap();
+    let client = orchestrator.client();
+    let pipeline_id = setup(&client, 1).await;
+    let job = JobBuilder::new(pipeline_id).launch(&client).await.unwrap();
+
+    job_service::cancel_job(orchestrator.pool(), job.id)
+        .await
+        .unwrap();
+    let err = client
+        .approve_job(job.id, &approval("alice"))
+        .await
+        .unwrap_err();
+    assert!(err.is_client_error());
+    let approvals = client.get_job_approvals(job.id).await.unwrap();
+    assert!(approvals[0].approvals.is_empty());
+
+    // Nor are approvals for environments the job does not deploy to
+    let job = JobBuilder::new(pipeline_id).launch(&client).await.unwrap();
+    let err = client
+        .approve_job(
+            job.id,
+            &ApproveJob {
+                environment: "staging".to_string(),
+                ..approval("alice")
+            },
+        )
+        .await
+        .unwrap_err();
+    assert!(err.is_client_error());
+}
+
+#[tokio::test]
+#[ignore = "needs a PostgreSQL server, see RIVET_TEST_DATABASE_URL"]
+async fn test_deployments_are_recorded() {
+    let orchestrator = TestOrchestrator::start().await.unwrap();
+    let client = orchestrator.client();
+    let pipeline_id = setup(&client, 0).await;
+    client.register_runner("runner-1").await.unwrap();
+
+    let first = JobBuilder::new(pipeline_id).launch(&client).await.unwrap();
+    let info = client.claim_job(first.id, "runner-1").await.unwrap();
+    client
+        .send_stage_updates(first.id, vec![stage("deploy", StageStatus::Running)])
+        .await
+        .unwrap();
+    let deployments = client.list_deployments("prod", None).await.unwrap();
+    assert_eq!(deployments.len(), 1);
+    assert_eq!(deployments[0].status, DeploymentStatus::Running);
+    assert_eq!(deployments[0].stage, "deploy");
+    assert!(deployments[0].completed_at.is_none());
+
+    deploy(&client, &first, StageStatus::Succeeded).await;
+    client
+        .complete_job(first.id, info.claim_token, JobResult::success())
+        .await
+        .unwrap();
+
+    // Only the stage deploying to the environment is recorded, once per run
+    let deployments = client.list_deployments("prod", None).await.unwrap();
+    assert_eq!(deployments.len(), 1);
+    assert_eq!(deployments[0].job_id, first.id);
+    assert_eq!(deployments[0].status, DeploymentStatus::Succeeded);
+    assert!(deployments[0].completed_at.is_some());
+
+    // A failed deployment is the latest, not the current one
+    let second = JobBuilder::new(pipeline_id).launch(&client).await.unwrap();
+    client.claim_job(second.id, "runner-1").await.unwrap();
+    deploy(&client, &second, StageStatus::Failed).await;
+
+    let environment = client.get_environment("prod").await.unwrap();
+    assert_eq!(environment.current.unwrap().job_id, first.id);
+    let latest = environment.latest.unwrap();
+    assert_eq!(latest.job_id, second.id);
+    assert_eq!(latest.status, DeploymentStatus::Failed);
+    let deployments = client.list_deployments("prod", None).await.unwrap();
+    assert_eq!(
+        deployments.iter().map(|d| d.job_id).collect::<Vec<_>>(),
+        [second.id, first.id]
+    );
+    assert_eq!(
+        client.get_job(second.id).await.unwrap().status,
+        JobStatus::Running
+    );
+}
+
+#[tokio::test]
+#[ignore = "needs a PostgreSQL server, see RIVET_TEST_DATABASE_URL"]
+async fn test_lock_is_released_on_completion_and_failure() {
+    let orchestrator = TestOrchestrator::start().await.unwrap();
+    let client = orchestrator.client();
+    let pipeline_id = setup(&client, 0).await;
+    client.register_runner("runner-1").await.unwrap();
+
+    let first = JobBuilder::new(pipeline_id).launch(&client).await.unwrap();
+    let first_claim = client.claim_job(first.id, "runner-1").await.unwrap();
+
+    // A job deploying to a locked environment waits for it
+    let second = JobBuilder::new(pipeline_id).launch(&client).await.unwrap();
+    assert_eq!(second.status, JobStatus::Blocked);
+    assert!(client.claim_job(second.id, "runner-1").await.is_err());
+    let lock = client.get_environment_lock("prod").await.unwrap();
+    assert_eq!(lock.lock.unwrap().job_id, first.id);
+    assert_eq!(lock.waiting, [second.id]);
+
+    client
+        .complete_job(first.id, first_claim.claim_token, JobResult::success())
+        .await
+        .unwrap();
+    let lock = client.get_environment_lock("prod").await.unwrap();
+    assert!(lock.lock.is_none());
+    assert!(lock.waiting.is_empty());
+    assert_eq!(
+        client.get_job(second.id).await.unwrap().status,
+        JobStatus::Queued
+    );
+
+    let second_claim = client.claim_job(second.id, "runner-1").await.unwrap();
+    let third = JobBuilder::new(pipeline_id).launch(&client).await.unwrap();
+    assert_eq!(third.status, JobStatus::Blocked);
+
+    client
+        .complete_job(
+            second.id,
+            second_claim.claim_token,
+            JobResult::failed("deploy failed".to_string()),
+        )
+        .await
+        .unwrap();
+    assert_eq!(
+        client.get_job(second.id).await.unwrap().status,
+        JobStatus::Failed
+    );
+    assert!(
+        client
+            .get_environment_lock("prod")
+            .await
+            .unwrap()
+            .lock
+            .is_none()
+    );
+    client.claim_job(third.id, "runner-1").await.unwrap();
+}
+
+#[tokio::test]
+#[ignore = "needs a PostgreSQL server, see RIVET_TEST_DATABASE_URL"]
+async fn test_admin_releases_a_lock() {
+    let orchestrator = TestOrchestrator::builder()
+        .configure(|server| server.admin_token("admin"))
+        .start()
+        .await
+        .unwrap();
+    let client = orchestrator.client();
+    let admin = OrchestratorClient::builder()
+        .base_url(orchestrator.url())
+        .token("admin")
+        .build()
+        .unwrap();
+    let pipeline_id = setup(&admin, 0).await;
+    client.register_runner("runner-1").await.unwrap();
+
+    // Releasing a free lock is refused
+    let err = admin.release_environment_lock("prod").await.unwrap_err();
+    assert_eq!(err.status(), Some(400));
+
+    let holder = JobBuilder::new(pipeline_id).launch(&client).await.unwrap();
+    client.claim_job(holder.id, "runner-1").await.unwrap();
+    let waiting = JobBuilder::new(pipeline_id).launch(&client).await.unwrap();
+    assert_eq!(waiting.status, JobStatus::Blocked);
+
+    let err = client.release_environment_lock("prod").await.unwrap_err();
+    assert_eq!(err.status(), Some(401));
+    assert!(
+        client
+            .get_environment_lock("prod")
+            .await
+            .unwrap()
+            .lock
+            .is_some()
+    );
+
+    // The holder keeps running, the jobs it blocked are queued again
+    admin.release_environment_lock("prod").await.unwrap();
+    assert!(
+        client
+            .get_environment_lock("prod")
+            .await
+            .unwrap()
+            .lock
+            .is_none()
+    );
+    assert_eq!(
+        client.get_job(holder.id).await.unwrap().status,
+        JobStatus::Running
+    );
+    assert_eq!(
+        client.get_job(waiting.id).await.unwrap().status,
+        JobStatus::Queued
+    );
+}
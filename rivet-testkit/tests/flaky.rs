@@ -0,0 +1,168 @@
+//! Flaky stage detection
+
+use chrono::Utc;
+use rivet_client::OrchestratorClient;
+use rivet_core::domain::job::{JobResult, JobStatus, StageStatus};
+use rivet_core::dto::job::StageUpdate;
+use rivet_core::dto::pipeline::{FlakyStage, FlakyStagesQuery};
+use rivet_testkit::{JobBuilder, PipelineBuilder, TestOrchestrator};
+use uuid::Uuid;
+
+fn stage(name: &str, status: StageStatus) -> StageUpdate {
+    StageUpdate {
+        name: name.to_string(),
+        status,
+        timestamp: Utc::now(),
+        error_message: None,
+        needs: Vec::new(),
+        result: None,
+    }
+}
+
+/// Runs the claimed job's `lint` stage, which always passes, and its `test`
+/// stage, which passes when `passed`, then completes the job accordingly
+async fn attempt(client: &OrchestratorClient, job_id: Uuid, passed: bool) {
+    let info = client.claim_job(job_id, "runner-1").await.unwrap();
+    let (status, result) = if passed {
+        (StageStatus::Succeeded, JobResult::success())
+    } else {
+        (
+            StageStatus::Failed,
+            JobResult::failed("test failed".to_string()),
+        )
+    };
+    for update in [
+        stage("lint", StageStatus::Running),
+        stage("lint", StageStatus::Succeeded),
+        stage("test", StageStatus::Running),
+        stage("test", status),
+    ] {
+        client
+            .send_stage_updates(job_id, vec![update])
+            .await
+            .unwrap();
+    }
+    client
+        .complete_job(job_id, info.claim_token, result)
+        .await
+        .unwrap();
+}
+
+/// Runs one job per outcome of its `test` stage
+async fn run(client: &OrchestratorClient, pipeline_id: Uuid, outcomes: &[bool]) {
+    for &passed in outcomes {
+        let job = JobBuilder::new(pipeline_id).launch(client).await.unwrap();
+        attempt(client, job.id, passed).await;
+    }
+}
+
+async fn setup(max_retries: u32) -> (TestOrchestrator, OrchestratorClient, Uuid) {
+    let orchestrator = TestOrchestrator::start().await.unwrap();
+    let client = orchestrator.client();
+    client.register_runner("runner-1").await.unwrap();
+    let pipeline = PipelineBuilder::new("ci")
+        .max_retries(max_retries)
+        .stage("lint", r#"log.info("linting")"#)
+        .stage("test", r#"log.info("testing")"#)
+        .create(&client)
+        .await
+        .unwrap();
+    (orchestrator, client, pipeline.id)
+}
+
+async fn flaky(client: &OrchestratorClient, pipeline_id: Uuid, min_flips: u32) -> Vec<FlakyStage> {
+    client
+        .flaky_stages(
+            pipeline_id,
+            &FlakyStagesQuery {
+                days: None,
+                min_flips: Some(min_flips),
+            },
+        )
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+#[ignore = "needs a PostgreSQL server, see RIVET_TEST_DATABASE_URL"]
+async fn test_stages_flipping_on_one_version_are_flagged() {
+    let (_orchestrator, client, pipeline_id) = setup(0).await;
+    run(&client, pipeline_id, &[true, true, false, true]).await;
+
+    let stages = flaky(&client, pipeline_id, 2).await;
+    assert_eq!(stages.len(), 1);
+    let stage = &stages[0];
+    assert_eq!(stage.stage, "test");
+    assert_eq!((stage.runs, stage.failures, stage.flips), (4, 1, 2));
+    assert_eq!(stage.retry_flips, 0);
+    assert!((stage.flip_rate - 2.0 / 3.0).abs() < 1e-9);
+
+    // One more flip than needed is too many
+    assert!(flaky(&client, pipeline_id, 3).await.is_empty());
+}
+
+#[tokio::test]
+#[ignore = "needs a PostgreSQL server, see RIVET_TEST_DATABASE_URL"]
+async fn test_flips_below_the_threshold_are_not_flagged() {
+    let (_orchestrator, client, pipeline_id) = setup(0).await;
+    run(&client, pipeline_id, &[true, true, false, false]).await;
+
+    // A stage that broke and stayed broken flipped once
+    assert!(flaky(&client, pipeline_id, 2).await.is_empty());
+    let stages = flaky(&client, pipeline_id, 1).await;
+    assert_eq!(stages.len(), 1);
+    assert_eq!(stages[0].flips, 1);
+
+    let err = client
+        .flaky_stages(
+            pipeline_id,
+            &FlakyStagesQuery {
+                days: None,
+                min_flips: Some(0),
+            },
+        )
+        .await
+        .unwrap_err();
+    assert!(err.is_client_error());
+}
+
+#[tokio::test]
+#[ignore = "needs a PostgreSQL server, see RIVET_TEST_DATABASE_URL"]
+async fn test_flips_across_versions_are_not_counted() {
+    let (orchestrator, client, pipeline_id) = setup(0).await;
+    run(&client, pipeline_id, &[true, false]).await;
+
+    // The script changes: the fix is a new version, not a flip
+    sqlx::query("UPDATE pipelines SET script = script || '\n-- v2' WHERE id = $1")
+        .bind(pipeline_id)
+        .execute(orchestrator.pool())
+        .await
+        .unwrap();
+    run(&client, pipeline_id, &[true, true]).await;
+
+    assert!(flaky(&client, pipeline_id, 2).await.is_empty());
+    let stages = flaky(&client, pipeline_id, 1).await;
+    assert_eq!(stages.len(), 1);
+    assert_eq!((stages[0].runs, stages[0].flips), (2, 1));
+}
+
+#[tokio::test]
+#[ignore = "needs a PostgreSQL server, see RIVET_TEST_DATABASE_URL"]
+async fn test_passing_on_a_retry_is_flagged() {
+    let (_orchestrator, client, pipeline_id) = setup(1).await;
+    let job = JobBuilder::new(pipeline_id).launch(&client).await.unwrap();
+
+    attempt(&client, job.id, false).await;
+    assert_eq!(
+        client.get_job(job.id).await.unwrap().status,
+        JobStatus::Queued
+    );
+    attempt(&client, job.id, true).await;
+
+    // Flagged on a single flip, whatever the threshold
+    let stages = flaky(&client, pipeline_id, 10).await;
+    assert_eq!(stages.len(), 1);
+    assert_eq!(stages[0].stage, "test");
+    assert_eq!((stages[0].runs, stages[0].flips), (2, 1));
+    assert_eq!(stages[0].retry_flips, 1);
+}
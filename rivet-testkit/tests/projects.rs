@@ -0,0 +1,76 @@
+//! Project scoping of the unscoped routes
+
+use rivet_client::OrchestratorClient;
+use rivet_core::domain::pipeline::Pipeline;
+use rivet_core::dto::project::CreateProject;
+use rivet_testkit::{JobBuilder, PipelineBuilder, TestOrchestrator};
+
+#[tokio::test]
+#[ignore = "needs a PostgreSQL server, see RIVET_TEST_DATABASE_URL"]
+async fn test_unscoped_listings_stay_in_the_default_project() {
+    let orchestrator = TestOrchestrator::builder()
+        .configure(|server| server.admin_token("admin"))
+        .start()
+        .await
+        .unwrap();
+    let client = |project: Option<&str>, token: Option<&str>| {
+        let mut builder = OrchestratorClient::builder().base_url(orchestrator.url());
+        if let Some(project) = project {
+            builder = builder.project(project);
+        }
+        if let Some(token) = token {
+            builder = builder.token(token);
+        }
+        builder.build().unwrap()
+    };
+    let (anonymous, admin, team) = (
+        client(None, None),
+        client(None, Some("admin")),
+        client(Some("team"), None),
+    );
+
+    admin
+        .create_project(CreateProject {
+            name: "team".to_string(),
+            description: None,
+        })
+        .await
+        .unwrap();
+    let default = PipelineBuilder::new("default")
+        .stage("compile", r#"log.info("compiling")"#)
+        .create(&anonymous)
+        .await
+        .unwrap();
+    let other = PipelineBuilder::new("other")
+        .stage("compile", r#"log.info("compiling")"#)
+        .create(&team)
+        .await
+        .unwrap();
+    JobBuilder::new(other.id).launch(&team).await.unwrap();
+
+    let names = |pipelines: Vec<Pipeline>| {
+        let mut names: Vec<_> = pipelines.into_iter().map(|p| p.name).collect();
+        names.sort();
+        names
+    };
+    assert_eq!(
+        names(anonymous.list_pipelines().await.unwrap()),
+        ["default"]
+    );
+    assert_eq!(names(team.list_pipelines().await.unwrap()), ["other"]);
+    assert_eq!(
+        names(admin.list_pipelines().await.unwrap()),
+        ["default", "other"]
+    );
+
+    assert!(anonymous.list_all_jobs().await.unwrap().is_empty());
+    assert_eq!(admin.list_all_jobs().await.unwrap().len(), 1);
+    assert_eq!(team.list_all_jobs().await.unwrap().len(), 1);
+
+    // Resources are still reachable by id, e.g. by the runners
+    assert_eq!(anonymous.get_pipeline(other.id).await.unwrap().id, other.id);
+    assert_eq!(
+        anonymous.get_pipeline(default.id).await.unwrap().id,
+        default.id
+    );
+}